@@ -1,11 +1,11 @@
 use super::utils::setup;
 use opcua::{
     nodes::TypeTree,
-    server::address_space::{ObjectBuilder, ReferenceDirection, VariableBuilder},
+    server::address_space::{ObjectBuilder, ReferenceDirection, VariableBuilder, ViewBuilder},
     types::{
         BrowseDescription, BrowseDirection, BrowsePath, BrowseResultMask, ByteString, DataTypeId,
         NodeClass, NodeClassMask, NodeId, ObjectId, ObjectTypeId, ReferenceTypeId, RelativePath,
-        RelativePathElement, StatusCode, VariableTypeId,
+        RelativePathElement, StatusCode, VariableTypeId, ViewDescription,
     },
 };
 use opcua_client::browser::BrowseFilter;
@@ -582,6 +582,149 @@ async fn translate_browse_paths_auto_impl() {
     );
 }
 
+#[tokio::test]
+async fn browse_view() {
+    let (_tester, nm, session) = setup().await;
+    let view_id = nm.inner().next_node_id();
+    let in_view = nm.inner().next_node_id();
+    let child = nm.inner().next_node_id();
+    let not_in_view = nm.inner().next_node_id();
+    {
+        let mut sp = nm.address_space().write();
+        ObjectBuilder::new(&in_view, "InView", "InView")
+            .organized_by(ObjectId::ObjectsFolder)
+            .insert(&mut *sp);
+        ObjectBuilder::new(&child, "Child", "Child")
+            .component_of(&in_view)
+            .insert(&mut *sp);
+        ObjectBuilder::new(&not_in_view, "NotInView", "NotInView")
+            .organized_by(ObjectId::ObjectsFolder)
+            .insert(&mut *sp);
+        ViewBuilder::new(&view_id, "TestView", "TestView")
+            .organized_by(ObjectId::ViewsFolder)
+            .organizes(&in_view)
+            .insert(&mut *sp);
+    }
+
+    let view = ViewDescription {
+        view_id: view_id.clone(),
+        ..Default::default()
+    };
+    let r = session
+        .browse(
+            &[
+                hierarchical_desc(view_id.clone()),
+                hierarchical_desc(in_view.clone()),
+                hierarchical_desc(ObjectId::ObjectsFolder.into()),
+            ],
+            1000,
+            Some(view.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(r.len(), 3);
+    let refs = r[0].references.clone().unwrap_or_default();
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].node_id.node_id, in_view);
+    let refs = r[1].references.clone().unwrap_or_default();
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].node_id.node_id, child);
+    assert_eq!(r[2].status_code, StatusCode::BadNodeNotInView);
+
+    // Inverse references leaving the view are excluded as well.
+    let mut desc = hierarchical_desc(in_view.clone());
+    desc.browse_direction = BrowseDirection::Inverse;
+    let r = session
+        .browse(&[desc], 1000, Some(view.clone()))
+        .await
+        .unwrap();
+    let refs = r[0].references.clone().unwrap_or_default();
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].node_id.node_id, view_id);
+
+    // Unknown views, and nodes that are not views, are rejected.
+    for view_id in [nm.inner().next_node_id(), in_view.clone()] {
+        let r = session
+            .browse(
+                &[hierarchical_desc(in_view.clone())],
+                1000,
+                Some(ViewDescription {
+                    view_id,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(r, StatusCode::BadViewIdUnknown);
+    }
+
+    let r = session
+        .browse(
+            &[hierarchical_desc(in_view.clone())],
+            1000,
+            Some(ViewDescription {
+                view_version: 5,
+                ..view
+            }),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(r, StatusCode::BadViewVersionInvalid);
+}
+
+#[tokio::test]
+async fn translate_browse_path_view() {
+    let (_tester, nm, session) = setup().await;
+    let view_id = nm.inner().next_node_id();
+    let in_view = nm.inner().next_node_id();
+    {
+        let mut sp = nm.address_space().write();
+        ObjectBuilder::new(&in_view, "InView", "InView")
+            .organized_by(ObjectId::ObjectsFolder)
+            .has_type_definition(ObjectTypeId::FolderType)
+            .insert(&mut *sp);
+        ViewBuilder::new(&view_id, "TestView", "TestView")
+            .organized_by(ObjectId::ViewsFolder)
+            .organizes(&in_view)
+            .insert(&mut *sp);
+    }
+
+    let path = |elements: &[(ReferenceTypeId, &str)]| BrowsePath {
+        starting_node: view_id.clone(),
+        relative_path: RelativePath {
+            elements: Some(
+                elements
+                    .iter()
+                    .map(|(ty, name)| RelativePathElement {
+                        reference_type_id: (*ty).into(),
+                        is_inverse: false,
+                        include_subtypes: true,
+                        target_name: (*name).into(),
+                    })
+                    .collect(),
+            ),
+        },
+    };
+
+    // Paths starting at a View only resolve to nodes in the view, the type definition
+    // of a node in the view is not itself part of it.
+    let r = session
+        .translate_browse_paths_to_node_ids(&[
+            path(&[(ReferenceTypeId::Organizes, "InView")]),
+            path(&[
+                (ReferenceTypeId::Organizes, "InView"),
+                (ReferenceTypeId::HasTypeDefinition, "FolderType"),
+            ]),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(r[0].status_code, StatusCode::Good);
+    let targets = r[0].targets.clone().unwrap_or_default();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].target_id.node_id, in_view);
+    assert_eq!(r[1].status_code, StatusCode::BadNoMatch);
+}
+
 #[tokio::test]
 async fn test_recursive_browser() {
     let (_tester, _nm, session) = setup().await;
@@ -0,0 +1,530 @@
+use std::time::Duration;
+
+use crate::utils::ChannelNotifications;
+
+use super::utils::setup;
+use opcua::{
+    server::conditions::Condition,
+    types::{
+        AttributeId, ByteString, CallMethodRequest, EventFilter, ExtensionObject, LocalizedText,
+        MethodId, MonitoredItemCreateRequest, MonitoringMode, MonitoringParameters, NodeId,
+        ObjectId, ObjectTypeId, ReadValueId, SimpleAttributeOperand, StatusCode,
+        TimestampsToReturn, Variant,
+    },
+};
+use opcua_types::ContentFilter;
+use tokio::{sync::mpsc::UnboundedReceiver, time::timeout};
+
+fn condition_event_filter() -> EventFilter {
+    EventFilter {
+        select_clauses: Some(vec![
+            SimpleAttributeOperand::new(
+                ObjectTypeId::BaseEventType,
+                "EventId",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand::new(
+                ObjectTypeId::BaseEventType,
+                "EventType",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand {
+                type_definition_id: ObjectTypeId::ConditionType.into(),
+                browse_path: Some(Vec::new()),
+                attribute_id: AttributeId::NodeId as u32,
+                index_range: Default::default(),
+            },
+            SimpleAttributeOperand::new(
+                ObjectTypeId::ConditionType,
+                "Retain",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand::new(
+                ObjectTypeId::AcknowledgeableConditionType,
+                "AckedState/Id",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand::new(
+                ObjectTypeId::AlarmConditionType,
+                "ActiveState/Id",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand::new(
+                ObjectTypeId::ConditionType,
+                "Comment",
+                AttributeId::Value,
+                Default::default(),
+            ),
+        ]),
+        where_clause: ContentFilter { elements: None },
+    }
+}
+
+async fn next_event(
+    events: &mut UnboundedReceiver<(ReadValueId, Option<Vec<Variant>>)>,
+) -> Vec<Variant> {
+    let (_, fields) = timeout(Duration::from_millis(1000), events.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    fields.unwrap()
+}
+
+fn event_id(fields: &[Variant]) -> ByteString {
+    let Variant::ByteString(b) = &fields[0] else {
+        panic!("Expected EventId, got {:?}", fields[0]);
+    };
+    b.clone()
+}
+
+#[tokio::test]
+async fn alarm_acknowledge() {
+    let (tester, nm, session) = setup().await;
+    let (notifs, _data, mut events) = ChannelNotifications::new();
+
+    let alarm_id = nm.inner().next_node_id();
+    let conditions = tester.handle.conditions();
+    conditions.add(
+        Condition::new_alarm(
+            alarm_id.clone(),
+            ObjectTypeId::AlarmConditionType,
+            ObjectId::Server,
+            "TestAlarm",
+        )
+        .set_source_name("Server")
+        .set_severity(500),
+    );
+
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![MonitoredItemCreateRequest {
+                item_to_monitor: ReadValueId {
+                    node_id: ObjectId::Server.into(),
+                    attribute_id: AttributeId::EventNotifier as u32,
+                    ..Default::default()
+                },
+                monitoring_mode: MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval: 0.0,
+                    queue_size: 100,
+                    discard_oldest: true,
+                    filter: ExtensionObject::from_message(condition_event_filter()),
+                    ..Default::default()
+                },
+            }],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+
+    conditions
+        .set_active(&alarm_id, true, "Alarm is active")
+        .unwrap();
+
+    let fields = next_event(&mut events).await;
+    assert_eq!(
+        fields[1],
+        Variant::from(NodeId::from(ObjectTypeId::AlarmConditionType))
+    );
+    assert_eq!(fields[2], Variant::from(alarm_id.clone()));
+    assert_eq!(fields[3], Variant::from(true));
+    assert_eq!(fields[4], Variant::from(false));
+    assert_eq!(fields[5], Variant::from(true));
+    let evt_id = event_id(&fields);
+
+    // Acknowledging with an unknown event ID fails.
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: alarm_id.clone(),
+            method_id: MethodId::AcknowledgeableConditionType_Acknowledge.into(),
+            input_arguments: Some(vec![
+                ByteString::from(vec![1u8, 2, 3]).into(),
+                LocalizedText::null().into(),
+            ]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadEventIdUnknown);
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: alarm_id.clone(),
+            method_id: MethodId::AcknowledgeableConditionType_Acknowledge.into(),
+            input_arguments: Some(vec![
+                evt_id.clone().into(),
+                LocalizedText::from("Handled").into(),
+            ]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+
+    let fields = next_event(&mut events).await;
+    assert_ne!(event_id(&fields), evt_id);
+    // Still active, so still retained.
+    assert_eq!(fields[3], Variant::from(true));
+    assert_eq!(fields[4], Variant::from(true));
+    assert_eq!(fields[6], Variant::from(LocalizedText::from("Handled")));
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: alarm_id.clone(),
+            method_id: MethodId::AcknowledgeableConditionType_Acknowledge.into(),
+            input_arguments: Some(vec![event_id(&fields).into(), LocalizedText::null().into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadConditionBranchAlreadyAcked);
+
+    conditions
+        .set_active(&alarm_id, false, "Alarm is inactive")
+        .unwrap();
+    let fields = next_event(&mut events).await;
+    assert_eq!(fields[3], Variant::from(false));
+    assert_eq!(fields[5], Variant::from(false));
+
+    let c = conditions.get(&alarm_id).unwrap();
+    assert_eq!(c.is_acked(), Some(true));
+    assert!(!c.is_retained());
+}
+
+#[tokio::test]
+async fn alarm_set_active_unchanged() {
+    let (tester, nm, session) = setup().await;
+    let (notifs, _data, mut events) = ChannelNotifications::new();
+
+    let alarm_id = nm.inner().next_node_id();
+    let conditions = tester.handle.conditions();
+    conditions.add(Condition::new_alarm(
+        alarm_id.clone(),
+        ObjectTypeId::AlarmConditionType,
+        ObjectId::Server,
+        "TestAlarm",
+    ));
+
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![MonitoredItemCreateRequest {
+                item_to_monitor: ReadValueId {
+                    node_id: ObjectId::Server.into(),
+                    attribute_id: AttributeId::EventNotifier as u32,
+                    ..Default::default()
+                },
+                monitoring_mode: MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval: 0.0,
+                    queue_size: 100,
+                    discard_oldest: true,
+                    filter: ExtensionObject::from_message(condition_event_filter()),
+                    ..Default::default()
+                },
+            }],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+
+    conditions.set_active(&alarm_id, true, "Active").unwrap();
+    let fields = next_event(&mut events).await;
+    let evt_id = event_id(&fields);
+
+    // Re-asserting the current state does not produce a new event.
+    conditions.set_active(&alarm_id, true, "Active").unwrap();
+    assert_eq!(conditions.get(&alarm_id).unwrap().event_id(), &evt_id);
+    assert!(timeout(Duration::from_millis(300), events.recv())
+        .await
+        .is_err());
+
+    conditions.set_active(&alarm_id, false, "Inactive").unwrap();
+    let fields = next_event(&mut events).await;
+    assert_ne!(event_id(&fields), evt_id);
+    assert_eq!(fields[5], Variant::from(false));
+}
+
+#[tokio::test]
+async fn alarm_enable_disable() {
+    let (tester, nm, session) = setup().await;
+
+    let alarm_id = nm.inner().next_node_id();
+    let conditions = tester.handle.conditions();
+    conditions.add(Condition::new_alarm(
+        alarm_id.clone(),
+        ObjectTypeId::AlarmConditionType,
+        ObjectId::Server,
+        "TestAlarm",
+    ));
+    conditions.set_active(&alarm_id, true, "Active").unwrap();
+
+    let call = |method: MethodId| CallMethodRequest {
+        object_id: alarm_id.clone(),
+        method_id: method.into(),
+        input_arguments: None,
+    };
+
+    let r = session
+        .call_one(call(MethodId::ConditionType_Enable))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadConditionAlreadyEnabled);
+
+    let r = session
+        .call_one(call(MethodId::ConditionType_Disable))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+    let c = conditions.get(&alarm_id).unwrap();
+    assert!(!c.is_enabled());
+    assert!(!c.is_retained());
+
+    // Methods requiring an event ID fail on disabled conditions.
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: alarm_id.clone(),
+            method_id: MethodId::ConditionType_AddComment.into(),
+            input_arguments: Some(vec![
+                c.event_id().clone().into(),
+                LocalizedText::from("Comment").into(),
+            ]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadConditionDisabled);
+
+    let r = session
+        .call_one(call(MethodId::ConditionType_Disable))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadConditionAlreadyDisabled);
+
+    let r = session
+        .call_one(call(MethodId::ConditionType_Enable))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+    assert!(conditions.get(&alarm_id).unwrap().is_retained());
+
+    // Condition methods on objects that are not conditions are rejected.
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectId::ObjectsFolder.into(),
+            method_id: MethodId::ConditionType_Enable.into(),
+            input_arguments: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadMethodInvalid);
+}
+
+#[tokio::test]
+async fn alarm_subtype_fields() {
+    let (tester, nm, session) = setup().await;
+    let (notifs, _data, mut events) = ChannelNotifications::new();
+
+    let alarm_id = nm.inner().next_node_id();
+    let conditions = tester.handle.conditions();
+    conditions.add(Condition::new_alarm(
+        alarm_id.clone(),
+        ObjectTypeId::OffNormalAlarmType,
+        ObjectId::Server,
+        "TestAlarm",
+    ));
+
+    // Select fields through intermediate supertypes of the concrete alarm type.
+    let filter = EventFilter {
+        select_clauses: Some(vec![
+            SimpleAttributeOperand::new(
+                ObjectTypeId::DiscreteAlarmType,
+                "ActiveState/Id",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand::new(
+                ObjectTypeId::OffNormalAlarmType,
+                "Retain",
+                AttributeId::Value,
+                Default::default(),
+            ),
+            SimpleAttributeOperand::new(
+                ObjectTypeId::ExclusiveLimitAlarmType,
+                "ActiveState/Id",
+                AttributeId::Value,
+                Default::default(),
+            ),
+        ]),
+        where_clause: ContentFilter { elements: None },
+    };
+
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![MonitoredItemCreateRequest {
+                item_to_monitor: ReadValueId {
+                    node_id: ObjectId::Server.into(),
+                    attribute_id: AttributeId::EventNotifier as u32,
+                    ..Default::default()
+                },
+                monitoring_mode: MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval: 0.0,
+                    queue_size: 100,
+                    discard_oldest: true,
+                    filter: ExtensionObject::from_message(filter),
+                    ..Default::default()
+                },
+            }],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+
+    conditions.set_active(&alarm_id, true, "Active").unwrap();
+
+    let fields = next_event(&mut events).await;
+    assert_eq!(fields[0], Variant::from(true));
+    assert_eq!(fields[1], Variant::from(true));
+    // Not a supertype of the alarm type.
+    assert_eq!(fields[2], Variant::Empty);
+}
+
+#[tokio::test]
+async fn condition_refresh() {
+    let (tester, nm, session) = setup().await;
+    let (notifs, _data, mut events) = ChannelNotifications::new();
+
+    let conditions = tester.handle.conditions();
+    let active_id = nm.inner().next_node_id();
+    let inactive_id = nm.inner().next_node_id();
+    for id in [&active_id, &inactive_id] {
+        conditions.add(Condition::new_alarm(
+            id.clone(),
+            ObjectTypeId::AlarmConditionType,
+            ObjectId::Server,
+            "TestAlarm",
+        ));
+    }
+    conditions.set_active(&active_id, true, "Active").unwrap();
+
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![MonitoredItemCreateRequest {
+                item_to_monitor: ReadValueId {
+                    node_id: ObjectId::Server.into(),
+                    attribute_id: AttributeId::EventNotifier as u32,
+                    ..Default::default()
+                },
+                monitoring_mode: MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval: 0.0,
+                    queue_size: 100,
+                    discard_oldest: true,
+                    filter: ExtensionObject::from_message(condition_event_filter()),
+                    ..Default::default()
+                },
+            }],
+        )
+        .await
+        .unwrap();
+    let item_id = res[0].monitored_item_id;
+
+    let check_refresh = |fields: Vec<Vec<Variant>>| {
+        assert_eq!(fields.len(), 3);
+        assert_eq!(
+            fields[0][1],
+            Variant::from(NodeId::from(ObjectTypeId::RefreshStartEventType))
+        );
+        assert_eq!(fields[1][2], Variant::from(active_id.clone()));
+        assert_eq!(
+            fields[2][1],
+            Variant::from(NodeId::from(ObjectTypeId::RefreshEndEventType))
+        );
+    };
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectTypeId::ConditionType.into(),
+            method_id: MethodId::ConditionType_ConditionRefresh.into(),
+            input_arguments: Some(vec![sub_id.into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+    let mut fields = Vec::new();
+    for _ in 0..3 {
+        fields.push(next_event(&mut events).await);
+    }
+    check_refresh(fields);
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectTypeId::ConditionType.into(),
+            method_id: MethodId::ConditionType_ConditionRefresh2.into(),
+            input_arguments: Some(vec![sub_id.into(), item_id.into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+    let mut fields = Vec::new();
+    for _ in 0..3 {
+        fields.push(next_event(&mut events).await);
+    }
+    check_refresh(fields);
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectTypeId::ConditionType.into(),
+            method_id: MethodId::ConditionType_ConditionRefresh2.into(),
+            input_arguments: Some(vec![sub_id.into(), (item_id + 100).into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadMonitoredItemIdInvalid);
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectTypeId::ConditionType.into(),
+            method_id: MethodId::ConditionType_ConditionRefresh.into(),
+            input_arguments: Some(vec![(sub_id + 100).into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadSubscriptionIdInvalid);
+
+    // ConditionRefresh is called on ConditionType, not on the server object.
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectId::Server.into(),
+            method_id: MethodId::ConditionType_ConditionRefresh.into(),
+            input_arguments: Some(vec![sub_id.into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadMethodInvalid);
+}
@@ -1,27 +1,43 @@
-use std::{sync::atomic::Ordering, time::Duration};
+use std::{
+    path::Path,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
 use super::utils::hostname;
 use bytes::BytesMut;
 use log::debug;
 use opcua::{
-    client::IdentityToken,
-    core::comms::tcp_codec::{Message, TcpCodec},
+    client::{Client, HttpsConnector, IdentityToken, StreamConnector, WebSocketConnector},
+    core::comms::{
+        tcp_codec::{Message, TcpCodec},
+        url::{HTTPS_SCHEME, OPC_TCP_SCHEME, OPC_WSS_SCHEME},
+    },
     core::config::Config,
-    crypto::SecurityPolicy,
+    crypto::{
+        CertificateStore, FileSystemCertificateStore, InMemoryCertificateStore, SecurityPolicy,
+        X509,
+    },
+    server::{HttpsListener, ServerEndpoint, WebSocketListener, ANONYMOUS_USER_TOKEN_ID},
+    sync::RwLock,
     types::{
-        ApplicationType, DecodingOptions, MessageSecurityMode, NodeId, ReadValueId, StatusCode,
-        TimestampsToReturn, VariableId, Variant,
+        profiles, ApplicationType, DecodingOptions, EndpointDescription, LocalizedText,
+        MdnsDiscoveryConfiguration, MessageSecurityMode, MonitoredItemCreateRequest,
+        MonitoringMode, MonitoringParameters, NodeId, ReadValueId, RegisteredServer, StatusCode,
+        TimestampsToReturn, UAString, UserTokenType, VariableId, Variant,
     },
 };
 use tokio::{
     io::AsyncReadExt,
     net::{TcpListener, TcpStream},
 };
+use tokio_rustls::rustls::ServerName;
 use tokio_util::codec::Decoder;
 
 use crate::utils::{
-    client_user_token, client_x509_token, copy_shared_certs, default_server, test_server, Tester,
-    CLIENT_USERPASS_ID, TEST_COUNTER,
+    client_user_token, client_x509_token, copy_shared_certs, create_ecc_certs, default_client,
+    default_server, test_server, tls_connector, tls_server_config, transport_roundtrip,
+    ChannelNotifications, LoopbackMdns, Tester, CLIENT_USERPASS_ID, TEST_COUNTER,
 };
 
 #[tokio::test]
@@ -209,6 +225,251 @@ async fn connect_aes256sha256rsapss_sign_and_encrypt() {
     .await;
 }
 
+async fn ecc_conn_test(policy: SecurityPolicy, mode: MessageSecurityMode, token: IdentityToken) {
+    opcua::console_logging::init();
+
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let user_token_ids = [
+        ANONYMOUS_USER_TOKEN_ID.to_owned(),
+        CLIENT_USERPASS_ID.to_owned(),
+    ];
+    let endpoint = match (policy, mode) {
+        (SecurityPolicy::EccNistP256, MessageSecurityMode::Sign) => {
+            ServerEndpoint::new_ecc_nist_p256_sign("/", &user_token_ids)
+        }
+        (SecurityPolicy::EccNistP256, _) => {
+            ServerEndpoint::new_ecc_nist_p256_sign_encrypt("/", &user_token_ids)
+        }
+        (_, MessageSecurityMode::Sign) => {
+            ServerEndpoint::new_ecc_nist_p384_sign("/", &user_token_ids)
+        }
+        _ => ServerEndpoint::new_ecc_nist_p384_sign_encrypt("/", &user_token_ids),
+    };
+    let server = test_server()
+        .add_endpoint("ecc", endpoint)
+        .discovery_urls(vec![format!("opc.tcp://{}:{}", hostname(), addr.port())])
+        .pki_dir(format!("./pki-server/{test_id}"));
+    // The server and client both need certificates on the curve of the policy
+    copy_shared_certs(test_id, &server.config().application_description());
+    create_ecc_certs(
+        test_id,
+        &server.config().application_description(),
+        policy.ecc_curve().unwrap(),
+    );
+
+    let (server, handle) = server.build().unwrap();
+    let _guard = handle.token().clone().drop_guard();
+    tokio::task::spawn(server.run_with(listener));
+
+    let mut client = default_client(test_id, false).client().unwrap();
+    let endpoint_url = format!("opc.tcp://{}:{}/", hostname(), addr.port());
+    let (session, handle) = client
+        .connect_to_matching_endpoint((&endpoint_url as &str, policy.to_str(), mode), token)
+        .await
+        .unwrap();
+    let _h = handle.spawn();
+
+    tokio::time::timeout(Duration::from_secs(20), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    session
+        .read(
+            &[ReadValueId::from(<VariableId as Into<NodeId>>::into(
+                VariableId::Server_ServiceLevel,
+            ))],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn connect_ecc_nist_p256_sign() {
+    ecc_conn_test(
+        SecurityPolicy::EccNistP256,
+        MessageSecurityMode::Sign,
+        IdentityToken::Anonymous,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn connect_ecc_nist_p256_sign_and_encrypt() {
+    ecc_conn_test(
+        SecurityPolicy::EccNistP256,
+        MessageSecurityMode::SignAndEncrypt,
+        IdentityToken::Anonymous,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn connect_ecc_nist_p384_sign_and_encrypt() {
+    ecc_conn_test(
+        SecurityPolicy::EccNistP384,
+        MessageSecurityMode::SignAndEncrypt,
+        IdentityToken::Anonymous,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn connect_ecc_nist_p384_with_username_password() {
+    ecc_conn_test(
+        SecurityPolicy::EccNistP384,
+        MessageSecurityMode::SignAndEncrypt,
+        client_user_token(),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn ecc_sign_endpoints_do_not_offer_passwords() {
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let user_token_ids = [
+        ANONYMOUS_USER_TOKEN_ID.to_owned(),
+        CLIENT_USERPASS_ID.to_owned(),
+    ];
+    let server = test_server()
+        .add_endpoint(
+            "ecc_sign",
+            ServerEndpoint::new_ecc_nist_p256_sign("/", &user_token_ids),
+        )
+        .add_endpoint(
+            "ecc_sign_encrypt",
+            ServerEndpoint::new_ecc_nist_p256_sign_encrypt("/", &user_token_ids),
+        )
+        .discovery_urls(vec![format!("opc.tcp://{}:{}", hostname(), addr.port())])
+        .pki_dir(format!("./pki-server/{test_id}"));
+    copy_shared_certs(test_id, &server.config().application_description());
+    create_ecc_certs(
+        test_id,
+        &server.config().application_description(),
+        SecurityPolicy::EccNistP256.ecc_curve().unwrap(),
+    );
+
+    let (server, handle) = server.build().unwrap();
+    let _guard = handle.token().clone().drop_guard();
+    tokio::task::spawn(server.run_with(listener));
+
+    // Passwords are not encrypted with ECC keys, so they are only accepted when
+    // the secure channel is encrypted.
+    let client = default_client(test_id, false).client().unwrap();
+    let endpoints = client
+        .get_server_endpoints_from_url(format!("opc.tcp://{}:{}/", hostname(), addr.port()))
+        .await
+        .unwrap();
+    for mode in [
+        MessageSecurityMode::Sign,
+        MessageSecurityMode::SignAndEncrypt,
+    ] {
+        let endpoint = endpoints
+            .iter()
+            .find(|e| {
+                e.security_policy_uri.as_ref() == SecurityPolicy::EccNistP256.to_uri()
+                    && e.security_mode == mode
+            })
+            .unwrap();
+        let has_user_pass = endpoint
+            .user_identity_tokens
+            .iter()
+            .flatten()
+            .any(|t| t.token_type == UserTokenType::UserName);
+        assert_eq!(has_user_pass, mode == MessageSecurityMode::SignAndEncrypt);
+    }
+}
+
+#[tokio::test]
+async fn connect_with_in_memory_certificate_stores() {
+    opcua::console_logging::init();
+
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Neither end has a PKI directory, and certificates are only trusted explicitly
+    let server = test_server()
+        .trust_client_certs(false)
+        .discovery_urls(vec![format!("opc.tcp://{}:{}", hostname(), addr.port())]);
+    let client = default_client(test_id, false).trust_server_certs(false);
+    let (server_cert, server_pkey) =
+        X509::cert_and_pkey(&server.config().application_description().into()).unwrap();
+    let (client_cert, client_pkey) = X509::cert_and_pkey(
+        &default_client(test_id, false)
+            .config()
+            .application_description()
+            .into(),
+    )
+    .unwrap();
+
+    let mut server_store =
+        InMemoryCertificateStore::new_with_cert_and_pkey(server_cert.clone(), server_pkey);
+    server_store.add_trusted_cert(client_cert.clone());
+    let server_store = Arc::new(RwLock::new(server_store));
+    let mut client_store =
+        InMemoryCertificateStore::new_with_cert_and_pkey(client_cert, client_pkey);
+    client_store.add_trusted_cert(server_cert);
+    let client_store = Arc::new(RwLock::new(client_store));
+
+    let (server, handle) = server
+        .with_certificate_store(server_store.clone())
+        .build()
+        .unwrap();
+    let _guard = handle.token().clone().drop_guard();
+    tokio::task::spawn(server.run_with(listener));
+
+    let mut client = client
+        .certificate_store(client_store.clone())
+        .client()
+        .unwrap();
+    let endpoint_url = format!("opc.tcp://{}:{}/", hostname(), addr.port());
+    let (session, handle) = client
+        .connect_to_matching_endpoint(
+            (
+                &endpoint_url as &str,
+                SecurityPolicy::Basic256Sha256.to_str(),
+                MessageSecurityMode::SignAndEncrypt,
+            ),
+            IdentityToken::Anonymous,
+        )
+        .await
+        .unwrap();
+    let _h = handle.spawn();
+
+    tokio::time::timeout(Duration::from_secs(20), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    session
+        .read(
+            &[ReadValueId::from(<VariableId as Into<NodeId>>::into(
+                VariableId::Server_ServiceLevel,
+            ))],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+
+    assert!(server_store.read().rejected_certs().is_empty());
+    assert!(client_store.read().rejected_certs().is_empty());
+    assert_eq!(client_store.read().trusted_certs().len(), 1);
+}
+
 #[tokio::test]
 async fn connect_basic128rsa15_with_username_password() {
     conn_test(
@@ -263,6 +524,324 @@ async fn find_servers() {
     assert_eq!(s.product_uri.as_ref(), "urn:integration_server Testkit");
 }
 
+#[tokio::test]
+async fn local_discovery_server() {
+    let mut tester = Tester::new(default_server().local_discovery_server(true), false).await;
+    let servers = tester
+        .client
+        .find_servers(tester.endpoint(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(servers.len(), 1);
+    assert_eq!(
+        servers[0].application_type,
+        ApplicationType::DiscoveryServer
+    );
+
+    let semaphore_file = format!("./pki-server/{}/semaphore", tester.test_id);
+    let registered = RegisteredServer {
+        server_uri: "urn:registered_server".into(),
+        product_uri: "urn:registered_server Testkit".into(),
+        server_names: Some(vec![
+            LocalizedText::new("en", "Registered server"),
+            LocalizedText::new("de", "Registrierter Server"),
+        ]),
+        server_type: ApplicationType::Server,
+        gateway_server_uri: UAString::null(),
+        discovery_urls: Some(vec!["opc.tcp://localhost:4855/".into()]),
+        semaphore_file_path: semaphore_file.as_str().into(),
+        is_online: true,
+    };
+
+    // Servers can only register with the application URI of their own certificate.
+    let res = tester
+        .client
+        .register_server(tester.endpoint(), registered.clone())
+        .await;
+    assert_eq!(res, Err(StatusCode::BadServerUriInvalid));
+
+    let mut client = default_client(tester.test_id, false)
+        .application_uri("urn:registered_server")
+        .pki_dir(format!("./pki-client/{}-registered", tester.test_id))
+        .client()
+        .unwrap();
+
+    // The semaphore file must exist when registering.
+    let res = client
+        .register_server(tester.endpoint(), registered.clone())
+        .await;
+    assert_eq!(res, Err(StatusCode::BadSempahoreFileMissing));
+
+    std::fs::write(&semaphore_file, []).unwrap();
+    client
+        .register_server(tester.endpoint(), registered.clone())
+        .await
+        .unwrap();
+
+    let servers = tester
+        .client
+        .find_servers(tester.endpoint(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(servers.len(), 2);
+
+    // Filter by server URI, and pick the server name by locale.
+    let servers = tester
+        .client
+        .find_servers(
+            tester.endpoint(),
+            Some(vec!["de".into()]),
+            Some(vec!["urn:registered_server".into()]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(servers.len(), 1);
+    let s = &servers[0];
+    assert_eq!(s.application_uri.as_ref(), "urn:registered_server");
+    assert_eq!(s.application_name.text.as_ref(), "Registrierter Server");
+    assert_eq!(s.application_type, ApplicationType::Server);
+    // localhost is replaced with the host used to reach the discovery server.
+    assert_eq!(
+        s.discovery_urls.as_ref().unwrap()[0].as_ref(),
+        format!("opc.tcp://{}:4855/", hostname())
+    );
+
+    // Deleting the semaphore file removes the registration.
+    std::fs::remove_file(&semaphore_file).unwrap();
+    let servers = tester
+        .client
+        .find_servers(tester.endpoint(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(servers.len(), 1);
+
+    // Registering as offline removes the registration.
+    let registered = RegisteredServer {
+        semaphore_file_path: UAString::null(),
+        ..registered
+    };
+    client
+        .register_server(tester.endpoint(), registered.clone())
+        .await
+        .unwrap();
+    let servers = tester
+        .client
+        .find_servers(tester.endpoint(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(servers.len(), 2);
+    client
+        .register_server(
+            tester.endpoint(),
+            RegisteredServer {
+                is_online: false,
+                ..registered
+            },
+        )
+        .await
+        .unwrap();
+    let servers = tester
+        .client
+        .find_servers(tester.endpoint(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(servers.len(), 1);
+}
+
+#[tokio::test]
+async fn discovery_server_registration() {
+    let tester = Tester::new(default_server().local_discovery_server(true), false).await;
+
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = default_server()
+        .application_uri("urn:registered_server")
+        .pki_dir(format!("./pki-server/{test_id}"))
+        .discovery_urls(vec![format!("opc.tcp://{}:{}/", hostname(), port)])
+        .discovery_server_url(tester.endpoint());
+    // The registering server needs its own certificate, since it can only
+    // register with the application URI of that certificate.
+    let _ = std::fs::remove_dir_all(format!("./pki-server/{test_id}"));
+
+    // The registering server must trust the discovery server.
+    let lds_cert = FileSystemCertificateStore::read_cert(Path::new(&format!(
+        "./pki-server/{}/own/cert.der",
+        tester.test_id
+    )))
+    .unwrap();
+    std::fs::create_dir_all(format!("./pki-server/{test_id}/trusted")).unwrap();
+    std::fs::copy(
+        format!("./pki-server/{}/own/cert.der", tester.test_id),
+        format!(
+            "./pki-server/{test_id}/trusted/{}",
+            FileSystemCertificateStore::cert_file_name(&lds_cert)
+        ),
+    )
+    .unwrap();
+
+    let (server, handle) = server.build().unwrap();
+    let _guard = handle.token().clone().drop_guard();
+    tokio::task::spawn(server.run_with(listener));
+
+    // The server registers itself with the discovery server as soon as it starts.
+    let servers = tokio::time::timeout(Duration::from_secs(20), async {
+        loop {
+            let servers = tester
+                .client
+                .find_servers(tester.endpoint(), None, None)
+                .await
+                .unwrap();
+            if servers.len() > 1 {
+                break servers;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let s = servers
+        .iter()
+        .find(|s| s.application_uri.as_ref() == "urn:registered_server")
+        .unwrap();
+    assert_eq!(s.application_type, ApplicationType::Server);
+    assert_eq!(s.application_name.text.as_ref(), "integration_server");
+    assert_eq!(
+        s.discovery_urls.as_ref().unwrap()[0].as_ref(),
+        format!("opc.tcp://{}:{}/", hostname(), port)
+    );
+}
+
+#[tokio::test]
+async fn multicast_discovery() {
+    let mdns = Arc::new(LoopbackMdns::default());
+    let tester = Tester::new(
+        default_server()
+            .local_discovery_server(true)
+            .mdns_server_name("lds")
+            .with_mdns_responder(mdns.clone()),
+        false,
+    )
+    .await;
+    let _other = Tester::new(
+        default_server()
+            .mdns_server_name("da_server")
+            .add_server_capability("DA")
+            .with_mdns_responder(mdns.clone()),
+        false,
+    )
+    .await;
+
+    // Servers registered with an mDNS configuration are announced by the discovery server.
+    let registry = tester.handle.info().server_registry.as_ref().unwrap();
+    let registered = RegisteredServer {
+        server_uri: "urn:registered_server".into(),
+        product_uri: "urn:registered_server Testkit".into(),
+        server_names: Some(vec![LocalizedText::new("en", "Registered server")]),
+        server_type: ApplicationType::Server,
+        gateway_server_uri: UAString::null(),
+        discovery_urls: Some(vec!["opc.tcp://localhost:4855/path".into()]),
+        semaphore_file_path: UAString::null(),
+        is_online: true,
+    };
+    registry
+        .register(
+            registered.clone(),
+            Some(MdnsDiscoveryConfiguration {
+                mdns_server_name: "registered".into(),
+                server_capabilities: Some(vec!["HD".into()]),
+            }),
+        )
+        .unwrap();
+
+    let wait_for_servers = |count: usize| {
+        let tester = &tester;
+        async move {
+            tokio::time::timeout(Duration::from_secs(20), async {
+                loop {
+                    let servers = tester
+                        .client
+                        .find_servers_on_network(tester.endpoint(), 0, 0, None)
+                        .await
+                        .unwrap()
+                        .servers
+                        .unwrap_or_default();
+                    if servers.len() == count {
+                        break servers;
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            })
+            .await
+            .unwrap()
+        }
+    };
+
+    let servers = wait_for_servers(3).await;
+    let find = |name: &str| {
+        servers
+            .iter()
+            .find(|s| s.server_name.as_ref() == name)
+            .unwrap()
+            .clone()
+    };
+    let lds = find("lds");
+    assert_eq!(lds.server_capabilities, Some(vec![UAString::from("LDS")]));
+    assert_eq!(
+        lds.discovery_url.as_ref(),
+        format!("opc.tcp://{}:{}", hostname(), tester.addr.port())
+    );
+    let da = find("da_server");
+    assert_eq!(da.server_capabilities, Some(vec![UAString::from("DA")]));
+    let reg = find("registered");
+    assert_eq!(reg.discovery_url.as_ref(), "opc.tcp://localhost:4855/path");
+    assert_eq!(reg.server_capabilities, Some(vec![UAString::from("HD")]));
+
+    // Capability filters are case insensitive.
+    let res = tester
+        .client
+        .find_servers_on_network(tester.endpoint(), 0, 0, Some(vec!["da".into()]))
+        .await
+        .unwrap();
+    let filtered = res.servers.unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].server_name.as_ref(), "da_server");
+
+    // Paging with max records and starting record ID.
+    let res = tester
+        .client
+        .find_servers_on_network(tester.endpoint(), 0, 1, None)
+        .await
+        .unwrap();
+    let first = res.servers.unwrap();
+    assert_eq!(first.len(), 1);
+    let res = tester
+        .client
+        .find_servers_on_network(tester.endpoint(), first[0].record_id, 0, None)
+        .await
+        .unwrap();
+    let rest = res.servers.unwrap();
+    assert_eq!(rest.len(), 2);
+    assert!(rest.iter().all(|s| s.record_id > first[0].record_id));
+
+    // Unregistering the server withdraws its announcement.
+    registry
+        .register(
+            RegisteredServer {
+                is_online: false,
+                ..registered
+            },
+            None,
+        )
+        .unwrap();
+    let servers = wait_for_servers(2).await;
+    assert!(servers
+        .iter()
+        .all(|s| s.server_name.as_ref() != "registered"));
+}
+
 #[tokio::test]
 async fn discovery_test() {
     let tester = Tester::new_default_server(true).await;
@@ -397,3 +976,257 @@ async fn recoverable_error_test_server() {
         .await
         .unwrap();
 }
+
+/// The client cannot get endpoints when using reverse connect,
+/// so fetch them using a regular connection first.
+async fn reverse_connect_endpoint(tester: &Tester) -> EndpointDescription {
+    let endpoints = tester
+        .client
+        .get_server_endpoints_from_url(tester.endpoint())
+        .await
+        .unwrap();
+    Client::find_matching_endpoint(
+        &endpoints,
+        &tester.endpoint(),
+        SecurityPolicy::None,
+        MessageSecurityMode::None,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn reverse_connect() {
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let client_url = format!(
+        "opc.tcp://{}:{}",
+        hostname(),
+        listener.local_addr().unwrap().port()
+    );
+    let server = test_server()
+        .add_reverse_connect_target(client_url)
+        .reverse_connect_interval_ms(100);
+    let mut tester = Tester::new(server, false).await;
+
+    let endpoint = reverse_connect_endpoint(&tester).await;
+    let (session, lp) = tester
+        .client
+        .connect_to_endpoint_reverse(
+            endpoint,
+            IdentityToken::Anonymous,
+            listener,
+            "urn:integration_server",
+        )
+        .unwrap();
+    lp.spawn();
+
+    tokio::time::timeout(Duration::from_secs(5), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    session
+        .read(
+            &[ReadValueId::from(<VariableId as Into<NodeId>>::into(
+                VariableId::Server_ServiceLevel,
+            ))],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn reverse_connect_unexpected_server() {
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let client_url = format!(
+        "opc.tcp://{}:{}",
+        hostname(),
+        listener.local_addr().unwrap().port()
+    );
+    let server = test_server()
+        .add_reverse_connect_target(client_url)
+        .reverse_connect_interval_ms(100);
+    let mut tester = Tester::new(server, false).await;
+
+    let endpoint = reverse_connect_endpoint(&tester).await;
+    let (session, lp) = tester
+        .client
+        .connect_to_endpoint_reverse(
+            endpoint,
+            IdentityToken::Anonymous,
+            listener,
+            "urn:some_other_server",
+        )
+        .unwrap();
+    lp.spawn();
+
+    // The client should reject the connection, since the server URI does not match.
+    tokio::time::timeout(Duration::from_secs(1), session.wait_for_connection())
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn in_memory_transport() {
+    // The server accepts in-memory streams sent on a channel.
+    let (stream_send, stream_recv) = tokio::sync::mpsc::channel(1);
+    let connector = || {
+        let stream_send = stream_send.clone();
+        StreamConnector::new(move || {
+            let stream_send = stream_send.clone();
+            async move {
+                let (client_stream, server_stream) = tokio::io::duplex(65536);
+                stream_send
+                    .send(server_stream)
+                    .await
+                    .map_err(|_| std::io::Error::other("Server closed"))?;
+                Ok(client_stream)
+            }
+        })
+    };
+    transport_roundtrip(
+        stream_recv,
+        connector,
+        OPC_TCP_SCHEME,
+        profiles::TRANSPORT_PROFILE_URI_BINARY,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn websocket_transport() {
+    let listener = Tester::listener().await;
+    let addr = listener.local_addr().unwrap();
+    let tls = tls_connector();
+    let connector = || {
+        let tls = tls.clone();
+        WebSocketConnector::new(move || {
+            let tls = tls.clone();
+            async move {
+                let stream = TcpStream::connect(addr).await?;
+                let server_name = ServerName::try_from(hostname().as_str()).unwrap();
+                tls.connect(server_name, stream).await
+            }
+        })
+    };
+    transport_roundtrip(
+        WebSocketListener::with_tls(listener, tls_server_config()),
+        connector,
+        OPC_WSS_SCHEME,
+        profiles::TRANSPORT_PROFILE_URI_WSS_BINARY,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn https_transport() {
+    let listener = Tester::listener().await;
+    let addr = listener.local_addr().unwrap();
+    let tls = tls_connector();
+    let connector = || {
+        let tls = tls.clone();
+        HttpsConnector::new(move || {
+            let tls = tls.clone();
+            async move {
+                let stream = TcpStream::connect(addr).await?;
+                let server_name = ServerName::try_from(hostname().as_str()).unwrap();
+                tls.connect(server_name, stream).await
+            }
+        })
+    };
+    let (session, _guard) = transport_roundtrip(
+        HttpsListener::with_tls(listener, tls_server_config()),
+        connector,
+        HTTPS_SCHEME,
+        profiles::TRANSPORT_PROFILE_URI_HTTPS_BINARY,
+    )
+    .await;
+
+    // Publish requests are held by the server, so other requests must be able
+    // to use the session on other connections at the same time.
+    let (notifs, mut data, _) = ChannelNotifications::new();
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let service_level: NodeId = VariableId::Server_ServiceLevel.into();
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![MonitoredItemCreateRequest {
+                item_to_monitor: ReadValueId::from(service_level.clone()),
+                monitoring_mode: MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval: 0.0,
+                    queue_size: 10,
+                    discard_oldest: true,
+                    ..Default::default()
+                },
+            }],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+
+    let (r, _) = tokio::time::timeout(Duration::from_secs(2), data.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(r.node_id, service_level);
+}
+
+#[tokio::test]
+async fn https_without_tls() {
+    opcua::console_logging::init();
+
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let listener = TcpListener::bind(format!("{}:0", hostname()))
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = test_server()
+        .pki_dir(format!("./pki-server/{test_id}"))
+        .discovery_urls(vec![format!("https://{}:{}", hostname(), addr.port())]);
+    copy_shared_certs(test_id, &server.config().application_description());
+    let (server, handle) = server.build().unwrap();
+    let _guard = handle.token().clone().drop_guard();
+
+    tokio::task::spawn(server.run_with(HttpsListener::new(listener)));
+
+    let client = default_client(test_id, false).client().unwrap();
+    let connector = || HttpsConnector::new(move || TcpStream::connect(addr));
+    let endpoint_url = format!("https://{}:{}/", hostname(), addr.port());
+    let endpoints = client
+        .get_endpoints_with_connector(&endpoint_url, connector())
+        .await
+        .unwrap();
+
+    // Without TLS, messages are not encrypted, so only the None security policy is accepted.
+    for (policy, mode, connects) in [
+        (SecurityPolicy::None, MessageSecurityMode::None, true),
+        (
+            SecurityPolicy::Basic256Sha256,
+            MessageSecurityMode::SignAndEncrypt,
+            false,
+        ),
+    ] {
+        let endpoint =
+            Client::find_matching_endpoint(&endpoints, &endpoint_url, policy, mode).unwrap();
+        let (session, lp) = client
+            .session_builder()
+            .connector(connector())
+            .connect_to_endpoint_directly(endpoint)
+            .unwrap()
+            .user_identity_token(IdentityToken::Anonymous)
+            .build(client.certificate_store().clone());
+        let lp = lp.spawn();
+        let res = tokio::time::timeout(Duration::from_secs(2), session.wait_for_connection()).await;
+        assert_eq!(res.is_ok(), connects);
+        lp.abort();
+    }
+}
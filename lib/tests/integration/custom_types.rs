@@ -0,0 +1,184 @@
+use super::utils::{read_value_id, setup, TestNodeManager, Tester};
+use opcua::{
+    server::address_space::{AccessLevel, DataTypeBuilder, ObjectBuilder, VariableBuilder},
+    types::{
+        custom::DynamicStructure, AttributeId, DataTypeDefinition, DataTypeId, DataValue,
+        EUInformation, ExtensionObject, NodeId, ObjectId, ObjectTypeId, QualifiedName,
+        ReferenceTypeId, StructureDefinition, StructureField, StructureType, TimestampsToReturn,
+        VariableTypeId, Variant,
+    },
+};
+
+/// Add a custom structure type with a binary encoding to the test node manager,
+/// returning the ID of the data type and the ID of the binary encoding.
+fn add_custom_struct(tester: &Tester, nm: &TestNodeManager) -> (NodeId, NodeId) {
+    let type_id = nm.inner().next_node_id();
+    let encoding_id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        DataTypeBuilder::new(&type_id, "CustomStruct", "CustomStruct")
+            .data_type_definition(DataTypeDefinition::Structure(StructureDefinition {
+                default_encoding_id: encoding_id.clone(),
+                base_data_type: DataTypeId::Structure.into(),
+                structure_type: StructureType::Structure,
+                fields: Some(vec![
+                    StructureField {
+                        name: "Id".into(),
+                        data_type: DataTypeId::Int32.into(),
+                        value_rank: -1,
+                        ..Default::default()
+                    },
+                    StructureField {
+                        name: "Name".into(),
+                        data_type: DataTypeId::String.into(),
+                        value_rank: -1,
+                        ..Default::default()
+                    },
+                    StructureField {
+                        name: "Values".into(),
+                        data_type: DataTypeId::Double.into(),
+                        value_rank: 1,
+                        ..Default::default()
+                    },
+                    StructureField {
+                        name: "Unit".into(),
+                        data_type: DataTypeId::EUInformation.into(),
+                        value_rank: -1,
+                        ..Default::default()
+                    },
+                ]),
+            }))
+            .build()
+            .into(),
+        &DataTypeId::Structure.into(),
+        &ReferenceTypeId::HasSubtype.into(),
+        None,
+        Vec::new(),
+    );
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        ObjectBuilder::new(
+            &encoding_id,
+            QualifiedName::new(0, "Default Binary"),
+            "Default Binary",
+        )
+        .build()
+        .into(),
+        &type_id,
+        &ReferenceTypeId::HasEncoding.into(),
+        Some(&ObjectTypeId::DataTypeEncodingType.into()),
+        Vec::new(),
+    );
+    (type_id, encoding_id)
+}
+
+#[tokio::test]
+async fn load_data_types() {
+    let (tester, nm, session) = setup().await;
+    let (type_id, encoding_id) = add_custom_struct(&tester, &nm);
+
+    let tree = session.load_data_types().await.unwrap();
+
+    let ty = tree.get_struct_type(&type_id).unwrap();
+    assert_eq!(ty.structure_type, StructureType::Structure);
+    assert!(!ty.is_abstract);
+    assert_eq!(ty.encoding_ids.binary_id, encoding_id);
+    assert_eq!(ty.fields.len(), 4);
+    assert_eq!(ty.get_field_by_name("Values").unwrap().value_rank, 1);
+    assert_eq!(
+        tree.encoding_to_data_type().get(&encoding_id),
+        Some(&type_id)
+    );
+
+    // Types from the core namespace are loaded too.
+    let eu = tree
+        .get_struct_type(&DataTypeId::EUInformation.into())
+        .unwrap();
+    assert_eq!(eu.fields.len(), 4);
+    // Abstract structures without definitions are included.
+    assert!(
+        tree.get_struct_type(&DataTypeId::Structure.into())
+            .unwrap()
+            .is_abstract
+    );
+    assert!(tree.get_type(&DataTypeId::Int32.into()).is_some());
+}
+
+#[tokio::test]
+async fn read_custom_struct() {
+    let (tester, nm, session) = setup().await;
+    let (type_id, _) = add_custom_struct(&tester, &nm);
+
+    let var_id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        VariableBuilder::new(&var_id, "CustomVar", "CustomVar")
+            .data_type(type_id.clone())
+            .access_level(AccessLevel::CURRENT_READ)
+            .user_access_level(AccessLevel::CURRENT_READ)
+            .build()
+            .into(),
+        &ObjectId::ObjectsFolder.into(),
+        &ReferenceTypeId::Organizes.into(),
+        Some(&VariableTypeId::BaseDataVariableType.into()),
+        Vec::new(),
+    );
+
+    let tree = session.load_data_types().await.unwrap();
+    let value = DynamicStructure::new_struct(
+        tree.get_struct_type(&type_id).unwrap().clone(),
+        tree.clone(),
+        vec![
+            Variant::from(5i32),
+            Variant::from("Custom"),
+            Variant::from(vec![1.5f64, 2.5]),
+            Variant::from(ExtensionObject::from_message(EUInformation {
+                namespace_uri: "my.namespace.uri".into(),
+                unit_id: 5,
+                display_name: "Degrees Celsius".into(),
+                description: "Description".into(),
+            })),
+        ],
+    )
+    .unwrap();
+    nm.set_value(
+        tester.handle.subscriptions(),
+        &var_id,
+        None,
+        DataValue::new_now(ExtensionObject::from_message(value.clone())),
+    )
+    .unwrap();
+
+    let r = session
+        .read(
+            &[read_value_id(AttributeId::Value, var_id.clone())],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+    let Some(Variant::ExtensionObject(obj)) = &r[0].value else {
+        panic!("Expected extension object, got {:?}", r[0]);
+    };
+    let read = obj.inner_as::<DynamicStructure>().unwrap();
+    assert_eq!(read, &value);
+    assert_eq!(read.get_field_by_name("Id"), Some(&Variant::from(5i32)));
+
+    // Loading the types again replaces the type loader, reading still works.
+    session.load_data_types().await.unwrap();
+    let r = session
+        .read(
+            &[read_value_id(AttributeId::Value, var_id)],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+    let Some(Variant::ExtensionObject(obj)) = &r[0].value else {
+        panic!("Expected extension object, got {:?}", r[0]);
+    };
+    assert_eq!(obj.inner_as::<DynamicStructure>().unwrap(), &value);
+}
@@ -0,0 +1,273 @@
+use std::time::Duration;
+
+use crate::utils::ChannelNotifications;
+
+use super::utils::{client_user_token, setup};
+use opcua::{
+    crypto::SecurityPolicy,
+    types::{
+        AttributeId, BrowseDescription, BrowseDirection, BrowseResultMask, DataValue,
+        MessageSecurityMode, NodeClassMask, NodeId, ObjectId, ObjectTypeId, ReadValueId,
+        ReferenceTypeId, ServerDiagnosticsSummaryDataType, SessionDiagnosticsDataType,
+        SessionSecurityDiagnosticsDataType, StatusCode, SubscriptionDiagnosticsDataType,
+        TimestampsToReturn, VariableId, Variant,
+    },
+};
+
+async fn read_value(session: &opcua::client::Session, node_id: NodeId) -> DataValue {
+    session
+        .read(
+            &[ReadValueId {
+                node_id,
+                attribute_id: AttributeId::Value as u32,
+                ..Default::default()
+            }],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap()
+}
+
+async fn browse_components(
+    session: &opcua::client::Session,
+    node_id: NodeId,
+) -> Vec<(String, NodeId)> {
+    let r = session
+        .browse(
+            &[BrowseDescription {
+                node_id,
+                browse_direction: BrowseDirection::Forward,
+                reference_type_id: ReferenceTypeId::HasComponent.into(),
+                include_subtypes: true,
+                node_class_mask: NodeClassMask::all().bits(),
+                result_mask: BrowseResultMask::All as u32,
+            }],
+            1000,
+            None,
+        )
+        .await
+        .unwrap();
+    r[0].references
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| (r.browse_name.name.as_ref().to_owned(), r.node_id.node_id))
+        .collect()
+}
+
+/// Find the `SessionSecurityDiagnostics` node of the session with ID `session_id`.
+async fn find_security_diagnostics(
+    session: &opcua::client::Session,
+    session_id: &NodeId,
+) -> NodeId {
+    let summary = browse_components(
+        session,
+        ObjectId::Server_ServerDiagnostics_SessionsDiagnosticsSummary.into(),
+    )
+    .await;
+    for (_, node_id) in summary {
+        let components = browse_components(session, node_id).await;
+        let Some((_, diag_node)) = components.iter().find(|(n, _)| n == "SessionDiagnostics")
+        else {
+            continue;
+        };
+        let v = read_value(session, diag_node.clone()).await;
+        let Some(Variant::ExtensionObject(obj)) = v.value else {
+            continue;
+        };
+        if &obj
+            .inner_as::<SessionDiagnosticsDataType>()
+            .unwrap()
+            .session_id
+            == session_id
+        {
+            return components
+                .into_iter()
+                .find(|(n, _)| n == "SessionSecurityDiagnostics")
+                .unwrap()
+                .1;
+        }
+    }
+    panic!("Session {session_id} not found");
+}
+
+#[tokio::test]
+async fn server_diagnostics_summary() {
+    let (_tester, _nm, session) = setup().await;
+
+    let v = read_value(
+        &session,
+        VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary.into(),
+    )
+    .await;
+    let Some(Variant::ExtensionObject(obj)) = v.value else {
+        panic!("Expected extension object, got {:?}", v.value);
+    };
+    let summary = obj.inner_as::<ServerDiagnosticsSummaryDataType>().unwrap();
+    assert_eq!(summary.current_session_count, 1);
+    assert!(summary.cumulated_session_count >= 1);
+
+    let v = read_value(
+        &session,
+        VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_CurrentSessionCount.into(),
+    )
+    .await;
+    assert_eq!(v.value, Some(Variant::UInt32(1)));
+
+    let v = read_value(
+        &session,
+        VariableId::Server_ServerDiagnostics_EnabledFlag.into(),
+    )
+    .await;
+    assert_eq!(v.value, Some(Variant::Boolean(true)));
+}
+
+#[tokio::test]
+async fn session_diagnostics() {
+    let (_tester, _nm, session) = setup().await;
+
+    // Find the diagnostics object for this session.
+    let r = session
+        .browse(
+            &[BrowseDescription {
+                node_id: ObjectId::Server_ServerDiagnostics_SessionsDiagnosticsSummary.into(),
+                browse_direction: BrowseDirection::Forward,
+                reference_type_id: ReferenceTypeId::HasComponent.into(),
+                include_subtypes: true,
+                node_class_mask: NodeClassMask::OBJECT.bits(),
+                result_mask: BrowseResultMask::All as u32,
+            }],
+            1000,
+            None,
+        )
+        .await
+        .unwrap();
+    let refs = r[0].references.clone().unwrap_or_default();
+    let session_refs: Vec<_> = refs
+        .iter()
+        .filter(|r| r.type_definition.node_id == ObjectTypeId::SessionDiagnosticsObjectType)
+        .collect();
+    assert_eq!(session_refs.len(), 1);
+    let session_node = session_refs[0].node_id.node_id.clone();
+
+    // Browse the session object for its components.
+    let r = session
+        .browse(
+            &[BrowseDescription {
+                node_id: session_node,
+                browse_direction: BrowseDirection::Forward,
+                reference_type_id: ReferenceTypeId::HasComponent.into(),
+                include_subtypes: true,
+                node_class_mask: NodeClassMask::all().bits(),
+                result_mask: BrowseResultMask::All as u32,
+            }],
+            1000,
+            None,
+        )
+        .await
+        .unwrap();
+    let refs = r[0].references.clone().unwrap_or_default();
+    assert_eq!(refs.len(), 3);
+    let diag_node = refs
+        .iter()
+        .find(|r| r.browse_name.name.as_ref() == "SessionDiagnostics")
+        .unwrap()
+        .node_id
+        .node_id
+        .clone();
+    let sub_diag_node = refs
+        .iter()
+        .find(|r| r.browse_name.name.as_ref() == "SubscriptionDiagnosticsArray")
+        .unwrap()
+        .node_id
+        .node_id
+        .clone();
+
+    let v = read_value(&session, diag_node).await;
+    let Some(Variant::ExtensionObject(obj)) = v.value else {
+        panic!("Expected extension object, got {:?}", v.value);
+    };
+    let diag = obj.inner_as::<SessionDiagnosticsDataType>().unwrap();
+    assert_eq!(diag.session_id, session.server_session_id());
+    // At least the reads and browses issued above.
+    assert!(diag.read_count.total_count >= 1);
+    assert!(diag.browse_count.total_count >= 2);
+    assert_eq!(diag.current_subscriptions_count, 0);
+
+    // Create a subscription, and check that it shows up in the diagnostics.
+    let (notifs, _data, _) = ChannelNotifications::new();
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+
+    let v = read_value(&session, sub_diag_node).await;
+    let Some(Variant::Array(arr)) = v.value else {
+        panic!("Expected array, got {:?}", v.value);
+    };
+    assert_eq!(arr.values.len(), 1);
+    let Variant::ExtensionObject(obj) = &arr.values[0] else {
+        panic!("Expected extension object, got {:?}", arr.values[0]);
+    };
+    let sub_diag = obj.inner_as::<SubscriptionDiagnosticsDataType>().unwrap();
+    assert_eq!(sub_diag.subscription_id, sub_id);
+    assert_eq!(sub_diag.session_id, session.server_session_id());
+
+    // The server-wide array contains the same subscription.
+    let v = read_value(
+        &session,
+        VariableId::Server_ServerDiagnostics_SubscriptionDiagnosticsArray.into(),
+    )
+    .await;
+    let Some(Variant::Array(arr)) = v.value else {
+        panic!("Expected array, got {:?}", v.value);
+    };
+    assert_eq!(arr.values.len(), 1);
+}
+
+#[tokio::test]
+async fn session_security_diagnostics_access() {
+    let (mut tester, _nm, anonymous) = setup().await;
+    let user = tester
+        .connect_and_wait(
+            SecurityPolicy::Basic256Sha256,
+            MessageSecurityMode::SignAndEncrypt,
+            client_user_token(),
+        )
+        .await
+        .unwrap();
+
+    let user_node = find_security_diagnostics(&anonymous, &user.server_session_id()).await;
+    let anonymous_node =
+        find_security_diagnostics(&anonymous, &anonymous.server_session_id()).await;
+
+    // A session may read its own security diagnostics.
+    let v = read_value(&user, user_node.clone()).await;
+    let Some(Variant::ExtensionObject(obj)) = v.value else {
+        panic!("Expected extension object, got {:?}", v.value);
+    };
+    let diag = obj
+        .inner_as::<SessionSecurityDiagnosticsDataType>()
+        .unwrap();
+    assert_eq!(diag.session_id, user.server_session_id());
+    let v = read_value(&anonymous, anonymous_node).await;
+    assert_eq!(v.status, Some(StatusCode::Good));
+
+    // But not those of other sessions.
+    let v = read_value(&anonymous, user_node).await;
+    assert_eq!(v.status, Some(StatusCode::BadUserAccessDenied));
+    assert_eq!(v.value, None);
+
+    // The server-wide array is restricted to security administrators.
+    let v = read_value(
+        &anonymous,
+        VariableId::Server_ServerDiagnostics_SessionsDiagnosticsSummary_SessionSecurityDiagnosticsArray
+            .into(),
+    )
+    .await;
+    assert_eq!(v.status, Some(StatusCode::BadUserAccessDenied));
+}
@@ -0,0 +1,278 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::utils::{default_client, test_server, ChannelNotifications, TestNodeManager, Tester};
+use futures::StreamExt;
+use opcua::{
+    server::address_space::{AccessLevel, VariableBuilder},
+    types::{
+        AttributeId, DataTypeId, DataValue, EndpointDescription, MessageSecurityMode,
+        MonitoredItemCreateRequest, MonitoringParameters, NodeId, ObjectId, ReadValueId,
+        ReferenceTypeId, StatusCode, TimestampsToReturn, UserTokenPolicy, VariableTypeId, Variant,
+    },
+};
+use opcua_client::{FailoverConfig, FailoverMode, Session, SessionConnectMode, SessionPollResult};
+use opcua_crypto::SecurityPolicy;
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+    time::timeout,
+};
+
+struct RedundantPair {
+    a: Tester,
+    nm_a: Arc<TestNodeManager>,
+    b: Tester,
+    nm_b: Arc<TestNodeManager>,
+    id: NodeId,
+}
+
+fn add_variable(tester: &Tester, nm: &TestNodeManager, id: &NodeId, value: i32) {
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        VariableBuilder::new(id, "FailoverVar", "FailoverVar")
+            .value(value)
+            .data_type(DataTypeId::Int32)
+            .access_level(AccessLevel::CURRENT_READ)
+            .user_access_level(AccessLevel::CURRENT_READ)
+            .build()
+            .into(),
+        &ObjectId::ObjectsFolder.into(),
+        &ReferenceTypeId::Organizes.into(),
+        Some(&VariableTypeId::BaseDataVariableType.into()),
+        Vec::new(),
+    );
+}
+
+/// Start two servers with the same variable, with value 1 on the first server,
+/// and 2 on the second.
+async fn redundant_pair() -> RedundantPair {
+    let a = Tester::new(test_server(), false).await;
+    let b = Tester::new(test_server(), false).await;
+    let nm_a = a
+        .handle
+        .node_managers()
+        .get_of_type::<TestNodeManager>()
+        .unwrap();
+    let nm_b = b
+        .handle
+        .node_managers()
+        .get_of_type::<TestNodeManager>()
+        .unwrap();
+    let id = nm_a.inner().next_node_id();
+    add_variable(&a, &nm_a, &id, 1);
+    add_variable(&b, &nm_b, &id, 2);
+
+    RedundantPair {
+        a,
+        nm_a,
+        b,
+        nm_b,
+        id,
+    }
+}
+
+fn endpoint(tester: &Tester) -> EndpointDescription {
+    (
+        tester.endpoint().as_str(),
+        SecurityPolicy::None.to_uri(),
+        MessageSecurityMode::None,
+        UserTokenPolicy::anonymous(),
+    )
+        .into()
+}
+
+/// Connect to the first server of the pair, with failover to the second,
+/// forwarding events from the session event loop to a channel.
+async fn connect_with_failover(
+    pair: &RedundantPair,
+    config: impl FnOnce(FailoverConfig) -> FailoverConfig,
+) -> (Arc<Session>, UnboundedReceiver<SessionPollResult>) {
+    let client = default_client(pair.a.test_id, false)
+        .keep_alive_interval(Duration::from_millis(100))
+        .client()
+        .unwrap();
+    let (session, event_loop) = client
+        .session_builder()
+        .connect_to_endpoint_directly(endpoint(&pair.a))
+        .unwrap()
+        .failover(config(FailoverConfig::new(vec![endpoint(&pair.b)])))
+        .build(client.certificate_store().clone());
+
+    let (tx, rx): (UnboundedSender<_>, _) = tokio::sync::mpsc::unbounded_channel();
+    tokio::task::spawn(async move {
+        let stream = event_loop.enter();
+        tokio::pin!(stream);
+        while let Some(Ok(evt)) = stream.next().await {
+            if tx.send(evt).is_err() {
+                break;
+            }
+        }
+    });
+
+    timeout(Duration::from_secs(2), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    (session, rx)
+}
+
+async fn wait_for_failover(
+    events: &mut UnboundedReceiver<SessionPollResult>,
+) -> (String, SessionConnectMode) {
+    timeout(Duration::from_secs(10), async {
+        loop {
+            if let SessionPollResult::FailedOver(url, mode) = events.recv().await.unwrap() {
+                break (url.as_ref().to_owned(), mode);
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
+
+async fn subscribe(session: &Session, id: &NodeId) -> UnboundedReceiver<(ReadValueId, DataValue)> {
+    let (notifs, data, _) = ChannelNotifications::new();
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![MonitoredItemCreateRequest {
+                item_to_monitor: ReadValueId {
+                    node_id: id.clone(),
+                    attribute_id: AttributeId::Value as u32,
+                    ..Default::default()
+                },
+                monitoring_mode: opcua::types::MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval: 0.0,
+                    queue_size: 10,
+                    discard_oldest: true,
+                    ..Default::default()
+                },
+            }],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+    data
+}
+
+async fn next_value(data: &mut UnboundedReceiver<(ReadValueId, DataValue)>) -> i32 {
+    let (_, v) = timeout(Duration::from_secs(2), data.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    match v.value {
+        Some(Variant::Int32(v)) => v,
+        _ => panic!("Expected integer value"),
+    }
+}
+
+#[tokio::test]
+async fn failover_on_connection_lost() {
+    let pair = redundant_pair().await;
+    let (session, mut events) = connect_with_failover(&pair, |c| c).await;
+
+    let mut data = subscribe(&session, &pair.id).await;
+    assert_eq!(next_value(&mut data).await, 1);
+
+    // Stop the first server, the session should move to the second.
+    pair.a.handle.cancel();
+    let (url, mode) = wait_for_failover(&mut events).await;
+    assert_eq!(url, pair.b.endpoint());
+    assert!(matches!(mode, SessionConnectMode::NewSession(_)));
+    assert_eq!(session.endpoint_url().as_ref(), pair.b.endpoint());
+
+    // The subscription is recreated on the second server.
+    assert_eq!(next_value(&mut data).await, 2);
+    pair.nm_b
+        .set_value(
+            pair.b.handle.subscriptions(),
+            &pair.id,
+            None,
+            DataValue::new_now(3),
+        )
+        .unwrap();
+    assert_eq!(next_value(&mut data).await, 3);
+
+    let status = session.redundancy_status().unwrap();
+    assert_eq!(status.len(), 2);
+    assert!(!status[0].active);
+    assert!(status[1].active);
+}
+
+#[tokio::test]
+async fn failover_on_degraded_service_level() {
+    let pair = redundant_pair().await;
+    let (session, mut events) = connect_with_failover(&pair, |c| c.mode(FailoverMode::Warm)).await;
+
+    let mut data = subscribe(&session, &pair.id).await;
+    assert_eq!(next_value(&mut data).await, 1);
+
+    pair.a.handle.set_service_level(50);
+    let (url, _) = wait_for_failover(&mut events).await;
+    assert_eq!(url, pair.b.endpoint());
+    assert_eq!(next_value(&mut data).await, 2);
+
+    let status = session.redundancy_status().unwrap();
+    assert_eq!(status[0].service_level, Some(50));
+    assert!(status[1].active);
+
+    // The first server is still running, but the session no longer uses it.
+    pair.nm_a
+        .set_value(
+            pair.a.handle.subscriptions(),
+            &pair.id,
+            None,
+            DataValue::new_now(4),
+        )
+        .unwrap();
+    pair.nm_b
+        .set_value(
+            pair.b.handle.subscriptions(),
+            &pair.id,
+            None,
+            DataValue::new_now(5),
+        )
+        .unwrap();
+    assert_eq!(next_value(&mut data).await, 5);
+}
+
+#[tokio::test]
+async fn no_failover_on_degraded_service_level_in_cold_mode() {
+    let pair = redundant_pair().await;
+    let (session, mut events) = connect_with_failover(&pair, |c| c.mode(FailoverMode::Cold)).await;
+
+    pair.a.handle.set_service_level(50);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    while let Ok(evt) = events.try_recv() {
+        assert!(!matches!(evt, SessionPollResult::FailedOver(..)));
+    }
+    assert_eq!(session.endpoint_url().as_ref(), pair.a.endpoint());
+    assert_eq!(
+        session.redundancy_status().unwrap()[0].service_level,
+        Some(50)
+    );
+}
+
+#[tokio::test]
+async fn mirrored_failover_without_mirrored_servers() {
+    // If the new server does not know the session, it is recreated,
+    // along with its subscriptions.
+    let pair = redundant_pair().await;
+    let (session, mut events) =
+        connect_with_failover(&pair, |c| c.mode(FailoverMode::HotAndMirrored)).await;
+
+    let mut data = subscribe(&session, &pair.id).await;
+    assert_eq!(next_value(&mut data).await, 1);
+
+    pair.a.handle.set_service_level(50);
+    let (url, mode) = wait_for_failover(&mut events).await;
+    assert_eq!(url, pair.b.endpoint());
+    assert!(matches!(mode, SessionConnectMode::NewSession(_)));
+    assert_eq!(next_value(&mut data).await, 2);
+}
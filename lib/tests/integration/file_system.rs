@@ -0,0 +1,353 @@
+use std::{fs, path::Path, sync::Arc};
+
+use opcua::{
+    client::{IdentityToken, Session},
+    crypto::SecurityPolicy,
+    server::node_manager::memory::{
+        FileNodeManagerBuilder, InMemoryNodeManagerBuilder, NamespaceMetadata,
+    },
+    types::{
+        AttributeId, ByteString, CallMethodRequest, Identifier, MessageSecurityMode, NodeId,
+        ObjectId, ReadValueId, StatusCode, TimestampsToReturn, Variant,
+    },
+};
+use tempdir::TempDir;
+
+use super::utils::{default_server, Tester};
+
+const NAMESPACE: &str = "urn:rustopcua:files";
+
+async fn setup_files(dir: &Path) -> (Tester, Arc<Session>, u16) {
+    fs::write(dir.join("hello.txt"), "Hello world").unwrap();
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub").join("nested.txt"), "nested").unwrap();
+
+    let server = default_server().with_node_manager(InMemoryNodeManagerBuilder::new(
+        FileNodeManagerBuilder::new(
+            NamespaceMetadata {
+                namespace_uri: NAMESPACE.to_owned(),
+                ..Default::default()
+            },
+            "files",
+        )
+        .add_directory(dir, "files", ObjectId::ObjectsFolder, true),
+    ));
+    let mut tester = Tester::new(server, false).await;
+    let session = tester
+        .connect_and_wait(
+            SecurityPolicy::None,
+            MessageSecurityMode::None,
+            IdentityToken::Anonymous,
+        )
+        .await
+        .unwrap();
+    let ns = tester.handle.get_namespace_index(NAMESPACE).unwrap();
+    (tester, session, ns)
+}
+
+async fn call(
+    session: &Session,
+    object_id: &NodeId,
+    method: &str,
+    args: Vec<Variant>,
+) -> Result<Vec<Variant>, StatusCode> {
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: object_id.clone(),
+            method_id: component_id(object_id, method),
+            input_arguments: Some(args),
+        })
+        .await
+        .unwrap();
+    if r.status_code.is_good() {
+        Ok(r.output_arguments.unwrap_or_default())
+    } else {
+        Err(r.status_code)
+    }
+}
+
+/// Get the ID of the component `name` of the file or directory `object_id`.
+fn component_id(object_id: &NodeId, name: &str) -> NodeId {
+    let Identifier::String(id) = &object_id.identifier else {
+        panic!("Expected string node ID, got {object_id}");
+    };
+    NodeId::new(object_id.namespace, format!("{id}//{name}"))
+}
+
+async fn read_property(session: &Session, object_id: &NodeId, name: &str) -> Variant {
+    let r = session
+        .read(
+            &[ReadValueId {
+                node_id: component_id(object_id, name),
+                attribute_id: AttributeId::Value as u32,
+                ..Default::default()
+            }],
+            TimestampsToReturn::Neither,
+            0.0,
+        )
+        .await
+        .unwrap();
+    r[0].value.clone().unwrap()
+}
+
+async fn open(session: &Session, file: &NodeId, mode: u8) -> Result<Variant, StatusCode> {
+    call(session, file, "Open", vec![mode.into()])
+        .await
+        .map(|r| r[0].clone())
+}
+
+#[tokio::test]
+async fn file_read_write() {
+    let dir = TempDir::new("file_read_write").unwrap();
+    let (_tester, session, ns) = setup_files(dir.path()).await;
+    let file = NodeId::new(ns, "files/hello.txt");
+
+    assert_eq!(
+        read_property(&session, &file, "Size").await,
+        Variant::UInt64(11)
+    );
+    assert_eq!(
+        read_property(&session, &file, "Writable").await,
+        Variant::Boolean(true)
+    );
+
+    // Read part of the file, then move the position and read the rest.
+    let handle = open(&session, &file, 1).await.unwrap();
+    assert_eq!(
+        read_property(&session, &file, "OpenCount").await,
+        Variant::UInt16(1)
+    );
+    let r = call(&session, &file, "Read", vec![handle.clone(), 5i32.into()])
+        .await
+        .unwrap();
+    assert_eq!(r[0], Variant::ByteString(ByteString::from(b"Hello")));
+    let r = call(&session, &file, "GetPosition", vec![handle.clone()])
+        .await
+        .unwrap();
+    assert_eq!(r[0], Variant::UInt64(5));
+    call(
+        &session,
+        &file,
+        "SetPosition",
+        vec![handle.clone(), 6u64.into()],
+    )
+    .await
+    .unwrap();
+    let r = call(&session, &file, "Read", vec![handle.clone(), 100i32.into()])
+        .await
+        .unwrap();
+    assert_eq!(r[0], Variant::ByteString(ByteString::from(b"world")));
+
+    // The file cannot be written through a read handle, or opened for writing
+    // while it is open.
+    let r = call(
+        &session,
+        &file,
+        "Write",
+        vec![handle.clone(), ByteString::from(b"data").into()],
+    )
+    .await;
+    assert_eq!(r, Err(StatusCode::BadInvalidState));
+    assert_eq!(
+        open(&session, &file, 2).await,
+        Err(StatusCode::BadNotWritable)
+    );
+    call(&session, &file, "Close", vec![handle.clone()])
+        .await
+        .unwrap();
+    assert_eq!(
+        call(&session, &file, "Close", vec![handle]).await,
+        Err(StatusCode::BadInvalidArgument)
+    );
+
+    // Write | EraseExisting replaces the content.
+    let handle = open(&session, &file, 2 | 4).await.unwrap();
+    assert_eq!(
+        open(&session, &file, 1).await,
+        Err(StatusCode::BadNotReadable)
+    );
+    call(
+        &session,
+        &file,
+        "Write",
+        vec![handle.clone(), ByteString::from(b"New content").into()],
+    )
+    .await
+    .unwrap();
+    call(&session, &file, "Close", vec![handle]).await.unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("hello.txt")).unwrap(),
+        "New content"
+    );
+
+    // Append without write is invalid.
+    assert_eq!(
+        open(&session, &file, 1 | 8).await,
+        Err(StatusCode::BadInvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn file_handles_belong_to_session() {
+    let dir = TempDir::new("file_handles_belong_to_session").unwrap();
+    let (mut tester, session, ns) = setup_files(dir.path()).await;
+    let file = NodeId::new(ns, "files/hello.txt");
+
+    let handle = open(&session, &file, 1).await.unwrap();
+    let other = tester
+        .connect_and_wait(
+            SecurityPolicy::None,
+            MessageSecurityMode::None,
+            IdentityToken::Anonymous,
+        )
+        .await
+        .unwrap();
+    let r = call(&other, &file, "Read", vec![handle, 5i32.into()]).await;
+    assert_eq!(r, Err(StatusCode::BadInvalidArgument));
+
+    // Closing the session releases its handles.
+    session.disconnect().await.unwrap();
+    assert_eq!(
+        read_property(&other, &file, "OpenCount").await,
+        Variant::UInt16(0)
+    );
+    open(&other, &file, 2).await.unwrap();
+}
+
+#[tokio::test]
+async fn directory_create_delete() {
+    let dir = TempDir::new("directory_create_delete").unwrap();
+    let (_tester, session, ns) = setup_files(dir.path()).await;
+    let root = NodeId::new(ns, "files");
+
+    let r = call(&session, &root, "CreateDirectory", vec!["new_dir".into()])
+        .await
+        .unwrap();
+    let new_dir = NodeId::new(ns, "files/new_dir");
+    assert_eq!(r[0], Variant::from(new_dir.clone()));
+    assert!(dir.path().join("new_dir").is_dir());
+
+    let r = call(
+        &session,
+        &new_dir,
+        "CreateFile",
+        vec!["file.bin".into(), true.into()],
+    )
+    .await
+    .unwrap();
+    let new_file = NodeId::new(ns, "files/new_dir/file.bin");
+    assert_eq!(r[0], Variant::from(new_file.clone()));
+    let handle = r[1].clone();
+    call(
+        &session,
+        &new_file,
+        "Write",
+        vec![handle.clone(), ByteString::from(vec![1, 2, 3]).into()],
+    )
+    .await
+    .unwrap();
+
+    // Existing names and invalid names are rejected.
+    let r = call(&session, &root, "CreateDirectory", vec!["new_dir".into()]).await;
+    assert_eq!(r, Err(StatusCode::BadBrowseNameDuplicated));
+    let r = call(&session, &root, "CreateDirectory", vec!["a/b".into()]).await;
+    assert_eq!(r, Err(StatusCode::BadInvalidArgument));
+
+    // The directory cannot be deleted while a file in it is open.
+    let r = call(&session, &root, "Delete", vec![new_dir.clone().into()]).await;
+    assert_eq!(r, Err(StatusCode::BadInvalidState));
+    call(&session, &new_file, "Close", vec![handle])
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read(dir.path().join("new_dir").join("file.bin")).unwrap(),
+        vec![1, 2, 3]
+    );
+
+    // Only direct children can be deleted.
+    let r = call(&session, &root, "Delete", vec![new_file.clone().into()]).await;
+    assert_eq!(r, Err(StatusCode::BadNotFound));
+    call(&session, &root, "Delete", vec![new_dir.clone().into()])
+        .await
+        .unwrap();
+    assert!(!dir.path().join("new_dir").exists());
+    let r = session
+        .read(
+            &[ReadValueId {
+                node_id: new_file,
+                attribute_id: AttributeId::BrowseName as u32,
+                ..Default::default()
+            }],
+            TimestampsToReturn::Neither,
+            0.0,
+        )
+        .await
+        .unwrap();
+    assert_eq!(r[0].status, Some(StatusCode::BadNodeIdUnknown));
+}
+
+#[tokio::test]
+async fn directory_move_or_copy() {
+    let dir = TempDir::new("directory_move_or_copy").unwrap();
+    let (_tester, session, ns) = setup_files(dir.path()).await;
+    let root = NodeId::new(ns, "files");
+    let sub = NodeId::new(ns, "files/sub");
+
+    // Copy a file into the sub directory with a new name.
+    let r = call(
+        &session,
+        &root,
+        "MoveOrCopy",
+        vec![
+            NodeId::new(ns, "files/hello.txt").into(),
+            sub.clone().into(),
+            true.into(),
+            "copy.txt".into(),
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(r[0], Variant::from(NodeId::new(ns, "files/sub/copy.txt")));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("sub").join("copy.txt")).unwrap(),
+        "Hello world"
+    );
+    assert!(dir.path().join("hello.txt").exists());
+
+    // Move the sub directory to a new name, the nodes of its content follow.
+    let r = call(
+        &session,
+        &root,
+        "MoveOrCopy",
+        vec![
+            sub.clone().into(),
+            root.clone().into(),
+            false.into(),
+            "moved".into(),
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(r[0], Variant::from(NodeId::new(ns, "files/moved")));
+    assert!(!dir.path().join("sub").exists());
+    let moved_file = NodeId::new(ns, "files/moved/nested.txt");
+    assert_eq!(
+        read_property(&session, &moved_file, "Size").await,
+        Variant::UInt64(6)
+    );
+
+    // A directory cannot be moved into itself.
+    let r = call(
+        &session,
+        &root,
+        "MoveOrCopy",
+        vec![
+            NodeId::new(ns, "files/moved").into(),
+            NodeId::new(ns, "files/moved").into(),
+            false.into(),
+            "inner".into(),
+        ],
+    )
+    .await;
+    assert_eq!(r, Err(StatusCode::BadInvalidArgument));
+}
@@ -0,0 +1,283 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::TimeDelta;
+use opcua::{
+    client::{HistoryReadAction, HistoryUpdateAction, Session},
+    server::{
+        address_space::{AccessLevel, VariableBuilder},
+        node_manager::memory::{
+            InMemoryHistoryConfig, InMemoryNodeManagerBuilder, NamespaceMetadata,
+            SimpleNodeManager, SimpleNodeManagerBuilder,
+        },
+    },
+    types::{
+        AggregateConfiguration, ByteString, DataTypeId, DataValue, DateTime, DeleteAtTimeDetails,
+        HistoryData, HistoryModifiedData, HistoryReadResult, HistoryReadValueId, HistoryUpdateType,
+        NodeId, ObjectId, PerformUpdateType, ReadAtTimeDetails, ReadProcessedDetails,
+        ReadRawModifiedDetails, StatusCode, StatusCodeValueType, TimestampsToReturn,
+        UpdateDataDetails, Variant,
+    },
+};
+
+use super::utils::{default_server, Tester};
+
+const NAMESPACE: &str = "urn:history-test";
+
+async fn setup_history() -> (Tester, Arc<SimpleNodeManager>, Arc<Session>, NodeId) {
+    let server = default_server().with_node_manager(
+        InMemoryNodeManagerBuilder::new(SimpleNodeManagerBuilder::new(
+            NamespaceMetadata {
+                namespace_uri: NAMESPACE.to_owned(),
+                ..Default::default()
+            },
+            "history",
+        ))
+        .with_history(InMemoryHistoryConfig {
+            max_values_per_node: 100,
+            max_values_per_read: 3,
+        }),
+    );
+    let mut tester = Tester::new(server, false).await;
+    let nm = tester
+        .handle
+        .node_managers()
+        .get_of_type::<SimpleNodeManager>()
+        .unwrap();
+    let ns = tester.handle.get_namespace_index(NAMESPACE).unwrap();
+    let id = NodeId::new(ns, "HistoryVar");
+    {
+        let mut address_space = nm.address_space().write();
+        let access =
+            AccessLevel::CURRENT_READ | AccessLevel::HISTORY_READ | AccessLevel::HISTORY_WRITE;
+        VariableBuilder::new(&id, "HistoryVar", "HistoryVar")
+            .organized_by(ObjectId::ObjectsFolder)
+            .data_type(DataTypeId::Double)
+            .historizing(true)
+            .access_level(access)
+            .user_access_level(access)
+            .value(0.0)
+            .insert(&mut *address_space);
+    }
+
+    let (session, lp) = tester.connect_default().await.unwrap();
+    lp.spawn();
+    tokio::time::timeout(Duration::from_secs(2), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    (tester, nm, session, id)
+}
+
+async fn read_history(
+    session: &Session,
+    action: HistoryReadAction,
+    id: &NodeId,
+    continuation_point: ByteString,
+) -> HistoryReadResult {
+    session
+        .history_read(
+            action,
+            TimestampsToReturn::Both,
+            false,
+            &[HistoryReadValueId {
+                node_id: id.clone(),
+                index_range: Default::default(),
+                data_encoding: Default::default(),
+                continuation_point,
+            }],
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap()
+}
+
+fn history_values(res: &HistoryReadResult) -> Vec<DataValue> {
+    res.history_data
+        .inner_as::<HistoryData>()
+        .unwrap()
+        .data_values
+        .clone()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn history_recorded_values() {
+    let (tester, nm, session, id) = setup_history().await;
+
+    let start = DateTime::now() - TimeDelta::try_seconds(100).unwrap();
+    let at = |s: f64| start + TimeDelta::try_milliseconds((s * 1000.0) as i64).unwrap();
+    for i in 0..10 {
+        nm.set_value(
+            tester.handle.subscriptions(),
+            &id,
+            None,
+            DataValue::new_at(i as f64, at(i as f64)),
+        )
+        .unwrap();
+    }
+    assert_eq!(nm.history().unwrap().values(&id).len(), 10);
+
+    // Raw values are returned three at a time.
+    let action = HistoryReadAction::ReadRawModifiedDetails(ReadRawModifiedDetails {
+        is_read_modified: false,
+        start_time: start,
+        end_time: at(20.0),
+        num_values_per_node: 0,
+        return_bounds: false,
+    });
+    let mut values = Vec::new();
+    let mut cp = ByteString::null();
+    loop {
+        let res = read_history(&session, action.clone(), &id, cp).await;
+        assert_eq!(res.status_code, StatusCode::Good);
+        let page = history_values(&res);
+        assert!(page.len() <= 3);
+        values.extend(page);
+        cp = res.continuation_point;
+        if cp.is_null() {
+            break;
+        }
+    }
+    assert_eq!(values.len(), 10);
+    for (i, v) in values.iter().enumerate() {
+        assert_eq!(v.value, Some(Variant::Double(i as f64)));
+        assert_eq!(v.source_timestamp, Some(at(i as f64)));
+    }
+
+    // Values between raw values are interpolated.
+    let res = read_history(
+        &session,
+        HistoryReadAction::ReadAtTimeDetails(ReadAtTimeDetails {
+            req_times: Some(vec![at(1.5), at(4.0)]),
+            use_simple_bounds: true,
+        }),
+        &id,
+        ByteString::null(),
+    )
+    .await;
+    let values = history_values(&res);
+    assert_eq!(values[0].value, Some(Variant::Double(1.5)));
+    assert_eq!(
+        values[0].status().value_type(),
+        StatusCodeValueType::Interpolated
+    );
+    assert_eq!(values[1].value, Some(Variant::Double(4.0)));
+    assert_eq!(values[1].status().value_type(), StatusCodeValueType::Raw);
+
+    // Processed reads use the aggregate library.
+    let res = session
+        .history_read(
+            HistoryReadAction::ReadProcessedDetails(ReadProcessedDetails {
+                start_time: start,
+                end_time: at(10.0),
+                processing_interval: 5000.0,
+                aggregate_type: Some(vec![ObjectId::AggregateFunction_Average.into()]),
+                aggregate_configuration: AggregateConfiguration {
+                    use_server_capabilities_defaults: true,
+                    ..Default::default()
+                },
+            }),
+            TimestampsToReturn::Both,
+            false,
+            &[HistoryReadValueId {
+                node_id: id.clone(),
+                index_range: Default::default(),
+                data_encoding: Default::default(),
+                continuation_point: ByteString::null(),
+            }],
+        )
+        .await
+        .unwrap();
+    let values = history_values(&res[0]);
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0].value, Some(Variant::Double(2.0)));
+    assert_eq!(values[1].value, Some(Variant::Double(7.0)));
+}
+
+#[tokio::test]
+async fn history_update_values() {
+    let (tester, nm, session, id) = setup_history().await;
+
+    let start = DateTime::now() - TimeDelta::try_seconds(100).unwrap();
+    let at = |s: i64| start + TimeDelta::try_seconds(s).unwrap();
+    for i in 0..3 {
+        nm.set_value(
+            tester.handle.subscriptions(),
+            &id,
+            None,
+            DataValue::new_at(i as f64, at(i)),
+        )
+        .unwrap();
+    }
+
+    let results = session
+        .history_update(&[
+            HistoryUpdateAction::UpdateDataDetails(UpdateDataDetails {
+                node_id: id.clone(),
+                perform_insert_replace: PerformUpdateType::Update,
+                update_values: Some(vec![
+                    DataValue::new_at(10.0, at(0)),
+                    DataValue::new_at(15.0, at(5)),
+                ]),
+            }),
+            HistoryUpdateAction::DeleteAtTimeDetails(DeleteAtTimeDetails {
+                node_id: id.clone(),
+                req_times: Some(vec![at(1), at(7)]),
+            }),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(results[0].status_code, StatusCode::Good);
+    assert_eq!(
+        results[0].operation_results,
+        Some(vec![
+            StatusCode::GoodEntryReplaced,
+            StatusCode::GoodEntryInserted
+        ])
+    );
+    assert_eq!(
+        results[1].operation_results,
+        Some(vec![StatusCode::Good, StatusCode::BadNoEntryExists])
+    );
+
+    let values: Vec<_> = nm
+        .history()
+        .unwrap()
+        .values(&id)
+        .into_iter()
+        .map(|v| v.value.unwrap())
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            Variant::Double(10.0),
+            Variant::Double(2.0),
+            Variant::Double(15.0)
+        ]
+    );
+
+    // The replaced and deleted values can be read as modified values.
+    let res = read_history(
+        &session,
+        HistoryReadAction::ReadRawModifiedDetails(ReadRawModifiedDetails {
+            is_read_modified: true,
+            start_time: start,
+            end_time: at(10),
+            num_values_per_node: 0,
+            return_bounds: false,
+        }),
+        &id,
+        ByteString::null(),
+    )
+    .await;
+    let modified = res.history_data.inner_as::<HistoryModifiedData>().unwrap();
+    let values = modified.data_values.clone().unwrap();
+    let infos = modified.modification_infos.clone().unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0].value, Some(Variant::Double(0.0)));
+    assert_eq!(infos[0].update_type, HistoryUpdateType::Update);
+    assert_eq!(values[1].value, Some(Variant::Double(1.0)));
+    assert_eq!(infos[1].update_type, HistoryUpdateType::Delete);
+}
@@ -7,10 +7,10 @@ use crate::utils::ChannelNotifications;
 
 use super::utils::setup;
 use opcua::{
-    server::address_space::MethodBuilder,
+    server::{address_space::MethodBuilder, node_manager::TypedMethod},
     types::{
-        AttributeId, CallMethodRequest, DataTypeId, NodeId, ObjectId, StatusCode, Variant,
-        VariantTypeId,
+        Argument, AttributeId, CallMethodRequest, DataTypeId, NodeId, ObjectId, Range, StatusCode,
+        Variant, VariantTypeId,
     },
 };
 use opcua_types::{
@@ -130,6 +130,141 @@ async fn call_args() {
     assert_eq!(r.status_code, StatusCode::BadInvalidArgument);
 }
 
+#[tokio::test]
+async fn call_typed() {
+    let (_tester, nm, session) = setup().await;
+
+    let id = nm.inner().next_node_id();
+    let input_id = nm.inner().next_node_id();
+    let output_id = nm.inner().next_node_id();
+    let method = TypedMethod::new(|name: String, values: Vec<u32>, range: Range| async move {
+        if values.is_empty() {
+            return Err(StatusCode::BadOutOfRange);
+        }
+        let sum: u32 = values.iter().sum();
+        Ok((format!("{name}: {sum}"), range.high - range.low))
+    })
+    .input_names(&["Name", "Values", "Range"])
+    .output_names(&["Message"]);
+    {
+        let mut sp = nm.address_space().write();
+        let builder = MethodBuilder::new(&id, "TypedMethod", "TypedMethod")
+            .executable(true)
+            .user_executable(true)
+            .component_of(ObjectId::ObjectsFolder);
+        method
+            .add_arguments(builder, &mut sp, &input_id, &output_id)
+            .insert(&mut *sp);
+    }
+    nm.inner().add_typed_method(id.clone(), method);
+
+    // The argument metadata is generated from the function signature.
+    let r = session
+        .read(
+            &[
+                ReadValueId::new_value(input_id),
+                ReadValueId::new_value(output_id),
+            ],
+            TimestampsToReturn::Neither,
+            0.0,
+        )
+        .await
+        .unwrap();
+    let args: Vec<Vec<Argument>> = r
+        .into_iter()
+        .map(|v| {
+            let Some(Variant::Array(a)) = v.value else {
+                panic!("Expected array, got {:?}", v.value);
+            };
+            a.values
+                .into_iter()
+                .map(|v| match v {
+                    Variant::ExtensionObject(o) => *o.into_inner_as::<Argument>().unwrap(),
+                    v => panic!("Expected argument, got {v:?}"),
+                })
+                .collect()
+        })
+        .collect();
+    let summary = |args: &[Argument]| {
+        args.iter()
+            .map(|a| (a.name.to_string(), a.data_type.clone(), a.value_rank))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        summary(&args[0]),
+        vec![
+            ("Name".to_owned(), DataTypeId::String.into(), -1),
+            ("Values".to_owned(), DataTypeId::UInt32.into(), 1),
+            ("Range".to_owned(), DataTypeId::Range.into(), -1),
+        ]
+    );
+    assert_eq!(
+        summary(&args[1]),
+        vec![
+            ("Message".to_owned(), DataTypeId::String.into(), -1),
+            ("Output2".to_owned(), DataTypeId::Double.into(), -1),
+        ]
+    );
+
+    let call = |args: Vec<Variant>| CallMethodRequest {
+        object_id: ObjectId::ObjectsFolder.into(),
+        method_id: id.clone(),
+        input_arguments: Some(args),
+    };
+    let range = Range {
+        low: 1.0,
+        high: 3.5,
+    };
+
+    let r = session
+        .call_one(call(vec![
+            "Sum".into(),
+            vec![1u32, 2, 3].into(),
+            range.clone().into(),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+    assert_eq!(
+        r.output_arguments.unwrap(),
+        vec![Variant::from("Sum: 6"), Variant::Double(2.5)]
+    );
+
+    // Errors returned by the function are passed on.
+    let r = session
+        .call_one(call(vec![
+            "Sum".into(),
+            Vec::<u32>::new().into(),
+            range.clone().into(),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadOutOfRange);
+
+    // Missing arguments
+    let r = session.call_one(call(vec!["Sum".into()])).await.unwrap();
+    assert_eq!(r.status_code, StatusCode::BadArgumentsMissing);
+
+    // Arguments with the wrong type get individual results.
+    let r = session
+        .call_one(call(vec![
+            "Sum".into(),
+            vec![1u32, 2, 3].into(),
+            Variant::Boolean(true),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadInvalidArgument);
+    assert_eq!(
+        r.input_argument_results.unwrap(),
+        vec![
+            StatusCode::Good,
+            StatusCode::Good,
+            StatusCode::BadTypeMismatch
+        ]
+    );
+}
+
 #[tokio::test]
 async fn call_fail() {
     let (_tester, nm, session) = setup().await;
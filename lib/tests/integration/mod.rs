@@ -1,8 +1,18 @@
 mod browse;
+mod conditions;
 mod core_tests;
+mod custom_types;
+mod diagnostics;
+mod failover;
+mod file_system;
+mod history;
 mod methods;
 mod node_management;
+mod query;
 mod read;
+mod redundancy;
+mod roles;
+mod server_configuration;
 mod subscriptions;
 mod write;
 
@@ -0,0 +1,273 @@
+use super::utils::setup;
+use opcua::{
+    server::address_space::{ObjectBuilder, ObjectTypeBuilder, VariableBuilder, ViewBuilder},
+    types::{
+        AttributeId, ContentFilter, ContentFilterBuilder, DataTypeId, ExpandedNodeId, NodeId,
+        NodeTypeDescription, ObjectId, ObjectTypeId, Operand, QualifiedName, QueryDataDescription,
+        ReferenceTypeId, RelativePath, RelativePathElement, StatusCode, UAString, VariableTypeId,
+        Variant, ViewDescription,
+    },
+};
+
+fn node_type(type_id: &NodeId) -> NodeTypeDescription {
+    NodeTypeDescription {
+        type_definition_node: ExpandedNodeId::new(type_id.clone()),
+        include_sub_types: false,
+        data_to_return: Some(vec![
+            QueryDataDescription {
+                relative_path: RelativePath::default(),
+                attribute_id: AttributeId::BrowseName as u32,
+                index_range: UAString::null(),
+            },
+            QueryDataDescription {
+                relative_path: RelativePath {
+                    elements: Some(vec![RelativePathElement {
+                        reference_type_id: ReferenceTypeId::HasProperty.into(),
+                        is_inverse: false,
+                        include_subtypes: true,
+                        target_name: "Level".into(),
+                    }]),
+                },
+                attribute_id: AttributeId::Value as u32,
+                index_range: UAString::null(),
+            },
+        ]),
+    }
+}
+
+#[tokio::test]
+async fn query() {
+    let (tester, nm, session) = setup().await;
+
+    let type_id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        ObjectTypeBuilder::new(&type_id, "QueryType", "QueryType")
+            .build()
+            .into(),
+        &ObjectTypeId::BaseObjectType.into(),
+        &ReferenceTypeId::HasSubtype.into(),
+        None,
+        Vec::new(),
+    );
+    // Declare the property on the type, so that it can be used in the filter.
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        VariableBuilder::new(&nm.inner().next_node_id(), "Level", "Level")
+            .data_type(DataTypeId::Int32)
+            .value(0)
+            .build()
+            .into(),
+        &type_id,
+        &ReferenceTypeId::HasProperty.into(),
+        Some(&VariableTypeId::PropertyType.into()),
+        Vec::new(),
+    );
+
+    let mut object_ids = Vec::new();
+    {
+        let mut sp = nm.address_space().write();
+        for i in 1..4 {
+            let id = nm.inner().next_node_id();
+            ObjectBuilder::new(&id, format!("QueryObject{i}"), format!("QueryObject{i}"))
+                .organized_by(ObjectId::ObjectsFolder)
+                .has_type_definition(type_id.clone())
+                .insert(&mut *sp);
+            VariableBuilder::new(&nm.inner().next_node_id(), "Level", "Level")
+                .property_of(id.clone())
+                .has_type_definition(VariableTypeId::PropertyType)
+                .data_type(DataTypeId::Int32)
+                .value(i)
+                .insert(&mut *sp);
+            object_ids.push(id);
+        }
+    }
+
+    // Query for all nodes of the type.
+    let r = session
+        .query_first(&[node_type(&type_id)], ContentFilter::default(), 0, None)
+        .await
+        .unwrap();
+    assert!(r.continuation_point.is_null());
+    let data_sets = r.query_data_sets.unwrap_or_default();
+    assert_eq!(data_sets.len(), 3);
+    // The order of data sets is not defined.
+    for (i, id) in object_ids.iter().enumerate() {
+        let set = data_sets.iter().find(|s| &s.node_id.node_id == id).unwrap();
+        assert_eq!(set.type_definition_node.node_id, type_id);
+        let values = set.values.clone().unwrap_or_default();
+        assert_eq!(
+            values,
+            vec![
+                Variant::from(QualifiedName::from(format!("QueryObject{}", i + 1))),
+                Variant::Int32(i as i32 + 1)
+            ]
+        );
+    }
+
+    // Filter on the value of the property.
+    let filter = ContentFilterBuilder::new()
+        .gt(
+            Operand::simple_attribute(
+                type_id.clone(),
+                "Level",
+                AttributeId::Value,
+                UAString::null(),
+            ),
+            Operand::literal(1),
+        )
+        .build();
+    let r = session
+        .query_first(&[node_type(&type_id)], filter, 0, None)
+        .await
+        .unwrap();
+    let data_sets = r.query_data_sets.unwrap_or_default();
+    assert_eq!(data_sets.len(), 2);
+    assert!(data_sets.iter().all(|s| s.node_id.node_id != object_ids[0]));
+}
+
+#[tokio::test]
+async fn query_continuation_point() {
+    let (tester, nm, session) = setup().await;
+
+    let type_id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        ObjectTypeBuilder::new(&type_id, "QueryType", "QueryType")
+            .build()
+            .into(),
+        &ObjectTypeId::BaseObjectType.into(),
+        &ReferenceTypeId::HasSubtype.into(),
+        None,
+        Vec::new(),
+    );
+
+    {
+        let mut sp = nm.address_space().write();
+        for i in 0..5 {
+            ObjectBuilder::new(
+                &nm.inner().next_node_id(),
+                format!("QueryObject{i}"),
+                format!("QueryObject{i}"),
+            )
+            .organized_by(ObjectId::ObjectsFolder)
+            .has_type_definition(type_id.clone())
+            .insert(&mut *sp);
+        }
+    }
+
+    let r = session
+        .query_first(&[node_type(&type_id)], ContentFilter::default(), 2, None)
+        .await
+        .unwrap();
+    assert_eq!(r.query_data_sets.unwrap_or_default().len(), 2);
+    assert!(!r.continuation_point.is_null());
+
+    let r = session
+        .query_next(false, r.continuation_point)
+        .await
+        .unwrap();
+    assert_eq!(r.query_data_sets.unwrap_or_default().len(), 2);
+    assert!(!r.revised_continuation_point.is_null());
+
+    let r2 = session
+        .query_next(false, r.revised_continuation_point.clone())
+        .await
+        .unwrap();
+    assert_eq!(r2.query_data_sets.unwrap_or_default().len(), 1);
+    assert!(r2.revised_continuation_point.is_null());
+
+    // The continuation point has been consumed.
+    let e = session
+        .query_next(false, r.revised_continuation_point)
+        .await
+        .unwrap_err();
+    assert_eq!(e, StatusCode::BadContinuationPointInvalid);
+
+    // Release a continuation point without reading the rest.
+    let r = session
+        .query_first(&[node_type(&type_id)], ContentFilter::default(), 2, None)
+        .await
+        .unwrap();
+    let r2 = session
+        .query_next(true, r.continuation_point.clone())
+        .await
+        .unwrap();
+    assert!(r2.query_data_sets.unwrap_or_default().is_empty());
+    let e = session
+        .query_next(false, r.continuation_point)
+        .await
+        .unwrap_err();
+    assert_eq!(e, StatusCode::BadContinuationPointInvalid);
+}
+
+#[tokio::test]
+async fn query_view() {
+    let (tester, nm, session) = setup().await;
+
+    let type_id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        ObjectTypeBuilder::new(&type_id, "QueryType", "QueryType")
+            .build()
+            .into(),
+        &ObjectTypeId::BaseObjectType.into(),
+        &ReferenceTypeId::HasSubtype.into(),
+        None,
+        Vec::new(),
+    );
+
+    let in_view = nm.inner().next_node_id();
+    let view_id = nm.inner().next_node_id();
+    {
+        let mut sp = nm.address_space().write();
+        ObjectBuilder::new(&in_view, "InView", "InView")
+            .organized_by(ObjectId::ObjectsFolder)
+            .has_type_definition(type_id.clone())
+            .insert(&mut *sp);
+        ObjectBuilder::new(&nm.inner().next_node_id(), "NotInView", "NotInView")
+            .organized_by(ObjectId::ObjectsFolder)
+            .has_type_definition(type_id.clone())
+            .insert(&mut *sp);
+        ViewBuilder::new(&view_id, "TestView", "TestView")
+            .organized_by(ObjectId::ViewsFolder)
+            .organizes(&in_view)
+            .insert(&mut *sp);
+    }
+
+    let view = ViewDescription {
+        view_id,
+        ..Default::default()
+    };
+    let r = session
+        .query_first(
+            &[node_type(&type_id)],
+            ContentFilter::default(),
+            0,
+            Some(view),
+        )
+        .await
+        .unwrap();
+    let data_sets = r.query_data_sets.unwrap_or_default();
+    assert_eq!(data_sets.len(), 1);
+    assert_eq!(data_sets[0].node_id.node_id, in_view);
+
+    // Unknown views are rejected.
+    let e = session
+        .query_first(
+            &[node_type(&type_id)],
+            ContentFilter::default(),
+            0,
+            Some(ViewDescription {
+                view_id: NodeId::new(2, "NoSuchView"),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(e, StatusCode::BadViewIdUnknown);
+}
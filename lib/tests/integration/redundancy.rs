@@ -0,0 +1,361 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::utils::{array_value, default_client, hostname, read_value_id, test_server, Tester};
+use futures::StreamExt;
+use opcua::{
+    server::{
+        redundancy::{InMemorySessionReplication, ServerHealth},
+        RedundancyConfig, RedundancyMode, RedundancyPeer,
+    },
+    types::{
+        ApplicationDescription, AttributeId, BrowseDescription, BrowseDirection, BrowseResultMask,
+        EndpointDescription, MessageSecurityMode, NodeId, ObjectId, ObjectTypeId,
+        RedundancySupport, RedundantServerDataType, ReferenceTypeId, ServerState,
+        TimestampsToReturn, UAString, UserTokenPolicy, VariableId, Variant,
+    },
+};
+use opcua_client::{FailoverConfig, FailoverMode, Session, SessionConnectMode, SessionPollResult};
+use opcua_crypto::SecurityPolicy;
+use tokio::{net::TcpListener, sync::mpsc::UnboundedReceiver, time::timeout};
+
+const URI_A: &str = "urn:redundancy_server_a";
+const URI_B: &str = "urn:redundancy_server_b";
+
+struct RedundantPair {
+    a: Tester,
+    b: Tester,
+}
+
+fn url(listener: &TcpListener) -> String {
+    format!(
+        "opc.tcp://{}:{}/",
+        hostname(),
+        listener.local_addr().unwrap().port()
+    )
+}
+
+fn redundancy(mode: RedundancyMode, peer_uri: &str, peer_url: &str) -> RedundancyConfig {
+    RedundancyConfig {
+        heartbeat_interval_ms: 100,
+        heartbeat_timeout_ms: 1000,
+        ..RedundancyConfig::new(mode, vec![RedundancyPeer::new(peer_uri, peer_url)])
+    }
+}
+
+/// Start two servers in a redundant server set, each monitoring the other.
+async fn redundant_pair(mode: RedundancyMode, replicate_sessions: bool) -> RedundantPair {
+    let (listener_a, listener_b) = (Tester::listener().await, Tester::listener().await);
+    let (url_a, url_b) = (url(&listener_a), url(&listener_b));
+    let replication = Arc::new(InMemorySessionReplication::new());
+
+    let server = |uri: &str, peer_uri: &str, peer_url: &str| {
+        let server = test_server()
+            .application_uri(uri)
+            .redundancy(redundancy(mode, peer_uri, peer_url));
+        if replicate_sessions {
+            server.with_session_replication(replication.clone())
+        } else {
+            server
+        }
+    };
+
+    let a = Tester::new_with_listener(server(URI_A, URI_B, &url_b), listener_a, false).await;
+    let b = Tester::new_with_listener(server(URI_B, URI_A, &url_a), listener_b, false).await;
+    // Each server is fully healthy once it has seen the other.
+    wait_for_peer_level(&a, 255).await;
+    wait_for_peer_level(&b, 255).await;
+    RedundantPair { a, b }
+}
+
+async fn wait_for_peer_level(tester: &Tester, level: u8) {
+    timeout(Duration::from_secs(5), async {
+        while tester.handle.redundancy().peers()[0].service_level != level {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+async fn connect(tester: &mut Tester) -> Arc<Session> {
+    let (session, event_loop) = tester.connect_default().await.unwrap();
+    event_loop.spawn();
+    timeout(Duration::from_secs(2), session.wait_for_connection())
+        .await
+        .unwrap();
+    session
+}
+
+async fn read(session: &Session, id: VariableId) -> Variant {
+    session
+        .read(
+            &[read_value_id(AttributeId::Value, id)],
+            TimestampsToReturn::Neither,
+            0.0,
+        )
+        .await
+        .unwrap()
+        .remove(0)
+        .value
+        .unwrap()
+}
+
+async fn redundant_servers(session: &Session) -> Vec<RedundantServerDataType> {
+    let v = session
+        .read(
+            &[read_value_id(
+                AttributeId::Value,
+                VariableId::Server_ServerRedundancy_RedundantServerArray,
+            )],
+            TimestampsToReturn::Neither,
+            0.0,
+        )
+        .await
+        .unwrap()
+        .remove(0);
+    array_value(&v)
+        .iter()
+        .map(|v| match v {
+            Variant::ExtensionObject(o) => *o
+                .clone()
+                .into_inner_as::<RedundantServerDataType>()
+                .unwrap(),
+            _ => panic!("Expected extension object"),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn redundancy_address_space() {
+    let mut pair = redundant_pair(RedundancyMode::Hot, false).await;
+    let session = connect(&mut pair.a).await;
+
+    assert_eq!(
+        read(
+            &session,
+            VariableId::Server_ServerRedundancy_RedundancySupport
+        )
+        .await,
+        Variant::Int32(RedundancySupport::Hot as i32)
+    );
+    assert_eq!(
+        read(&session, VariableId::Server_ServerRedundancy_ServerUriArray).await,
+        Variant::from(vec![UAString::from(URI_A), UAString::from(URI_B)])
+    );
+    assert_eq!(
+        read(&session, VariableId::Server_ServiceLevel).await,
+        Variant::Byte(255)
+    );
+    let servers = redundant_servers(&session).await;
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[0].server_id.as_ref(), URI_A);
+    assert_eq!(servers[0].service_level, 255);
+    assert_eq!(servers[0].server_state, ServerState::Running);
+    assert_eq!(servers[1].server_id.as_ref(), URI_B);
+    assert_eq!(servers[1].service_level, 255);
+    assert_eq!(servers[1].server_state, ServerState::Running);
+
+    // The server redundancy object has the non-transparent type.
+    let refs = session
+        .browse(
+            &[BrowseDescription {
+                node_id: ObjectId::Server_ServerRedundancy.into(),
+                browse_direction: BrowseDirection::Forward,
+                reference_type_id: ReferenceTypeId::HasTypeDefinition.into(),
+                include_subtypes: false,
+                node_class_mask: 0,
+                result_mask: BrowseResultMask::All as u32,
+            }],
+            1000,
+            None,
+        )
+        .await
+        .unwrap();
+    let refs = refs[0].references.as_ref().unwrap();
+    assert_eq!(refs.len(), 1);
+    assert_eq!(
+        refs[0].node_id.node_id,
+        NodeId::from(ObjectTypeId::NonTransparentRedundancyType)
+    );
+}
+
+#[tokio::test]
+async fn transparent_redundancy_address_space() {
+    let mut pair = redundant_pair(RedundancyMode::Transparent, false).await;
+    let session = connect(&mut pair.b).await;
+
+    assert_eq!(
+        read(
+            &session,
+            VariableId::Server_ServerRedundancy_RedundancySupport
+        )
+        .await,
+        Variant::Int32(RedundancySupport::Transparent as i32)
+    );
+    assert_eq!(
+        read(
+            &session,
+            VariableId::Server_ServerRedundancy_CurrentServerId
+        )
+        .await,
+        Variant::from(URI_B)
+    );
+    let servers = redundant_servers(&session).await;
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[1].server_id.as_ref(), URI_A);
+}
+
+#[tokio::test]
+async fn lost_peer_lowers_service_level() {
+    let mut pair = redundant_pair(RedundancyMode::Hot, false).await;
+    let session = connect(&mut pair.a).await;
+
+    pair.b.handle.cancel();
+    wait_for_peer_level(&pair.a, 0).await;
+
+    assert_eq!(
+        read(&session, VariableId::Server_ServiceLevel).await,
+        Variant::Byte(200)
+    );
+    let servers = redundant_servers(&session).await;
+    assert_eq!(servers[0].service_level, 200);
+    assert_eq!(servers[1].service_level, 0);
+    assert_eq!(servers[1].server_state, ServerState::CommunicationFault);
+}
+
+#[tokio::test]
+async fn service_level_from_health() {
+    let mut pair = redundant_pair(RedundancyMode::Warm, false).await;
+    let session = connect(&mut pair.a).await;
+
+    for (health, level) in [
+        (ServerHealth::Degraded(0.5), 101),
+        (ServerHealth::NoData, 1),
+        (ServerHealth::Maintenance, 0),
+        (ServerHealth::Healthy, 255),
+    ] {
+        pair.a.handle.set_health(health);
+        assert_eq!(
+            read(&session, VariableId::Server_ServiceLevel).await,
+            Variant::Byte(level)
+        );
+    }
+
+    // The peer sees the new service level on its next heartbeat.
+    pair.a.handle.set_health(ServerHealth::Degraded(0.0));
+    wait_for_peer_level(&pair.b, 2).await;
+}
+
+fn endpoint(tester: &Tester, uri: &str) -> EndpointDescription {
+    let mut endpoint: EndpointDescription = (
+        tester.endpoint().as_str(),
+        SecurityPolicy::None.to_uri(),
+        MessageSecurityMode::None,
+        UserTokenPolicy::anonymous(),
+    )
+        .into();
+    endpoint.server = ApplicationDescription {
+        application_uri: uri.into(),
+        ..Default::default()
+    };
+    endpoint
+}
+
+/// Connect to the first server of the pair with failover to the second,
+/// forwarding events from the session event loop to a channel.
+async fn connect_with_failover(
+    pair: &RedundantPair,
+    mode: FailoverMode,
+) -> (Arc<Session>, UnboundedReceiver<SessionPollResult>) {
+    let client = default_client(pair.a.test_id, false)
+        .keep_alive_interval(Duration::from_millis(100))
+        .client()
+        .unwrap();
+    let (session, event_loop) = client
+        .session_builder()
+        .connect_to_endpoint_directly(endpoint(&pair.a, URI_A))
+        .unwrap()
+        .failover(FailoverConfig::new(vec![endpoint(&pair.b, URI_B)]).mode(mode))
+        .build(client.certificate_store().clone());
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::task::spawn(async move {
+        let stream = event_loop.enter();
+        tokio::pin!(stream);
+        while let Some(Ok(evt)) = stream.next().await {
+            if tx.send(evt).is_err() {
+                break;
+            }
+        }
+    });
+
+    timeout(Duration::from_secs(2), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    (session, rx)
+}
+
+async fn wait_for_failover(
+    events: &mut UnboundedReceiver<SessionPollResult>,
+) -> (String, SessionConnectMode) {
+    timeout(Duration::from_secs(10), async {
+        loop {
+            if let SessionPollResult::FailedOver(url, mode) = events.recv().await.unwrap() {
+                break (url.as_ref().to_owned(), mode);
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn client_fails_over_to_healthy_server() {
+    let pair = redundant_pair(RedundancyMode::Hot, false).await;
+    let (session, mut events) = connect_with_failover(&pair, FailoverMode::Warm).await;
+
+    // The client learns the service level of the second server
+    // from the redundant server array of the first.
+    timeout(Duration::from_secs(5), async {
+        while session.redundancy_status().unwrap()[1].service_level != Some(255) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    pair.a.handle.set_health(ServerHealth::Degraded(0.5));
+    let (url, mode) = wait_for_failover(&mut events).await;
+    assert_eq!(url, pair.b.endpoint());
+    assert!(matches!(mode, SessionConnectMode::NewSession(_)));
+
+    let status = session.redundancy_status().unwrap();
+    assert_eq!(status[0].service_level, Some(101));
+    assert!(status[1].active);
+}
+
+#[tokio::test]
+async fn mirrored_session_reactivated_on_peer() {
+    let pair = redundant_pair(RedundancyMode::HotAndMirrored, true).await;
+    let (session, mut events) = connect_with_failover(&pair, FailoverMode::HotAndMirrored).await;
+
+    pair.a.handle.set_health(ServerHealth::Maintenance);
+    let (url, mode) = wait_for_failover(&mut events).await;
+    assert_eq!(url, pair.b.endpoint());
+    assert!(matches!(mode, SessionConnectMode::ReactivatedSession(_)));
+
+    // The restored session works on the second server.
+    assert_eq!(
+        read(&session, VariableId::Server_ServerRedundancy_ServerUriArray).await,
+        Variant::from(vec![UAString::from(URI_B), UAString::from(URI_A)])
+    );
+    // The second server also has the heartbeat session from the first.
+    let sessions = pair.b.handle.session_manager().read();
+    assert!(sessions.sessions().any(|s| s
+        .read()
+        .application_description()
+        .application_uri
+        .as_ref()
+        == "x"));
+}
@@ -0,0 +1,417 @@
+use std::time::Duration;
+
+use opcua::{
+    client::IdentityToken,
+    crypto::SecurityPolicy,
+    server::address_space::{
+        AccessLevel, EventNotifier, MethodBuilder, ObjectBuilder, VariableBuilder,
+    },
+    types::{
+        AccessRestrictionType, AttributeId, BrowseDescription, BrowseDirection, BrowseResultMask,
+        CallMethodRequest, DataTypeId, DataValue, EventFilter, ExtensionObject,
+        IdentityCriteriaType, IdentityMappingRuleType, MessageSecurityMode, MethodId,
+        MonitoredItemCreateRequest, MonitoringMode, MonitoringParameters, NodeClassMask, NodeId,
+        ObjectId, ObjectTypeId, PermissionType, ReadValueId, ReferenceTypeId, RolePermissionType,
+        SimpleAttributeOperand, StatusCode, TimestampsToReturn, UAString, VariableId, Variant,
+        WriteValue,
+    },
+};
+
+use super::utils::{
+    client_user_token, read_value_id, setup, ChannelNotifications, CLIENT_USERPASS_ID,
+};
+
+fn role(role: ObjectId, permissions: PermissionType) -> RolePermissionType {
+    RolePermissionType {
+        role_id: role.into(),
+        permissions,
+    }
+}
+
+fn write_value(node_id: &NodeId, value: impl Into<Variant>) -> WriteValue {
+    WriteValue {
+        node_id: node_id.clone(),
+        attribute_id: AttributeId::Value as u32,
+        index_range: UAString::null(),
+        value: DataValue::new_now(value.into()),
+    }
+}
+
+fn user_name_rule(user: &str) -> IdentityMappingRuleType {
+    IdentityMappingRuleType {
+        criteria_type: IdentityCriteriaType::UserName,
+        criteria: user.into(),
+    }
+}
+
+#[tokio::test]
+async fn role_permissions_enforced() {
+    let (mut tester, nm, session) = setup().await;
+
+    let read_only = nm.inner().next_node_id();
+    let hidden = nm.inner().next_node_id();
+    let method = nm.inner().next_node_id();
+    {
+        let mut sp = nm.address_space().write();
+        let access = AccessLevel::CURRENT_READ | AccessLevel::CURRENT_WRITE;
+        VariableBuilder::new(&read_only, "ReadOnly", "ReadOnly")
+            .organized_by(ObjectId::ObjectsFolder)
+            .data_type(DataTypeId::Int32)
+            .access_level(access)
+            .user_access_level(access)
+            .value(1)
+            .role_permissions(vec![
+                role(
+                    ObjectId::WellKnownRole_Anonymous,
+                    PermissionType::Browse | PermissionType::Read,
+                ),
+                role(ObjectId::WellKnownRole_Operator, PermissionType::all()),
+            ])
+            .insert(&mut *sp);
+        VariableBuilder::new(&hidden, "Hidden", "Hidden")
+            .organized_by(ObjectId::ObjectsFolder)
+            .data_type(DataTypeId::Int32)
+            .access_level(access)
+            .user_access_level(access)
+            .value(2)
+            .role_permissions(vec![role(
+                ObjectId::WellKnownRole_Operator,
+                PermissionType::all(),
+            )])
+            .insert(&mut *sp);
+        MethodBuilder::new(&method, "Restricted", "Restricted")
+            .executable(true)
+            .user_executable(true)
+            .component_of(ObjectId::ObjectsFolder)
+            .role_permissions(vec![role(
+                ObjectId::WellKnownRole_Anonymous,
+                PermissionType::Browse,
+            )])
+            .insert(&mut *sp);
+    }
+
+    // The anonymous user can read, but not write.
+    let r = session
+        .read(
+            &[
+                read_value_id(AttributeId::Value, &read_only),
+                read_value_id(AttributeId::UserAccessLevel, &read_only),
+                read_value_id(AttributeId::UserRolePermissions, &read_only),
+                read_value_id(AttributeId::RolePermissions, &read_only),
+                read_value_id(AttributeId::Value, &hidden),
+                read_value_id(AttributeId::UserExecutable, &method),
+            ],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+    assert_eq!(r[0].value, Some(Variant::Int32(1)));
+    assert_eq!(
+        r[1].value,
+        Some(Variant::Byte(AccessLevel::CURRENT_READ.bits()))
+    );
+    let Some(Variant::Array(user_permissions)) = &r[2].value else {
+        panic!("Expected array, got {:?}", r[2]);
+    };
+    assert_eq!(
+        user_permissions.values,
+        vec![Variant::from(ExtensionObject::from_message(role(
+            ObjectId::WellKnownRole_Anonymous,
+            PermissionType::Browse | PermissionType::Read,
+        )))]
+    );
+    assert_eq!(r[3].status, Some(StatusCode::BadUserAccessDenied));
+    assert_eq!(r[4].status, Some(StatusCode::BadNodeIdUnknown));
+    assert_eq!(r[5].value, Some(Variant::Boolean(false)));
+
+    let r = session
+        .write(&[write_value(&read_only, 5), write_value(&hidden, 5)])
+        .await
+        .unwrap();
+    assert_eq!(r[0], StatusCode::BadUserAccessDenied);
+    assert_eq!(r[1], StatusCode::BadNodeIdUnknown);
+
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: ObjectId::ObjectsFolder.into(),
+            method_id: method.clone(),
+            input_arguments: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::BadUserAccessDenied);
+
+    // Nodes the user cannot browse are not visible.
+    let r = session
+        .browse(
+            &[BrowseDescription {
+                node_id: ObjectId::ObjectsFolder.into(),
+                browse_direction: BrowseDirection::Forward,
+                reference_type_id: ReferenceTypeId::HierarchicalReferences.into(),
+                include_subtypes: true,
+                node_class_mask: NodeClassMask::all().bits(),
+                result_mask: BrowseResultMask::All as u32,
+            }],
+            1000,
+            None,
+        )
+        .await
+        .unwrap();
+    let refs = r[0].references.clone().unwrap_or_default();
+    assert!(refs.iter().any(|r| r.node_id.node_id == read_only));
+    assert!(refs.iter().any(|r| r.node_id.node_id == method));
+    assert!(!refs.iter().any(|r| r.node_id.node_id == hidden));
+
+    // Roles are resolved when the session is activated, so a new session is
+    // needed to pick up the new rule.
+    tester
+        .handle
+        .roles()
+        .add_identity(
+            &ObjectId::WellKnownRole_Operator.into(),
+            IdentityMappingRuleType {
+                criteria_type: IdentityCriteriaType::Anonymous,
+                criteria: UAString::null(),
+            },
+        )
+        .unwrap();
+    let session = tester
+        .connect_and_wait(
+            SecurityPolicy::None,
+            MessageSecurityMode::None,
+            IdentityToken::Anonymous,
+        )
+        .await
+        .unwrap();
+
+    let r = session
+        .write(&[write_value(&read_only, 5), write_value(&hidden, 5)])
+        .await
+        .unwrap();
+    assert_eq!(r, vec![StatusCode::Good, StatusCode::Good]);
+}
+
+#[tokio::test]
+async fn role_set_identities() {
+    let (mut tester, _nm, anonymous) = setup().await;
+
+    let roles = tester.handle.roles();
+    roles
+        .add_identity(
+            &ObjectId::WellKnownRole_SecurityAdmin.into(),
+            user_name_rule(CLIENT_USERPASS_ID),
+        )
+        .unwrap();
+    assert!(roles
+        .get(&ObjectId::WellKnownRole_Operator.into())
+        .unwrap()
+        .identities()
+        .is_empty());
+
+    let admin = tester
+        .connect_and_wait(
+            SecurityPolicy::Basic256Sha256,
+            MessageSecurityMode::SignAndEncrypt,
+            client_user_token(),
+        )
+        .await
+        .unwrap();
+
+    let add_identity = |user: &str| CallMethodRequest {
+        object_id: ObjectId::WellKnownRole_Operator.into(),
+        method_id: MethodId::WellKnownRole_Operator_AddIdentity.into(),
+        input_arguments: Some(vec![
+            ExtensionObject::from_message(user_name_rule(user)).into()
+        ]),
+    };
+
+    // Only the security admin may manage roles.
+    let r = anonymous.call_one(add_identity("operator")).await.unwrap();
+    assert_eq!(r.status_code, StatusCode::BadUserAccessDenied);
+    let r = admin.call_one(add_identity("operator")).await.unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+
+    assert_eq!(
+        tester
+            .handle
+            .roles()
+            .get(&ObjectId::WellKnownRole_Operator.into())
+            .unwrap()
+            .identities(),
+        &[user_name_rule("operator")]
+    );
+
+    let identities = read_value_id(
+        AttributeId::Value,
+        VariableId::WellKnownRole_Operator_Identities,
+    );
+    let r = admin
+        .read(
+            std::slice::from_ref(&identities),
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+    let Some(Variant::Array(arr)) = &r[0].value else {
+        panic!("Expected array, got {:?}", r[0]);
+    };
+    assert_eq!(arr.values.len(), 1);
+    let r = anonymous
+        .read(&[identities], TimestampsToReturn::Both, 0.0)
+        .await
+        .unwrap();
+    assert_eq!(r[0].status, Some(StatusCode::BadUserAccessDenied));
+
+    let r = admin
+        .call_one(CallMethodRequest {
+            object_id: ObjectId::WellKnownRole_Operator.into(),
+            method_id: MethodId::WellKnownRole_Operator_RemoveIdentity.into(),
+            input_arguments: Some(vec![ExtensionObject::from_message(user_name_rule(
+                "operator",
+            ))
+            .into()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(r.status_code, StatusCode::Good);
+    let r = admin.call_one(add_identity("")).await.unwrap();
+    assert_eq!(r.status_code, StatusCode::BadInvalidArgument);
+}
+
+#[tokio::test]
+async fn namespace_defaults_enforced_for_all_node_managers() {
+    let (tester, _nm, session) = setup().await;
+
+    // Session diagnostics are served by the diagnostics node manager, which does
+    // not check role permissions itself.
+    let mut node = NodeId::from(ObjectId::Server_ServerDiagnostics_SessionsDiagnosticsSummary);
+    for name in [None, Some("SessionDiagnostics")] {
+        let r = session
+            .browse(
+                &[BrowseDescription {
+                    node_id: node.clone(),
+                    browse_direction: BrowseDirection::Forward,
+                    reference_type_id: ReferenceTypeId::HasComponent.into(),
+                    include_subtypes: true,
+                    node_class_mask: NodeClassMask::all().bits(),
+                    result_mask: BrowseResultMask::All as u32,
+                }],
+                1000,
+                None,
+            )
+            .await
+            .unwrap();
+        node = r[0]
+            .references
+            .as_ref()
+            .and_then(|r| {
+                r.iter().find(|r| match name {
+                    Some(name) => r.browse_name.name.as_ref() == name,
+                    None => r.node_id.node_id.namespace != 0,
+                })
+            })
+            .map(|r| r.node_id.node_id.clone())
+            .expect("Expected session diagnostics node");
+    }
+
+    let r = session
+        .read(
+            &[read_value_id(AttributeId::Value, &node)],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+    assert_eq!(r[0].status, Some(StatusCode::Good));
+
+    tester.handle.roles().set_namespace_defaults(
+        node.namespace,
+        Some(vec![role(
+            ObjectId::WellKnownRole_Anonymous,
+            PermissionType::Browse,
+        )]),
+        AccessRestrictionType::empty(),
+    );
+
+    let r = session
+        .read(
+            &[
+                read_value_id(AttributeId::BrowseName, &node),
+                read_value_id(AttributeId::Value, &node),
+            ],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+    assert_eq!(r[0].status, Some(StatusCode::Good));
+    assert_eq!(r[1].status, Some(StatusCode::BadUserAccessDenied));
+}
+
+#[tokio::test]
+async fn receive_events_permission() {
+    let (_tester, nm, session) = setup().await;
+
+    let allowed = nm.inner().next_node_id();
+    let denied = nm.inner().next_node_id();
+    {
+        let mut sp = nm.address_space().write();
+        for (id, permissions) in [
+            (
+                &allowed,
+                PermissionType::Browse | PermissionType::ReceiveEvents,
+            ),
+            (&denied, PermissionType::Browse),
+        ] {
+            ObjectBuilder::new(id, "Notifier", "Notifier")
+                .organized_by(ObjectId::ObjectsFolder)
+                .event_notifier(EventNotifier::SUBSCRIBE_TO_EVENTS)
+                .role_permissions(vec![role(ObjectId::WellKnownRole_Anonymous, permissions)])
+                .insert(&mut *sp);
+        }
+    }
+
+    let (notifs, _data, _events) = ChannelNotifications::new();
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+    let filter = EventFilter {
+        select_clauses: Some(vec![SimpleAttributeOperand::new(
+            ObjectTypeId::BaseEventType,
+            "EventId",
+            AttributeId::Value,
+            Default::default(),
+        )]),
+        where_clause: Default::default(),
+    };
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            [&allowed, &denied]
+                .into_iter()
+                .map(|id| MonitoredItemCreateRequest {
+                    item_to_monitor: ReadValueId {
+                        node_id: id.clone(),
+                        attribute_id: AttributeId::EventNotifier as u32,
+                        ..Default::default()
+                    },
+                    monitoring_mode: MonitoringMode::Reporting,
+                    requested_parameters: MonitoringParameters {
+                        sampling_interval: 0.0,
+                        queue_size: 10,
+                        discard_oldest: true,
+                        filter: ExtensionObject::from_message(filter.clone()),
+                        ..Default::default()
+                    },
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+    assert_eq!(res[1].status_code, StatusCode::BadUserAccessDenied);
+}
@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use opcua::{
+    client::{IdentityToken, Session},
+    core::config::Config,
+    crypto::{CertificateSigningRequest, PrivateKey, SecurityPolicy, X509},
+    types::{
+        BinaryDecodable, ByteString, CallMethodRequest, ContextOwned, IdentityCriteriaType,
+        IdentityMappingRuleType, MessageSecurityMode, MethodId, NodeId, ObjectId, ObjectTypeId,
+        StatusCode, TrustListDataType, TrustListMasks, UAString, Variant,
+    },
+};
+
+use super::utils::{client_user_token, default_server, setup, Tester, CLIENT_USERPASS_ID};
+
+const TRUST_LIST: ObjectId =
+    ObjectId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList;
+
+/// Grant the security admin role to the username/password user, and connect as that
+/// user over an encrypted channel.
+async fn connect_admin(tester: &mut Tester) -> Arc<Session> {
+    tester
+        .handle
+        .roles()
+        .add_identity(
+            &ObjectId::WellKnownRole_SecurityAdmin.into(),
+            IdentityMappingRuleType {
+                criteria_type: IdentityCriteriaType::UserName,
+                criteria: CLIENT_USERPASS_ID.into(),
+            },
+        )
+        .unwrap();
+    tester
+        .connect_and_wait(
+            SecurityPolicy::Basic256Sha256,
+            MessageSecurityMode::SignAndEncrypt,
+            client_user_token(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn call(
+    session: &Session,
+    object_id: impl Into<NodeId>,
+    method_id: impl Into<NodeId>,
+    args: Vec<Variant>,
+) -> Result<Vec<Variant>, StatusCode> {
+    let r = session
+        .call_one(CallMethodRequest {
+            object_id: object_id.into(),
+            method_id: method_id.into(),
+            input_arguments: Some(args),
+        })
+        .await
+        .unwrap();
+    if r.status_code.is_good() {
+        Ok(r.output_arguments.unwrap_or_default())
+    } else {
+        Err(r.status_code)
+    }
+}
+
+async fn read_trust_list(session: &Session) -> TrustListDataType {
+    let r = call(
+        session,
+        TRUST_LIST,
+        MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Open,
+        vec![1u8.into()],
+    )
+    .await
+    .unwrap();
+    let handle = r[0].clone();
+    let r = call(
+        session,
+        TRUST_LIST,
+        MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Read,
+        vec![handle.clone(), 1_000_000i32.into()],
+    )
+    .await
+    .unwrap();
+    let Variant::ByteString(data) = &r[0] else {
+        panic!("Expected byte string, got {:?}", r[0]);
+    };
+    call(
+        session,
+        TRUST_LIST,
+        MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Close,
+        vec![handle],
+    )
+    .await
+    .unwrap();
+
+    let ctx = ContextOwned::default();
+    TrustListDataType::decode(&mut data.as_ref(), &ctx.context()).unwrap()
+}
+
+#[tokio::test]
+async fn server_configuration_requires_security_admin() {
+    let (mut tester, _nm, anonymous) = setup().await;
+
+    let r = call(
+        &anonymous,
+        ObjectId::ServerConfiguration,
+        MethodId::ServerConfiguration_GetRejectedList,
+        Vec::new(),
+    )
+    .await;
+    assert_eq!(r, Err(StatusCode::BadUserAccessDenied));
+
+    let admin = connect_admin(&mut tester).await;
+    let r = call(
+        &admin,
+        ObjectId::ServerConfiguration,
+        MethodId::ServerConfiguration_GetRejectedList,
+        Vec::new(),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(r[0], Variant::Array(_) | Variant::Empty));
+}
+
+#[tokio::test]
+async fn create_signing_request() {
+    let (mut tester, _nm, _session) = setup().await;
+    let admin = connect_admin(&mut tester).await;
+    let server_cert = tester.handle.info().server_certificate.load_full().unwrap();
+    let server_pkey = tester.handle.info().server_pkey.load_full().unwrap();
+
+    for regenerate in [false, true] {
+        let r = call(
+            &admin,
+            ObjectId::ServerConfiguration,
+            MethodId::ServerConfiguration_CreateSigningRequest,
+            vec![
+                NodeId::null().into(),
+                NodeId::from(ObjectTypeId::RsaSha256ApplicationCertificateType).into(),
+                UAString::null().into(),
+                regenerate.into(),
+                ByteString::null().into(),
+            ],
+        )
+        .await
+        .unwrap();
+        let Variant::ByteString(csr) = &r[0] else {
+            panic!("Expected byte string, got {:?}", r[0]);
+        };
+        let csr = CertificateSigningRequest::from_der(csr.as_ref()).unwrap();
+        assert!(csr.is_signature_valid());
+        assert_eq!(csr.subject_name(), server_cert.subject_name());
+        assert_eq!(csr.is_public_key_of(&server_pkey), !regenerate);
+    }
+
+    // Only the default application group exists.
+    let r = call(
+        &admin,
+        ObjectId::ServerConfiguration,
+        MethodId::ServerConfiguration_CreateSigningRequest,
+        vec![
+            NodeId::new(0, 1234).into(),
+            NodeId::null().into(),
+            UAString::null().into(),
+            false.into(),
+            ByteString::null().into(),
+        ],
+    )
+    .await;
+    assert_eq!(r, Err(StatusCode::BadInvalidArgument));
+}
+
+#[tokio::test]
+async fn update_certificate() {
+    let (mut tester, _nm, _session) = setup().await;
+    let admin = connect_admin(&mut tester).await;
+    let old_cert = tester.handle.info().server_certificate.load_full().unwrap();
+
+    let pkey = PrivateKey::new(2048).unwrap();
+    let cert = X509::from_pkey(
+        &pkey,
+        &default_server().config().application_description().into(),
+    )
+    .unwrap();
+    let update = |cert: &X509, pkey: &PrivateKey| {
+        vec![
+            NodeId::null().into(),
+            NodeId::from(ObjectTypeId::RsaSha256ApplicationCertificateType).into(),
+            cert.as_byte_string().into(),
+            Variant::Empty,
+            UAString::from("PEM").into(),
+            ByteString::from(pkey.to_pem().unwrap().into_bytes()).into(),
+        ]
+    };
+
+    // The private key must match the certificate.
+    let r = call(
+        &admin,
+        ObjectId::ServerConfiguration,
+        MethodId::ServerConfiguration_UpdateCertificate,
+        update(&cert, &PrivateKey::new(2048).unwrap()),
+    )
+    .await;
+    assert_eq!(r, Err(StatusCode::BadSecurityChecksFailed));
+
+    let r = call(
+        &admin,
+        ObjectId::ServerConfiguration,
+        MethodId::ServerConfiguration_UpdateCertificate,
+        update(&cert, &pkey),
+    )
+    .await
+    .unwrap();
+    assert_eq!(r, vec![Variant::Boolean(true)]);
+    // Nothing changes until the changes are applied.
+    assert_eq!(
+        tester
+            .handle
+            .info()
+            .server_certificate
+            .load_full()
+            .unwrap()
+            .thumbprint(),
+        old_cert.thumbprint()
+    );
+
+    call(
+        &admin,
+        ObjectId::ServerConfiguration,
+        MethodId::ServerConfiguration_ApplyChanges,
+        Vec::new(),
+    )
+    .await
+    .unwrap();
+
+    let endpoints = tester
+        .client
+        .get_server_endpoints_from_url(tester.endpoint())
+        .await
+        .unwrap();
+    assert!(endpoints
+        .iter()
+        .all(|e| e.server_certificate == cert.as_byte_string()));
+    let own_cert = tester
+        .handle
+        .info()
+        .certificate_manager
+        .certificate_store()
+        .read()
+        .read_own_cert()
+        .unwrap();
+    assert_eq!(own_cert.thumbprint(), cert.thumbprint());
+
+    // New secure channels use the new certificate.
+    tester
+        .connect_and_wait(
+            SecurityPolicy::Basic256Sha256,
+            MessageSecurityMode::SignAndEncrypt,
+            IdentityToken::Anonymous,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn trust_list_add_remove_certificate() {
+    let (mut tester, _nm, _session) = setup().await;
+    let admin = connect_admin(&mut tester).await;
+
+    let trust_list = read_trust_list(&admin).await;
+    assert_eq!(trust_list.specified_lists, TrustListMasks::All as u32);
+    let trusted = trust_list.trusted_certificates.unwrap_or_default();
+
+    let (cert, _) = X509::cert_and_pkey(&opcua::crypto::X509Data::sample_cert()).unwrap();
+    let r = call(
+        &admin,
+        TRUST_LIST,
+        MethodId::TrustListType_AddCertificate,
+        vec![cert.as_byte_string().into(), true.into()],
+    )
+    .await;
+    assert_eq!(r, Ok(Vec::new()));
+
+    let trust_list = read_trust_list(&admin).await;
+    let new_trusted = trust_list.trusted_certificates.unwrap_or_default();
+    assert_eq!(new_trusted.len(), trusted.len() + 1);
+    assert!(new_trusted.contains(&cert.as_byte_string()));
+
+    let r = call(
+        &admin,
+        TRUST_LIST,
+        MethodId::TrustListType_RemoveCertificate,
+        vec![cert.thumbprint().as_hex_string().into(), true.into()],
+    )
+    .await;
+    assert_eq!(r, Ok(Vec::new()));
+    let trust_list = read_trust_list(&admin).await;
+    assert_eq!(trust_list.trusted_certificates.unwrap_or_default(), trusted);
+
+    // Removing an unknown certificate fails.
+    let r = call(
+        &admin,
+        TRUST_LIST,
+        MethodId::TrustListType_RemoveCertificate,
+        vec![cert.thumbprint().as_hex_string().into(), true.into()],
+    )
+    .await;
+    assert_eq!(r, Err(StatusCode::BadInvalidArgument));
+}
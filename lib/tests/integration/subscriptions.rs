@@ -1,8 +1,9 @@
 use std::{collections::HashMap, time::Duration};
 
-use crate::utils::{test_server, ChannelNotifications, TestNodeManager, Tester};
+use crate::utils::{read_value_id, test_server, ChannelNotifications, TestNodeManager, Tester};
 
 use super::utils::setup;
+use futures::StreamExt;
 use opcua::{
     server::address_space::{AccessLevel, VariableBuilder},
     types::{
@@ -11,10 +12,15 @@ use opcua::{
         StatusCode, TimestampsToReturn, VariableTypeId, Variant,
     },
 };
-use opcua_client::{services::TransferSubscriptions, IdentityToken, Subscription, UARequest};
+use opcua_client::{
+    services::TransferSubscriptions, IdentityToken, OverflowPolicy, StreamNotification,
+    StreamOptions, Subscription, UARequest,
+};
 use opcua_crypto::SecurityPolicy;
 use opcua_types::{
-    DataChangeFilter, DataChangeTrigger, DeadbandType, ExtensionObject, MessageSecurityMode, Range,
+    AggregateConfiguration, AggregateFilter, AggregateFilterResult, DataChangeFilter,
+    DataChangeTrigger, DateTime, DeadbandType, ExtensionObject, MessageSecurityMode, Range,
+    StatusCodeValueType,
 };
 use tokio::{sync::mpsc::UnboundedReceiver, time::timeout};
 
@@ -791,4 +797,307 @@ async fn test_data_change_filters() {
     assert_eq!(v.value.unwrap(), Variant::Double(9.0));
 }
 
+#[tokio::test]
+async fn test_aggregate_filter() {
+    let (tester, nm, session) = setup().await;
+
+    let id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        VariableBuilder::new(&id, "TestVar1", "TestVar1")
+            .value(0.0f64)
+            .data_type(DataTypeId::Double)
+            .access_level(AccessLevel::CURRENT_READ)
+            .user_access_level(AccessLevel::CURRENT_READ)
+            .build()
+            .into(),
+        &ObjectId::ObjectsFolder.into(),
+        &ReferenceTypeId::Organizes.into(),
+        Some(&VariableTypeId::BaseDataVariableType.into()),
+        Vec::new(),
+    );
+
+    let (notifs, mut data, _) = ChannelNotifications::new();
+    let sub_id = session
+        .create_subscription(Duration::from_millis(100), 100, 20, 1000, 0, true, notifs)
+        .await
+        .unwrap();
+
+    let make_request = |aggregate_type: ObjectId| MonitoredItemCreateRequest {
+        item_to_monitor: ReadValueId {
+            node_id: id.clone(),
+            attribute_id: AttributeId::Value as u32,
+            ..Default::default()
+        },
+        monitoring_mode: MonitoringMode::Reporting,
+        requested_parameters: MonitoringParameters {
+            sampling_interval: 0.0,
+            queue_size: 10,
+            discard_oldest: true,
+            filter: ExtensionObject::from_message(AggregateFilter {
+                start_time: DateTime::null(),
+                aggregate_type: aggregate_type.into(),
+                processing_interval: 500.0,
+                aggregate_configuration: AggregateConfiguration {
+                    use_server_capabilities_defaults: true,
+                    ..Default::default()
+                },
+            }),
+            ..Default::default()
+        },
+    };
+
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![
+                make_request(ObjectId::AggregateFunction_Maximum),
+                make_request(ObjectId::AggregateFunction_AnnotationCount),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+    let filter_res = res[0]
+        .filter_result
+        .inner_as::<AggregateFilterResult>()
+        .unwrap();
+    assert_eq!(filter_res.revised_processing_interval, 500.0);
+    assert!(!filter_res.revised_start_time.is_null());
+    assert_eq!(res[1].status_code, StatusCode::BadAggregateNotSupported);
+
+    for i in 1..4 {
+        nm.set_value(
+            tester.handle.subscriptions(),
+            &id,
+            None,
+            DataValue::new_now(i as f64),
+        )
+        .unwrap();
+    }
+
+    // Aggregates are reported once per processing interval, the first interval
+    // containing our values should report the maximum.
+    let value = timeout(Duration::from_millis(3000), async {
+        loop {
+            let (_, v) = data.recv().await.unwrap();
+            if v.value == Some(Variant::Double(3.0)) {
+                break v;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(value.status().value_type(), StatusCodeValueType::Calculated);
+}
+
 // TODO: Add more detailed high level tests on subscriptions.
+
+fn stream_value(n: Option<StreamNotification>) -> (u32, i32) {
+    match n {
+        Some(StreamNotification::DataChange {
+            client_handle,
+            value,
+        }) => match value.value {
+            Some(Variant::Int32(v)) => (client_handle, v),
+            r => panic!("Expected integer value, got {r:?}"),
+        },
+        r => panic!("Expected data change, got {r:?}"),
+    }
+}
+
+#[tokio::test]
+async fn subscription_streams() {
+    let (tester, nm, session) = setup().await;
+
+    let ids: Vec<_> = (0..3)
+        .map(|i| {
+            let id = nm.inner().next_node_id();
+            nm.inner().add_node(
+                nm.address_space(),
+                tester.handle.type_tree(),
+                VariableBuilder::new(&id, format!("TestVar{i}"), format!("TestVar{i}"))
+                    .value(i)
+                    .data_type(DataTypeId::Int32)
+                    .access_level(AccessLevel::CURRENT_READ)
+                    .user_access_level(AccessLevel::CURRENT_READ)
+                    .build()
+                    .into(),
+                &ObjectId::ObjectsFolder.into(),
+                &ReferenceTypeId::Organizes.into(),
+                Some(&VariableTypeId::BaseDataVariableType.into()),
+                Vec::new(),
+            );
+            id
+        })
+        .collect();
+
+    let mut stream = session
+        .create_subscription_stream(
+            Duration::from_millis(100),
+            100,
+            20,
+            1000,
+            0,
+            true,
+            StreamOptions::default(),
+        )
+        .await
+        .unwrap();
+    let sub_id = stream.subscription_id();
+    assert!(session.subscription_state().lock().get(sub_id).is_some());
+
+    // Create the first two items with their own streams.
+    let res = session
+        .create_value_streams(
+            &stream,
+            TimestampsToReturn::Both,
+            ids[0..2]
+                .iter()
+                .map(|id| read_value_id(AttributeId::Value, id.clone()))
+                .collect(),
+            0.0,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.len(), 2);
+    let mut item_streams: Vec<_> = res
+        .into_iter()
+        .map(|(r, s)| {
+            assert_eq!(r.status_code, StatusCode::Good);
+            s.unwrap()
+        })
+        .collect();
+
+    // The last item goes to the subscription stream.
+    let res = session
+        .create_monitored_items(
+            sub_id,
+            TimestampsToReturn::Both,
+            vec![ids[2].clone().into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res[0].status_code, StatusCode::Good);
+
+    for (i, item_stream) in item_streams.iter_mut().enumerate() {
+        let (handle, v) = stream_value(
+            timeout(Duration::from_millis(500), item_stream.next())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(handle, item_stream.client_handle());
+        assert_eq!(v, i as i32);
+    }
+    let (_, v) = stream_value(
+        timeout(Duration::from_millis(500), stream.next())
+            .await
+            .unwrap(),
+    );
+    assert_eq!(v, 2);
+
+    nm.set_value(
+        tester.handle.subscriptions(),
+        &ids[1],
+        None,
+        DataValue::new_now(10),
+    )
+    .unwrap();
+    let (_, v) = stream_value(
+        timeout(Duration::from_millis(500), item_streams[1].next())
+            .await
+            .unwrap(),
+    );
+    assert_eq!(v, 10);
+    assert_eq!(stream.buffered_len(), 0);
+    assert_eq!(item_streams[0].buffered_len(), 0);
+
+    // Deleting the subscription ends all the streams.
+    session.delete_subscription(sub_id).await.unwrap();
+    assert!(stream.next().await.is_none());
+    assert!(item_streams[0].next().await.is_none());
+}
+
+#[tokio::test]
+async fn subscription_stream_overflow() {
+    let (tester, nm, session) = setup().await;
+
+    let id = nm.inner().next_node_id();
+    nm.inner().add_node(
+        nm.address_space(),
+        tester.handle.type_tree(),
+        VariableBuilder::new(&id, "TestVar1", "TestVar1")
+            .value(0)
+            .data_type(DataTypeId::Int32)
+            .access_level(AccessLevel::CURRENT_READ)
+            .user_access_level(AccessLevel::CURRENT_READ)
+            .build()
+            .into(),
+        &ObjectId::ObjectsFolder.into(),
+        &ReferenceTypeId::Organizes.into(),
+        Some(&VariableTypeId::BaseDataVariableType.into()),
+        Vec::new(),
+    );
+
+    let stream = session
+        .create_subscription_stream(
+            Duration::from_millis(100),
+            100,
+            20,
+            1000,
+            0,
+            true,
+            StreamOptions::new(2, OverflowPolicy::DropOldest),
+        )
+        .await
+        .unwrap();
+    let mut res = session
+        .create_value_streams(
+            &stream,
+            TimestampsToReturn::Both,
+            vec![read_value_id(AttributeId::Value, id.clone())],
+            0.0,
+            100,
+        )
+        .await
+        .unwrap();
+    let mut item_stream = res.remove(0).1.unwrap();
+
+    // Wait for the initial value.
+    let (_, v) = stream_value(
+        timeout(Duration::from_millis(500), item_stream.next())
+            .await
+            .unwrap(),
+    );
+    assert_eq!(v, 0);
+
+    // Write more values than the stream can hold, without consuming them.
+    for i in 1..=5 {
+        nm.set_value(
+            tester.handle.subscriptions(),
+            &id,
+            None,
+            DataValue::new_now(i),
+        )
+        .unwrap();
+        // Wait for the value to be sampled and published.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    // The buffer fills up, and then overflows.
+    assert_eq!(item_stream.buffered_len(), 2);
+
+    let Some(StreamNotification::Lagged(n)) = item_stream.next().await else {
+        panic!("Expected lagged notification");
+    };
+    assert_eq!(n, 3);
+    assert_eq!(stream_value(item_stream.next().await).1, 4);
+    assert_eq!(stream_value(item_stream.next().await).1, 5);
+    assert_eq!(stream.buffered_len(), 0);
+    session
+        .delete_subscription(stream.subscription_id())
+        .await
+        .unwrap();
+}
@@ -0,0 +1,53 @@
+use std::{collections::HashMap, io, sync::Mutex};
+
+use futures::{channel::mpsc::UnboundedSender, stream::BoxStream, StreamExt};
+use opcua::server::{MdnsEvent, MdnsResponder, MdnsServerRecord};
+
+/// In-process stand-in for multicast DNS, servers sharing the same
+/// instance see each other's announcements.
+#[derive(Default)]
+pub struct LoopbackMdns {
+    inner: Mutex<LoopbackMdnsInner>,
+}
+
+#[derive(Default)]
+struct LoopbackMdnsInner {
+    records: HashMap<String, MdnsServerRecord>,
+    subscribers: Vec<UnboundedSender<MdnsEvent>>,
+}
+
+impl LoopbackMdnsInner {
+    fn notify(&mut self, event: MdnsEvent) {
+        self.subscribers
+            .retain(|s| s.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+impl MdnsResponder for LoopbackMdns {
+    fn announce(&self, record: &MdnsServerRecord) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .records
+            .insert(record.server_name.clone(), record.clone());
+        inner.notify(MdnsEvent::ServerFound(record.clone()));
+        Ok(())
+    }
+
+    fn withdraw(&self, server_name: &str) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.records.remove(server_name).is_some() {
+            inner.notify(MdnsEvent::ServerRemoved(server_name.to_owned()));
+        }
+        Ok(())
+    }
+
+    fn browse(&self) -> io::Result<BoxStream<'static, MdnsEvent>> {
+        let mut inner = self.inner.lock().unwrap();
+        let (send, recv) = futures::channel::mpsc::unbounded();
+        for record in inner.records.values() {
+            let _ = send.unbounded_send(MdnsEvent::ServerFound(record.clone()));
+        }
+        inner.subscribers.push(send);
+        Ok(recv.boxed())
+    }
+}
@@ -1,9 +1,11 @@
+mod mdns;
 mod node_manager;
 mod tester;
 
 pub const CLIENT_USERPASS_ID: &str = "sample1";
 pub const CLIENT_X509_ID: &str = "x509";
 
+pub use mdns::*;
 pub use node_manager::*;
 use opcua::types::{AttributeId, DataValue, NodeId, ReadValueId, Variant};
 use opcua_client::OnSubscriptionNotification;
@@ -18,7 +18,7 @@ use opcua::{
             AddNodeItem, AddReferenceItem, DeleteNodeItem, DeleteReferenceItem, HistoryNode,
             HistoryUpdateNode, MethodCall, MonitoredItemRef, MonitoredItemUpdateRef,
             NodeManagerBuilder, NodeManagersRef, ParsedReadValueId, RequestContext, ServerContext,
-            WriteNode,
+            TypedMethod, WriteNode,
         },
         ContinuationPoint, CreateMonitoredItem,
     },
@@ -55,6 +55,7 @@ pub struct TestNodeManagerImpl {
     history_data: RwLock<HashMap<NodeId, HistoryData>>,
     call_info: Mutex<CallInfo>,
     method_cbs: Mutex<HashMap<NodeId, Box<MethodCb>>>,
+    typed_methods: RwLock<HashMap<NodeId, TypedMethod>>,
     node_id_generator: AtomicU32,
     namespace_index: u16,
     node_managers: NodeManagersRef,
@@ -396,8 +397,13 @@ impl InMemoryNodeManagerImpl for TestNodeManagerImpl {
             }
         }
 
-        let mut cbs = self.method_cbs.lock();
         for method in methods_to_call {
+            let typed = self.typed_methods.read().get(method.method_id()).cloned();
+            if let Some(typed) = typed {
+                typed.call(method).await;
+                continue;
+            }
+            let mut cbs = self.method_cbs.lock();
             let Some(cb) = cbs.get_mut(method.method_id()) else {
                 method.set_status(StatusCode::BadMethodInvalid);
                 continue;
@@ -718,6 +724,7 @@ impl TestNodeManagerImpl {
             history_data: Default::default(),
             call_info: Default::default(),
             method_cbs: Default::default(),
+            typed_methods: Default::default(),
             node_id_generator: AtomicU32::new(1),
             namespace_index,
             node_managers,
@@ -739,6 +746,10 @@ impl TestNodeManagerImpl {
         cbs.insert(node_id, Box::new(cb));
     }
 
+    pub fn add_typed_method(&self, node_id: NodeId, method: TypedMethod) {
+        self.typed_methods.write().insert(node_id, method);
+    }
+
     fn history_read_raw_modified(
         &self,
         details: &ReadRawModifiedDetails,
@@ -10,15 +10,19 @@ use std::{
 };
 
 use opcua::{
-    client::{Client, ClientBuilder, IdentityToken, Session, SessionEventLoop},
-    crypto::SecurityPolicy,
-    server::{ServerBuilder, ServerHandle, ServerUserToken, ANONYMOUS_USER_TOKEN_ID},
-    types::{MessageSecurityMode, StatusCode},
+    client::{Client, ClientBuilder, Connector, IdentityToken, Session, SessionEventLoop},
+    crypto::{PrivateKey, SecurityPolicy},
+    server::{Listener, ServerBuilder, ServerHandle, ServerUserToken, ANONYMOUS_USER_TOKEN_ID},
+    types::{MessageSecurityMode, NodeId, ReadValueId, StatusCode, TimestampsToReturn, VariableId},
 };
 use opcua_core::config::Config;
-use opcua_crypto::CertificateStore;
+use opcua_crypto::{EccCurve, FileSystemCertificateStore};
 use opcua_types::ApplicationDescription;
 use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{self, Certificate, ClientConfig, RootCertStore, ServerConfig},
+    TlsConnector,
+};
 use tokio_util::sync::{CancellationToken, DropGuard};
 
 use super::{test_node_manager, TestNodeManager, CLIENT_USERPASS_ID, CLIENT_X509_ID};
@@ -238,19 +242,20 @@ pub fn test_server() -> ServerBuilder {
 
 static SHARED_CERT_LOCK: Mutex<()> = Mutex::new(());
 
-pub fn copy_shared_certs(test_id: u16, desc: &ApplicationDescription) {
+/// Create the certificates shared by all tests, if they do not exist yet.
+fn create_shared_certs(desc: &ApplicationDescription) {
     let _lck = SHARED_CERT_LOCK.lock();
     if !Path::new("certs").exists() {
         std::fs::create_dir_all("certs/server").unwrap();
         std::fs::create_dir_all("certs/client").unwrap();
-        CertificateStore::create_certificate_and_key(
+        FileSystemCertificateStore::create_certificate_and_key(
             &desc.clone().into(),
             true,
             Path::new("certs/server/cert.der"),
             Path::new("certs/server/private.pem"),
         )
         .unwrap();
-        CertificateStore::create_certificate_and_key(
+        FileSystemCertificateStore::create_certificate_and_key(
             &desc.clone().into(),
             true,
             Path::new("certs/client/cert.der"),
@@ -258,6 +263,10 @@ pub fn copy_shared_certs(test_id: u16, desc: &ApplicationDescription) {
         )
         .unwrap();
     }
+}
+
+pub fn copy_shared_certs(test_id: u16, desc: &ApplicationDescription) {
+    create_shared_certs(desc);
 
     std::fs::create_dir_all(format!("pki-server/{test_id}/own")).unwrap();
     std::fs::create_dir_all(format!("pki-server/{test_id}/private")).unwrap();
@@ -286,8 +295,129 @@ pub fn copy_shared_certs(test_id: u16, desc: &ApplicationDescription) {
     .unwrap();
 }
 
+/// TLS configuration for a server using the shared server certificate.
+#[allow(unused)]
+pub fn tls_server_config() -> Arc<ServerConfig> {
+    create_shared_certs(&test_server().config().application_description());
+    let cert = fs::read("certs/server/cert.der").unwrap();
+    let key = PrivateKey::read_pem_file(Path::new("certs/server/private.pem")).unwrap();
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(cert)],
+            rustls::PrivateKey(key.to_der().unwrap().as_bytes().to_vec()),
+        )
+        .unwrap();
+    Arc::new(config)
+}
+
+/// TLS connector trusting the shared server certificate.
+#[allow(unused)]
+pub fn tls_connector() -> TlsConnector {
+    create_shared_certs(&test_server().config().application_description());
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(&Certificate(fs::read("certs/server/cert.der").unwrap()))
+        .unwrap();
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// Run a test server on `listener`, and connect to it using connectors created by `connector`.
+///
+/// Checks that endpoints are advertised with `scheme` URLs and the transport profile `profile`,
+/// and that a session using `Basic256Sha256` can read from the server. Returns the session,
+/// and a guard that stops the server when dropped.
+#[allow(unused)]
+pub async fn transport_roundtrip<C: Connector + 'static>(
+    listener: impl Listener + 'static,
+    connector: impl Fn() -> C,
+    scheme: &str,
+    profile: &str,
+) -> (Arc<Session>, DropGuard) {
+    opcua::console_logging::init();
+
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let server = test_server().pki_dir(format!("./pki-server/{test_id}"));
+    // The server uses port 0 in endpoint URLs if the listener is not bound to a port.
+    let port = listener.local_port().unwrap_or_default();
+    let endpoint_url = format!("{scheme}://{}:{port}/", hostname());
+    let server = server.discovery_urls(vec![endpoint_url.clone()]);
+    copy_shared_certs(test_id, &server.config().application_description());
+    let (server, handle) = server.build().unwrap();
+    let guard = handle.token().clone().drop_guard();
+
+    tokio::task::spawn(server.run_with(listener));
+
+    let client = default_client(test_id, false).client().unwrap();
+    let endpoints = client
+        .get_endpoints_with_connector(&endpoint_url, connector())
+        .await
+        .unwrap();
+    assert!(!endpoints.is_empty());
+    for endpoint in &endpoints {
+        assert!(endpoint
+            .endpoint_url
+            .as_ref()
+            .starts_with(&format!("{scheme}://")));
+        assert_eq!(endpoint.transport_profile_uri.as_ref(), profile);
+    }
+    let endpoint = Client::find_matching_endpoint(
+        &endpoints,
+        &endpoint_url,
+        SecurityPolicy::Basic256Sha256,
+        MessageSecurityMode::SignAndEncrypt,
+    )
+    .unwrap();
+
+    let (session, lp) = client
+        .session_builder()
+        .connector(connector())
+        .connect_to_endpoint_directly(endpoint)
+        .unwrap()
+        .user_identity_token(IdentityToken::Anonymous)
+        .build(client.certificate_store().clone());
+    lp.spawn();
+
+    tokio::time::timeout(Duration::from_secs(5), session.wait_for_connection())
+        .await
+        .unwrap();
+
+    let service_level: NodeId = VariableId::Server_ServiceLevel.into();
+    session
+        .read(
+            &[ReadValueId::from(service_level)],
+            TimestampsToReturn::Both,
+            0.0,
+        )
+        .await
+        .unwrap();
+
+    (session, guard)
+}
+
+/// Create new ECC application instance certificates for the server and client of a test,
+/// since the shared certificates are RSA.
+#[allow(unused)]
+pub fn create_ecc_certs(test_id: u16, desc: &ApplicationDescription, curve: EccCurve) {
+    for dir in ["pki-server", "pki-client"] {
+        FileSystemCertificateStore::create_ecc_certificate_and_key(
+            &desc.clone().into(),
+            curve,
+            true,
+            Path::new(&format!("{dir}/{test_id}/own/cert.der")),
+            Path::new(&format!("{dir}/{test_id}/private/private.pem")),
+        )
+        .unwrap();
+    }
+}
+
 impl Tester {
-    async fn listener() -> TcpListener {
+    pub async fn listener() -> TcpListener {
         TcpListener::bind(format!("{}:0", hostname()))
             .await
             .unwrap()
@@ -325,10 +455,20 @@ impl Tester {
 
     #[allow(unused)]
     pub async fn new(server: ServerBuilder, quick_timeout: bool) -> Self {
+        Self::new_with_listener(server, Self::listener().await, quick_timeout).await
+    }
+
+    /// Start a server on a listener bound in advance, so that its address
+    /// can be given to other servers.
+    #[allow(unused)]
+    pub async fn new_with_listener(
+        server: ServerBuilder,
+        listener: TcpListener,
+        quick_timeout: bool,
+    ) -> Self {
         opcua::console_logging::init();
 
         let test_id = TEST_COUNTER.fetch_add(1, Ordering::Relaxed);
-        let listener = Self::listener().await;
         let addr = listener.local_addr().unwrap();
 
         let server = server
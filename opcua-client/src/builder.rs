@@ -1,7 +1,11 @@
-use std::{path::PathBuf, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use log::error;
-use opcua_core::config::{Config, ConfigError};
+use opcua_core::{
+    config::{Config, ConfigError},
+    sync::RwLock,
+};
+use opcua_crypto::CertificateStore;
 
 use super::{Client, ClientConfig, ClientEndpoint, ClientUserToken, ANONYMOUS_USER_TOKEN_ID};
 
@@ -9,6 +13,7 @@ use super::{Client, ClientConfig, ClientEndpoint, ClientUserToken, ANONYMOUS_USE
 /// Client builder.
 pub struct ClientBuilder {
     config: ClientConfig,
+    certificate_store: Option<Arc<RwLock<dyn CertificateStore>>>,
 }
 
 impl ClientBuilder {
@@ -21,6 +26,7 @@ impl ClientBuilder {
     pub fn from_config(path: impl Into<PathBuf>) -> Result<ClientBuilder, ConfigError> {
         Ok(ClientBuilder {
             config: ClientConfig::load(&path.into())?,
+            certificate_store: None,
         })
     }
 
@@ -34,6 +40,11 @@ impl ClientBuilder {
                 error!("{err}");
             }
             Err(e)
+        } else if let Some(certificate_store) = self.certificate_store {
+            Ok(Client::new_with_certificate_store(
+                self.config,
+                certificate_store,
+            ))
         } else {
             Ok(Client::new(self.config))
         }
@@ -110,6 +121,14 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets whether the client should accept server certificates whose issuers have no
+    /// current revocation list. Certificates revoked by a known revocation list are still
+    /// rejected.
+    pub fn allow_unknown_revocation(mut self, allow_unknown_revocation: bool) -> Self {
+        self.config.allow_unknown_revocation = allow_unknown_revocation;
+        self
+    }
+
     /// Sets the pki directory where client's own key pair is stored and where `/trusted` and
     /// `/rejected` server certificates are stored.
     pub fn pki_dir(mut self, pki_dir: impl Into<PathBuf>) -> Self {
@@ -117,6 +136,17 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets a custom certificate store holding the client's own key pair and the trusted
+    /// server certificates. If this is set, the pki directory, certificate path and private key
+    /// path are ignored, and no key pair is created.
+    pub fn certificate_store(
+        mut self,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
+    ) -> Self {
+        self.certificate_store = Some(certificate_store);
+        self
+    }
+
     /// Sets the preferred locales of the client. These are passed to the server during session
     /// creation to ensure localized strings are in the preferred language.
     pub fn preferred_locales(mut self, preferred_locales: Vec<String>) -> Self {
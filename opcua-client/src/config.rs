@@ -238,6 +238,9 @@ pub struct ClientConfig {
     /// Verify server certificates. For testing/samples only unless you're sure what you're
     /// doing.
     pub(crate) verify_server_certs: bool,
+    /// Accept server certificates whose issuers have no current revocation list.
+    #[serde(default)]
+    pub(crate) allow_unknown_revocation: bool,
     /// PKI folder, either absolute or relative to executable
     pub(crate) pki_dir: PathBuf,
     /// Preferred locales
@@ -574,6 +577,7 @@ impl ClientConfig {
             private_key_path: None,
             trust_server_certs: false,
             verify_server_certs: defaults::verify_server_certs(),
+            allow_unknown_revocation: false,
             product_uri: String::new(),
             pki_dir,
             preferred_locales: Vec::new(),
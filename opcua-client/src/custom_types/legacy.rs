@@ -0,0 +1,384 @@
+//! Fallback for servers that do not expose the `DataTypeDefinition` attribute,
+//! loading structure definitions from the legacy `DataTypeDictionary` instead.
+//!
+//! See OPC UA Part 5, Annex D for a description of the data type dictionaries.
+
+use hashbrown::{HashMap, HashSet};
+use log::warn;
+use opcua_types::{
+    BrowseDirection, DataTypeId, Error, NodeClassMask, NodeId, QualifiedName, ReadValueId,
+    ReferenceTypeId, StructureDefinition, StructureField, StructureType, Variant,
+};
+use opcua_xml::schema::opc_binary_schema::{StructuredType, TypeDictionaryItem};
+
+use crate::RequestRetryPolicy;
+
+use super::DataTypeTreeBuilder;
+
+impl<'a, R: RequestRetryPolicy + Clone + 'a> DataTypeTreeBuilder<'a, R> {
+    /// Load structure definitions from the legacy data type dictionaries.
+    ///
+    /// `candidates` is a list of data type IDs with their binary encoding IDs,
+    /// `types` is the full data type hierarchy, used to resolve type names in
+    /// the dictionaries.
+    pub(super) async fn load_legacy_definitions(
+        &self,
+        candidates: &[(NodeId, NodeId)],
+        types: &HashMap<NodeId, (NodeId, QualifiedName)>,
+    ) -> Result<HashMap<NodeId, StructureDefinition>, Error> {
+        if candidates.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // The binary encoding object references a DataTypeDescription variable, which
+        // is a component of the DataTypeDictionary containing the type.
+        let binary_ids: Vec<_> = candidates.iter().map(|(_, b)| b.clone()).collect();
+        let descriptions: HashMap<_, _> = self
+            .browse_one_level(
+                &binary_ids,
+                BrowseDirection::Forward,
+                ReferenceTypeId::HasDescription,
+                NodeClassMask::VARIABLE,
+            )
+            .await?
+            .into_iter()
+            .filter_map(|(id, refs)| Some((id, refs.into_iter().next()?.node_id.node_id)))
+            .collect();
+
+        let description_ids: Vec<_> = descriptions.values().cloned().collect();
+        let dictionaries: HashMap<_, _> = self
+            .browse_one_level(
+                &description_ids,
+                BrowseDirection::Inverse,
+                ReferenceTypeId::HasComponent,
+                NodeClassMask::VARIABLE,
+            )
+            .await?
+            .into_iter()
+            .filter_map(|(id, refs)| Some((id, refs.into_iter().next()?.node_id.node_id)))
+            .collect();
+
+        // Read the name of each type in its dictionary, and the dictionaries themselves.
+        let dictionary_ids: Vec<_> = dictionaries
+            .values()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .cloned()
+            .collect();
+        let values = self
+            .read_values(
+                description_ids
+                    .iter()
+                    .chain(dictionary_ids.iter())
+                    .map(|id| ReadValueId::new_value(id.clone()))
+                    .collect(),
+            )
+            .await?;
+        let (description_values, dictionary_values) = values.split_at(description_ids.len());
+
+        let type_names: HashMap<_, _> = description_ids
+            .iter()
+            .zip(description_values)
+            .filter_map(|(id, v)| match &v.value {
+                Some(Variant::String(s)) if !s.is_null() => Some((id, s.as_ref().to_owned())),
+                _ => None,
+            })
+            .collect();
+
+        let mut parsed_dictionaries = HashMap::new();
+        for (id, value) in dictionary_ids.iter().zip(dictionary_values) {
+            let Some(Variant::ByteString(data)) = &value.value else {
+                warn!(
+                    "Failed to read data type dictionary {id}: {}",
+                    value.status()
+                );
+                continue;
+            };
+            let Some(data) = data.value.as_ref() else {
+                continue;
+            };
+            let Ok(text) = std::str::from_utf8(data) else {
+                warn!("Data type dictionary {id} is not valid UTF-8");
+                continue;
+            };
+            let dictionary = match opcua_xml::load_bsd_file(text) {
+                Ok(d) => d,
+                Err(e) => {
+                    warn!("Failed to parse data type dictionary {id}: {e}");
+                    continue;
+                }
+            };
+            let structs: HashMap<_, _> = dictionary
+                .elements
+                .into_iter()
+                .filter_map(|e| match e {
+                    TypeDictionaryItem::Structured(s) => Some((s.description.name.clone(), s)),
+                    _ => None,
+                })
+                .collect();
+            parsed_dictionaries.insert(id, structs);
+        }
+
+        let resolver = TypeNameResolver::new(types);
+        let mut res = HashMap::new();
+        for (data_type_id, binary_id) in candidates {
+            let Some(description_id) = descriptions.get(binary_id) else {
+                continue;
+            };
+            let (Some(dictionary_id), Some(type_name)) = (
+                dictionaries.get(description_id),
+                type_names.get(description_id),
+            ) else {
+                continue;
+            };
+            let Some(ty) = parsed_dictionaries
+                .get(dictionary_id)
+                .and_then(|d| d.get(type_name))
+            else {
+                warn!("Type {type_name} not found in data type dictionary {dictionary_id}");
+                continue;
+            };
+            match structure_definition_from_bsd(ty, |name| {
+                resolver.resolve(name, data_type_id.namespace)
+            }) {
+                Ok(def) => {
+                    res.insert(data_type_id.clone(), def);
+                }
+                Err(e) => warn!("Failed to convert legacy definition of {data_type_id}: {e}"),
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+/// Resolves type names in a data type dictionary to data type IDs,
+/// using the browse names of the data types on the server.
+struct TypeNameResolver {
+    by_name: HashMap<String, Vec<NodeId>>,
+}
+
+impl TypeNameResolver {
+    fn new(types: &HashMap<NodeId, (NodeId, QualifiedName)>) -> Self {
+        let mut by_name: HashMap<String, Vec<NodeId>> = HashMap::new();
+        for (id, (_, browse_name)) in types {
+            by_name
+                .entry(browse_name.name.as_ref().to_owned())
+                .or_default()
+                .push(id.clone());
+        }
+        Self { by_name }
+    }
+
+    /// Resolve `name`, preferring types in `namespace`, then types
+    /// in the base namespace.
+    fn resolve(&self, name: &str, namespace: u16) -> Option<NodeId> {
+        let local = local_name(name);
+        if let Some(builtin) = builtin_type(local) {
+            return Some(builtin.into());
+        }
+        let candidates = self.by_name.get(local)?;
+        candidates
+            .iter()
+            .find(|c| c.namespace == namespace)
+            .or_else(|| candidates.iter().find(|c| c.namespace == 0))
+            .or_else(|| candidates.first())
+            .cloned()
+    }
+}
+
+/// Strip the XML namespace prefix from a type name, i.e. `opc:Int32` becomes `Int32`.
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map(|(_, n)| n).unwrap_or(name)
+}
+
+/// Map the built-in types in the OPC Binary schema to data type IDs.
+fn builtin_type(name: &str) -> Option<DataTypeId> {
+    Some(match name {
+        "Boolean" => DataTypeId::Boolean,
+        "SByte" => DataTypeId::SByte,
+        "Byte" => DataTypeId::Byte,
+        "Int16" => DataTypeId::Int16,
+        "UInt16" => DataTypeId::UInt16,
+        "Int32" => DataTypeId::Int32,
+        "UInt32" => DataTypeId::UInt32,
+        "Int64" => DataTypeId::Int64,
+        "UInt64" => DataTypeId::UInt64,
+        "Float" => DataTypeId::Float,
+        "Double" => DataTypeId::Double,
+        "String" | "CharArray" => DataTypeId::String,
+        "DateTime" => DataTypeId::DateTime,
+        "Guid" => DataTypeId::Guid,
+        "ByteString" => DataTypeId::ByteString,
+        "XmlElement" => DataTypeId::XmlElement,
+        "NodeId" => DataTypeId::NodeId,
+        "ExpandedNodeId" => DataTypeId::ExpandedNodeId,
+        "StatusCode" => DataTypeId::StatusCode,
+        "QualifiedName" => DataTypeId::QualifiedName,
+        "LocalizedText" => DataTypeId::LocalizedText,
+        "ExtensionObject" => DataTypeId::Structure,
+        "DataValue" => DataTypeId::DataValue,
+        "Variant" => DataTypeId::BaseDataType,
+        "DiagnosticInfo" => DataTypeId::DiagnosticInfo,
+        _ => return None,
+    })
+}
+
+/// Convert a structured type from an OPC Binary schema into a structure definition.
+///
+/// Array length fields and the bit fields used to encode optional fields are
+/// implicit in a structure definition, so they are left out.
+pub(super) fn structure_definition_from_bsd(
+    ty: &StructuredType,
+    resolve: impl Fn(&str) -> Option<NodeId>,
+) -> Result<StructureDefinition, String> {
+    let length_fields: HashSet<_> = ty
+        .fields
+        .iter()
+        .filter_map(|f| f.length_field.as_deref())
+        .collect();
+    let switch_fields: HashSet<_> = ty
+        .fields
+        .iter()
+        .filter_map(|f| f.switch_field.as_deref())
+        .collect();
+    let is_union = ty.fields.iter().any(|f| f.switch_value.is_some());
+
+    let mut fields = Vec::with_capacity(ty.fields.len());
+    let mut has_optional = false;
+    for field in &ty.fields {
+        let type_name = field.type_name.as_deref().unwrap_or_default();
+        if local_name(type_name) == "Bit"
+            || length_fields.contains(field.name.as_str())
+            || switch_fields.contains(field.name.as_str())
+        {
+            continue;
+        }
+        let Some(data_type) = resolve(type_name) else {
+            return Err(format!("Unknown type {type_name} for field {}", field.name));
+        };
+        let is_optional = !is_union && field.switch_field.is_some();
+        has_optional |= is_optional;
+        fields.push(StructureField {
+            name: field.name.as_str().into(),
+            data_type,
+            value_rank: if field.length_field.is_some() { 1 } else { -1 },
+            is_optional,
+            ..Default::default()
+        });
+    }
+
+    let structure_type = if is_union {
+        StructureType::Union
+    } else if has_optional {
+        StructureType::StructureWithOptionalFields
+    } else {
+        StructureType::Structure
+    };
+
+    Ok(StructureDefinition {
+        default_encoding_id: NodeId::null(),
+        base_data_type: DataTypeId::Structure.into(),
+        structure_type,
+        fields: Some(fields),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use opcua_types::{DataTypeId, NodeId, StructureType};
+    use opcua_xml::schema::opc_binary_schema::TypeDictionaryItem;
+
+    use super::{builtin_type, local_name, structure_definition_from_bsd};
+
+    const DICTIONARY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<opc:TypeDictionary xmlns:opc="http://opcfoundation.org/BinarySchema/"
+    xmlns:ua="http://opcfoundation.org/UA/" xmlns:tns="urn:test"
+    DefaultByteOrder="LittleEndian" TargetNamespace="urn:test">
+  <opc:StructuredType Name="Plain" BaseType="ua:ExtensionObject">
+    <opc:Field Name="Id" TypeName="opc:Int32" />
+    <opc:Field Name="NoOfValues" TypeName="opc:Int32" />
+    <opc:Field Name="Values" TypeName="opc:Double" LengthField="NoOfValues" />
+    <opc:Field Name="Unit" TypeName="ua:EUInformation" />
+  </opc:StructuredType>
+  <opc:StructuredType Name="WithOptional" BaseType="ua:ExtensionObject">
+    <opc:Field Name="NameSpecified" TypeName="opc:Bit" />
+    <opc:Field Name="Reserved1" TypeName="opc:Bit" Length="31" />
+    <opc:Field Name="Id" TypeName="opc:UInt32" />
+    <opc:Field Name="Name" TypeName="opc:String" SwitchField="NameSpecified" />
+  </opc:StructuredType>
+  <opc:StructuredType Name="Choice" BaseType="ua:Union">
+    <opc:Field Name="SwitchField" TypeName="opc:UInt32" />
+    <opc:Field Name="Number" TypeName="opc:Int32" SwitchField="SwitchField" SwitchValue="1" />
+    <opc:Field Name="Text" TypeName="opc:String" SwitchField="SwitchField" SwitchValue="2" />
+  </opc:StructuredType>
+</opc:TypeDictionary>"#;
+
+    fn resolve(name: &str) -> Option<NodeId> {
+        match local_name(name) {
+            "EUInformation" => Some(DataTypeId::EUInformation.into()),
+            n => builtin_type(n).map(|t| t.into()),
+        }
+    }
+
+    fn load(name: &str) -> opcua_types::StructureDefinition {
+        let dictionary = opcua_xml::load_bsd_file(DICTIONARY).unwrap();
+        let ty = dictionary
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                TypeDictionaryItem::Structured(s) if s.description.name == name => Some(s),
+                _ => None,
+            })
+            .unwrap();
+        structure_definition_from_bsd(ty, resolve).unwrap()
+    }
+
+    #[test]
+    fn legacy_plain_structure() {
+        let def = load("Plain");
+        assert_eq!(def.structure_type, StructureType::Structure);
+        let fields = def.fields.unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].name.as_ref(), "Id");
+        assert_eq!(fields[0].data_type, DataTypeId::Int32);
+        assert_eq!(fields[0].value_rank, -1);
+        assert_eq!(fields[1].name.as_ref(), "Values");
+        assert_eq!(fields[1].data_type, DataTypeId::Double);
+        assert_eq!(fields[1].value_rank, 1);
+        assert_eq!(fields[2].data_type, DataTypeId::EUInformation);
+    }
+
+    #[test]
+    fn legacy_optional_fields() {
+        let def = load("WithOptional");
+        assert_eq!(
+            def.structure_type,
+            StructureType::StructureWithOptionalFields
+        );
+        let fields = def.fields.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert!(!fields[0].is_optional);
+        assert_eq!(fields[1].name.as_ref(), "Name");
+        assert!(fields[1].is_optional);
+    }
+
+    #[test]
+    fn legacy_union() {
+        let def = load("Choice");
+        assert_eq!(def.structure_type, StructureType::Union);
+        let fields = def.fields.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].data_type, DataTypeId::Int32);
+        assert_eq!(fields[1].data_type, DataTypeId::String);
+    }
+
+    #[test]
+    fn legacy_unknown_type() {
+        let dictionary = opcua_xml::load_bsd_file(DICTIONARY).unwrap();
+        let TypeDictionaryItem::Structured(ty) = &dictionary.elements[0] else {
+            panic!("Expected structured type");
+        };
+        assert!(structure_definition_from_bsd(ty, |_| None).is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! This module contains a utility for loading the data types defined on a server
+//! at runtime, so that custom structures can be decoded without generated code.
+//!
+//! The [DataTypeTreeBuilder] walks the data type hierarchy on the server starting at
+//! `BaseDataType`, reads the `IsAbstract` and `DataTypeDefinition` attributes of
+//! each data type, and finds the encoding IDs of each structure. The result is a
+//! [DataTypeTree], which can be registered on the session as a
+//! [DynamicTypeLoader](opcua_types::custom::DynamicTypeLoader).
+//! Once this is done, any structure defined on the server will be decoded as a
+//! [DynamicStructure](opcua_types::custom::DynamicStructure) when it is received
+//! in reads, subscriptions, method results, or anywhere else.
+//!
+//! Servers that predate OPC-UA 1.04 may not expose the `DataTypeDefinition` attribute.
+//! With the `xml` feature enabled, the builder will fall back to loading
+//! structure definitions from the legacy `DataTypeDictionary` nodes, which contain
+//! the type descriptions as OPC Binary schemas.
+//!
+//! Note that the type tree uses the namespace indexes of the server at the time it
+//! was loaded. If the server namespace array changes, the types must be reloaded.
+
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use hashbrown::HashMap;
+use log::warn;
+use opcua_types::{
+    custom::{DataTypeTree, EncodingIds, GenericTypeInfo, ParentIds, StructTypeInfo, TypeInfo},
+    AttributeId, BrowseDescription, BrowseDirection, DataTypeDefinition, DataTypeId, DataValue,
+    Error, NodeClassMask, NodeId, QualifiedName, ReadValueId, ReferenceDescription,
+    ReferenceTypeId, StatusCode, StructureType, Variant, VariantScalarTypeId,
+};
+
+use crate::{
+    browser::{BrowseFilter, BrowseResultItem},
+    session::Read,
+    RequestRetryPolicy, Session,
+};
+
+#[cfg(feature = "xml")]
+mod legacy;
+
+/// Attributes read from each data type node.
+struct DataTypeAttributes {
+    is_abstract: bool,
+    definition: Option<DataTypeDefinition>,
+}
+
+/// Builder for loading a [DataTypeTree] from the server.
+///
+/// Create this using [Session::data_type_tree_builder], then call
+/// [DataTypeTreeBuilder::load] to load the tree, or
+/// [DataTypeTreeBuilder::load_and_register] to also register it on the session.
+pub struct DataTypeTreeBuilder<'a, R> {
+    session: &'a Session,
+    retry_policy: R,
+    max_nodes_per_request: usize,
+    #[cfg(feature = "xml")]
+    legacy_dictionaries: bool,
+}
+
+impl<'a, R> DataTypeTreeBuilder<'a, R> {
+    /// Create a new data type tree builder.
+    pub fn new(session: &'a Session, retry_policy: R) -> Self {
+        Self {
+            session,
+            retry_policy,
+            max_nodes_per_request: 100,
+            #[cfg(feature = "xml")]
+            legacy_dictionaries: true,
+        }
+    }
+
+    /// Set a new retry policy.
+    pub fn retry_policy<R2: RequestRetryPolicy + Clone + 'a>(
+        self,
+        retry_policy: R2,
+    ) -> DataTypeTreeBuilder<'a, R2> {
+        DataTypeTreeBuilder {
+            session: self.session,
+            retry_policy,
+            max_nodes_per_request: self.max_nodes_per_request,
+            #[cfg(feature = "xml")]
+            legacy_dictionaries: self.legacy_dictionaries,
+        }
+    }
+
+    /// Set the maximum number of nodes per `Browse` or `Read` request.
+    /// Defaults to 100. Zero means no limit.
+    pub fn max_nodes_per_request(mut self, max_nodes_per_request: usize) -> Self {
+        self.max_nodes_per_request = max_nodes_per_request;
+        self
+    }
+
+    #[cfg(feature = "xml")]
+    /// Set whether to fall back to the legacy `DataTypeDictionary` for structures
+    /// that are missing the `DataTypeDefinition` attribute. Defaults to `true`.
+    pub fn legacy_dictionaries(mut self, legacy_dictionaries: bool) -> Self {
+        self.legacy_dictionaries = legacy_dictionaries;
+        self
+    }
+}
+
+impl<'a, R: RequestRetryPolicy + Clone + 'a> DataTypeTreeBuilder<'a, R> {
+    /// Load the data type tree from the server, and register a
+    /// [DynamicTypeLoader](opcua_types::custom::DynamicTypeLoader)
+    /// using it on the session. This replaces any type tree previously registered
+    /// with [Session::set_data_type_tree].
+    pub async fn load_and_register(self) -> Result<Arc<DataTypeTree>, Error> {
+        let session = self.session;
+        let tree = Arc::new(self.load().await?);
+        session.set_data_type_tree(tree.clone());
+        Ok(tree)
+    }
+
+    /// Load the data type tree from the server.
+    pub async fn load(self) -> Result<DataTypeTree, Error> {
+        let types = self.browse_type_hierarchy().await?;
+        let (ids, parent_ids) = {
+            let mut parent_ids = ParentIds::new();
+            let mut ids = Vec::with_capacity(types.len());
+            for (id, (parent, _)) in &types {
+                parent_ids.add_type(id.clone(), parent.clone());
+                ids.push(id.clone());
+            }
+            (ids, parent_ids)
+        };
+
+        let attributes = self.read_type_attributes(&ids).await?;
+
+        let struct_ids: Vec<_> = ids
+            .iter()
+            .filter(|id| {
+                parent_ids.get_builtin_type(id) == Some(VariantScalarTypeId::ExtensionObject)
+            })
+            .cloned()
+            .collect();
+        let mut encodings = self.browse_encodings(&struct_ids).await?;
+
+        let mut tree = DataTypeTree::new(parent_ids);
+        let mut missing = Vec::new();
+        for (id, attrs) in ids.into_iter().zip(attributes) {
+            let is_struct = tree.parent_ids().get_builtin_type(&id)
+                == Some(VariantScalarTypeId::ExtensionObject);
+            match attrs.definition {
+                Some(definition) => {
+                    let encoding_ids = encodings.remove(&id);
+                    match TypeInfo::from_type_definition(
+                        definition,
+                        encoding_ids,
+                        attrs.is_abstract,
+                        &id,
+                        tree.parent_ids(),
+                    ) {
+                        Ok(info) => tree.add_type(id, info),
+                        Err(e) => warn!("Failed to load data type definition for {id}: {e}"),
+                    }
+                }
+                None if is_struct => missing.push((id, attrs.is_abstract)),
+                None => tree.add_type(id, GenericTypeInfo::new(attrs.is_abstract)),
+            }
+        }
+
+        #[cfg(feature = "xml")]
+        if self.legacy_dictionaries {
+            let candidates: Vec<_> = missing
+                .iter()
+                .filter(|(_, is_abstract)| !*is_abstract)
+                .filter_map(|(id, _)| {
+                    let binary_id = &encodings.get(id)?.binary_id;
+                    (!binary_id.is_null()).then(|| (id.clone(), binary_id.clone()))
+                })
+                .collect();
+            let definitions = self.load_legacy_definitions(&candidates, &types).await?;
+            let mut remaining = Vec::with_capacity(missing.len());
+            for (id, is_abstract) in missing {
+                let Some(definition) = definitions.get(&id) else {
+                    remaining.push((id, is_abstract));
+                    continue;
+                };
+                match TypeInfo::from_type_definition(
+                    DataTypeDefinition::Structure(definition.clone()),
+                    encodings.remove(&id),
+                    is_abstract,
+                    &id,
+                    tree.parent_ids(),
+                ) {
+                    Ok(info) => tree.add_type(id, info),
+                    Err(e) => {
+                        warn!("Failed to load legacy data type definition for {id}: {e}");
+                        remaining.push((id, is_abstract));
+                    }
+                }
+            }
+            missing = remaining;
+        }
+
+        for (id, is_abstract) in missing {
+            if is_abstract {
+                // Abstract structures, like `Structure` itself, have no definition,
+                // but fields with these types are encoded as extension objects,
+                // so we need to know about them.
+                tree.add_type(
+                    id.clone(),
+                    StructTypeInfo {
+                        structure_type: StructureType::Structure,
+                        fields: Vec::new(),
+                        index_by_name: Default::default(),
+                        encoding_ids: encodings.remove(&id).unwrap_or_else(|| EncodingIds {
+                            binary_id: NodeId::null(),
+                            json_id: NodeId::null(),
+                            xml_id: NodeId::null(),
+                        }),
+                        is_abstract: true,
+                        node_id: id,
+                    },
+                );
+            } else {
+                warn!("Structure data type {id} has no data type definition, it will not be decoded dynamically");
+            }
+        }
+
+        Ok(tree)
+    }
+
+    /// Recursively browse the data type hierarchy, returning each discovered
+    /// data type with its parent and browse name.
+    async fn browse_type_hierarchy(
+        &self,
+    ) -> Result<HashMap<NodeId, (NodeId, QualifiedName)>, Error> {
+        let filter =
+            BrowseFilter::new(BrowseDirection::Forward, ReferenceTypeId::HasSubtype, false)
+                .node_class_mask(NodeClassMask::DATA_TYPE);
+        let initial = vec![filter.new_description_from_node(DataTypeId::BaseDataType.into())];
+        let stream = self
+            .session
+            .browser()
+            .retry_policy(self.retry_policy.clone())
+            .max_nodes_per_request(self.max_nodes_per_request)
+            .handler(filter)
+            .run(initial);
+        futures::pin_mut!(stream);
+
+        let mut types = HashMap::new();
+        while let Some(item) = stream.try_next().await? {
+            if item.status().is_bad() {
+                warn!(
+                    "Failed to browse subtypes of data type {}: {}",
+                    item.parent_id(),
+                    item.status()
+                );
+                continue;
+            }
+            let (parent_id, references) = item.into_results();
+            for r in references {
+                if r.node_id.server_index != 0 {
+                    continue;
+                }
+                types.insert(r.node_id.node_id, (parent_id.clone(), r.browse_name));
+            }
+        }
+
+        Ok(types)
+    }
+
+    /// Read the `IsAbstract` and `DataTypeDefinition` attributes of each data type.
+    async fn read_type_attributes(&self, ids: &[NodeId]) -> Result<Vec<DataTypeAttributes>, Error> {
+        let to_read: Vec<_> = ids
+            .iter()
+            .flat_map(|id| {
+                [
+                    ReadValueId::new(id.clone(), AttributeId::IsAbstract),
+                    ReadValueId::new(id.clone(), AttributeId::DataTypeDefinition),
+                ]
+            })
+            .collect();
+        let values = self.read_values(to_read).await?;
+
+        let mut res = Vec::with_capacity(ids.len());
+        let mut iter = values.into_iter();
+        while let (Some(is_abstract), Some(definition)) = (iter.next(), iter.next()) {
+            let is_abstract = matches!(is_abstract.value, Some(Variant::Boolean(true)));
+            let definition = match definition.value {
+                Some(Variant::ExtensionObject(o)) if o.body.is_some() => {
+                    DataTypeDefinition::from_extension_object(o).ok()
+                }
+                _ => None,
+            };
+            res.push(DataTypeAttributes {
+                is_abstract,
+                definition,
+            });
+        }
+        Ok(res)
+    }
+
+    /// Find the encoding IDs of each structure type.
+    async fn browse_encodings(
+        &self,
+        struct_ids: &[NodeId],
+    ) -> Result<HashMap<NodeId, EncodingIds>, Error> {
+        let references = self
+            .browse_one_level(
+                struct_ids,
+                BrowseDirection::Forward,
+                ReferenceTypeId::HasEncoding,
+                NodeClassMask::OBJECT,
+            )
+            .await?;
+
+        let mut res = HashMap::with_capacity(references.len());
+        for (id, refs) in references {
+            let mut ids = EncodingIds {
+                binary_id: NodeId::null(),
+                json_id: NodeId::null(),
+                xml_id: NodeId::null(),
+            };
+            for r in refs {
+                if r.browse_name.namespace_index != 0 {
+                    continue;
+                }
+                match r.browse_name.name.as_ref() {
+                    "Default Binary" => ids.binary_id = r.node_id.node_id,
+                    "Default JSON" => ids.json_id = r.node_id.node_id,
+                    "Default XML" => ids.xml_id = r.node_id.node_id,
+                    _ => (),
+                }
+            }
+            if !ids.binary_id.is_null() || !ids.json_id.is_null() || !ids.xml_id.is_null() {
+                res.insert(id, ids);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Browse a single level of references from each node in `nodes`, without recursing.
+    async fn browse_one_level(
+        &self,
+        nodes: &[NodeId],
+        direction: BrowseDirection,
+        reference_type_id: ReferenceTypeId,
+        node_class_mask: NodeClassMask,
+    ) -> Result<HashMap<NodeId, Vec<ReferenceDescription>>, Error> {
+        let filter =
+            BrowseFilter::new(direction, reference_type_id, false).node_class_mask(node_class_mask);
+        let initial: Vec<BrowseDescription> = nodes
+            .iter()
+            .map(|n| filter.new_description_from_node(n.clone()))
+            .collect();
+        let stream = self
+            .session
+            .browser()
+            .retry_policy(self.retry_policy.clone())
+            .max_nodes_per_request(self.max_nodes_per_request)
+            .handler(|_: &BrowseResultItem| Vec::new())
+            .run(initial);
+        futures::pin_mut!(stream);
+
+        let mut res: HashMap<NodeId, Vec<ReferenceDescription>> = HashMap::new();
+        while let Some(item) = stream.try_next().await? {
+            if item.status().is_bad() {
+                continue;
+            }
+            let (parent_id, references) = item.into_results();
+            res.entry(parent_id).or_default().extend(
+                references
+                    .into_iter()
+                    .filter(|r| r.node_id.server_index == 0),
+            );
+        }
+        Ok(res)
+    }
+
+    /// Read a list of values, split into chunks of at most `max_nodes_per_request`.
+    async fn read_values(&self, to_read: Vec<ReadValueId>) -> Result<Vec<DataValue>, Error> {
+        let chunk_size = if self.max_nodes_per_request == 0 {
+            to_read.len().max(1)
+        } else {
+            self.max_nodes_per_request
+        };
+        let mut res = Vec::with_capacity(to_read.len());
+        for chunk in to_read.chunks(chunk_size) {
+            let response = self
+                .session
+                .send_with_retry(
+                    Read::new(self.session).nodes_to_read(chunk.to_vec()),
+                    self.retry_policy.clone(),
+                )
+                .await
+                .map_err(|e| Error::new(e, "Read failed"))?;
+            let results = response.results.unwrap_or_default();
+            if results.len() != chunk.len() {
+                return Err(Error::new(
+                    StatusCode::BadUnexpectedError,
+                    format!(
+                        "Incorrect number of results returned from Read, expected {}, got {}",
+                        chunk.len(),
+                        results.len()
+                    ),
+                ));
+            }
+            res.extend(results);
+        }
+        Ok(res)
+    }
+}
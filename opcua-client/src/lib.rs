@@ -116,6 +116,7 @@
 pub mod browser;
 mod builder;
 mod config;
+pub mod custom_types;
 mod retry;
 mod session;
 mod transport;
@@ -126,12 +127,21 @@ pub use builder::ClientBuilder;
 pub use config::{ClientConfig, ClientEndpoint, ClientUserToken, ANONYMOUS_USER_TOKEN_ID};
 pub use retry::{ExponentialBackoff, SessionRetryPolicy};
 pub use session::{
-    Client, DataChangeCallback, DefaultRetryPolicy, EventCallback, HistoryReadAction,
-    HistoryUpdateAction, MonitoredItem, OnSubscriptionNotification, RequestRetryPolicy, Session,
-    SessionActivity, SessionConnectMode, SessionEventLoop, SessionPollResult, Subscription,
-    SubscriptionCallbacks, UARequest,
+    Client, DataChangeCallback, DefaultRetryPolicy, EventCallback, FailoverConfig, FailoverMode,
+    HistoryReadAction, HistoryUpdateAction, MonitoredItem, MonitoredItemStream,
+    NotificationStreams, OnSubscriptionNotification, OverflowPolicy, RedundantServerStatus,
+    RequestRetryPolicy, Session, SessionActivity, SessionConnectMode, SessionEventLoop,
+    SessionPollResult, StreamNotification, StreamOptions, Subscription, SubscriptionCallbacks,
+    SubscriptionStream, UARequest,
+};
+#[cfg(feature = "https")]
+pub use transport::https::HttpsConnector;
+#[cfg(feature = "websocket")]
+pub use transport::websocket::WebSocketConnector;
+pub use transport::{
+    tcp::{ReverseTcpConnector, StreamConnector, TcpConnector},
+    AsyncSecureChannel, Connector,
 };
-pub use transport::AsyncSecureChannel;
 
 pub mod services {
     //! This module contains request builders for most OPC-UA services.
@@ -144,8 +154,9 @@ pub mod services {
         ActivateSession, AddNodes, AddReferences, Browse, BrowseNext, Call, Cancel, CloseSession,
         CreateMonitoredItems, CreateSession, CreateSubscription, DeleteMonitoredItems, DeleteNodes,
         DeleteReferences, DeleteSubscriptions, HistoryRead, HistoryUpdate, ModifyMonitoredItems,
-        ModifySubscription, Read, RegisterNodes, SetMonitoringMode, SetPublishingMode,
-        SetTriggering, TransferSubscriptions, TranslateBrowsePaths, UnregisterNodes, Write,
+        ModifySubscription, QueryFirst, QueryNext, Read, RegisterNodes, SetMonitoringMode,
+        SetPublishingMode, SetTriggering, TransferSubscriptions, TranslateBrowsePaths,
+        UnregisterNodes, Write,
     };
 }
 
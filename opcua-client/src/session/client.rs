@@ -2,12 +2,12 @@ use std::{str::FromStr, sync::Arc};
 
 use chrono::Duration;
 use log::{debug, error};
-use tokio::{pin, select};
+use tokio::{net::TcpListener, pin, select};
 
 use crate::{
     transport::{
         tcp::{TcpConnector, TransportConfiguration},
-        TransportPollResult,
+        Connector, TransportPollResult,
     },
     AsyncSecureChannel, ClientConfig, ClientEndpoint, IdentityToken,
 };
@@ -18,9 +18,9 @@ use opcua_core::{
     },
     config::Config,
     sync::RwLock,
-    ResponseMessage,
+    trace_write_lock, ResponseMessage,
 };
-use opcua_crypto::{CertificateStore, SecurityPolicy};
+use opcua_crypto::{CertificateStore, FileSystemCertificateStore, SecurityPolicy};
 use opcua_types::{
     ApplicationDescription, ContextOwned, DecodingOptions, EndpointDescription,
     FindServersOnNetworkRequest, FindServersOnNetworkResponse, FindServersRequest,
@@ -39,7 +39,7 @@ pub struct Client {
     /// Client configuration
     pub(super) config: ClientConfig,
     /// Certificate store is where certificates go.
-    certificate_store: Arc<RwLock<CertificateStore>>,
+    certificate_store: Arc<RwLock<dyn CertificateStore>>,
 }
 
 impl Client {
@@ -57,8 +57,8 @@ impl Client {
             None
         };
 
-        let (mut certificate_store, client_certificate, client_pkey) =
-            CertificateStore::new_with_x509_data(
+        let (certificate_store, client_certificate, client_pkey) =
+            FileSystemCertificateStore::new_with_x509_data(
                 &config.pki_dir,
                 false,
                 config.certificate_path.as_deref(),
@@ -69,18 +69,42 @@ impl Client {
             error!("Client is missing its application instance certificate and/or its private key. Encrypted endpoints will not function correctly.")
         }
 
-        // Clients may choose to skip additional server certificate validations
-        certificate_store.set_skip_verify_certs(!config.verify_server_certs);
+        Self::new_with_certificate_store(config, Arc::new(RwLock::new(certificate_store)))
+    }
+
+    /// Create a new client from config, using the given certificate store instead of
+    /// the PKI directory in the config.
+    ///
+    /// Note that this does not make any connection to the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Client configuration object.
+    /// * `certificate_store` - Store holding the client's own certificate and private key,
+    ///   and the trusted server certificates.
+    pub fn new_with_certificate_store(
+        config: ClientConfig,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
+    ) -> Self {
+        {
+            let mut certificate_store = trace_write_lock!(certificate_store);
 
-        // Clients may choose to auto trust servers to save some messing around with rejected certs
-        certificate_store.set_trust_unknown_certs(config.trust_server_certs);
+            // Clients may choose to skip additional server certificate validations
+            certificate_store.set_skip_verify_certs(!config.verify_server_certs);
+
+            // Clients may choose to auto trust servers to save some messing around with rejected certs
+            certificate_store.set_trust_unknown_certs(config.trust_server_certs);
+
+            // Clients may choose to accept servers whose CAs publish no revocation lists
+            certificate_store.set_allow_unknown_revocation(config.allow_unknown_revocation);
+        }
 
         // The session retry policy dictates how many times to retry if connection to the server goes down
         // and on what interval
 
         Self {
             config,
-            certificate_store: Arc::new(RwLock::new(certificate_store)),
+            certificate_store,
         }
     }
 
@@ -180,6 +204,43 @@ impl Client {
             .build(self.certificate_store.clone()))
     }
 
+    /// Connects to a server using reverse connect. Instead of opening a connection to the server,
+    /// the client waits for the server to connect to `listener` and identify itself with
+    /// a `ReverseHello` message.
+    ///
+    /// This function returns both a reference to the session, and a `SessionEventLoop`. You must run and
+    /// poll the event loop in order to actually establish a connection.
+    ///
+    /// Since the client cannot connect to the server on its own, it is not possible to get endpoints
+    /// from the server first, so the endpoint must be known in advance.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - Endpoint to connect to.
+    /// * `identity_token` - Identity token for authentication.
+    /// * `listener` - Listener the server will connect to.
+    /// * `server_uri` - Application URI of the server. Connections from other servers are rejected.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((Arc<Session>, SessionEventLoop))` - Session and event loop.
+    /// * `Err(String)` - Endpoint is invalid.
+    ///
+    pub fn connect_to_endpoint_reverse(
+        &mut self,
+        endpoint: impl Into<EndpointDescription>,
+        identity_token: IdentityToken,
+        listener: TcpListener,
+        server_uri: impl Into<String>,
+    ) -> Result<(Arc<Session>, SessionEventLoop), String> {
+        Ok(self
+            .session_builder()
+            .reverse_connect(listener, server_uri)
+            .connect_to_endpoint_directly(endpoint)?
+            .user_identity_token(identity_token)
+            .build(self.certificate_store.clone()))
+    }
+
     /// Creates a new [`Session`] using the default endpoint specified in the config. If
     /// there is no default, or the endpoint does not exist, this function will return an error
     ///
@@ -220,6 +281,15 @@ impl Client {
         &self,
         session_info: SessionInfo,
         channel_lifetime: u32,
+    ) -> AsyncSecureChannel {
+        self.channel_with_connector(session_info, channel_lifetime, Box::new(TcpConnector))
+    }
+
+    fn channel_with_connector(
+        &self,
+        session_info: SessionInfo,
+        channel_lifetime: u32,
+        connector: Box<dyn Connector>,
     ) -> AsyncSecureChannel {
         AsyncSecureChannel::new(
             self.certificate_store.clone(),
@@ -234,7 +304,7 @@ impl Client {
                 max_message_size: self.config.decoding_options.max_message_size,
                 max_chunk_count: self.config.decoding_options.max_chunk_count,
             },
-            Box::new(TcpConnector),
+            connector,
             channel_lifetime,
             // We should only ever need the default decoding context for temporary connections.
             Arc::new(RwLock::new(ContextOwned::new_default(
@@ -366,6 +436,43 @@ impl Client {
         if !is_opc_ua_binary_url(&server_url) {
             return Err(StatusCode::BadTcpEndpointUrlInvalid);
         }
+        self.get_endpoints_inner(server_url, locale_ids, profile_uris, Box::new(TcpConnector))
+            .await
+    }
+
+    /// Get the list of endpoints for the server at the given URL, using a custom
+    /// connector. This is needed to get endpoints from servers that are not
+    /// available over plain `opc.tcp`, for example when using WebSockets.
+    ///
+    /// # Arguments
+    ///
+    /// * `server_url` - URL of the discovery server to get endpoints from.
+    /// * `connector` - Connector used to connect to the server.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<EndpointDescription>)` - A list of the available endpoints on the server.
+    /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
+    pub async fn get_endpoints_with_connector(
+        &self,
+        server_url: impl Into<String>,
+        connector: impl Connector + 'static,
+    ) -> Result<Vec<EndpointDescription>, StatusCode> {
+        let server_url = server_url.into();
+        if !is_valid_opc_ua_url(&server_url) {
+            return Err(StatusCode::BadTcpEndpointUrlInvalid);
+        }
+        self.get_endpoints_inner(server_url, &[], &[], Box::new(connector))
+            .await
+    }
+
+    async fn get_endpoints_inner(
+        &self,
+        server_url: String,
+        locale_ids: &[&str],
+        profile_uris: &[&str],
+        connector: Box<dyn Connector>,
+    ) -> Result<Vec<EndpointDescription>, StatusCode> {
         let preferred_locales = Vec::new();
         // Most of these fields mean nothing when getting endpoints
         let endpoint = EndpointDescription::from(server_url.as_ref());
@@ -374,7 +481,8 @@ impl Client {
             user_identity_token: IdentityToken::Anonymous,
             preferred_locales,
         };
-        let channel = self.channel_from_session_info(session_info, self.config.channel_lifetime);
+        let channel =
+            self.channel_with_connector(session_info, self.config.channel_lifetime, connector);
 
         let mut evt_loop = channel.connect().await?;
 
@@ -752,7 +860,7 @@ impl Client {
     }
 
     /// Get the certificate store.
-    pub fn certificate_store(&self) -> &Arc<RwLock<CertificateStore>> {
+    pub fn certificate_store(&self) -> &Arc<RwLock<dyn CertificateStore>> {
         &self.certificate_store
     }
 }
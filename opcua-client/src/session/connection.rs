@@ -1,24 +1,29 @@
 use std::{str::FromStr, sync::Arc};
 
 use log::error;
-use opcua_core::{comms::url::is_opc_ua_binary_url, config::Config, sync::RwLock};
+use opcua_core::{comms::url::is_valid_opc_ua_url, config::Config, sync::RwLock};
 use opcua_crypto::{CertificateStore, SecurityPolicy};
 use opcua_types::{
     EndpointDescription, MessageSecurityMode, NodeId, StatusCode, TypeLoader, UserTokenType,
 };
+use tokio::net::TcpListener;
 
 use crate::{
-    transport::{tcp::TcpConnector, Connector},
+    transport::{
+        tcp::{ReverseTcpConnector, TcpConnector},
+        Connector,
+    },
     ClientConfig, IdentityToken,
 };
 
-use super::{Client, Session, SessionEventLoop, SessionInfo};
+use super::{Client, FailoverConfig, Session, SessionEventLoop, SessionInfo};
 
 struct SessionBuilderInner {
     session_id: Option<NodeId>,
     user_identity_token: IdentityToken,
     connector: Box<dyn Connector>,
     type_loaders: Vec<Arc<dyn TypeLoader>>,
+    failover: Option<FailoverConfig>,
 }
 
 /// Type-state builder for a session and session event loop.
@@ -44,6 +49,7 @@ impl<'a> SessionBuilder<'a, (), ()> {
                 user_identity_token: IdentityToken::Anonymous,
                 connector: Box::new(TcpConnector),
                 type_loaders: Vec::new(),
+                failover: None,
             },
         }
     }
@@ -89,6 +95,37 @@ impl<T, R> SessionBuilder<'_, T, R> {
         self
     }
 
+    /// Use reverse connect. Instead of connecting to the server, the session waits for
+    /// the server with application URI `server_uri` to connect to `listener`, and
+    /// send a `ReverseHello` message. This is useful when the client is not allowed to
+    /// open connections into the network the server is in.
+    ///
+    /// The session will wait for a new connection from the server each time
+    /// it needs to reconnect. Since the client cannot connect to the server on its own,
+    /// the endpoint must be known in advance, for example by using
+    /// [SessionBuilder::connect_to_endpoint_directly].
+    pub fn reverse_connect(mut self, listener: TcpListener, server_uri: impl Into<String>) -> Self {
+        self.inner.connector = Box::new(ReverseTcpConnector::new(listener, server_uri));
+        self
+    }
+
+    /// Fail over between the servers of a redundant server set. The session
+    /// monitors the health of the active server, and switches to another server in
+    /// the set if it becomes unavailable or, depending on the [`FailoverMode`](crate::FailoverMode),
+    /// unhealthy. The [`Session`] stays the same, and subscriptions are moved to the new server.
+    pub fn failover(mut self, config: FailoverConfig) -> Self {
+        self.inner.failover = Some(config);
+        self
+    }
+
+    /// Use a custom connector to establish connections to the server. For example,
+    /// use a [`StreamConnector`](crate::StreamConnector) to run OPC-UA over a Unix
+    /// domain socket or a TLS stream.
+    pub fn connector(mut self, connector: impl Connector + 'static) -> Self {
+        self.inner.connector = Box::new(connector);
+        self
+    }
+
     fn endpoint_supports_token(&self, endpoint: &EndpointDescription) -> bool {
         match &self.inner.user_identity_token {
             IdentityToken::Anonymous => {
@@ -247,7 +284,7 @@ impl<'a, R> SessionBuilder<'a, (), R> {
         endpoint: impl Into<EndpointDescription>,
     ) -> Result<SessionBuilder<'a, EndpointDescription, R>, String> {
         let endpoint = endpoint.into();
-        if !is_opc_ua_binary_url(endpoint.endpoint_url.as_ref()) {
+        if !is_valid_opc_ua_url(endpoint.endpoint_url.as_ref()) {
             return Err(format!(
                 "Endpoint url {} is not a valid / supported url",
                 endpoint.endpoint_url
@@ -267,7 +304,7 @@ impl<R> SessionBuilder<'_, EndpointDescription, R> {
     /// start polling the event loop before a connection is actually established.
     pub fn build(
         self,
-        certificate_store: Arc<RwLock<CertificateStore>>,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
     ) -> (Arc<Session>, SessionEventLoop) {
         Session::new(
             certificate_store,
@@ -284,6 +321,7 @@ impl<R> SessionBuilder<'_, EndpointDescription, R> {
             self.inner.session_id,
             self.inner.connector,
             self.inner.type_loaders,
+            self.inner.failover,
         )
     }
 }
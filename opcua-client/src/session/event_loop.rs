@@ -12,7 +12,7 @@ use crate::{
     transport::{SecureChannelEventLoop, TransportPollResult},
 };
 use opcua_types::{
-    AttributeId, QualifiedName, ReadValueId, StatusCode, TimestampsToReturn, VariableId,
+    AttributeId, QualifiedName, ReadValueId, StatusCode, TimestampsToReturn, UAString, VariableId,
 };
 
 use super::{
@@ -44,6 +44,10 @@ pub enum SessionPollResult {
     BeginConnect,
     /// Disconnect due to a keep alive terminated.
     FinishedDisconnect,
+    /// The session connected to a different server in the redundant server set
+    /// than before, with the endpoint URL of the new server. This is emitted instead of
+    /// `Reconnected` when the session fails over.
+    FailedOver(UAString, SessionConnectMode),
 }
 
 struct ConnectedState {
@@ -142,6 +146,9 @@ impl SessionEventLoop {
                                     if !should_reconnect {
                                         return Ok(None);
                                     }
+                                    if !state.currently_closing {
+                                        slf.inner.mark_active_server_failed();
+                                    }
 
                                     Ok((
                                         SessionPollResult::ConnectionLost(code),
@@ -162,7 +169,24 @@ impl SessionEventLoop {
                                 };
 
                                 match r {
-                                    SessionActivity::KeepAliveSucceeded => state.current_failed_keep_alive_count = 0,
+                                    SessionActivity::KeepAliveSucceeded => {
+                                        state.current_failed_keep_alive_count = 0;
+                                        if !state.currently_closing && slf.inner.should_fail_over() {
+                                            session_warn!(slf.inner, "Service level of the server is degraded, failing over to a different server");
+                                            slf.inner.mark_active_server_failed();
+                                            state.currently_closing = true;
+                                            let s = slf.inner.clone();
+                                            state.disconnect_fut = async move {
+                                                // With mirrored servers, the session lives on in the
+                                                // redundant server set, so only the channel is closed.
+                                                if !s.failover_is_mirrored() {
+                                                    let _ = s.close_session(true).await;
+                                                }
+                                                s.channel.close_channel().await;
+                                                Ok(())
+                                            }.boxed();
+                                        }
+                                    }
                                     SessionActivity::KeepAliveFailed(status_code) => {
                                         session_warn!(slf.inner, "Keep alive failed: {status_code}");
                                         state.current_failed_keep_alive_count += 1;
@@ -171,6 +195,7 @@ impl SessionEventLoop {
                                             && slf.max_failed_keep_alive_count != 0
                                         {
                                             session_error!(slf.inner, "Maximum number of failed keep-alives exceed limit, session will be closed.");
+                                            slf.inner.mark_active_server_failed();
                                             state.currently_closing = true;
                                             let s = slf.inner.clone();
                                             state.disconnect_fut = async move {
@@ -200,6 +225,7 @@ impl SessionEventLoop {
                             _ = &mut state.disconnect_fut => {
                                 // Do nothing, if this terminates we will very soon be transitioning
                                 // to a disconnected state.
+                                state.disconnect_fut = futures::future::pending().boxed();
                                 Ok((
                                     SessionPollResult::FinishedDisconnect,
                                     SessionEventLoopState::Connected(state)
@@ -209,6 +235,7 @@ impl SessionEventLoop {
                     }
                     SessionEventLoopState::Disconnected => {
                         let connector = SessionConnector::new(slf.inner.clone());
+                        slf.inner.begin_failover_round();
 
                         let _ = slf.inner.state_watch_tx.send(SessionState::Connecting);
 
@@ -224,11 +251,19 @@ impl SessionEventLoop {
                     SessionEventLoopState::Connecting(connector, mut backoff, next_try) => {
                         tokio::time::sleep_until(next_try.into()).await;
 
+                        let server = slf.inner.prepare_failover_attempt();
                         match connector.try_connect().await {
                             Ok((channel, result)) => {
                                 let _ = slf.inner.state_watch_tx.send(SessionState::Connected);
+                                let failed_over = server
+                                    .is_some_and(|idx| slf.inner.finish_failover_attempt(idx));
+                                let res = if failed_over {
+                                    SessionPollResult::FailedOver(slf.inner.endpoint_url(), result)
+                                } else {
+                                    SessionPollResult::Reconnected(result)
+                                };
                                 Ok((
-                                    SessionPollResult::Reconnected(result),
+                                    res,
                                     SessionEventLoopState::Connected(ConnectedState {
                                         channel,
                                         keep_alive: SessionActivityLoop::new(
@@ -251,6 +286,21 @@ impl SessionEventLoop {
                             }
                             Err(e) => {
                                 warn!("Failed to connect to server, status code: {e}");
+                                // Try the remaining servers in the redundant server set
+                                // before backing off.
+                                if slf.inner.has_failover_candidates() {
+                                    return Ok(Some((
+                                        SessionPollResult::ReconnectFailed(e),
+                                        (
+                                            slf,
+                                            SessionEventLoopState::Connecting(
+                                                connector,
+                                                backoff,
+                                                Instant::now(),
+                                            ),
+                                        ),
+                                    )));
+                                }
                                 match backoff.next() {
                                     Some(x) => Ok((
                                         SessionPollResult::ReconnectFailed(e),
@@ -323,22 +373,25 @@ impl SessionActivityLoop {
             match slf.tick_gen.next().await {
                 SessionTickEvent::KeepAlive => {
                     let now = Instant::now();
+                    let mut nodes_to_read = vec![ReadValueId {
+                        node_id: VariableId::Server_ServerStatus_State.into(),
+                        attribute_id: AttributeId::Value as u32,
+                        index_range: Default::default(),
+                        data_encoding: QualifiedName::null(),
+                    }];
+                    nodes_to_read.extend(slf.inner.redundancy_read_ids());
                     let res = slf
                         .inner
-                        .read(
-                            &[ReadValueId {
-                                node_id: VariableId::Server_ServerStatus_State.into(),
-                                attribute_id: AttributeId::Value as u32,
-                                index_range: Default::default(),
-                                data_encoding: QualifiedName::null(),
-                            }],
-                            TimestampsToReturn::Server,
-                            1f64,
-                        )
+                        .read(&nodes_to_read, TimestampsToReturn::Server, 1f64)
                         .await;
                     let elapsed = now.elapsed();
 
-                    let data_value = match res.map(|r| r.into_iter().next()) {
+                    let data_value = match res.map(|r| {
+                        if r.len() > 1 {
+                            slf.inner.update_redundancy_status(&r[1..]);
+                        }
+                        r.into_iter().next()
+                    }) {
                         Ok(Some(data_value)) => {
                             // Only update if the request was successful to avoid
                             // skewing the roundtrip time by processing timeouts.
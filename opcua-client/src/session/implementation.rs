@@ -10,6 +10,7 @@ use arc_swap::ArcSwap;
 
 use crate::{
     browser::Browser,
+    custom_types::DataTypeTreeBuilder,
     retry::SessionRetryPolicy,
     session::session_warn,
     transport::{tcp::TransportConfiguration, Connector},
@@ -21,11 +22,13 @@ use opcua_core::{
 };
 use opcua_crypto::CertificateStore;
 use opcua_types::{
-    ApplicationDescription, ContextOwned, DecodingOptions, IntegerId, NamespaceMap, NodeId,
+    custom::{DataTypeTree, DynamicTypeLoader},
+    ApplicationDescription, ContextOwned, DecodingOptions, Error, IntegerId, NamespaceMap, NodeId,
     RequestHeader, StatusCode, TypeLoader, UAString,
 };
 
 use super::{
+    redundancy::{FailoverConfig, FailoverState},
     services::subscriptions::{state::SubscriptionState, PublishLimits},
     DefaultRetryPolicy, SessionEventLoop, SessionInfo,
 };
@@ -51,11 +54,10 @@ pub struct Session {
     pub(super) channel: AsyncSecureChannel,
     pub(super) state_watch_rx: tokio::sync::watch::Receiver<SessionState>,
     pub(super) state_watch_tx: tokio::sync::watch::Sender<SessionState>,
-    pub(super) certificate_store: Arc<RwLock<CertificateStore>>,
+    pub(super) certificate_store: Arc<RwLock<dyn CertificateStore>>,
     pub(super) session_id: Arc<ArcSwap<NodeId>>,
     pub(super) auth_token: Arc<ArcSwap<NodeId>>,
     pub(super) internal_session_id: AtomicU32,
-    pub(super) session_info: SessionInfo,
     pub(super) session_name: UAString,
     pub(super) application_description: ApplicationDescription,
     pub(super) request_timeout: Duration,
@@ -72,12 +74,14 @@ pub struct Session {
     pub(super) should_reconnect: AtomicBool,
     pub(super) auto_recreate_subscriptions: bool,
     pub(super) encoding_context: Arc<RwLock<ContextOwned>>,
+    data_type_loader: Mutex<Option<Arc<dyn TypeLoader>>>,
+    pub(super) failover: Option<Mutex<FailoverState>>,
 }
 
 impl Session {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
-        certificate_store: Arc<RwLock<CertificateStore>>,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
         session_info: SessionInfo,
         session_name: UAString,
         application_description: ApplicationDescription,
@@ -87,6 +91,7 @@ impl Session {
         session_id: Option<NodeId>,
         connector: Box<dyn Connector>,
         extra_type_loaders: Vec<Arc<dyn TypeLoader>>,
+        failover: Option<FailoverConfig>,
     ) -> (Arc<Self>, SessionEventLoop) {
         let auth_token: Arc<ArcSwap<NodeId>> = Arc::default();
         let (publish_limits_watch_tx, publish_limits_watch_rx) =
@@ -103,11 +108,12 @@ impl Session {
         }
 
         let encoding_context = Arc::new(RwLock::new(encoding_context));
+        let failover = failover.map(|f| Mutex::new(FailoverState::new(&session_info.endpoint, f)));
 
         let session = Arc::new(Session {
             channel: AsyncSecureChannel::new(
                 certificate_store.clone(),
-                session_info.clone(),
+                session_info,
                 session_retry_policy.clone(),
                 config.performance.ignore_clock_skew,
                 auth_token.clone(),
@@ -126,7 +132,6 @@ impl Session {
             state_watch_rx,
             state_watch_tx,
             session_id: Arc::new(ArcSwap::new(Arc::new(session_id.unwrap_or_default()))),
-            session_info,
             auth_token,
             session_name,
             application_description,
@@ -147,6 +152,8 @@ impl Session {
             should_reconnect: AtomicBool::new(true),
             auto_recreate_subscriptions: config.auto_recreate_subscriptions,
             encoding_context,
+            data_type_loader: Mutex::new(None),
+            failover,
         });
 
         (
@@ -293,6 +300,21 @@ impl Session {
         self.encoding_context.write().loaders_mut().add(type_loader);
     }
 
+    /// Register a [DynamicTypeLoader] for the given data type tree on the encoding
+    /// context, replacing the loader registered by any previous call to this method.
+    ///
+    /// You will typically get the tree from [Session::load_data_types].
+    pub fn set_data_type_tree(&self, type_tree: Arc<DataTypeTree>) {
+        let loader: Arc<dyn TypeLoader> = Arc::new(DynamicTypeLoader::new(type_tree));
+        let mut current = self.data_type_loader.lock();
+        let mut context = self.encoding_context.write();
+        if let Some(old) = current.take() {
+            context.loaders_mut().remove(&old);
+        }
+        context.loaders_mut().add(loader.clone());
+        *current = Some(loader);
+    }
+
     /// Get a reference to the encoding
     pub fn context(&self) -> Arc<RwLock<ContextOwned>> {
         self.channel.secure_channel.read().context_arc()
@@ -313,4 +335,28 @@ impl Session {
             )),
         )
     }
+
+    /// Create a builder for loading the data types defined on the server.
+    ///
+    /// See [DataTypeTreeBuilder] for details.
+    pub fn data_type_tree_builder(&self) -> DataTypeTreeBuilder<'_, DefaultRetryPolicy<'_>> {
+        DataTypeTreeBuilder::new(
+            self,
+            DefaultRetryPolicy::new(ExponentialBackoff::new(
+                Duration::from_secs(30),
+                Some(5),
+                Duration::from_millis(500),
+            )),
+        )
+    }
+
+    /// Load all data types defined on the server, and register a type loader
+    /// on the session, so that custom structures are decoded automatically
+    /// as [DynamicStructure](opcua_types::custom::DynamicStructure).
+    ///
+    /// This is a shorthand for
+    /// `session.data_type_tree_builder().load_and_register()`.
+    pub async fn load_data_types(&self) -> Result<Arc<DataTypeTree>, Error> {
+        self.data_type_tree_builder().load_and_register().await
+    }
 }
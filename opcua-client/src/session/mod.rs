@@ -3,6 +3,7 @@ mod connect;
 mod connection;
 mod event_loop;
 mod implementation;
+mod redundancy;
 mod request_builder;
 mod retry;
 mod services;
@@ -44,6 +45,7 @@ pub use connect::SessionConnectMode;
 pub use event_loop::{SessionActivity, SessionEventLoop, SessionPollResult};
 pub use implementation::Session;
 use log::{error, info};
+pub use redundancy::{FailoverConfig, FailoverMode, RedundantServerStatus};
 pub use request_builder::UARequest;
 pub use retry::{DefaultRetryPolicy, RequestRetryPolicy};
 pub use services::attributes::{
@@ -51,12 +53,14 @@ pub use services::attributes::{
 };
 pub use services::method::Call;
 pub use services::node_management::{AddNodes, AddReferences, DeleteNodes, DeleteReferences};
+pub use services::query::{QueryFirst, QueryNext};
 pub use services::session::{ActivateSession, Cancel, CloseSession, CreateSession};
 pub use services::subscriptions::{
     CreateMonitoredItems, CreateSubscription, DataChangeCallback, DeleteMonitoredItems,
     DeleteSubscriptions, EventCallback, ModifyMonitoredItems, ModifySubscription, MonitoredItem,
-    OnSubscriptionNotification, SetMonitoringMode, SetPublishingMode, SetTriggering, Subscription,
-    SubscriptionCallbacks, TransferSubscriptions,
+    MonitoredItemStream, NotificationStreams, OnSubscriptionNotification, OverflowPolicy,
+    SetMonitoringMode, SetPublishingMode, SetTriggering, StreamNotification, StreamOptions,
+    Subscription, SubscriptionCallbacks, SubscriptionStream, TransferSubscriptions,
 };
 pub use services::view::{
     Browse, BrowseNext, RegisterNodes, TranslateBrowsePaths, UnregisterNodes,
@@ -0,0 +1,490 @@
+use std::collections::VecDeque;
+
+use opcua_types::{
+    AttributeId, DataValue, EndpointDescription, QualifiedName, ReadValueId, RedundancySupport,
+    RedundantServerDataType, ServerState, UAString, VariableId, Variant,
+};
+
+use super::{session_debug, Session, SessionInfo};
+
+/// How the client fails over between the servers in a redundant server set.
+///
+/// See OPC UA Part 4 - Services 6.6.2 for a description of each mode.
+///
+/// `Hot` redundancy is not supported, since the client only keeps a connection to the
+/// active server, and does not monitor the backup servers through their own sessions.
+/// Servers reporting `Hot` redundancy support are handled with [`FailoverMode::Warm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverMode {
+    /// Only switch to a different server once the active server is unreachable.
+    /// Subscriptions are recreated on the new server.
+    Cold,
+    /// Also switch to a different server if the `ServiceLevel` of the active server
+    /// drops below the configured minimum. Subscriptions are recreated on the new server.
+    Warm,
+    /// Like [`FailoverMode::Warm`], but the servers mirror session and subscription state,
+    /// so the client reactivates the existing session on the new server and
+    /// transfers its subscriptions instead of recreating them.
+    HotAndMirrored,
+}
+
+impl FailoverMode {
+    /// Get the failover mode a client should use for a server reporting the
+    /// given [`RedundancySupport`].
+    pub fn from_redundancy_support(support: RedundancySupport) -> Self {
+        match support {
+            RedundancySupport::None | RedundancySupport::Cold => FailoverMode::Cold,
+            RedundancySupport::Warm | RedundancySupport::Hot => FailoverMode::Warm,
+            RedundancySupport::Transparent | RedundancySupport::HotAndMirrored => {
+                FailoverMode::HotAndMirrored
+            }
+        }
+    }
+}
+
+/// Configuration for failover between the servers in a redundant server set.
+#[derive(Debug, Clone)]
+pub struct FailoverConfig {
+    endpoints: Vec<EndpointDescription>,
+    mode: Option<FailoverMode>,
+    min_service_level: u8,
+}
+
+impl FailoverConfig {
+    /// Create a new failover configuration with the given endpoints, one for each
+    /// server in the redundant server set.
+    ///
+    /// The endpoint the session is built with is the server the session connects to first,
+    /// it does not need to be included in this list.
+    pub fn new(endpoints: Vec<EndpointDescription>) -> Self {
+        Self {
+            endpoints,
+            mode: None,
+            min_service_level: 200,
+        }
+    }
+
+    /// Set the failover mode. By default the mode is picked from the
+    /// `RedundancySupport` reported by the active server.
+    pub fn mode(mut self, mode: FailoverMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Set the minimum `ServiceLevel` of the active server. In warm and hot and mirrored modes,
+    /// if the service level drops below this, the session fails over to a
+    /// server that may be healthier. The default is 200, which is the lower bound of the
+    /// `Healthy` sub-range defined by the standard.
+    pub fn min_service_level(mut self, level: u8) -> Self {
+        self.min_service_level = level;
+        self
+    }
+}
+
+/// The last known status of a server in a redundant server set.
+#[derive(Debug, Clone)]
+pub struct RedundantServerStatus {
+    /// The endpoint used to connect to the server.
+    pub endpoint: EndpointDescription,
+    /// The last `ServiceLevel` reported for the server, if any.
+    pub service_level: Option<u8>,
+    /// The last `RedundancySupport` reported by the server, if any.
+    pub redundancy_support: Option<RedundancySupport>,
+    /// Whether this is the server the session is currently using.
+    pub active: bool,
+}
+
+#[derive(Debug)]
+struct RedundantServer {
+    endpoint: EndpointDescription,
+    service_level: Option<u8>,
+    redundancy_support: Option<RedundancySupport>,
+}
+
+/// Failover state of a session, tracking the health of each server in
+/// the redundant server set and the servers left to try when reconnecting.
+#[derive(Debug)]
+pub(crate) struct FailoverState {
+    servers: Vec<RedundantServer>,
+    active: usize,
+    mode: Option<FailoverMode>,
+    min_service_level: u8,
+    avoid_active: bool,
+    candidates: VecDeque<usize>,
+}
+
+impl FailoverState {
+    pub(crate) fn new(initial: &EndpointDescription, config: FailoverConfig) -> Self {
+        let mut servers = vec![RedundantServer::new(initial.clone())];
+        for endpoint in config.endpoints {
+            if !servers
+                .iter()
+                .any(|s| s.endpoint.endpoint_url == endpoint.endpoint_url)
+            {
+                servers.push(RedundantServer::new(endpoint));
+            }
+        }
+        Self {
+            servers,
+            active: 0,
+            mode: config.mode,
+            min_service_level: config.min_service_level,
+            avoid_active: false,
+            candidates: VecDeque::new(),
+        }
+    }
+
+    /// The configured failover mode, or the mode picked from the
+    /// redundancy support of the active server.
+    pub(crate) fn mode(&self) -> FailoverMode {
+        self.mode.unwrap_or_else(|| {
+            self.servers[self.active]
+                .redundancy_support
+                .map(FailoverMode::from_redundancy_support)
+                .unwrap_or(FailoverMode::Cold)
+        })
+    }
+
+    fn update_active(
+        &mut self,
+        service_level: Option<u8>,
+        redundancy_support: Option<RedundancySupport>,
+    ) {
+        let server = &mut self.servers[self.active];
+        if service_level.is_some() {
+            server.service_level = service_level;
+        }
+        if redundancy_support.is_some() {
+            server.redundancy_support = redundancy_support;
+        }
+    }
+
+    /// Update the service level of other servers from the `RedundantServerArray`
+    /// of the active server. Servers are matched on application URI, so this only
+    /// works for endpoints obtained from `GetEndpoints`.
+    fn update_redundant_servers(&mut self, redundant_servers: &[RedundantServerDataType]) {
+        for (idx, server) in self.servers.iter_mut().enumerate() {
+            if idx == self.active || server.endpoint.server.application_uri.is_null() {
+                continue;
+            }
+            if let Some(r) = redundant_servers
+                .iter()
+                .find(|r| r.server_id == server.endpoint.server.application_uri)
+            {
+                server.service_level = Some(if r.server_state == ServerState::Running {
+                    r.service_level
+                } else {
+                    0
+                });
+            }
+        }
+    }
+
+    fn rank(&self, idx: usize) -> u8 {
+        // Servers we know nothing about are assumed to be barely healthy.
+        self.servers[idx]
+            .service_level
+            .unwrap_or(self.min_service_level)
+    }
+
+    /// Whether the session should leave the active server for a healthier one.
+    pub(crate) fn should_fail_over(&self) -> bool {
+        if self.mode() == FailoverMode::Cold {
+            return false;
+        }
+        let Some(level) = self.servers[self.active].service_level else {
+            return false;
+        };
+        level < self.min_service_level
+            && (0..self.servers.len()).any(|idx| idx != self.active && self.rank(idx) > level)
+    }
+
+    /// Mark the active server as unavailable, so that the next round of
+    /// connection attempts tries the other servers first.
+    pub(crate) fn mark_active_failed(&mut self) {
+        self.avoid_active = true;
+    }
+
+    /// Start a new round of connection attempts, ordering servers by
+    /// their last known service level.
+    pub(crate) fn begin_round(&mut self) {
+        let mut others: Vec<_> = (0..self.servers.len())
+            .filter(|idx| *idx != self.active)
+            .collect();
+        others.sort_by_key(|idx| std::cmp::Reverse(self.rank(*idx)));
+        self.candidates = others.into();
+        if self.avoid_active {
+            self.candidates.push_back(self.active);
+        } else {
+            self.candidates.push_front(self.active);
+        }
+        self.avoid_active = false;
+    }
+
+    /// Get the next server to try in the current round.
+    pub(crate) fn next_candidate(&mut self) -> Option<usize> {
+        self.candidates.pop_front()
+    }
+
+    /// Whether there are servers left to try in the current round.
+    pub(crate) fn has_candidates(&self) -> bool {
+        !self.candidates.is_empty()
+    }
+
+    pub(crate) fn active(&self) -> usize {
+        self.active
+    }
+
+    pub(crate) fn endpoint(&self, idx: usize) -> &EndpointDescription {
+        &self.servers[idx].endpoint
+    }
+
+    /// Set the server the session is connected to, and end the current round.
+    pub(crate) fn set_active(&mut self, idx: usize) {
+        self.active = idx;
+        self.candidates.clear();
+    }
+
+    fn status(&self) -> Vec<RedundantServerStatus> {
+        self.servers
+            .iter()
+            .enumerate()
+            .map(|(idx, s)| RedundantServerStatus {
+                endpoint: s.endpoint.clone(),
+                service_level: s.service_level,
+                redundancy_support: s.redundancy_support,
+                active: idx == self.active,
+            })
+            .collect()
+    }
+}
+
+impl RedundantServer {
+    fn new(endpoint: EndpointDescription) -> Self {
+        Self {
+            endpoint,
+            service_level: None,
+            redundancy_support: None,
+        }
+    }
+}
+
+fn read_value_id(variable: VariableId) -> ReadValueId {
+    ReadValueId {
+        node_id: variable.into(),
+        attribute_id: AttributeId::Value as u32,
+        index_range: Default::default(),
+        data_encoding: QualifiedName::null(),
+    }
+}
+
+impl Session {
+    /// Get the last known status of each server in the redundant server set,
+    /// or `None` if failover is not configured for this session.
+    pub fn redundancy_status(&self) -> Option<Vec<RedundantServerStatus>> {
+        self.failover.as_ref().map(|f| f.lock().status())
+    }
+
+    /// Get the URL of the endpoint the session currently connects to.
+    pub fn endpoint_url(&self) -> UAString {
+        self.channel.session_info().endpoint.endpoint_url.clone()
+    }
+
+    /// Nodes read together with the server state during keep-alive, when
+    /// failover is configured.
+    pub(super) fn redundancy_read_ids(&self) -> Vec<ReadValueId> {
+        if self.failover.is_none() {
+            return Vec::new();
+        }
+        vec![
+            read_value_id(VariableId::Server_ServiceLevel),
+            read_value_id(VariableId::Server_ServerRedundancy_RedundancySupport),
+            read_value_id(VariableId::Server_ServerRedundancy_RedundantServerArray),
+        ]
+    }
+
+    /// Update the failover state from the values read by [`Session::redundancy_read_ids`].
+    pub(super) fn update_redundancy_status(&self, values: &[DataValue]) {
+        let Some(failover) = &self.failover else {
+            return;
+        };
+        let good = |idx: usize| {
+            values
+                .get(idx)
+                .filter(|v| v.status.is_none_or(|s| s.is_good()))
+                .and_then(|v| v.value.clone())
+        };
+        let service_level = good(0).and_then(|v| v.try_cast_to::<u8>().ok());
+        let redundancy_support = good(1)
+            .and_then(|v| v.try_cast_to::<i32>().ok())
+            .and_then(|v| RedundancySupport::try_from(v).ok());
+        let redundant_servers: Vec<RedundantServerDataType> = match good(2) {
+            Some(Variant::Array(arr)) => arr
+                .values
+                .into_iter()
+                .filter_map(|v| match v {
+                    Variant::ExtensionObject(o) => o.into_inner_as().map(|r| *r),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        session_debug!(
+            self,
+            "Service level {service_level:?}, redundancy support {redundancy_support:?}"
+        );
+
+        let mut failover = failover.lock();
+        failover.update_active(service_level, redundancy_support);
+        failover.update_redundant_servers(&redundant_servers);
+    }
+
+    /// Whether the session should leave the active server for a healthier one.
+    pub(super) fn should_fail_over(&self) -> bool {
+        self.failover
+            .as_ref()
+            .is_some_and(|f| f.lock().should_fail_over())
+    }
+
+    /// Whether the session should only close the channel, and not the session,
+    /// when leaving the active server.
+    pub(super) fn failover_is_mirrored(&self) -> bool {
+        self.failover
+            .as_ref()
+            .is_some_and(|f| f.lock().mode() == FailoverMode::HotAndMirrored)
+    }
+
+    /// Mark the active server as unavailable.
+    pub(super) fn mark_active_server_failed(&self) {
+        if let Some(failover) = &self.failover {
+            failover.lock().mark_active_failed();
+        }
+    }
+
+    /// Start a new round of connection attempts over the redundant server set.
+    pub(super) fn begin_failover_round(&self) {
+        if let Some(failover) = &self.failover {
+            failover.lock().begin_round();
+        }
+    }
+
+    /// Whether there are servers left to try before the current round
+    /// of connection attempts is exhausted.
+    pub(super) fn has_failover_candidates(&self) -> bool {
+        self.failover
+            .as_ref()
+            .is_some_and(|f| f.lock().has_candidates())
+    }
+
+    /// Pick the next server to connect to, and point the channel at it.
+    /// Returns the index of the server, or `None` if failover is not configured.
+    pub(super) fn prepare_failover_attempt(&self) -> Option<usize> {
+        let (idx, endpoint, mirrored) = {
+            let mut failover = self.failover.as_ref()?.lock();
+            if !failover.has_candidates() {
+                failover.begin_round();
+            }
+            let idx = failover.next_candidate()?;
+            (
+                idx,
+                failover.endpoint(idx).clone(),
+                failover.mode() == FailoverMode::HotAndMirrored,
+            )
+        };
+        self.switch_server(endpoint, mirrored);
+        Some(idx)
+    }
+
+    /// Mark the server the session connected to as the active server,
+    /// returning `true` if this is a different server than before.
+    pub(super) fn finish_failover_attempt(&self, idx: usize) -> bool {
+        let Some(failover) = &self.failover else {
+            return false;
+        };
+        let mut failover = failover.lock();
+        let changed = failover.active() != idx;
+        failover.set_active(idx);
+        changed
+    }
+
+    /// Switch the channel to the given server in the redundant server set.
+    /// Unless servers mirror session state, the session on the old server is
+    /// forgotten, so that a new session is created and subscriptions are recreated.
+    fn switch_server(&self, endpoint: EndpointDescription, mirrored: bool) {
+        let info = self.channel.session_info();
+        if info.endpoint.endpoint_url == endpoint.endpoint_url {
+            return;
+        }
+        session_debug!(self, "Failing over to {}", endpoint.endpoint_url);
+        self.channel.set_session_info(SessionInfo {
+            endpoint,
+            user_identity_token: info.user_identity_token.clone(),
+            preferred_locales: info.preferred_locales.clone(),
+        });
+        if !mirrored {
+            self.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opcua_types::{EndpointDescription, RedundancySupport};
+
+    use super::{FailoverConfig, FailoverMode, FailoverState};
+
+    fn endpoint(url: &str) -> EndpointDescription {
+        EndpointDescription::from(url)
+    }
+
+    fn state() -> FailoverState {
+        FailoverState::new(
+            &endpoint("opc.tcp://a:4840"),
+            FailoverConfig::new(vec![
+                endpoint("opc.tcp://a:4840"),
+                endpoint("opc.tcp://b:4840"),
+                endpoint("opc.tcp://c:4840"),
+            ]),
+        )
+    }
+
+    fn round(state: &mut FailoverState) -> Vec<usize> {
+        state.begin_round();
+        std::iter::from_fn(|| state.next_candidate()).collect()
+    }
+
+    #[test]
+    fn candidate_order() {
+        let mut state = state();
+        assert_eq!(round(&mut state), vec![0, 1, 2]);
+
+        state.servers[2].service_level = Some(250);
+        state.servers[1].service_level = Some(10);
+        assert_eq!(round(&mut state), vec![0, 2, 1]);
+
+        state.mark_active_failed();
+        assert_eq!(round(&mut state), vec![2, 1, 0]);
+        // Only the next round avoids the failed server.
+        assert_eq!(round(&mut state), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn fail_over_on_degraded_service_level() {
+        let mut state = state();
+        state.update_active(Some(50), Some(RedundancySupport::Cold));
+        assert_eq!(state.mode(), FailoverMode::Cold);
+        assert!(!state.should_fail_over());
+
+        state.update_active(None, Some(RedundancySupport::Hot));
+        assert_eq!(state.mode(), FailoverMode::Warm);
+        assert!(state.should_fail_over());
+
+        // No other server is known to be better.
+        state.servers[1].service_level = Some(20);
+        state.servers[2].service_level = Some(50);
+        assert!(!state.should_fail_over());
+
+        state.update_active(Some(255), None);
+        assert!(!state.should_fail_over());
+    }
+}
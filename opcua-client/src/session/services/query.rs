@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use crate::{
+    session::{
+        process_service_result, process_unexpected_response,
+        request_builder::{builder_base, builder_debug, builder_error, RequestHeaderBuilder},
+    },
+    Session, UARequest,
+};
+use opcua_core::ResponseMessage;
+use opcua_types::{
+    ByteString, ContentFilter, IntegerId, NodeId, NodeTypeDescription, QueryFirstRequest,
+    QueryFirstResponse, QueryNextRequest, QueryNextResponse, StatusCode, ViewDescription,
+};
+
+#[derive(Debug, Clone)]
+/// Search the address space for nodes matching a set of type definitions and a
+/// content filter by sending a [`QueryFirstRequest`] to the server.
+///
+/// See OPC UA Part 4 - Services 5.9.3 for complete description of the service and error responses.
+pub struct QueryFirst {
+    view: ViewDescription,
+    node_types: Vec<NodeTypeDescription>,
+    filter: ContentFilter,
+    max_data_sets_to_return: u32,
+    max_references_to_return: u32,
+
+    header: RequestHeaderBuilder,
+}
+
+builder_base!(QueryFirst);
+
+impl QueryFirst {
+    /// Construct a new call to the `QueryFirst` service.
+    pub fn new(session: &Session) -> Self {
+        Self {
+            view: ViewDescription::default(),
+            node_types: Vec::new(),
+            filter: ContentFilter::default(),
+            max_data_sets_to_return: 0,
+            max_references_to_return: 0,
+
+            header: RequestHeaderBuilder::new_from_session(session),
+        }
+    }
+
+    /// Construct a new call to the `QueryFirst` service, setting header parameters manually.
+    pub fn new_manual(
+        session_id: u32,
+        timeout: Duration,
+        auth_token: NodeId,
+        request_handle: IntegerId,
+    ) -> Self {
+        Self {
+            view: ViewDescription::default(),
+            node_types: Vec::new(),
+            filter: ContentFilter::default(),
+            max_data_sets_to_return: 0,
+            max_references_to_return: 0,
+
+            header: RequestHeaderBuilder::new(session_id, timeout, auth_token, request_handle),
+        }
+    }
+
+    /// Set the view to query.
+    pub fn view(mut self, view: ViewDescription) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Set node types to query, overwriting any that were set previously.
+    pub fn node_types(mut self, node_types: Vec<NodeTypeDescription>) -> Self {
+        self.node_types = node_types;
+        self
+    }
+
+    /// Add a node type to query.
+    pub fn node_type(mut self, node_type: NodeTypeDescription) -> Self {
+        self.node_types.push(node_type);
+        self
+    }
+
+    /// Set the content filter that returned nodes must match.
+    pub fn filter(mut self, filter: ContentFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Set the maximum number of data sets to return. The default is zero,
+    /// meaning server-defined.
+    pub fn max_data_sets_to_return(mut self, max_data_sets_to_return: u32) -> Self {
+        self.max_data_sets_to_return = max_data_sets_to_return;
+        self
+    }
+
+    /// Set the maximum number of references to return. The default is zero,
+    /// meaning server-defined.
+    pub fn max_references_to_return(mut self, max_references_to_return: u32) -> Self {
+        self.max_references_to_return = max_references_to_return;
+        self
+    }
+}
+
+impl UARequest for QueryFirst {
+    type Out = QueryFirstResponse;
+
+    async fn send<'a>(self, channel: &'a crate::AsyncSecureChannel) -> Result<Self::Out, StatusCode>
+    where
+        Self: 'a,
+    {
+        if self.node_types.is_empty() {
+            builder_error!(self, "query_first was not supplied with any node types");
+            return Err(StatusCode::BadNothingToDo);
+        }
+        let request = QueryFirstRequest {
+            request_header: self.header.header,
+            view: self.view,
+            node_types: Some(self.node_types),
+            filter: self.filter,
+            max_data_sets_to_return: self.max_data_sets_to_return,
+            max_references_to_return: self.max_references_to_return,
+        };
+        let response = channel.send(request, self.header.timeout).await?;
+        if let ResponseMessage::QueryFirst(response) = response {
+            builder_debug!(self, "query_first, success");
+            process_service_result(&response.response_header)?;
+            Ok(*response)
+        } else {
+            builder_error!(self, "query_first failed");
+            Err(process_unexpected_response(response))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Continue a query by sending the continuation point returned from `QueryFirst`
+/// or a previous `QueryNext` in a [`QueryNextRequest`] to the server.
+///
+/// See OPC UA Part 4 - Services 5.9.4 for complete description of the service and error responses.
+pub struct QueryNext {
+    continuation_point: ByteString,
+    release_continuation_point: bool,
+
+    header: RequestHeaderBuilder,
+}
+
+builder_base!(QueryNext);
+
+impl QueryNext {
+    /// Construct a new call to the `QueryNext` service.
+    pub fn new(session: &Session) -> Self {
+        Self {
+            continuation_point: ByteString::null(),
+            release_continuation_point: false,
+
+            header: RequestHeaderBuilder::new_from_session(session),
+        }
+    }
+
+    /// Construct a new call to the `QueryNext` service, setting header parameters manually.
+    pub fn new_manual(
+        session_id: u32,
+        timeout: Duration,
+        auth_token: NodeId,
+        request_handle: IntegerId,
+    ) -> Self {
+        Self {
+            continuation_point: ByteString::null(),
+            release_continuation_point: false,
+
+            header: RequestHeaderBuilder::new(session_id, timeout, auth_token, request_handle),
+        }
+    }
+
+    /// Set the continuation point to continue from.
+    pub fn continuation_point(mut self, continuation_point: ByteString) -> Self {
+        self.continuation_point = continuation_point;
+        self
+    }
+
+    /// Set release continuation point. Default is false, if this is true,
+    /// the continuation point will be released and no results will be returned.
+    pub fn release_continuation_point(mut self, release_continuation_point: bool) -> Self {
+        self.release_continuation_point = release_continuation_point;
+        self
+    }
+}
+
+impl UARequest for QueryNext {
+    type Out = QueryNextResponse;
+
+    async fn send<'a>(self, channel: &'a crate::AsyncSecureChannel) -> Result<Self::Out, StatusCode>
+    where
+        Self: 'a,
+    {
+        if self.continuation_point.is_null() {
+            builder_error!(
+                self,
+                "query_next was not supplied with a continuation point"
+            );
+            return Err(StatusCode::BadNothingToDo);
+        }
+        let request = QueryNextRequest {
+            request_header: self.header.header,
+            release_continuation_point: self.release_continuation_point,
+            continuation_point: self.continuation_point,
+        };
+        let response = channel.send(request, self.header.timeout).await?;
+        if let ResponseMessage::QueryNext(response) = response {
+            builder_debug!(self, "query_next, success");
+            process_service_result(&response.response_header)?;
+            Ok(*response)
+        } else {
+            builder_error!(self, "query_next failed");
+            Err(process_unexpected_response(response))
+        }
+    }
+}
+
+impl Session {
+    /// Search the address space for nodes matching a set of type definitions and a
+    /// content filter by sending a [`QueryFirstRequest`] to the server.
+    ///
+    /// See OPC UA Part 4 - Services 5.9.3 for complete description of the service and error responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_types` - A list of [`NodeTypeDescription`] describing the types of node to return,
+    ///   and which values to return for each.
+    /// * `filter` - A [`ContentFilter`] nodes must match to be returned.
+    /// * `max_data_sets_to_return` - Maximum number of data sets to return, zero means server-defined.
+    /// * `view` - Optional view to restrict the query to.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(QueryFirstResponse)` - The query result. This may contain a continuation point,
+    ///   for use with `query_next()`, as well as parsing and filter results if the query was invalid.
+    /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
+    ///
+    pub async fn query_first(
+        &self,
+        node_types: &[NodeTypeDescription],
+        filter: ContentFilter,
+        max_data_sets_to_return: u32,
+        view: Option<ViewDescription>,
+    ) -> Result<QueryFirstResponse, StatusCode> {
+        QueryFirst::new(self)
+            .node_types(node_types.to_vec())
+            .filter(filter)
+            .max_data_sets_to_return(max_data_sets_to_return)
+            .view(view.unwrap_or_default())
+            .send(&self.channel)
+            .await
+    }
+
+    /// Continue a query by sending a continuation point in a [`QueryNextRequest`] to the server.
+    ///
+    /// See OPC UA Part 4 - Services 5.9.4 for complete description of the service and error responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `release_continuation_point` - Flag indicating if the continuation point should be released by the server
+    /// * `continuation_point` - The continuation point returned from `query_first()` or `query_next()`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(QueryNextResponse)` - The next set of results, and a revised continuation point
+    ///   if there are more.
+    /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
+    ///
+    pub async fn query_next(
+        &self,
+        release_continuation_point: bool,
+        continuation_point: ByteString,
+    ) -> Result<QueryNextResponse, StatusCode> {
+        QueryNext::new(self)
+            .continuation_point(continuation_point)
+            .release_continuation_point(release_continuation_point)
+            .send(&self.channel)
+            .await
+    }
+}
@@ -7,8 +7,10 @@ use opcua_core::{
     trace_read_lock, trace_write_lock, ResponseMessage,
 };
 use opcua_crypto::{
-    self, certificate_store::CertificateStore, user_identity::make_user_name_identity_token, PKey,
-    SecurityPolicy,
+    self,
+    certificate_store::{CertificateStore, FileSystemCertificateStore},
+    user_identity::make_user_name_identity_token,
+    PrivateKey, SecurityPolicy,
 };
 use opcua_types::{
     ActivateSessionRequest, ActivateSessionResponse, AnonymousIdentityToken,
@@ -17,7 +19,6 @@ use opcua_types::{
     ExtensionObject, IntegerId, NodeId, SignatureData, SignedSoftwareCertificate, StatusCode,
     UAString, UserTokenType, X509IdentityToken,
 };
-use rsa::RsaPrivateKey;
 
 use crate::{
     session::{
@@ -47,7 +48,7 @@ pub struct CreateSession<'a> {
     client_certificate: ByteString,
     session_timeout: f64,
     max_response_message_size: u32,
-    certificate_store: &'a RwLock<CertificateStore>,
+    certificate_store: &'a RwLock<dyn CertificateStore>,
     endpoint: &'a EndpointDescription,
 
     header: RequestHeaderBuilder,
@@ -59,9 +60,9 @@ impl<'a> CreateSession<'a> {
     /// Create a new `CreateSession` request on the given session.
     ///
     /// Crate private since there is no way to safely use this.
-    pub(crate) fn new(session: &'a Session) -> Self {
+    pub(crate) fn new(session: &'a Session, endpoint: &'a EndpointDescription) -> Self {
         Self {
-            endpoint_url: session.session_info.endpoint.endpoint_url.clone(),
+            endpoint_url: endpoint.endpoint_url.clone(),
             server_uri: UAString::null(),
             client_description: session.application_description.clone(),
             session_name: session.session_name.clone(),
@@ -73,7 +74,7 @@ impl<'a> CreateSession<'a> {
                     .map(|m| m.as_byte_string())
                     .unwrap_or_default()
             },
-            endpoint: &session.session_info.endpoint,
+            endpoint,
             certificate_store: &session.certificate_store,
             session_timeout: session.session_timeout,
             max_response_message_size: 0,
@@ -83,7 +84,7 @@ impl<'a> CreateSession<'a> {
 
     /// Create a new `CreateSession` request with the given data.
     pub fn new_manual(
-        certificate_store: &'a RwLock<CertificateStore>,
+        certificate_store: &'a RwLock<dyn CertificateStore>,
         endpoint: &'a EndpointDescription,
         session_id: u32,
         timeout: Duration,
@@ -135,7 +136,10 @@ impl<'a> CreateSession<'a> {
     }
 
     /// Load the client certificate from the certificate store.
-    pub fn client_cert_from_store(mut self, certificate_store: &RwLock<CertificateStore>) -> Self {
+    pub fn client_cert_from_store(
+        mut self,
+        certificate_store: &RwLock<dyn CertificateStore>,
+    ) -> Self {
         let cert_store = trace_read_lock!(certificate_store);
         self.client_certificate = cert_store
             .read_own_cert()
@@ -228,7 +232,7 @@ impl<'b> UARequest for CreateSession<'b> {
 /// is handled automatically as part of connect/reconnect logic.
 pub struct ActivateSession {
     identity_token: IdentityToken,
-    private_key: Option<PKey<RsaPrivateKey>>,
+    private_key: Option<PrivateKey>,
     locale_ids: Vec<UAString>,
     client_software_certificates: Vec<SignedSoftwareCertificate>,
     endpoint: EndpointDescription,
@@ -243,20 +247,20 @@ impl ActivateSession {
     ///
     /// Crate private since there is no way to safely use this.
     pub(crate) fn new(session: &Session) -> Self {
+        let session_info = session.channel.session_info();
         Self {
-            identity_token: session.session_info.user_identity_token.clone(),
+            identity_token: session_info.user_identity_token.clone(),
             private_key: {
                 let cert_store = trace_read_lock!(session.certificate_store);
                 cert_store.read_own_pkey().ok()
             },
-            locale_ids: session
-                .session_info
+            locale_ids: session_info
                 .preferred_locales
                 .iter()
                 .map(UAString::from)
                 .collect(),
             client_software_certificates: Vec::new(),
-            endpoint: session.session_info.endpoint.clone(),
+            endpoint: session_info.endpoint.clone(),
             header: RequestHeaderBuilder::new_from_session(session),
         }
     }
@@ -286,7 +290,7 @@ impl ActivateSession {
     }
 
     /// Set the client private key.
-    pub fn private_key(mut self, private_key: PKey<RsaPrivateKey>) -> Self {
+    pub fn private_key(mut self, private_key: PrivateKey) -> Self {
         self.private_key = Some(private_key);
         self
     }
@@ -379,22 +383,24 @@ impl ActivateSession {
                     error!("Cannot create an X509IdentityToken because the remote server has no cert with which to create a signature");
                     return Err(StatusCode::BadCertificateInvalid);
                 };
-                let certificate_data = CertificateStore::read_cert(cert_path).map_err(|e| {
-                    error!(
-                        "Certificate cannot be loaded from path {}, error = {}",
-                        cert_path.to_str().unwrap(),
-                        e
-                    );
-                    StatusCode::BadSecurityPolicyRejected
-                })?;
-                let private_key = CertificateStore::read_pkey(private_key_path).map_err(|e| {
-                    error!(
-                        "Private key cannot be loaded from path {}, error = {}",
-                        private_key_path.to_str().unwrap(),
-                        e
-                    );
-                    StatusCode::BadSecurityPolicyRejected
-                })?;
+                let certificate_data =
+                    FileSystemCertificateStore::read_cert(cert_path).map_err(|e| {
+                        error!(
+                            "Certificate cannot be loaded from path {}, error = {}",
+                            cert_path.to_str().unwrap(),
+                            e
+                        );
+                        StatusCode::BadSecurityPolicyRejected
+                    })?;
+                let private_key =
+                    FileSystemCertificateStore::read_pkey(private_key_path).map_err(|e| {
+                        error!(
+                            "Private key cannot be loaded from path {}, error = {}",
+                            private_key_path.to_str().unwrap(),
+                            e
+                        );
+                        StatusCode::BadSecurityPolicyRejected
+                    })?;
                 let user_token_signature = opcua_crypto::create_signature_data(
                     &private_key,
                     security_policy,
@@ -632,7 +638,10 @@ impl Session {
     /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
     ///
     pub(crate) async fn create_session(&self) -> Result<NodeId, StatusCode> {
-        let response = CreateSession::new(self).send(&self.channel).await?;
+        let session_info = self.channel.session_info();
+        let response = CreateSession::new(self, &session_info.endpoint)
+            .send(&self.channel)
+            .await?;
 
         let session_id = {
             self.session_id.store(Arc::new(response.session_id.clone()));
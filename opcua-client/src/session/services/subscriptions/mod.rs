@@ -1,16 +1,18 @@
 pub mod event_loop;
 mod service;
 pub mod state;
+mod stream;
 
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, HashSet},
     time::Duration,
 };
 
+use log::warn;
 use opcua_types::{
     match_extension_object_owned, DataChangeNotification, DataValue, EventNotificationList,
     ExtensionObject, MonitoringMode, NotificationMessage, ReadValueId, StatusChangeNotification,
-    Variant,
+    StatusCode, Variant,
 };
 
 pub use service::{
@@ -18,6 +20,10 @@ pub use service::{
     ModifyMonitoredItems, ModifySubscription, SetMonitoringMode, SetPublishingMode, SetTriggering,
     TransferSubscriptions,
 };
+pub use stream::{
+    MonitoredItemStream, NotificationStreams, OverflowPolicy, StreamNotification, StreamOptions,
+    SubscriptionStream,
+};
 
 pub(crate) struct CreateMonitoredItem {
     pub id: u32,
@@ -40,6 +46,11 @@ pub(crate) struct ModifyMonitoredItem {
 /// You may implement this on your own struct, or simply use [SubscriptionCallbacks]
 /// for a simple collection of closures.
 pub trait OnSubscriptionNotification: Send + Sync {
+    /// Called when the subscription has been created on the server, and again if it is
+    /// recreated with a new ID, for example after a reconnect.
+    #[allow(unused)]
+    fn on_subscription_created(&mut self, subscription_id: u32) {}
+
     /// Called when a subscription changes state on the server.
     #[allow(unused)]
     fn on_subscription_status_change(&mut self, notification: StatusChangeNotification) {}
@@ -51,6 +62,14 @@ pub trait OnSubscriptionNotification: Send + Sync {
     /// Called for each received event.
     #[allow(unused)]
     fn on_event(&mut self, event_fields: Option<Vec<Variant>>, item: &MonitoredItem) {}
+
+    /// Called when a notification message is missing from the sequence, and could not be
+    /// republished by the server. `status` is typically `BadMessageNotAvailable`.
+    ///
+    /// This is called in sequence order, so any notifications from before the lost message
+    /// have already been delivered.
+    #[allow(unused)]
+    fn on_notification_lost(&mut self, sequence_number: u32, status: StatusCode) {}
 }
 
 type StatusChangeCallbackFun = dyn FnMut(StatusChangeNotification) + Send + Sync;
@@ -268,9 +287,55 @@ pub struct Subscription {
     /// A map of client handle to monitored item id
     client_handles: HashMap<u32, u32>,
 
+    /// Sequence number of the last notification message delivered, if any.
+    last_sequence_number: Option<u32>,
+    /// Sequence number of the last keep-alive message. A keep-alive carries the sequence
+    /// number of the next data message, but some servers consume it for the keep-alive
+    /// itself, which shows as the number not being available for republishing.
+    keep_alive_sequence_number: Option<u32>,
+    /// Messages that cannot be delivered until earlier missing messages are resolved.
+    pending_messages: HashMap<u32, PendingMessage>,
+    /// Sequence numbers with a republish request in flight.
+    republishing: HashSet<u32>,
+
     callback: Box<dyn OnSubscriptionNotification>,
 }
 
+enum PendingMessage {
+    Received(NotificationMessage),
+    KeepAlive,
+    Lost(StatusCode),
+}
+
+/// Get the sequence number following `sequence_number`. Sequence numbers wrap around to 1.
+fn next_sequence_number(sequence_number: u32) -> u32 {
+    if sequence_number == u32::MAX {
+        1
+    } else {
+        sequence_number + 1
+    }
+}
+
+/// Get the sequence number preceding `sequence_number`. Sequence numbers wrap around to 1.
+fn previous_sequence_number(sequence_number: u32) -> u32 {
+    if sequence_number <= 1 {
+        u32::MAX
+    } else {
+        sequence_number - 1
+    }
+}
+
+/// Get the number of steps from `from` to `to`, or `None` if `to` is not after `from`.
+fn sequence_distance(from: u32, to: u32) -> Option<u32> {
+    let distance = if to < from {
+        // Skip 0 when wrapping around.
+        to.wrapping_sub(from).wrapping_sub(1)
+    } else {
+        to - from
+    };
+    (distance > 0 && distance < u32::MAX / 2).then_some(distance)
+}
+
 impl Subscription {
     /// Creates a new subscription using the supplied parameters and the supplied data change callback.
     #[allow(clippy::too_many_arguments)]
@@ -282,8 +347,9 @@ impl Subscription {
         max_notifications_per_publish: u32,
         priority: u8,
         publishing_enabled: bool,
-        status_change_callback: Box<dyn OnSubscriptionNotification>,
+        mut status_change_callback: Box<dyn OnSubscriptionNotification>,
     ) -> Subscription {
+        status_change_callback.on_subscription_created(subscription_id);
         Subscription {
             subscription_id,
             publishing_interval,
@@ -294,6 +360,10 @@ impl Subscription {
             priority,
             monitored_items: HashMap::new(),
             client_handles: HashMap::new(),
+            last_sequence_number: None,
+            keep_alive_sequence_number: None,
+            pending_messages: HashMap::new(),
+            republishing: HashSet::new(),
             callback: status_change_callback,
         }
     }
@@ -420,6 +490,157 @@ impl Subscription {
         }
     }
 
+    /// Largest gap in sequence numbers the subscription will try to recover.
+    /// Larger gaps are assumed to be caused by a server restart, and are skipped.
+    const MAX_SEQUENCE_GAP: u32 = 1000;
+
+    /// Handle a notification message from a publish response, delivering it, and any
+    /// messages it was waiting for, in sequence order.
+    ///
+    /// Returns a list of missing sequence numbers that should be republished.
+    pub(crate) fn on_publish_response(
+        &mut self,
+        notification: NotificationMessage,
+        available_sequence_numbers: &[u32],
+    ) -> Vec<u32> {
+        let sequence_number = notification.sequence_number;
+        let is_keep_alive = notification
+            .notification_data
+            .as_ref()
+            .is_none_or(|d| d.is_empty());
+
+        // A keep-alive means the data message with its sequence number has not been
+        // sent yet, so everything up to the previous number should have been received.
+        let expected = if is_keep_alive {
+            previous_sequence_number(sequence_number)
+        } else {
+            sequence_number
+        };
+
+        let Some(last) = self.last_sequence_number else {
+            // First message, nothing to compare against.
+            self.last_sequence_number = Some(expected);
+            if is_keep_alive {
+                self.keep_alive_sequence_number = Some(sequence_number);
+            } else {
+                self.on_notification(notification);
+            }
+            return Vec::new();
+        };
+
+        let Some(distance) = sequence_distance(last, expected) else {
+            // Already delivered, for example because it was republished.
+            if is_keep_alive {
+                self.keep_alive_sequence_number = Some(sequence_number);
+            }
+            return Vec::new();
+        };
+
+        if distance > Self::MAX_SEQUENCE_GAP {
+            warn!(
+                "Subscription {} skipped from sequence number {} to {}, notifications may have been lost",
+                self.subscription_id, last, sequence_number
+            );
+            self.pending_messages.clear();
+            self.last_sequence_number = Some(expected);
+            if is_keep_alive {
+                self.keep_alive_sequence_number = Some(sequence_number);
+            } else {
+                self.keep_alive_sequence_number = None;
+                self.on_notification(notification);
+            }
+            return Vec::new();
+        }
+
+        if !is_keep_alive {
+            match self.pending_messages.get(&sequence_number) {
+                Some(PendingMessage::Received(_)) => (),
+                _ => {
+                    self.pending_messages
+                        .insert(sequence_number, PendingMessage::Received(notification));
+                }
+            }
+        }
+
+        let mut missing = Vec::new();
+        let mut next = next_sequence_number(last);
+        let end = next_sequence_number(expected);
+        while next != end {
+            if !self.pending_messages.contains_key(&next) && !self.republishing.contains(&next) {
+                if available_sequence_numbers.contains(&next) {
+                    self.republishing.insert(next);
+                    missing.push(next);
+                } else if self.keep_alive_sequence_number == Some(next) {
+                    // The server used this sequence number for the keep-alive.
+                    self.pending_messages
+                        .insert(next, PendingMessage::KeepAlive);
+                } else {
+                    self.pending_messages.insert(
+                        next,
+                        PendingMessage::Lost(StatusCode::BadMessageNotAvailable),
+                    );
+                }
+            }
+            next = next_sequence_number(next);
+        }
+        if is_keep_alive {
+            self.keep_alive_sequence_number = Some(sequence_number);
+        }
+
+        self.deliver_pending();
+        missing
+    }
+
+    /// Handle the result of republishing a missing notification message.
+    pub(crate) fn on_republish_result(
+        &mut self,
+        sequence_number: u32,
+        result: Result<NotificationMessage, StatusCode>,
+    ) {
+        self.republishing.remove(&sequence_number);
+        let is_pending = self
+            .last_sequence_number
+            .is_some_and(|last| sequence_distance(last, sequence_number).is_some());
+        if !is_pending || self.pending_messages.contains_key(&sequence_number) {
+            // Received in the meantime.
+            return;
+        }
+        let message = match result {
+            Ok(n) if n.notification_data.as_ref().is_none_or(|d| d.is_empty()) => {
+                PendingMessage::KeepAlive
+            }
+            Ok(n) => PendingMessage::Received(n),
+            Err(e) => PendingMessage::Lost(e),
+        };
+        self.pending_messages.insert(sequence_number, message);
+        self.deliver_pending();
+    }
+
+    fn deliver_pending(&mut self) {
+        let Some(mut last) = self.last_sequence_number else {
+            return;
+        };
+        loop {
+            let next = next_sequence_number(last);
+            let Some(message) = self.pending_messages.remove(&next) else {
+                break;
+            };
+            last = next;
+            match message {
+                PendingMessage::Received(n) => self.on_notification(n),
+                PendingMessage::KeepAlive => (),
+                PendingMessage::Lost(status) => {
+                    warn!(
+                        "Notification message {} on subscription {} was lost: {}",
+                        next, self.subscription_id, status
+                    );
+                    self.callback.on_notification_lost(next, status);
+                }
+            }
+        }
+        self.last_sequence_number = Some(last);
+    }
+
     pub(crate) fn on_notification(&mut self, notification: NotificationMessage) {
         let Some(notifications) = notification.notification_data else {
             return;
@@ -501,3 +722,206 @@ impl PublishLimits {
             * (self.min_publish_requests);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use opcua_core::sync::Mutex;
+    use opcua_types::{
+        DataValue, DateTime, MonitoredItemNotification, NotificationMessage, StatusCode,
+    };
+
+    use super::{MonitoredItem, OnSubscriptionNotification, Subscription};
+
+    type Recorded = Arc<Mutex<Vec<(u32, StatusCode)>>>;
+
+    /// Records delivered sequence numbers, with the status for lost messages.
+    struct Recorder(Recorded);
+
+    impl OnSubscriptionNotification for Recorder {
+        fn on_data_value(&mut self, notification: DataValue, _item: &MonitoredItem) {
+            let seq: i32 = notification.value.unwrap().try_cast_to().unwrap();
+            self.0.lock().push((seq as u32, StatusCode::Good));
+        }
+
+        fn on_notification_lost(&mut self, sequence_number: u32, status: StatusCode) {
+            self.0.lock().push((sequence_number, status));
+        }
+    }
+
+    fn subscription() -> (Subscription, Recorded) {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let mut sub = Subscription::new(
+            1,
+            Duration::from_millis(100),
+            100,
+            20,
+            0,
+            0,
+            true,
+            Box::new(Recorder(recorded.clone())),
+        );
+        sub.insert_existing_monitored_item(MonitoredItem::new(1));
+        (sub, recorded)
+    }
+
+    fn message(sequence_number: u32) -> NotificationMessage {
+        NotificationMessage::data_change(
+            sequence_number,
+            DateTime::now(),
+            vec![MonitoredItemNotification {
+                client_handle: 1,
+                value: DataValue::new_now(sequence_number as i32),
+            }],
+            Vec::new(),
+        )
+    }
+
+    fn keep_alive(sequence_number: u32) -> NotificationMessage {
+        NotificationMessage::keep_alive(sequence_number, DateTime::now())
+    }
+
+    fn delivered(recorded: &Mutex<Vec<(u32, StatusCode)>>) -> Vec<(u32, StatusCode)> {
+        std::mem::take(&mut *recorded.lock())
+    }
+
+    #[test]
+    fn sequential_messages() {
+        let (mut sub, recorded) = subscription();
+        for i in 1..=3 {
+            assert!(sub.on_publish_response(message(i), &[]).is_empty());
+        }
+        // Duplicates are ignored.
+        assert!(sub.on_publish_response(message(2), &[]).is_empty());
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (1, StatusCode::Good),
+                (2, StatusCode::Good),
+                (3, StatusCode::Good)
+            ]
+        );
+    }
+
+    #[test]
+    fn republish_missing() {
+        let (mut sub, recorded) = subscription();
+        sub.on_publish_response(message(1), &[1]);
+        // 2 and 3 are missing, only 3 is available on the server.
+        let missing = sub.on_publish_response(message(4), &[3, 4]);
+        assert_eq!(missing, vec![3]);
+        // Message 2 is reported as lost, 4 is held back until 3 is republished.
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (1, StatusCode::Good),
+                (2, StatusCode::BadMessageNotAvailable)
+            ]
+        );
+        // A later publish response does not request 3 again.
+        assert!(sub.on_publish_response(message(5), &[3, 5]).is_empty());
+        assert!(delivered(&recorded).is_empty());
+
+        sub.on_republish_result(3, Ok(message(3)));
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (3, StatusCode::Good),
+                (4, StatusCode::Good),
+                (5, StatusCode::Good)
+            ]
+        );
+
+        // Failed republish is reported as lost.
+        assert_eq!(sub.on_publish_response(message(7), &[6, 7]), vec![6]);
+        sub.on_republish_result(6, Err(StatusCode::BadMessageNotAvailable));
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (6, StatusCode::BadMessageNotAvailable),
+                (7, StatusCode::Good)
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_order_responses() {
+        let (mut sub, recorded) = subscription();
+        sub.on_publish_response(message(1), &[]);
+        assert_eq!(sub.on_publish_response(message(3), &[2, 3]), vec![2]);
+        // The original response arrives before the republish completes.
+        assert!(sub.on_publish_response(message(2), &[2, 3]).is_empty());
+        sub.on_republish_result(2, Ok(message(2)));
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (1, StatusCode::Good),
+                (2, StatusCode::Good),
+                (3, StatusCode::Good)
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_alive_sequence_numbers() {
+        let (mut sub, recorded) = subscription();
+        // Keep-alive carrying the next sequence number.
+        sub.on_publish_response(keep_alive(1), &[]);
+        sub.on_publish_response(keep_alive(1), &[]);
+        assert!(sub.on_publish_response(message(1), &[]).is_empty());
+        // Keep-alive consuming a sequence number.
+        sub.on_publish_response(keep_alive(2), &[]);
+        assert!(sub.on_publish_response(message(3), &[]).is_empty());
+        // Keep-alive revealing a lost message.
+        sub.on_publish_response(keep_alive(5), &[]);
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (1, StatusCode::Good),
+                (3, StatusCode::Good),
+                (4, StatusCode::BadMessageNotAvailable)
+            ]
+        );
+    }
+
+    #[test]
+    fn data_lost_after_keep_alive() {
+        let (mut sub, recorded) = subscription();
+        sub.on_publish_response(message(5), &[5]);
+        sub.on_publish_response(keep_alive(6), &[5]);
+        // The data message with sequence number 6 is lost, so 6 is still available.
+        assert_eq!(sub.on_publish_response(message(7), &[5, 6, 7]), vec![6]);
+        assert_eq!(delivered(&recorded), vec![(5, StatusCode::Good)]);
+        sub.on_republish_result(6, Ok(message(6)));
+        assert_eq!(
+            delivered(&recorded),
+            vec![(6, StatusCode::Good), (7, StatusCode::Good)]
+        );
+
+        // A server that used 9 for the keep-alive does not have it available.
+        sub.on_publish_response(message(8), &[8]);
+        sub.on_publish_response(keep_alive(9), &[8]);
+        assert!(sub.on_publish_response(message(10), &[8, 10]).is_empty());
+        assert_eq!(
+            delivered(&recorded),
+            vec![(8, StatusCode::Good), (10, StatusCode::Good)]
+        );
+    }
+
+    #[test]
+    fn sequence_number_wrap_around() {
+        let (mut sub, recorded) = subscription();
+        sub.on_publish_response(message(u32::MAX - 1), &[]);
+        sub.on_publish_response(message(u32::MAX), &[]);
+        assert!(sub.on_publish_response(message(1), &[]).is_empty());
+        assert_eq!(
+            delivered(&recorded),
+            vec![
+                (u32::MAX - 1, StatusCode::Good),
+                (u32::MAX, StatusCode::Good),
+                (1, StatusCode::Good)
+            ]
+        );
+    }
+}
@@ -28,7 +28,10 @@ use opcua_types::{
     TransferSubscriptionsRequest, TransferSubscriptionsResponse,
 };
 
-use super::{state::SubscriptionState, OnSubscriptionNotification};
+use super::{
+    state::SubscriptionState, MonitoredItemStream, NotificationStreams, OnSubscriptionNotification,
+    StreamOptions, SubscriptionStream,
+};
 
 /// Create a subscription by sending a [`CreateSubscriptionRequest`] to the server.
 ///
@@ -1761,6 +1764,140 @@ impl Session {
             .unwrap_or_default())
     }
 
+    /// Create a subscription whose notifications are delivered through a [`SubscriptionStream`]
+    /// instead of callbacks.
+    ///
+    /// The parameters are the same as for [`Session::create_subscription`]. `options` controls
+    /// how many notifications are buffered and what happens when the buffer is full.
+    ///
+    /// The stream ends when the subscription is deleted. It is kept across reconnects,
+    /// including when the subscription is recreated on the server with a new ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SubscriptionStream)` - Stream of notifications for the new subscription.
+    /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_subscription_stream(
+        &self,
+        publishing_interval: Duration,
+        lifetime_count: u32,
+        max_keep_alive_count: u32,
+        max_notifications_per_publish: u32,
+        priority: u8,
+        publishing_enabled: bool,
+        options: StreamOptions,
+    ) -> Result<SubscriptionStream, StatusCode> {
+        let (callback, stream) = NotificationStreams::new(options);
+        self.create_subscription_inner(
+            publishing_interval,
+            lifetime_count,
+            max_keep_alive_count,
+            max_notifications_per_publish,
+            publishing_enabled,
+            priority,
+            Box::new(callback),
+        )
+        .await?;
+        Ok(stream)
+    }
+
+    /// Create monitored items on the subscription behind `stream`, with a separate
+    /// [`MonitoredItemStream`] for each item.
+    ///
+    /// The item streams are registered before the request is sent, so initial values
+    /// are not lost. Items without a client handle are assigned one.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - Stream returned from [`Session::create_subscription_stream`].
+    /// * `timestamps_to_return` - An enumeration that specifies the timestamp Attributes to be transmitted for each MonitoredItem.
+    /// * `items_to_create` - A list of [`MonitoredItemCreateRequest`] to be created and assigned to the subscription.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<(MonitoredItemCreateResult, Option<MonitoredItemStream>)>)` - The result of creating each item,
+    ///   along with its stream if the item was created. The size and order of the list matches `items_to_create`.
+    /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
+    ///
+    pub async fn create_monitored_item_streams(
+        &self,
+        stream: &SubscriptionStream,
+        timestamps_to_return: TimestampsToReturn,
+        mut items_to_create: Vec<MonitoredItemCreateRequest>,
+    ) -> Result<Vec<(MonitoredItemCreateResult, Option<MonitoredItemStream>)>, StatusCode> {
+        let mut streams = Vec::with_capacity(items_to_create.len());
+        for item in &mut items_to_create {
+            if item.requested_parameters.client_handle == 0 {
+                item.requested_parameters.client_handle = self.monitored_item_handle.next();
+            }
+            streams.push(stream.monitored_item_stream(item.requested_parameters.client_handle));
+        }
+
+        let results = self
+            .create_monitored_items(
+                stream.subscription_id(),
+                timestamps_to_return,
+                items_to_create,
+            )
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .zip(streams)
+            .map(|(result, stream)| {
+                let stream = result.status_code.is_good().then_some(stream);
+                (result, stream)
+            })
+            .collect())
+    }
+
+    /// Create monitored items for the values in `nodes_to_monitor` on the subscription
+    /// behind `stream`, with a separate [`MonitoredItemStream`] for each item.
+    ///
+    /// This is a shorthand for [`Session::create_monitored_item_streams`] with reporting
+    /// enabled, the given sampling interval and queue size, and no filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - Stream returned from [`Session::create_subscription_stream`].
+    /// * `timestamps_to_return` - An enumeration that specifies the timestamp Attributes to be transmitted for each MonitoredItem.
+    /// * `nodes_to_monitor` - The node attributes to monitor.
+    /// * `sampling_interval` - Requested sampling interval in milliseconds.
+    /// * `queue_size` - Requested queue size on the server.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<(MonitoredItemCreateResult, Option<MonitoredItemStream>)>)` - The result of creating each item,
+    ///   along with its stream if the item was created. The size and order of the list matches `nodes_to_monitor`.
+    /// * `Err(StatusCode)` - Request failed, [Status code](StatusCode) is the reason for failure.
+    ///
+    pub async fn create_value_streams(
+        &self,
+        stream: &SubscriptionStream,
+        timestamps_to_return: TimestampsToReturn,
+        nodes_to_monitor: Vec<ReadValueId>,
+        sampling_interval: f64,
+        queue_size: u32,
+    ) -> Result<Vec<(MonitoredItemCreateResult, Option<MonitoredItemStream>)>, StatusCode> {
+        let items_to_create = nodes_to_monitor
+            .into_iter()
+            .map(|item_to_monitor| MonitoredItemCreateRequest {
+                item_to_monitor,
+                monitoring_mode: MonitoringMode::Reporting,
+                requested_parameters: MonitoringParameters {
+                    sampling_interval,
+                    queue_size,
+                    discard_oldest: true,
+                    ..Default::default()
+                },
+            })
+            .collect();
+        self.create_monitored_item_streams(stream, timestamps_to_return, items_to_create)
+            .await
+    }
+
     /// Modifies monitored items on a subscription by sending a [`ModifyMonitoredItemsRequest`] to the server.
     ///
     /// See OPC UA Part 4 - Services 5.12.3 for complete description of the service and error responses.
@@ -1929,10 +2066,23 @@ impl Session {
             Ok(ResponseMessage::Publish(r)) => {
                 session_debug!(self, "PublishResponse");
 
-                {
+                let missing = {
                     let mut subscription_state = trace_lock!(self.subscription_state);
-                    subscription_state
-                        .handle_notification(r.subscription_id, r.notification_message);
+                    subscription_state.handle_notification(
+                        r.subscription_id,
+                        r.notification_message,
+                        r.available_sequence_numbers.as_deref().unwrap_or_default(),
+                    )
+                };
+
+                if !missing.is_empty() {
+                    session_warn!(
+                        self,
+                        "Missing notification messages {:?} on subscription {}, requesting republish",
+                        missing,
+                        r.subscription_id
+                    );
+                    self.republish_missing(r.subscription_id, missing).await;
                 }
 
                 return Ok(r.more_notifications);
@@ -1956,6 +2106,9 @@ impl Session {
     ///
     /// If this succeeds, the session will automatically acknowledge the notification in the next publish request.
     ///
+    /// The session already republishes missing notification messages automatically, so this is
+    /// only needed for custom handling of notification messages.
+    ///
     /// # Arguments
     ///
     /// * `subscription_id` - The Server-assigned identifier for the Subscription to republish from.
@@ -1993,6 +2146,15 @@ impl Session {
         }
     }
 
+    /// Republish missing notification messages, delivering them or reporting them as lost.
+    async fn republish_missing(&self, subscription_id: u32, sequence_numbers: Vec<u32>) {
+        for sequence_number in sequence_numbers {
+            let result = self.republish(subscription_id, sequence_number).await;
+            let mut subscription_state = trace_lock!(self.subscription_state);
+            subscription_state.handle_republish_result(subscription_id, sequence_number, result);
+        }
+    }
+
     /// This code attempts to take the existing subscriptions created by a previous session and
     /// either transfer them to this session, or construct them from scratch.
     pub(crate) async fn transfer_subscriptions_from_old_session(&self) {
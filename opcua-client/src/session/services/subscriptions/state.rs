@@ -3,7 +3,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use opcua_types::{MonitoringMode, NotificationMessage, SubscriptionAcknowledgement};
+use opcua_types::{MonitoringMode, NotificationMessage, StatusCode, SubscriptionAcknowledgement};
 
 use super::{CreateMonitoredItem, ModifyMonitoredItem, PublishLimits, Subscription};
 
@@ -208,14 +208,30 @@ impl SubscriptionState {
         }
     }
 
+    /// Handle a notification message from a publish response. Returns a list of
+    /// missing sequence numbers that should be republished.
     pub(crate) fn handle_notification(
         &mut self,
         subscription_id: u32,
         notification: NotificationMessage,
-    ) {
+        available_sequence_numbers: &[u32],
+    ) -> Vec<u32> {
         self.add_acknowledgement(subscription_id, notification.sequence_number);
         if let Some(sub) = self.subscriptions.get_mut(&subscription_id) {
-            sub.on_notification(notification);
+            sub.on_publish_response(notification, available_sequence_numbers)
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub(crate) fn handle_republish_result(
+        &mut self,
+        subscription_id: u32,
+        sequence_number: u32,
+        result: Result<NotificationMessage, StatusCode>,
+    ) {
+        if let Some(sub) = self.subscriptions.get_mut(&subscription_id) {
+            sub.on_republish_result(sequence_number, result);
         }
     }
 
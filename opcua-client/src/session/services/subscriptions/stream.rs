@@ -0,0 +1,478 @@
+//! Stream based alternative to [`OnSubscriptionNotification`] callbacks.
+//!
+//! Notifications are buffered in bounded queues and consumed through [`futures::Stream`]s,
+//! either for an entire subscription or for individual monitored items.
+//!
+//! Notifications are pushed from the subscription event loop, which must never wait
+//! for a slow consumer, since that would stall publishing for every subscription on the
+//! session. Instead, when a buffer is full, notifications are dropped according to the
+//! configured [`OverflowPolicy`], and the stream yields a [`StreamNotification::Lagged`]
+//! with the number of dropped notifications before the next notification.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use futures::Stream;
+use opcua_core::sync::Mutex;
+use opcua_types::{DataValue, StatusChangeNotification, StatusCode, Variant};
+
+use super::{MonitoredItem, OnSubscriptionNotification};
+
+/// What to do when a notification arrives and the stream buffer is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest notification in the buffer to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Drop the incoming notification, keeping the buffer as is.
+    DropNewest,
+}
+
+/// Configuration for notification streams.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamOptions {
+    /// Maximum number of notifications buffered per stream.
+    pub buffer_size: usize,
+    /// Policy for dropping notifications when the buffer is full.
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            buffer_size: 1000,
+            overflow_policy: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+impl StreamOptions {
+    /// Create new stream options with the given buffer size and overflow policy.
+    pub fn new(buffer_size: usize, overflow_policy: OverflowPolicy) -> Self {
+        Self {
+            buffer_size,
+            overflow_policy,
+        }
+    }
+}
+
+/// A notification received through a [`SubscriptionStream`] or [`MonitoredItemStream`].
+#[derive(Debug, Clone)]
+pub enum StreamNotification {
+    /// A data value change on a monitored item.
+    DataChange {
+        /// Client handle of the monitored item. This is stable even if the
+        /// subscription is recreated on the server.
+        client_handle: u32,
+        /// The new value.
+        value: DataValue,
+    },
+    /// An event on a monitored item.
+    Event {
+        /// Client handle of the monitored item.
+        client_handle: u32,
+        /// Event fields, in the order given by the select clauses of the event filter.
+        fields: Option<Vec<Variant>>,
+    },
+    /// The subscription changed state on the server.
+    StatusChange(StatusChangeNotification),
+    /// A notification message was missing and could not be republished,
+    /// so any notifications it contained are lost.
+    NotificationLost {
+        /// Sequence number of the lost message.
+        sequence_number: u32,
+        /// Reason the message could not be republished, typically `BadMessageNotAvailable`.
+        status: StatusCode,
+    },
+    /// The buffer overflowed, and this many notifications were dropped.
+    Lagged(u64),
+}
+
+struct BufferState {
+    queue: VecDeque<StreamNotification>,
+    lagged: u64,
+    waker: Option<Waker>,
+    /// The producer side is gone, the stream ends once the queue is drained.
+    finished: bool,
+    /// The consumer side is gone, further notifications are discarded.
+    closed: bool,
+}
+
+struct NotificationBuffer {
+    options: StreamOptions,
+    state: Mutex<BufferState>,
+}
+
+impl NotificationBuffer {
+    fn new(options: StreamOptions) -> Arc<Self> {
+        Arc::new(Self {
+            options,
+            state: Mutex::new(BufferState {
+                queue: VecDeque::new(),
+                lagged: 0,
+                waker: None,
+                finished: false,
+                closed: false,
+            }),
+        })
+    }
+
+    fn push(&self, notification: StreamNotification) {
+        let mut state = self.state.lock();
+        if state.closed {
+            return;
+        }
+        if state.queue.len() >= self.options.buffer_size.max(1) {
+            state.lagged += 1;
+            match self.options.overflow_policy {
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                }
+                OverflowPolicy::DropNewest => return,
+            }
+        }
+        state.queue.push_back(notification);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn finish(&self) {
+        let mut state = self.state.lock();
+        state.finished = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.state.lock();
+        state.closed = true;
+        state.queue.clear();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.lock().closed
+    }
+
+    fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<StreamNotification>> {
+        let mut state = self.state.lock();
+        if state.lagged > 0 {
+            let lagged = std::mem::take(&mut state.lagged);
+            return Poll::Ready(Some(StreamNotification::Lagged(lagged)));
+        }
+        if let Some(notification) = state.queue.pop_front() {
+            return Poll::Ready(Some(notification));
+        }
+        if state.finished {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn len(&self) -> usize {
+        self.state.lock().queue.len()
+    }
+}
+
+struct RouterState {
+    options: StreamOptions,
+    subscription: Arc<NotificationBuffer>,
+    items: HashMap<u32, Arc<NotificationBuffer>>,
+}
+
+impl RouterState {
+    fn buffer_for(&mut self, client_handle: u32) -> &NotificationBuffer {
+        if self
+            .items
+            .get(&client_handle)
+            .is_some_and(|b| b.is_closed())
+        {
+            self.items.remove(&client_handle);
+        }
+        self.items.get(&client_handle).unwrap_or(&self.subscription)
+    }
+}
+
+struct Shared {
+    subscription_id: AtomicU32,
+    router: Mutex<RouterState>,
+}
+
+/// Implementation of [`OnSubscriptionNotification`] that routes notifications to
+/// [`SubscriptionStream`]s and [`MonitoredItemStream`]s.
+///
+/// Pass this as the callback when creating a subscription, or use
+/// [`crate::Session::create_subscription_stream`] to do both in one step.
+pub struct NotificationStreams {
+    shared: Arc<Shared>,
+}
+
+impl NotificationStreams {
+    /// Create a new notification router, along with the stream for the subscription.
+    ///
+    /// Notifications for monitored items that do not have their own
+    /// [`MonitoredItemStream`], as well as subscription status changes, are
+    /// delivered to the returned [`SubscriptionStream`].
+    pub fn new(options: StreamOptions) -> (Self, SubscriptionStream) {
+        let buffer = NotificationBuffer::new(options);
+        let shared = Arc::new(Shared {
+            subscription_id: AtomicU32::new(0),
+            router: Mutex::new(RouterState {
+                options,
+                subscription: buffer.clone(),
+                items: HashMap::new(),
+            }),
+        });
+        (
+            Self {
+                shared: shared.clone(),
+            },
+            SubscriptionStream { shared, buffer },
+        )
+    }
+}
+
+impl OnSubscriptionNotification for NotificationStreams {
+    fn on_subscription_created(&mut self, subscription_id: u32) {
+        self.shared
+            .subscription_id
+            .store(subscription_id, Ordering::Relaxed);
+    }
+
+    fn on_subscription_status_change(&mut self, notification: StatusChangeNotification) {
+        let router = self.shared.router.lock();
+        router
+            .subscription
+            .push(StreamNotification::StatusChange(notification));
+    }
+
+    fn on_notification_lost(&mut self, sequence_number: u32, status: StatusCode) {
+        let router = self.shared.router.lock();
+        router
+            .subscription
+            .push(StreamNotification::NotificationLost {
+                sequence_number,
+                status,
+            });
+    }
+
+    fn on_data_value(&mut self, notification: DataValue, item: &MonitoredItem) {
+        let mut router = self.shared.router.lock();
+        router
+            .buffer_for(item.client_handle())
+            .push(StreamNotification::DataChange {
+                client_handle: item.client_handle(),
+                value: notification,
+            });
+    }
+
+    fn on_event(&mut self, event_fields: Option<Vec<Variant>>, item: &MonitoredItem) {
+        let mut router = self.shared.router.lock();
+        router
+            .buffer_for(item.client_handle())
+            .push(StreamNotification::Event {
+                client_handle: item.client_handle(),
+                fields: event_fields,
+            });
+    }
+}
+
+impl Drop for NotificationStreams {
+    fn drop(&mut self) {
+        // The subscription is gone, end all streams once they are drained.
+        let router = self.shared.router.lock();
+        router.subscription.finish();
+        for item in router.items.values() {
+            item.finish();
+        }
+    }
+}
+
+/// A stream of notifications for a subscription.
+///
+/// This stream ends when the subscription is deleted. It stays valid if the session
+/// reconnects and the subscription is transferred or recreated on the server.
+pub struct SubscriptionStream {
+    shared: Arc<Shared>,
+    buffer: Arc<NotificationBuffer>,
+}
+
+impl SubscriptionStream {
+    /// The current server-assigned ID of the subscription. This may change
+    /// if the subscription is recreated after a reconnect.
+    pub fn subscription_id(&self) -> u32 {
+        self.shared.subscription_id.load(Ordering::Relaxed)
+    }
+
+    /// Number of notifications currently buffered in this stream.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Register a stream for the monitored item with the given client handle.
+    ///
+    /// Notifications for this item are delivered only to the returned stream, not to
+    /// the subscription stream. If the item stream is dropped, notifications go back
+    /// to the subscription stream.
+    ///
+    /// The stream should be registered before the monitored item is created,
+    /// so that the initial value is not missed.
+    pub fn monitored_item_stream(&self, client_handle: u32) -> MonitoredItemStream {
+        let mut router = self.shared.router.lock();
+        let buffer = NotificationBuffer::new(router.options);
+        if let Some(old) = router.items.insert(client_handle, buffer.clone()) {
+            old.finish();
+        }
+        MonitoredItemStream {
+            client_handle,
+            shared: self.shared.clone(),
+            buffer,
+        }
+    }
+}
+
+impl Stream for SubscriptionStream {
+    type Item = StreamNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.buffer.poll_next(cx)
+    }
+}
+
+impl Drop for SubscriptionStream {
+    fn drop(&mut self) {
+        self.buffer.close();
+    }
+}
+
+/// A stream of notifications for a single monitored item.
+///
+/// This stream ends when the subscription is deleted, or if another stream is
+/// registered for the same client handle. Deleting only the monitored item does not
+/// end the stream.
+pub struct MonitoredItemStream {
+    client_handle: u32,
+    shared: Arc<Shared>,
+    buffer: Arc<NotificationBuffer>,
+}
+
+impl MonitoredItemStream {
+    /// Client handle of the monitored item.
+    pub fn client_handle(&self) -> u32 {
+        self.client_handle
+    }
+
+    /// The current server-assigned ID of the subscription the monitored item belongs to.
+    pub fn subscription_id(&self) -> u32 {
+        self.shared.subscription_id.load(Ordering::Relaxed)
+    }
+
+    /// Number of notifications currently buffered in this stream.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl Stream for MonitoredItemStream {
+    type Item = StreamNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.buffer.poll_next(cx)
+    }
+}
+
+impl Drop for MonitoredItemStream {
+    fn drop(&mut self) {
+        self.buffer.close();
+        let mut router = self.shared.router.lock();
+        if router
+            .items
+            .get(&self.client_handle)
+            .is_some_and(|b| Arc::ptr_eq(b, &self.buffer))
+        {
+            router.items.remove(&self.client_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use opcua_types::DataValue;
+
+    use super::{
+        MonitoredItem, NotificationStreams, OnSubscriptionNotification, OverflowPolicy,
+        StreamNotification, StreamOptions,
+    };
+
+    fn item(client_handle: u32) -> MonitoredItem {
+        MonitoredItem::new(client_handle)
+    }
+
+    fn value_of(n: Option<StreamNotification>) -> i32 {
+        match n {
+            Some(StreamNotification::DataChange { value, .. }) => {
+                value.value.unwrap().try_cast_to().unwrap()
+            }
+            r => panic!("Expected data change, got {r:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest() {
+        let (mut router, mut stream) =
+            NotificationStreams::new(StreamOptions::new(2, OverflowPolicy::DropOldest));
+        for i in 0..5 {
+            router.on_data_value(DataValue::new_now(i), &item(1));
+        }
+        assert_eq!(stream.buffered_len(), 2);
+        assert!(matches!(
+            stream.next().await,
+            Some(StreamNotification::Lagged(3))
+        ));
+        assert_eq!(value_of(stream.next().await), 3);
+        assert_eq!(value_of(stream.next().await), 4);
+        drop(router);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn drop_newest() {
+        let (mut router, mut stream) =
+            NotificationStreams::new(StreamOptions::new(2, OverflowPolicy::DropNewest));
+        for i in 0..5 {
+            router.on_data_value(DataValue::new_now(i), &item(1));
+        }
+        assert!(matches!(
+            stream.next().await,
+            Some(StreamNotification::Lagged(3))
+        ));
+        assert_eq!(value_of(stream.next().await), 0);
+        assert_eq!(value_of(stream.next().await), 1);
+    }
+
+    #[tokio::test]
+    async fn route_to_item_stream() {
+        let (mut router, mut stream) = NotificationStreams::new(StreamOptions::default());
+        let mut item_stream = stream.monitored_item_stream(1);
+        router.on_data_value(DataValue::new_now(1), &item(1));
+        router.on_data_value(DataValue::new_now(2), &item(2));
+        assert_eq!(value_of(item_stream.next().await), 1);
+        assert_eq!(value_of(stream.next().await), 2);
+        assert_eq!(stream.buffered_len(), 0);
+
+        // Once the item stream is dropped, values go to the subscription stream.
+        drop(item_stream);
+        router.on_data_value(DataValue::new_now(3), &item(1));
+        assert_eq!(value_of(stream.next().await), 3);
+    }
+}
@@ -4,6 +4,8 @@ use async_trait::async_trait;
 use opcua_core::{comms::secure_channel::SecureChannel, sync::RwLock};
 use opcua_types::StatusCode;
 
+#[cfg(feature = "https")]
+use super::https::HttpsTransport;
 use super::{
     tcp::{TcpTransport, TransportConfiguration},
     OutgoingMessage, TransportPollResult,
@@ -19,7 +21,7 @@ use super::{
 ///  - This should not do any retries, that's handled on a higher level.
 pub trait Connector: Send + Sync {
     /// Attempt to establish a connection to the OPC UA endpoint given by `endpoint_url`.
-    /// Note that on success, this returns a `TcpTransport`. The caller is responsible for
+    /// Note that on success, this returns a `ClientTransport`. The caller is responsible for
     /// calling `run` on the returned transport in order to actually send and receive messages.
     async fn connect(
         &self,
@@ -27,7 +29,39 @@ pub trait Connector: Send + Sync {
         outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
         config: TransportConfiguration,
         endpoint_url: &str,
-    ) -> Result<TcpTransport, StatusCode>;
+    ) -> Result<ClientTransport, StatusCode>;
+}
+
+/// Transport for an open connection to a server, returned from a [`Connector`].
+pub enum ClientTransport {
+    /// OPC-UA binary protocol, over TCP or any other stream.
+    Tcp(TcpTransport),
+    /// OPC-UA binary over HTTPS.
+    #[cfg(feature = "https")]
+    Https(HttpsTransport),
+}
+
+impl From<TcpTransport> for ClientTransport {
+    fn from(value: TcpTransport) -> Self {
+        Self::Tcp(value)
+    }
+}
+
+#[cfg(feature = "https")]
+impl From<HttpsTransport> for ClientTransport {
+    fn from(value: HttpsTransport) -> Self {
+        Self::Https(value)
+    }
+}
+
+impl Transport for ClientTransport {
+    async fn poll(&mut self) -> TransportPollResult {
+        match self {
+            Self::Tcp(t) => t.poll().await,
+            #[cfg(feature = "https")]
+            Self::Https(t) => t.poll().await,
+        }
+    }
 }
 
 /// Trait for client transport channels.
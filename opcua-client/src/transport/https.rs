@@ -0,0 +1,414 @@
+use std::{
+    future::Future,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::{
+    body::Bytes,
+    client::conn::http1::{self, SendRequest},
+    header::{CONTENT_TYPE, HOST},
+    Method, Request as HttpRequest, Uri,
+};
+use hyper_util::rt::TokioIo;
+use log::{debug, error};
+use opcua_core::{
+    comms::{
+        https::{
+            decode_message, encode_message, OPCUA_BINARY_CONTENT_TYPE, OPCUA_SECURITY_POLICY_HEADER,
+        },
+        secure_channel::SecureChannel,
+        tcp_codec::AsyncStream,
+        url::is_opc_ua_https_url,
+    },
+    trace_read_lock, RequestMessage, ResponseMessage,
+};
+use opcua_types::{
+    ChannelSecurityToken, DateTime, OpenSecureChannelRequest, OpenSecureChannelResponse,
+    ResponseHeader, StatusCode,
+};
+use parking_lot::{Mutex, RwLock};
+use tokio::{sync::mpsc::Receiver, task::JoinHandle};
+
+use super::{
+    connect::{ClientTransport, Connector, Transport},
+    core::{OutgoingMessage, TransportPollResult},
+    tcp::TransportConfiguration,
+};
+
+type BoxedStream = Box<dyn AsyncStream>;
+
+type OpenStream = Arc<dyn Fn() -> BoxFuture<'static, io::Result<BoxedStream>> + Send + Sync>;
+
+/// Idle connections, and the time they became idle.
+type IdleConnections = Arc<Mutex<Vec<(SendRequest<Full<Bytes>>, Instant)>>>;
+
+/// Time an HTTPS connection may be idle before it is closed by the client.
+/// This is lower than the server keep-alive timeout, so that the client does not
+/// send requests on connections the server is about to close.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connector for OPC-UA binary over HTTPS, using `https` endpoint URLs.
+///
+/// The connector is created from a function that opens a new stream to the server.
+/// This should normally be a TLS stream, but a plain TCP stream can be used if TLS
+/// is terminated by a proxy. Since each connection can only handle a single request
+/// at a time, the function is called whenever a request is sent and there is no idle
+/// connection available.
+///
+/// There are no secure channels in the HTTPS mapping, message security is provided by TLS.
+/// The security policy of the endpoint is only used to sign and validate the session, and
+/// is sent to the server in the `OPCUA-SecurityPolicy` HTTP header.
+pub struct HttpsConnector<F> {
+    open_stream: Arc<F>,
+}
+
+impl<F, Fut, S> HttpsConnector<F>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = io::Result<S>> + Send + 'static,
+    S: AsyncStream,
+{
+    /// Create a new HTTPS connector, using `open_stream` to open new
+    /// connections to the server.
+    pub fn new(open_stream: F) -> Self {
+        Self {
+            open_stream: Arc::new(open_stream),
+        }
+    }
+}
+
+#[async_trait]
+impl<F, Fut, S> Connector for HttpsConnector<F>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = io::Result<S>> + Send + 'static,
+    S: AsyncStream,
+{
+    async fn connect(
+        &self,
+        channel: Arc<RwLock<SecureChannel>>,
+        outgoing_recv: Receiver<OutgoingMessage>,
+        config: TransportConfiguration,
+        endpoint_url: &str,
+    ) -> Result<ClientTransport, StatusCode> {
+        if !is_opc_ua_https_url(endpoint_url) {
+            error!("Endpoint url {endpoint_url} is not an HTTPS url");
+            return Err(StatusCode::BadTcpEndpointUrlInvalid);
+        }
+        let uri: Uri = endpoint_url.parse().map_err(|e| {
+            error!("Invalid endpoint url {endpoint_url}: {e}");
+            StatusCode::BadTcpEndpointUrlInvalid
+        })?;
+
+        let f = self.open_stream.clone();
+        let open_stream: OpenStream = Arc::new(move || {
+            let f = f.clone();
+            Box::pin(async move { Ok(Box::new(f().await?) as BoxedStream) })
+        });
+
+        // Open the first connection right away, so that we fail early
+        // if the server cannot be reached.
+        let sender = HttpsTransport::open_connection(&open_stream).await?;
+
+        Ok(HttpsTransport {
+            channel,
+            outgoing_recv,
+            open_stream,
+            uri,
+            max_message_size: config.max_message_size,
+            idle: Arc::new(Mutex::new(vec![(sender, Instant::now())])),
+            pending: FuturesUnordered::new(),
+            last_token_id: 0,
+            should_close: false,
+            closed: None,
+        }
+        .into())
+    }
+}
+
+/// Transport implementation for the OPC-UA HTTPS transport mapping.
+///
+/// Each request is sent as a separate HTTP request, on an idle connection
+/// if one is available, or on a new connection if not.
+pub struct HttpsTransport {
+    channel: Arc<RwLock<SecureChannel>>,
+    outgoing_recv: Receiver<OutgoingMessage>,
+    open_stream: OpenStream,
+    uri: Uri,
+    max_message_size: usize,
+    idle: IdleConnections,
+    /// Requests in progress.
+    pending: FuturesUnordered<JoinHandle<()>>,
+    last_token_id: u32,
+    should_close: bool,
+    closed: Option<StatusCode>,
+}
+
+impl HttpsTransport {
+    async fn open_connection(
+        open_stream: &OpenStream,
+    ) -> Result<SendRequest<Full<Bytes>>, StatusCode> {
+        let stream = open_stream().await.map_err(|e| {
+            error!("Could not open HTTPS connection: {e}");
+            StatusCode::BadCommunicationError
+        })?;
+        let (sender, connection) = http1::handshake(TokioIo::new(stream)).await.map_err(|e| {
+            error!("HTTP handshake failed: {e}");
+            StatusCode::BadCommunicationError
+        })?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("HTTPS connection closed with error: {e}");
+            }
+        });
+        Ok(sender)
+    }
+
+    /// Get an idle connection that is still open, if there is one.
+    fn take_idle(&mut self) -> Option<SendRequest<Full<Bytes>>> {
+        let now = Instant::now();
+        let mut idle = self.idle.lock();
+        while let Some((sender, idle_since)) = idle.pop() {
+            if !sender.is_closed() && now - idle_since < IDLE_TIMEOUT {
+                return Some(sender);
+            }
+        }
+        None
+    }
+
+    async fn send_request(
+        sender: Option<SendRequest<Full<Bytes>>>,
+        open_stream: OpenStream,
+        request: HttpRequest<Full<Bytes>>,
+        channel: Arc<RwLock<SecureChannel>>,
+        max_message_size: usize,
+    ) -> (
+        Result<ResponseMessage, StatusCode>,
+        Option<SendRequest<Full<Bytes>>>,
+    ) {
+        // If the idle connection was closed by the server, open a new one.
+        // This is safe, since the request has not been sent yet.
+        let sender = match sender {
+            Some(mut s) => s.ready().await.is_ok().then_some(s),
+            None => None,
+        };
+        let mut sender = match sender {
+            Some(s) => s,
+            None => match Self::open_connection(&open_stream).await {
+                Ok(s) => s,
+                Err(e) => return (Err(e), None),
+            },
+        };
+
+        let response = match sender.send_request(request).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Failed to send HTTPS request: {e}");
+                return (Err(StatusCode::BadCommunicationError), None);
+            }
+        };
+        let status = response.status();
+        let limit = if max_message_size == 0 {
+            usize::MAX
+        } else {
+            max_message_size
+        };
+        let body = match Limited::new(response.into_body(), limit).collect().await {
+            Ok(b) => b.to_bytes(),
+            Err(e) if e.is::<LengthLimitError>() => {
+                error!("HTTPS response exceeds max message size {max_message_size}");
+                return (Err(StatusCode::BadResponseTooLarge), None);
+            }
+            Err(e) => {
+                error!("Failed to read HTTPS response: {e}");
+                return (Err(StatusCode::BadCommunicationError), None);
+            }
+        };
+        if !status.is_success() {
+            error!("HTTPS request failed with status {status}");
+            return (Err(StatusCode::BadCommunicationError), Some(sender));
+        }
+
+        let channel = trace_read_lock!(channel);
+        let ctx_r = channel.context();
+        let result = decode_message(&body, &ctx_r.context()).map_err(|e| {
+            error!("Failed to decode HTTPS response: {e}");
+            e.status()
+        });
+        (result, Some(sender))
+    }
+
+    /// Respond to a request to open or renew the secure channel. There is no secure
+    /// channel in the HTTPS mapping, but the channel still needs a security token, and
+    /// the client nonce for the session.
+    fn open_secure_channel(&mut self, request: &OpenSecureChannelRequest) -> ResponseMessage {
+        self.last_token_id += 1;
+        let server_nonce = trace_read_lock!(self.channel)
+            .security_policy()
+            .random_nonce();
+        OpenSecureChannelResponse {
+            response_header: ResponseHeader::new_good(&request.request_header),
+            server_protocol_version: 0,
+            security_token: ChannelSecurityToken {
+                channel_id: 1,
+                token_id: self.last_token_id,
+                created_at: DateTime::now(),
+                revised_lifetime: request.requested_lifetime,
+            },
+            server_nonce,
+        }
+        .into()
+    }
+
+    fn encode_request(
+        &self,
+        request: &RequestMessage,
+    ) -> Result<HttpRequest<Full<Bytes>>, StatusCode> {
+        let channel = trace_read_lock!(self.channel);
+        let body = {
+            let ctx_r = channel.context();
+            encode_message(request, &ctx_r.context()).map_err(|e| {
+                error!("Failed to encode outgoing message: {e}");
+                e.status()
+            })?
+        };
+        if self.max_message_size > 0 && body.len() > self.max_message_size {
+            error!(
+                "Max message size is {} and message {} exceeds that",
+                self.max_message_size,
+                body.len()
+            );
+            return Err(StatusCode::BadRequestTooLarge);
+        }
+
+        let mut builder = HttpRequest::builder()
+            .method(Method::POST)
+            .uri(self.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/"))
+            .header(CONTENT_TYPE, OPCUA_BINARY_CONTENT_TYPE)
+            .header(
+                OPCUA_SECURITY_POLICY_HEADER,
+                channel.security_policy().to_uri(),
+            );
+        if let Some(authority) = self.uri.authority() {
+            builder = builder.header(HOST, authority.as_str());
+        }
+        builder.body(Full::new(body.into())).map_err(|e| {
+            error!("Failed to create HTTP request: {e}");
+            StatusCode::BadInternalError
+        })
+    }
+
+    fn send(&mut self, outgoing: OutgoingMessage) -> TransportPollResult {
+        let request = match outgoing.request {
+            RequestMessage::OpenSecureChannel(request) => {
+                let response = self.open_secure_channel(&request);
+                if let Some(callback) = outgoing.callback {
+                    let _ = callback.send(Ok(response));
+                }
+                return TransportPollResult::OutgoingMessage;
+            }
+            RequestMessage::CloseSecureChannel(_) => {
+                debug!("Closing HTTPS transport");
+                self.should_close = true;
+                return TransportPollResult::OutgoingMessage;
+            }
+            request => request,
+        };
+
+        let http_request = match self.encode_request(&request) {
+            Ok(r) => r,
+            Err(e) => {
+                if let Some(callback) = outgoing.callback {
+                    let _ = callback.send(Err(e));
+                }
+                return TransportPollResult::RecoverableError(e);
+            }
+        };
+
+        let fut = Self::send_request(
+            self.take_idle(),
+            self.open_stream.clone(),
+            http_request,
+            self.channel.clone(),
+            self.max_message_size,
+        );
+        let deadline = outgoing.deadline;
+        let callback = outgoing.callback;
+        let idle = self.idle.clone();
+        // Each request runs as a separate task, so that a slow request like
+        // a publish does not block any others.
+        self.pending.push(tokio::spawn(async move {
+            let (result, sender) = tokio::time::timeout_at(deadline.into(), fut)
+                .await
+                .unwrap_or((Err(StatusCode::BadTimeout), None));
+            // Return the connection before responding, so that the next request,
+            // like an `ActivateSession` following a `CreateSession`, reuses it.
+            if let Some(sender) = sender {
+                idle.lock().push((sender, Instant::now()));
+            }
+            if let Some(callback) = callback {
+                let _ = callback.send(result);
+            }
+        }));
+        TransportPollResult::OutgoingMessage
+    }
+
+    async fn poll_inner(&mut self) -> TransportPollResult {
+        if self.should_close && self.pending.is_empty() {
+            return TransportPollResult::Closed(StatusCode::Good);
+        }
+        // Both branches are cancel safe.
+        tokio::select! {
+            outgoing = self.outgoing_recv.recv(), if !self.should_close => {
+                let Some(outgoing) = outgoing else {
+                    return TransportPollResult::Closed(StatusCode::Good);
+                };
+                self.send(outgoing)
+            }
+            Some(_) = self.pending.next(), if !self.pending.is_empty() => {
+                TransportPollResult::IncomingMessage
+            }
+        }
+    }
+
+    /// Close the transport, aborting any pending requests.
+    fn close(&mut self, status: StatusCode) {
+        self.closed = Some(status);
+        let request_status = if status.is_good() {
+            StatusCode::BadConnectionClosed
+        } else {
+            status
+        };
+        // Aborting the tasks drops the callbacks, which fails the requests.
+        for pending in self.pending.iter() {
+            pending.abort();
+        }
+        self.pending.clear();
+        self.idle.lock().clear();
+
+        self.outgoing_recv.close();
+        while let Ok(msg) = self.outgoing_recv.try_recv() {
+            if let Some(cb) = msg.callback {
+                let _ = cb.send(Err(request_status));
+            }
+        }
+    }
+}
+
+impl Transport for HttpsTransport {
+    async fn poll(&mut self) -> TransportPollResult {
+        if let Some(status) = self.closed {
+            return TransportPollResult::Closed(status);
+        }
+        let r = self.poll_inner().await;
+        if let TransportPollResult::Closed(status) = &r {
+            self.close(*status);
+        }
+        r
+    }
+}
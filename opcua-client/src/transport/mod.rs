@@ -1,8 +1,12 @@
 mod channel;
 mod connect;
 mod core;
+#[cfg(feature = "https")]
+pub mod https;
 mod state;
 pub mod tcp;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 pub use channel::{AsyncSecureChannel, SecureChannelEventLoop};
 pub use connect::Connector;
@@ -1,17 +1,17 @@
-use std::sync::Arc;
+use std::{future::Future, sync::Arc, time::Duration};
 
-use super::connect::{Connector, Transport};
+use super::connect::{ClientTransport, Connector, Transport};
 use super::core::{OutgoingMessage, TransportPollResult, TransportState};
 use async_trait::async_trait;
 use futures::StreamExt;
-use log::{debug, error};
-use opcua_core::comms::tcp_types::AcknowledgeMessage;
+use log::{debug, error, warn};
+use opcua_core::comms::tcp_types::{AcknowledgeMessage, ReverseHelloMessage};
 use opcua_core::RequestMessage;
 use opcua_core::{
     comms::{
         buffer::SendBuffer,
         secure_channel::SecureChannel,
-        tcp_codec::{Message, TcpCodec},
+        tcp_codec::{AsyncStream, Message, TcpCodec},
         tcp_types::HelloMessage,
         url::hostname_port_from_url,
     },
@@ -20,7 +20,7 @@ use opcua_core::{
 use opcua_types::StatusCode;
 use parking_lot::RwLock;
 use tokio::io::{AsyncWriteExt, ReadHalf, WriteHalf};
-use tokio::net::TcpStream;
+use tokio::net::{TcpListener, TcpStream};
 use tokio_util::codec::FramedRead;
 
 #[derive(Debug, Clone, Copy)]
@@ -30,10 +30,13 @@ enum TransportCloseState {
     Closed(StatusCode),
 }
 
+type BoxedStream = Box<dyn AsyncStream>;
+
+/// Transport implementation for the OPC-UA binary protocol, over TCP or any other stream.
 pub struct TcpTransport {
     state: TransportState,
-    read: FramedRead<ReadHalf<TcpStream>, TcpCodec>,
-    write: WriteHalf<TcpStream>,
+    read: FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+    write: WriteHalf<BoxedStream>,
     send_buffer: SendBuffer,
     should_close: bool,
     closed: TransportCloseState,
@@ -48,21 +51,21 @@ pub struct TransportConfiguration {
     pub max_chunk_count: usize,
 }
 
+/// Connector for opc.tcp, opening a new TCP connection to the server.
 pub struct TcpConnector;
 
+type HandshakeResult = (
+    FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+    WriteHalf<BoxedStream>,
+    AcknowledgeMessage,
+);
+
 impl TcpConnector {
     async fn connect_inner(
         secure_channel: &RwLock<SecureChannel>,
         config: &TransportConfiguration,
         endpoint_url: &str,
-    ) -> Result<
-        (
-            FramedRead<ReadHalf<TcpStream>, TcpCodec>,
-            WriteHalf<TcpStream>,
-            AcknowledgeMessage,
-        ),
-        StatusCode,
-    > {
+    ) -> Result<HandshakeResult, StatusCode> {
         let (host, port) = hostname_port_from_url(
             endpoint_url,
             opcua_core::constants::DEFAULT_OPC_UA_SERVER_PORT,
@@ -96,8 +99,33 @@ impl TcpConnector {
             StatusCode::BadCommunicationError
         })?;
 
-        let (reader, mut writer) = tokio::io::split(socket);
+        let (framed_read, writer) = Self::split_stream(socket, secure_channel);
+        Self::hello(framed_read, writer, config, endpoint_url).await
+    }
 
+    /// Split a stream into a framed reader and a writer.
+    fn split_stream(
+        stream: impl AsyncStream,
+        secure_channel: &RwLock<SecureChannel>,
+    ) -> (
+        FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+        WriteHalf<BoxedStream>,
+    ) {
+        let (reader, writer) = tokio::io::split(Box::new(stream) as BoxedStream);
+        let framed_read = {
+            let secure_channel = trace_read_lock!(secure_channel);
+            FramedRead::new(reader, TcpCodec::new(secure_channel.decoding_options()))
+        };
+        (framed_read, writer)
+    }
+
+    /// Exchange HELLO/ACKNOWLEDGE with the server on an open connection.
+    async fn hello(
+        mut framed_read: FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+        mut writer: WriteHalf<BoxedStream>,
+        config: &TransportConfiguration,
+        endpoint_url: &str,
+    ) -> Result<HandshakeResult, StatusCode> {
         let hello = HelloMessage::new(
             endpoint_url,
             config.send_buffer_size,
@@ -106,10 +134,6 @@ impl TcpConnector {
             config.max_chunk_count,
         );
         log::trace!("Send hello message: {hello:?}");
-        let mut framed_read = {
-            let secure_channel = trace_read_lock!(secure_channel);
-            FramedRead::new(reader, TcpCodec::new(secure_channel.decoding_options()))
-        };
 
         writer
             .write_all(&opcua_types::SimpleBinaryEncodable::encode_to_vec(&hello))
@@ -150,12 +174,184 @@ impl Connector for TcpConnector {
         outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
         config: TransportConfiguration,
         endpoint_url: &str,
-    ) -> Result<TcpTransport, StatusCode> {
+    ) -> Result<ClientTransport, StatusCode> {
         let (framed_read, writer, ack) =
-            match Self::connect_inner(&channel, &config, endpoint_url).await {
-                Ok(k) => k,
-                Err(status) => return Err(status),
+            Self::connect_inner(&channel, &config, endpoint_url).await?;
+        Ok(TcpTransport::new(channel, outgoing_recv, config, framed_read, writer, ack).into())
+    }
+}
+
+/// Connector for OPC-UA reverse connect. Instead of connecting to the server,
+/// this waits for the server to connect to a listener owned by the client.
+///
+/// Once a connection arrives, the server sends a `ReverseHello` message.
+/// Connections from servers other than the expected one are closed, and the
+/// connector keeps waiting. After a valid `ReverseHello` the regular
+/// HELLO/ACKNOWLEDGE exchange takes place on the same socket.
+pub struct ReverseTcpConnector {
+    listener: TcpListener,
+    server_uri: String,
+}
+
+/// Time to wait for a `ReverseHello` after a server has connected.
+const REVERSE_HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl ReverseTcpConnector {
+    /// Create a new reverse connector waiting for connections from the server
+    /// with application URI `server_uri` on `listener`.
+    pub fn new(listener: TcpListener, server_uri: impl Into<String>) -> Self {
+        Self {
+            listener,
+            server_uri: server_uri.into(),
+        }
+    }
+
+    async fn wait_for_reverse_hello(
+        &self,
+        secure_channel: &RwLock<SecureChannel>,
+    ) -> Result<
+        (
+            FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+            WriteHalf<BoxedStream>,
+            ReverseHelloMessage,
+        ),
+        StatusCode,
+    > {
+        loop {
+            let (socket, addr) = self.listener.accept().await.map_err(|e| {
+                error!("Failed to accept reverse connection: {e}");
+                StatusCode::BadCommunicationError
+            })?;
+            debug!("Accepted reverse connection from {addr}");
+
+            let (mut framed_read, writer) = TcpConnector::split_stream(socket, secure_channel);
+
+            let rhe = match tokio::time::timeout(REVERSE_HELLO_TIMEOUT, framed_read.next()).await {
+                Ok(Some(Ok(Message::ReverseHello(rhe)))) => rhe,
+                Ok(other) => {
+                    warn!("Expected ReverseHello from {addr}, got {other:?}");
+                    continue;
+                }
+                Err(_) => {
+                    warn!("Timeout waiting for ReverseHello from {addr}");
+                    continue;
+                }
             };
+            if !rhe.is_valid() {
+                warn!("Received invalid ReverseHello from {addr}");
+                continue;
+            }
+            if rhe.server_uri.as_ref() != self.server_uri {
+                warn!(
+                    "Received ReverseHello from unexpected server {}, expected {}",
+                    rhe.server_uri, self.server_uri
+                );
+                continue;
+            }
+            log::trace!("Received reverse hello: {rhe:?}");
+            return Ok((framed_read, writer, rhe));
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for ReverseTcpConnector {
+    async fn connect(
+        &self,
+        channel: Arc<RwLock<SecureChannel>>,
+        outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
+        config: TransportConfiguration,
+        endpoint_url: &str,
+    ) -> Result<ClientTransport, StatusCode> {
+        let (framed_read, writer, rhe) = self.wait_for_reverse_hello(&channel).await?;
+        // The client should use the endpoint URL the server sent,
+        // the configured one may not be reachable from the server side at all.
+        let endpoint_url = rhe.endpoint_url.value().as_deref().unwrap_or(endpoint_url);
+        let (framed_read, writer, ack) =
+            TcpConnector::hello(framed_read, writer, &config, endpoint_url).await?;
+        Ok(TcpTransport::new(channel, outgoing_recv, config, framed_read, writer, ack).into())
+    }
+}
+
+/// Connector running the OPC-UA binary protocol over an arbitrary stream,
+/// for example a Unix domain socket, a TLS stream, or an in-memory
+/// [`tokio::io::duplex`] stream.
+///
+/// The connector is created from a function that opens a new stream, which is called
+/// each time the session needs to (re)connect to the server. The regular
+/// HELLO/ACKNOWLEDGE exchange takes place on the returned stream.
+pub struct StreamConnector<F> {
+    open_stream: F,
+}
+
+impl<F, Fut, S> StreamConnector<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = std::io::Result<S>> + Send,
+    S: AsyncStream,
+{
+    /// Create a new stream connector, using `open_stream` to open a new
+    /// connection to the server.
+    pub fn new(open_stream: F) -> Self {
+        Self { open_stream }
+    }
+}
+
+#[async_trait]
+impl<F, Fut, S> Connector for StreamConnector<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = std::io::Result<S>> + Send,
+    S: AsyncStream,
+{
+    async fn connect(
+        &self,
+        channel: Arc<RwLock<SecureChannel>>,
+        outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
+        config: TransportConfiguration,
+        endpoint_url: &str,
+    ) -> Result<ClientTransport, StatusCode> {
+        let stream = (self.open_stream)().await.map_err(|e| {
+            error!("Could not open stream to {endpoint_url}: {e}");
+            StatusCode::BadCommunicationError
+        })?;
+        TcpTransport::connect_stream(stream, channel, outgoing_recv, config, endpoint_url)
+            .await
+            .map(Into::into)
+    }
+}
+
+impl TcpTransport {
+    /// Exchange HELLO/ACKNOWLEDGE with the server on an open stream, and
+    /// create a transport from it.
+    pub(super) async fn connect_stream(
+        stream: impl AsyncStream,
+        channel: Arc<RwLock<SecureChannel>>,
+        outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
+        config: TransportConfiguration,
+        endpoint_url: &str,
+    ) -> Result<Self, StatusCode> {
+        let (framed_read, writer) = TcpConnector::split_stream(stream, &channel);
+        let (framed_read, writer, ack) =
+            TcpConnector::hello(framed_read, writer, &config, endpoint_url).await?;
+        Ok(Self::new(
+            channel,
+            outgoing_recv,
+            config,
+            framed_read,
+            writer,
+            ack,
+        ))
+    }
+
+    fn new(
+        channel: Arc<RwLock<SecureChannel>>,
+        outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
+        config: TransportConfiguration,
+        read: FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+        write: WriteHalf<BoxedStream>,
+        ack: AcknowledgeMessage,
+    ) -> Self {
         let mut buffer = SendBuffer::new(
             config.send_buffer_size,
             config.max_message_size,
@@ -167,23 +363,21 @@ impl Connector for TcpConnector {
             ack.max_chunk_count as usize,
         );
 
-        Ok(TcpTransport {
+        TcpTransport {
             state: TransportState::new(
                 channel,
                 outgoing_recv,
                 config.max_pending_incoming,
                 ack.send_buffer_size.min(config.recv_buffer_size as u32) as usize,
             ),
-            read: framed_read,
-            write: writer,
+            read,
+            write,
             send_buffer: buffer,
             should_close: false,
             closed: TransportCloseState::Open,
-        })
+        }
     }
-}
 
-impl TcpTransport {
     fn handle_incoming_message(
         &mut self,
         incoming: Option<Result<Message, std::io::Error>>,
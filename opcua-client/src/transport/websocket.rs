@@ -0,0 +1,75 @@
+use std::{future::Future, sync::Arc};
+
+use async_trait::async_trait;
+use log::error;
+use opcua_core::comms::{
+    secure_channel::SecureChannel, tcp_codec::AsyncStream, url::is_opc_ua_websocket_url,
+    websocket::WebSocketChunkStream,
+};
+use opcua_types::StatusCode;
+use parking_lot::RwLock;
+
+use super::{
+    connect::{ClientTransport, Connector},
+    core::OutgoingMessage,
+    tcp::{TcpTransport, TransportConfiguration},
+};
+
+/// Connector for OPC-UA over WebSockets, using `opc.wss` endpoint URLs.
+///
+/// The connector is created from a function that opens the stream the WebSocket
+/// connection runs over, which is called each time the session needs to (re)connect.
+/// For `opc.wss` this should normally be a TLS stream, but a plain TCP stream
+/// can be used if TLS is terminated by a proxy.
+///
+/// Message chunks are sent using the `opcua+uacp` WebSocket subprotocol.
+pub struct WebSocketConnector<F> {
+    open_stream: F,
+}
+
+impl<F, Fut, S> WebSocketConnector<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = std::io::Result<S>> + Send,
+    S: AsyncStream,
+{
+    /// Create a new WebSocket connector, using `open_stream` to open a new
+    /// connection to the server.
+    pub fn new(open_stream: F) -> Self {
+        Self { open_stream }
+    }
+}
+
+#[async_trait]
+impl<F, Fut, S> Connector for WebSocketConnector<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = std::io::Result<S>> + Send,
+    S: AsyncStream,
+{
+    async fn connect(
+        &self,
+        channel: Arc<RwLock<SecureChannel>>,
+        outgoing_recv: tokio::sync::mpsc::Receiver<OutgoingMessage>,
+        config: TransportConfiguration,
+        endpoint_url: &str,
+    ) -> Result<ClientTransport, StatusCode> {
+        if !is_opc_ua_websocket_url(endpoint_url) {
+            error!("Endpoint url {endpoint_url} is not a WebSocket url");
+            return Err(StatusCode::BadTcpEndpointUrlInvalid);
+        }
+        let stream = (self.open_stream)().await.map_err(|e| {
+            error!("Could not open stream to {endpoint_url}: {e}");
+            StatusCode::BadCommunicationError
+        })?;
+        let stream = WebSocketChunkStream::connect(stream, endpoint_url)
+            .await
+            .map_err(|e| {
+                error!("WebSocket handshake with {endpoint_url} failed: {e}");
+                StatusCode::BadCommunicationError
+            })?;
+        TcpTransport::connect_stream(stream, channel, outgoing_recv, config, endpoint_url)
+            .await
+            .map(Into::into)
+    }
+}
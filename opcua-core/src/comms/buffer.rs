@@ -235,7 +235,7 @@ mod tests {
 
     use crate::comms::secure_channel::{Role, SecureChannel};
     use crate::RequestMessage;
-    use opcua_crypto::CertificateStore;
+    use opcua_crypto::FileSystemCertificateStore;
     use opcua_types::StatusCode;
     use opcua_types::{
         DateTime, NodeId, ReadRequest, ReadValueId, RequestHeader, TimestampsToReturn,
@@ -244,9 +244,9 @@ mod tests {
     fn get_buffer_and_channel() -> (SendBuffer, SecureChannel) {
         let buffer = SendBuffer::new(8196, 81960, 5);
         let channel = SecureChannel::new(
-            Arc::new(RwLock::new(CertificateStore::new(std::path::Path::new(
-                "./pki",
-            )))),
+            Arc::new(RwLock::new(FileSystemCertificateStore::new(
+                std::path::Path::new("./pki"),
+            ))),
             Role::Client,
             Default::default(),
         );
@@ -0,0 +1,62 @@
+//! Utilities for the OPC-UA HTTPS transport mapping, as described in Part 6.
+//!
+//! With HTTPS, each service request is sent as the body of an HTTP POST request,
+//! and the response is returned as the body of the HTTP response. Messages use the
+//! regular binary encoding, but are not split into chunks and there is no secure channel,
+//! message security is provided by TLS instead.
+
+use std::io::Cursor;
+
+use opcua_types::{BinaryDecodable, BinaryEncodable, Context, EncodingResult, Error, NodeId};
+
+use crate::Message;
+
+/// Content type of HTTP requests and responses using the binary encoding.
+pub const OPCUA_BINARY_CONTENT_TYPE: &str = "application/opcua+uabinary";
+
+/// HTTP header containing the URI of the security policy used for the session.
+pub const OPCUA_SECURITY_POLICY_HEADER: &str = "OPCUA-SecurityPolicy";
+
+/// Encode a message as the body of an HTTP request or response. This is
+/// the encoding ID of the message followed by the encoded message.
+pub fn encode_message(message: &impl Message, ctx: &Context<'_>) -> EncodingResult<Vec<u8>> {
+    let node_id = message.type_id();
+    let mut buf = Vec::with_capacity(node_id.byte_len(ctx) + message.byte_len(ctx));
+    node_id.encode(&mut buf, ctx)?;
+    message.encode(&mut buf, ctx)?;
+    Ok(buf)
+}
+
+/// Decode a message from the body of an HTTP request or response.
+pub fn decode_message<T: Message>(body: &[u8], ctx: &Context<'_>) -> EncodingResult<T> {
+    let mut stream = Cursor::new(body);
+    let node_id = NodeId::decode(&mut stream, ctx)?;
+    let object_id = node_id
+        .as_object_id()
+        .map_err(|_| Error::decoding(format!("The message id {node_id} is not an object id")))?;
+    T::decode_by_object_id(&mut stream, object_id, ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use opcua_types::{ContextOwned, GetEndpointsRequest, RequestHeader, UAString};
+
+    use super::{decode_message, encode_message};
+    use crate::RequestMessage;
+
+    #[test]
+    fn encode_decode_message() {
+        let ctx_owned = ContextOwned::default();
+        let ctx = ctx_owned.context();
+        let message: RequestMessage = GetEndpointsRequest {
+            request_header: RequestHeader::dummy(),
+            endpoint_url: UAString::from("https://localhost:4855/"),
+            locale_ids: None,
+            profile_uris: None,
+        }
+        .into();
+        let body = encode_message(&message, &ctx).unwrap();
+        let decoded: RequestMessage = decode_message(&body, &ctx).unwrap();
+        assert_eq!(message, decoded);
+    }
+}
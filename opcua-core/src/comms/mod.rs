@@ -7,6 +7,7 @@
 
 pub mod buffer;
 pub mod chunker;
+pub mod https;
 pub mod message_chunk;
 pub mod message_chunk_info;
 pub mod secure_channel;
@@ -14,3 +15,5 @@ pub mod security_header;
 pub mod tcp_codec;
 pub mod tcp_types;
 pub mod url;
+#[cfg(feature = "websocket")]
+pub mod websocket;
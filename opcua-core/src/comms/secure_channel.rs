@@ -79,6 +79,8 @@ pub struct SecureChannel {
     remote_nonce: Vec<u8>,
     /// Our nonce generated while handling open secure channel
     local_nonce: Vec<u8>,
+    /// Our ephemeral key pair, whose public key is the local nonce, for ECC security policies
+    local_ephemeral_key: Option<PrivateKey>,
     /// Client (i.e. other end's set of keys) Symmetric Signing Key, Encrypt Key, IV
     ///
     /// This is a map of channel token ids and their respective keys. We need to keep
@@ -107,6 +109,7 @@ impl SecureChannel {
             token_created_at: DateTime::now(),
             token_lifetime: 0,
             local_nonce: Vec::new(),
+            local_ephemeral_key: None,
             remote_nonce: Vec::new(),
             cert: None,
             private_key: None,
@@ -120,7 +123,7 @@ impl SecureChannel {
     /// Create a new secure channel with the given certificate store
     /// and role.
     pub fn new(
-        certificate_store: Arc<RwLock<CertificateStore>>,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
         role: Role,
         encoding_context: Arc<RwLock<ContextOwned>>,
     ) -> SecureChannel {
@@ -151,6 +154,7 @@ impl SecureChannel {
             token_created_at: DateTime::now(),
             token_lifetime: 0,
             local_nonce: Vec::new(),
+            local_ephemeral_key: None,
             remote_nonce: Vec::new(),
             cert,
             private_key,
@@ -161,6 +165,13 @@ impl SecureChannel {
         }
     }
 
+    /// Set the role of the channel.
+    /// For testing purposes only
+    #[cfg(test)]
+    pub fn set_role(&mut self, role: Role) {
+        self.role = role;
+    }
+
     /// Return `true` if this channel is for a client.
     pub fn is_client_role(&self) -> bool {
         self.role == Role::Client
@@ -314,12 +325,15 @@ impl SecureChannel {
                     trace!("AsymmetricSecurityHeader security policy none");
                     AsymmetricSecurityHeader::none()
                 } else {
-                    let receiver_certificate_thumbprint =
-                        if let Some(ref remote_cert) = self.remote_cert {
-                            remote_cert.thumbprint().as_byte_string()
-                        } else {
-                            ByteString::null()
-                        };
+                    // ECC policies sign but do not encrypt the OpenSecureChannel message, so
+                    // there is no receiver certificate to identify.
+                    let receiver_certificate_thumbprint = if self.security_policy.is_ecc() {
+                        ByteString::null()
+                    } else if let Some(ref remote_cert) = self.remote_cert {
+                        remote_cert.thumbprint().as_byte_string()
+                    } else {
+                        ByteString::null()
+                    };
                     AsymmetricSecurityHeader::new(
                         self.security_policy,
                         self.cert.as_ref().unwrap(),
@@ -338,11 +352,18 @@ impl SecureChannel {
         }
     }
 
-    /// Creates a nonce for the connection. The nonce should be the same size as the symmetric key
+    /// Creates a nonce for the connection. The nonce should be the same size as the symmetric key.
+    /// For ECC security policies the nonce is the public key of a new ephemeral key pair.
     pub fn create_random_nonce(&mut self) {
-        self.local_nonce
-            .resize(self.security_policy.secure_channel_nonce_length(), 0);
-        random::bytes(&mut self.local_nonce);
+        self.local_ephemeral_key = self.security_policy.ephemeral_key();
+        if let Some(ref key) = self.local_ephemeral_key {
+            // Keys on a supported curve always encode as a nonce
+            self.local_nonce = key.to_public_key().to_ecc_nonce().unwrap();
+        } else {
+            self.local_nonce
+                .resize(self.security_policy.secure_channel_nonce_length(), 0);
+            random::bytes(&mut self.local_nonce);
+        }
     }
 
     /// Sets the remote certificate
@@ -428,15 +449,57 @@ impl SecureChannel {
     /// The Client keys are used to secure Messages sent by the Client. The Server keys
     /// are used to secure Messages sent by the Server.
     ///
-    pub fn derive_keys(&mut self) {
-        self.insert_remote_keys(
-            self.security_policy
-                .make_secure_channel_keys(&self.local_nonce, &self.remote_nonce),
-        );
-        self.local_keys = Some(
-            self.security_policy
-                .make_secure_channel_keys(&self.remote_nonce, &self.local_nonce),
-        );
+    /// ECC security policies instead derive the keys from the secret agreed on with the
+    /// ephemeral keys exchanged as nonces, see
+    /// [`SecurityPolicy::make_ecc_secure_channel_keys`].
+    pub fn derive_keys(&mut self) -> Result<(), StatusCode> {
+        if let Some(curve) = self.security_policy.ecc_curve() {
+            let Some(ref local_key) = self.local_ephemeral_key else {
+                error!("Cannot derive keys without a local ephemeral key");
+                return Err(StatusCode::BadNonceInvalid);
+            };
+            let remote_key = PublicKey::from_ecc_nonce(curve, &self.remote_nonce).map_err(|e| {
+                error!("Remote nonce is not a valid ephemeral key: {}", e);
+                StatusCode::BadNonceInvalid
+            })?;
+            let shared_secret = local_key.ecdh(&remote_key).map_err(|e| {
+                error!("Failed to agree on a shared secret: {}", e);
+                StatusCode::BadSecurityChecksFailed
+            })?;
+            let (client_nonce, server_nonce) = if self.is_client_role() {
+                (&self.local_nonce, &self.remote_nonce)
+            } else {
+                (&self.remote_nonce, &self.local_nonce)
+            };
+            let client_keys = self.security_policy.make_ecc_secure_channel_keys(
+                &shared_secret,
+                client_nonce,
+                server_nonce,
+                true,
+            );
+            let server_keys = self.security_policy.make_ecc_secure_channel_keys(
+                &shared_secret,
+                client_nonce,
+                server_nonce,
+                false,
+            );
+            if self.is_client_role() {
+                self.insert_remote_keys(server_keys);
+                self.local_keys = Some(client_keys);
+            } else {
+                self.insert_remote_keys(client_keys);
+                self.local_keys = Some(server_keys);
+            }
+        } else {
+            self.insert_remote_keys(
+                self.security_policy
+                    .make_secure_channel_keys(&self.local_nonce, &self.remote_nonce),
+            );
+            self.local_keys = Some(
+                self.security_policy
+                    .make_secure_channel_keys(&self.remote_nonce, &self.local_nonce),
+            );
+        }
         trace!("Remote nonce = {:?}", self.remote_nonce);
         trace!("Local nonce = {:?}", self.local_nonce);
         trace!(
@@ -444,6 +507,7 @@ impl SecureChannel {
             self.get_remote_keys(self.token_id)
         );
         trace!("Derived local keys = {:?}", self.local_keys);
+        Ok(())
     }
 
     /// Get the deadline as an [`Instant`] for token renewal, used
@@ -467,7 +531,7 @@ impl SecureChannel {
             SecurityHeader::Asymmetric(security_header) => {
                 if !security_header.sender_certificate.is_null() {
                     let x509 = X509::from_byte_string(&security_header.sender_certificate).unwrap();
-                    x509.public_key().unwrap().size()
+                    x509.public_key().unwrap().signature_size()
                 } else {
                     trace!("No certificate / public key was supplied in the asymmetric security header");
                     0
@@ -506,6 +570,8 @@ impl SecureChannel {
         }
 
         match security_header {
+            // The OpenSecureChannel of ECC policies is only signed, so it is not padded
+            SecurityHeader::Asymmetric(_) if self.security_policy.is_ecc() => (0, 0),
             SecurityHeader::Asymmetric(security_header) => {
                 if security_header.sender_certificate.is_null() {
                     error!("Sender has not supplied a certificate so it is doubtful that this will work");
@@ -872,13 +938,21 @@ impl SecureChannel {
         let header_size = encrypted_range.start;
 
         let signing_key = self.private_key.as_ref().unwrap();
-        let signing_key_size = signing_key.size();
+        let signing_key_size = signing_key.signature_size();
 
         let signed_range = 0..(encrypted_range.end - signing_key_size);
         let signature_range = signed_range.end..encrypted_range.end;
 
         trace!("Header size = {}, Encrypted range = {:?}, Signed range = {:?}, Signature range = {:?}, signature size = {}", header_size, encrypted_range, signed_range, signature_range, signing_key_size);
 
+        if security_policy.is_ecc() {
+            // Sign the whole message, nothing is encrypted
+            let (l, r) = src.split_at_mut(signed_range.end);
+            security_policy.asymmetric_sign(signing_key, l, &mut r[0..signing_key_size])?;
+            dst[..signature_range.end].copy_from_slice(&src[..signature_range.end]);
+            return Ok(signature_range.end);
+        }
+
         let encryption_key = self.remote_cert.as_ref().unwrap().public_key()?;
 
         // Encryption will change the size of the chunk. Since we sign before encrypting, we need to
@@ -1028,6 +1102,17 @@ impl SecureChannel {
         // The receiver certificate thumbprint identifies which of our certs was used by the client
         // to encrypt the message. We have to work out from the thumbprint which cert to use
 
+        if security_policy.is_ecc() {
+            return self.asymmetric_verify(
+                security_policy,
+                verification_key,
+                receiver_thumbprint,
+                src,
+                their_key,
+                dst,
+            );
+        }
+
         let our_cert = self.cert.as_ref().unwrap();
         let our_thumbprint = our_cert.thumbprint();
         if our_thumbprint.value() != receiver_thumbprint.as_ref() {
@@ -1111,6 +1196,48 @@ impl SecureChannel {
         }
     }
 
+    /// Verify an OpenSecureChannel message of an ECC security policy, which is signed but not
+    /// encrypted and therefore carries no padding.
+    fn asymmetric_verify(
+        &self,
+        security_policy: SecurityPolicy,
+        verification_key: &PublicKey,
+        receiver_thumbprint: ByteString,
+        src: &[u8],
+        their_key: Option<PrivateKey>,
+        dst: &mut [u8],
+    ) -> Result<usize, Error> {
+        // The thumbprint is optional since the message is not encrypted, but if it is
+        // supplied it must still identify our certificate
+        if !receiver_thumbprint.is_null() {
+            let our_thumbprint = self.cert.as_ref().unwrap().thumbprint();
+            if our_thumbprint.value() != receiver_thumbprint.as_ref() {
+                return Err(Error::new(
+                    StatusCode::BadNoValidCertificates,
+                    "Supplied thumbprint does not match application certificate's thumbprint",
+                ));
+            }
+        }
+
+        let signature_size = verification_key.signature_size();
+        if src.len() < signature_size {
+            return Err(Error::new(
+                StatusCode::BadSecurityChecksFailed,
+                "Message is too short to contain a signature",
+            ));
+        }
+        let signature_start = src.len() - signature_size;
+        security_policy.asymmetric_verify_signature(
+            verification_key,
+            &src[..signature_start],
+            &src[signature_start..],
+            their_key,
+        )?;
+
+        dst[..signature_start].copy_from_slice(&src[..signature_start]);
+        Ok(signature_start)
+    }
+
     /// Get the local nonce.
     pub fn local_nonce(&self) -> &[u8] {
         &self.local_nonce
@@ -1408,7 +1535,9 @@ impl SecureChannel {
             | SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
             | SecurityPolicy::Aes128Sha256RsaOaep
-            | SecurityPolicy::Aes256Sha256RsaPss => {}
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256
+            | SecurityPolicy::EccNistP384 => {}
             _ => {
                 panic!("Unsupported security policy");
             }
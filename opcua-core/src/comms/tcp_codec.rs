@@ -11,10 +11,12 @@
 //! * MSG - Message chunk
 //! * OPN - Open Secure Channel message
 //! * CLO - Close Secure Channel message
+//! * RHE - Reverse Hello message
 use std::io;
 
 use bytes::{BufMut, BytesMut};
 use log::error;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::codec::{Decoder, Encoder};
 
 use opcua_types::{
@@ -26,7 +28,7 @@ use super::{
     message_chunk::MessageChunk,
     tcp_types::{
         AcknowledgeMessage, ErrorMessage, HelloMessage, MessageHeader, MessageType,
-        MESSAGE_HEADER_LEN,
+        ReverseHelloMessage, MESSAGE_HEADER_LEN,
     },
 };
 
@@ -42,8 +44,17 @@ pub enum Message {
     Error(ErrorMessage),
     /// Part of a general OPC-UA message.
     Chunk(MessageChunk),
+    /// Reverse hello message, sent by the server when it initiates
+    /// the connection to the client.
+    ReverseHello(ReverseHelloMessage),
 }
 
+/// Trait for byte streams the OPC-UA binary protocol can be run over,
+/// such as TCP sockets, Unix domain sockets or TLS streams.
+pub trait AsyncStream: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static> AsyncStream for T {}
+
 /// Implements a tokio codec that as close as possible, allows incoming data to be transformed into
 /// OPC UA message chunks with no intermediate buffers. Chunks are subsequently transformed into
 /// messages so there is still some buffers within message chunks, but not at the raw socket level.
@@ -98,6 +109,7 @@ impl Encoder<Message> for TcpCodec {
             Message::Acknowledge(msg) => self.write(msg, buf),
             Message::Error(msg) => self.write(msg, buf),
             Message::Chunk(msg) => self.write(msg, buf),
+            Message::ReverseHello(msg) => self.write(msg, buf),
         }
     }
 }
@@ -141,6 +153,10 @@ impl TcpCodec {
                 &mut buf,
                 decoding_options,
             )?)),
+            MessageType::ReverseHello => Ok(Message::ReverseHello(ReverseHelloMessage::decode(
+                &mut buf,
+                decoding_options,
+            )?)),
             MessageType::Chunk => Ok(Message::Chunk(MessageChunk::decode(
                 &mut buf,
                 decoding_options,
@@ -27,6 +27,8 @@ pub(crate) const HELLO_MESSAGE: &[u8] = b"HEL";
 pub(crate) const ACKNOWLEDGE_MESSAGE: &[u8] = b"ACK";
 /// Message header type for error messages.
 pub(crate) const ERROR_MESSAGE: &[u8] = b"ERR";
+/// Message header type for reverse hello messages.
+pub(crate) const REVERSE_HELLO_MESSAGE: &[u8] = b"RHE";
 
 /// ChunkIsFinal type for the final chunk in a message.
 pub(crate) const CHUNK_FINAL: u8 = b'F';
@@ -54,6 +56,8 @@ pub enum MessageType {
     Chunk,
     /// Fatal error, followed by shutting down the channel.
     Error,
+    /// REVERSE HELLO message, sent by the server on reverse connect.
+    ReverseHello,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -75,6 +79,7 @@ impl SimpleBinaryEncodable for MessageHeader {
             MessageType::Hello => stream.write_all(HELLO_MESSAGE),
             MessageType::Acknowledge => stream.write_all(ACKNOWLEDGE_MESSAGE),
             MessageType::Error => stream.write_all(ERROR_MESSAGE),
+            MessageType::ReverseHello => stream.write_all(REVERSE_HELLO_MESSAGE),
             MessageType::Chunk => {
                 panic!("Don't write chunks to stream with this call, use Chunk and Chunker");
             }
@@ -167,6 +172,7 @@ impl MessageHeader {
                 HELLO_MESSAGE => MessageType::Hello,
                 ACKNOWLEDGE_MESSAGE => MessageType::Acknowledge,
                 ERROR_MESSAGE => MessageType::Error,
+                REVERSE_HELLO_MESSAGE => MessageType::ReverseHello,
                 CHUNK_MESSAGE | OPEN_SECURE_CHANNEL_MESSAGE | CLOSE_SECURE_CHANNEL_MESSAGE => {
                     MessageType::Chunk
                 }
@@ -390,6 +396,71 @@ impl AcknowledgeMessage {
     }
 }
 
+/// Implementation of the RHE message in OPC UA, sent by the server
+/// when it initiates a connection to a client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReverseHelloMessage {
+    message_header: MessageHeader,
+    /// Application URI of the server sending the message.
+    pub server_uri: UAString,
+    /// Endpoint URL the client should use in its HELLO message.
+    pub endpoint_url: UAString,
+}
+
+impl SimpleBinaryEncodable for ReverseHelloMessage {
+    fn byte_len(&self) -> usize {
+        self.message_header.byte_len() + self.server_uri.byte_len() + self.endpoint_url.byte_len()
+    }
+
+    fn encode<S: Write + ?Sized>(&self, stream: &mut S) -> EncodingResult<()> {
+        self.message_header.encode(stream)?;
+        self.server_uri.encode(stream)?;
+        self.endpoint_url.encode(stream)
+    }
+}
+
+impl SimpleBinaryDecodable for ReverseHelloMessage {
+    fn decode<S: Read + ?Sized>(
+        stream: &mut S,
+        decoding_options: &DecodingOptions,
+    ) -> EncodingResult<Self> {
+        let message_header = MessageHeader::decode(stream, decoding_options)?;
+        let server_uri = UAString::decode(stream, decoding_options)?;
+        let endpoint_url = UAString::decode(stream, decoding_options)?;
+        Ok(ReverseHelloMessage {
+            message_header,
+            server_uri,
+            endpoint_url,
+        })
+    }
+}
+
+impl ReverseHelloMessage {
+    /// Maximum length of the server URI and endpoint URL, in bytes.
+    const MAX_URI_LEN: usize = 4096;
+
+    /// Create a new RHE message.
+    pub fn new(server_uri: &str, endpoint_url: &str) -> ReverseHelloMessage {
+        let mut msg = ReverseHelloMessage {
+            message_header: MessageHeader::new(MessageType::ReverseHello),
+            server_uri: UAString::from(server_uri),
+            endpoint_url: UAString::from(endpoint_url),
+        };
+        msg.message_header.message_size = msg.byte_len() as u32;
+        msg
+    }
+
+    /// Check that the server URI and endpoint URL are present and not too long.
+    pub fn is_valid(&self) -> bool {
+        let valid = |s: &UAString| {
+            s.value()
+                .as_ref()
+                .is_some_and(|v| v.len() <= Self::MAX_URI_LEN)
+        };
+        valid(&self.server_uri) && valid(&self.endpoint_url)
+    }
+}
+
 /// Implementation of the ERR message in OPC UA
 #[derive(Debug, Clone, PartialEq)]
 pub struct ErrorMessage {
@@ -450,10 +521,12 @@ impl ErrorMessage {
 mod tests {
     use std::io::Cursor;
 
-    use crate::comms::tcp_types::{AcknowledgeMessage, HelloMessage, MessageHeader, MessageType};
+    use crate::comms::tcp_types::{
+        AcknowledgeMessage, HelloMessage, MessageHeader, MessageType, ReverseHelloMessage,
+    };
     use opcua_types::{
         ApplicationDescription, ByteString, DecodingOptions, EndpointDescription,
-        MessageSecurityMode, SimpleBinaryDecodable, UAString,
+        MessageSecurityMode, SimpleBinaryDecodable, SimpleBinaryEncodable, UAString,
     };
 
     fn hello_data() -> Vec<u8> {
@@ -507,6 +580,32 @@ mod tests {
         assert_eq!(ack.max_chunk_count, 65535);
     }
 
+    #[test]
+    pub fn reverse_hello() {
+        let rhe = ReverseHelloMessage::new("urn:server", "opc.tcp://127.0.0.1:1234/");
+        let data = rhe.encode_to_vec();
+        assert_eq!(&data[0..4], b"RHEF");
+        assert_eq!(data.len(), rhe.message_header.message_size as usize);
+
+        let mut stream = Cursor::new(data);
+        let decoding_options = DecodingOptions::test();
+        let decoded = ReverseHelloMessage::decode(&mut stream, &decoding_options).unwrap();
+        assert_eq!(
+            decoded.message_header.message_type,
+            MessageType::ReverseHello
+        );
+        assert_eq!(decoded, rhe);
+        assert!(decoded.is_valid());
+
+        let rhe = ReverseHelloMessage::new("", "opc.tcp://127.0.0.1:1234/");
+        assert!(rhe.is_valid());
+        let rhe = ReverseHelloMessage {
+            server_uri: UAString::null(),
+            ..rhe
+        };
+        assert!(!rhe.is_valid());
+    }
+
     #[test]
     fn endpoint_url() {
         // Ensure hello with None endpoint is invalid
@@ -11,6 +11,10 @@ use opcua_types::status_code::StatusCode;
 
 /// Scheme for OPC-UA TCP.
 pub const OPC_TCP_SCHEME: &str = "opc.tcp";
+/// Scheme for OPC-UA over secure WebSockets.
+pub const OPC_WSS_SCHEME: &str = "opc.wss";
+/// Scheme for OPC-UA binary over HTTPS.
+pub const HTTPS_SCHEME: &str = "https";
 
 /// Creates a `Url` from the input string, supplying a default port if necessary.
 fn opc_url_from_str(s: &str) -> Result<Url, url::ParseError> {
@@ -69,9 +73,9 @@ pub fn server_url_from_endpoint_url(
     })
 }
 
-/// Check if this is a valid OPC-UA TCP URL.
+/// Check if this is a valid OPC-UA URL, using either TCP, WebSockets or HTTPS.
 pub fn is_valid_opc_ua_url(url: &str) -> bool {
-    is_opc_ua_binary_url(url)
+    is_opc_ua_binary_url(url) || is_opc_ua_websocket_url(url) || is_opc_ua_https_url(url)
 }
 
 /// Check if this is an OPC-UA TCP URL.
@@ -83,6 +87,24 @@ pub fn is_opc_ua_binary_url(url: &str) -> bool {
     }
 }
 
+/// Check if this is an OPC-UA secure WebSocket URL.
+pub fn is_opc_ua_websocket_url(url: &str) -> bool {
+    if let Ok(url) = opc_url_from_str(url) {
+        url.scheme() == OPC_WSS_SCHEME
+    } else {
+        false
+    }
+}
+
+/// Check if this is an HTTPS URL.
+pub fn is_opc_ua_https_url(url: &str) -> bool {
+    if let Ok(url) = opc_url_from_str(url) {
+        url.scheme() == HTTPS_SCHEME
+    } else {
+        false
+    }
+}
+
 /// Error returned when getting host name from URL.
 pub enum HostnameFromUrlError {
     /// URL failed to parse.
@@ -133,6 +155,15 @@ mod tests {
             "opc.tcp://[FEDC:BA98:7654:3210:FEDC:BA98:7654:3210]:80/xyz"
         ));
         assert!(!is_opc_ua_binary_url("http://foo/xyz"));
+        assert!(!is_opc_ua_binary_url("opc.wss://foo/xyz"));
+        assert!(is_opc_ua_websocket_url("opc.wss://foo/xyz"));
+        assert!(!is_opc_ua_websocket_url("opc.tcp://foo/xyz"));
+        assert!(is_valid_opc_ua_url("opc.wss://foo/xyz"));
+        assert!(is_valid_opc_ua_url("opc.tcp://foo/xyz"));
+        assert!(is_opc_ua_https_url("https://foo/xyz"));
+        assert!(!is_opc_ua_https_url("http://foo/xyz"));
+        assert!(is_valid_opc_ua_url("https://foo/xyz"));
+        assert!(!is_valid_opc_ua_url("http://foo/xyz"));
     }
 
     #[test]
@@ -0,0 +1,234 @@
+//! Implementation of the OPC-UA WebSocket transport mapping, as described in Part 6.
+//!
+//! UA-TCP messages are sent over WebSockets using the `opcua+uacp` subprotocol,
+//! with each message chunk in a separate binary WebSocket message. This module
+//! adapts a WebSocket connection to a plain byte stream, so the regular OPC-UA binary
+//! protocol can run on top of it.
+//!
+//! TLS is not handled here. For `opc.wss` the underlying stream is expected to be a
+//! TLS stream, or the connection must go through a TLS-terminating proxy.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_tungstenite::{
+    tungstenite::{
+        client::IntoClientRequest,
+        handshake::server::{ErrorResponse, Request, Response},
+        http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderValue, StatusCode},
+        Message,
+    },
+    WebSocketStream,
+};
+
+use super::{tcp_codec::AsyncStream, tcp_types::MESSAGE_HEADER_LEN, url::OPC_WSS_SCHEME};
+
+/// WebSocket subprotocol for UA-TCP messages with the binary encoding.
+pub const OPCUA_UACP_SUBPROTOCOL: &str = "opcua+uacp";
+
+/// Byte stream carrying OPC-UA message chunks over a WebSocket connection.
+///
+/// Bytes written to the stream are split into message chunks, each sent as
+/// a single binary WebSocket message. Bytes read from the stream are the contents
+/// of received binary messages.
+pub struct WebSocketChunkStream<S> {
+    inner: WebSocketStream<S>,
+    read_buf: Bytes,
+    write_buf: BytesMut,
+    needs_flush: bool,
+    closed: bool,
+}
+
+fn ws_error_to_io(err: tokio_tungstenite::tungstenite::Error) -> io::Error {
+    match err {
+        tokio_tungstenite::tungstenite::Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+/// Check if the subprotocol header in a WebSocket handshake includes `opcua+uacp`.
+fn has_uacp_subprotocol(value: Option<&HeaderValue>) -> bool {
+    value
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|p| p.trim() == OPCUA_UACP_SUBPROTOCOL))
+}
+
+impl<S: AsyncStream> WebSocketChunkStream<S> {
+    fn new(inner: WebSocketStream<S>) -> Self {
+        Self {
+            inner,
+            read_buf: Bytes::new(),
+            write_buf: BytesMut::new(),
+            needs_flush: false,
+            closed: false,
+        }
+    }
+
+    /// Perform the client side of the WebSocket handshake on `stream`, requesting
+    /// the `opcua+uacp` subprotocol. `endpoint_url` is the `opc.wss` URL of the server.
+    pub async fn connect(stream: S, endpoint_url: &str) -> io::Result<Self> {
+        let Some(path) = endpoint_url.strip_prefix(&format!("{OPC_WSS_SCHEME}://")) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Endpoint URL {endpoint_url} is not an {OPC_WSS_SCHEME} URL"),
+            ));
+        };
+        let mut request = format!("wss://{path}")
+            .into_client_request()
+            .map_err(ws_error_to_io)?;
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(OPCUA_UACP_SUBPROTOCOL),
+        );
+        let (inner, response) = tokio_tungstenite::client_async(request, stream)
+            .await
+            .map_err(ws_error_to_io)?;
+        if !has_uacp_subprotocol(response.headers().get(SEC_WEBSOCKET_PROTOCOL)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Server did not accept the {OPCUA_UACP_SUBPROTOCOL} subprotocol"),
+            ));
+        }
+        Ok(Self::new(inner))
+    }
+
+    /// Perform the server side of the WebSocket handshake on `stream`. Clients that
+    /// do not request the `opcua+uacp` subprotocol are rejected.
+    // The error type of the handshake callback is defined by tungstenite.
+    #[allow(clippy::result_large_err)]
+    pub async fn accept(stream: S) -> io::Result<Self> {
+        let inner = tokio_tungstenite::accept_hdr_async(
+            stream,
+            |request: &Request, mut response: Response| {
+                if !has_uacp_subprotocol(request.headers().get(SEC_WEBSOCKET_PROTOCOL)) {
+                    let mut response = ErrorResponse::new(Some(format!(
+                        "Missing {OPCUA_UACP_SUBPROTOCOL} subprotocol"
+                    )));
+                    *response.status_mut() = StatusCode::BAD_REQUEST;
+                    return Err(response);
+                }
+                response.headers_mut().insert(
+                    SEC_WEBSOCKET_PROTOCOL,
+                    HeaderValue::from_static(OPCUA_UACP_SUBPROTOCOL),
+                );
+                Ok(response)
+            },
+        )
+        .await
+        .map_err(ws_error_to_io)?;
+        Ok(Self::new(inner))
+    }
+
+    /// Get the length of the next complete message chunk in the write buffer, if any.
+    fn next_chunk_len(&self) -> io::Result<Option<usize>> {
+        if self.write_buf.len() < MESSAGE_HEADER_LEN {
+            return Ok(None);
+        }
+        let len = u32::from_le_bytes([
+            self.write_buf[4],
+            self.write_buf[5],
+            self.write_buf[6],
+            self.write_buf[7],
+        ]) as usize;
+        if len < MESSAGE_HEADER_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid message chunk size {len}"),
+            ));
+        }
+        Ok((self.write_buf.len() >= len).then_some(len))
+    }
+
+    /// Send any complete message chunks in the write buffer, and flush the connection.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while let Some(len) = self.next_chunk_len()? {
+            ready!(self.inner.poll_ready_unpin(cx)).map_err(ws_error_to_io)?;
+            let chunk = self.write_buf.split_to(len).freeze();
+            self.inner
+                .start_send_unpin(Message::Binary(chunk))
+                .map_err(ws_error_to_io)?;
+            self.needs_flush = true;
+        }
+        if self.needs_flush {
+            ready!(self.inner.poll_flush_unpin(cx)).map_err(ws_error_to_io)?;
+            self.needs_flush = false;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncStream> AsyncRead for WebSocketChunkStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        // Nothing else may be driving the connection, so make progress on
+        // outgoing data while waiting for incoming messages.
+        if let Poll::Ready(Err(e)) = this.poll_send(cx) {
+            return Poll::Ready(Err(e));
+        }
+
+        loop {
+            if !this.read_buf.is_empty() {
+                let len = this.read_buf.len().min(buf.remaining());
+                buf.put_slice(&this.read_buf[..len]);
+                this.read_buf.advance(len);
+                return Poll::Ready(Ok(()));
+            }
+            if this.closed {
+                return Poll::Ready(Ok(()));
+            }
+            match ready!(this.inner.poll_next_unpin(cx)) {
+                Some(Ok(Message::Binary(data))) => this.read_buf = data,
+                Some(Ok(Message::Close(_))) | None => this.closed = true,
+                Some(Ok(Message::Text(_))) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Received text message on OPC-UA WebSocket connection",
+                    )))
+                }
+                // Ping and pong messages are handled by the WebSocket implementation.
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Poll::Ready(Err(ws_error_to_io(e))),
+            }
+        }
+    }
+}
+
+impl<S: AsyncStream> AsyncWrite for WebSocketChunkStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        // Make sure previously written chunks are sent before accepting more data,
+        // so that the write buffer does not grow without bounds.
+        ready!(this.poll_send(cx))?;
+        this.write_buf.extend_from_slice(buf);
+        // Try to send right away. If this does not complete, sending continues on
+        // the next read or write.
+        if let Poll::Ready(Err(e)) = this.poll_send(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_send(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_send(cx))?;
+        this.inner.poll_close_unpin(cx).map_err(ws_error_to_io)
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::Debug;
 use std::io::Cursor;
 
 use opcua_crypto::{
-    pkey::PrivateKey,
+    pkey::{EccCurve, PrivateKey},
     security_policy::SecurityPolicy,
     x509::{X509Data, X509},
 };
@@ -14,7 +14,10 @@ use opcua_types::{
     UAString,
 };
 
-use crate::{comms::secure_channel::SecureChannel, RequestMessage};
+use crate::{
+    comms::secure_channel::{Role, SecureChannel},
+    RequestMessage,
+};
 
 pub(crate) fn serialize_test_and_return<T>(value: T) -> T
 where
@@ -64,7 +67,7 @@ fn make_secure_channel(
     secure_channel.set_security_policy(security_policy);
     secure_channel.set_local_nonce(&local_nonce);
     secure_channel.set_remote_nonce(&remote_nonce);
-    secure_channel.derive_keys();
+    secure_channel.derive_keys().unwrap();
     secure_channel
 }
 
@@ -73,6 +76,9 @@ fn make_secure_channels(
     security_mode: MessageSecurityMode,
     security_policy: SecurityPolicy,
 ) -> (SecureChannel, SecureChannel) {
+    if security_policy.is_ecc() {
+        return make_ecc_secure_channels(security_mode, security_policy);
+    }
     let local_nonce = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
     let remote_nonce = vec![
         16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
@@ -93,6 +99,32 @@ fn make_secure_channels(
     (secure_channel1, secure_channel2)
 }
 
+/// Makes a client and a server secure channel for an ECC security policy, which derive their
+/// keys from the ephemeral keys they exchange as nonces
+fn make_ecc_secure_channels(
+    security_mode: MessageSecurityMode,
+    security_policy: SecurityPolicy,
+) -> (SecureChannel, SecureChannel) {
+    let mut client = SecureChannel::new_no_certificate_store();
+    let mut server = SecureChannel::new_no_certificate_store();
+    client.set_role(Role::Client);
+    server.set_role(Role::Server);
+    for secure_channel in [&mut client, &mut server] {
+        secure_channel.set_security_mode(security_mode);
+        secure_channel.set_security_policy(security_policy);
+        secure_channel.create_random_nonce();
+    }
+    client
+        .set_remote_nonce_from_byte_string(&server.local_nonce_as_byte_string())
+        .unwrap();
+    server
+        .set_remote_nonce_from_byte_string(&client.local_nonce_as_byte_string())
+        .unwrap();
+    client.derive_keys().unwrap();
+    server.derive_keys().unwrap();
+    (client, server)
+}
+
 fn make_open_secure_channel_response() -> OpenSecureChannelResponse {
     OpenSecureChannelResponse {
         response_header: ResponseHeader {
@@ -164,6 +196,10 @@ fn make_test_cert_4096() -> (X509, PrivateKey) {
     make_test_cert(4096)
 }
 
+fn make_test_ecc_cert(curve: EccCurve) -> (X509, PrivateKey) {
+    X509::ecc_cert_and_pkey(&X509Data::sample_cert(), curve).unwrap()
+}
+
 struct Test;
 
 impl Test {
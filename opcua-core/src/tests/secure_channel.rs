@@ -53,13 +53,17 @@ fn test_asymmetric_encrypt_decrypt(
     for i in 0..2 {
         // Create a cert and private key pretending to be us and them. Keysizes are different to shake out issues with
         // signature lengths. Encrypting key will be 4096 bits to test extra padding functionality.
-        let (our_cert, our_key) = if i == 0 {
+        let (our_cert, our_key) = if let Some(curve) = security_policy.ecc_curve() {
+            make_test_ecc_cert(curve)
+        } else if i == 0 {
             make_test_cert_4096()
         } else {
             make_test_cert_2048()
         };
         //    let (our_cert, our_key) = make_test_cert_1024();
-        let (their_cert, their_key) = if i == 0 {
+        let (their_cert, their_key) = if let Some(curve) = security_policy.ecc_curve() {
+            make_test_ecc_cert(curve)
+        } else if i == 0 {
             make_test_cert_2048()
         } else {
             make_test_cert_4096()
@@ -148,6 +152,30 @@ fn asymmetric_sign_and_encrypt_message_chunk_basic256sha256() {
     );
 }
 
+#[test]
+fn asymmetric_sign_message_chunk_ecc_nist_p256() {
+    use crate::ResponseMessage;
+
+    let _ = Test::setup();
+    error!("asymmetric_sign_message_chunk_ecc_nist_p256");
+    let m: ResponseMessage = make_open_secure_channel_response().into();
+    test_asymmetric_encrypt_decrypt(
+        m,
+        MessageSecurityMode::SignAndEncrypt,
+        SecurityPolicy::EccNistP256,
+    );
+}
+
+#[test]
+fn asymmetric_sign_message_chunk_ecc_nist_p384() {
+    use crate::ResponseMessage;
+
+    let _ = Test::setup();
+    error!("asymmetric_sign_message_chunk_ecc_nist_p384");
+    let m: ResponseMessage = make_open_secure_channel_response().into();
+    test_asymmetric_encrypt_decrypt(m, MessageSecurityMode::Sign, SecurityPolicy::EccNistP384);
+}
+
 /// Create a message, encode it to a chunk, sign the chunk, verify the signature and decode back to message
 #[test]
 fn symmetric_sign_message_chunk_basic128rsa15() {
@@ -217,3 +245,49 @@ fn symmetric_sign_and_encrypt_message_chunk_basic256sha256() {
         SecurityPolicy::Basic256Sha256,
     );
 }
+
+#[test]
+fn symmetric_sign_message_chunk_ecc_nist_p256() {
+    let _ = Test::setup();
+    error!("symmetric_sign_message_chunk_ecc_nist_p256");
+    test_symmetric_encrypt_decrypt(
+        make_sample_message(),
+        MessageSecurityMode::Sign,
+        SecurityPolicy::EccNistP256,
+    );
+}
+
+#[test]
+fn symmetric_sign_message_chunk_ecc_nist_p384() {
+    let _ = Test::setup();
+    error!("symmetric_sign_message_chunk_ecc_nist_p384");
+    test_symmetric_encrypt_decrypt(
+        make_sample_message(),
+        MessageSecurityMode::Sign,
+        SecurityPolicy::EccNistP384,
+    );
+}
+
+/// Create a message, encode it to a chunk, sign the chunk, encrypt, decrypt, verify the signature and decode back to message
+#[test]
+fn symmetric_sign_and_encrypt_message_chunk_ecc_nist_p256() {
+    let _ = Test::setup();
+    error!("symmetric_sign_and_encrypt_message_chunk_ecc_nist_p256");
+    test_symmetric_encrypt_decrypt(
+        make_sample_message(),
+        MessageSecurityMode::SignAndEncrypt,
+        SecurityPolicy::EccNistP256,
+    );
+}
+
+/// Create a message, encode it to a chunk, sign the chunk, encrypt, decrypt, verify the signature and decode back to message
+#[test]
+fn symmetric_sign_and_encrypt_message_chunk_ecc_nist_p384() {
+    let _ = Test::setup();
+    error!("symmetric_sign_and_encrypt_message_chunk_ecc_nist_p384");
+    test_symmetric_encrypt_decrypt(
+        make_sample_message(),
+        MessageSecurityMode::SignAndEncrypt,
+        SecurityPolicy::EccNistP384,
+    );
+}
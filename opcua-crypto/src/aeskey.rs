@@ -128,7 +128,9 @@ impl AesKey {
             | SecurityPolicy::Aes128Sha256RsaOaep
             | SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
-            | SecurityPolicy::Aes256Sha256RsaPss => AES_BLOCK_SIZE,
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256
+            | SecurityPolicy::EccNistP384 => AES_BLOCK_SIZE,
             _ => 0,
         }
     }
@@ -140,7 +142,9 @@ impl AesKey {
             | SecurityPolicy::Aes128Sha256RsaOaep
             | SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
-            | SecurityPolicy::Aes256Sha256RsaPss => AES_BLOCK_SIZE,
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256
+            | SecurityPolicy::EccNistP384 => AES_BLOCK_SIZE,
             _ => 0,
         }
     }
@@ -148,11 +152,14 @@ impl AesKey {
     /// Get the AES key length.
     pub fn key_length(&self) -> usize {
         match self.security_policy {
-            SecurityPolicy::Basic128Rsa15 | SecurityPolicy::Aes128Sha256RsaOaep => AES128_KEY_SIZE,
+            SecurityPolicy::Basic128Rsa15
+            | SecurityPolicy::Aes128Sha256RsaOaep
+            | SecurityPolicy::EccNistP256 => AES128_KEY_SIZE,
 
             SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
-            | SecurityPolicy::Aes256Sha256RsaPss => AES256_KEY_SIZE,
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP384 => AES256_KEY_SIZE,
             _ => 0,
         }
     }
@@ -160,13 +167,14 @@ impl AesKey {
     /// Encrypt data in `src` into `dst`.
     pub fn encrypt(&self, src: &[u8], iv: &[u8], dst: &mut [u8]) -> EncryptResult {
         match self.security_policy {
-            SecurityPolicy::Basic128Rsa15 | SecurityPolicy::Aes128Sha256RsaOaep => {
-                self.encrypt_aes128_cbc(src, iv, dst)
-            }
+            SecurityPolicy::Basic128Rsa15
+            | SecurityPolicy::Aes128Sha256RsaOaep
+            | SecurityPolicy::EccNistP256 => self.encrypt_aes128_cbc(src, iv, dst),
 
             SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
-            | SecurityPolicy::Aes256Sha256RsaPss => self.encrypt_aes256_cbc(src, iv, dst),
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP384 => self.encrypt_aes256_cbc(src, iv, dst),
 
             _ => Err(Error::new(
                 StatusCode::BadUnexpectedError,
@@ -178,13 +186,14 @@ impl AesKey {
     /// Decrypts data using AES. The initialization vector is the nonce generated for the secure channel
     pub fn decrypt(&self, src: &[u8], iv: &[u8], dst: &mut [u8]) -> EncryptResult {
         match self.security_policy {
-            SecurityPolicy::Basic128Rsa15 | SecurityPolicy::Aes128Sha256RsaOaep => {
-                self.decrypt_aes128_cbc(src, iv, dst)
-            }
+            SecurityPolicy::Basic128Rsa15
+            | SecurityPolicy::Aes128Sha256RsaOaep
+            | SecurityPolicy::EccNistP256 => self.decrypt_aes128_cbc(src, iv, dst),
 
             SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
-            | SecurityPolicy::Aes256Sha256RsaPss => self.decrypt_aes256_cbc(src, iv, dst),
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP384 => self.decrypt_aes256_cbc(src, iv, dst),
 
             _ => Err(Error::new(
                 StatusCode::BadUnexpectedError,
@@ -0,0 +1,653 @@
+// OPCUA for Rust
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (C) 2017-2024 Adam Lock
+
+//! A certificate store keeping private keys and certificates in a PKI directory on disk.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use log::{error, info, trace, warn};
+
+use opcua_types::status_code::StatusCode;
+
+use super::{cert_name, CertificateStore, ValidationOptions};
+use crate::{
+    crl::CertificateRevocationList,
+    pkey::{EccCurve, PrivateKey},
+    thumbprint::Thumbprint,
+    x509::{X509Data, X509},
+};
+
+use x509_cert;
+
+/// Default path to the applications own certificate
+const OWN_CERTIFICATE_PATH: &str = "own/cert.der";
+/// Default path to the applications own private key
+const OWN_PRIVATE_KEY_PATH: &str = "private/private.pem";
+/// The directory holding trusted certificates
+const TRUSTED_CERTS_DIR: &str = "trusted";
+/// The directory holding rejected certificates
+const REJECTED_CERTS_DIR: &str = "rejected";
+/// The directory holding CA certificates that are not trusted, but are used to build
+/// certificate chains
+const ISSUER_CERTS_DIR: &str = "issuers";
+/// The directory holding certificate revocation lists
+const CRL_DIR: &str = "crl";
+
+/// A [`CertificateStore`] keeping the certificates in a PKI directory on disk. Each cert and
+/// key is stored in its own file, with different directories representing trusted, rejected
+/// and issuer certs, and revocation lists.
+pub struct FileSystemCertificateStore {
+    /// Path to the applications own certificate
+    own_certificate_path: PathBuf,
+    /// Path to the applications own private key
+    own_private_key_path: PathBuf,
+    /// Path to the certificate store on disk
+    pub(crate) pki_path: PathBuf,
+    /// Options used when validating certificates
+    validation_options: ValidationOptions,
+}
+
+impl FileSystemCertificateStore {
+    /// Sets up the certificate store to the specified PKI directory.
+    /// It is a bad idea to have more than one running instance pointing to the same path
+    /// location on disk.
+    pub fn new(pki_path: &Path) -> FileSystemCertificateStore {
+        FileSystemCertificateStore {
+            own_certificate_path: PathBuf::from(OWN_CERTIFICATE_PATH),
+            own_private_key_path: PathBuf::from(OWN_PRIVATE_KEY_PATH),
+            pki_path: pki_path.to_path_buf(),
+            validation_options: ValidationOptions::default(),
+        }
+    }
+
+    /// Create a new certificate store with application certificate from the given
+    /// `cert_path`.
+    pub fn new_with_x509_data<X>(
+        pki_path: &Path,
+        overwrite: bool,
+        cert_path: Option<&Path>,
+        pkey_path: Option<&Path>,
+        x509_data: Option<X>,
+    ) -> (FileSystemCertificateStore, Option<X509>, Option<PrivateKey>)
+    where
+        X: Into<X509Data>,
+    {
+        let mut certificate_store = FileSystemCertificateStore::new(pki_path);
+        if let (Some(cert_path), Some(pkey_path)) = (cert_path, pkey_path) {
+            certificate_store.own_certificate_path = cert_path.to_path_buf();
+            certificate_store.own_private_key_path = pkey_path.to_path_buf();
+        }
+        let (cert, pkey) = if certificate_store.ensure_pki_path().is_err() {
+            error!("Folder for storing certificates cannot be examined so server has no application instance certificate or private key.");
+            (None, None)
+        } else {
+            let cert = certificate_store.read_own_cert();
+            let pkey = certificate_store.read_own_pkey();
+            match (cert, pkey, x509_data) {
+                (Ok(cert), Ok(pkey), _) => (Some(cert), Some(pkey)),
+                (_, _, Some(x509_data)) => {
+                    info!("Creating sample application instance certificate and private key");
+                    let x509_data = x509_data.into();
+                    let result = certificate_store
+                        .create_and_store_application_instance_cert(&x509_data, overwrite);
+                    match result {
+                        Ok((cert, pkey)) => (Some(cert), Some(pkey)),
+                        Err(err) => {
+                            error!("Certificate creation failed, error = {}", err);
+                            (None, None)
+                        }
+                    }
+                }
+                (Err(e1), Err(e2), _) => {
+                    error!("Failed to get cert and private key: {e1}, {e2}");
+                    (None, None)
+                }
+                (Err(e), _, _) | (_, Err(e), _) => {
+                    error!("Failed to get cert or private key: {e}");
+                    (None, None)
+                }
+            }
+        };
+        (certificate_store, cert, pkey)
+    }
+
+    /// Reads a private key from a path on disk.
+    pub fn read_pkey(path: &Path) -> Result<PrivateKey, String> {
+        if let Ok(pkey) = PrivateKey::read_pem_file(path) {
+            return Ok(pkey);
+        }
+
+        Err(format!("Cannot read pkey from path {:?}", path))
+    }
+
+    /// Create a certificate and key pair to the specified locations
+    pub fn create_certificate_and_key(
+        args: &X509Data,
+        overwrite: bool,
+        cert_path: &Path,
+        pkey_path: &Path,
+    ) -> Result<(X509, PrivateKey), String> {
+        let (cert, pkey) = X509::cert_and_pkey(args)?;
+        FileSystemCertificateStore::store_cert_and_key(cert, pkey, overwrite, cert_path, pkey_path)
+    }
+
+    /// Create a certificate and key pair on the given elliptic curve, for the ECC security
+    /// policies, to the specified locations
+    pub fn create_ecc_certificate_and_key(
+        args: &X509Data,
+        curve: EccCurve,
+        overwrite: bool,
+        cert_path: &Path,
+        pkey_path: &Path,
+    ) -> Result<(X509, PrivateKey), String> {
+        let (cert, pkey) = X509::ecc_cert_and_pkey(args, curve)?;
+        FileSystemCertificateStore::store_cert_and_key(cert, pkey, overwrite, cert_path, pkey_path)
+    }
+
+    fn store_cert_and_key(
+        cert: X509,
+        pkey: PrivateKey,
+        overwrite: bool,
+        cert_path: &Path,
+        pkey_path: &Path,
+    ) -> Result<(X509, PrivateKey), String> {
+        // Write the public cert
+        let _ = FileSystemCertificateStore::store_cert(&cert, cert_path, overwrite)?;
+
+        // Write the private key
+        let _ = FileSystemCertificateStore::store_pkey(&pkey, pkey_path, overwrite)?;
+        Ok((cert, pkey))
+    }
+
+    /// Writes a private key in .pem format to the specified path
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn store_pkey(pkey: &PrivateKey, path: &Path, overwrite: bool) -> Result<usize, String> {
+        let pem = pkey
+            .to_pem()
+            .map_err(|e| format!("Could not encode private key: {e}"))?;
+        FileSystemCertificateStore::write_to_file(pem.as_bytes(), path, overwrite)
+    }
+
+    /// This function will use the supplied arguments to create an Application Instance Certificate
+    /// consisting of a X509v3 certificate and public/private key pair. The cert (including pubkey)
+    /// and private key will be written to disk under the pki path.
+    pub fn create_and_store_application_instance_cert(
+        &self,
+        args: &X509Data,
+        overwrite: bool,
+    ) -> Result<(X509, PrivateKey), String> {
+        FileSystemCertificateStore::create_certificate_and_key(
+            args,
+            overwrite,
+            &self.own_certificate_path(),
+            &self.own_private_key_path(),
+        )
+    }
+
+    /// Like [`FileSystemCertificateStore::create_and_store_application_instance_cert`], but the key pair
+    /// is on the given elliptic curve, for servers and clients using the ECC security policies.
+    pub fn create_and_store_ecc_application_instance_cert(
+        &self,
+        args: &X509Data,
+        curve: EccCurve,
+        overwrite: bool,
+    ) -> Result<(X509, PrivateKey), String> {
+        FileSystemCertificateStore::create_ecc_certificate_and_key(
+            args,
+            curve,
+            overwrite,
+            &self.own_certificate_path(),
+            &self.own_private_key_path(),
+        )
+    }
+
+    /// Returns a certificate file name from the cert's issuer and thumbprint fields.
+    /// File name is either "prefix - \[thumbprint\].der" or "thumbprint.der" depending on
+    /// the cert's common name being empty or not
+    pub fn cert_file_name(cert: &X509) -> String {
+        format!("{}.der", cert_name(cert))
+    }
+
+    /// Ensures that the cert provided is the same as the one specified by a path. This is a
+    /// security check to stop someone from renaming a cert on disk to match another cert and
+    /// somehow bypassing or subverting a check. The disk cert must exactly match the memory cert
+    /// or the test is assumed to fail.
+    fn ensure_cert_and_file_are_the_same(cert: &X509, cert_path: &Path) -> bool {
+        if !cert_path.exists() {
+            trace!("Cannot find cert on disk");
+            false
+        } else {
+            match FileSystemCertificateStore::read_cert(cert_path) {
+                Ok(file_der) => {
+                    // Compare the buffers
+                    trace!("Comparing cert on disk to memory");
+                    let der;
+                    {
+                        let r = cert.to_der();
+                        match r {
+                            Err(_) => return false,
+                            Ok(val) => der = val,
+                        }
+                    }
+
+                    let target_der;
+                    {
+                        let r = file_der.to_der();
+                        match r {
+                            Err(_) => return false,
+                            Ok(val) => target_der = val,
+                        }
+                    }
+
+                    der == target_der
+                }
+                Err(err) => {
+                    trace!("Cannot read cert from disk {:?} - {}", cert_path, err);
+                    // No cert2 to compare to
+                    false
+                }
+            }
+        }
+    }
+
+    /// Creates the PKI directory structure
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    pub fn ensure_pki_path(&self) -> Result<(), String> {
+        let mut path = self.pki_path.clone();
+        let subdirs = [
+            TRUSTED_CERTS_DIR,
+            REJECTED_CERTS_DIR,
+            ISSUER_CERTS_DIR,
+            CRL_DIR,
+        ];
+        for subdir in &subdirs {
+            path.push(subdir);
+            FileSystemCertificateStore::ensure_dir(&path)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// Ensure the directory exists, creating it if necessary
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn ensure_dir(path: &Path) -> Result<(), String> {
+        if path.exists() {
+            if !path.is_dir() {
+                Err(format!("{} is not a directory ", path.display()))
+            } else {
+                Ok(())
+            }
+        } else {
+            std::fs::create_dir_all(path)
+                .map_err(|_| format!("Cannot make directories for {}", path.display()))
+        }
+    }
+
+    /// Get path to application instance certificate
+    pub fn own_certificate_path(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.pki_path);
+        path.push(&self.own_certificate_path);
+        path
+    }
+
+    /// Get path to application instance private key
+    pub fn own_private_key_path(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.pki_path);
+        path.push(&self.own_private_key_path);
+        path
+    }
+
+    /// Get the path to the rejected certs dir
+    pub fn rejected_certs_dir(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.pki_path);
+        path.push(REJECTED_CERTS_DIR);
+        path
+    }
+
+    /// Get the path to the trusted certs dir
+    pub fn trusted_certs_dir(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.pki_path);
+        path.push(TRUSTED_CERTS_DIR);
+        path
+    }
+
+    /// Get the path to the issuer certs dir
+    pub fn issuer_certs_dir(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.pki_path);
+        path.push(ISSUER_CERTS_DIR);
+        path
+    }
+
+    /// Get the path to the certificate revocation lists dir
+    pub fn crl_dir(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.pki_path);
+        path.push(CRL_DIR);
+        path
+    }
+
+    /// Writes a cert to the given directory, named after the cert. If the write succeeds, the
+    /// function returns a path to the written file.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn store_cert_in_dir(cert: &X509, dir: PathBuf) -> Result<PathBuf, String> {
+        let mut cert_path = dir;
+        cert_path.push(FileSystemCertificateStore::cert_file_name(cert));
+        let _ = FileSystemCertificateStore::store_cert(cert, &cert_path, true)?;
+        Ok(cert_path)
+    }
+
+    /// Checks if a cert with the same file name exists in the given directory.
+    fn has_cert_in_dir(cert: &X509, dir: PathBuf, description: &str) -> Result<bool, StatusCode> {
+        if !dir.exists() {
+            error!(
+                "Path for {} certificates {} does not exist",
+                description,
+                dir.display()
+            );
+            return Err(StatusCode::BadUnexpectedError);
+        }
+        let mut cert_path = dir;
+        cert_path.push(FileSystemCertificateStore::cert_file_name(cert));
+        Ok(cert_path.exists())
+    }
+
+    /// Writes a cert to the specified directory
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn store_cert(cert: &X509, path: &Path, overwrite: bool) -> Result<usize, String> {
+        let der = cert.to_der().unwrap();
+        info!("Writing X509 cert to {}", path.display());
+        match path.extension() {
+            Some(v) if v == "pem" => {
+                let pem = x509_cert::der::pem::encode_string(
+                    "CERTIFICATE",
+                    x509_cert::der::pem::LineEnding::LF,
+                    &der,
+                )
+                .map_err(|e| format!("Could not encode cert: {e}"))?;
+                FileSystemCertificateStore::write_to_file(pem.as_bytes(), path, overwrite)
+            }
+            _ => FileSystemCertificateStore::write_to_file(&der, path, overwrite),
+        }
+    }
+
+    /// Removes every file in a directory and writes the given files into it instead.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn replace_files_in_dir(dir: &Path, files: &[(String, Vec<u8>)]) -> Result<(), String> {
+        FileSystemCertificateStore::ensure_dir(dir)?;
+        let entries = std::fs::read_dir(dir)
+            .map_err(|_| format!("Cannot read directory {}", dir.display()))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                std::fs::remove_file(&path)
+                    .map_err(|_| format!("Cannot remove file {}", path.display()))?;
+            }
+        }
+        for (name, bytes) in files {
+            let _ = FileSystemCertificateStore::write_to_file(bytes, &dir.join(name), true)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the certificates in a directory, naming each file after its cert.
+    fn replace_certs_in_dir(dir: &Path, certs: &[X509]) -> Result<(), String> {
+        let files = certs
+            .iter()
+            .map(|cert| {
+                cert.to_der()
+                    .map(|der| (FileSystemCertificateStore::cert_file_name(cert), der))
+                    .map_err(|_| format!("Could not encode cert {}", cert_name(cert)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        FileSystemCertificateStore::replace_files_in_dir(dir, &files)
+    }
+
+    /// Reads an X509 certificate in .def or .pem format from disk
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    pub fn read_cert(path: &Path) -> Result<X509, String> {
+        let file = File::open(path);
+        if file.is_err() {
+            return Err(format!("Could not open cert file {}", path.display()));
+        }
+
+        let mut file: File = file.unwrap();
+        let mut cert = Vec::new();
+        let bytes_read = file.read_to_end(&mut cert);
+        if bytes_read.is_err() {
+            return Err(format!(
+                "Could not read bytes from cert file {}",
+                path.display()
+            ));
+        }
+
+        let cert = match path.extension() {
+            Some(v) if v == "der" => X509::from_der(&cert),
+            Some(v) if v == "pem" => X509::from_pem(&cert),
+            _ => return Err("Only .der and .pem certificates are supported".to_string()),
+        };
+
+        match cert {
+            Err(_) => Err(format!(
+                "Could not read cert from cert file {}",
+                path.display()
+            )),
+            Ok(val) => Ok(val),
+        }
+    }
+
+    /// Reads all certificates in a directory, skipping files that cannot be read
+    fn read_certs_in_dir(dir: &Path) -> Vec<X509> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(
+                |entry| match FileSystemCertificateStore::read_cert(&entry.path()) {
+                    Ok(cert) => Some(cert),
+                    Err(err) => {
+                        warn!("Skipping file in certificate directory: {}", err);
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Reads the certificate revocation lists in a directory, skipping files that
+    /// cannot be read
+    fn read_crls_in_dir(dir: &Path) -> Vec<CertificateRevocationList> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(
+                |entry| match FileSystemCertificateStore::read_crl(&entry.path()) {
+                    Ok(crl) => Some(crl),
+                    Err(err) => {
+                        warn!("Skipping file in CRL directory: {}", err);
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Reads a certificate revocation list in .der, .crl or .pem format from disk
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    pub fn read_crl(path: &Path) -> Result<CertificateRevocationList, String> {
+        let data = std::fs::read(path)
+            .map_err(|_| format!("Could not read CRL file {}", path.display()))?;
+        let crl = match path.extension() {
+            Some(v) if v == "der" || v == "crl" => CertificateRevocationList::from_der(&data),
+            Some(v) if v == "pem" => CertificateRevocationList::from_pem(&data),
+            _ => return Err("Only .der, .crl and .pem revocation lists are supported".to_string()),
+        };
+        crl.map_err(|_| format!("Could not read CRL from file {}", path.display()))
+    }
+
+    /// Writes bytes to file and returns the size written, or an error reason for failure.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn write_to_file(bytes: &[u8], file_path: &Path, overwrite: bool) -> Result<usize, String> {
+        if !overwrite && file_path.exists() {
+            Err(format!("File {} already exists and will not be overwritten. Enable overwrite to disable this safeguard.", file_path.display()))
+        } else {
+            if let Some(parent) = file_path.parent() {
+                FileSystemCertificateStore::ensure_dir(parent)?;
+            }
+            match File::create(file_path) {
+                Ok(mut file) => file
+                    .write(bytes)
+                    .map_err(|_| format!("Could not write bytes to file {}", file_path.display())),
+                Err(_) => Err(format!("Could not create file {}", file_path.display())),
+            }
+        }
+    }
+}
+
+impl CertificateStore for FileSystemCertificateStore {
+    fn read_own_cert(&self) -> Result<X509, String> {
+        FileSystemCertificateStore::read_cert(&self.own_certificate_path()).map_err(|e| {
+            format!(
+                "Cannot read cert from path {:?}: {e}",
+                self.own_certificate_path()
+            )
+        })
+    }
+
+    fn read_own_pkey(&self) -> Result<PrivateKey, String> {
+        FileSystemCertificateStore::read_pkey(&self.own_private_key_path()).map_err(|e| {
+            format!(
+                "Cannot read pkey from path {:?}: {e}",
+                self.own_private_key_path()
+            )
+        })
+    }
+
+    fn is_trusted_cert(&self, cert: &X509) -> Result<bool, StatusCode> {
+        // These checks are more strict to ensure the cert is genuinely trusted
+        if !FileSystemCertificateStore::has_cert_in_dir(cert, self.trusted_certs_dir(), "trusted")?
+        {
+            return Ok(false);
+        }
+        // If the cert is in the trusted folder, make sure it matches the one supplied
+        let cert_path = self
+            .trusted_certs_dir()
+            .join(FileSystemCertificateStore::cert_file_name(cert));
+        if !FileSystemCertificateStore::ensure_cert_and_file_are_the_same(cert, &cert_path) {
+            error!("Certificate in memory does not match the one on disk {} so cert will automatically be treated as untrusted", cert_path.display());
+            return Err(StatusCode::BadUnexpectedError);
+        }
+        Ok(true)
+    }
+
+    fn is_rejected_cert(&self, cert: &X509) -> Result<bool, StatusCode> {
+        FileSystemCertificateStore::has_cert_in_dir(cert, self.rejected_certs_dir(), "rejected")
+    }
+
+    fn trusted_certs(&self) -> Vec<X509> {
+        FileSystemCertificateStore::read_certs_in_dir(&self.trusted_certs_dir())
+    }
+
+    fn issuer_certs(&self) -> Vec<X509> {
+        FileSystemCertificateStore::read_certs_in_dir(&self.issuer_certs_dir())
+    }
+
+    fn crls(&self) -> Vec<CertificateRevocationList> {
+        FileSystemCertificateStore::read_crls_in_dir(&self.crl_dir())
+    }
+
+    fn store_trusted_cert(&self, cert: &X509) -> Result<(), String> {
+        FileSystemCertificateStore::store_cert_in_dir(cert, self.trusted_certs_dir()).map(|_| ())
+    }
+
+    fn store_rejected_cert(&self, cert: &X509) -> Result<(), String> {
+        FileSystemCertificateStore::store_cert_in_dir(cert, self.rejected_certs_dir()).map(|_| ())
+    }
+
+    fn rejected_certs(&self) -> Vec<X509> {
+        FileSystemCertificateStore::read_certs_in_dir(&self.rejected_certs_dir())
+    }
+
+    fn store_own_cert_and_pkey(&mut self, cert: &X509, pkey: &PrivateKey) -> Result<(), String> {
+        let _ = FileSystemCertificateStore::store_cert(cert, &self.own_certificate_path(), true)?;
+        let _ = FileSystemCertificateStore::store_pkey(pkey, &self.own_private_key_path(), true)?;
+        Ok(())
+    }
+
+    fn set_trusted_certs(&mut self, certs: &[X509]) -> Result<(), String> {
+        FileSystemCertificateStore::replace_certs_in_dir(&self.trusted_certs_dir(), certs)
+    }
+
+    fn set_issuer_certs(&mut self, certs: &[X509]) -> Result<(), String> {
+        FileSystemCertificateStore::replace_certs_in_dir(&self.issuer_certs_dir(), certs)
+    }
+
+    fn set_crls(&mut self, crls: &[CertificateRevocationList]) -> Result<(), String> {
+        use sha1::Digest;
+
+        // Revocation lists have no thumbprint of their own, so name them after the hash of
+        // their contents
+        let files = crls
+            .iter()
+            .map(|crl| {
+                let der = crl
+                    .to_der()
+                    .map_err(|_| "Could not encode revocation list".to_string())?;
+                let digest = sha1::Sha1::digest(&der);
+                Ok((
+                    format!("{}.crl", Thumbprint::new(&digest).as_hex_string()),
+                    der,
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        FileSystemCertificateStore::replace_files_in_dir(&self.crl_dir(), &files)
+    }
+
+    fn validation_options(&self) -> &ValidationOptions {
+        &self.validation_options
+    }
+
+    fn validation_options_mut(&mut self) -> &mut ValidationOptions {
+        &mut self.validation_options
+    }
+}
@@ -0,0 +1,178 @@
+// OPCUA for Rust
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (C) 2017-2024 Adam Lock
+
+//! A certificate store keeping private keys and certificates in memory.
+
+use std::sync::RwLock;
+
+use opcua_types::status_code::StatusCode;
+
+use super::{CertificateStore, ValidationOptions};
+use crate::{crl::CertificateRevocationList, pkey::PrivateKey, x509::X509};
+
+/// A [`CertificateStore`] keeping the certificates in memory, for applications that
+/// get their certificates and keys from somewhere other than a PKI directory, e.g. secrets
+/// injected into a container.
+///
+/// Nothing is persisted, so certificates trusted or rejected while running are lost
+/// when the store is dropped.
+#[derive(Default)]
+pub struct InMemoryCertificateStore {
+    /// The applications own certificate
+    own_cert: Option<X509>,
+    /// The applications own private key
+    own_pkey: Option<PrivateKey>,
+    /// Trusted certificates
+    trusted_certs: RwLock<Vec<X509>>,
+    /// Rejected certificates
+    rejected_certs: RwLock<Vec<X509>>,
+    /// CA certificates that are not trusted, but are used to build certificate chains
+    issuer_certs: Vec<X509>,
+    /// Certificate revocation lists
+    crls: Vec<CertificateRevocationList>,
+    /// Options used when validating certificates
+    validation_options: ValidationOptions,
+}
+
+impl InMemoryCertificateStore {
+    /// Create an empty certificate store, with no certificate or private key of its own.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a certificate store with the given application instance certificate
+    /// and private key.
+    pub fn new_with_cert_and_pkey(cert: X509, pkey: PrivateKey) -> Self {
+        Self {
+            own_cert: Some(cert),
+            own_pkey: Some(pkey),
+            ..Default::default()
+        }
+    }
+
+    /// Set the application instance certificate and private key.
+    pub fn set_own_cert_and_pkey(&mut self, cert: X509, pkey: PrivateKey) {
+        self.own_cert = Some(cert);
+        self.own_pkey = Some(pkey);
+    }
+
+    /// Add a certificate to the trust list.
+    pub fn add_trusted_cert(&mut self, cert: X509) {
+        let _ = self.store_trusted_cert(&cert);
+    }
+
+    /// Remove a certificate from the trust list, returning `true` if it was trusted.
+    pub fn remove_trusted_cert(&mut self, cert: &X509) -> bool {
+        let trusted_certs = self.trusted_certs.get_mut().unwrap();
+        let len = trusted_certs.len();
+        trusted_certs.retain(|c| c.thumbprint() != cert.thumbprint());
+        trusted_certs.len() != len
+    }
+
+    /// Add a CA certificate used to build certificate chains, without trusting it.
+    pub fn add_issuer_cert(&mut self, cert: X509) {
+        self.issuer_certs.push(cert);
+    }
+
+    /// Add a certificate revocation list.
+    pub fn add_crl(&mut self, crl: CertificateRevocationList) {
+        self.crls.push(crl);
+    }
+
+    /// Forget all rejected certificates.
+    pub fn clear_rejected_certs(&mut self) {
+        self.rejected_certs.get_mut().unwrap().clear();
+    }
+
+    fn contains(certs: &RwLock<Vec<X509>>, cert: &X509) -> bool {
+        let thumbprint = cert.thumbprint();
+        certs
+            .read()
+            .unwrap()
+            .iter()
+            .any(|c| c.thumbprint() == thumbprint)
+    }
+
+    fn insert(certs: &RwLock<Vec<X509>>, cert: &X509) {
+        if !Self::contains(certs, cert) {
+            certs.write().unwrap().push(cert.clone());
+        }
+    }
+}
+
+impl CertificateStore for InMemoryCertificateStore {
+    fn read_own_cert(&self) -> Result<X509, String> {
+        self.own_cert
+            .clone()
+            .ok_or_else(|| "Certificate store has no certificate".to_string())
+    }
+
+    fn read_own_pkey(&self) -> Result<PrivateKey, String> {
+        self.own_pkey
+            .clone()
+            .ok_or_else(|| "Certificate store has no private key".to_string())
+    }
+
+    fn is_trusted_cert(&self, cert: &X509) -> Result<bool, StatusCode> {
+        Ok(Self::contains(&self.trusted_certs, cert))
+    }
+
+    fn is_rejected_cert(&self, cert: &X509) -> Result<bool, StatusCode> {
+        Ok(Self::contains(&self.rejected_certs, cert))
+    }
+
+    fn trusted_certs(&self) -> Vec<X509> {
+        self.trusted_certs.read().unwrap().clone()
+    }
+
+    fn issuer_certs(&self) -> Vec<X509> {
+        self.issuer_certs.clone()
+    }
+
+    fn crls(&self) -> Vec<CertificateRevocationList> {
+        self.crls.clone()
+    }
+
+    fn store_trusted_cert(&self, cert: &X509) -> Result<(), String> {
+        Self::insert(&self.trusted_certs, cert);
+        Ok(())
+    }
+
+    fn store_rejected_cert(&self, cert: &X509) -> Result<(), String> {
+        Self::insert(&self.rejected_certs, cert);
+        Ok(())
+    }
+
+    fn rejected_certs(&self) -> Vec<X509> {
+        self.rejected_certs.read().unwrap().clone()
+    }
+
+    fn store_own_cert_and_pkey(&mut self, cert: &X509, pkey: &PrivateKey) -> Result<(), String> {
+        self.set_own_cert_and_pkey(cert.clone(), pkey.clone());
+        Ok(())
+    }
+
+    fn set_trusted_certs(&mut self, certs: &[X509]) -> Result<(), String> {
+        *self.trusted_certs.get_mut().unwrap() = certs.to_vec();
+        Ok(())
+    }
+
+    fn set_issuer_certs(&mut self, certs: &[X509]) -> Result<(), String> {
+        self.issuer_certs = certs.to_vec();
+        Ok(())
+    }
+
+    fn set_crls(&mut self, crls: &[CertificateRevocationList]) -> Result<(), String> {
+        self.crls = crls.to_vec();
+        Ok(())
+    }
+
+    fn validation_options(&self) -> &ValidationOptions {
+        &self.validation_options
+    }
+
+    fn validation_options_mut(&mut self) -> &mut ValidationOptions {
+        &mut self.validation_options
+    }
+}
@@ -0,0 +1,458 @@
+// OPCUA for Rust
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (C) 2017-2024 Adam Lock
+
+//! The certificate store holds and retrieves private keys and certificates. It is responsible
+//! for checking certificates supplied by the remote end to see if they are valid and trusted or not.
+//!
+//! The [`CertificateStore`] trait abstracts over where certificates are kept. The
+//! [`FileSystemCertificateStore`] keeps them in a PKI directory on disk, the
+//! [`InMemoryCertificateStore`] keeps them in memory, and applications may implement the trait
+//! to keep them anywhere else.
+
+use chrono::{DateTime, Utc};
+use log::{debug, error, warn};
+
+use opcua_types::status_code::StatusCode;
+
+use super::{
+    crl::CertificateRevocationList, pkey::PrivateKey, security_policy::SecurityPolicy, x509::X509,
+};
+
+mod file_system;
+mod memory;
+
+pub use file_system::FileSystemCertificateStore;
+pub use memory::InMemoryCertificateStore;
+
+/// Maximum number of certificates in a certificate chain
+const MAX_CHAIN_LENGTH: usize = 10;
+
+/// Options controlling how strictly a [`CertificateStore`] validates certificates.
+#[derive(Debug, Clone)]
+pub struct ValidationOptions {
+    /// Timestamps of the cert are normally checked on the cert to ensure it cannot be used before
+    /// or after its limits, but this check can be disabled.
+    pub check_time: bool,
+    /// This option lets you skip additional certificate validations (e.g. hostname, application
+    /// uri and the not before / after values). Certificates are always checked to see if they are
+    /// trusted and have a valid key length.
+    pub skip_verify_certs: bool,
+    /// Ordinarily an unknown cert will be stored as rejected, but it can be stored as trusted
+    /// if this flag is set. Trusted certs must still pass validity checks.
+    pub trust_unknown_certs: bool,
+    /// Normally every CA in the chain of a cert must have a current revocation list, but
+    /// this check can be disabled. Certs that are revoked by a known list are still rejected.
+    pub allow_unknown_revocation: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            check_time: true,
+            skip_verify_certs: false,
+            trust_unknown_certs: false,
+            allow_unknown_revocation: false,
+        }
+    }
+}
+
+/// The certificate store manages the storage of a server/client's own certificate & private key
+/// and the trust / rejection of certificates from the other end.
+///
+/// Implementations only provide storage, the validation of certificates is implemented
+/// on top of it by the provided methods of the trait.
+pub trait CertificateStore: Send + Sync {
+    /// Reads the store's own certificate
+    fn read_own_cert(&self) -> Result<X509, String>;
+
+    /// Reads the store's own private key
+    fn read_own_pkey(&self) -> Result<PrivateKey, String>;
+
+    /// Tests if the certificate is in the trust list.
+    ///
+    /// # Errors
+    ///
+    /// A non `Good` status code if the trust list cannot be examined.
+    ///
+    fn is_trusted_cert(&self, cert: &X509) -> Result<bool, StatusCode>;
+
+    /// Tests if the certificate has been rejected.
+    ///
+    /// # Errors
+    ///
+    /// A non `Good` status code if the rejected certificates cannot be examined.
+    ///
+    fn is_rejected_cert(&self, cert: &X509) -> Result<bool, StatusCode>;
+
+    /// Get all certificates in the trust list.
+    fn trusted_certs(&self) -> Vec<X509>;
+
+    /// Get the CA certificates that are not trusted themselves, but are used to build
+    /// certificate chains.
+    fn issuer_certs(&self) -> Vec<X509>;
+
+    /// Get the certificate revocation lists of the trusted and issuer CA certificates.
+    fn crls(&self) -> Vec<CertificateRevocationList>;
+
+    /// Adds a certificate to the trust list.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn store_trusted_cert(&self, cert: &X509) -> Result<(), String>;
+
+    /// Stores a certificate as rejected, so that an administrator can inspect it and
+    /// move it to the trust list.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn store_rejected_cert(&self, cert: &X509) -> Result<(), String>;
+
+    /// Get all certificates that have been rejected.
+    fn rejected_certs(&self) -> Vec<X509>;
+
+    /// Replaces the store's own certificate and private key, e.g. when the application
+    /// instance certificate is renewed.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn store_own_cert_and_pkey(&mut self, cert: &X509, pkey: &PrivateKey) -> Result<(), String>;
+
+    /// Replaces all certificates in the trust list.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn set_trusted_certs(&mut self, certs: &[X509]) -> Result<(), String>;
+
+    /// Replaces all CA certificates used to build certificate chains.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn set_issuer_certs(&mut self, certs: &[X509]) -> Result<(), String>;
+
+    /// Replaces all certificate revocation lists.
+    ///
+    /// # Errors
+    ///
+    /// A string description of any failure
+    ///
+    fn set_crls(&mut self, crls: &[CertificateRevocationList]) -> Result<(), String>;
+
+    /// Get the options used when validating certificates.
+    fn validation_options(&self) -> &ValidationOptions;
+
+    /// Get a mutable reference to the options used when validating certificates.
+    fn validation_options_mut(&mut self) -> &mut ValidationOptions;
+
+    /// Set `skip_verify_certs` to not verify incoming certificates.
+    fn set_skip_verify_certs(&mut self, skip_verify_certs: bool) {
+        self.validation_options_mut().skip_verify_certs = skip_verify_certs;
+    }
+
+    /// Set `trust_unknown_certs` to automatically trust valid but
+    /// untrusted certificates.
+    fn set_trust_unknown_certs(&mut self, trust_unknown_certs: bool) {
+        self.validation_options_mut().trust_unknown_certs = trust_unknown_certs;
+    }
+
+    /// Check expiration time of incoming certificates.
+    fn set_check_time(&mut self, check_time: bool) {
+        self.validation_options_mut().check_time = check_time;
+    }
+
+    /// Set `allow_unknown_revocation` to accept certificates whose issuers
+    /// have no current revocation list.
+    fn set_allow_unknown_revocation(&mut self, allow_unknown_revocation: bool) {
+        self.validation_options_mut().allow_unknown_revocation = allow_unknown_revocation;
+    }
+
+    /// Validates the cert as trusted and valid. If the cert is unknown, it will be stored as
+    /// rejected so that the administrator can manually move it to the trust list.
+    ///
+    /// # Errors
+    ///
+    /// A non `Good` status code indicates a failure in the cert or in some action required in
+    /// order to validate it.
+    ///
+    fn validate_or_reject_application_instance_cert(
+        &self,
+        cert: &X509,
+        security_policy: SecurityPolicy,
+        hostname: Option<&str>,
+        application_uri: Option<&str>,
+    ) -> Result<(), StatusCode> {
+        self.validate_application_instance_cert(cert, security_policy, hostname, application_uri)
+    }
+
+    /// Validates the certificate according to the strictness set in the store's
+    /// [`ValidationOptions`], following the validation steps in Part 4, 6.1.3.
+    ///
+    /// The certificate chain is built from the trusted and issuer certificates, and the
+    /// certificate is trusted if it, or one of the CA certificates in its chain, is in
+    /// the trust list. Unless additional verifications are skipped, the validity period and
+    /// usage of every certificate in the chain is checked, as well as the revocation lists of
+    /// each issuer.
+    ///
+    /// # Errors
+    ///
+    /// A non `Good` status code indicates a failure in the cert or in some action required in
+    /// order to validate it.
+    ///
+    fn validate_application_instance_cert(
+        &self,
+        cert: &X509,
+        security_policy: SecurityPolicy,
+        hostname: Option<&str>,
+        application_uri: Option<&str>,
+    ) -> Result<(), StatusCode> {
+        let cert_name = cert_name(cert);
+        debug!("Validating cert {}", cert_name);
+
+        // If the cert is rejected there is no purpose going any further
+        if self.is_rejected_cert(cert)? {
+            warn!(
+                "Certificate {} is untrusted because it has been rejected",
+                cert_name
+            );
+            return Err(StatusCode::BadSecurityChecksFailed);
+        }
+
+        let is_trusted_cert = self.is_trusted_cert(cert)?;
+
+        // Build the chain of issuers up to a self-signed root
+        let trusted_certs = self.trusted_certs();
+        let issuer_certs = self.issuer_certs();
+        let chain = match build_chain(cert, &trusted_certs, &issuer_certs) {
+            Ok(chain) => chain,
+            Err(status) => {
+                if !is_trusted_cert {
+                    warn!(
+                        "Certificate {} has an invalid or incomplete chain so it will be rejected",
+                        cert_name
+                    );
+                    let _ = self.store_rejected_cert(cert);
+                }
+                return Err(status);
+            }
+        };
+
+        // The cert is trusted if it, or one of its issuers, is in the trust list
+        let has_trusted_issuer = chain[1..].iter().any(|issuer| {
+            let thumbprint = issuer.thumbprint();
+            trusted_certs.iter().any(|t| t.thumbprint() == thumbprint)
+        });
+        let options = self.validation_options();
+        if !is_trusted_cert && !has_trusted_issuer {
+            if options.trust_unknown_certs {
+                // Put the unknown cert into the trust list
+                warn!(
+                    "Certificate {} is unknown but policy will add it to the trust list",
+                    cert_name
+                );
+                let _ = self.store_trusted_cert(cert);
+            // Note that we drop through and still check the cert for validity
+            } else {
+                warn!(
+                    "Certificate {} is unknown and untrusted so it will be rejected",
+                    cert_name
+                );
+                let _ = self.store_rejected_cert(cert);
+                return Err(StatusCode::BadCertificateUntrusted);
+            }
+        }
+
+        // Check that the certificate is the right length for the security policy
+        match cert.key_length() {
+            Err(_) => {
+                error!("Cannot read key length from certificate {}", cert_name);
+                return Err(StatusCode::BadSecurityChecksFailed);
+            }
+            Ok(key_length) => {
+                if !security_policy.is_valid_keylength(key_length) {
+                    warn!(
+                        "Certificate {} has an invalid key length {} for the policy {}",
+                        cert_name, key_length, security_policy
+                    );
+                    return Err(StatusCode::BadSecurityChecksFailed);
+                }
+            }
+        }
+
+        if options.skip_verify_certs {
+            debug!(
+                "Skipping additional verifications for certificate {}",
+                cert_name
+            );
+            return Ok(());
+        }
+
+        // Now inspect the cert not before / after values to ensure its validity
+        if options.check_time {
+            let now = Utc::now();
+            cert.is_time_valid(&now)?;
+            for issuer in &chain[1..] {
+                if issuer.is_time_valid(&now).is_err() {
+                    warn!(
+                        "Issuer certificate {} is not valid at this time",
+                        issuer.subject_name()
+                    );
+                    return Err(StatusCode::BadCertificateIssuerTimeInvalid);
+                }
+            }
+        }
+
+        // Compare the hostname of the cert against the cert supplied
+        if let Some(hostname) = hostname {
+            cert.is_hostname_valid(hostname)?;
+        }
+
+        // Compare the application / product uri to the supplied application description
+        if let Some(application_uri) = application_uri {
+            cert.is_application_uri_valid(application_uri)?;
+        }
+
+        // Check that the certificates in the chain are used for their intended purpose
+        cert.is_usage_valid()?;
+        for (depth, issuer) in chain[1..].iter().enumerate() {
+            issuer.is_issuer_usage_valid(depth)?;
+        }
+
+        // Check the revocation lists of the issuers
+        let now = options.check_time.then(Utc::now);
+        check_revocation(&chain, &self.crls(), now, options.allow_unknown_revocation)
+    }
+}
+
+/// Returns a name for the cert from its common name and thumbprint. The name is either
+/// "prefix \[thumbprint\]" or "thumbprint" depending on the cert's common name being
+/// empty or not
+fn cert_name(cert: &X509) -> String {
+    let prefix = if let Ok(common_name) = cert.common_name() {
+        common_name.trim().to_string().replace('/', "")
+    } else {
+        String::new()
+    };
+    let thumbprint = cert.thumbprint().as_hex_string();
+
+    if !prefix.is_empty() {
+        format!("{} [{}]", prefix, thumbprint)
+    } else {
+        thumbprint
+    }
+}
+
+/// Builds the chain of certificates from `cert` up to a self-signed root certificate,
+/// looking for issuers among the trusted and issuer certificates. The chain starts
+/// with `cert` itself.
+fn build_chain(
+    cert: &X509,
+    trusted_certs: &[X509],
+    issuer_certs: &[X509],
+) -> Result<Vec<X509>, StatusCode> {
+    let mut chain = vec![cert.clone()];
+    loop {
+        let current = &chain[chain.len() - 1];
+        if current.issuer() == current.subject() {
+            if current.is_self_signed() {
+                return Ok(chain);
+            }
+            error!(
+                "Self-signed certificate {} has an invalid signature",
+                current.subject_name()
+            );
+            return Err(StatusCode::BadCertificateInvalid);
+        }
+        if chain.len() >= MAX_CHAIN_LENGTH {
+            error!(
+                "Certificate chain of {} is longer than {} certificates",
+                cert.subject_name(),
+                MAX_CHAIN_LENGTH
+            );
+            return Err(StatusCode::BadCertificateChainIncomplete);
+        }
+
+        let mut candidates = trusted_certs
+            .iter()
+            .chain(issuer_certs)
+            .filter(|c| c.subject() == current.issuer())
+            .peekable();
+        if candidates.peek().is_none() {
+            warn!(
+                "Cannot find the issuer of certificate {}",
+                current.subject_name()
+            );
+            return Err(StatusCode::BadCertificateChainIncomplete);
+        }
+        let Some(issuer) = candidates.find(|c| current.is_issued_by(c)) else {
+            error!(
+                "Certificate {} has a signature that does not match its issuer",
+                current.subject_name()
+            );
+            return Err(StatusCode::BadCertificateInvalid);
+        };
+        chain.push(issuer.clone());
+    }
+}
+
+/// Checks that no certificate in the chain has been revoked by its issuer. Every issuer
+/// in the chain must have a revocation list, unless `allow_unknown` is set. If `now` is
+/// given, revocation lists past their next update time are not considered current, and
+/// are only used to find revoked certificates.
+fn check_revocation(
+    chain: &[X509],
+    crls: &[CertificateRevocationList],
+    now: Option<DateTime<Utc>>,
+    allow_unknown: bool,
+) -> Result<(), StatusCode> {
+    for (idx, certs) in chain.windows(2).enumerate() {
+        let (cert, issuer) = (&certs[0], &certs[1]);
+        let is_issuer = idx > 0;
+        let revocation_unknown = if is_issuer {
+            StatusCode::BadCertificateIssuerRevocationUnknown
+        } else {
+            StatusCode::BadCertificateRevocationUnknown
+        };
+        let issuer_crls: Vec<_> = crls.iter().filter(|c| c.is_issued_by(issuer)).collect();
+        if issuer_crls.is_empty() {
+            if allow_unknown {
+                continue;
+            }
+            warn!(
+                "Cannot find a revocation list issued by {}",
+                issuer.subject_name()
+            );
+            return Err(revocation_unknown);
+        }
+        if issuer_crls.iter().any(|c| c.is_revoked(cert)) {
+            warn!("Certificate {} has been revoked", cert.subject_name());
+            return Err(if is_issuer {
+                StatusCode::BadCertificateIssuerRevoked
+            } else {
+                StatusCode::BadCertificateRevoked
+            });
+        }
+        if let Some(now) = now.filter(|_| !allow_unknown) {
+            if !issuer_crls
+                .iter()
+                .any(|c| c.next_update().is_none_or(|t| t >= now))
+            {
+                warn!(
+                    "The revocation lists issued by {} are out of date",
+                    issuer.subject_name()
+                );
+                return Err(revocation_unknown);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+// OPCUA for Rust
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (C) 2017-2024 Adam Lock
+
+//! Wrapper for X509 certificate revocation lists, used to check whether certificates
+//! issued by a certificate authority have been revoked.
+
+use std::fmt::{self, Debug, Formatter};
+
+use chrono::{DateTime, Utc};
+use x509_cert::{self as x509, crl::CertificateList};
+
+use super::x509::{verify_signed_data, X509Error, X509};
+
+/// PEM label of certificate revocation lists.
+const PEM_LABEL: &str = "X509 CRL";
+
+#[derive(Clone)]
+/// Wrapper around an X509 certificate revocation list.
+pub struct CertificateRevocationList {
+    value: CertificateList,
+}
+
+impl Debug for CertificateRevocationList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[crl]")
+    }
+}
+
+impl CertificateRevocationList {
+    /// Load a certificate revocation list from a pem file.
+    pub fn from_pem(data: &[u8]) -> Result<Self, X509Error> {
+        let (label, der) = x509::der::pem::decode_vec(data).map_err(|_| X509Error)?;
+        if label != PEM_LABEL {
+            return Err(X509Error);
+        }
+        Self::from_der(&der)
+    }
+
+    /// Load a certificate revocation list from a der file.
+    pub fn from_der(data: &[u8]) -> Result<Self, X509Error> {
+        use x509::der::Decode;
+
+        let value = CertificateList::from_der(data)?;
+        Ok(Self { value })
+    }
+
+    /// Serialize the certificate revocation list to a der file.
+    pub fn to_der(&self) -> Result<Vec<u8>, X509Error> {
+        use x509::der::Encode;
+
+        Ok(self.value.to_der()?)
+    }
+
+    /// Tests if the revocation list was issued by `issuer`, i.e. the issuer name of the list
+    /// is the subject name of `issuer`, and the signature of the list verifies with the public
+    /// key of `issuer`.
+    pub fn is_issued_by(&self, issuer: &X509) -> bool {
+        use x509::der::Encode;
+
+        if self.value.tbs_cert_list.issuer != *issuer.subject() {
+            return false;
+        }
+        let (Ok(public_key), Ok(tbs), Some(signature)) = (
+            issuer.public_key(),
+            self.value.tbs_cert_list.to_der(),
+            self.value.signature.as_bytes(),
+        ) else {
+            return false;
+        };
+        verify_signed_data(
+            &public_key,
+            &self.value.signature_algorithm,
+            &tbs,
+            signature,
+        )
+    }
+
+    /// Tests if `cert` is revoked by this list. The list must have been issued by the issuer
+    /// of `cert`, which should be checked with [`CertificateRevocationList::is_issued_by`].
+    pub fn is_revoked(&self, cert: &X509) -> bool {
+        self.value.tbs_cert_list.issuer == *cert.issuer()
+            && self
+                .value
+                .tbs_cert_list
+                .revoked_certificates
+                .iter()
+                .flatten()
+                .any(|r| r.serial_number == *cert.serial_number())
+    }
+
+    /// Time when the next revocation list is expected to be issued, if set.
+    pub fn next_update(&self) -> Option<DateTime<Utc>> {
+        let next_update = self.value.tbs_cert_list.next_update?;
+        DateTime::from_timestamp_micros(next_update.to_unix_duration().as_micros() as i64)
+    }
+}
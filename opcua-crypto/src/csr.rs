@@ -0,0 +1,175 @@
+// OPCUA for Rust
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (C) 2017-2024 Adam Lock
+
+//! Wrapper for PKCS#10 certificate signing requests, used to request a certificate for a
+//! private key from a certificate authority.
+
+use std::fmt::{self, Debug, Formatter};
+use std::str::FromStr;
+
+use rsa::pkcs1v15;
+use x509_cert::{
+    self as x509,
+    builder::{Builder, Error as BuilderError, RequestBuilder},
+    ext::pkix::SubjectAltName,
+    name::Name,
+    request::CertReq,
+};
+
+use opcua_types::Error;
+
+use super::{
+    pkey::{PrivateKey, PrivateKeyValue, PublicKey},
+    x509::{verify_signed_data, AlternateNames, X509Error, X509},
+};
+
+/// PEM label of certificate signing requests.
+const PEM_LABEL: &str = "CERTIFICATE REQUEST";
+
+#[derive(Clone)]
+/// Wrapper around a PKCS#10 certificate signing request.
+pub struct CertificateSigningRequest {
+    value: CertReq,
+}
+
+impl Debug for CertificateSigningRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[csr]")
+    }
+}
+
+impl CertificateSigningRequest {
+    /// Create a signing request for the public key of `pkey`, signed with `pkey`. The
+    /// subject name is a comma separated list of attributes such as "CN=foo,O=bar". Any
+    /// alternate names are requested as a subject alt name extension.
+    pub fn new(
+        pkey: &PrivateKey,
+        subject_name: &str,
+        alt_names: &AlternateNames,
+    ) -> Result<Self, String> {
+        let subject =
+            Name::from_str(subject_name).map_err(|e| format!("Invalid subject name: {e}"))?;
+        Self::create(pkey, subject, &alt_names.names)
+            .map_err(|e| format!("Failed to create signing request: {e}"))
+    }
+
+    /// Create a signing request for a new certificate replacing `cert`, for the public key
+    /// of `pkey`. The subject name and subject alt names are copied from `cert`, unless
+    /// `subject_name` is given.
+    pub fn for_cert(
+        cert: &X509,
+        pkey: &PrivateKey,
+        subject_name: Option<&str>,
+    ) -> Result<Self, String> {
+        let subject = match subject_name {
+            Some(subject_name) => {
+                Name::from_str(subject_name).map_err(|e| format!("Invalid subject name: {e}"))?
+            }
+            None => cert.subject().clone(),
+        };
+        let alt_names = SubjectAltName(cert.alternate_names().unwrap_or_default());
+        Self::create(pkey, subject, &alt_names)
+            .map_err(|e| format!("Failed to create signing request: {e}"))
+    }
+
+    fn create(
+        pkey: &PrivateKey,
+        subject: Name,
+        alt_names: &SubjectAltName,
+    ) -> Result<Self, BuilderError> {
+        match &pkey.value {
+            PrivateKeyValue::Rsa(key) => Self::build::<_, pkcs1v15::Signature>(
+                subject,
+                alt_names,
+                &pkcs1v15::SigningKey::<sha2::Sha256>::new(key.as_ref().clone()),
+            ),
+            PrivateKeyValue::NistP256(key) => Self::build::<_, p256::ecdsa::DerSignature>(
+                subject,
+                alt_names,
+                &p256::ecdsa::SigningKey::from(key),
+            ),
+            PrivateKeyValue::NistP384(key) => Self::build::<_, p384::ecdsa::DerSignature>(
+                subject,
+                alt_names,
+                &p384::ecdsa::SigningKey::from(key),
+            ),
+        }
+    }
+
+    fn build<S, Signature>(
+        subject: Name,
+        alt_names: &SubjectAltName,
+        signing_key: &S,
+    ) -> Result<Self, BuilderError>
+    where
+        S: rsa::signature::Keypair
+            + x509::spki::DynSignatureAlgorithmIdentifier
+            + rsa::signature::Signer<Signature>,
+        S::VerifyingKey: x509::spki::EncodePublicKey,
+        Signature: x509::spki::SignatureBitStringEncoding,
+    {
+        let mut builder = RequestBuilder::new(subject, signing_key)?;
+        if !alt_names.0.is_empty() {
+            builder.add_extension(alt_names)?;
+        }
+        let value = builder.build::<Signature>()?;
+        Ok(Self { value })
+    }
+
+    /// Load a certificate signing request from a pem file.
+    pub fn from_pem(data: &[u8]) -> Result<Self, X509Error> {
+        let (label, der) = x509::der::pem::decode_vec(data).map_err(|_| X509Error)?;
+        if label != PEM_LABEL {
+            return Err(X509Error);
+        }
+        Self::from_der(&der)
+    }
+
+    /// Load a certificate signing request from a der file.
+    pub fn from_der(data: &[u8]) -> Result<Self, X509Error> {
+        use x509::der::Decode;
+
+        let value = CertReq::from_der(data)?;
+        Ok(Self { value })
+    }
+
+    /// Serialize the certificate signing request to a der file.
+    pub fn to_der(&self) -> Result<Vec<u8>, X509Error> {
+        use x509::der::Encode;
+
+        Ok(self.value.to_der()?)
+    }
+
+    /// Produces a subject name string such as "CN=foo,O=bar"
+    pub fn subject_name(&self) -> String {
+        self.value.info.subject.to_string()
+    }
+
+    /// Get the public key the certificate is requested for.
+    pub fn public_key(&self) -> Result<PublicKey, Error> {
+        PublicKey::from_info(&self.value.info.public_key)
+    }
+
+    /// Tests if `pkey` is the private key matching the public key of this request.
+    pub fn is_public_key_of(&self, pkey: &PrivateKey) -> bool {
+        pkey.public_key_to_info().is_ok_and(|info| {
+            info.subject_public_key == self.value.info.public_key.subject_public_key
+        })
+    }
+
+    /// Tests if the signature of the request verifies with the public key it contains,
+    /// i.e. the request was made by the holder of the private key.
+    pub fn is_signature_valid(&self) -> bool {
+        use x509::der::Encode;
+
+        let (Ok(public_key), Ok(info), Some(signature)) = (
+            self.public_key(),
+            self.value.info.to_der(),
+            self.value.signature.as_bytes(),
+        ) else {
+            return false;
+        };
+        verify_signed_data(&public_key, &self.value.algorithm, &info, signature)
+    }
+}
@@ -15,8 +15,8 @@ use opcua_types::{
     status_code::StatusCode, ByteString, EncodingResult, Error, SignatureData, UAString,
 };
 pub use {
-    aeskey::*, certificate_store::*, hash::*, pkey::*, security_policy::*, thumbprint::*,
-    user_identity::*, x509::*,
+    aeskey::*, certificate_store::*, crl::*, csr::*, hash::*, pkey::*, security_policy::*,
+    thumbprint::*, user_identity::*, x509::*,
 };
 
 #[cfg(test)]
@@ -24,6 +24,8 @@ mod tests;
 
 pub mod aeskey;
 pub mod certificate_store;
+pub mod crl;
+pub mod csr;
 pub mod hash;
 pub mod pkey;
 pub mod random;
@@ -36,6 +38,8 @@ pub mod x509;
 pub const SHA1_SIZE: usize = 20;
 /// Size of a SHA256 hash value bytes
 pub const SHA256_SIZE: usize = 32;
+/// Size of a SHA384 hash value bytes
+pub const SHA384_SIZE: usize = 48;
 
 /// These are algorithms that are used by various policies or external to this file
 pub(crate) mod algorithms {
@@ -72,6 +76,15 @@ pub(crate) mod algorithms {
     /// Asymmetric digital signature algorithm using RSA-PSS_SHA2-256
     pub const DSIG_RSA_PSS_SHA2_256: &str = "http://opcfoundation.org/UA/security/rsa-pss-sha2-256";
 
+    /// SymmetricSignatureAlgorithm – HmacSha384 – (http://www.w3.org/2001/04/xmldsig-more#hmac-sha384).
+    pub const DSIG_HMAC_SHA384: &str = "http://www.w3.org/2001/04/xmldsig-more#hmac-sha384";
+
+    /// Asymmetric digital signature algorithm using ECDSA-SHA256
+    pub const DSIG_ECDSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha256";
+
+    /// Asymmetric digital signature algorithm using ECDSA-SHA384
+    pub const DSIG_ECDSA_SHA384: &str = "http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha384";
+
     // Key derivation algorithm P_SHA1
     //pub const KEY_P_SHA1: &str = "http://docs.oasis-open.org/ws-sx/ws-secureconversation/200512/dk/p_sha1";
 
@@ -107,8 +120,7 @@ pub fn create_signature_data(
             }
 
             let data = concat_data_and_nonce(contained_cert.as_ref(), nonce.as_ref());
-            let signing_key_size = signing_key.size();
-            let mut signature = vec![0u8; signing_key_size];
+            let mut signature = vec![0u8; signing_key.signature_size()];
             let _ = security_policy.asymmetric_sign(signing_key, &data, &mut signature)?;
             (
                 UAString::from(security_policy.asymmetric_signature_algorithm()),
@@ -9,6 +9,7 @@ use std::{
     result::Result,
 };
 
+use const_oid::AssociatedOid;
 use rand;
 use rsa::pkcs1;
 use rsa::pkcs1v15;
@@ -17,7 +18,11 @@ use rsa::pss;
 use rsa::signature::{RandomizedSigner, SignatureEncoding, Verifier};
 use rsa::{Oaep, Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
 use sha1;
-use sha2;
+use sha2::{self, digest::FixedOutputReset, Digest};
+
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{self, ecdsa::signature::hazmat::PrehashSigner};
+use p384;
 
 use x509_cert;
 use x509_cert::spki::SubjectPublicKeyInfoOwned;
@@ -65,6 +70,53 @@ impl From<rsa::Error> for PKeyError {
     }
 }
 
+impl From<p256::elliptic_curve::Error> for PKeyError {
+    fn from(_err: p256::elliptic_curve::Error) -> Self {
+        PKeyError
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Elliptic curve of an ECC key.
+pub enum EccCurve {
+    /// NIST P-256, also known as secp256r1.
+    NistP256,
+    /// NIST P-384, also known as secp384r1.
+    NistP384,
+}
+
+impl EccCurve {
+    /// Size in bytes of a coordinate of a point on the curve.
+    pub fn coordinate_size(&self) -> usize {
+        match self {
+            EccCurve::NistP256 => 32,
+            EccCurve::NistP384 => 48,
+        }
+    }
+}
+
+/// Key material of a public key.
+#[derive(Clone)]
+pub enum PublicKeyValue {
+    /// RSA public key.
+    Rsa(RsaPublicKey),
+    /// ECC public key on the NIST P-256 curve.
+    NistP256(p256::PublicKey),
+    /// ECC public key on the NIST P-384 curve.
+    NistP384(p384::PublicKey),
+}
+
+/// Key material of a private key.
+#[derive(Clone)]
+pub enum PrivateKeyValue {
+    /// RSA private key.
+    Rsa(Box<RsaPrivateKey>),
+    /// ECC private key on the NIST P-256 curve.
+    NistP256(p256::SecretKey),
+    /// ECC private key on the NIST P-384 curve.
+    NistP384(p384::SecretKey),
+}
+
 /// This is a wrapper around an asymmetric key pair. Since the PKey is either
 /// a public or private key so we have to differentiate that as well.
 #[derive(Clone)]
@@ -73,9 +125,23 @@ pub struct PKey<T> {
 }
 
 /// A public key
-pub type PublicKey = PKey<RsaPublicKey>;
+pub type PublicKey = PKey<PublicKeyValue>;
 /// A private key
-pub type PrivateKey = PKey<RsaPrivateKey>;
+pub type PrivateKey = PKey<PrivateKeyValue>;
+
+fn not_rsa_key() -> Error {
+    Error::new(
+        StatusCode::BadSecurityPolicyRejected,
+        "Operation requires an RSA key",
+    )
+}
+
+fn not_ecc_key() -> Error {
+    Error::new(
+        StatusCode::BadSecurityPolicyRejected,
+        "Operation requires an ECC key",
+    )
+}
 
 impl<T> Debug for PKey<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -92,9 +158,12 @@ pub trait KeySize {
         self.size() * 8
     }
 
-    /// Length in bytes.
+    /// Length in bytes. For ECC keys this is the size of a coordinate on the curve.
     fn size(&self) -> usize;
 
+    /// Length in bytes of a signature made with the key.
+    fn signature_size(&self) -> usize;
+
     /// Get the cipher block size with given data size and padding.
     fn calculate_cipher_text_size(&self, data_size: usize, padding: RsaPadding) -> usize {
         let plain_text_block_size = self.plain_text_block_size(padding);
@@ -133,85 +202,138 @@ impl KeySize for PrivateKey {
     /// Length in bits
     fn size(&self) -> usize {
         use rsa::traits::PublicKeyParts;
-        self.value.size()
+        match &self.value {
+            PrivateKeyValue::Rsa(key) => key.size(),
+            PrivateKeyValue::NistP256(_) => EccCurve::NistP256.coordinate_size(),
+            PrivateKeyValue::NistP384(_) => EccCurve::NistP384.coordinate_size(),
+        }
+    }
+
+    fn signature_size(&self) -> usize {
+        match &self.value {
+            PrivateKeyValue::Rsa(_) => self.size(),
+            // Raw r || s pair
+            _ => self.size() * 2,
+        }
     }
 }
 
 impl PrivateKey {
-    /// Generate a new private key with the given length in bits.
+    /// Generate a new RSA private key with the given length in bits.
     pub fn new(bit_length: u32) -> Result<PrivateKey, rsa::Error> {
         let mut rng = rand::thread_rng();
 
         let key = RsaPrivateKey::new(&mut rng, bit_length as usize)?;
-        Ok(PKey { value: key })
+        Ok(PKey {
+            value: PrivateKeyValue::Rsa(Box::new(key)),
+        })
+    }
+
+    /// Generate a new ECC private key on the given curve.
+    pub fn new_ecc(curve: EccCurve) -> PrivateKey {
+        let mut rng = rand::thread_rng();
+
+        let value = match curve {
+            EccCurve::NistP256 => PrivateKeyValue::NistP256(p256::SecretKey::random(&mut rng)),
+            EccCurve::NistP384 => PrivateKeyValue::NistP384(p384::SecretKey::random(&mut rng)),
+        };
+        PKey { value }
     }
 
     /// Read a private key from the given path.
     pub fn read_pem_file(path: &std::path::Path) -> Result<PrivateKey, PKeyError> {
-        use pkcs8::DecodePrivateKey;
-        use rsa::pkcs1::DecodeRsaPrivateKey;
-
-        let r = RsaPrivateKey::read_pkcs8_pem_file(path);
-        match r {
-            Err(_) => {
-                let val = RsaPrivateKey::read_pkcs1_pem_file(path)?;
-                Ok(PKey { value: val })
-            }
-            Ok(val) => Ok(PKey { value: val }),
-        }
+        let bytes = std::fs::read(path).map_err(|_| PKeyError)?;
+        Self::from_pem(&bytes)
     }
 
-    /// Create a private key from a pem file loaded into a byte array.
+    /// Create a private key from a pem file loaded into a byte array. RSA keys may be
+    /// PKCS#8 or PKCS#1 encoded, ECC keys may be PKCS#8 or SEC1 encoded.
     pub fn from_pem(bytes: &[u8]) -> Result<PrivateKey, PKeyError> {
         use pkcs8::DecodePrivateKey;
         use rsa::pkcs1::DecodeRsaPrivateKey;
 
-        let converted = std::str::from_utf8(bytes);
-        match converted {
-            Err(_) => Err(PKeyError),
-            Ok(pem) => {
-                let r = RsaPrivateKey::from_pkcs8_pem(pem);
-                match r {
-                    Err(_) => {
-                        let val = RsaPrivateKey::from_pkcs1_pem(pem)?;
-                        Ok(PKey { value: val })
-                    }
-                    Ok(val) => Ok(PKey { value: val }),
-                }
-            }
-        }
+        let pem = std::str::from_utf8(bytes).map_err(|_| PKeyError)?;
+        let value = if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(pem) {
+            PrivateKeyValue::Rsa(Box::new(key))
+        } else if let Ok(key) = p256::SecretKey::from_pkcs8_pem(pem) {
+            PrivateKeyValue::NistP256(key)
+        } else if let Ok(key) = p384::SecretKey::from_pkcs8_pem(pem) {
+            PrivateKeyValue::NistP384(key)
+        } else if let Ok(key) = p256::SecretKey::from_sec1_pem(pem) {
+            PrivateKeyValue::NistP256(key)
+        } else if let Ok(key) = p384::SecretKey::from_sec1_pem(pem) {
+            PrivateKeyValue::NistP384(key)
+        } else {
+            PrivateKeyValue::Rsa(Box::new(RsaPrivateKey::from_pkcs1_pem(pem)?))
+        };
+        Ok(PKey { value })
     }
 
     /// Serialize the private key to a der file.
     pub fn to_der(&self) -> pkcs8::Result<pkcs8::SecretDocument> {
         use pkcs8::EncodePrivateKey;
 
-        self.value.to_pkcs8_der()
+        match &self.value {
+            PrivateKeyValue::Rsa(key) => key.to_pkcs8_der(),
+            PrivateKeyValue::NistP256(key) => key.to_pkcs8_der(),
+            PrivateKeyValue::NistP384(key) => key.to_pkcs8_der(),
+        }
+    }
+
+    /// Serialize the private key to a PKCS#8 pem file.
+    pub fn to_pem(&self) -> pkcs8::Result<String> {
+        use pkcs8::EncodePrivateKey;
+
+        let pem = match &self.value {
+            PrivateKeyValue::Rsa(key) => key.to_pkcs8_pem(pkcs8::LineEnding::LF),
+            PrivateKeyValue::NistP256(key) => key.to_pkcs8_pem(pkcs8::LineEnding::LF),
+            PrivateKeyValue::NistP384(key) => key.to_pkcs8_pem(pkcs8::LineEnding::LF),
+        }?;
+        Ok(pem.to_string())
     }
 
     /// Get the public key info for this private key.
     pub fn public_key_to_info(&self) -> x509_cert::spki::Result<SubjectPublicKeyInfoOwned> {
         use rsa::pkcs8::EncodePublicKey;
-        SubjectPublicKeyInfoOwned::try_from(
-            self.value
-                .to_public_key()
-                .to_public_key_der()
-                .unwrap()
-                .as_bytes(),
-        )
+        let der = match &self.value {
+            PrivateKeyValue::Rsa(key) => key.to_public_key().to_public_key_der(),
+            PrivateKeyValue::NistP256(key) => key.public_key().to_public_key_der(),
+            PrivateKeyValue::NistP384(key) => key.public_key().to_public_key_der(),
+        };
+        SubjectPublicKeyInfoOwned::try_from(der.unwrap().as_bytes())
     }
 
     /// Create a public key based on this private key.
     pub fn to_public_key(&self) -> PublicKey {
-        PublicKey {
-            value: self.value.to_public_key(),
+        let value = match &self.value {
+            PrivateKeyValue::Rsa(key) => PublicKeyValue::Rsa(key.to_public_key()),
+            PrivateKeyValue::NistP256(key) => PublicKeyValue::NistP256(key.public_key()),
+            PrivateKeyValue::NistP384(key) => PublicKeyValue::NistP384(key.public_key()),
+        };
+        PublicKey { value }
+    }
+
+    /// Get the curve of an ECC private key, or `None` for an RSA key.
+    pub fn ecc_curve(&self) -> Option<EccCurve> {
+        match &self.value {
+            PrivateKeyValue::Rsa(_) => None,
+            PrivateKeyValue::NistP256(_) => Some(EccCurve::NistP256),
+            PrivateKeyValue::NistP384(_) => Some(EccCurve::NistP384),
+        }
+    }
+
+    pub(crate) fn rsa(&self) -> Option<&RsaPrivateKey> {
+        match &self.value {
+            PrivateKeyValue::Rsa(key) => Some(key.as_ref()),
+            _ => None,
         }
     }
 
     /// Signs the data using RSA-SHA1
     pub fn sign_sha1(&self, data: &[u8], signature: &mut [u8]) -> Result<usize, Error> {
         let mut rng = rand::thread_rng();
-        let signing_key = pkcs1v15::SigningKey::<sha1::Sha1>::new(self.value.clone());
+        let key = self.rsa().ok_or_else(not_rsa_key)?;
+        let signing_key = pkcs1v15::SigningKey::<sha1::Sha1>::new(key.clone());
         match signing_key.try_sign_with_rng(&mut rng, data) {
             Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
             Ok(signed) => {
@@ -225,7 +347,8 @@ impl PrivateKey {
     /// Signs the data using RSA-SHA256
     pub fn sign_sha256(&self, data: &[u8], signature: &mut [u8]) -> Result<usize, Error> {
         let mut rng = rand::thread_rng();
-        let signing_key = pkcs1v15::SigningKey::<sha2::Sha256>::new(self.value.clone());
+        let key = self.rsa().ok_or_else(not_rsa_key)?;
+        let signing_key = pkcs1v15::SigningKey::<sha2::Sha256>::new(key.clone());
         match signing_key.try_sign_with_rng(&mut rng, data) {
             Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
             Ok(signed) => {
@@ -239,7 +362,8 @@ impl PrivateKey {
     /// Signs the data using RSA-SHA256-PSS
     pub fn sign_sha256_pss(&self, data: &[u8], signature: &mut [u8]) -> Result<usize, Error> {
         let mut rng = rand::thread_rng();
-        let signing_key = pss::BlindedSigningKey::<sha2::Sha256>::new(self.value.clone());
+        let key = self.rsa().ok_or_else(not_rsa_key)?;
+        let signing_key = pss::BlindedSigningKey::<sha2::Sha256>::new(key.clone());
         match signing_key.try_sign_with_rng(&mut rng, data) {
             Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
             Ok(signed) => {
@@ -250,18 +374,61 @@ impl PrivateKey {
         }
     }
 
-    fn pkcs1_decrypt(&self, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
-        self.value.decrypt(Pkcs1v15Encrypt, src)
+    /// Signs the data using ECDSA with the digest `D`. The signature is the raw `r || s` pair.
+    pub fn sign_ecdsa<D: Digest>(&self, data: &[u8], signature: &mut [u8]) -> Result<usize, Error> {
+        let hash = D::digest(data);
+        let signed = match &self.value {
+            PrivateKeyValue::NistP256(key) => {
+                let signing_key = p256::ecdsa::SigningKey::from(key);
+                PrehashSigner::<p256::ecdsa::Signature>::sign_prehash(&signing_key, &hash)
+                    .map(|s| s.to_bytes().to_vec())
+            }
+            PrivateKeyValue::NistP384(key) => {
+                let signing_key = p384::ecdsa::SigningKey::from(key);
+                PrehashSigner::<p384::ecdsa::Signature>::sign_prehash(&signing_key, &hash)
+                    .map(|s| s.to_bytes().to_vec())
+            }
+            PrivateKeyValue::Rsa(_) => return Err(not_ecc_key()),
+        };
+        match signed {
+            Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
+            Ok(val) => {
+                signature.copy_from_slice(&val);
+                Ok(val.len())
+            }
+        }
+    }
+
+    /// Computes the ECDH shared secret, i.e. the x coordinate of the shared point, from this
+    /// private key and the public key of the other party. Both keys must be on the same curve.
+    pub fn ecdh(&self, public_key: &PublicKey) -> Result<Vec<u8>, PKeyError> {
+        match (&self.value, &public_key.value) {
+            (PrivateKeyValue::NistP256(secret), PublicKeyValue::NistP256(public)) => Ok(
+                p256::ecdh::diffie_hellman(secret.to_nonzero_scalar(), public.as_affine())
+                    .raw_secret_bytes()
+                    .to_vec(),
+            ),
+            (PrivateKeyValue::NistP384(secret), PublicKeyValue::NistP384(public)) => Ok(
+                p384::ecdh::diffie_hellman(secret.to_nonzero_scalar(), public.as_affine())
+                    .raw_secret_bytes()
+                    .to_vec(),
+            ),
+            _ => Err(PKeyError),
+        }
+    }
+
+    fn pkcs1_decrypt(key: &RsaPrivateKey, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
+        key.decrypt(Pkcs1v15Encrypt, src)
     }
 
-    fn oaepsha1_decrypt(&self, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
+    fn oaepsha1_decrypt(key: &RsaPrivateKey, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
         let padding = Oaep::new::<sha1::Sha1>();
-        self.value.decrypt(padding, src)
+        key.decrypt(padding, src)
     }
 
-    fn oaepsha2_decrypt(&self, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
+    fn oaepsha2_decrypt(key: &RsaPrivateKey, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
         let padding = Oaep::new::<sha2::Sha256>();
-        self.value.decrypt(padding, src)
+        key.decrypt(padding, src)
     }
 
     /// Decrypts data in src to dst using the specified padding and returning the size of the decrypted
@@ -272,6 +439,7 @@ impl PrivateKey {
         dst: &mut [u8],
         padding: RsaPadding,
     ) -> Result<usize, PKeyError> {
+        let key = self.rsa().ok_or(PKeyError)?;
         let cipher_text_block_size = self.cipher_text_block_size();
 
         // Decrypt the data
@@ -288,9 +456,9 @@ impl PrivateKey {
                 let dst = &mut dst[dst_idx..(dst_idx + cipher_text_block_size)];
 
                 let decrypted = match padding {
-                    RsaPadding::OaepSha256 => self.oaepsha2_decrypt(src)?,
-                    RsaPadding::Pkcs1 => self.pkcs1_decrypt(src)?,
-                    RsaPadding::OaepSha1 => self.oaepsha1_decrypt(src)?,
+                    RsaPadding::OaepSha256 => Self::oaepsha2_decrypt(key, src)?,
+                    RsaPadding::Pkcs1 => Self::pkcs1_decrypt(key, src)?,
+                    RsaPadding::OaepSha1 => Self::oaepsha1_decrypt(key, src)?,
                 };
 
                 let size = decrypted.len();
@@ -311,14 +479,93 @@ impl KeySize for PublicKey {
     /// Length in bits
     fn size(&self) -> usize {
         use rsa::traits::PublicKeyParts;
-        self.value.size()
+        match &self.value {
+            PublicKeyValue::Rsa(key) => key.size(),
+            PublicKeyValue::NistP256(_) => EccCurve::NistP256.coordinate_size(),
+            PublicKeyValue::NistP384(_) => EccCurve::NistP384.coordinate_size(),
+        }
+    }
+
+    fn signature_size(&self) -> usize {
+        match &self.value {
+            PublicKeyValue::Rsa(_) => self.size(),
+            // Raw r || s pair
+            _ => self.size() * 2,
+        }
     }
 }
 
 impl PublicKey {
+    /// Read a public key from its subject public key info. The key is either an RSA key or
+    /// an ECC key on one of the supported curves.
+    pub fn from_info(info: &SubjectPublicKeyInfoOwned) -> Result<PublicKey, Error> {
+        use x509_cert::der::referenced::OwnedToRef;
+
+        let info = info.owned_to_ref();
+        let value = if let Ok(key) = p256::PublicKey::try_from(info.clone()) {
+            PublicKeyValue::NistP256(key)
+        } else if let Ok(key) = p384::PublicKey::try_from(info.clone()) {
+            PublicKeyValue::NistP384(key)
+        } else {
+            match RsaPublicKey::try_from(info) {
+                Err(e) => return Err(Error::new(StatusCode::BadCertificateInvalid, e)),
+                Ok(v) => PublicKeyValue::Rsa(v),
+            }
+        };
+        Ok(PublicKey { value })
+    }
+
+    /// Get the curve of an ECC public key, or `None` for an RSA key.
+    pub fn ecc_curve(&self) -> Option<EccCurve> {
+        match &self.value {
+            PublicKeyValue::Rsa(_) => None,
+            PublicKeyValue::NistP256(_) => Some(EccCurve::NistP256),
+            PublicKeyValue::NistP384(_) => Some(EccCurve::NistP384),
+        }
+    }
+
+    /// Read an ECC public key from a nonce, which holds the uncompressed point on the
+    /// curve without the leading SEC1 tag, i.e. `x || y`.
+    pub fn from_ecc_nonce(curve: EccCurve, nonce: &[u8]) -> Result<PublicKey, PKeyError> {
+        if nonce.len() != curve.coordinate_size() * 2 {
+            return Err(PKeyError);
+        }
+        let mut point = Vec::with_capacity(nonce.len() + 1);
+        point.push(0x04);
+        point.extend_from_slice(nonce);
+        let value = match curve {
+            EccCurve::NistP256 => {
+                PublicKeyValue::NistP256(p256::PublicKey::from_sec1_bytes(&point)?)
+            }
+            EccCurve::NistP384 => {
+                PublicKeyValue::NistP384(p384::PublicKey::from_sec1_bytes(&point)?)
+            }
+        };
+        Ok(PKey { value })
+    }
+
+    /// Encode an ECC public key as a nonce, see [`PublicKey::from_ecc_nonce`]. Returns `None`
+    /// for an RSA key.
+    pub fn to_ecc_nonce(&self) -> Option<Vec<u8>> {
+        let point = match &self.value {
+            PublicKeyValue::Rsa(_) => return None,
+            PublicKeyValue::NistP256(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+            PublicKeyValue::NistP384(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+        };
+        Some(point[1..].to_vec())
+    }
+
+    pub(crate) fn rsa(&self) -> Option<&RsaPublicKey> {
+        match &self.value {
+            PublicKeyValue::Rsa(key) => Some(key),
+            _ => None,
+        }
+    }
+
     /// Verifies the data using RSA-SHA1
     pub fn verify_sha1(&self, data: &[u8], signature: &[u8]) -> Result<bool, Error> {
-        let verifying_key = pkcs1v15::VerifyingKey::<sha1::Sha1>::new(self.value.clone());
+        let key = self.rsa().ok_or_else(not_rsa_key)?;
+        let verifying_key = pkcs1v15::VerifyingKey::<sha1::Sha1>::new(key.clone());
         let r = pkcs1v15::Signature::try_from(signature);
         match r {
             Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
@@ -331,7 +578,8 @@ impl PublicKey {
 
     /// Verifies the data using RSA-SHA256
     pub fn verify_sha256(&self, data: &[u8], signature: &[u8]) -> Result<bool, Error> {
-        let verifying_key = pkcs1v15::VerifyingKey::<sha2::Sha256>::new(self.value.clone());
+        let key = self.rsa().ok_or_else(not_rsa_key)?;
+        let verifying_key = pkcs1v15::VerifyingKey::<sha2::Sha256>::new(key.clone());
         let r = pkcs1v15::Signature::try_from(signature);
         match r {
             Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
@@ -344,7 +592,8 @@ impl PublicKey {
 
     /// Verifies the data using RSA-SHA256-PSS
     pub fn verify_sha256_pss(&self, data: &[u8], signature: &[u8]) -> Result<bool, Error> {
-        let verifying_key = pss::VerifyingKey::<sha2::Sha256>::new(self.value.clone());
+        let key = self.rsa().ok_or_else(not_rsa_key)?;
+        let verifying_key = pss::VerifyingKey::<sha2::Sha256>::new(key.clone());
         let r = pss::Signature::try_from(signature);
         match r {
             Err(e) => Err(Error::new(StatusCode::BadUnexpectedError, e)),
@@ -355,28 +604,105 @@ impl PublicKey {
         }
     }
 
-    fn pkcs1_encrypt(&self, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
+    /// Verifies the data using ECDSA with the digest `D`. The signature is the raw `r || s` pair.
+    pub fn verify_ecdsa<D: Digest>(&self, data: &[u8], signature: &[u8]) -> Result<bool, Error> {
+        use p256::ecdsa::signature::hazmat::PrehashVerifier;
+
+        let hash = D::digest(data);
+        let verified = match &self.value {
+            PublicKeyValue::NistP256(key) => {
+                let signature = p256::ecdsa::Signature::from_slice(signature)
+                    .map_err(|e| Error::new(StatusCode::BadUnexpectedError, e))?;
+                p256::ecdsa::VerifyingKey::from(key)
+                    .verify_prehash(&hash, &signature)
+                    .is_ok()
+            }
+            PublicKeyValue::NistP384(key) => {
+                let signature = p384::ecdsa::Signature::from_slice(signature)
+                    .map_err(|e| Error::new(StatusCode::BadUnexpectedError, e))?;
+                p384::ecdsa::VerifyingKey::from(key)
+                    .verify_prehash(&hash, &signature)
+                    .is_ok()
+            }
+            PublicKeyValue::Rsa(_) => return Err(not_ecc_key()),
+        };
+        Ok(verified)
+    }
+
+    /// Verifies the data using ECDSA with the digest `D`, where the signature is DER encoded
+    /// as it is in X509 certificates.
+    pub(crate) fn verify_ecdsa_der<D: Digest>(&self, data: &[u8], signature: &[u8]) -> bool {
+        use p256::ecdsa::signature::hazmat::PrehashVerifier;
+
+        let hash = D::digest(data);
+        match &self.value {
+            PublicKeyValue::NistP256(key) => {
+                p256::ecdsa::Signature::from_der(signature).is_ok_and(|signature| {
+                    p256::ecdsa::VerifyingKey::from(key)
+                        .verify_prehash(&hash, &signature)
+                        .is_ok()
+                })
+            }
+            PublicKeyValue::NistP384(key) => {
+                p384::ecdsa::Signature::from_der(signature).is_ok_and(|signature| {
+                    p384::ecdsa::VerifyingKey::from(key)
+                        .verify_prehash(&hash, &signature)
+                        .is_ok()
+                })
+            }
+            PublicKeyValue::Rsa(_) => false,
+        }
+    }
+
+    /// Verifies the data using RSA PKCS#1 v1.5 with the digest `D`.
+    pub(crate) fn verify_pkcs1v15<D>(&self, data: &[u8], signature: &[u8]) -> bool
+    where
+        D: Digest + AssociatedOid,
+    {
+        let Some(key) = self.rsa() else {
+            return false;
+        };
+        let verifying_key = pkcs1v15::VerifyingKey::<D>::new(key.clone());
+        pkcs1v15::Signature::try_from(signature)
+            .is_ok_and(|signature| verifying_key.verify(data, &signature).is_ok())
+    }
+
+    /// Verifies the data using RSA-PSS with the digest `D` and the given salt length.
+    pub(crate) fn verify_pss<D>(&self, data: &[u8], signature: &[u8], salt_len: usize) -> bool
+    where
+        D: Digest + FixedOutputReset,
+    {
+        let Some(key) = self.rsa() else {
+            return false;
+        };
+        let verifying_key = pss::VerifyingKey::<D>::new_with_salt_len(key.clone(), salt_len);
+        pss::Signature::try_from(signature)
+            .is_ok_and(|signature| verifying_key.verify(data, &signature).is_ok())
+    }
+
+    fn pkcs1_encrypt(key: &RsaPublicKey, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
         let mut rng = rand::thread_rng();
-        self.value.encrypt(&mut rng, Pkcs1v15Encrypt, src)
+        key.encrypt(&mut rng, Pkcs1v15Encrypt, src)
     }
 
-    fn oaepsha1_encrypt(&self, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
+    fn oaepsha1_encrypt(key: &RsaPublicKey, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
         let mut rng = rand::thread_rng();
         let padding = Oaep::new::<sha1::Sha1>();
-        self.value.encrypt(&mut rng, padding, src)
+        key.encrypt(&mut rng, padding, src)
     }
 
-    fn oaepsha2_encrypt(&self, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
+    fn oaepsha2_encrypt(key: &RsaPublicKey, src: &[u8]) -> rsa::errors::Result<Vec<u8>> {
         let mut rng = rand::thread_rng();
         let padding = Oaep::new::<sha2::Sha256>();
-        self.value.encrypt(&mut rng, padding, src)
+        key.encrypt(&mut rng, padding, src)
     }
 
     fn encrypt_data_chunk(&self, src: &[u8], padding: RsaPadding) -> Result<Vec<u8>, PKeyError> {
+        let key = self.rsa().ok_or(PKeyError)?;
         let r = match padding {
-            RsaPadding::OaepSha256 => self.oaepsha2_encrypt(src),
-            RsaPadding::Pkcs1 => self.pkcs1_encrypt(src),
-            RsaPadding::OaepSha1 => self.oaepsha1_encrypt(src),
+            RsaPadding::OaepSha256 => Self::oaepsha2_encrypt(key, src),
+            RsaPadding::Pkcs1 => Self::pkcs1_encrypt(key, src),
+            RsaPadding::OaepSha1 => Self::oaepsha1_encrypt(key, src),
         };
 
         match r {
@@ -14,8 +14,8 @@ use opcua_types::{constants, status_code::StatusCode, ByteString, Error};
 use super::{
     aeskey::AesKey,
     hash,
-    pkey::{PrivateKey, PublicKey, RsaPadding},
-    random, SHA1_SIZE, SHA256_SIZE,
+    pkey::{EccCurve, PrivateKey, PublicKey, RsaPadding},
+    random, SHA1_SIZE, SHA256_SIZE, SHA384_SIZE,
 };
 
 // These are constants that govern the different encryption / signing modes for OPC UA. In some
@@ -159,6 +159,58 @@ mod basic_256 {
     pub const ASYMMETRIC_KEY_LENGTH: (usize, usize) = (1024, 2048);
 }
 
+/// ECC_nistP256 security policy
+///
+///   AsymmetricSignatureAlgorithm_ECDSA-SHA2-256
+///   CertificateSignatureAlgorithm_ECDSA-SHA2-256
+///   KeyAgreementAlgorithm_ECDH-nistP256
+///   KeyDerivationAlgorithm_HKDF-SHA2-256
+///   SymmetricEncryptionAlgorithm_AES128-CBC
+///   SymmetricSignatureAlgorithm_HMAC-SHA2-256
+///
+/// # Limits
+///
+///   DerivedSignatureKeyLength – 256 bits
+///   AsymmetricKeyLength - 256 bits
+///   SecureChannelNonceLength - 64 bytes
+mod ecc_nist_p256 {
+    use crate::algorithms::*;
+
+    pub const SECURITY_POLICY: &str = "ECC-nistP256";
+    pub const SECURITY_POLICY_URI: &str = "http://opcfoundation.org/UA/SecurityPolicy#ECC_nistP256";
+
+    pub const SYMMETRIC_SIGNATURE_ALGORITHM: &str = DSIG_HMAC_SHA256;
+    pub const ASYMMETRIC_SIGNATURE_ALGORITHM: &str = DSIG_ECDSA_SHA256;
+    pub const DERIVED_SIGNATURE_KEY_LENGTH: usize = 256;
+    pub const ASYMMETRIC_KEY_LENGTH: (usize, usize) = (256, 256);
+}
+
+/// ECC_nistP384 security policy
+///
+///   AsymmetricSignatureAlgorithm_ECDSA-SHA2-384
+///   CertificateSignatureAlgorithm_ECDSA-SHA2-384
+///   KeyAgreementAlgorithm_ECDH-nistP384
+///   KeyDerivationAlgorithm_HKDF-SHA2-384
+///   SymmetricEncryptionAlgorithm_AES256-CBC
+///   SymmetricSignatureAlgorithm_HMAC-SHA2-384
+///
+/// # Limits
+///
+///   DerivedSignatureKeyLength – 384 bits
+///   AsymmetricKeyLength - 384 bits
+///   SecureChannelNonceLength - 96 bytes
+mod ecc_nist_p384 {
+    use crate::algorithms::*;
+
+    pub const SECURITY_POLICY: &str = "ECC-nistP384";
+    pub const SECURITY_POLICY_URI: &str = "http://opcfoundation.org/UA/SecurityPolicy#ECC_nistP384";
+
+    pub const SYMMETRIC_SIGNATURE_ALGORITHM: &str = DSIG_HMAC_SHA384;
+    pub const ASYMMETRIC_SIGNATURE_ALGORITHM: &str = DSIG_ECDSA_SHA384;
+    pub const DERIVED_SIGNATURE_KEY_LENGTH: usize = 384;
+    pub const ASYMMETRIC_KEY_LENGTH: (usize, usize) = (384, 384);
+}
+
 /// SecurityPolicy implies what encryption and signing algorithms and their relevant key strengths
 /// are used during an encrypted session.
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -177,6 +229,10 @@ pub enum SecurityPolicy {
     Basic128Rsa15,
     /// Basic256.
     Basic256,
+    /// ECC with the NIST P-256 curve.
+    EccNistP256,
+    /// ECC with the NIST P-384 curve.
+    EccNistP384,
 }
 
 impl fmt::Display for SecurityPolicy {
@@ -204,6 +260,12 @@ impl FromStr for SecurityPolicy {
             | aes_128_sha_256_rsa_oaep::SECURITY_POLICY_URI => SecurityPolicy::Aes128Sha256RsaOaep,
             aes_256_sha_256_rsa_pss::SECURITY_POLICY
             | aes_256_sha_256_rsa_pss::SECURITY_POLICY_URI => SecurityPolicy::Aes256Sha256RsaPss,
+            ecc_nist_p256::SECURITY_POLICY | ecc_nist_p256::SECURITY_POLICY_URI => {
+                SecurityPolicy::EccNistP256
+            }
+            ecc_nist_p384::SECURITY_POLICY | ecc_nist_p384::SECURITY_POLICY_URI => {
+                SecurityPolicy::EccNistP384
+            }
             _ => {
                 error!("Specified security policy \"{}\" is not recognized", s);
                 SecurityPolicy::Unknown
@@ -230,6 +292,8 @@ impl SecurityPolicy {
             SecurityPolicy::Basic256Sha256 => basic_256_sha_256::SECURITY_POLICY_URI,
             SecurityPolicy::Aes128Sha256RsaOaep => aes_128_sha_256_rsa_oaep::SECURITY_POLICY_URI,
             SecurityPolicy::Aes256Sha256RsaPss => aes_256_sha_256_rsa_pss::SECURITY_POLICY_URI,
+            SecurityPolicy::EccNistP256 => ecc_nist_p256::SECURITY_POLICY_URI,
+            SecurityPolicy::EccNistP384 => ecc_nist_p384::SECURITY_POLICY_URI,
             _ => {
                 panic!("Shouldn't be turning an unknown policy into a uri");
             }
@@ -246,9 +310,28 @@ impl SecurityPolicy {
                 | SecurityPolicy::Basic256Sha256
                 | SecurityPolicy::Aes128Sha256RsaOaep
                 | SecurityPolicy::Aes256Sha256RsaPss
+                | SecurityPolicy::EccNistP256
+                | SecurityPolicy::EccNistP384
         )
     }
 
+    /// Returns true if the security policy is one of the ECC policies. These policies agree
+    /// on the secure channel keys with ephemeral ECDH keys exchanged as nonces, and sign but
+    /// never encrypt with the application instance certificates.
+    pub fn is_ecc(&self) -> bool {
+        self.ecc_curve().is_some()
+    }
+
+    /// Get the curve of the ephemeral keys and certificates of an ECC policy, or `None` for
+    /// any other policy.
+    pub fn ecc_curve(&self) -> Option<EccCurve> {
+        match self {
+            SecurityPolicy::EccNistP256 => Some(EccCurve::NistP256),
+            SecurityPolicy::EccNistP384 => Some(EccCurve::NistP384),
+            _ => None,
+        }
+    }
+
     /// Returns true if the security policy has been deprecated by the OPC UA specification
     pub fn is_deprecated(&self) -> bool {
         // Since 1.04 because SHA-1 is no longer considered safe
@@ -269,6 +352,8 @@ impl SecurityPolicy {
             SecurityPolicy::Basic256Sha256 => basic_256_sha_256::SECURITY_POLICY,
             SecurityPolicy::Aes128Sha256RsaOaep => aes_128_sha_256_rsa_oaep::SECURITY_POLICY,
             SecurityPolicy::Aes256Sha256RsaPss => aes_256_sha_256_rsa_pss::SECURITY_POLICY,
+            SecurityPolicy::EccNistP256 => ecc_nist_p256::SECURITY_POLICY,
+            SecurityPolicy::EccNistP384 => ecc_nist_p384::SECURITY_POLICY,
             _ => {
                 panic!("Shouldn't be turning an unknown policy into a string");
             }
@@ -277,7 +362,8 @@ impl SecurityPolicy {
 
     /// Get the asymmetric encryption algorithm for this security policy.
     ///
-    /// This will panic if the security policy is `Unknown` or `None`.
+    /// This will panic if the security policy is `Unknown`, `None` or an ECC policy, since
+    /// those do not use asymmetric encryption.
     pub fn asymmetric_encryption_algorithm(&self) -> &'static str {
         match self {
             SecurityPolicy::Basic128Rsa15 => basic_128_rsa_15::ASYMMETRIC_ENCRYPTION_ALGORITHM,
@@ -309,6 +395,8 @@ impl SecurityPolicy {
             SecurityPolicy::Aes256Sha256RsaPss => {
                 aes_256_sha_256_rsa_pss::ASYMMETRIC_SIGNATURE_ALGORITHM
             }
+            SecurityPolicy::EccNistP256 => ecc_nist_p256::ASYMMETRIC_SIGNATURE_ALGORITHM,
+            SecurityPolicy::EccNistP384 => ecc_nist_p384::ASYMMETRIC_SIGNATURE_ALGORITHM,
             _ => {
                 panic!("Invalid policy");
             }
@@ -329,6 +417,8 @@ impl SecurityPolicy {
             SecurityPolicy::Aes256Sha256RsaPss => {
                 aes_256_sha_256_rsa_pss::SYMMETRIC_SIGNATURE_ALGORITHM
             }
+            SecurityPolicy::EccNistP256 => ecc_nist_p256::SYMMETRIC_SIGNATURE_ALGORITHM,
+            SecurityPolicy::EccNistP384 => ecc_nist_p384::SYMMETRIC_SIGNATURE_ALGORITHM,
             _ => {
                 panic!("Invalid policy");
             }
@@ -344,7 +434,9 @@ impl SecurityPolicy {
             | SecurityPolicy::Basic256
             | SecurityPolicy::Basic256Sha256
             | SecurityPolicy::Aes128Sha256RsaOaep
-            | SecurityPolicy::Aes256Sha256RsaPss => 16,
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256
+            | SecurityPolicy::EccNistP384 => 16,
             _ => {
                 panic!("Invalid policy");
             }
@@ -360,7 +452,9 @@ impl SecurityPolicy {
             SecurityPolicy::Basic128Rsa15 | SecurityPolicy::Basic256 => SHA1_SIZE,
             SecurityPolicy::Basic256Sha256
             | SecurityPolicy::Aes128Sha256RsaOaep
-            | SecurityPolicy::Aes256Sha256RsaPss => SHA256_SIZE,
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256 => SHA256_SIZE,
+            SecurityPolicy::EccNistP384 => SHA384_SIZE,
             _ => {
                 panic!("Invalid policy");
             }
@@ -381,6 +475,8 @@ impl SecurityPolicy {
             SecurityPolicy::Aes256Sha256RsaPss => {
                 aes_256_sha_256_rsa_pss::DERIVED_SIGNATURE_KEY_LENGTH
             }
+            SecurityPolicy::EccNistP256 => ecc_nist_p256::DERIVED_SIGNATURE_KEY_LENGTH,
+            SecurityPolicy::EccNistP384 => ecc_nist_p384::DERIVED_SIGNATURE_KEY_LENGTH,
             _ => {
                 panic!("Invalid policy");
             }
@@ -396,6 +492,8 @@ impl SecurityPolicy {
             SecurityPolicy::Basic256Sha256 => basic_256_sha_256::ASYMMETRIC_KEY_LENGTH,
             SecurityPolicy::Aes128Sha256RsaOaep => aes_128_sha_256_rsa_oaep::ASYMMETRIC_KEY_LENGTH,
             SecurityPolicy::Aes256Sha256RsaPss => aes_256_sha_256_rsa_pss::ASYMMETRIC_KEY_LENGTH,
+            SecurityPolicy::EccNistP256 => ecc_nist_p256::ASYMMETRIC_KEY_LENGTH,
+            SecurityPolicy::EccNistP384 => ecc_nist_p384::ASYMMETRIC_KEY_LENGTH,
             _ => {
                 panic!("Invalid policy");
             }
@@ -408,7 +506,10 @@ impl SecurityPolicy {
         keylength >= min_max.0 && keylength <= min_max.1
     }
 
-    /// Creates a random nonce in a bytestring with a length appropriate for the policy
+    /// Creates a random nonce in a bytestring with a length appropriate for the policy.
+    ///
+    /// The secure channel of an ECC policy uses the public key of an ephemeral key pair as its
+    /// nonce instead, see [`SecurityPolicy::ephemeral_key`].
     pub fn random_nonce(&self) -> ByteString {
         match self {
             SecurityPolicy::None => ByteString::null(),
@@ -416,6 +517,12 @@ impl SecurityPolicy {
         }
     }
 
+    /// Creates an ephemeral ECC key pair on the curve of the policy, or `None` if this is not
+    /// an ECC policy. The public key encoded as a nonce is sent to the other party.
+    pub fn ephemeral_key(&self) -> Option<PrivateKey> {
+        self.ecc_curve().map(PrivateKey::new_ecc)
+    }
+
     /// Length of the secure channel nonce for this security policy.
     pub fn secure_channel_nonce_length(&self) -> usize {
         match self {
@@ -424,6 +531,10 @@ impl SecurityPolicy {
             | SecurityPolicy::Basic256Sha256
             | SecurityPolicy::Aes128Sha256RsaOaep
             | SecurityPolicy::Aes256Sha256RsaPss => 32,
+            // The uncompressed point of the ephemeral public key
+            SecurityPolicy::EccNistP256 | SecurityPolicy::EccNistP384 => {
+                self.ecc_curve().unwrap().coordinate_size() * 2
+            }
             // The nonce can be used for password or X509 authentication
             // even when the security policy is None.
             // see https://github.com/advisories/GHSA-pq4w-qm9g-qx68
@@ -441,6 +552,8 @@ impl SecurityPolicy {
             basic_256_sha_256::SECURITY_POLICY_URI => SecurityPolicy::Basic256Sha256,
             aes_128_sha_256_rsa_oaep::SECURITY_POLICY_URI => SecurityPolicy::Aes128Sha256RsaOaep,
             aes_256_sha_256_rsa_pss::SECURITY_POLICY_URI => SecurityPolicy::Aes256Sha256RsaPss,
+            ecc_nist_p256::SECURITY_POLICY_URI => SecurityPolicy::EccNistP256,
+            ecc_nist_p384::SECURITY_POLICY_URI => SecurityPolicy::EccNistP384,
             _ => {
                 error!(
                     "Specified security policy uri \"{}\" is not recognized",
@@ -511,15 +624,7 @@ impl SecurityPolicy {
     ) -> (Vec<u8>, AesKey, Vec<u8>) {
         // Work out the length of stuff
         let signing_key_length = self.derived_signature_key_size();
-        let (encrypting_key_length, encrypting_block_size) = match self {
-            SecurityPolicy::Basic128Rsa15 | SecurityPolicy::Aes128Sha256RsaOaep => (16, 16),
-            SecurityPolicy::Basic256
-            | SecurityPolicy::Basic256Sha256
-            | SecurityPolicy::Aes256Sha256RsaPss => (32, 16),
-            _ => {
-                panic!("Invalid policy");
-            }
-        };
+        let (encrypting_key_length, encrypting_block_size) = self.encrypting_key_sizes();
 
         let signing_key = self.prf(secret, seed, signing_key_length, 0);
         let encrypting_key = self.prf(secret, seed, encrypting_key_length, signing_key_length);
@@ -534,6 +639,84 @@ impl SecurityPolicy {
         (signing_key, encrypting_key, iv)
     }
 
+    /// Length of the symmetric encryption key and the block size (which is the length of the
+    /// initialization vector) in bytes.
+    fn encrypting_key_sizes(&self) -> (usize, usize) {
+        match self {
+            SecurityPolicy::Basic128Rsa15
+            | SecurityPolicy::Aes128Sha256RsaOaep
+            | SecurityPolicy::EccNistP256 => (16, 16),
+            SecurityPolicy::Basic256
+            | SecurityPolicy::Basic256Sha256
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP384 => (32, 16),
+            _ => {
+                panic!("Invalid policy");
+            }
+        }
+    }
+
+    /// Part 6
+    /// 6.8.1
+    /// The ECC security policies derive the keys from the secret agreed on with ECDH, using
+    /// the ephemeral keys exchanged as nonces in the OpenSecureChannel call, rather than from
+    /// the nonces themselves. The keys are created with HKDF using a salt which is also passed
+    /// as the info parameter:
+    ///
+    /// Key | Salt
+    /// ClientKeys | L \| UTF8(opcua-client) \| ClientNonce \| ServerNonce
+    /// ServerKeys | L \| UTF8(opcua-server) \| ServerNonce \| ClientNonce
+    ///
+    /// Where L is the length of the derived keys as a little endian UInt16. The signing key,
+    /// encrypting key and initialization vector are taken in that order from the derived bytes.
+    ///
+    /// The Client keys are used to secure Messages sent by the Client. The Server keys
+    /// are used to secure Messages sent by the Server.
+    pub fn make_ecc_secure_channel_keys(
+        &self,
+        shared_secret: &[u8],
+        client_nonce: &[u8],
+        server_nonce: &[u8],
+        client_keys: bool,
+    ) -> (Vec<u8>, AesKey, Vec<u8>) {
+        let signing_key_length = self.derived_signature_key_size();
+        let (encrypting_key_length, encrypting_block_size) = self.encrypting_key_sizes();
+        let length = signing_key_length + encrypting_key_length + encrypting_block_size;
+
+        let mut salt = Vec::with_capacity(2 + 12 + client_nonce.len() + server_nonce.len());
+        salt.extend_from_slice(&(length as u16).to_le_bytes());
+        if client_keys {
+            salt.extend_from_slice(b"opcua-client");
+            salt.extend_from_slice(client_nonce);
+            salt.extend_from_slice(server_nonce);
+        } else {
+            salt.extend_from_slice(b"opcua-server");
+            salt.extend_from_slice(server_nonce);
+            salt.extend_from_slice(client_nonce);
+        }
+
+        let mut keys = vec![0u8; length];
+        let expanded = match self {
+            SecurityPolicy::EccNistP256 => {
+                hkdf::Hkdf::<sha2::Sha256>::new(Some(&salt), shared_secret).expand(&salt, &mut keys)
+            }
+            SecurityPolicy::EccNistP384 => {
+                hkdf::Hkdf::<sha2::Sha384>::new(Some(&salt), shared_secret).expand(&salt, &mut keys)
+            }
+            _ => {
+                panic!("Invalid policy");
+            }
+        };
+        // The length is far below the HKDF limit of 255 hashes
+        expanded.unwrap();
+
+        let iv = keys.split_off(signing_key_length + encrypting_key_length);
+        let encrypting_key = AesKey::new(*self, &keys[signing_key_length..]);
+        keys.truncate(signing_key_length);
+
+        (keys, encrypting_key, iv)
+    }
+
     /// Produce a signature of the data using an asymmetric key. Stores the signature in the supplied
     /// `signature` buffer. Returns the size of the signature within that buffer.
     pub fn asymmetric_sign(
@@ -550,6 +733,12 @@ impl SecurityPolicy {
                 signing_key.sign_sha256(data, signature)?
             }
             SecurityPolicy::Aes256Sha256RsaPss => signing_key.sign_sha256_pss(data, signature)?,
+            SecurityPolicy::EccNistP256 => {
+                signing_key.sign_ecdsa::<sha2::Sha256>(data, signature)?
+            }
+            SecurityPolicy::EccNistP384 => {
+                signing_key.sign_ecdsa::<sha2::Sha384>(data, signature)?
+            }
             _ => {
                 panic!("Invalid policy");
             }
@@ -578,6 +767,12 @@ impl SecurityPolicy {
             SecurityPolicy::Aes256Sha256RsaPss => {
                 verification_key.verify_sha256_pss(data, signature)?
             }
+            SecurityPolicy::EccNistP256 => {
+                verification_key.verify_ecdsa::<sha2::Sha256>(data, signature)?
+            }
+            SecurityPolicy::EccNistP384 => {
+                verification_key.verify_ecdsa::<sha2::Sha384>(data, signature)?
+            }
             _ => {
                 panic!("Invalid policy");
             }
@@ -591,7 +786,7 @@ impl SecurityPolicy {
                 use crate::pkey::KeySize;
                 use log::trace;
                 // Calculate the signature using their key, see what we were expecting versus theirs
-                let mut their_signature = vec![0u8; their_key.size()];
+                let mut their_signature = vec![0u8; their_key.signature_size()];
                 self.asymmetric_sign(&their_key, data, their_signature.as_mut_slice())?;
                 trace!(
                     "Using their_key, signature should be {:?}",
@@ -664,7 +859,9 @@ impl SecurityPolicy {
             }
             SecurityPolicy::Basic256Sha256
             | SecurityPolicy::Aes128Sha256RsaOaep
-            | SecurityPolicy::Aes256Sha256RsaPss => hash::hmac_sha256(key, data, signature),
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256 => hash::hmac_sha256(key, data, signature),
+            SecurityPolicy::EccNistP384 => hash::hmac_sha384(key, data, signature),
             _ => {
                 panic!("Unsupported policy")
             }
@@ -678,14 +875,16 @@ impl SecurityPolicy {
         data: &[u8],
         signature: &[u8],
     ) -> Result<bool, Error> {
-        // Verify the signature using SHA-1 / SHA-256 HMAC
+        // Verify the signature using SHA-1 / SHA-256 / SHA-384 HMAC
         let verified = match self {
             SecurityPolicy::Basic128Rsa15 | SecurityPolicy::Basic256 => {
                 hash::verify_hmac_sha1(key, data, signature)
             }
             SecurityPolicy::Basic256Sha256
             | SecurityPolicy::Aes128Sha256RsaOaep
-            | SecurityPolicy::Aes256Sha256RsaPss => hash::verify_hmac_sha256(key, data, signature),
+            | SecurityPolicy::Aes256Sha256RsaPss
+            | SecurityPolicy::EccNistP256 => hash::verify_hmac_sha256(key, data, signature),
+            SecurityPolicy::EccNistP384 => hash::verify_hmac_sha384(key, data, signature),
             _ => {
                 panic!("Unsupported policy")
             }
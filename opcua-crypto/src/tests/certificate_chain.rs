@@ -0,0 +1,462 @@
+use std::{str::FromStr, time::Duration};
+
+use opcua_types::StatusCode;
+use rsa::pkcs1v15;
+use x509_cert::{
+    builder::{Builder, CertificateBuilder, Profile},
+    crl::{CertificateList, RevokedCert, TbsCertList},
+    der::{asn1::BitString, Encode},
+    ext::pkix::{BasicConstraints, ExtendedKeyUsage, KeyUsage, KeyUsages},
+    name::Name,
+    serial_number::SerialNumber,
+    spki::DynSignatureAlgorithmIdentifier,
+    time::{Time, Validity},
+    Version,
+};
+
+use crate::{
+    certificate_store::{CertificateStore, FileSystemCertificateStore, InMemoryCertificateStore},
+    crl::CertificateRevocationList,
+    pkey::PrivateKey,
+    tests::{make_certificate_store, APPLICATION_HOSTNAME, APPLICATION_URI},
+    x509::{AlternateNames, X509},
+    SecurityPolicy,
+};
+
+struct TestCert {
+    cert: X509,
+    pkey: PrivateKey,
+}
+
+/// Kinds of certificates issued in tests.
+enum Usage {
+    Ca {
+        path_len: Option<u8>,
+    },
+    Application,
+    /// An application certificate with an extended key usage not allowing client or server auth.
+    CodeSigning,
+}
+
+fn issue_cert(common_name: &str, serial: u32, usage: Usage, issuer: Option<&TestCert>) -> TestCert {
+    let pkey = PrivateKey::new(1024).unwrap();
+    let subject = Name::from_str(&format!("CN={common_name},O=x.org")).unwrap();
+    let (issuer_name, issuer_key) = match issuer {
+        Some(issuer) => (issuer.cert.subject().clone(), &issuer.pkey),
+        None => (subject.clone(), &pkey),
+    };
+    let signing_key = pkcs1v15::SigningKey::<sha2::Sha256>::new(issuer_key.rsa().unwrap().clone());
+
+    let mut builder = CertificateBuilder::new(
+        Profile::Manual {
+            issuer: Some(issuer_name),
+        },
+        SerialNumber::from(serial),
+        Validity::from_now(Duration::from_secs(86400)).unwrap(),
+        subject,
+        pkey.public_key_to_info().unwrap(),
+        &signing_key,
+    )
+    .unwrap();
+    match usage {
+        Usage::Ca { path_len } => {
+            builder
+                .add_extension(&BasicConstraints {
+                    ca: true,
+                    path_len_constraint: path_len,
+                })
+                .unwrap();
+            builder
+                .add_extension(&KeyUsage(KeyUsages::KeyCertSign | KeyUsages::CRLSign))
+                .unwrap();
+        }
+        Usage::Application | Usage::CodeSigning => {
+            builder
+                .add_extension(&BasicConstraints {
+                    ca: false,
+                    path_len_constraint: None,
+                })
+                .unwrap();
+            builder
+                .add_extension(&KeyUsage(
+                    KeyUsages::DigitalSignature
+                        | KeyUsages::NonRepudiation
+                        | KeyUsages::KeyEncipherment
+                        | KeyUsages::DataEncipherment,
+                ))
+                .unwrap();
+            let extended_key_usage = if matches!(usage, Usage::CodeSigning) {
+                vec![const_oid::db::rfc5280::ID_KP_CODE_SIGNING]
+            } else {
+                vec![
+                    const_oid::db::rfc5280::ID_KP_CLIENT_AUTH,
+                    const_oid::db::rfc5280::ID_KP_SERVER_AUTH,
+                ]
+            };
+            builder
+                .add_extension(&ExtendedKeyUsage(extended_key_usage))
+                .unwrap();
+            let alt_host_names = AlternateNames::new_from_addresses(vec![
+                APPLICATION_URI.to_string(),
+                APPLICATION_HOSTNAME.to_string(),
+            ]);
+            builder.add_extension(&alt_host_names.names).unwrap();
+        }
+    }
+    let cert = builder.build().unwrap();
+    TestCert {
+        cert: X509::from_der(&cert.to_der().unwrap()).unwrap(),
+        pkey,
+    }
+}
+
+fn make_crl(issuer: &TestCert, revoked: &[&TestCert]) -> CertificateRevocationList {
+    make_crl_with_next_update(issuer, revoked, None)
+}
+
+fn make_crl_with_next_update(
+    issuer: &TestCert,
+    revoked: &[&TestCert],
+    next_update: Option<std::time::SystemTime>,
+) -> CertificateRevocationList {
+    let signing_key = pkcs1v15::SigningKey::<sha2::Sha256>::new(issuer.pkey.rsa().unwrap().clone());
+    let now = Time::try_from(std::time::SystemTime::now()).unwrap();
+    let revoked_certificates = revoked
+        .iter()
+        .map(|c| RevokedCert {
+            serial_number: c.cert.serial_number().clone(),
+            revocation_date: now,
+            crl_entry_extensions: None,
+        })
+        .collect::<Vec<_>>();
+    let tbs_cert_list = TbsCertList {
+        version: Version::V2,
+        signature: signing_key.signature_algorithm_identifier().unwrap(),
+        issuer: issuer.cert.subject().clone(),
+        this_update: now,
+        next_update: next_update.map(|t| Time::try_from(t).unwrap()),
+        revoked_certificates: (!revoked_certificates.is_empty()).then_some(revoked_certificates),
+        crl_extensions: None,
+    };
+    let mut signature = [0u8; 128];
+    issuer
+        .pkey
+        .sign_sha256(&tbs_cert_list.to_der().unwrap(), &mut signature)
+        .unwrap();
+    let crl = CertificateList {
+        signature_algorithm: tbs_cert_list.signature.clone(),
+        tbs_cert_list,
+        signature: BitString::from_bytes(&signature).unwrap(),
+    };
+    CertificateRevocationList::from_der(&crl.to_der().unwrap()).unwrap()
+}
+
+fn add_trusted_cert(cert_store: &FileSystemCertificateStore, cert: &TestCert) {
+    let path = cert_store
+        .trusted_certs_dir()
+        .join(FileSystemCertificateStore::cert_file_name(&cert.cert));
+    std::fs::write(path, cert.cert.to_der().unwrap()).unwrap();
+}
+
+fn add_issuer_cert(cert_store: &FileSystemCertificateStore, cert: &TestCert) {
+    let path = cert_store
+        .issuer_certs_dir()
+        .join(FileSystemCertificateStore::cert_file_name(&cert.cert));
+    std::fs::write(path, cert.cert.to_der().unwrap()).unwrap();
+}
+
+fn add_crl(cert_store: &FileSystemCertificateStore, name: &str, crl: &CertificateRevocationList) {
+    let path = cert_store.crl_dir().join(format!("{name}.crl"));
+    std::fs::write(path, crl.to_der().unwrap()).unwrap();
+}
+
+fn validate(cert_store: &dyn CertificateStore, cert: &TestCert) -> Result<(), StatusCode> {
+    cert_store.validate_application_instance_cert(
+        &cert.cert,
+        SecurityPolicy::Basic128Rsa15,
+        Some(APPLICATION_HOSTNAME),
+        Some(APPLICATION_URI),
+    )
+}
+
+#[test]
+fn trusted_ca_issued_cert() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let app = issue_cert("App", 2, Usage::Application, Some(&root));
+    assert!(app.cert.is_issued_by(&root.cert));
+    assert!(!app.cert.is_self_signed());
+    assert!(root.cert.is_self_signed());
+
+    // The issuer of the certificate is unknown.
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateChainIncomplete)
+    );
+
+    // Certificates issued by a trusted CA are trusted, once the CA has a revocation list.
+    std::fs::remove_dir_all(cert_store.rejected_certs_dir()).unwrap();
+    add_trusted_cert(&cert_store, &root);
+    cert_store.ensure_pki_path().unwrap();
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateRevocationUnknown)
+    );
+    add_crl(&cert_store, "root", &make_crl(&root, &[]));
+    assert_eq!(validate(&cert_store, &app), Ok(()));
+
+    // Revoked certificates are rejected.
+    add_crl(&cert_store, "root", &make_crl(&root, &[&app]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateRevoked)
+    );
+}
+
+#[test]
+fn untrusted_ca_issued_cert() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let app = issue_cert("App", 2, Usage::Application, Some(&root));
+
+    // The chain is complete, but neither the certificate nor its issuer is trusted.
+    add_issuer_cert(&cert_store, &root);
+    add_crl(&cert_store, "root", &make_crl(&root, &[]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateUntrusted)
+    );
+
+    // Trusting the certificate itself is enough, the issuer only completes the chain.
+    std::fs::remove_dir_all(cert_store.rejected_certs_dir()).unwrap();
+    cert_store.ensure_pki_path().unwrap();
+    add_trusted_cert(&cert_store, &app);
+    assert_eq!(validate(&cert_store, &app), Ok(()));
+}
+
+#[test]
+fn intermediate_ca_chain() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let intermediate = issue_cert("Plant CA", 2, Usage::Ca { path_len: None }, Some(&root));
+    let app = issue_cert("App", 3, Usage::Application, Some(&intermediate));
+
+    add_trusted_cert(&cert_store, &root);
+    add_issuer_cert(&cert_store, &intermediate);
+    add_crl(&cert_store, "intermediate", &make_crl(&intermediate, &[]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevocationUnknown)
+    );
+    add_crl(&cert_store, "root", &make_crl(&root, &[]));
+    assert_eq!(validate(&cert_store, &app), Ok(()));
+
+    add_crl(&cert_store, "root", &make_crl(&root, &[&intermediate]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevoked)
+    );
+}
+
+#[test]
+fn allow_unknown_revocation() {
+    let (_tmp_dir, mut cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let intermediate = issue_cert("Plant CA", 2, Usage::Ca { path_len: None }, Some(&root));
+    let app = issue_cert("App", 3, Usage::Application, Some(&intermediate));
+
+    add_trusted_cert(&cert_store, &root);
+    add_issuer_cert(&cert_store, &intermediate);
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateRevocationUnknown)
+    );
+
+    // A chain without any revocation lists passes when unknown revocation is allowed.
+    cert_store.set_allow_unknown_revocation(true);
+    assert_eq!(validate(&cert_store, &app), Ok(()));
+
+    // Revocations in a known list still apply.
+    add_crl(&cert_store, "root", &make_crl(&root, &[&intermediate]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevoked)
+    );
+}
+
+#[test]
+fn expired_crl() {
+    let (_tmp_dir, mut cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let intermediate = issue_cert("Plant CA", 2, Usage::Ca { path_len: None }, Some(&root));
+    let app = issue_cert("App", 3, Usage::Application, Some(&intermediate));
+    let past = std::time::SystemTime::now() - Duration::from_secs(3600);
+    let future = std::time::SystemTime::now() + Duration::from_secs(3600);
+
+    add_trusted_cert(&cert_store, &root);
+    add_issuer_cert(&cert_store, &intermediate);
+    add_crl(
+        &cert_store,
+        "root",
+        &make_crl_with_next_update(&root, &[], Some(future)),
+    );
+    add_crl(
+        &cert_store,
+        "intermediate",
+        &make_crl_with_next_update(&intermediate, &[], Some(past)),
+    );
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateRevocationUnknown)
+    );
+
+    add_crl(
+        &cert_store,
+        "intermediate",
+        &make_crl_with_next_update(&intermediate, &[], Some(future)),
+    );
+    add_crl(
+        &cert_store,
+        "root",
+        &make_crl_with_next_update(&root, &[], Some(past)),
+    );
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevocationUnknown)
+    );
+
+    // Expired revocation lists are accepted when time checks are disabled.
+    cert_store.set_check_time(false);
+    assert_eq!(validate(&cert_store, &app), Ok(()));
+
+    // Revocations in an expired list still apply.
+    add_crl(
+        &cert_store,
+        "root",
+        &make_crl_with_next_update(&root, &[&intermediate], Some(past)),
+    );
+    cert_store.set_check_time(true);
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevoked)
+    );
+}
+
+#[test]
+fn issuer_usage() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: Some(0) }, None);
+    let intermediate = issue_cert("Plant CA", 2, Usage::Ca { path_len: None }, Some(&root));
+    let app = issue_cert("App", 3, Usage::Application, Some(&intermediate));
+    add_trusted_cert(&cert_store, &root);
+    add_issuer_cert(&cert_store, &intermediate);
+    add_crl(&cert_store, "root", &make_crl(&root, &[]));
+    add_crl(&cert_store, "intermediate", &make_crl(&intermediate, &[]));
+
+    // The root CA may not issue intermediate CAs.
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerUseNotAllowed)
+    );
+
+    // Application certificates cannot be used to issue certificates.
+    let not_ca = issue_cert("Not a CA", 4, Usage::Application, Some(&root));
+    let app = issue_cert("App", 5, Usage::Application, Some(&not_ca));
+    add_issuer_cert(&cert_store, &not_ca);
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerUseNotAllowed)
+    );
+}
+
+#[test]
+fn application_cert_usage() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    add_trusted_cert(&cert_store, &root);
+    add_crl(&cert_store, "root", &make_crl(&root, &[]));
+
+    let ca = issue_cert("Other CA", 2, Usage::Ca { path_len: None }, Some(&root));
+    assert_eq!(
+        ca.cert.is_usage_valid(),
+        Err(StatusCode::BadCertificateUseNotAllowed)
+    );
+
+    let code_signing = issue_cert("Code signing", 3, Usage::CodeSigning, Some(&root));
+    assert_eq!(
+        validate(&cert_store, &code_signing),
+        Err(StatusCode::BadCertificateUseNotAllowed)
+    );
+}
+
+#[test]
+fn forged_signature() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    // A self-signed CA with the same name as the trusted root, issuing a certificate.
+    let forged_root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let app = issue_cert("App", 2, Usage::Application, Some(&forged_root));
+    add_trusted_cert(&cert_store, &root);
+    add_crl(&cert_store, "root", &make_crl(&root, &[]));
+
+    assert!(!app.cert.is_issued_by(&root.cert));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateInvalid)
+    );
+
+    // Revocation lists not signed by the issuer are ignored.
+    let crl = make_crl(&forged_root, &[]);
+    assert!(!crl.is_issued_by(&root.cert));
+}
+
+#[test]
+fn in_memory_ca_chain() {
+    let mut cert_store = InMemoryCertificateStore::new();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let intermediate = issue_cert("Plant CA", 2, Usage::Ca { path_len: None }, Some(&root));
+    let app = issue_cert("App", 3, Usage::Application, Some(&intermediate));
+
+    cert_store.add_trusted_cert(root.cert.clone());
+    cert_store.add_issuer_cert(intermediate.cert.clone());
+    cert_store.add_crl(make_crl(&intermediate, &[]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevocationUnknown)
+    );
+    cert_store.add_crl(make_crl(&root, &[]));
+    assert_eq!(validate(&cert_store, &app), Ok(()));
+
+    // Revoking the intermediate CA revokes the certificates it issued.
+    cert_store.add_crl(make_crl(&root, &[&intermediate]));
+    assert_eq!(
+        validate(&cert_store, &app),
+        Err(StatusCode::BadCertificateIssuerRevoked)
+    );
+}
+
+#[test]
+fn read_crl() {
+    let (_tmp_dir, cert_store) = make_certificate_store();
+    let root = issue_cert("Root CA", 1, Usage::Ca { path_len: None }, None);
+    let app = issue_cert("App", 2, Usage::Application, Some(&root));
+    let crl = make_crl(&root, &[&app]);
+
+    let der_path = cert_store.crl_dir().join("root.der");
+    std::fs::write(&der_path, crl.to_der().unwrap()).unwrap();
+    let pem_path = cert_store.crl_dir().join("root.pem");
+    let pem = x509_cert::der::pem::encode_string(
+        "X509 CRL",
+        x509_cert::der::pem::LineEnding::LF,
+        &crl.to_der().unwrap(),
+    )
+    .unwrap();
+    std::fs::write(&pem_path, pem).unwrap();
+
+    for path in [der_path, pem_path] {
+        let crl = FileSystemCertificateStore::read_crl(&path).unwrap();
+        assert!(crl.is_issued_by(&root.cert));
+        assert!(crl.is_revoked(&app.cert));
+        assert!(!crl.is_revoked(&root.cert));
+    }
+}
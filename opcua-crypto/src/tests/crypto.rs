@@ -6,8 +6,9 @@ use opcua_types::StatusCode;
 use crate::{
     aeskey::AesKey,
     certificate_store::*,
+    csr::CertificateSigningRequest,
     from_hex, hash,
-    pkey::{KeySize, PrivateKey, RsaPadding},
+    pkey::{EccCurve, KeySize, PrivateKey, RsaPadding},
     random,
     tests::{
         make_certificate_store, make_test_cert_1024, make_test_cert_2048, APPLICATION_HOSTNAME,
@@ -115,8 +116,11 @@ fn create_rejected_cert_in_pki() {
     let result = cert_store.store_rejected_cert(&cert);
     assert!(result.is_ok());
 
-    let path = result.unwrap();
+    let path = cert_store
+        .rejected_certs_dir()
+        .join(FileSystemCertificateStore::cert_file_name(&cert));
     assert!(path.exists());
+    assert!(cert_store.is_rejected_cert(&cert).unwrap());
     drop(tmp_dir);
 }
 
@@ -147,7 +151,7 @@ fn test_and_trust_application_instance_cert() {
     // Simulate user/admin copying cert to the trusted folder
     let der = cert.to_der().unwrap();
     let mut cert_trusted_path = cert_store.trusted_certs_dir();
-    cert_trusted_path.push(CertificateStore::cert_file_name(&cert));
+    cert_trusted_path.push(FileSystemCertificateStore::cert_file_name(&cert));
     {
         println!("Writing der file to {:?}", cert_trusted_path);
         let mut file = File::create(cert_trusted_path).unwrap();
@@ -178,7 +182,7 @@ fn test_and_reject_thumbprint_mismatch() {
     // e.g. to trick the cert store to trust an untrusted cert
     let der = cert.to_der().unwrap();
     let mut cert_trusted_path = cert_store.trusted_certs_dir();
-    cert_trusted_path.push(CertificateStore::cert_file_name(&cert2));
+    cert_trusted_path.push(FileSystemCertificateStore::cert_file_name(&cert2));
     {
         let mut file = File::create(cert_trusted_path).unwrap();
         assert!(file.write(&der).is_ok());
@@ -196,6 +200,132 @@ fn test_and_reject_thumbprint_mismatch() {
     drop(tmp_dir);
 }
 
+#[test]
+fn in_memory_store_own_cert() {
+    let mut cert_store = InMemoryCertificateStore::new();
+    assert!(cert_store.read_own_cert().is_err());
+    assert!(cert_store.read_own_pkey().is_err());
+
+    let (cert, pkey) = make_test_cert_1024();
+    cert_store.set_own_cert_and_pkey(cert.clone(), pkey);
+    assert_eq!(
+        cert_store.read_own_cert().unwrap().thumbprint(),
+        cert.thumbprint()
+    );
+    assert!(cert_store.read_own_pkey().is_ok());
+}
+
+#[test]
+fn in_memory_store_trust_and_reject() {
+    let mut cert_store = InMemoryCertificateStore::new();
+
+    // An unknown cert is rejected, and stays rejected
+    let (cert, _) = make_test_cert_1024();
+    let result = cert_store.validate_or_reject_application_instance_cert(
+        &cert,
+        SecurityPolicy::Basic128Rsa15,
+        None,
+        None,
+    );
+    assert_eq!(result, Err(StatusCode::BadCertificateUntrusted));
+    assert_eq!(cert_store.rejected_certs().len(), 1);
+    cert_store.add_trusted_cert(cert.clone());
+    let result = cert_store.validate_or_reject_application_instance_cert(
+        &cert,
+        SecurityPolicy::Basic128Rsa15,
+        None,
+        None,
+    );
+    assert_eq!(result, Err(StatusCode::BadSecurityChecksFailed));
+
+    // Once no longer rejected, the trusted cert is valid
+    cert_store.clear_rejected_certs();
+    let result = cert_store.validate_or_reject_application_instance_cert(
+        &cert,
+        SecurityPolicy::Basic128Rsa15,
+        None,
+        None,
+    );
+    assert!(result.is_ok());
+    assert!(cert_store.remove_trusted_cert(&cert));
+    assert!(!cert_store.is_trusted_cert(&cert).unwrap());
+
+    // Unknown certs are added to the trust list when the policy says so
+    let (cert2, _) = make_test_cert_1024();
+    cert_store.set_trust_unknown_certs(true);
+    let result = cert_store.validate_or_reject_application_instance_cert(
+        &cert2,
+        SecurityPolicy::Basic128Rsa15,
+        None,
+        None,
+    );
+    assert!(result.is_ok());
+    assert!(cert_store.is_trusted_cert(&cert2).unwrap());
+    assert!(cert_store.rejected_certs().is_empty());
+}
+
+#[test]
+fn file_system_store_replace_certs() {
+    let (tmp_dir, mut cert_store) = make_certificate_store();
+
+    let (cert, _) = make_test_cert_1024();
+    let (cert2, pkey2) = make_test_cert_1024();
+    cert_store.store_trusted_cert(&cert).unwrap();
+    assert!(cert_store.is_trusted_cert(&cert).unwrap());
+
+    // Replacing the trust list removes certs that are not in the new list
+    cert_store
+        .set_trusted_certs(std::slice::from_ref(&cert2))
+        .unwrap();
+    assert!(!cert_store.is_trusted_cert(&cert).unwrap());
+    assert!(cert_store.is_trusted_cert(&cert2).unwrap());
+    assert_eq!(cert_store.trusted_certs().len(), 1);
+
+    cert_store
+        .set_issuer_certs(std::slice::from_ref(&cert))
+        .unwrap();
+    assert_eq!(cert_store.issuer_certs().len(), 1);
+    cert_store.set_issuer_certs(&[]).unwrap();
+    assert!(cert_store.issuer_certs().is_empty());
+
+    // The own cert and key are overwritten
+    cert_store.store_own_cert_and_pkey(&cert2, &pkey2).unwrap();
+    let own_cert = cert_store.read_own_cert().unwrap();
+    assert_eq!(own_cert.thumbprint(), cert2.thumbprint());
+    assert!(own_cert.is_public_key_of(&cert_store.read_own_pkey().unwrap()));
+
+    drop(tmp_dir);
+}
+
+#[test]
+fn certificate_signing_request() {
+    let (cert, pkey) = make_test_cert_2048();
+
+    // A request for the existing key pair copies the subject of the cert
+    let csr = CertificateSigningRequest::for_cert(&cert, &pkey, None).unwrap();
+    assert!(csr.is_signature_valid());
+    assert_eq!(csr.subject_name(), cert.subject_name());
+    assert_eq!(csr.public_key().unwrap().bit_length(), 2048);
+
+    // The request survives being encoded and decoded
+    let der = csr.to_der().unwrap();
+    let csr = CertificateSigningRequest::from_der(&der).unwrap();
+    assert!(csr.is_signature_valid());
+
+    // A request for a new ECC key, with a new subject name
+    let pkey = PrivateKey::new_ecc(EccCurve::NistP256);
+    let csr = CertificateSigningRequest::for_cert(&cert, &pkey, Some("CN=y,O=y.org")).unwrap();
+    assert!(csr.is_signature_valid());
+    assert_eq!(csr.subject_name(), "CN=y,O=y.org");
+    assert_eq!(
+        csr.public_key().unwrap().ecc_curve(),
+        Some(EccCurve::NistP256)
+    );
+    assert!(!cert.is_public_key_of(&pkey));
+
+    assert!(CertificateSigningRequest::from_der(&der[1..]).is_err());
+}
+
 fn test_asymmetric_encrypt_and_decrypt(
     cert: &X509,
     key: &PrivateKey,
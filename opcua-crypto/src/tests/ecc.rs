@@ -0,0 +1,235 @@
+use std::fs::File;
+use std::io::Write;
+
+use crate::{
+    certificate_store::*,
+    from_hex, hash,
+    pkey::{EccCurve, KeySize, PrivateKey, PublicKey},
+    tests::{make_certificate_store, APPLICATION_HOSTNAME, APPLICATION_URI},
+    x509::{X509Data, X509},
+    SecurityPolicy, SHA384_SIZE,
+};
+
+fn make_test_ecc_cert(curve: EccCurve) -> (X509, PrivateKey) {
+    let args = X509Data {
+        key_size: 0,
+        common_name: "x".to_string(),
+        organization: "x.org".to_string(),
+        organizational_unit: "x.org ops".to_string(),
+        country: "EN".to_string(),
+        state: "London".to_string(),
+        alt_host_names: vec![
+            APPLICATION_URI.to_string(),
+            "foo".to_string(),
+            APPLICATION_HOSTNAME.to_string(),
+        ]
+        .into(),
+        certificate_duration_days: 60,
+    };
+    X509::ecc_cert_and_pkey(&args, curve).unwrap()
+}
+
+#[test]
+fn create_ecc_cert() {
+    for (curve, key_length) in [(EccCurve::NistP256, 256), (EccCurve::NistP384, 384)] {
+        let (cert, pkey) = make_test_ecc_cert(curve);
+        assert_eq!(pkey.ecc_curve(), Some(curve));
+        assert_eq!(cert.key_length().unwrap(), key_length);
+        assert!(cert.is_self_signed());
+        assert!(cert.is_usage_valid().is_ok());
+
+        let public_key = cert.public_key().unwrap();
+        assert_eq!(public_key.ecc_curve(), Some(curve));
+        assert_eq!(public_key.bit_length(), key_length);
+        assert_eq!(
+            public_key.to_ecc_nonce(),
+            pkey.to_public_key().to_ecc_nonce()
+        );
+    }
+}
+
+#[test]
+fn create_own_ecc_cert_in_pki() {
+    let (tmp_dir, cert_store) = make_certificate_store();
+    let args = X509Data::sample_cert();
+    let (cert, _) = cert_store
+        .create_and_store_ecc_application_instance_cert(&args, EccCurve::NistP256, false)
+        .unwrap();
+
+    // The key and cert can be read back
+    let pkey = cert_store.read_own_pkey().unwrap();
+    assert_eq!(pkey.ecc_curve(), Some(EccCurve::NistP256));
+    assert_eq!(
+        cert_store.read_own_cert().unwrap().thumbprint(),
+        cert.thumbprint()
+    );
+
+    // Create again with no overwrite
+    assert!(cert_store
+        .create_and_store_ecc_application_instance_cert(&args, EccCurve::NistP256, false)
+        .is_err());
+    drop(tmp_dir);
+}
+
+#[test]
+fn validate_ecc_application_instance_cert() {
+    let (tmp_dir, cert_store) = make_certificate_store();
+
+    let (cert, _) = make_test_ecc_cert(EccCurve::NistP256);
+    let mut cert_trusted_path = cert_store.trusted_certs_dir();
+    cert_trusted_path.push(FileSystemCertificateStore::cert_file_name(&cert));
+    {
+        let mut file = File::create(cert_trusted_path).unwrap();
+        assert!(file.write(&cert.to_der().unwrap()).is_ok());
+    }
+
+    assert!(cert_store
+        .validate_or_reject_application_instance_cert(
+            &cert,
+            SecurityPolicy::EccNistP256,
+            None,
+            None,
+        )
+        .is_ok());
+    // The key is on the wrong curve for this policy
+    assert!(cert_store
+        .validate_or_reject_application_instance_cert(
+            &cert,
+            SecurityPolicy::EccNistP384,
+            None,
+            None,
+        )
+        .is_err());
+
+    drop(tmp_dir);
+}
+
+#[test]
+fn sign_verify_ecdsa() {
+    for security_policy in [SecurityPolicy::EccNistP256, SecurityPolicy::EccNistP384] {
+        let (cert, private_key) = make_test_ecc_cert(security_policy.ecc_curve().unwrap());
+
+        let msg = b"Mary had a little lamb";
+        let msg2 = b"It's fleece was white as snow";
+        let mut signature = vec![0u8; private_key.signature_size()];
+        let signed_len = security_policy
+            .asymmetric_sign(&private_key, msg, &mut signature)
+            .unwrap();
+        assert_eq!(signed_len, signature.len());
+
+        let public_key = cert.public_key().unwrap();
+        assert!(security_policy
+            .asymmetric_verify_signature(&public_key, msg, &signature, None)
+            .is_ok());
+        assert!(security_policy
+            .asymmetric_verify_signature(&public_key, msg2, &signature, None)
+            .is_err());
+
+        signature[0] = !signature[0]; // bitwise not
+        assert!(security_policy
+            .asymmetric_verify_signature(&public_key, msg, &signature, None)
+            .is_err());
+    }
+}
+
+#[test]
+fn sign_hmac_sha384() {
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    let mut signature_wrong_size = [0u8; SHA384_SIZE - 1];
+    assert!(hash::hmac_sha384(key, data, &mut signature_wrong_size).is_err());
+
+    let mut signature = [0u8; SHA384_SIZE];
+    assert!(hash::hmac_sha384(key, data, &mut signature).is_ok());
+    let expected = from_hex("d7f4727e2c0b39ae0f1e40cc96f60242d5b7801841cea6fc592c5d3e1ae50700582a96cf35e1e554995fe4e03381c237");
+    assert_eq!(&signature, &expected[..]);
+
+    assert!(hash::verify_hmac_sha384(key, data, &expected));
+    assert!(!hash::verify_hmac_sha384(key, &data[1..], &expected));
+}
+
+#[test]
+fn ecc_nonce() {
+    for security_policy in [SecurityPolicy::EccNistP256, SecurityPolicy::EccNistP384] {
+        let curve = security_policy.ecc_curve().unwrap();
+        let key = security_policy.ephemeral_key().unwrap();
+        let nonce = key.to_public_key().to_ecc_nonce().unwrap();
+        assert_eq!(nonce.len(), security_policy.secure_channel_nonce_length());
+
+        let public_key = PublicKey::from_ecc_nonce(curve, &nonce).unwrap();
+        assert_eq!(public_key.to_ecc_nonce().unwrap(), nonce);
+
+        // Wrong length, or not a point on the curve
+        assert!(PublicKey::from_ecc_nonce(curve, &nonce[1..]).is_err());
+        assert!(PublicKey::from_ecc_nonce(curve, &vec![0xffu8; nonce.len()]).is_err());
+    }
+}
+
+#[test]
+fn derive_ecc_keys() {
+    for security_policy in [SecurityPolicy::EccNistP256, SecurityPolicy::EccNistP384] {
+        let client_key = security_policy.ephemeral_key().unwrap();
+        let server_key = security_policy.ephemeral_key().unwrap();
+        let client_nonce = client_key.to_public_key().to_ecc_nonce().unwrap();
+        let server_nonce = server_key.to_public_key().to_ecc_nonce().unwrap();
+
+        // Both ends agree on the same secret
+        let client_secret = client_key.ecdh(&server_key.to_public_key()).unwrap();
+        let server_secret = server_key.ecdh(&client_key.to_public_key()).unwrap();
+        assert_eq!(client_secret, server_secret);
+
+        let client_keys = security_policy.make_ecc_secure_channel_keys(
+            &client_secret,
+            &client_nonce,
+            &server_nonce,
+            true,
+        );
+        let server_keys = security_policy.make_ecc_secure_channel_keys(
+            &server_secret,
+            &client_nonce,
+            &server_nonce,
+            false,
+        );
+
+        let (signing_key_length, encrypting_key_length) = match security_policy {
+            SecurityPolicy::EccNistP256 => (32, 16),
+            _ => (48, 32),
+        };
+        for (signing_key, encrypting_key, iv) in [&client_keys, &server_keys] {
+            assert_eq!(signing_key.len(), signing_key_length);
+            assert_eq!(encrypting_key.key_length(), encrypting_key_length);
+            assert_eq!(iv.len(), 16);
+        }
+        assert_ne!(client_keys.0, server_keys.0);
+        assert_ne!(client_keys.2, server_keys.2);
+
+        // Data encrypted with the client keys can be decrypted with the same keys
+        let plain_text = [7u8; 32];
+        let mut cipher_text = [0u8; 48];
+        let mut decrypted = [0u8; 48];
+        let (_, encrypting_key, iv) = &client_keys;
+        let size = security_policy
+            .symmetric_encrypt(encrypting_key, iv, &plain_text, &mut cipher_text)
+            .unwrap();
+        let size = security_policy
+            .symmetric_decrypt(encrypting_key, iv, &cipher_text[..size], &mut decrypted)
+            .unwrap();
+        assert_eq!(&decrypted[..size], &plain_text[..]);
+    }
+}
+
+#[test]
+fn ecc_key_pem_round_trip() {
+    let key = PrivateKey::new_ecc(EccCurve::NistP384);
+    let der = key.to_der().unwrap();
+    let pem = der
+        .to_pem("PRIVATE KEY", rsa::pkcs8::LineEnding::LF)
+        .unwrap();
+    let key2 = PrivateKey::from_pem(pem.as_bytes()).unwrap();
+    assert_eq!(key2.ecc_curve(), Some(EccCurve::NistP384));
+    assert_eq!(
+        key.to_public_key().to_ecc_nonce(),
+        key2.to_public_key().to_ecc_nonce()
+    );
+}
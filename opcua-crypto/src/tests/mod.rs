@@ -1,6 +1,6 @@
 use tempdir::TempDir;
 
-use crate::CertificateStore;
+use crate::FileSystemCertificateStore;
 
 use crate::{
     pkey::PrivateKey,
@@ -10,9 +10,9 @@ use crate::{
 const APPLICATION_URI: &str = "urn:testapplication";
 const APPLICATION_HOSTNAME: &str = "testhost";
 
-fn make_certificate_store() -> (TempDir, CertificateStore) {
+fn make_certificate_store() -> (TempDir, FileSystemCertificateStore) {
     let tmp_dir = TempDir::new("pki").unwrap();
-    let cert_store = CertificateStore::new(tmp_dir.path());
+    let cert_store = FileSystemCertificateStore::new(tmp_dir.path());
     assert!(cert_store.ensure_pki_path().is_ok());
     (tmp_dir, cert_store)
 }
@@ -48,5 +48,7 @@ fn make_test_cert_2048() -> (X509, PrivateKey) {
 }
 
 mod authentication;
+mod certificate_chain;
 mod crypto;
+mod ecc;
 mod security_policy;
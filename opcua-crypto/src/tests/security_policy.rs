@@ -93,6 +93,24 @@ fn from_str() {
             .unwrap(),
         SecurityPolicy::Aes256Sha256RsaPss
     );
+    assert_eq!(
+        SecurityPolicy::from_str("ECC-nistP256").unwrap(),
+        SecurityPolicy::EccNistP256
+    );
+    assert_eq!(
+        SecurityPolicy::from_str("http://opcfoundation.org/UA/SecurityPolicy#ECC_nistP256")
+            .unwrap(),
+        SecurityPolicy::EccNistP256
+    );
+    assert_eq!(
+        SecurityPolicy::from_str("ECC-nistP384").unwrap(),
+        SecurityPolicy::EccNistP384
+    );
+    assert_eq!(
+        SecurityPolicy::from_str("http://opcfoundation.org/UA/SecurityPolicy#ECC_nistP384")
+            .unwrap(),
+        SecurityPolicy::EccNistP384
+    );
 }
 
 #[test]
@@ -121,6 +139,14 @@ fn to_uri() {
         SecurityPolicy::Aes256Sha256RsaPss.to_uri(),
         "http://opcfoundation.org/UA/SecurityPolicy#Aes256_Sha256_RsaPss"
     );
+    assert_eq!(
+        SecurityPolicy::EccNistP256.to_uri(),
+        "http://opcfoundation.org/UA/SecurityPolicy#ECC_nistP256"
+    );
+    assert_eq!(
+        SecurityPolicy::EccNistP384.to_uri(),
+        "http://opcfoundation.org/UA/SecurityPolicy#ECC_nistP384"
+    );
 }
 
 #[test]
@@ -149,4 +175,33 @@ fn is_valid_keylength() {
     assert!(SecurityPolicy::Aes256Sha256RsaPss.is_valid_keylength(4096));
     assert!(!SecurityPolicy::Aes256Sha256RsaPss.is_valid_keylength(1024));
     assert!(!SecurityPolicy::Aes256Sha256RsaPss.is_valid_keylength(8192));
+
+    assert!(SecurityPolicy::EccNistP256.is_valid_keylength(256));
+    assert!(!SecurityPolicy::EccNistP256.is_valid_keylength(384));
+    assert!(!SecurityPolicy::EccNistP256.is_valid_keylength(2048));
+
+    assert!(SecurityPolicy::EccNistP384.is_valid_keylength(384));
+    assert!(!SecurityPolicy::EccNistP384.is_valid_keylength(256));
+    assert!(!SecurityPolicy::EccNistP384.is_valid_keylength(2048));
+}
+
+#[test]
+fn ecc_policies() {
+    assert!(SecurityPolicy::EccNistP256.is_ecc());
+    assert!(SecurityPolicy::EccNistP384.is_ecc());
+    assert!(!SecurityPolicy::Basic256Sha256.is_ecc());
+    assert!(!SecurityPolicy::None.is_ecc());
+
+    assert_eq!(
+        SecurityPolicy::EccNistP256.secure_channel_nonce_length(),
+        64
+    );
+    assert_eq!(
+        SecurityPolicy::EccNistP384.secure_channel_nonce_length(),
+        96
+    );
+    assert_eq!(SecurityPolicy::EccNistP256.symmetric_signature_size(), 32);
+    assert_eq!(SecurityPolicy::EccNistP384.symmetric_signature_size(), 48);
+    assert!(!SecurityPolicy::EccNistP256.is_deprecated());
+    assert!(!SecurityPolicy::EccNistP384.is_deprecated());
 }
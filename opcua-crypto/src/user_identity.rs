@@ -67,6 +67,14 @@ pub fn make_user_name_identity_token(
             // This should only happen if channel_security_policy were Unknown when it shouldn't be
             panic!("Don't know how to make the token for this server");
         }
+        SecurityPolicy::EccNistP256 | SecurityPolicy::EccNistP384 => {
+            // The EccEncryptedSecret used to encrypt passwords for ECC policies is not supported
+            error!(
+                "Cannot encrypt a password for security policy {}",
+                security_policy
+            );
+            return Err(StatusCode::BadSecurityPolicyRejected);
+        }
         security_policy => {
             // Create a password which is encrypted using the secure channel info and the user token policy for the endpoint
             let password = legacy_password_encrypt(
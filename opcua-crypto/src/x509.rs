@@ -18,11 +18,13 @@ type ChronoUtc = DateTime<Utc>;
 
 use rsa;
 use rsa::pkcs1v15;
-use rsa::RsaPublicKey;
 use x509_cert::{
     self as x509,
     der::asn1::{Ia5String, OctetString},
-    ext::pkix::name::GeneralName,
+    ext::pkix::{name::GeneralName, BasicConstraints, ExtendedKeyUsage, KeyUsage},
+    name::Name,
+    serial_number::SerialNumber,
+    spki::AlgorithmIdentifierOwned,
 };
 
 use const_oid;
@@ -33,7 +35,7 @@ use opcua_types::{status_code::StatusCode, ApplicationDescription, ByteString, E
 
 use super::{
     hostname,
-    pkey::{PrivateKey, PublicKey},
+    pkey::{EccCurve, PrivateKey, PrivateKeyValue, PublicKey},
     thumbprint::Thumbprint,
 };
 
@@ -470,6 +472,19 @@ impl X509 {
         Ok((cert, pkey))
     }
 
+    /// Creates a self-signed X509v3 certificate and ECC private key on the given curve from
+    /// the supplied creation args, for use with the ECC security policies. The key size in the
+    /// creation args is ignored. See [`X509::cert_and_pkey`].
+    pub fn ecc_cert_and_pkey(
+        x509_data: &X509Data,
+        curve: EccCurve,
+    ) -> Result<(Self, PrivateKey), String> {
+        let pkey = PrivateKey::new_ecc(curve);
+        let cert = Self::from_pkey(&pkey, x509_data)?;
+
+        Ok((cert, pkey))
+    }
+
     fn append_to_name(name: &mut String, param: &str, data: &str) {
         if !data.is_empty() {
             if !name.is_empty() {
@@ -497,6 +512,41 @@ impl X509 {
     }
 
     fn create_from_pkey(pkey: &PrivateKey, x509_data: &X509Data) -> Result<Self, BuilderError> {
+        match &pkey.value {
+            PrivateKeyValue::Rsa(key) => Self::build_from_pkey::<_, pkcs1v15::Signature>(
+                pkey,
+                x509_data,
+                &pkcs1v15::SigningKey::<sha2::Sha256>::new(key.as_ref().clone()),
+            ),
+            PrivateKeyValue::NistP256(key) => {
+                Self::build_from_pkey::<_, p256::ecdsa::DerSignature>(
+                    pkey,
+                    x509_data,
+                    &p256::ecdsa::SigningKey::from(key),
+                )
+            }
+            PrivateKeyValue::NistP384(key) => {
+                Self::build_from_pkey::<_, p384::ecdsa::DerSignature>(
+                    pkey,
+                    x509_data,
+                    &p384::ecdsa::SigningKey::from(key),
+                )
+            }
+        }
+    }
+
+    fn build_from_pkey<S, Signature>(
+        pkey: &PrivateKey,
+        x509_data: &X509Data,
+        signing_key: &S,
+    ) -> Result<Self, BuilderError>
+    where
+        S: rsa::signature::Keypair
+            + x509::spki::DynSignatureAlgorithmIdentifier
+            + rsa::signature::Signer<Signature>,
+        S::VerifyingKey: x509::spki::EncodePublicKey,
+        Signature: x509::spki::SignatureBitStringEncoding,
+    {
         use std::time::Duration;
         use x509_cert::builder::{CertificateBuilder, Profile};
         use x509_cert::name::Name;
@@ -518,8 +568,6 @@ impl X509 {
         ))
         .unwrap();
 
-        let signing_key = pkcs1v15::SigningKey::<sha2::Sha256>::new(pkey.value.clone());
-
         let serial_number = SerialNumber::from(42u32);
 
         let subject;
@@ -559,7 +607,7 @@ impl X509 {
             validity,
             subject.clone(),
             pub_key,
-            &signing_key,
+            signing_key,
         )?;
 
         builder.add_extension(&x509::ext::pkix::SubjectKeyIdentifier(
@@ -579,11 +627,19 @@ impl X509 {
             use x509::ext::pkix::KeyUsage;
             use x509::ext::pkix::KeyUsages;
 
-            let key_usage = KeyUsages::DigitalSignature
-                | KeyUsages::NonRepudiation
-                | KeyUsages::KeyEncipherment
-                | KeyUsages::DataEncipherment
-                | KeyUsages::KeyCertSign;
+            // ECC keys are used for key agreement rather than encryption
+            let key_usage = if pkey.ecc_curve().is_some() {
+                KeyUsages::DigitalSignature
+                    | KeyUsages::NonRepudiation
+                    | KeyUsages::KeyAgreement
+                    | KeyUsages::KeyCertSign
+            } else {
+                KeyUsages::DigitalSignature
+                    | KeyUsages::NonRepudiation
+                    | KeyUsages::KeyEncipherment
+                    | KeyUsages::DataEncipherment
+                    | KeyUsages::KeyCertSign
+            };
             builder.add_extension(&KeyUsage(key_usage))?;
         }
 
@@ -603,7 +659,7 @@ impl X509 {
         }
 
         use x509_cert::builder::Builder;
-        let built = builder.build()?;
+        let built = builder.build::<Signature>()?;
 
         Ok(X509 { value: built })
     }
@@ -630,20 +686,22 @@ impl X509 {
         ByteString::from(&der)
     }
 
-    /// Try to get the public key from this certificate.
+    /// Try to get the public key from this certificate. The key is either an RSA key or
+    /// an ECC key on one of the supported curves.
     pub fn public_key(&self) -> Result<PublicKey, Error> {
-        use x509_cert::der::referenced::OwnedToRef;
+        PublicKey::from_info(&self.value.tbs_certificate.subject_public_key_info)
+    }
 
-        let r = RsaPublicKey::try_from(
-            self.value
-                .tbs_certificate
-                .subject_public_key_info
-                .owned_to_ref(),
-        );
-        match r {
-            Err(e) => Err(Error::new(StatusCode::BadCertificateInvalid, e)),
-            Ok(v) => Ok(PublicKey { value: v }),
-        }
+    /// Tests if `pkey` is the private key matching the public key of this certificate.
+    pub fn is_public_key_of(&self, pkey: &PrivateKey) -> bool {
+        pkey.public_key_to_info().is_ok_and(|info| {
+            info.subject_public_key
+                == self
+                    .value
+                    .tbs_certificate
+                    .subject_public_key_info
+                    .subject_public_key
+        })
     }
 
     /// Returns the key length in bits (if possible)
@@ -713,7 +771,7 @@ impl X509 {
         Ok(())
     }
 
-    fn get_alternate_names(&self) -> Option<x509::ext::pkix::name::GeneralNames> {
+    pub(crate) fn alternate_names(&self) -> Option<x509::ext::pkix::name::GeneralNames> {
         use x509::ext::pkix::SubjectAltName;
 
         let r: Result<Option<(bool, SubjectAltName)>, _> = self.value.tbs_certificate.get();
@@ -735,7 +793,7 @@ impl X509 {
         if hostname.is_empty() {
             error!("Hostname is empty");
             Err(StatusCode::BadCertificateHostNameInvalid)
-        } else if let Some(subject_alt_names) = self.get_alternate_names() {
+        } else if let Some(subject_alt_names) = self.alternate_names() {
             let found = subject_alt_names
                 .iter()
                 .skip(1) //skip the application uri
@@ -764,7 +822,7 @@ impl X509 {
     pub fn is_application_uri_valid(&self, application_uri: &str) -> Result<(), StatusCode> {
         // Expecting the first subject alternative name to be a uri that matches with the supplied
         // application uri
-        if let Some(alt_names) = self.get_alternate_names() {
+        if let Some(alt_names) = self.alternate_names() {
             if !alt_names.is_empty() {
                 match AlternateNames::convert_name(&alt_names[0]) {
                     Some(val) => {
@@ -842,6 +900,180 @@ impl X509 {
             Some(val) => Ok(val),
         }
     }
+
+    pub(crate) fn subject(&self) -> &Name {
+        &self.value.tbs_certificate.subject
+    }
+
+    pub(crate) fn issuer(&self) -> &Name {
+        &self.value.tbs_certificate.issuer
+    }
+
+    pub(crate) fn serial_number(&self) -> &SerialNumber {
+        &self.value.tbs_certificate.serial_number
+    }
+
+    /// Tests if the certificate was issued by `issuer`, i.e. the issuer name of the certificate
+    /// is the subject name of `issuer`, and the signature of the certificate verifies with the
+    /// public key of `issuer`.
+    pub fn is_issued_by(&self, issuer: &X509) -> bool {
+        use x509_cert::der::Encode;
+
+        if self.issuer() != issuer.subject() {
+            return false;
+        }
+        let (Ok(public_key), Ok(tbs), Some(signature)) = (
+            issuer.public_key(),
+            self.value.tbs_certificate.to_der(),
+            self.value.signature.as_bytes(),
+        ) else {
+            return false;
+        };
+        verify_signed_data(
+            &public_key,
+            &self.value.signature_algorithm,
+            &tbs,
+            signature,
+        )
+    }
+
+    /// Tests if the certificate is self-signed, i.e. it was issued by itself.
+    pub fn is_self_signed(&self) -> bool {
+        self.is_issued_by(self)
+    }
+
+    fn get_extension<T>(&self) -> Option<T>
+    where
+        T: for<'a> x509::der::Decode<'a> + const_oid::AssociatedOid,
+    {
+        match self.value.tbs_certificate.get::<T>() {
+            Ok(Some((_, ext))) => Some(ext),
+            _ => None,
+        }
+    }
+
+    /// Tests if the certificate is a certificate authority, i.e. it has the basic
+    /// constraints extension with the cA flag set.
+    pub fn is_ca(&self) -> bool {
+        self.get_extension::<BasicConstraints>()
+            .is_some_and(|c| c.ca)
+    }
+
+    /// Tests if the certificate may be used as an application instance certificate, using
+    /// the basic constraints, key usage and extended key usage extensions on the cert.
+    pub fn is_usage_valid(&self) -> Result<(), StatusCode> {
+        if self.is_ca() && !self.is_self_signed() {
+            error!("Certificate is a CA certificate, not an application instance certificate");
+            return Err(StatusCode::BadCertificateUseNotAllowed);
+        }
+        if let Some(key_usage) = self.get_extension::<KeyUsage>() {
+            if !key_usage.digital_signature() {
+                error!("Certificate key usage does not allow digital signatures");
+                return Err(StatusCode::BadCertificateUseNotAllowed);
+            }
+        }
+        if let Some(extended_key_usage) = self.get_extension::<ExtendedKeyUsage>() {
+            use const_oid::db::rfc5280::{
+                ANY_EXTENDED_KEY_USAGE, ID_KP_CLIENT_AUTH, ID_KP_SERVER_AUTH,
+            };
+            if !extended_key_usage.0.iter().any(|u| {
+                *u == ID_KP_CLIENT_AUTH || *u == ID_KP_SERVER_AUTH || *u == ANY_EXTENDED_KEY_USAGE
+            }) {
+                error!(
+                    "Certificate extended key usage does not allow client or server authentication"
+                );
+                return Err(StatusCode::BadCertificateUseNotAllowed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Tests if the certificate may be used to issue certificates, where `depth` is the number
+    /// of intermediate CA certificates between it and the application instance certificate.
+    pub fn is_issuer_usage_valid(&self, depth: usize) -> Result<(), StatusCode> {
+        let Some(constraints) = self.get_extension::<BasicConstraints>() else {
+            error!("Issuer certificate has no basic constraints");
+            return Err(StatusCode::BadCertificateIssuerUseNotAllowed);
+        };
+        if !constraints.ca {
+            error!("Issuer certificate is not a CA certificate");
+            return Err(StatusCode::BadCertificateIssuerUseNotAllowed);
+        }
+        if let Some(path_len) = constraints.path_len_constraint {
+            if depth > path_len as usize {
+                error!("Issuer certificate path length constraint {path_len} exceeded");
+                return Err(StatusCode::BadCertificateIssuerUseNotAllowed);
+            }
+        }
+        if let Some(key_usage) = self.get_extension::<KeyUsage>() {
+            if !key_usage.key_cert_sign() {
+                error!("Issuer certificate key usage does not allow signing certificates");
+                return Err(StatusCode::BadCertificateIssuerUseNotAllowed);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Verifies a signature over `data` made with the private key of `public_key`,
+/// using the X509 signature algorithm `algorithm`.
+pub(crate) fn verify_signed_data(
+    public_key: &PublicKey,
+    algorithm: &AlgorithmIdentifierOwned,
+    data: &[u8],
+    signature: &[u8],
+) -> bool {
+    use const_oid::db::rfc5912;
+
+    match algorithm.oid {
+        rfc5912::SHA_1_WITH_RSA_ENCRYPTION => {
+            public_key.verify_pkcs1v15::<sha1::Sha1>(data, signature)
+        }
+        rfc5912::SHA_256_WITH_RSA_ENCRYPTION => {
+            public_key.verify_pkcs1v15::<sha2::Sha256>(data, signature)
+        }
+        rfc5912::SHA_384_WITH_RSA_ENCRYPTION => {
+            public_key.verify_pkcs1v15::<sha2::Sha384>(data, signature)
+        }
+        rfc5912::SHA_512_WITH_RSA_ENCRYPTION => {
+            public_key.verify_pkcs1v15::<sha2::Sha512>(data, signature)
+        }
+        rfc5912::ECDSA_WITH_SHA_256 => public_key.verify_ecdsa_der::<sha2::Sha256>(data, signature),
+        rfc5912::ECDSA_WITH_SHA_384 => public_key.verify_ecdsa_der::<sha2::Sha384>(data, signature),
+        rfc5912::ECDSA_WITH_SHA_512 => public_key.verify_ecdsa_der::<sha2::Sha512>(data, signature),
+        rfc5912::ID_RSASSA_PSS => {
+            use rsa::pkcs1::RsaPssParams;
+
+            let Some(params) = algorithm
+                .parameters
+                .as_ref()
+                .and_then(|p| p.decode_as::<RsaPssParams<'_>>().ok())
+            else {
+                error!("Invalid RSA-PSS signature parameters");
+                return false;
+            };
+            let salt_len = params.salt_len as usize;
+            match params.hash.oid {
+                rfc5912::ID_SHA_256 => {
+                    public_key.verify_pss::<sha2::Sha256>(data, signature, salt_len)
+                }
+                rfc5912::ID_SHA_384 => {
+                    public_key.verify_pss::<sha2::Sha384>(data, signature, salt_len)
+                }
+                rfc5912::ID_SHA_512 => {
+                    public_key.verify_pss::<sha2::Sha512>(data, signature, salt_len)
+                }
+                oid => {
+                    error!("Unsupported RSA-PSS hash algorithm {oid}");
+                    false
+                }
+            }
+        }
+        oid => {
+            error!("Unsupported signature algorithm {oid}");
+            false
+        }
+    }
 }
 
 #[cfg(test)]
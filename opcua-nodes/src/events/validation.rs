@@ -232,9 +232,34 @@ fn validate_select_clause(
         });
     }
 
-    let Some(node) = type_tree.find_type_prop_by_browse_path(&clause.type_definition_id, &path)
-    else {
-        return Err(StatusCode::BadNodeIdUnknown);
+    // An empty browse path refers to the event itself. This is how clients select
+    // the `ConditionId` of condition events, by requesting the `NodeId` attribute.
+    if path.is_empty() {
+        if type_tree.get(&clause.type_definition_id) != Some(NodeClass::ObjectType) {
+            return Err(StatusCode::BadNodeIdUnknown);
+        }
+        if attribute_id != AttributeId::NodeId {
+            return Err(StatusCode::BadAttributeIdInvalid);
+        }
+        return Ok(ParsedSimpleAttributeOperand {
+            type_definition_id: clause.type_definition_id,
+            browse_path: path,
+            attribute_id,
+            index_range,
+        });
+    }
+
+    // Instance declarations are inherited, so the property may be defined
+    // on a supertype of the type definition.
+    let mut type_id = &clause.type_definition_id;
+    let node = loop {
+        if let Some(node) = type_tree.find_type_prop_by_browse_path(type_id, &path) {
+            break node;
+        }
+        match type_tree.get_supertype(type_id) {
+            Some(parent) => type_id = parent,
+            None => return Err(StatusCode::BadNodeIdUnknown),
+        }
     };
 
     // Validate the attribute id. Per spec:
@@ -463,10 +488,11 @@ fn has_cycles(
 
 #[cfg(test)]
 mod tests {
-    use crate::{events::validation::validate_where_clause, DefaultTypeTree};
+    use crate::{events::validation::validate_where_clause, DefaultTypeTree, ParsedEventFilter};
     use opcua_types::{
-        AttributeId, ContentFilter, ContentFilterElement, ContentFilterResult, FilterOperator,
-        NodeClass, NodeId, ObjectTypeId, Operand, SimpleAttributeOperand, StatusCode,
+        AttributeId, ContentFilter, ContentFilterElement, ContentFilterResult, EventFilter,
+        FilterOperator, NodeClass, NodeId, ObjectTypeId, Operand, SimpleAttributeOperand,
+        StatusCode,
     };
 
     #[test]
@@ -614,6 +640,52 @@ mod tests {
         assert_eq!(filter.unwrap_err(), StatusCode::BadEventFilterInvalid);
     }
 
+    #[test]
+    fn test_validate_select_event_node_id() {
+        let mut type_tree = DefaultTypeTree::new();
+
+        type_tree.add_type_node(
+            &NodeId::new(1, "event"),
+            &ObjectTypeId::BaseEventType.into(),
+            NodeClass::ObjectType,
+        );
+
+        let filter = EventFilter {
+            select_clauses: Some(vec![
+                SimpleAttributeOperand {
+                    type_definition_id: NodeId::new(1, "event"),
+                    browse_path: Some(Vec::new()),
+                    attribute_id: AttributeId::NodeId as u32,
+                    index_range: Default::default(),
+                },
+                SimpleAttributeOperand {
+                    type_definition_id: NodeId::new(1, "event"),
+                    browse_path: Some(Vec::new()),
+                    attribute_id: AttributeId::Value as u32,
+                    index_range: Default::default(),
+                },
+                SimpleAttributeOperand {
+                    type_definition_id: NodeId::new(1, "other"),
+                    browse_path: Some(Vec::new()),
+                    attribute_id: AttributeId::NodeId as u32,
+                    index_range: Default::default(),
+                },
+            ]),
+            where_clause: ContentFilter { elements: None },
+        };
+
+        let (result, filter) = ParsedEventFilter::new(filter, &type_tree);
+        assert_eq!(
+            result.select_clause_results.unwrap(),
+            vec![
+                StatusCode::Good,
+                StatusCode::BadAttributeIdInvalid,
+                StatusCode::BadNodeIdUnknown
+            ]
+        );
+        assert_eq!(filter.unwrap().select_clauses.len(), 1);
+    }
+
     #[test]
     fn test_validate_circular_filter() {
         let type_tree = DefaultTypeTree::new();
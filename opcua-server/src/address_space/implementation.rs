@@ -374,6 +374,11 @@ impl AddressSpace {
         self.node_map.get_mut(node_id)
     }
 
+    /// Iterate over all nodes in the address space, in no particular order.
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &NodeType> {
+        self.node_map.values()
+    }
+
     /// Check if the read is allowed.
     pub fn validate_node_read<'a>(
         &'a self,
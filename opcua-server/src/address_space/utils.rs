@@ -1,17 +1,24 @@
-use crate::node_manager::{ParsedReadValueId, ParsedWriteValue, RequestContext, ServerContext};
+use crate::{
+    node_manager::{ParsedReadValueId, ParsedWriteValue, RequestContext, ServerContext},
+    roles::{permissions_for_roles, RoleSet},
+};
 use log::debug;
 use opcua_nodes::TypeTree;
 use opcua_types::{
-    AttributeId, DataEncoding, DataTypeId, DataValue, NumericRange, StatusCode, TimestampsToReturn,
-    Variant, WriteMask,
+    AccessRestrictionType, AttributeId, DataEncoding, DataTypeId, DataValue, ExtensionObject,
+    MessageSecurityMode, NodeId, NumericRange, PermissionType, RolePermissionType, StatusCode,
+    TimestampsToReturn, Variant, WriteMask,
 };
 
 use super::{AccessLevel, AddressSpace, HasNodeId, NodeType, Variable};
 
 /// Validate that the user given by `context` can read the value
 /// of the given node.
+///
+/// This only checks the access level given by the authenticator, role permissions
+/// are checked by [validate_node_read].
 pub fn is_readable(context: &RequestContext, node: &NodeType) -> Result<(), StatusCode> {
-    if !user_access_level(context, node).contains(AccessLevel::CURRENT_READ) {
+    if !authenticator_access_level(context, node).contains(AccessLevel::CURRENT_READ) {
         Err(StatusCode::BadUserAccessDenied)
     } else {
         Ok(())
@@ -25,6 +32,14 @@ pub fn is_writable(
     node: &NodeType,
     attribute_id: AttributeId,
 ) -> Result<(), StatusCode> {
+    let permission = match (node, attribute_id) {
+        (NodeType::Variable(_), AttributeId::Value) => PermissionType::Write,
+        (_, AttributeId::RolePermissions) => PermissionType::WriteRolePermissions,
+        (_, AttributeId::Historizing) => PermissionType::WriteHistorizing,
+        _ => PermissionType::WriteAttribute,
+    };
+    validate_permission(context, node, permission)?;
+
     if let (NodeType::Variable(_), AttributeId::Value) = (node, attribute_id) {
         if !user_access_level(context, node).contains(AccessLevel::CURRENT_WRITE) {
             return Err(StatusCode::BadUserAccessDenied);
@@ -70,8 +85,7 @@ pub fn is_writable(
     }
 }
 
-/// Get the effective user access level for `node`.
-pub fn user_access_level(context: &RequestContext, node: &NodeType) -> AccessLevel {
+fn authenticator_access_level(context: &RequestContext, node: &NodeType) -> AccessLevel {
     let user_access_level = if let NodeType::Variable(ref node) = node {
         node.user_access_level()
     } else {
@@ -84,6 +98,182 @@ pub fn user_access_level(context: &RequestContext, node: &NodeType) -> AccessLev
     )
 }
 
+/// Remove any access from `access_level` that is not granted by `permissions`.
+fn mask_access_level(access_level: AccessLevel, permissions: PermissionType) -> AccessLevel {
+    let mut access_level = access_level;
+    for (permission, level) in [
+        (PermissionType::Read, AccessLevel::CURRENT_READ),
+        (PermissionType::Write, AccessLevel::CURRENT_WRITE),
+        (PermissionType::ReadHistory, AccessLevel::HISTORY_READ),
+    ] {
+        if !permissions.contains(permission) {
+            access_level.remove(level);
+        }
+    }
+    if !permissions.intersects(
+        PermissionType::InsertHistory
+            | PermissionType::ModifyHistory
+            | PermissionType::DeleteHistory,
+    ) {
+        access_level.remove(AccessLevel::HISTORY_WRITE);
+    }
+    access_level
+}
+
+/// Get the effective user access level for `node`, taking both the authenticator
+/// and the role permissions of the user into account.
+pub fn user_access_level(context: &RequestContext, node: &NodeType) -> AccessLevel {
+    let access_level = authenticator_access_level(context, node);
+    match user_permissions(context, node) {
+        Some(permissions) => mask_access_level(access_level, permissions),
+        None => access_level,
+    }
+}
+
+/// The `RolePermissions` and `AccessRestrictions` of a node.
+///
+/// Node managers report these for their nodes through
+/// [NodeManager::node_permissions](crate::node_manager::NodeManager::node_permissions),
+/// and the server validates them before each service call reaches the node manager.
+#[derive(Debug, Clone, Default)]
+pub struct NodePermissions {
+    /// Role permissions of the node. If `None`, the default role permissions
+    /// of the namespace apply.
+    pub role_permissions: Option<Vec<RolePermissionType>>,
+    /// Access restrictions of the node. If `None`, the default access restrictions
+    /// of the namespace apply.
+    pub access_restrictions: Option<AccessRestrictionType>,
+}
+
+impl NodePermissions {
+    /// Get the permissions defined on `node`.
+    pub fn of_node(node: &NodeType) -> Self {
+        let node = node.as_node();
+        Self {
+            role_permissions: node.role_permissions().map(|p| p.to_vec()),
+            access_restrictions: node.access_restrictions(),
+        }
+    }
+
+    /// Fill in any permissions not defined on the node with the defaults of `namespace`.
+    pub fn with_namespace_defaults(mut self, roles: &RoleSet, namespace: u16) -> Self {
+        if self.role_permissions.is_none() {
+            self.role_permissions = roles.namespace_role_permissions(namespace);
+        }
+        if self.access_restrictions.is_none() {
+            self.access_restrictions = Some(roles.namespace_access_restrictions(namespace));
+        }
+        self
+    }
+
+    /// Get the permissions granted to `roles`, or `None` if access to the node is
+    /// not restricted by roles.
+    pub fn granted(&self, roles: &[NodeId]) -> Option<PermissionType> {
+        self.role_permissions
+            .as_deref()
+            .map(|p| permissions_for_roles(p, roles))
+    }
+
+    /// Validate that a session using `security_mode` satisfies the access restrictions.
+    /// Unless the restrictions apply to browse, they are only checked for operations
+    /// other than browsing the node.
+    pub fn validate_access_restrictions(
+        &self,
+        security_mode: MessageSecurityMode,
+        is_browse: bool,
+    ) -> Result<(), StatusCode> {
+        let restrictions = self.access_restrictions.unwrap_or_default();
+        if is_browse && !restrictions.contains(AccessRestrictionType::ApplyRestrictionsToBrowse) {
+            return Ok(());
+        }
+        let is_encrypted = security_mode == MessageSecurityMode::SignAndEncrypt;
+        let is_signed = is_encrypted || security_mode == MessageSecurityMode::Sign;
+        if restrictions.contains(AccessRestrictionType::EncryptionRequired) && !is_encrypted
+            || restrictions.contains(AccessRestrictionType::SigningRequired) && !is_signed
+        {
+            return Err(StatusCode::BadSecurityModeInsufficient);
+        }
+        Ok(())
+    }
+
+    /// Validate that a user with `roles`, connected using `security_mode`, has all
+    /// of `permissions`, and that the access restrictions are satisfied.
+    ///
+    /// If the user is not allowed to browse the node, this returns `BadNodeIdUnknown`,
+    /// since the node should not be visible to them at all.
+    pub fn validate(
+        &self,
+        roles: &[NodeId],
+        security_mode: MessageSecurityMode,
+        permissions: PermissionType,
+    ) -> Result<(), StatusCode> {
+        let is_browse =
+            (PermissionType::Browse | PermissionType::ReadRolePermissions).contains(permissions);
+        self.validate_access_restrictions(security_mode, is_browse)?;
+        let Some(granted) = self.granted(roles) else {
+            return Ok(());
+        };
+        if !granted.contains(PermissionType::Browse) {
+            Err(StatusCode::BadNodeIdUnknown)
+        } else if !granted.contains(permissions) {
+            Err(StatusCode::BadUserAccessDenied)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn node_permissions(context: &RequestContext, node: &NodeType) -> NodePermissions {
+    NodePermissions::of_node(node)
+        .with_namespace_defaults(&context.info.roles, node.node_id().namespace)
+}
+
+/// Get the role permissions that apply to `node`, either from the node itself
+/// or from the default role permissions of its namespace.
+pub fn effective_role_permissions(
+    context: &RequestContext,
+    node: &NodeType,
+) -> Option<Vec<RolePermissionType>> {
+    node_permissions(context, node).role_permissions
+}
+
+/// Get the permissions of the user given by `context` on `node`.
+///
+/// Returns `None` if neither the node nor its namespace define role permissions,
+/// meaning that access to the node is not restricted by roles.
+pub fn user_permissions(context: &RequestContext, node: &NodeType) -> Option<PermissionType> {
+    node_permissions(context, node).granted(&context.roles)
+}
+
+/// Validate that the session given by `context` satisfies the access restrictions on
+/// `node`. Unless the restrictions apply to browse, they are only checked for operations
+/// other than browsing the node.
+pub fn validate_access_restrictions(
+    context: &RequestContext,
+    node: &NodeType,
+    is_browse: bool,
+) -> Result<(), StatusCode> {
+    node_permissions(context, node).validate_access_restrictions(context.security_mode, is_browse)
+}
+
+/// Validate that the user given by `context` has all of `permissions` on `node`,
+/// and that the access restrictions of the node are satisfied.
+///
+/// If the user is not allowed to browse the node, this returns `BadNodeIdUnknown`,
+/// since the node should not be visible to them at all.
+pub fn validate_permission(
+    context: &RequestContext,
+    node: &NodeType,
+    permissions: PermissionType,
+) -> Result<(), StatusCode> {
+    node_permissions(context, node).validate(&context.roles, context.security_mode, permissions)
+}
+
+/// Get whether the user given by `context` is allowed to see `node` when browsing.
+pub fn is_browsable(context: &RequestContext, node: &NodeType) -> bool {
+    validate_permission(context, node, PermissionType::Browse).is_ok()
+}
+
 /// Validate that the user given by `context` is allowed to read
 /// the value of `node`.
 pub fn validate_node_read(
@@ -91,6 +281,12 @@ pub fn validate_node_read(
     context: &RequestContext,
     node_to_read: &ParsedReadValueId,
 ) -> Result<(), StatusCode> {
+    let permission = match (node, node_to_read.attribute_id) {
+        (NodeType::Variable(_), AttributeId::Value) => PermissionType::Read,
+        (_, AttributeId::RolePermissions) => PermissionType::ReadRolePermissions,
+        _ => PermissionType::Browse,
+    };
+    validate_permission(context, node, permission)?;
     is_readable(context, node)?;
 
     if node_to_read.attribute_id != AttributeId::Value
@@ -204,6 +400,23 @@ pub fn read_node_value(
 ) -> DataValue {
     let mut result_value = DataValue::null();
 
+    if node_to_read.attribute_id == AttributeId::UserRolePermissions {
+        // Only include the permissions of roles granted to the current user.
+        match effective_role_permissions(context, node) {
+            Some(permissions) => {
+                let value: Vec<_> = permissions
+                    .into_iter()
+                    .filter(|p| context.roles.contains(&p.role_id))
+                    .map(ExtensionObject::from_message)
+                    .collect();
+                result_value.value = Some(value.into());
+                result_value.status = Some(StatusCode::Good);
+            }
+            None => result_value.status = Some(StatusCode::BadAttributeIdInvalid),
+        }
+        return result_value;
+    }
+
     let Some(attribute) = node.as_node().get_attribute_max_age(
         timestamps_to_return,
         node_to_read.attribute_id,
@@ -224,6 +437,10 @@ pub fn read_node_value(
                     access_level,
                     node.node_id(),
                 );
+                let access_level = match user_permissions(context, node) {
+                    Some(permissions) => mask_access_level(access_level, permissions),
+                    None => access_level,
+                };
                 Some(Variant::from(access_level.bits()))
             }
             Some(v) => Some(v),
@@ -238,7 +455,9 @@ pub fn read_node_value(
             Some(Variant::Boolean(val)) => Some(Variant::from(
                 val && context
                     .authenticator
-                    .is_user_executable(&context.token, node.node_id()),
+                    .is_user_executable(&context.token, node.node_id())
+                    && user_permissions(context, node)
+                        .is_none_or(|p| p.contains(PermissionType::Call)),
             )),
             r => r,
         }
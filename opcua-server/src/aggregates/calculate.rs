@@ -0,0 +1,722 @@
+use std::ops::Range;
+
+use opcua_types::{
+    AggregateConfiguration, DataValue, DateTime, StatusCode, StatusCodeInfoType,
+    StatusCodeValueType, Variant,
+};
+
+use super::AggregateFunction;
+
+#[derive(Debug, Clone, Copy)]
+/// A single processing interval, and the raw data used to calculate an
+/// aggregate over it.
+pub struct AggregateInterval<'a> {
+    /// Start of the interval, inclusive.
+    pub start: DateTime,
+    /// End of the interval, exclusive.
+    pub end: DateTime,
+    /// Raw values ordered by timestamp. This should contain the values inside
+    /// the interval, as well as the values surrounding it, which are used to
+    /// calculate bounding values. Values outside the interval are otherwise ignored.
+    pub data: &'a [DataValue],
+    /// Whether the variable is stepped. Stepped values are held until the next
+    /// raw value, other values are interpolated linearly between raw values.
+    pub stepped: bool,
+    /// Whether the interval is known to be incomplete, for example because it is
+    /// shorter than the processing interval.
+    pub partial: bool,
+}
+
+/// Value of a variable at a point in time, derived from the raw values around it.
+struct Bound {
+    value: Variant,
+    status: StatusCode,
+    raw: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quality {
+    Good,
+    Bad,
+    NoData,
+}
+
+/// Durations in milliseconds of good, bad and missing data in an interval,
+/// and the integral of the good data over time.
+#[derive(Default)]
+struct TimeWeighted {
+    good: f64,
+    bad: f64,
+    no_data: f64,
+    integral: f64,
+    invalid_inputs: bool,
+}
+
+pub(super) fn is_good(config: &AggregateConfiguration, value: &DataValue) -> bool {
+    let status = value.status();
+    status.is_good() || status.is_uncertain() && !config.treat_uncertain_as_bad
+}
+
+pub(super) fn timestamp(value: &DataValue) -> Option<DateTime> {
+    value.source_timestamp.or(value.server_timestamp)
+}
+
+pub(super) fn sort_key(value: &DataValue) -> DateTime {
+    timestamp(value).unwrap_or_else(DateTime::null)
+}
+
+fn interpolate(a: &DataValue, b: &DataValue, at: DateTime) -> Option<Variant> {
+    let va = a.value.as_ref()?.as_f64()?;
+    let vb = b.value.as_ref()?.as_f64()?;
+    let span = (sort_key(b) - sort_key(a)).num_microseconds()? as f64;
+    if span == 0.0 {
+        return Some(Variant::Double(vb));
+    }
+    let offset = (at - sort_key(a)).num_microseconds()? as f64;
+    Some(Variant::Double(va + (vb - va) * offset / span))
+}
+
+fn millis(from: DateTime, to: DateTime) -> f64 {
+    (to - from)
+        .num_microseconds()
+        .map(|us| us as f64 / 1000.0)
+        .unwrap_or(f64::MAX)
+}
+
+impl AggregateInterval<'_> {
+    fn range(&self) -> Range<usize> {
+        let lo = self.data.partition_point(|v| sort_key(v) < self.start);
+        let hi = self.data.partition_point(|v| sort_key(v) < self.end);
+        lo..hi.max(lo)
+    }
+
+    /// Find the value at `at`, using simple bounding values. A good raw value at
+    /// exactly `at` is used as-is, otherwise the value is interpolated between
+    /// the nearest good raw values, or extrapolated from earlier values if there is
+    /// no later good value. Skipping bad values or extrapolating makes the bound uncertain.
+    fn bound(&self, config: &AggregateConfiguration, at: DateTime) -> Option<Bound> {
+        let data = self.data;
+        let usable = |v: &DataValue| is_good(config, v) && v.value.is_some();
+        let idx = data.partition_point(|v| sort_key(v) < at);
+        if let Some(v) = data.get(idx) {
+            if sort_key(v) == at && usable(v) {
+                return Some(Bound {
+                    value: v.value.clone()?,
+                    status: v.status(),
+                    raw: true,
+                });
+            }
+        }
+
+        let before = data[..idx].iter().rposition(usable)?;
+        let after = data[idx..].iter().position(usable).map(|i| i + idx);
+        let prior = &data[before];
+        let prior_value = prior.value.clone()?;
+        let skipped_bad = before + 1 != idx || after.is_some_and(|a| a != idx);
+        let mut status = if skipped_bad {
+            StatusCode::UncertainDataSubNormal
+        } else {
+            StatusCode::Good
+        };
+
+        let value = match after {
+            Some(after) if !self.stepped => {
+                interpolate(prior, &data[after], at).unwrap_or(prior_value)
+            }
+            Some(_) => prior_value,
+            None => {
+                status = StatusCode::UncertainDataSubNormal;
+                let earlier = if config.use_sloped_extrapolation && !self.stepped {
+                    data[..before].iter().rposition(usable).map(|i| &data[i])
+                } else {
+                    None
+                };
+                earlier
+                    .and_then(|e| interpolate(e, prior, at))
+                    .unwrap_or(prior_value)
+            }
+        };
+
+        Some(Bound {
+            value,
+            status,
+            raw: false,
+        })
+    }
+
+    /// Split the interval into periods between raw values, each with the quality
+    /// of the raw value in effect at its start, and sum up the durations and the
+    /// area under the good data.
+    fn time_weighted(&self, config: &AggregateConfiguration) -> TimeWeighted {
+        let range = self.range();
+        let values = &self.data[range.clone()];
+        let quality = |v: &DataValue| {
+            if is_good(config, v) {
+                Quality::Good
+            } else {
+                Quality::Bad
+            }
+        };
+        let numeric = |v: Option<Variant>| v.and_then(|v| v.as_f64());
+
+        let mut points = Vec::with_capacity(values.len() + 2);
+        if values.first().is_none_or(|v| sort_key(v) > self.start) {
+            match self.data[..range.start].last() {
+                Some(prior) => points.push((
+                    self.start,
+                    numeric(self.bound(config, self.start).map(|b| b.value)),
+                    quality(prior),
+                )),
+                None => points.push((self.start, None, Quality::NoData)),
+            }
+        }
+        for v in values {
+            points.push((sort_key(v), numeric(v.value.clone()), quality(v)));
+        }
+        let end_value = numeric(self.bound(config, self.end).map(|b| b.value));
+
+        let mut res = TimeWeighted::default();
+        for (i, (time, value, quality)) in points.iter().enumerate() {
+            // Interpolate across bad values to the next good value.
+            let (next_time, next_value) = match points.get(i + 1) {
+                Some((t, v, Quality::Good)) => (*t, *v),
+                Some((t, _, _)) => (*t, numeric(self.bound(config, *t).map(|b| b.value))),
+                None => (self.end, end_value),
+            };
+            let duration = millis(*time, next_time);
+            match quality {
+                Quality::Good => {
+                    res.good += duration;
+                    let Some(value) = value else {
+                        res.invalid_inputs = true;
+                        continue;
+                    };
+                    let next_value = if self.stepped {
+                        *value
+                    } else {
+                        next_value.unwrap_or(*value)
+                    };
+                    res.integral += (value + next_value) / 2.0 * duration;
+                }
+                Quality::Bad => res.bad += duration,
+                Quality::NoData => res.no_data += duration,
+            }
+        }
+        res
+    }
+}
+
+/// Get the status of an aggregate from the amount of good and bad data, following
+/// the `PercentDataGood` and `PercentDataBad` settings in `config`.
+fn aggregate_status(
+    config: &AggregateConfiguration,
+    good: f64,
+    bad: f64,
+    total: f64,
+) -> StatusCode {
+    if good <= 0.0 && bad <= 0.0 {
+        StatusCode::BadNoData
+    } else if bad > 0.0 && bad * 100.0 >= config.percent_data_bad as f64 * total {
+        StatusCode::Bad
+    } else if good * 100.0 >= config.percent_data_good as f64 * total {
+        StatusCode::Good
+    } else {
+        StatusCode::UncertainDataSubNormal
+    }
+}
+
+fn finish(
+    mut result: DataValue,
+    status: StatusCode,
+    value_type: StatusCodeValueType,
+    partial: bool,
+) -> DataValue {
+    result.status = Some(
+        status
+            .set_info_type(StatusCodeInfoType::DataValue)
+            .set_value_type(value_type)
+            .set_partial(partial),
+    );
+    result
+}
+
+/// Calculate `function` over a single processing interval.
+///
+/// The result is timestamped with the start of the interval, except for aggregates
+/// returning a raw value with its own timestamp. The status code has the aggregate
+/// bits set: whether the value is raw, calculated or interpolated, whether the
+/// interval is partial, and whether the returned extreme value occurs more than once.
+pub fn calculate_aggregate(
+    function: AggregateFunction,
+    config: &AggregateConfiguration,
+    interval: &AggregateInterval<'_>,
+) -> DataValue {
+    let range = interval.range();
+    let values = &interval.data[range.clone()];
+    // The interval is partial if the data starts after the interval does.
+    let covered = range.start > 0
+        || values
+            .first()
+            .is_some_and(|v| sort_key(v) <= interval.start);
+    let partial = interval.partial || !covered;
+
+    let mut result = DataValue {
+        value: None,
+        status: None,
+        source_timestamp: Some(interval.start),
+        source_picoseconds: None,
+        server_timestamp: Some(DateTime::now()),
+        server_picoseconds: None,
+    };
+    let value_type = function.value_type();
+
+    match function {
+        AggregateFunction::Interpolative => {
+            let Some(bound) = interval.bound(config, interval.start) else {
+                return finish(result, StatusCode::BadNoData, value_type, partial);
+            };
+            result.value = Some(bound.value);
+            let value_type = if bound.raw {
+                StatusCodeValueType::Raw
+            } else {
+                value_type
+            };
+            return finish(
+                result,
+                bound.status.set_info_type(StatusCodeInfoType::NotUsed),
+                value_type,
+                partial,
+            );
+        }
+        AggregateFunction::TimeAverage
+        | AggregateFunction::DurationGood
+        | AggregateFunction::DurationBad
+        | AggregateFunction::PercentGood
+        | AggregateFunction::PercentBad => {
+            let tw = interval.time_weighted(config);
+            let total = millis(interval.start, interval.end);
+            // Periods without data count as bad.
+            let bad = tw.bad + tw.no_data;
+            if tw.good <= 0.0 && tw.bad <= 0.0 {
+                return finish(result, StatusCode::BadNoData, value_type, partial);
+            }
+            let value = match function {
+                AggregateFunction::TimeAverage => {
+                    let status = aggregate_status(config, tw.good, bad, total);
+                    if status.is_bad() {
+                        return finish(result, status, value_type, partial);
+                    }
+                    if tw.invalid_inputs {
+                        return finish(
+                            result,
+                            StatusCode::BadAggregateInvalidInputs,
+                            value_type,
+                            partial,
+                        );
+                    }
+                    result.value = Some(Variant::Double(tw.integral / tw.good));
+                    return finish(result, status, value_type, partial);
+                }
+                AggregateFunction::DurationGood => tw.good,
+                AggregateFunction::DurationBad => bad,
+                AggregateFunction::PercentGood => tw.good * 100.0 / total,
+                _ => bad * 100.0 / total,
+            };
+            result.value = Some(Variant::Double(value));
+            return finish(result, StatusCode::Good, value_type, partial);
+        }
+        _ => (),
+    }
+
+    let good: Vec<_> = values.iter().filter(|v| is_good(config, v)).collect();
+    let total = values.len();
+    let bad = total - good.len();
+
+    let status = if total == 0 && function == AggregateFunction::Count {
+        StatusCode::Good
+    } else {
+        aggregate_status(config, good.len() as f64, bad as f64, total as f64)
+    };
+
+    if status.is_bad() {
+        return finish(result, status, value_type, partial);
+    }
+
+    match function {
+        AggregateFunction::Count => {
+            result.value = Some(Variant::Int32(good.len() as i32));
+            return finish(result, status, value_type, partial);
+        }
+        // Start and End report the first and last raw values as-is.
+        AggregateFunction::Start | AggregateFunction::End => {
+            let raw = if function == AggregateFunction::Start {
+                values.first()
+            } else {
+                values.last()
+            };
+            let Some(raw) = raw else {
+                return finish(result, StatusCode::BadNoData, value_type, partial);
+            };
+            result.value = raw.value.clone();
+            result.source_timestamp = timestamp(raw).or(Some(interval.start));
+            return finish(
+                result,
+                raw.status().set_info_type(StatusCodeInfoType::NotUsed),
+                value_type,
+                partial,
+            );
+        }
+        _ => (),
+    }
+
+    let numeric: Vec<_> = good
+        .iter()
+        .filter_map(|v| v.value.as_ref().and_then(|n| n.as_f64()).map(|n| (n, *v)))
+        .collect();
+    if numeric.is_empty() {
+        let status = if good.is_empty() {
+            StatusCode::BadNoData
+        } else {
+            StatusCode::BadAggregateInvalidInputs
+        };
+        return finish(result, status, value_type, partial);
+    }
+
+    // Keep the first occurrence of the extreme values.
+    let min = numeric
+        .iter()
+        .copied()
+        .reduce(|a, b| if b.0 < a.0 { b } else { a })
+        .unwrap();
+    let max = numeric
+        .iter()
+        .copied()
+        .reduce(|a, b| if b.0 > a.0 { b } else { a })
+        .unwrap();
+
+    let mut status = status;
+    match function {
+        AggregateFunction::Average => {
+            let sum: f64 = numeric.iter().map(|(n, _)| n).sum();
+            result.value = Some(Variant::Double(sum / numeric.len() as f64));
+        }
+        AggregateFunction::Range => {
+            result.value = Some(Variant::Double(max.0 - min.0));
+        }
+        AggregateFunction::Delta => {
+            let first = numeric.first().unwrap().0;
+            let last = numeric.last().unwrap().0;
+            result.value = Some(Variant::Double(last - first));
+        }
+        AggregateFunction::Minimum
+        | AggregateFunction::Maximum
+        | AggregateFunction::MinimumActualTime
+        | AggregateFunction::MaximumActualTime => {
+            let (extreme, raw) = match function {
+                AggregateFunction::Minimum | AggregateFunction::MinimumActualTime => min,
+                _ => max,
+            };
+            result.value = raw.value.clone();
+            if matches!(
+                function,
+                AggregateFunction::MinimumActualTime | AggregateFunction::MaximumActualTime
+            ) {
+                result.source_timestamp = timestamp(raw).or(Some(interval.start));
+            }
+            // Flag that the extreme value occurs more than once in the interval.
+            if numeric.iter().filter(|(n, _)| *n == extreme).count() > 1 {
+                status = status
+                    .set_info_type(StatusCodeInfoType::DataValue)
+                    .set_multi_value(true);
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    finish(result, status, value_type, partial)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use opcua_types::{
+        AggregateConfiguration, DataValue, DateTime, StatusCode, StatusCodeValueType, Variant,
+    };
+
+    use super::{calculate_aggregate, AggregateInterval};
+    use crate::aggregates::{default_aggregate_configuration, AggregateFunction};
+
+    fn values(start: DateTime, vals: &[(f64, StatusCode)]) -> Vec<DataValue> {
+        vals.iter()
+            .enumerate()
+            .map(|(i, (v, s))| DataValue {
+                value: Some(Variant::Double(*v)),
+                status: Some(*s),
+                source_timestamp: Some(start + Duration::milliseconds(i as i64 * 100)),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn interval(start: DateTime, ms: i64, data: &[DataValue]) -> AggregateInterval<'_> {
+        AggregateInterval {
+            start,
+            end: start + Duration::milliseconds(ms),
+            data,
+            stepped: false,
+            partial: false,
+        }
+    }
+
+    #[test]
+    fn simple_aggregates() {
+        let start = DateTime::now();
+        let config = default_aggregate_configuration();
+        let vals = values(
+            start,
+            &[
+                (1.0, StatusCode::Good),
+                (5.0, StatusCode::Good),
+                (3.0, StatusCode::Good),
+                (5.0, StatusCode::Good),
+            ],
+        );
+
+        let calc = |f| calculate_aggregate(f, &config, &interval(start, 400, &vals));
+
+        let avg = calc(AggregateFunction::Average);
+        assert_eq!(avg.value, Some(Variant::Double(3.5)));
+        assert!(avg.status().is_good());
+        assert!(!avg.status().partial());
+        assert_eq!(avg.status().value_type(), StatusCodeValueType::Calculated);
+        assert_eq!(avg.source_timestamp, Some(start));
+
+        assert_eq!(
+            calc(AggregateFunction::Count).value,
+            Some(Variant::Int32(4))
+        );
+        assert_eq!(
+            calc(AggregateFunction::Range).value,
+            Some(Variant::Double(4.0))
+        );
+        assert_eq!(
+            calc(AggregateFunction::Delta).value,
+            Some(Variant::Double(4.0))
+        );
+        assert_eq!(
+            calc(AggregateFunction::Minimum).value,
+            Some(Variant::Double(1.0))
+        );
+
+        let max = calc(AggregateFunction::MaximumActualTime);
+        assert_eq!(max.value, Some(Variant::Double(5.0)));
+        assert!(max.status().multi_value());
+        assert_eq!(max.status().value_type(), StatusCodeValueType::Raw);
+        assert_eq!(max.source_timestamp, vals[1].source_timestamp);
+
+        let end = calc(AggregateFunction::End);
+        assert_eq!(end.value, Some(Variant::Double(5.0)));
+        assert_eq!(end.source_timestamp, vals[3].source_timestamp);
+    }
+
+    #[test]
+    fn aggregate_status() {
+        let start = DateTime::now();
+        let config = default_aggregate_configuration();
+
+        // No data
+        let res = calculate_aggregate(
+            AggregateFunction::Average,
+            &config,
+            &interval(start, 100, &[]),
+        );
+        assert_eq!(res.status().sub_code(), StatusCode::BadNoData.sub_code());
+        assert!(res.status().partial());
+        let res = calculate_aggregate(
+            AggregateFunction::Count,
+            &config,
+            &interval(start, 100, &[]),
+        );
+        assert_eq!(res.value, Some(Variant::Int32(0)));
+
+        // Some bad data, with default configuration the result is uncertain.
+        let vals = values(
+            start,
+            &[(1.0, StatusCode::Good), (2.0, StatusCode::BadSensorFailure)],
+        );
+        let res = calculate_aggregate(
+            AggregateFunction::Average,
+            &config,
+            &interval(start, 200, &vals),
+        );
+        assert_eq!(res.value, Some(Variant::Double(1.0)));
+        assert_eq!(
+            res.status().sub_code(),
+            StatusCode::UncertainDataSubNormal.sub_code()
+        );
+
+        // All bad
+        let vals = values(start, &[(1.0, StatusCode::BadSensorFailure)]);
+        let res = calculate_aggregate(
+            AggregateFunction::Average,
+            &config,
+            &interval(start, 100, &vals),
+        );
+        assert!(res.status().is_bad());
+        assert!(res.value.is_none());
+
+        // Treating uncertain data as good.
+        let config = AggregateConfiguration {
+            use_server_capabilities_defaults: false,
+            treat_uncertain_as_bad: false,
+            percent_data_bad: 50,
+            percent_data_good: 50,
+            use_sloped_extrapolation: false,
+        };
+        let vals = values(
+            start,
+            &[(1.0, StatusCode::Good), (2.0, StatusCode::Uncertain)],
+        );
+        let res = calculate_aggregate(
+            AggregateFunction::Average,
+            &config,
+            &interval(start, 200, &vals),
+        );
+        assert_eq!(res.value, Some(Variant::Double(1.5)));
+        assert!(res.status().is_good());
+    }
+
+    #[test]
+    fn interpolated_aggregates() {
+        let t0 = DateTime::now();
+        let config = default_aggregate_configuration();
+        // Raw values at 0, 100, 200 and 300 ms.
+        let vals = values(
+            t0,
+            &[
+                (0.0, StatusCode::Good),
+                (10.0, StatusCode::Good),
+                (20.0, StatusCode::BadSensorFailure),
+                (30.0, StatusCode::Good),
+            ],
+        );
+        let at = |ms| t0 + Duration::milliseconds(ms);
+
+        // Interpolated between the values at 0 and 100.
+        let res = calculate_aggregate(
+            AggregateFunction::Interpolative,
+            &config,
+            &interval(at(50), 100, &vals),
+        );
+        assert_eq!(res.value, Some(Variant::Double(5.0)));
+        assert!(res.status().is_good());
+        assert_eq!(res.status().value_type(), StatusCodeValueType::Interpolated);
+
+        // Stepped variables hold the previous value.
+        let mut stepped = interval(at(50), 100, &vals);
+        stepped.stepped = true;
+        let res = calculate_aggregate(AggregateFunction::Interpolative, &config, &stepped);
+        assert_eq!(res.value, Some(Variant::Double(0.0)));
+
+        // Exactly on a raw value.
+        let res = calculate_aggregate(
+            AggregateFunction::Interpolative,
+            &config,
+            &interval(at(100), 100, &vals),
+        );
+        assert_eq!(res.value, Some(Variant::Double(10.0)));
+        assert_eq!(res.status().value_type(), StatusCodeValueType::Raw);
+
+        // Interpolating across the bad value makes the result uncertain.
+        let res = calculate_aggregate(
+            AggregateFunction::Interpolative,
+            &config,
+            &interval(at(250), 100, &vals),
+        );
+        assert_eq!(res.value, Some(Variant::Double(25.0)));
+        assert_eq!(
+            res.status().sub_code(),
+            StatusCode::UncertainDataSubNormal.sub_code()
+        );
+
+        // Extrapolation after the last value.
+        let res = calculate_aggregate(
+            AggregateFunction::Interpolative,
+            &config,
+            &interval(at(500), 100, &vals),
+        );
+        assert_eq!(res.value, Some(Variant::Double(30.0)));
+        assert!(res.status().is_uncertain());
+
+        // No data before the interval.
+        let res = calculate_aggregate(
+            AggregateFunction::Interpolative,
+            &config,
+            &interval(at(-50), 100, &vals),
+        );
+        assert_eq!(res.status().sub_code(), StatusCode::BadNoData.sub_code());
+    }
+
+    #[test]
+    fn time_weighted_aggregates() {
+        let t0 = DateTime::now();
+        let config = AggregateConfiguration {
+            use_server_capabilities_defaults: false,
+            treat_uncertain_as_bad: true,
+            percent_data_bad: 50,
+            percent_data_good: 50,
+            use_sloped_extrapolation: false,
+        };
+        let vals = values(
+            t0,
+            &[
+                (0.0, StatusCode::Good),
+                (10.0, StatusCode::Good),
+                (20.0, StatusCode::BadSensorFailure),
+                (30.0, StatusCode::Good),
+            ],
+        );
+        let at = |ms| t0 + Duration::milliseconds(ms);
+        let calc = |f, i: &AggregateInterval<'_>| calculate_aggregate(f, &config, i);
+
+        // Sloped: 0 -> 10 over the first 100ms, then towards 30 at 300ms,
+        // skipping the bad value, giving an end bound of 20.
+        let full = interval(t0, 200, &vals);
+        let res = calc(AggregateFunction::TimeAverage, &full);
+        assert_eq!(res.value, Some(Variant::Double(10.0)));
+        assert!(res.status().is_good());
+
+        // Good for 300 of 400 ms, the value is held after the last raw value.
+        let res = calc(AggregateFunction::TimeAverage, &interval(t0, 400, &vals));
+        assert_eq!(
+            res.value,
+            Some(Variant::Double((500.0 + 1500.0 + 3000.0) / 300.0))
+        );
+
+        let mut stepped = interval(t0, 200, &vals);
+        stepped.stepped = true;
+        let res = calc(AggregateFunction::TimeAverage, &stepped);
+        assert_eq!(res.value, Some(Variant::Double(5.0)));
+
+        let whole = interval(t0, 400, &vals);
+        assert_eq!(
+            calc(AggregateFunction::DurationGood, &whole).value,
+            Some(Variant::Double(300.0))
+        );
+        assert_eq!(
+            calc(AggregateFunction::DurationBad, &whole).value,
+            Some(Variant::Double(100.0))
+        );
+        assert_eq!(
+            calc(AggregateFunction::PercentGood, &whole).value,
+            Some(Variant::Double(75.0))
+        );
+
+        // No data before the first value counts as bad, and makes the interval partial.
+        let early = interval(at(-100), 200, &vals);
+        let res = calc(AggregateFunction::PercentBad, &early);
+        assert_eq!(res.value, Some(Variant::Double(50.0)));
+        assert!(res.status().partial());
+        assert_eq!(res.status().value_type(), StatusCodeValueType::Calculated);
+    }
+}
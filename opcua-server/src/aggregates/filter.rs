@@ -0,0 +1,265 @@
+use chrono::Duration;
+use opcua_types::{
+    AggregateConfiguration, AggregateFilter, AggregateFilterResult, DataValue, DateTime, StatusCode,
+};
+
+use super::{
+    calculate::{sort_key, timestamp},
+    calculate_aggregate, revise_aggregate_configuration, AggregateFunction, AggregateInterval,
+};
+
+#[derive(Debug, Clone)]
+/// Parsed and revised aggregate filter for a monitored item.
+pub struct ParsedAggregateFilter {
+    /// Aggregate function to calculate for each processing interval.
+    pub function: AggregateFunction,
+    /// Start of the first processing interval. Later intervals are
+    /// aligned to this.
+    pub start_time: DateTime,
+    /// Length of each processing interval in milliseconds.
+    pub processing_interval: f64,
+    /// Aggregate configuration used for the calculation.
+    pub configuration: AggregateConfiguration,
+}
+
+impl ParsedAggregateFilter {
+    /// Parse an aggregate filter for a monitored item with the given revised
+    /// sampling interval in milliseconds.
+    ///
+    /// The processing interval cannot be shorter than the sampling interval.
+    pub fn parse(
+        filter: AggregateFilter,
+        sampling_interval: f64,
+    ) -> (AggregateFilterResult, Result<Self, StatusCode>) {
+        let start_time = if filter.start_time.is_null() {
+            DateTime::now()
+        } else {
+            filter.start_time
+        };
+        let processing_interval = if !filter.processing_interval.is_finite()
+            || filter.processing_interval < sampling_interval
+        {
+            sampling_interval
+        } else {
+            filter.processing_interval
+        };
+        let configuration = revise_aggregate_configuration(filter.aggregate_configuration.clone());
+
+        let result = AggregateFilterResult {
+            revised_start_time: start_time,
+            revised_processing_interval: processing_interval,
+            revised_aggregate_configuration: configuration
+                .clone()
+                .unwrap_or(filter.aggregate_configuration),
+        };
+
+        let Some(function) = AggregateFunction::from_node_id(&filter.aggregate_type) else {
+            return (result, Err(StatusCode::BadAggregateNotSupported));
+        };
+        let configuration = match configuration {
+            Ok(c) => c,
+            Err(e) => return (result, Err(e)),
+        };
+
+        (
+            result,
+            Ok(Self {
+                function,
+                start_time,
+                processing_interval,
+                configuration,
+            }),
+        )
+    }
+
+    fn interval_us(&self) -> i64 {
+        // Avoid a zero length interval, which would never advance.
+        ((self.processing_interval * 1000.0) as i64).max(1000)
+    }
+}
+
+#[derive(Debug)]
+/// Collects values for a monitored item with an aggregate filter, and
+/// calculates the aggregate when each processing interval ends.
+pub(crate) struct IntervalAggregator {
+    filter: ParsedAggregateFilter,
+    interval_start: DateTime,
+    partial: bool,
+    /// Last value of the previous interval, used to calculate bounding values.
+    prior: Option<DataValue>,
+    values: Vec<DataValue>,
+}
+
+impl IntervalAggregator {
+    pub(crate) fn new(filter: ParsedAggregateFilter, now: DateTime) -> Self {
+        let mut aggregator = Self {
+            interval_start: filter.start_time,
+            filter,
+            partial: false,
+            prior: None,
+            values: Vec::new(),
+        };
+        aggregator.skip(now);
+        aggregator
+    }
+
+    fn elapsed_intervals(&self, now: DateTime) -> i64 {
+        if now < self.interval_start {
+            return 0;
+        }
+        (now - self.interval_start)
+            .num_microseconds()
+            .unwrap_or(i64::MAX)
+            / self.filter.interval_us()
+    }
+
+    fn advance(&mut self, intervals: i64) {
+        self.interval_start = self.interval_start
+            + Duration::microseconds(self.filter.interval_us().saturating_mul(intervals));
+    }
+
+    /// Discard any collected values and move to the interval containing `now`.
+    /// The current interval is marked as partial, since it is missing data.
+    pub(crate) fn skip(&mut self, now: DateTime) {
+        self.advance(self.elapsed_intervals(now));
+        self.prior = None;
+        self.values.clear();
+        self.partial = now > self.interval_start;
+    }
+
+    /// Add a value to the current interval. Values received before the first interval
+    /// starts are ignored.
+    pub(crate) fn push(&mut self, value: DataValue, now: DateTime) {
+        if now >= self.interval_start {
+            self.values.push(value);
+        }
+    }
+
+    /// Calculate the aggregate for each interval that has ended by `now`. At most
+    /// `max_results` results are returned, older empty intervals are skipped.
+    pub(crate) fn close_elapsed(&mut self, now: DateTime, max_results: usize) -> Vec<DataValue> {
+        let elapsed = self.elapsed_intervals(now);
+        if elapsed <= 0 {
+            return Vec::new();
+        }
+        let mut results = Vec::new();
+        results.push(self.calculate_current());
+        self.advance(1);
+
+        // The remaining intervals have no data.
+        let empty = elapsed - 1;
+        let skip = (empty - max_results.saturating_sub(1) as i64).max(0);
+        self.advance(skip);
+        for _ in skip..empty {
+            results.push(self.calculate_current());
+            self.advance(1);
+        }
+        results
+    }
+
+    fn calculate_current(&mut self) -> DataValue {
+        let start = self.interval_start;
+        let end = start + Duration::microseconds(self.filter.interval_us());
+        // Values belong to the interval they arrived in, even if their source
+        // timestamp is slightly off.
+        let latest = end - Duration::microseconds(1);
+        let mut data: Vec<_> = self.prior.take().into_iter().collect();
+        data.extend(std::mem::take(&mut self.values).into_iter().map(|mut v| {
+            v.source_timestamp = Some(timestamp(&v).unwrap_or(start).clamp(start, latest));
+            v
+        }));
+        data.sort_by_key(sort_key);
+
+        let result = calculate_aggregate(
+            self.filter.function,
+            &self.filter.configuration,
+            &AggregateInterval {
+                start,
+                end,
+                data: &data,
+                stepped: false,
+                partial: std::mem::take(&mut self.partial),
+            },
+        );
+        self.prior = data.pop();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use opcua_types::{
+        AggregateConfiguration, AggregateFilter, DataValue, DateTime, ObjectId, StatusCode, Variant,
+    };
+
+    use super::{IntervalAggregator, ParsedAggregateFilter};
+
+    #[test]
+    fn parse_filter() {
+        let (res, filter) = ParsedAggregateFilter::parse(
+            AggregateFilter {
+                start_time: DateTime::null(),
+                aggregate_type: ObjectId::AggregateFunction_Average.into(),
+                processing_interval: 50.0,
+                aggregate_configuration: AggregateConfiguration {
+                    use_server_capabilities_defaults: true,
+                    ..Default::default()
+                },
+            },
+            100.0,
+        );
+        filter.unwrap();
+        assert_eq!(res.revised_processing_interval, 100.0);
+        assert!(!res.revised_start_time.is_null());
+        assert_eq!(res.revised_aggregate_configuration.percent_data_good, 100);
+
+        let (_, filter) = ParsedAggregateFilter::parse(
+            AggregateFilter {
+                start_time: DateTime::null(),
+                aggregate_type: ObjectId::AggregateFunction_AnnotationCount.into(),
+                processing_interval: 1000.0,
+                aggregate_configuration: Default::default(),
+            },
+            100.0,
+        );
+        assert_eq!(filter.unwrap_err(), StatusCode::BadAggregateNotSupported);
+    }
+
+    #[test]
+    fn aggregate_intervals() {
+        let start = DateTime::now();
+        let (_, filter) = ParsedAggregateFilter::parse(
+            AggregateFilter {
+                start_time: start,
+                aggregate_type: ObjectId::AggregateFunction_Count.into(),
+                processing_interval: 1000.0,
+                aggregate_configuration: AggregateConfiguration {
+                    use_server_capabilities_defaults: true,
+                    ..Default::default()
+                },
+            },
+            100.0,
+        );
+        let mut agg = IntervalAggregator::new(filter.unwrap(), start);
+        let at = |ms: i64| start + Duration::milliseconds(ms);
+
+        agg.push(DataValue::new_at(1.0, at(100)), at(100));
+        agg.push(DataValue::new_at(2.0, at(500)), at(500));
+        assert!(agg.close_elapsed(at(900), 10).is_empty());
+
+        let res = agg.close_elapsed(at(1100), 10);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].value, Some(Variant::Int32(2)));
+        assert_eq!(res[0].source_timestamp, Some(start));
+
+        // Three intervals pass, but only the latest two are returned.
+        agg.push(DataValue::new_at(3.0, at(1500)), at(1500));
+        let res = agg.close_elapsed(at(4100), 2);
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].value, Some(Variant::Int32(1)));
+        assert_eq!(res[0].source_timestamp, Some(at(1000)));
+        assert_eq!(res[1].value, Some(Variant::Int32(0)));
+        assert_eq!(res[1].source_timestamp, Some(at(3000)));
+    }
+}
@@ -0,0 +1,195 @@
+//! Calculation of aggregates, as described in Part 13 of the standard.
+//!
+//! The server uses this to implement the `AggregateFilter` for monitored items,
+//! where an aggregate is calculated over the values sampled during each processing
+//! interval and reported instead of the raw values.
+//!
+//! Node managers implementing history can use [`read_processed`] to serve
+//! `ReadProcessed` requests from their raw history.
+
+mod calculate;
+mod filter;
+mod processed;
+
+use opcua_types::{AggregateConfiguration, NodeId, ObjectId, StatusCode, StatusCodeValueType};
+
+pub use calculate::{calculate_aggregate, AggregateInterval};
+pub(crate) use filter::IntervalAggregator;
+pub use filter::ParsedAggregateFilter;
+pub use processed::{calculate_processed, read_processed};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An aggregate function supported by the server.
+pub enum AggregateFunction {
+    /// Value at the start of the interval, interpolated from the surrounding raw values.
+    Interpolative,
+    /// Average of the good values in the interval.
+    Average,
+    /// Time weighted average of the good data in the interval.
+    TimeAverage,
+    /// Number of good values in the interval.
+    Count,
+    /// Smallest good value in the interval, timestamped with the interval start.
+    Minimum,
+    /// Largest good value in the interval, timestamped with the interval start.
+    Maximum,
+    /// Smallest good value in the interval, with its own timestamp.
+    MinimumActualTime,
+    /// Largest good value in the interval, with its own timestamp.
+    MaximumActualTime,
+    /// Difference between the largest and smallest good value in the interval.
+    Range,
+    /// First value in the interval.
+    Start,
+    /// Last value in the interval.
+    End,
+    /// Difference between the last and first good value in the interval.
+    Delta,
+    /// Duration in milliseconds of good data in the interval.
+    DurationGood,
+    /// Duration in milliseconds of bad data in the interval.
+    DurationBad,
+    /// Percentage of the interval with good data.
+    PercentGood,
+    /// Percentage of the interval with bad data.
+    PercentBad,
+}
+
+impl AggregateFunction {
+    /// All aggregate functions supported by the server.
+    pub const ALL: [AggregateFunction; 16] = [
+        AggregateFunction::Interpolative,
+        AggregateFunction::Average,
+        AggregateFunction::TimeAverage,
+        AggregateFunction::Count,
+        AggregateFunction::Minimum,
+        AggregateFunction::Maximum,
+        AggregateFunction::MinimumActualTime,
+        AggregateFunction::MaximumActualTime,
+        AggregateFunction::Range,
+        AggregateFunction::Start,
+        AggregateFunction::End,
+        AggregateFunction::Delta,
+        AggregateFunction::DurationGood,
+        AggregateFunction::DurationBad,
+        AggregateFunction::PercentGood,
+        AggregateFunction::PercentBad,
+    ];
+
+    /// Get the aggregate function identified by the given `AggregateFunction` node ID,
+    /// if it is supported.
+    pub fn from_node_id(id: &NodeId) -> Option<Self> {
+        let Ok(id) = id.as_object_id() else {
+            return None;
+        };
+        Some(match id {
+            ObjectId::AggregateFunction_Interpolative => Self::Interpolative,
+            ObjectId::AggregateFunction_Average => Self::Average,
+            ObjectId::AggregateFunction_TimeAverage => Self::TimeAverage,
+            ObjectId::AggregateFunction_Count => Self::Count,
+            ObjectId::AggregateFunction_Minimum => Self::Minimum,
+            ObjectId::AggregateFunction_Maximum => Self::Maximum,
+            ObjectId::AggregateFunction_MinimumActualTime => Self::MinimumActualTime,
+            ObjectId::AggregateFunction_MaximumActualTime => Self::MaximumActualTime,
+            ObjectId::AggregateFunction_Range => Self::Range,
+            ObjectId::AggregateFunction_Start => Self::Start,
+            ObjectId::AggregateFunction_End => Self::End,
+            ObjectId::AggregateFunction_Delta => Self::Delta,
+            ObjectId::AggregateFunction_DurationGood => Self::DurationGood,
+            ObjectId::AggregateFunction_DurationBad => Self::DurationBad,
+            ObjectId::AggregateFunction_PercentGood => Self::PercentGood,
+            ObjectId::AggregateFunction_PercentBad => Self::PercentBad,
+            _ => return None,
+        })
+    }
+
+    /// Get the ID of the `AggregateFunction` object for this aggregate.
+    pub fn object_id(&self) -> ObjectId {
+        match self {
+            Self::Interpolative => ObjectId::AggregateFunction_Interpolative,
+            Self::Average => ObjectId::AggregateFunction_Average,
+            Self::TimeAverage => ObjectId::AggregateFunction_TimeAverage,
+            Self::Count => ObjectId::AggregateFunction_Count,
+            Self::Minimum => ObjectId::AggregateFunction_Minimum,
+            Self::Maximum => ObjectId::AggregateFunction_Maximum,
+            Self::MinimumActualTime => ObjectId::AggregateFunction_MinimumActualTime,
+            Self::MaximumActualTime => ObjectId::AggregateFunction_MaximumActualTime,
+            Self::Range => ObjectId::AggregateFunction_Range,
+            Self::Start => ObjectId::AggregateFunction_Start,
+            Self::End => ObjectId::AggregateFunction_End,
+            Self::Delta => ObjectId::AggregateFunction_Delta,
+            Self::DurationGood => ObjectId::AggregateFunction_DurationGood,
+            Self::DurationBad => ObjectId::AggregateFunction_DurationBad,
+            Self::PercentGood => ObjectId::AggregateFunction_PercentGood,
+            Self::PercentBad => ObjectId::AggregateFunction_PercentBad,
+        }
+    }
+
+    fn value_type(&self) -> StatusCodeValueType {
+        match self {
+            Self::MinimumActualTime | Self::MaximumActualTime | Self::Start | Self::End => {
+                StatusCodeValueType::Raw
+            }
+            Self::Interpolative => StatusCodeValueType::Interpolated,
+            _ => StatusCodeValueType::Calculated,
+        }
+    }
+}
+
+/// Get the aggregate configuration the server uses when the client requests
+/// the server defaults.
+pub fn default_aggregate_configuration() -> AggregateConfiguration {
+    AggregateConfiguration {
+        use_server_capabilities_defaults: true,
+        treat_uncertain_as_bad: true,
+        percent_data_bad: 100,
+        percent_data_good: 100,
+        use_sloped_extrapolation: false,
+    }
+}
+
+/// Validate an aggregate configuration requested by the client, returning the
+/// configuration that will actually be used.
+pub fn revise_aggregate_configuration(
+    config: AggregateConfiguration,
+) -> Result<AggregateConfiguration, StatusCode> {
+    if config.use_server_capabilities_defaults {
+        return Ok(default_aggregate_configuration());
+    }
+    // PercentDataGood must be at least 100 - PercentDataBad, so that an interval
+    // cannot be both good and bad at the same time.
+    if config.percent_data_bad > 100
+        || config.percent_data_good > 100
+        || (config.percent_data_bad as u16 + config.percent_data_good as u16) < 100
+    {
+        return Err(StatusCode::BadAggregateConfigurationRejected);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use opcua_types::{AggregateConfiguration, StatusCode};
+
+    use super::{default_aggregate_configuration, revise_aggregate_configuration};
+
+    #[test]
+    fn revise_configuration() {
+        let config = revise_aggregate_configuration(AggregateConfiguration {
+            use_server_capabilities_defaults: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(config, default_aggregate_configuration());
+
+        assert_eq!(
+            revise_aggregate_configuration(AggregateConfiguration {
+                use_server_capabilities_defaults: false,
+                percent_data_bad: 20,
+                percent_data_good: 50,
+                ..Default::default()
+            }),
+            Err(StatusCode::BadAggregateConfigurationRejected)
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::Duration;
+use opcua_types::{
+    DataValue, DateTime, HistoryData, NodeId, ReadProcessedDetails, StatusCode, TimestampsToReturn,
+};
+
+use crate::{node_manager::HistoryNode, ContinuationPoint};
+
+use super::{
+    calculate_aggregate, revise_aggregate_configuration, AggregateFunction, AggregateInterval,
+};
+
+/// Continuation point for processed history reads, the index of the next
+/// interval to calculate.
+struct ProcessedContinuationPoint {
+    next_interval: u64,
+}
+
+/// The processing intervals of a `ReadProcessed` request.
+struct Intervals {
+    start: DateTime,
+    end: DateTime,
+    interval_us: i64,
+    count: u64,
+}
+
+impl Intervals {
+    fn new(details: &ReadProcessedDetails) -> Result<Self, StatusCode> {
+        let (start, end) = (details.start_time, details.end_time);
+        if start.is_null()
+            || end.is_null()
+            || start == end
+            || !details.processing_interval.is_finite()
+            || details.processing_interval < 0.0
+        {
+            return Err(StatusCode::BadInvalidArgument);
+        }
+        let total_us = (end - start)
+            .num_microseconds()
+            .ok_or(StatusCode::BadInvalidArgument)?
+            .abs();
+        // A processing interval of zero means a single interval covering the whole range.
+        let interval_us = if details.processing_interval == 0.0 {
+            total_us
+        } else {
+            ((details.processing_interval * 1000.0) as i64).clamp(1, total_us)
+        };
+        let count = (total_us as u64).div_ceil(interval_us as u64);
+        Ok(Self {
+            start,
+            end,
+            interval_us,
+            count,
+        })
+    }
+
+    /// Get the bounds of interval `index`. If the end time is before the start time,
+    /// intervals are counted backwards from the start time.
+    fn get(&self, index: u64) -> (DateTime, DateTime) {
+        let length = Duration::microseconds(self.interval_us);
+        let offset = Duration::microseconds(self.interval_us.saturating_mul(index as i64));
+        if self.start < self.end {
+            let lo = self.start + offset;
+            (lo, (lo + length).min(self.end))
+        } else {
+            let hi = self.start - offset;
+            ((hi - length).max(self.end), hi)
+        }
+    }
+}
+
+fn process(
+    details: &ReadProcessedDetails,
+    aggregate_type: &NodeId,
+    data: &[DataValue],
+    stepped: bool,
+    first_interval: u64,
+    max_values: usize,
+) -> Result<(Vec<DataValue>, Option<u64>), StatusCode> {
+    let function = AggregateFunction::from_node_id(aggregate_type)
+        .ok_or(StatusCode::BadAggregateNotSupported)?;
+    let config = revise_aggregate_configuration(details.aggregate_configuration.clone())?;
+    let intervals = Intervals::new(details)?;
+
+    let last = if max_values == 0 {
+        intervals.count
+    } else {
+        intervals
+            .count
+            .min(first_interval.saturating_add(max_values as u64))
+    };
+    let values = (first_interval..last)
+        .map(|index| {
+            let (start, end) = intervals.get(index);
+            calculate_aggregate(
+                function,
+                &config,
+                &AggregateInterval {
+                    start,
+                    end,
+                    data,
+                    stepped,
+                    partial: (end - start).num_microseconds() != Some(intervals.interval_us),
+                },
+            )
+        })
+        .collect();
+
+    Ok((values, (last < intervals.count).then_some(last)))
+}
+
+/// Calculate the processed values for a `ReadProcessed` request on a single node.
+///
+/// `data` is the raw history of the node, ordered by timestamp. It must contain the
+/// raw values between the start and end time of the request, as well as the values
+/// just outside that range, which are used as bounding values. Any other values are ignored,
+/// so the entire history may be passed if it is available.
+///
+/// `stepped` should be set if the variable is stepped, meaning that it holds its
+/// value until the next raw value rather than changing linearly.
+pub fn calculate_processed(
+    details: &ReadProcessedDetails,
+    aggregate_type: &NodeId,
+    data: &[DataValue],
+    stepped: bool,
+) -> Result<Vec<DataValue>, StatusCode> {
+    process(details, aggregate_type, data, stepped, 0, 0).map(|(values, _)| values)
+}
+
+/// Read processed history for `node` from its raw history `data`, setting either
+/// a `HistoryData` result or an error status on the node.
+///
+/// This is the complete implementation of `ReadProcessed` for a single node, see
+/// [`calculate_processed`] for the requirements on `data`. At most `max_values` values
+/// are returned, if there are more, a continuation point is set on the node.
+/// A `max_values` of zero means no limit.
+pub fn read_processed(
+    details: &ReadProcessedDetails,
+    node: &mut HistoryNode,
+    data: &[DataValue],
+    stepped: bool,
+    timestamps_to_return: TimestampsToReturn,
+    max_values: usize,
+) {
+    let Some(aggregate_type) = node.aggregate_type().cloned() else {
+        node.set_status(StatusCode::BadAggregateListMismatch);
+        return;
+    };
+    let first_interval = match node.continuation_point() {
+        Some(cp) => match cp.get::<ProcessedContinuationPoint>() {
+            Some(cp) => cp.next_interval,
+            None => {
+                node.set_status(StatusCode::BadContinuationPointInvalid);
+                return;
+            }
+        },
+        None => 0,
+    };
+
+    match process(
+        details,
+        &aggregate_type,
+        data,
+        stepped,
+        first_interval,
+        max_values,
+    ) {
+        Ok((mut values, next)) => {
+            for value in &mut values {
+                match timestamps_to_return {
+                    TimestampsToReturn::Source => value.server_timestamp = None,
+                    TimestampsToReturn::Server => value.source_timestamp = None,
+                    TimestampsToReturn::Neither => {
+                        value.source_timestamp = None;
+                        value.server_timestamp = None;
+                    }
+                    _ => (),
+                }
+            }
+            node.set_next_continuation_point(next.map(|next_interval| {
+                ContinuationPoint::new(Box::new(ProcessedContinuationPoint { next_interval }))
+            }));
+            node.set_result(HistoryData {
+                data_values: Some(values),
+            });
+            node.set_status(StatusCode::Good);
+        }
+        Err(e) => node.set_status(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use opcua_types::{
+        AggregateConfiguration, DataValue, DateTime, NodeId, ObjectId, ReadProcessedDetails,
+        StatusCode, Variant,
+    };
+
+    use super::{calculate_processed, process};
+
+    #[test]
+    fn processed_intervals() {
+        let t0 = DateTime::now();
+        let at = |ms| t0 + Duration::milliseconds(ms);
+        // One value per second, 0, 1, 2, ...
+        let data: Vec<_> = (0..10)
+            .map(|i| DataValue::new_at(i as f64, at(i * 1000)))
+            .collect();
+        let details = ReadProcessedDetails {
+            start_time: at(500),
+            end_time: at(5000),
+            processing_interval: 2000.0,
+            aggregate_type: None,
+            aggregate_configuration: AggregateConfiguration {
+                use_server_capabilities_defaults: true,
+                ..Default::default()
+            },
+        };
+        let average: NodeId = ObjectId::AggregateFunction_Average.into();
+
+        let res = calculate_processed(&details, &average, &data, false).unwrap();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].source_timestamp, Some(at(500)));
+        assert_eq!(res[0].value, Some(Variant::Double(1.5)));
+        assert_eq!(res[1].value, Some(Variant::Double(3.5)));
+        // The last interval is shorter than the processing interval.
+        assert!(!res[1].status().partial());
+        assert!(res[2].status().partial());
+
+        let interpolative: NodeId = ObjectId::AggregateFunction_Interpolative.into();
+        let res = calculate_processed(&details, &interpolative, &data, false).unwrap();
+        assert_eq!(res[0].value, Some(Variant::Double(0.5)));
+        assert_eq!(res[2].value, Some(Variant::Double(4.5)));
+
+        // Reverse order
+        let reverse = ReadProcessedDetails {
+            start_time: at(5000),
+            end_time: at(500),
+            ..details.clone()
+        };
+        let res = calculate_processed(&reverse, &average, &data, false).unwrap();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].source_timestamp, Some(at(3000)));
+        assert_eq!(res[0].value, Some(Variant::Double(3.5)));
+        assert_eq!(res[2].source_timestamp, Some(at(500)));
+
+        // Continuation
+        let (res, next) = process(&details, &average, &data, false, 0, 2).unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(next, Some(2));
+        let (res, next) = process(&details, &average, &data, false, 2, 2).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(next, None);
+
+        assert_eq!(
+            calculate_processed(
+                &details,
+                &ObjectId::AggregateFunction_AnnotationCount.into(),
+                &data,
+                false
+            )
+            .unwrap_err(),
+            StatusCode::BadAggregateNotSupported
+        );
+        let empty = ReadProcessedDetails {
+            end_time: at(500),
+            ..details.clone()
+        };
+        assert_eq!(
+            calculate_processed(&empty, &average, &data, false).unwrap_err(),
+            StatusCode::BadInvalidArgument
+        );
+    }
+}
@@ -80,8 +80,9 @@ impl UserToken {
 /// that these methods should load and store any information you need to check user
 /// access level down the line.
 ///
-/// This is currently the only way to restrict access to core resources. For resources in
-/// your own custom node managers you are free to use whatever access regime you want.
+/// In addition, users may be granted roles, which are checked against the `RolePermissions`
+/// of nodes, see [crate::roles]. For resources in your own custom node managers you are
+/// free to use whatever access regime you want.
 pub trait AuthManager: Send + Sync + 'static {
     /// Validate whether an anonymous user is allowed to access the given endpoint.
     /// This does not return a user token, all anonymous users share the same special token.
@@ -135,6 +136,15 @@ pub trait AuthManager: Send + Sync + 'static {
         true
     }
 
+    /// Return the IDs of the roles granted to the user given by `token`. These are
+    /// granted in addition to any roles matched by the identity mapping rules in
+    /// the server [RoleSet](crate::roles::RoleSet).
+    ///
+    /// Roles are resolved once when the session is activated.
+    fn user_roles(&self, token: &UserToken) -> Vec<NodeId> {
+        Vec::new()
+    }
+
     /// Return the valid user token policies for the given endpoint.
     /// Only valid tokens will be passed to the authenticator.
     fn user_token_policies(&self, endpoint: &ServerEndpoint) -> Vec<UserTokenPolicy>;
@@ -268,10 +278,12 @@ impl AuthManager for DefaultAuthenticator {
             });
         }
         // User pass policy
-        if endpoint.user_token_ids.iter().any(|id| {
-            id != ANONYMOUS_USER_TOKEN_ID
-                && self.users.get(id).is_some_and(|token| token.is_user_pass())
-        }) {
+        if is_user_pass_allowed(endpoint)
+            && endpoint.user_token_ids.iter().any(|id| {
+                id != ANONYMOUS_USER_TOKEN_ID
+                    && self.users.get(id).is_some_and(|token| token.is_user_pass())
+            })
+        {
             // The endpoint may set a password security policy
             user_identity_tokens.push(UserTokenPolicy {
                 policy_id: user_pass_security_policy_id(endpoint),
@@ -315,6 +327,8 @@ pub fn user_pass_security_policy_id(endpoint: &ServerEndpoint) -> UAString {
         SecurityPolicy::Aes128Sha256RsaOaep | SecurityPolicy::Aes256Sha256RsaPss => {
             POLICY_ID_USER_PASS_RSA_OAEP
         }
+        // Passwords are not encrypted with ECC keys, see `user_pass_security_policy_uri`
+        SecurityPolicy::EccNistP256 | SecurityPolicy::EccNistP384 => POLICY_ID_USER_PASS_NONE,
         _ => {
             panic!()
         }
@@ -322,9 +336,25 @@ pub fn user_pass_security_policy_id(endpoint: &ServerEndpoint) -> UAString {
     .into()
 }
 
+/// Get whether username and password tokens may be used on the given endpoint.
+///
+/// Encrypting passwords with the EccEncryptedSecret is not supported, so on endpoints
+/// with an ECC password security policy the password is only protected by the secure
+/// channel, which must then encrypt messages.
+pub fn is_user_pass_allowed(endpoint: &ServerEndpoint) -> bool {
+    !endpoint.password_security_policy().is_ecc()
+        || endpoint.message_security_mode() == MessageSecurityMode::SignAndEncrypt
+}
+
 /// Get the username and password policy URI for the given endpioint.
-pub fn user_pass_security_policy_uri(_endpoint: &ServerEndpoint) -> UAString {
+pub fn user_pass_security_policy_uri(endpoint: &ServerEndpoint) -> UAString {
     // TODO we could force the security policy uri for passwords to be something other than the default
     //  here to ensure they're secure even when the endpoint's security policy is None.
-    UAString::null()
+    if endpoint.password_security_policy().is_ecc() {
+        // Encrypting passwords with the EccEncryptedSecret is not supported, so on ECC endpoints
+        // the password is only protected by the secure channel, see `is_user_pass_allowed`.
+        UAString::from(SecurityPolicy::None.to_uri())
+    } else {
+        UAString::null()
+    }
 }
@@ -3,14 +3,18 @@ use std::{path::PathBuf, sync::Arc};
 use log::warn;
 use tokio_util::sync::CancellationToken;
 
-use crate::{constants, node_manager::TypeTreeForUser};
-use opcua_core::config::Config;
-use opcua_crypto::SecurityPolicy;
+use crate::{
+    constants, discovery::MdnsResponder, node_manager::TypeTreeForUser,
+    redundancy::SessionReplication,
+};
+use opcua_core::{config::Config, sync::RwLock};
+use opcua_crypto::{CertificateStore, SecurityPolicy};
 use opcua_types::{BuildInfo, MessageSecurityMode, TypeLoader, TypeLoaderCollection};
 
 use super::{
-    authenticator::AuthManager, node_manager::NodeManagerBuilder, Limits, Server, ServerConfig,
-    ServerEndpoint, ServerHandle, ServerUserToken, ANONYMOUS_USER_TOKEN_ID,
+    authenticator::AuthManager, node_manager::NodeManagerBuilder, Limits, RedundancyConfig,
+    ReverseConnectTarget, Server, ServerConfig, ServerEndpoint, ServerHandle, ServerUserToken,
+    ANONYMOUS_USER_TOKEN_ID,
 };
 
 /// Server builder, used to configure the server programatically,
@@ -24,6 +28,9 @@ pub struct ServerBuilder {
     pub(crate) type_loaders: TypeLoaderCollection,
     pub(crate) token: CancellationToken,
     pub(crate) build_info: BuildInfo,
+    pub(crate) mdns_responder: Option<Arc<dyn MdnsResponder>>,
+    pub(crate) session_replication: Option<Arc<dyn SessionReplication>>,
+    pub(crate) certificate_store: Option<Arc<RwLock<dyn CertificateStore>>>,
 }
 
 impl Default for ServerBuilder {
@@ -36,6 +43,9 @@ impl Default for ServerBuilder {
             type_tree_getter: None,
             build_info: BuildInfo::default(),
             type_loaders: TypeLoaderCollection::new(),
+            mdns_responder: None,
+            session_replication: None,
+            certificate_store: None,
         };
         #[cfg(feature = "generated-address-space")]
         {
@@ -271,6 +281,20 @@ impl ServerBuilder {
         self
     }
 
+    /// Set a custom certificate store, holding the server's own certificate and private key,
+    /// and the trusted client certificates.
+    ///
+    /// If this is not set, the server uses a certificate store in the pki directory.
+    /// If it is, the pki directory, certificate path and private key path are ignored, and no
+    /// sample key pair is created.
+    pub fn with_certificate_store(
+        mut self,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
+    ) -> Self {
+        self.certificate_store = Some(certificate_store);
+        self
+    }
+
     /// Set a custom type tree getter. Most servers do not need to touch this.
     ///
     /// The type tree getter gets a type tree for a specific user, letting you have different type trees
@@ -343,6 +367,13 @@ impl ServerBuilder {
         self
     }
 
+    /// Accept client certificates whose issuers have no current revocation list.
+    /// Certificates revoked by a known revocation list are still rejected.
+    pub fn allow_unknown_revocation(mut self, allow_unknown_revocation: bool) -> Self {
+        self.config.certificate_validation.allow_unknown_revocation = allow_unknown_revocation;
+        self
+    }
+
     /// PKI folder, either absolute or relative to executable.
     pub fn pki_dir(mut self, pki_dir: impl Into<PathBuf>) -> Self {
         self.config.pki_dir = pki_dir.into();
@@ -418,6 +449,75 @@ impl ServerBuilder {
         self
     }
 
+    /// Add a client the server should connect to using reverse connect.
+    /// The server will open a connection to the client, and send a `ReverseHello`
+    /// message, after which the client may use the connection as normal.
+    pub fn add_reverse_connect_target(mut self, target: impl Into<ReverseConnectTarget>) -> Self {
+        self.config.reverse_connect_targets.push(target.into());
+        self
+    }
+
+    /// Time to wait between failed attempts to open a reverse connection, in milliseconds.
+    pub fn reverse_connect_interval_ms(mut self, interval: u64) -> Self {
+        self.config.reverse_connect_interval_ms = interval;
+        self
+    }
+
+    /// Run the server as a local discovery server. Other servers may then register
+    /// themselves with this server, and are returned from `FindServers`.
+    pub fn local_discovery_server(mut self, enabled: bool) -> Self {
+        self.config.local_discovery_server = enabled;
+        self
+    }
+
+    /// Time in milliseconds before a registration with the local discovery server
+    /// expires, if it is not renewed.
+    pub fn registration_timeout_ms(mut self, timeout: u64) -> Self {
+        self.config.registration_timeout_ms = timeout;
+        self
+    }
+
+    /// Set the multicast DNS responder used for multicast discovery. With this, the
+    /// server announces itself on the network, keeps a list of servers announced by
+    /// others, and answers `FindServersOnNetwork`.
+    ///
+    /// If the server is running as a local discovery server, servers registering with an
+    /// mDNS configuration through `RegisterServer2` are announced on their behalf.
+    pub fn with_mdns_responder(mut self, responder: Arc<dyn MdnsResponder>) -> Self {
+        self.mdns_responder = Some(responder);
+        self
+    }
+
+    /// Name used to announce the server over multicast DNS. This must be unique
+    /// on the network. Defaults to the application name.
+    pub fn mdns_server_name(mut self, name: impl Into<String>) -> Self {
+        self.config.mdns_server_name = Some(name.into());
+        self
+    }
+
+    /// Add a capability identifier announced over multicast DNS, for example `DA` for
+    /// servers providing current data. See Part 12 for the list of identifiers.
+    pub fn add_server_capability(mut self, capability: impl Into<String>) -> Self {
+        self.config.server_capabilities.push(capability.into());
+        self
+    }
+
+    /// Set the redundant server set the server belongs to. The server populates
+    /// `Server.ServerRedundancy` from this, and monitors its peers to compute
+    /// the `ServiceLevel`.
+    pub fn redundancy(mut self, redundancy: RedundancyConfig) -> Self {
+        self.config.redundancy = redundancy;
+        self
+    }
+
+    /// Share sessions with the other servers in the redundant server set, letting
+    /// clients reactivate their session on a different server after failing over.
+    /// Subscriptions are not shared, and must be recreated by the client.
+    pub fn with_session_replication(mut self, replication: Arc<dyn SessionReplication>) -> Self {
+        self.session_replication = Some(replication);
+        self
+    }
+
     /// Interval in milliseconds between each time the subscriptions are polled.
     pub fn subscription_poll_interval_ms(mut self, interval: u64) -> Self {
         self.config.subscription_poll_interval_ms = interval;
@@ -0,0 +1,771 @@
+//! Remote management of the server's certificate and trust list, using the push model
+//! described in Part 12 of the standard.
+//!
+//! The `ServerConfiguration` object in the address space lets a client with the
+//! `SecurityAdmin` role create a signing request for a new application instance certificate,
+//! update the certificate once it has been signed by a certificate authority, and read
+//! or replace the trust list of the server through the `TrustList` file object.
+//!
+//! Changes are written to the server's [CertificateStore], and a new certificate is used
+//! by secure channels and sessions created after `ApplyChanges` is called, without restarting
+//! the server. These methods are handled by the core node manager.
+
+use std::sync::Arc;
+
+use log::{error, info};
+use opcua_core::{
+    sync::{Mutex, RwLock},
+    trace_lock, trace_read_lock, trace_write_lock,
+};
+use opcua_crypto::{
+    CertificateRevocationList, CertificateSigningRequest, CertificateStore, EccCurve, KeySize,
+    PrivateKey, X509,
+};
+use opcua_types::{
+    BinaryDecodable, BinaryEncodable, ByteString, MessageSecurityMode, MethodId, NodeId, ObjectId,
+    ObjectTypeId, OpenFileMode, PermissionType, ReferenceTypeId, StatusCode, TrustListDataType,
+    TrustListMasks, UAString, VariableId, Variant, VariantScalarTypeId, VariantTypeId,
+};
+
+use crate::{
+    address_space::{AddressSpace, NodeBase, NodeType},
+    info::ServerInfo,
+    load_method_args,
+    node_manager::{FileHandle, FileHandles, MethodCall, RequestContext},
+    roles::security_admin_permissions,
+};
+
+/// The only private key format supported by `UpdateCertificate`.
+const PEM_KEY_FORMAT: &str = "PEM";
+
+/// Methods on the server configuration object and its default application group.
+const SERVER_CONFIGURATION_METHODS: [MethodId; 6] = [
+    MethodId::ServerConfiguration_CreateSigningRequest,
+    MethodId::ServerConfiguration_UpdateCertificate,
+    MethodId::ServerConfiguration_ApplyChanges,
+    MethodId::ServerConfiguration_CancelChanges,
+    MethodId::ServerConfiguration_GetRejectedList,
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_GetRejectedList,
+];
+
+/// Methods on the trust list of the default application group. The last four
+/// are only defined on `TrustListType`, and are referenced from the trust list instance
+/// when the address space is initialized.
+const TRUST_LIST_METHODS: [MethodId; 10] = [
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Open,
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Close,
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Read,
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Write,
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_GetPosition,
+    MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_SetPosition,
+    MethodId::TrustListType_OpenWithMasks,
+    MethodId::TrustListType_CloseAndUpdate,
+    MethodId::TrustListType_AddCertificate,
+    MethodId::TrustListType_RemoveCertificate,
+];
+
+/// File open mode for reading, see Part 5, 9.2.1.
+const OPEN_MODE_READ: u8 = OpenFileMode::Read as u8;
+/// File open mode for writing, erasing the existing contents, see Part 5, 9.2.1.
+const OPEN_MODE_WRITE_ERASE_EXISTING: u8 =
+    OpenFileMode::Write as u8 | OpenFileMode::EraseExisting as u8;
+
+/// A certificate and private key given to `UpdateCertificate`, waiting for `ApplyChanges`.
+struct PendingCertificate {
+    cert: X509,
+    pkey: PrivateKey,
+    issuer_certs: Vec<X509>,
+}
+
+/// The contents of an open trust list file.
+struct TrustListFile {
+    data: Vec<u8>,
+    position: usize,
+}
+
+#[derive(Default)]
+struct CertificateManagerState {
+    /// Private key generated by `CreateSigningRequest`, to be used with the certificate
+    /// issued for it.
+    regenerated_pkey: Option<PrivateKey>,
+    /// Certificate waiting to be applied.
+    pending: Option<PendingCertificate>,
+    /// Open trust list files.
+    files: FileHandles<TrustListFile>,
+}
+
+/// Manager for the `ServerConfiguration` object, applying changes made through it
+/// to the server's certificate store.
+pub struct CertificateManager {
+    certificate_store: Arc<RwLock<dyn CertificateStore>>,
+    state: Mutex<CertificateManagerState>,
+}
+
+impl CertificateManager {
+    /// Create a new certificate manager applying changes to `certificate_store`.
+    pub fn new(certificate_store: Arc<RwLock<dyn CertificateStore>>) -> Self {
+        Self {
+            certificate_store,
+            state: Mutex::new(CertificateManagerState::default()),
+        }
+    }
+
+    /// Get the certificate store of the server.
+    pub fn certificate_store(&self) -> &Arc<RwLock<dyn CertificateStore>> {
+        &self.certificate_store
+    }
+
+    /// Get whether `method` is one of the methods handled by the certificate manager.
+    pub(crate) fn is_certificate_method(method: MethodId) -> bool {
+        SERVER_CONFIGURATION_METHODS.contains(&method) || TRUST_LIST_METHODS.contains(&method)
+    }
+
+    /// Make the server configuration methods executable, restrict them to the
+    /// `SecurityAdmin` role, and add the trust list methods only defined on the type
+    /// to the trust list of the default application group.
+    pub(crate) fn init_address_space(address_space: &mut AddressSpace) {
+        for method in SERVER_CONFIGURATION_METHODS
+            .iter()
+            .chain(&TRUST_LIST_METHODS)
+        {
+            if let Some(NodeType::Method(m)) = address_space.find_mut(*method) {
+                m.set_executable(true);
+                m.set_user_executable(true);
+                m.set_role_permissions(Some(security_admin_permissions(PermissionType::Call)));
+            }
+        }
+        for method in &TRUST_LIST_METHODS[6..] {
+            address_space.insert_reference(
+                &trust_list_id(),
+                &(*method).into(),
+                ReferenceTypeId::HasComponent,
+            );
+        }
+    }
+
+    /// Get the value of a variable on the server configuration object, if it is managed
+    /// by the certificate manager.
+    pub(crate) fn read_value(&self, info: &ServerInfo, variable: VariableId) -> Option<Variant> {
+        let v = match variable {
+            VariableId::ServerConfiguration_SupportedPrivateKeyFormats => {
+                vec![UAString::from(PEM_KEY_FORMAT)].into()
+            }
+            // The trust list is not limited in size.
+            VariableId::ServerConfiguration_MaxTrustListSize => 0u32.into(),
+            VariableId::ServerConfiguration_MulticastDnsEnabled => {
+                info.multicast_discovery.is_some().into()
+            }
+            VariableId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_CertificateTypes => {
+                let pkey = info.server_pkey.load();
+                let types: Vec<NodeId> = pkey
+                    .as_deref()
+                    .map(|pkey| vec![certificate_type(pkey).into()])
+                    .unwrap_or_default();
+                types.into()
+            }
+            VariableId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Size => {
+                (self.encode_trust_list(info, TrustListMasks::All as u32).len() as u64).into()
+            }
+            VariableId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_OpenCount => {
+                (trace_lock!(self.state).files.open_count(&trust_list_id()) as u16).into()
+            }
+            VariableId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Writable
+            | VariableId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_UserWritable => {
+                true.into()
+            }
+            _ => return None,
+        };
+        Some(v)
+    }
+
+    /// Close the trust list handles opened by a session that has been closed.
+    pub(crate) fn session_closed(&self, session_id: u32) {
+        trace_lock!(self.state).files.remove_session(session_id);
+    }
+
+    /// Call one of the methods on the server configuration object or its trust list.
+    pub(crate) fn call(
+        &self,
+        context: &RequestContext,
+        call: &mut MethodCall,
+    ) -> Result<(), StatusCode> {
+        let Ok(id) = call.method_id().as_method_id() else {
+            return Err(StatusCode::BadMethodInvalid);
+        };
+        // Part 12 requires certificates and keys to only be sent over encrypted channels.
+        if context.security_mode != MessageSecurityMode::SignAndEncrypt {
+            return Err(StatusCode::BadSecurityModeInsufficient);
+        }
+
+        let info = &context.info;
+        let outputs: Vec<Variant> = match id {
+            MethodId::ServerConfiguration_CreateSigningRequest => {
+                let (group_id, type_id, subject_name, regenerate_pkey, _nonce) =
+                    load_method_args!(call, NodeId, NodeId, String, Boolean, ByteString)?;
+                let csr = self.create_signing_request(
+                    info,
+                    &group_id,
+                    &type_id,
+                    &subject_name,
+                    regenerate_pkey,
+                )?;
+                vec![csr.into()]
+            }
+            MethodId::ServerConfiguration_UpdateCertificate => {
+                let (group_id, type_id, cert) =
+                    load_method_args!(call, NodeId, NodeId, ByteString)?;
+                let issuer_certs = match call.arguments().get(3) {
+                    Some(Variant::Empty) | None => Vec::new(),
+                    Some(v) => v
+                        .clone()
+                        .try_cast_to::<Vec<ByteString>>()
+                        .map_err(|_| StatusCode::BadInvalidArgument)?,
+                };
+                let (pkey_format, pkey) = match call.arguments().get(4..6) {
+                    Some([pkey_format, pkey]) => (
+                        pkey_format
+                            .clone()
+                            .try_cast_to::<UAString>()
+                            .map_err(|_| StatusCode::BadInvalidArgument)?,
+                        pkey.clone()
+                            .try_cast_to::<ByteString>()
+                            .map_err(|_| StatusCode::BadInvalidArgument)?,
+                    ),
+                    _ => return Err(StatusCode::BadArgumentsMissing),
+                };
+                self.update_certificate(
+                    info,
+                    &group_id,
+                    &type_id,
+                    &cert,
+                    &issuer_certs,
+                    &pkey_format,
+                    &pkey,
+                )?;
+                // The new certificate is not used until the changes are applied.
+                vec![true.into()]
+            }
+            MethodId::ServerConfiguration_ApplyChanges => {
+                self.apply_changes(info)?;
+                Vec::new()
+            }
+            MethodId::ServerConfiguration_CancelChanges => {
+                let mut state = trace_lock!(self.state);
+                state.pending = None;
+                state.regenerated_pkey = None;
+                Vec::new()
+            }
+            MethodId::ServerConfiguration_GetRejectedList
+            | MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_GetRejectedList => {
+                let rejected: Vec<ByteString> = trace_read_lock!(self.certificate_store)
+                    .rejected_certs()
+                    .iter()
+                    .map(|c| c.as_byte_string())
+                    .collect();
+                vec![rejected.into()]
+            }
+            MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Open => {
+                let mode = load_method_args!(call, Byte)?;
+                let handle = match mode {
+                    OPEN_MODE_READ => self.open_trust_list(
+                        info,
+                        context.session_id,
+                        TrustListMasks::All as u32,
+                    )?,
+                    OPEN_MODE_WRITE_ERASE_EXISTING => {
+                        self.open_file(context.session_id, mode, Vec::new())?
+                    }
+                    _ => return Err(StatusCode::BadInvalidArgument),
+                };
+                vec![handle.into()]
+            }
+            MethodId::TrustListType_OpenWithMasks => {
+                let masks = load_method_args!(call, UInt32)?;
+                let handle = self.open_trust_list(info, context.session_id, masks)?;
+                vec![handle.into()]
+            }
+            MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Close => {
+                let handle = load_method_args!(call, UInt32)?;
+                self.close_file(context.session_id, handle)?;
+                Vec::new()
+            }
+            MethodId::TrustListType_CloseAndUpdate => {
+                let handle = load_method_args!(call, UInt32)?;
+                let file = self.close_file(context.session_id, handle)?;
+                if !file.is_writable() {
+                    return Err(StatusCode::BadInvalidState);
+                }
+                self.update_trust_list(info, &file.file.data)?;
+                // Trust list changes take effect immediately.
+                vec![false.into()]
+            }
+            MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Read => {
+                let (handle, length) = load_method_args!(call, UInt32, Int32)?;
+                let length = usize::try_from(length).map_err(|_| StatusCode::BadInvalidArgument)?;
+                let data = self.with_file(context.session_id, handle, |h| {
+                    if h.is_writable() {
+                        return Err(StatusCode::BadInvalidState);
+                    }
+                    let start = h.file.position.min(h.file.data.len());
+                    let end = start.saturating_add(length).min(h.file.data.len());
+                    h.file.position = end;
+                    Ok(ByteString::from(&h.file.data[start..end]))
+                })?;
+                vec![data.into()]
+            }
+            MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_Write => {
+                let (handle, data) = load_method_args!(call, UInt32, ByteString)?;
+                self.with_file(context.session_id, handle, |h| {
+                    if !h.is_writable() {
+                        return Err(StatusCode::BadInvalidState);
+                    }
+                    let bytes = data.as_ref();
+                    let end = (h.file.position + bytes.len()).min(h.file.data.len());
+                    h.file.data.splice(h.file.position..end, bytes.iter().copied());
+                    h.file.position += bytes.len();
+                    Ok(())
+                })?;
+                Vec::new()
+            }
+            MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_GetPosition => {
+                let handle = load_method_args!(call, UInt32)?;
+                let position =
+                    self.with_file(context.session_id, handle, |h| Ok(h.file.position as u64))?;
+                vec![position.into()]
+            }
+            MethodId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList_SetPosition => {
+                let (handle, position) = load_method_args!(call, UInt32, UInt64)?;
+                self.with_file(context.session_id, handle, |h| {
+                    h.file.position = (position as usize).min(h.file.data.len());
+                    Ok(())
+                })?;
+                Vec::new()
+            }
+            MethodId::TrustListType_AddCertificate => {
+                let (cert, is_trusted) = load_method_args!(call, ByteString, Boolean)?;
+                self.add_certificate(&cert, is_trusted)?;
+                Vec::new()
+            }
+            MethodId::TrustListType_RemoveCertificate => {
+                let (thumbprint, is_trusted) = load_method_args!(call, String, Boolean)?;
+                self.remove_certificate(thumbprint.as_ref(), is_trusted)?;
+                Vec::new()
+            }
+            _ => return Err(StatusCode::BadMethodInvalid),
+        };
+
+        call.set_outputs(outputs);
+        call.set_status(StatusCode::Good);
+        Ok(())
+    }
+
+    fn create_signing_request(
+        &self,
+        info: &ServerInfo,
+        group_id: &NodeId,
+        type_id: &NodeId,
+        subject_name: &UAString,
+        regenerate_pkey: bool,
+    ) -> Result<ByteString, StatusCode> {
+        check_certificate_group(group_id)?;
+        let (Some(cert), Some(pkey)) = (
+            info.server_certificate.load_full(),
+            info.server_pkey.load_full(),
+        ) else {
+            error!("Cannot create a signing request, the server has no certificate");
+            return Err(StatusCode::BadInvalidState);
+        };
+        let curve = requested_curve(type_id, &pkey)?;
+
+        let new_pkey = if regenerate_pkey {
+            Some(match curve {
+                Some(curve) => PrivateKey::new_ecc(curve),
+                None => PrivateKey::new(pkey.bit_length() as u32).map_err(|e| {
+                    error!("Failed to generate RSA private key: {e}");
+                    StatusCode::BadInternalError
+                })?,
+            })
+        } else if curve != pkey.ecc_curve() {
+            // A different kind of key is needed for the requested certificate type.
+            return Err(StatusCode::BadInvalidArgument);
+        } else {
+            None
+        };
+
+        let csr = CertificateSigningRequest::for_cert(
+            &cert,
+            new_pkey.as_ref().unwrap_or(&pkey),
+            subject_name.value().as_deref().filter(|s| !s.is_empty()),
+        )
+        .and_then(|csr| csr.to_der().map_err(|e| e.to_string()))
+        .map_err(|e| {
+            error!("Failed to create certificate signing request: {e}");
+            StatusCode::BadInvalidArgument
+        })?;
+
+        if new_pkey.is_some() {
+            trace_lock!(self.state).regenerated_pkey = new_pkey;
+        }
+        Ok(ByteString::from(csr))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update_certificate(
+        &self,
+        info: &ServerInfo,
+        group_id: &NodeId,
+        type_id: &NodeId,
+        cert: &ByteString,
+        issuer_certs: &[ByteString],
+        pkey_format: &UAString,
+        pkey: &ByteString,
+    ) -> Result<(), StatusCode> {
+        check_certificate_group(group_id)?;
+        let cert = X509::from_byte_string(cert).map_err(|_| StatusCode::BadCertificateInvalid)?;
+        let issuer_certs = issuer_certs
+            .iter()
+            .map(X509::from_byte_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| StatusCode::BadCertificateInvalid)?;
+
+        let mut state = trace_lock!(self.state);
+        let pkey = if pkey.is_null_or_empty() {
+            // The certificate is issued for the key generated by `CreateSigningRequest`,
+            // or for the current key if no key was generated.
+            match state.regenerated_pkey.clone() {
+                Some(pkey) => pkey,
+                None => info
+                    .server_pkey
+                    .load_full()
+                    .map(|pkey| (*pkey).clone())
+                    .ok_or(StatusCode::BadInvalidState)?,
+            }
+        } else if pkey_format.as_ref() == PEM_KEY_FORMAT {
+            PrivateKey::from_pem(pkey.as_ref()).map_err(|_| StatusCode::BadInvalidArgument)?
+        } else {
+            error!("Unsupported private key format {}", pkey_format.as_ref());
+            return Err(StatusCode::BadNotSupported);
+        };
+
+        if requested_curve(type_id, &pkey)? != pkey.ecc_curve() {
+            return Err(StatusCode::BadCertificateInvalid);
+        }
+        if !cert.is_public_key_of(&pkey) {
+            error!("The new certificate does not match the private key of the server");
+            return Err(StatusCode::BadSecurityChecksFailed);
+        }
+        cert.is_time_valid(&chrono::Utc::now())?;
+        if !issuer_certs.is_empty() && !issuer_certs.iter().any(|i| cert.is_issued_by(i)) {
+            error!("The new certificate is not issued by any of the issuer certificates");
+            return Err(StatusCode::BadSecurityChecksFailed);
+        }
+
+        state.pending = Some(PendingCertificate {
+            cert,
+            pkey,
+            issuer_certs,
+        });
+        Ok(())
+    }
+
+    fn apply_changes(&self, info: &ServerInfo) -> Result<(), StatusCode> {
+        let mut state = trace_lock!(self.state);
+        let Some(pending) = state.pending.take() else {
+            return Ok(());
+        };
+        state.regenerated_pkey = None;
+
+        {
+            let mut store = trace_write_lock!(self.certificate_store);
+            store
+                .store_own_cert_and_pkey(&pending.cert, &pending.pkey)
+                .map_err(|e| {
+                    error!("Failed to store the new server certificate: {e}");
+                    StatusCode::BadInternalError
+                })?;
+            if !pending.issuer_certs.is_empty() {
+                let mut issuer_certs = store.issuer_certs();
+                for cert in pending.issuer_certs {
+                    if !issuer_certs
+                        .iter()
+                        .any(|c| c.thumbprint() == cert.thumbprint())
+                    {
+                        issuer_certs.push(cert);
+                    }
+                }
+                store.set_issuer_certs(&issuer_certs).map_err(|e| {
+                    error!("Failed to store the issuer certificates: {e}");
+                    StatusCode::BadInternalError
+                })?;
+            }
+        }
+
+        info!(
+            "Applying new server certificate {}",
+            pending.cert.thumbprint().as_hex_string()
+        );
+        info.server_pkey.store(Some(Arc::new(pending.pkey)));
+        info.server_certificate.store(Some(Arc::new(pending.cert)));
+        Ok(())
+    }
+
+    fn open_trust_list(
+        &self,
+        info: &ServerInfo,
+        session_id: u32,
+        masks: u32,
+    ) -> Result<u32, StatusCode> {
+        let data = self.encode_trust_list(info, masks);
+        self.open_file(session_id, OPEN_MODE_READ, data)
+    }
+
+    fn open_file(&self, session_id: u32, mode: u8, data: Vec<u8>) -> Result<u32, StatusCode> {
+        let mut state = trace_lock!(self.state);
+        state.files.check_open(&trust_list_id(), mode)?;
+        Ok(state.files.insert(
+            trust_list_id(),
+            session_id,
+            mode,
+            TrustListFile { data, position: 0 },
+        ))
+    }
+
+    fn close_file(
+        &self,
+        session_id: u32,
+        handle: u32,
+    ) -> Result<FileHandle<TrustListFile>, StatusCode> {
+        trace_lock!(self.state)
+            .files
+            .remove(&trust_list_id(), session_id, handle)
+    }
+
+    fn with_file<T>(
+        &self,
+        session_id: u32,
+        handle: u32,
+        f: impl FnOnce(&mut FileHandle<TrustListFile>) -> Result<T, StatusCode>,
+    ) -> Result<T, StatusCode> {
+        let mut state = trace_lock!(self.state);
+        f(state.files.get_mut(&trust_list_id(), session_id, handle)?)
+    }
+
+    /// Get whether the trust list is open for writing, in which case it cannot
+    /// be changed by other methods.
+    fn is_open_for_writing(&self) -> bool {
+        trace_lock!(self.state).files.any(|h| h.is_writable())
+    }
+
+    /// Get the lists of the trust list selected by `masks`, encoded as a `TrustListDataType`,
+    /// which is the content of the trust list file.
+    fn encode_trust_list(&self, info: &ServerInfo, masks: u32) -> Vec<u8> {
+        let masks = masks & TrustListMasks::All as u32;
+        let (trusted_certs, issuer_certs, crls) = {
+            let store = trace_read_lock!(self.certificate_store);
+            (store.trusted_certs(), store.issuer_certs(), store.crls())
+        };
+        let (trusted_crls, issuer_crls) = split_crls(crls, &trusted_certs);
+
+        let certs = |mask: TrustListMasks, certs: &[X509]| {
+            (masks & mask as u32 != 0).then(|| certs.iter().map(|c| c.as_byte_string()).collect())
+        };
+        let crls = |mask: TrustListMasks, crls: &[CertificateRevocationList]| {
+            (masks & mask as u32 != 0).then(|| {
+                crls.iter()
+                    .filter_map(|c| c.to_der().ok())
+                    .map(ByteString::from)
+                    .collect()
+            })
+        };
+        let trust_list = TrustListDataType {
+            specified_lists: masks,
+            trusted_certificates: certs(TrustListMasks::TrustedCertificates, &trusted_certs),
+            trusted_crls: crls(TrustListMasks::TrustedCrls, &trusted_crls),
+            issuer_certificates: certs(TrustListMasks::IssuerCertificates, &issuer_certs),
+            issuer_crls: crls(TrustListMasks::IssuerCrls, &issuer_crls),
+        };
+        let ctx = info.initial_encoding_context();
+        trust_list.encode_to_vec(&ctx.context())
+    }
+
+    /// Replace the lists of the trust list specified in the written trust list file.
+    fn update_trust_list(&self, info: &ServerInfo, data: &[u8]) -> Result<(), StatusCode> {
+        let ctx = info.initial_encoding_context();
+        let trust_list = TrustListDataType::decode(&mut &data[..], &ctx.context())
+            .map_err(|_| StatusCode::BadInvalidArgument)?;
+        let masks = trust_list.specified_lists;
+        let is_specified = |mask: TrustListMasks| masks & mask as u32 != 0;
+
+        let parse_certs = |certs: Option<Vec<ByteString>>| {
+            certs
+                .unwrap_or_default()
+                .iter()
+                .map(X509::from_byte_string)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| StatusCode::BadCertificateInvalid)
+        };
+        let parse_crls = |crls: Option<Vec<ByteString>>| {
+            crls.unwrap_or_default()
+                .iter()
+                .map(|c| CertificateRevocationList::from_der(c.as_ref()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| StatusCode::BadCertificateInvalid)
+        };
+        let new_trusted_certs = parse_certs(trust_list.trusted_certificates)?;
+        let new_issuer_certs = parse_certs(trust_list.issuer_certificates)?;
+        let new_trusted_crls = parse_crls(trust_list.trusted_crls)?;
+        let new_issuer_crls = parse_crls(trust_list.issuer_crls)?;
+
+        let mut store = trace_write_lock!(self.certificate_store);
+        let trusted_certs = if is_specified(TrustListMasks::TrustedCertificates) {
+            new_trusted_certs
+        } else {
+            store.trusted_certs()
+        };
+        let (mut trusted_crls, mut issuer_crls) = split_crls(store.crls(), &trusted_certs);
+        if is_specified(TrustListMasks::TrustedCrls) {
+            trusted_crls = new_trusted_crls;
+        }
+        if is_specified(TrustListMasks::IssuerCrls) {
+            issuer_crls = new_issuer_crls;
+        }
+
+        let result = (|| {
+            if is_specified(TrustListMasks::TrustedCertificates) {
+                store.set_trusted_certs(&trusted_certs)?;
+            }
+            if is_specified(TrustListMasks::IssuerCertificates) {
+                store.set_issuer_certs(&new_issuer_certs)?;
+            }
+            if is_specified(TrustListMasks::TrustedCrls) || is_specified(TrustListMasks::IssuerCrls)
+            {
+                trusted_crls.append(&mut issuer_crls);
+                store.set_crls(&trusted_crls)?;
+            }
+            Ok::<_, String>(())
+        })();
+        result.map_err(|e| {
+            error!("Failed to update the trust list: {e}");
+            StatusCode::BadInternalError
+        })
+    }
+
+    fn add_certificate(&self, cert: &ByteString, is_trusted: bool) -> Result<(), StatusCode> {
+        // Only trusted certificates can be added with this method, issuer certificates
+        // must be added by writing the trust list.
+        if !is_trusted {
+            return Err(StatusCode::BadCertificateInvalid);
+        }
+        let cert = X509::from_byte_string(cert).map_err(|_| StatusCode::BadCertificateInvalid)?;
+        if self.is_open_for_writing() {
+            return Err(StatusCode::BadInvalidState);
+        }
+        trace_write_lock!(self.certificate_store)
+            .store_trusted_cert(&cert)
+            .map_err(|e| {
+                error!("Failed to add certificate to the trust list: {e}");
+                StatusCode::BadInternalError
+            })
+    }
+
+    fn remove_certificate(&self, thumbprint: &str, is_trusted: bool) -> Result<(), StatusCode> {
+        if self.is_open_for_writing() {
+            return Err(StatusCode::BadInvalidState);
+        }
+        let mut store = trace_write_lock!(self.certificate_store);
+        let mut certs = if is_trusted {
+            store.trusted_certs()
+        } else {
+            store.issuer_certs()
+        };
+        let Some(index) = certs.iter().position(|c| {
+            c.thumbprint()
+                .as_hex_string()
+                .eq_ignore_ascii_case(thumbprint)
+        }) else {
+            return Err(StatusCode::BadInvalidArgument);
+        };
+        let cert = certs.remove(index);
+
+        let result = if is_trusted {
+            store.set_trusted_certs(&certs)
+        } else {
+            store.set_issuer_certs(&certs)
+        };
+        // Revocation lists issued by a removed CA are removed with it.
+        let crls = store.crls();
+        let len = crls.len();
+        let crls: Vec<_> = crls
+            .into_iter()
+            .filter(|c| !c.is_issued_by(&cert))
+            .collect();
+        let result = result.and_then(|_| {
+            if crls.len() != len {
+                store.set_crls(&crls)
+            } else {
+                Ok(())
+            }
+        });
+        result.map_err(|e| {
+            error!("Failed to remove certificate from the trust list: {e}");
+            StatusCode::BadInternalError
+        })
+    }
+}
+
+fn trust_list_id() -> NodeId {
+    ObjectId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup_TrustList.into()
+}
+
+/// Only the default application group is supported. A null group ID refers to it.
+fn check_certificate_group(group_id: &NodeId) -> Result<(), StatusCode> {
+    if group_id.is_null()
+        || *group_id == ObjectId::ServerConfiguration_CertificateGroups_DefaultApplicationGroup
+    {
+        Ok(())
+    } else {
+        Err(StatusCode::BadInvalidArgument)
+    }
+}
+
+/// Get the curve of the key needed for a certificate of type `type_id`, or `None` for an
+/// RSA key. A null type ID refers to the type of the current key.
+fn requested_curve(type_id: &NodeId, pkey: &PrivateKey) -> Result<Option<EccCurve>, StatusCode> {
+    if type_id.is_null() {
+        return Ok(pkey.ecc_curve());
+    }
+    if *type_id == ObjectTypeId::ApplicationCertificateType
+        || *type_id == ObjectTypeId::RsaMinApplicationCertificateType
+        || *type_id == ObjectTypeId::RsaSha256ApplicationCertificateType
+    {
+        Ok(None)
+    } else if *type_id == ObjectTypeId::EccNistP256ApplicationCertificateType {
+        Ok(Some(EccCurve::NistP256))
+    } else if *type_id == ObjectTypeId::EccNistP384ApplicationCertificateType {
+        Ok(Some(EccCurve::NistP384))
+    } else {
+        Err(StatusCode::BadNotSupported)
+    }
+}
+
+/// Get the certificate type of a certificate for `pkey`.
+fn certificate_type(pkey: &PrivateKey) -> ObjectTypeId {
+    match pkey.ecc_curve() {
+        None => ObjectTypeId::RsaSha256ApplicationCertificateType,
+        Some(EccCurve::NistP256) => ObjectTypeId::EccNistP256ApplicationCertificateType,
+        Some(EccCurve::NistP384) => ObjectTypeId::EccNistP384ApplicationCertificateType,
+    }
+}
+
+/// Split revocation lists into those issued by one of the trusted certificates,
+/// and those issued by other CAs.
+fn split_crls(
+    crls: Vec<CertificateRevocationList>,
+    trusted_certs: &[X509],
+) -> (
+    Vec<CertificateRevocationList>,
+    Vec<CertificateRevocationList>,
+) {
+    crls.into_iter()
+        .partition(|crl| trusted_certs.iter().any(|c| crl.is_issued_by(c)))
+}
@@ -0,0 +1,273 @@
+use opcua_nodes::{BaseEventType, Event, TypeTree};
+use opcua_types::{
+    AttributeId, DateTime, EventField, LocalizedText, NodeId, NumericRange, ObjectTypeId,
+    QualifiedName, Variant,
+};
+
+use super::{Condition, TwoState};
+
+/// Event produced whenever the state of a condition changes, and when
+/// the condition is reported as part of a `ConditionRefresh`.
+///
+/// This is a snapshot of the condition at the time the event was produced.
+#[derive(Debug)]
+pub struct ConditionEvent {
+    base: BaseEventType,
+    condition: Condition,
+    /// The condition type and its supertypes, up to and including `ConditionType`.
+    condition_types: Vec<NodeId>,
+}
+
+impl ConditionEvent {
+    pub(super) fn new(condition: &Condition, type_tree: &dyn TypeTree) -> Self {
+        let base = BaseEventType {
+            event_id: condition.event_id.clone(),
+            event_type: condition.condition_type.clone(),
+            source_node: condition.source_node.clone(),
+            source_name: condition.source_name.clone(),
+            time: condition.time,
+            receive_time: condition.time,
+            local_time: None,
+            message: condition.message.clone(),
+            severity: condition.severity,
+            condition_class_id: Some(condition.condition_class_id.clone()),
+            condition_class_name: Some(condition.condition_class_name.clone()),
+            condition_sub_class_id: None,
+            condition_sub_class_name: None,
+        };
+        Self {
+            base,
+            condition: condition.clone(),
+            condition_types: Self::condition_types(condition, type_tree),
+        }
+    }
+
+    /// Collect the types a select clause may reference to get fields from
+    /// this event, by walking the type tree from the concrete condition type.
+    fn condition_types(condition: &Condition, type_tree: &dyn TypeTree) -> Vec<NodeId> {
+        let mut types = vec![condition.condition_type.clone()];
+        let mut ty = &condition.condition_type;
+        while ty != &ObjectTypeId::ConditionType {
+            let Some(parent) = type_tree.get_supertype(ty) else {
+                break;
+            };
+            types.push(parent.clone());
+            ty = parent;
+        }
+        // The condition type may not be registered in the type tree, so always include
+        // the standard types implied by the state machines of the condition.
+        let standard = [
+            Some(ObjectTypeId::ConditionType),
+            condition
+                .acked
+                .as_ref()
+                .map(|_| ObjectTypeId::AcknowledgeableConditionType),
+            condition
+                .active
+                .as_ref()
+                .map(|_| ObjectTypeId::AlarmConditionType),
+        ];
+        for ty in standard.into_iter().flatten() {
+            if !types.iter().any(|t| t == &ty) {
+                types.push(ty.into());
+            }
+        }
+        types
+    }
+
+    /// Get the condition state this event was created from.
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    fn is_condition_type(&self, type_definition_id: &NodeId) -> bool {
+        self.condition_types.contains(type_definition_id)
+    }
+}
+
+impl Event for ConditionEvent {
+    fn get_field(
+        &self,
+        type_definition_id: &NodeId,
+        attribute_id: AttributeId,
+        index_range: &NumericRange,
+        browse_path: &[QualifiedName],
+    ) -> Variant {
+        if type_definition_id == &ObjectTypeId::BaseEventType {
+            return self.base.get_value(attribute_id, index_range, browse_path);
+        }
+        if !self.is_condition_type(type_definition_id) {
+            return Variant::Empty;
+        }
+        // The ConditionId is selected by requesting the NodeId attribute
+        // of the condition type itself.
+        if browse_path.is_empty() {
+            if attribute_id == AttributeId::NodeId {
+                return self.condition.node_id.clone().into();
+            }
+            return Variant::Empty;
+        }
+        self.get_value(attribute_id, index_range, browse_path)
+    }
+
+    fn time(&self) -> &DateTime {
+        &self.base.time
+    }
+}
+
+impl EventField for ConditionEvent {
+    fn get_value(
+        &self,
+        attribute_id: AttributeId,
+        index_range: &NumericRange,
+        remaining_path: &[QualifiedName],
+    ) -> Variant {
+        let Some(field) = remaining_path.first() else {
+            return Variant::Empty;
+        };
+        if field.namespace_index != 0 {
+            return Variant::Empty;
+        }
+        let rest = &remaining_path[1..];
+        let c = &self.condition;
+        match field.name.as_ref() {
+            "ConditionName" => c.condition_name.get_value(attribute_id, index_range, rest),
+            "BranchId" => NodeId::null().get_value(attribute_id, index_range, rest),
+            "Retain" => c.is_retained().get_value(attribute_id, index_range, rest),
+            "ClientUserId" => c.client_user_id.get_value(attribute_id, index_range, rest),
+            "EnabledState" => TwoStateField::new(&c.enabled, "Enabled", "Disabled").get_value(
+                attribute_id,
+                index_range,
+                rest,
+            ),
+            "Quality" => ConditionVariableField::new(c.quality, c.quality_time).get_value(
+                attribute_id,
+                index_range,
+                rest,
+            ),
+            "LastSeverity" => ConditionVariableField::new(c.last_severity, c.severity_time)
+                .get_value(attribute_id, index_range, rest),
+            "Comment" => ConditionVariableField::new(c.comment.clone(), c.comment_time).get_value(
+                attribute_id,
+                index_range,
+                rest,
+            ),
+            "AckedState" => c
+                .acked
+                .as_ref()
+                .map(|s| TwoStateField::new(s, "Acknowledged", "Unacknowledged"))
+                .get_value(attribute_id, index_range, rest),
+            "ConfirmedState" => c
+                .confirmed
+                .as_ref()
+                .map(|s| TwoStateField::new(s, "Confirmed", "Unconfirmed"))
+                .get_value(attribute_id, index_range, rest),
+            "ActiveState" => c
+                .active
+                .as_ref()
+                .map(|s| TwoStateField::new(s, "Active", "Inactive"))
+                .get_value(attribute_id, index_range, rest),
+            // Shelving and suppression are not supported, so alarms are never
+            // suppressed or shelved.
+            "SuppressedOrShelved" => {
+                c.active
+                    .as_ref()
+                    .map(|_| false)
+                    .get_value(attribute_id, index_range, rest)
+            }
+            _ => self
+                .base
+                .get_value(attribute_id, index_range, remaining_path),
+        }
+    }
+}
+
+/// Event field for an instance of `TwoStateVariableType`.
+struct TwoStateField<'a> {
+    state: &'a TwoState,
+    true_state: &'static str,
+    false_state: &'static str,
+}
+
+impl<'a> TwoStateField<'a> {
+    fn new(state: &'a TwoState, true_state: &'static str, false_state: &'static str) -> Self {
+        Self {
+            state,
+            true_state,
+            false_state,
+        }
+    }
+}
+
+impl EventField for TwoStateField<'_> {
+    fn get_value(
+        &self,
+        attribute_id: AttributeId,
+        index_range: &NumericRange,
+        remaining_path: &[QualifiedName],
+    ) -> Variant {
+        let Some(field) = remaining_path.first() else {
+            let name = if self.state.value {
+                self.true_state
+            } else {
+                self.false_state
+            };
+            return LocalizedText::from(name).get_value(attribute_id, index_range, &[]);
+        };
+        if field.namespace_index != 0 || remaining_path.len() > 1 {
+            return Variant::Empty;
+        }
+        match field.name.as_ref() {
+            "Id" => self.state.value.get_value(attribute_id, index_range, &[]),
+            "TransitionTime" => {
+                self.state
+                    .transition_time
+                    .get_value(attribute_id, index_range, &[])
+            }
+            "TrueState" => {
+                LocalizedText::from(self.true_state).get_value(attribute_id, index_range, &[])
+            }
+            "FalseState" => {
+                LocalizedText::from(self.false_state).get_value(attribute_id, index_range, &[])
+            }
+            _ => Variant::Empty,
+        }
+    }
+}
+
+/// Event field for an instance of `ConditionVariableType`.
+struct ConditionVariableField<T> {
+    value: T,
+    source_timestamp: DateTime,
+}
+
+impl<T> ConditionVariableField<T> {
+    fn new(value: T, source_timestamp: DateTime) -> Self {
+        Self {
+            value,
+            source_timestamp,
+        }
+    }
+}
+
+impl<T: EventField> EventField for ConditionVariableField<T> {
+    fn get_value(
+        &self,
+        attribute_id: AttributeId,
+        index_range: &NumericRange,
+        remaining_path: &[QualifiedName],
+    ) -> Variant {
+        let Some(field) = remaining_path.first() else {
+            return self.value.get_value(attribute_id, index_range, &[]);
+        };
+        if field.namespace_index == 0
+            && remaining_path.len() == 1
+            && field.name.as_ref() == "SourceTimestamp"
+        {
+            self.source_timestamp
+                .get_value(attribute_id, index_range, &[])
+        } else {
+            Variant::Empty
+        }
+    }
+}
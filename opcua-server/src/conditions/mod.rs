@@ -0,0 +1,699 @@
+//! Support for OPC UA Alarms & Conditions, as described in Part 9 of the standard.
+//!
+//! Conditions are registered in the server-wide [ConditionCache], typically by the
+//! node manager that owns the condition instance nodes. The cache tracks the
+//! condition state machines, produces events when they change, and implements
+//! the standard condition methods, as well as `ConditionRefresh` and `ConditionRefresh2`.
+//!
+//! Clients call condition methods using the method IDs defined on the condition types,
+//! for example `AcknowledgeableConditionType_Acknowledge`, with the condition
+//! as the object. These calls are handled by the core node manager.
+
+mod event;
+
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use log::debug;
+use opcua_core::{sync::RwLock, trace_lock, trace_read_lock, trace_write_lock};
+use opcua_crypto::random;
+use opcua_nodes::{BaseEventType, DefaultTypeTree, Event};
+use opcua_types::{
+    AttributeId, ByteString, DateTime, LocalizedText, MethodId, NodeId, ObjectId, ObjectTypeId,
+    StatusCode, UAString, Variant, VariantScalarTypeId, VariantTypeId,
+};
+
+use crate::{
+    load_method_args,
+    node_manager::{MethodCall, RequestContext},
+    SubscriptionCache,
+};
+
+pub use event::ConditionEvent;
+
+#[derive(Debug, Clone)]
+pub(crate) struct TwoState {
+    value: bool,
+    transition_time: DateTime,
+}
+
+impl TwoState {
+    fn new(value: bool) -> Self {
+        Self {
+            value,
+            transition_time: DateTime::now(),
+        }
+    }
+
+    fn set(&mut self, value: bool, time: DateTime) {
+        if self.value != value {
+            self.value = value;
+            self.transition_time = time;
+        }
+    }
+}
+
+/// The state of a single condition.
+///
+/// A condition is either a plain `ConditionType`, an `AcknowledgeableConditionType`
+/// with an optional `ConfirmedState`, or an `AlarmConditionType`. The type definition
+/// of the condition may be any subtype of these.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    node_id: NodeId,
+    condition_type: NodeId,
+    source_node: NodeId,
+    source_name: UAString,
+    condition_name: UAString,
+    condition_class_id: NodeId,
+    condition_class_name: LocalizedText,
+
+    event_id: ByteString,
+    time: DateTime,
+    message: LocalizedText,
+    severity: u16,
+    last_severity: u16,
+    severity_time: DateTime,
+    quality: StatusCode,
+    quality_time: DateTime,
+    comment: LocalizedText,
+    comment_time: DateTime,
+    client_user_id: UAString,
+    retain: bool,
+
+    enabled: TwoState,
+    acked: Option<TwoState>,
+    confirmed: Option<TwoState>,
+    active: Option<TwoState>,
+}
+
+impl Condition {
+    /// Create a new plain condition. `condition_type` must be `ConditionType`
+    /// or a subtype of it.
+    ///
+    /// Plain conditions have no state machine beyond `EnabledState`, so
+    /// `Retain` is controlled using [`Condition::set_retain`].
+    pub fn new(
+        node_id: impl Into<NodeId>,
+        condition_type: impl Into<NodeId>,
+        source_node: impl Into<NodeId>,
+        condition_name: impl Into<UAString>,
+    ) -> Self {
+        let now = DateTime::now();
+        Self {
+            node_id: node_id.into(),
+            condition_type: condition_type.into(),
+            source_node: source_node.into(),
+            source_name: UAString::null(),
+            condition_name: condition_name.into(),
+            condition_class_id: ObjectTypeId::BaseConditionClassType.into(),
+            condition_class_name: LocalizedText::from("BaseConditionClass"),
+            event_id: ByteString::null(),
+            time: now,
+            message: LocalizedText::null(),
+            severity: 1,
+            last_severity: 1,
+            severity_time: now,
+            quality: StatusCode::Good,
+            quality_time: now,
+            comment: LocalizedText::null(),
+            comment_time: now,
+            client_user_id: UAString::null(),
+            retain: false,
+            enabled: TwoState::new(true),
+            acked: None,
+            confirmed: None,
+            active: None,
+        }
+    }
+
+    /// Create a new acknowledgeable condition, initially acknowledged.
+    /// `condition_type` must be `AcknowledgeableConditionType` or a subtype of it.
+    pub fn new_acknowledgeable(
+        node_id: impl Into<NodeId>,
+        condition_type: impl Into<NodeId>,
+        source_node: impl Into<NodeId>,
+        condition_name: impl Into<UAString>,
+    ) -> Self {
+        let mut cond = Self::new(node_id, condition_type, source_node, condition_name);
+        cond.acked = Some(TwoState::new(true));
+        cond
+    }
+
+    /// Create a new alarm, initially inactive and acknowledged.
+    /// `condition_type` must be `AlarmConditionType` or a subtype of it.
+    pub fn new_alarm(
+        node_id: impl Into<NodeId>,
+        condition_type: impl Into<NodeId>,
+        source_node: impl Into<NodeId>,
+        condition_name: impl Into<UAString>,
+    ) -> Self {
+        let mut cond =
+            Self::new_acknowledgeable(node_id, condition_type, source_node, condition_name);
+        cond.active = Some(TwoState::new(false));
+        cond
+    }
+
+    /// Enable the optional `ConfirmedState` of an acknowledgeable condition.
+    /// Has no effect on plain conditions.
+    pub fn set_confirmable(mut self) -> Self {
+        if self.acked.is_some() {
+            self.confirmed = Some(TwoState::new(true));
+        }
+        self
+    }
+
+    /// Set the name of the source node.
+    pub fn set_source_name(mut self, source_name: impl Into<UAString>) -> Self {
+        self.source_name = source_name.into();
+        self
+    }
+
+    /// Set the condition class. The default is `BaseConditionClassType`.
+    pub fn set_condition_class(
+        mut self,
+        condition_class_id: impl Into<NodeId>,
+        condition_class_name: impl Into<LocalizedText>,
+    ) -> Self {
+        self.condition_class_id = condition_class_id.into();
+        self.condition_class_name = condition_class_name.into();
+        self
+    }
+
+    /// Set the initial severity of the condition.
+    pub fn set_severity(mut self, severity: u16) -> Self {
+        self.severity = severity;
+        self.last_severity = severity;
+        self
+    }
+
+    /// Set the initial message of the condition.
+    pub fn set_message(mut self, message: impl Into<LocalizedText>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    /// Set whether a plain condition should be retained.
+    /// For acknowledgeable conditions and alarms this is computed from the condition state.
+    pub fn set_retain(mut self, retain: bool) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    /// Get the node ID of the condition, its `ConditionId`.
+    pub fn node_id(&self) -> &NodeId {
+        &self.node_id
+    }
+
+    /// Get the type definition of the condition.
+    pub fn condition_type(&self) -> &NodeId {
+        &self.condition_type
+    }
+
+    /// Get the node ID of the source of the condition.
+    pub fn source_node(&self) -> &NodeId {
+        &self.source_node
+    }
+
+    /// Get the `EventId` of the last event produced for this condition.
+    pub fn event_id(&self) -> &ByteString {
+        &self.event_id
+    }
+
+    /// Get the current severity.
+    pub fn severity(&self) -> u16 {
+        self.severity
+    }
+
+    /// Get the current quality.
+    pub fn quality(&self) -> StatusCode {
+        self.quality
+    }
+
+    /// Get the last comment added to the condition.
+    pub fn comment(&self) -> &LocalizedText {
+        &self.comment
+    }
+
+    /// Get whether the condition is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.value
+    }
+
+    /// Get the `AckedState`, or `None` if the condition is not acknowledgeable.
+    pub fn is_acked(&self) -> Option<bool> {
+        self.acked.as_ref().map(|s| s.value)
+    }
+
+    /// Get the `ConfirmedState`, or `None` if the condition is not confirmable.
+    pub fn is_confirmed(&self) -> Option<bool> {
+        self.confirmed.as_ref().map(|s| s.value)
+    }
+
+    /// Get the `ActiveState`, or `None` if the condition is not an alarm.
+    pub fn is_active(&self) -> Option<bool> {
+        self.active.as_ref().map(|s| s.value)
+    }
+
+    /// Get whether the condition is retained, meaning that it is in a state that
+    /// is interesting for clients, and will be reported in a `ConditionRefresh`.
+    pub fn is_retained(&self) -> bool {
+        self.enabled.value && self.retain
+    }
+
+    fn update_retain(&mut self) {
+        if self.acked.is_none() && self.active.is_none() {
+            return;
+        }
+        self.retain = self.is_active() == Some(true)
+            || self.is_acked() == Some(false)
+            || self.is_confirmed() == Some(false);
+    }
+
+    /// Prepare the condition for a new event, generating a new `EventId`.
+    /// Returns a snapshot of the condition to create the event from.
+    fn new_event(&mut self, message: LocalizedText) -> Condition {
+        self.event_id = random::byte_string(16);
+        self.time = DateTime::now();
+        self.message = message;
+        self.clone()
+    }
+}
+
+/// Server-wide registry of conditions.
+///
+/// Node managers register their conditions here, and update them as the underlying
+/// process changes. State changes produce events that are sent to any
+/// monitored items on the condition source, or on the `Server` object.
+pub struct ConditionCache {
+    conditions: RwLock<HashMap<NodeId, Condition>>,
+    subscriptions: Arc<SubscriptionCache>,
+    type_tree: Arc<RwLock<DefaultTypeTree>>,
+}
+
+impl ConditionCache {
+    pub(crate) fn new(
+        subscriptions: Arc<SubscriptionCache>,
+        type_tree: Arc<RwLock<DefaultTypeTree>>,
+    ) -> Self {
+        Self {
+            conditions: RwLock::new(HashMap::new()),
+            subscriptions,
+            type_tree,
+        }
+    }
+
+    /// Register a condition. This does not produce an event. If a condition with
+    /// the same node ID is already registered, it is replaced.
+    pub fn add(&self, condition: Condition) {
+        let mut lck = trace_write_lock!(self.conditions);
+        lck.insert(condition.node_id.clone(), condition);
+    }
+
+    /// Remove a condition, returning it if it was registered.
+    pub fn remove(&self, node_id: &NodeId) -> Option<Condition> {
+        let mut lck = trace_write_lock!(self.conditions);
+        lck.remove(node_id)
+    }
+
+    /// Get a copy of the current state of a condition.
+    pub fn get(&self, node_id: &NodeId) -> Option<Condition> {
+        let lck = trace_read_lock!(self.conditions);
+        lck.get(node_id).cloned()
+    }
+
+    /// Check whether a condition with the given node ID is registered.
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        let lck = trace_read_lock!(self.conditions);
+        lck.contains_key(node_id)
+    }
+
+    /// Modify a condition and report the new state to clients.
+    /// `f` returns whether the condition changed, nothing is reported if it did not.
+    /// Changes to disabled conditions are recorded, but not reported until
+    /// the condition is enabled.
+    fn update(
+        &self,
+        node_id: &NodeId,
+        message: LocalizedText,
+        f: impl FnOnce(&mut Condition, DateTime) -> Result<bool, StatusCode>,
+    ) -> Result<(), StatusCode> {
+        let snapshot = {
+            let mut lck = trace_write_lock!(self.conditions);
+            let condition = lck.get_mut(node_id).ok_or(StatusCode::BadNodeIdUnknown)?;
+            let was_enabled = condition.enabled.value;
+            if !f(condition, DateTime::now())? {
+                return Ok(());
+            }
+            condition.update_retain();
+            if !was_enabled && !condition.enabled.value {
+                condition.message = message;
+                return Ok(());
+            }
+            condition.new_event(message)
+        };
+        let event = ConditionEvent::new(&snapshot, &*trace_read_lock!(self.type_tree));
+        self.subscriptions
+            .notify_events([(&event as &dyn Event, event.condition().source_node())].into_iter());
+        Ok(())
+    }
+
+    /// Set the `ActiveState` of an alarm. When an alarm becomes active it
+    /// must be acknowledged, and confirmed if it is confirmable.
+    pub fn set_active(
+        &self,
+        node_id: &NodeId,
+        active: bool,
+        message: impl Into<LocalizedText>,
+    ) -> Result<(), StatusCode> {
+        self.update(node_id, message.into(), |c, now| {
+            let Some(state) = c.active.as_mut() else {
+                return Err(StatusCode::BadNotSupported);
+            };
+            if state.value == active {
+                return Ok(false);
+            }
+            state.set(active, now);
+            if active {
+                if let Some(acked) = c.acked.as_mut() {
+                    acked.set(false, now);
+                }
+                if let Some(confirmed) = c.confirmed.as_mut() {
+                    confirmed.set(false, now);
+                }
+            }
+            Ok(true)
+        })
+    }
+
+    /// Set the severity of a condition. The previous severity is
+    /// reported as `LastSeverity`.
+    pub fn set_severity(
+        &self,
+        node_id: &NodeId,
+        severity: u16,
+        message: impl Into<LocalizedText>,
+    ) -> Result<(), StatusCode> {
+        self.update(node_id, message.into(), |c, now| {
+            c.last_severity = c.severity;
+            c.severity = severity;
+            c.severity_time = now;
+            Ok(true)
+        })
+    }
+
+    /// Set the quality of the process values the condition is based on.
+    pub fn set_quality(
+        &self,
+        node_id: &NodeId,
+        quality: StatusCode,
+        message: impl Into<LocalizedText>,
+    ) -> Result<(), StatusCode> {
+        self.update(node_id, message.into(), |c, now| {
+            c.quality = quality;
+            c.quality_time = now;
+            Ok(true)
+        })
+    }
+
+    /// Set whether a plain condition is retained.
+    pub fn set_retain(
+        &self,
+        node_id: &NodeId,
+        retain: bool,
+        message: impl Into<LocalizedText>,
+    ) -> Result<(), StatusCode> {
+        self.update(node_id, message.into(), |c, _| {
+            if c.acked.is_some() || c.active.is_some() {
+                return Err(StatusCode::BadNotSupported);
+            }
+            c.retain = retain;
+            Ok(true)
+        })
+    }
+
+    fn check_event_id(c: &Condition, event_id: &ByteString) -> Result<(), StatusCode> {
+        if !c.enabled.value {
+            Err(StatusCode::BadConditionDisabled)
+        } else if &c.event_id != event_id {
+            Err(StatusCode::BadEventIdUnknown)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn set_comment(c: &mut Condition, comment: LocalizedText, user: UAString, now: DateTime) {
+        c.client_user_id = user;
+        if !comment.text.is_empty() {
+            c.comment = comment;
+            c.comment_time = now;
+        }
+    }
+
+    /// Acknowledge a condition. `event_id` must be the `EventId` of the last
+    /// event produced for the condition.
+    pub fn acknowledge(
+        &self,
+        node_id: &NodeId,
+        event_id: &ByteString,
+        comment: LocalizedText,
+        user: impl Into<UAString>,
+    ) -> Result<(), StatusCode> {
+        let user = user.into();
+        self.update(
+            node_id,
+            LocalizedText::from("The condition was acknowledged."),
+            |c, now| {
+                Self::check_event_id(c, event_id)?;
+                let Some(acked) = c.acked.as_mut() else {
+                    return Err(StatusCode::BadMethodInvalid);
+                };
+                if acked.value {
+                    return Err(StatusCode::BadConditionBranchAlreadyAcked);
+                }
+                acked.set(true, now);
+                Self::set_comment(c, comment, user, now);
+                Ok(true)
+            },
+        )
+    }
+
+    /// Confirm a condition. `event_id` must be the `EventId` of the last
+    /// event produced for the condition.
+    pub fn confirm(
+        &self,
+        node_id: &NodeId,
+        event_id: &ByteString,
+        comment: LocalizedText,
+        user: impl Into<UAString>,
+    ) -> Result<(), StatusCode> {
+        let user = user.into();
+        self.update(
+            node_id,
+            LocalizedText::from("The condition was confirmed."),
+            |c, now| {
+                Self::check_event_id(c, event_id)?;
+                let Some(confirmed) = c.confirmed.as_mut() else {
+                    return Err(StatusCode::BadMethodInvalid);
+                };
+                if confirmed.value {
+                    return Err(StatusCode::BadConditionBranchAlreadyConfirmed);
+                }
+                confirmed.set(true, now);
+                Self::set_comment(c, comment, user, now);
+                Ok(true)
+            },
+        )
+    }
+
+    /// Add a comment to a condition. `event_id` must be the `EventId` of the last
+    /// event produced for the condition.
+    pub fn add_comment(
+        &self,
+        node_id: &NodeId,
+        event_id: &ByteString,
+        comment: LocalizedText,
+        user: impl Into<UAString>,
+    ) -> Result<(), StatusCode> {
+        let user = user.into();
+        self.update(
+            node_id,
+            LocalizedText::from("A comment was added to the condition."),
+            |c, now| {
+                Self::check_event_id(c, event_id)?;
+                c.client_user_id = user;
+                c.comment = comment;
+                c.comment_time = now;
+                Ok(true)
+            },
+        )
+    }
+
+    /// Enable a condition.
+    pub fn enable(&self, node_id: &NodeId) -> Result<(), StatusCode> {
+        self.update(
+            node_id,
+            LocalizedText::from("The condition was enabled."),
+            |c, now| {
+                if c.enabled.value {
+                    return Err(StatusCode::BadConditionAlreadyEnabled);
+                }
+                c.enabled.set(true, now);
+                Ok(true)
+            },
+        )
+    }
+
+    /// Disable a condition. Disabled conditions are not retained, and
+    /// do not produce events until they are enabled again.
+    pub fn disable(&self, node_id: &NodeId) -> Result<(), StatusCode> {
+        self.update(
+            node_id,
+            LocalizedText::from("The condition was disabled."),
+            |c, now| {
+                if !c.enabled.value {
+                    return Err(StatusCode::BadConditionAlreadyDisabled);
+                }
+                c.enabled.set(false, now);
+                Ok(true)
+            },
+        )
+    }
+
+    /// Send the current state of all retained conditions to the event monitored items
+    /// in a subscription, or a single monitored item if `monitored_item_id` is given.
+    ///
+    /// The conditions are preceded by a `RefreshStartEvent` and followed by
+    /// a `RefreshEndEvent`.
+    pub fn refresh(
+        &self,
+        session_id: u32,
+        subscription_id: u32,
+        monitored_item_id: Option<u32>,
+    ) -> Result<(), StatusCode> {
+        let retained: Vec<_> = {
+            let lck = trace_read_lock!(self.conditions);
+            lck.values().filter(|c| c.is_retained()).cloned().collect()
+        };
+        let events: Vec<_> = {
+            let type_tree = trace_read_lock!(self.type_tree);
+            retained
+                .iter()
+                .map(|c| ConditionEvent::new(c, &*type_tree))
+                .collect()
+        };
+        let server_id: NodeId = ObjectId::Server.into();
+        let start = Self::refresh_event(ObjectTypeId::RefreshStartEventType);
+        let end = Self::refresh_event(ObjectTypeId::RefreshEndEventType);
+
+        let subs = self
+            .subscriptions
+            .get_session_subscriptions(session_id)
+            .ok_or(StatusCode::BadSubscriptionIdInvalid)?;
+        let mut subs = trace_lock!(subs);
+        let (roles, security_mode) = {
+            let session = trace_read_lock!(subs.session());
+            (session.roles().clone(), session.message_security_mode())
+        };
+        let sub = subs
+            .get_mut(subscription_id)
+            .ok_or(StatusCode::BadSubscriptionIdInvalid)?;
+
+        let items: Vec<_> = sub
+            .items()
+            .filter(|i| i.item_to_monitor().attribute_id == AttributeId::EventNotifier)
+            .filter(|i| monitored_item_id.is_none_or(|id| id == i.id()))
+            .map(|i| (i.id(), i.item_to_monitor().node_id.clone()))
+            .collect();
+        if monitored_item_id.is_some() && items.is_empty() {
+            return Err(StatusCode::BadMonitoredItemIdInvalid);
+        }
+
+        debug!(
+            "Refreshing {} conditions for {} monitored items in subscription {}",
+            events.len(),
+            items.len(),
+            subscription_id
+        );
+
+        for (id, notifier) in items {
+            sub.notify_event(&id, &start, &roles, security_mode);
+            for evt in &events {
+                if notifier == server_id || &notifier == evt.condition().source_node() {
+                    sub.notify_event(&id, evt, &roles, security_mode);
+                }
+            }
+            sub.notify_event(&id, &end, &roles, security_mode);
+        }
+
+        Ok(())
+    }
+
+    fn refresh_event(event_type: ObjectTypeId) -> BaseEventType {
+        BaseEventType::new_now(event_type, random::byte_string(16), "")
+            .set_source_node(ObjectId::Server.into())
+            .set_source_name("Server".into())
+            .set_severity(1)
+    }
+
+    /// Check whether the method call is a call to a standard condition method
+    /// on a registered condition, or a call to `ConditionRefresh` or `ConditionRefresh2`
+    /// on `ConditionType`.
+    pub fn is_condition_method_call(&self, object_id: &NodeId, method_id: &NodeId) -> bool {
+        match method_id.as_method_id() {
+            Ok(
+                MethodId::AcknowledgeableConditionType_Acknowledge
+                | MethodId::AcknowledgeableConditionType_Confirm
+                | MethodId::ConditionType_AddComment
+                | MethodId::ConditionType_Enable
+                | MethodId::ConditionType_Disable,
+            ) => self.contains(object_id),
+            Ok(
+                MethodId::ConditionType_ConditionRefresh
+                | MethodId::ConditionType_ConditionRefresh2,
+            ) => object_id == &ObjectTypeId::ConditionType,
+            _ => false,
+        }
+    }
+
+    /// Call a standard condition method. This handles the condition methods
+    /// `Acknowledge`, `Confirm`, `AddComment`, `Enable` and `Disable` on registered conditions,
+    /// as well as `ConditionRefresh` and `ConditionRefresh2`.
+    ///
+    /// Returns `BadMethodInvalid` if the method is not a condition method.
+    pub fn call(&self, context: &RequestContext, call: &mut MethodCall) -> Result<(), StatusCode> {
+        let Ok(id) = call.method_id().as_method_id() else {
+            return Err(StatusCode::BadMethodInvalid);
+        };
+        let user = UAString::from(&context.token.0);
+        let object_id = call.object_id().clone();
+
+        match id {
+            MethodId::AcknowledgeableConditionType_Acknowledge => {
+                let (event_id, comment) = load_method_args!(call, ByteString, LocalizedText)?;
+                self.acknowledge(&object_id, &event_id, *comment, user)?;
+            }
+            MethodId::AcknowledgeableConditionType_Confirm => {
+                let (event_id, comment) = load_method_args!(call, ByteString, LocalizedText)?;
+                self.confirm(&object_id, &event_id, *comment, user)?;
+            }
+            MethodId::ConditionType_AddComment => {
+                let (event_id, comment) = load_method_args!(call, ByteString, LocalizedText)?;
+                self.add_comment(&object_id, &event_id, *comment, user)?;
+            }
+            MethodId::ConditionType_Enable => self.enable(&object_id)?,
+            MethodId::ConditionType_Disable => self.disable(&object_id)?,
+            MethodId::ConditionType_ConditionRefresh => {
+                let subscription_id = load_method_args!(call, UInt32)?;
+                self.refresh(context.session_id, subscription_id, None)?;
+            }
+            MethodId::ConditionType_ConditionRefresh2 => {
+                let (subscription_id, monitored_item_id) = load_method_args!(call, UInt32, UInt32)?;
+                self.refresh(context.session_id, subscription_id, Some(monitored_item_id))?;
+            }
+            _ => return Err(StatusCode::BadMethodInvalid),
+        }
+        call.set_status(StatusCode::Good);
+        Ok(())
+    }
+}
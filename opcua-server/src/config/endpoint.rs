@@ -91,6 +91,8 @@ impl ServerEndpoint {
             SecurityPolicy::Basic256 => 3,
             SecurityPolicy::Basic256Sha256 => 4,
             SecurityPolicy::Aes256Sha256RsaPss => 5,
+            SecurityPolicy::EccNistP256 => 6,
+            SecurityPolicy::EccNistP384 => 7,
             _ => 0,
         };
         if security_mode == MessageSecurityMode::SignAndEncrypt {
@@ -272,6 +274,66 @@ impl ServerEndpoint {
         )
     }
 
+    /// Create a new server endpoint with ECC NIST P-256 signing.
+    ///
+    /// The server needs an ECC application instance certificate on the same curve.
+    pub fn new_ecc_nist_p256_sign<T>(path: T, user_token_ids: &[String]) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(
+            path,
+            SecurityPolicy::EccNistP256,
+            MessageSecurityMode::Sign,
+            user_token_ids,
+        )
+    }
+
+    /// Create a new server endpoint with ECC NIST P-256 encryption.
+    ///
+    /// The server needs an ECC application instance certificate on the same curve.
+    pub fn new_ecc_nist_p256_sign_encrypt<T>(path: T, user_token_ids: &[String]) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(
+            path,
+            SecurityPolicy::EccNistP256,
+            MessageSecurityMode::SignAndEncrypt,
+            user_token_ids,
+        )
+    }
+
+    /// Create a new server endpoint with ECC NIST P-384 signing.
+    ///
+    /// The server needs an ECC application instance certificate on the same curve.
+    pub fn new_ecc_nist_p384_sign<T>(path: T, user_token_ids: &[String]) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(
+            path,
+            SecurityPolicy::EccNistP384,
+            MessageSecurityMode::Sign,
+            user_token_ids,
+        )
+    }
+
+    /// Create a new server endpoint with ECC NIST P-384 encryption.
+    ///
+    /// The server needs an ECC application instance certificate on the same curve.
+    pub fn new_ecc_nist_p384_sign_encrypt<T>(path: T, user_token_ids: &[String]) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(
+            path,
+            SecurityPolicy::EccNistP384,
+            MessageSecurityMode::SignAndEncrypt,
+            user_token_ids,
+        )
+    }
+
     /// Validate the endpoint and return a list of validation errors.
     pub fn validate(
         &self,
@@ -303,7 +365,7 @@ impl ServerEndpoint {
         let security_policy = SecurityPolicy::from_str(&self.security_policy).unwrap();
         let security_mode = MessageSecurityMode::from(self.security_mode.as_ref());
         if security_policy == SecurityPolicy::Unknown {
-            errors.push(format!("Endpoint {} is invalid. Security policy \"{}\" is invalid. Valid values are None, Basic128Rsa15, Basic256, Basic256Sha256, Aes128Sha256RsaOaep, Aes256Sha256RsaPss,", id, self.security_policy));
+            errors.push(format!("Endpoint {} is invalid. Security policy \"{}\" is invalid. Valid values are None, Basic128Rsa15, Basic256, Basic256Sha256, Aes128Sha256RsaOaep, Aes256Sha256RsaPss, ECC-nistP256, ECC-nistP384", id, self.security_policy));
         } else if security_mode == MessageSecurityMode::Invalid {
             errors.push(format!("Endpoint {} is invalid. Security mode \"{}\" is invalid. Valid values are None, Sign, SignAndEncrypt", id, self.security_mode));
         } else if (security_policy == SecurityPolicy::None
@@ -1,9 +1,11 @@
 mod capabilities;
 mod endpoint;
 mod limits;
+mod redundancy;
 mod server;
 
 pub use capabilities::{HistoryServerCapabilities, ServerCapabilities};
 pub use endpoint::{EndpointIdentifier, ServerEndpoint};
 pub use limits::{Limits, OperationalLimits, SubscriptionLimits};
-pub use server::{ServerConfig, ServerUserToken, ANONYMOUS_USER_TOKEN_ID};
+pub use redundancy::{RedundancyConfig, RedundancyMode, RedundancyPeer};
+pub use server::{ReverseConnectTarget, ServerConfig, ServerUserToken, ANONYMOUS_USER_TOKEN_ID};
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use opcua_types::RedundancySupport;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Default)]
+/// Redundancy support of the redundant server set the server belongs to,
+/// see Part 4 6.6.2.
+pub enum RedundancyMode {
+    /// The server is not part of a redundant server set.
+    #[default]
+    None,
+    /// Backup servers are started only when the active server fails.
+    Cold,
+    /// Backup servers are running, but cannot serve the data of the active server.
+    Warm,
+    /// All servers in the set are running and serve the same data.
+    Hot,
+    /// The servers appear as a single server to clients, and failover is handled by
+    /// the servers themselves.
+    Transparent,
+    /// Like `Hot`, but sessions are mirrored between the servers, so that clients
+    /// can reactivate their session on a different server.
+    HotAndMirrored,
+}
+
+impl From<RedundancyMode> for RedundancySupport {
+    fn from(value: RedundancyMode) -> Self {
+        match value {
+            RedundancyMode::None => RedundancySupport::None,
+            RedundancyMode::Cold => RedundancySupport::Cold,
+            RedundancyMode::Warm => RedundancySupport::Warm,
+            RedundancyMode::Hot => RedundancySupport::Hot,
+            RedundancyMode::Transparent => RedundancySupport::Transparent,
+            RedundancyMode::HotAndMirrored => RedundancySupport::HotAndMirrored,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+/// Another server in the redundant server set.
+pub struct RedundancyPeer {
+    /// Server URI of the peer. This is the application URI of the peer,
+    /// or its server ID in a transparent server set.
+    pub server_uri: String,
+    /// URL of an endpoint on the peer, used for the heartbeat.
+    /// The endpoint must accept anonymous sessions.
+    pub endpoint_url: String,
+}
+
+impl RedundancyPeer {
+    /// Create a new redundancy peer.
+    pub fn new(server_uri: impl Into<String>, endpoint_url: impl Into<String>) -> Self {
+        Self {
+            server_uri: server_uri.into(),
+            endpoint_url: endpoint_url.into(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+/// Configuration of the redundant server set the server belongs to.
+pub struct RedundancyConfig {
+    /// Redundancy support of the server set.
+    #[serde(default)]
+    pub mode: RedundancyMode,
+    /// ID of this server in the set. Defaults to the application URI. Servers in a
+    /// transparent set share an application URI, so they must each set a unique ID.
+    #[serde(default)]
+    pub server_id: Option<String>,
+    /// The other servers in the set.
+    #[serde(default)]
+    pub peers: Vec<RedundancyPeer>,
+    /// Interval between heartbeats to each peer, in milliseconds.
+    #[serde(default = "defaults::heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64,
+    /// Timeout for each heartbeat, in milliseconds. A peer that does not respond
+    /// within this time is considered failed.
+    #[serde(default = "defaults::heartbeat_timeout_ms")]
+    pub heartbeat_timeout_ms: u64,
+}
+
+impl Default for RedundancyConfig {
+    fn default() -> Self {
+        Self {
+            mode: RedundancyMode::None,
+            server_id: None,
+            peers: Vec::new(),
+            heartbeat_interval_ms: defaults::heartbeat_interval_ms(),
+            heartbeat_timeout_ms: defaults::heartbeat_timeout_ms(),
+        }
+    }
+}
+
+impl RedundancyConfig {
+    /// Create a new redundancy configuration with the given mode and peers.
+    pub fn new(mode: RedundancyMode, peers: Vec<RedundancyPeer>) -> Self {
+        Self {
+            mode,
+            peers,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the server is part of a redundant server set.
+    pub fn is_enabled(&self) -> bool {
+        self.mode != RedundancyMode::None
+    }
+}
+
+mod defaults {
+    pub fn heartbeat_interval_ms() -> u64 {
+        1_000
+    }
+
+    pub fn heartbeat_timeout_ms() -> u64 {
+        5_000
+    }
+}
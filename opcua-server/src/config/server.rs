@@ -13,14 +13,17 @@ use log::{trace, warn};
 use serde::{Deserialize, Serialize};
 
 use crate::constants;
-use opcua_core::{comms::url::url_matches_except_host, config::Config};
-use opcua_crypto::{CertificateStore, SecurityPolicy, Thumbprint};
+use opcua_core::{
+    comms::url::{is_opc_ua_binary_url, url_matches_except_host},
+    config::Config,
+};
+use opcua_crypto::{FileSystemCertificateStore, SecurityPolicy, Thumbprint};
 use opcua_types::{
     ApplicationDescription, ApplicationType, DecodingOptions, LocalizedText, MessageSecurityMode,
     UAString,
 };
 
-use super::{endpoint::ServerEndpoint, limits::Limits};
+use super::{endpoint::ServerEndpoint, limits::Limits, redundancy::RedundancyConfig};
 
 /// Token ID for the anonymous user token.
 pub const ANONYMOUS_USER_TOKEN_ID: &str = "ANONYMOUS";
@@ -85,7 +88,7 @@ impl ServerUserToken {
             // obtain its thumbprint. This will be used when a session is activated.
             if let Some(ref x509_path) = self.x509 {
                 let path = PathBuf::from(x509_path);
-                if let Ok(x509) = CertificateStore::read_cert(&path) {
+                if let Ok(x509) = FileSystemCertificateStore::read_cert(&path) {
                     self.thumbprint = Some(x509.thumbprint());
                 }
             }
@@ -141,6 +144,9 @@ pub struct CertificateValidation {
     pub trust_client_certs: bool,
     /// Check the valid from/to fields of a certificate
     pub check_time: bool,
+    /// Accept client certificates whose issuers have no current revocation list.
+    #[serde(default)]
+    pub allow_unknown_revocation: bool,
 }
 
 impl Default for CertificateValidation {
@@ -148,8 +154,50 @@ impl Default for CertificateValidation {
         Self {
             trust_client_certs: false,
             check_time: true,
+            allow_unknown_revocation: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+/// A client the server connects to using reverse connect.
+pub struct ReverseConnectTarget {
+    /// URL the client listens for reverse connections on, for example `opc.tcp://client:4844`.
+    pub client_url: String,
+    /// Endpoint URL sent to the client in the `ReverseHello` message. The client will use
+    /// this in its `HELLO`, so it must match one of the server endpoints.
+    /// If this is not set, the URL of the default endpoint is used, or the
+    /// first endpoint if there is no default.
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+}
+
+impl ReverseConnectTarget {
+    /// Create a new reverse connect target for the client listening on `client_url`.
+    pub fn new(client_url: impl Into<String>) -> Self {
+        Self {
+            client_url: client_url.into(),
+            endpoint_url: None,
         }
     }
+
+    /// Set the endpoint URL sent to the client.
+    pub fn endpoint_url(mut self, endpoint_url: impl Into<String>) -> Self {
+        self.endpoint_url = Some(endpoint_url.into());
+        self
+    }
+}
+
+impl From<&str> for ReverseConnectTarget {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for ReverseConnectTarget {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -223,6 +271,33 @@ pub struct ServerConfig {
     /// we will instantly time out.
     #[serde(default = "defaults::max_session_timeout_ms")]
     pub max_session_timeout_ms: u64,
+    /// Clients the server should connect to using reverse connect. The server
+    /// keeps a connection open to each of these, waiting for the client to use it.
+    #[serde(default)]
+    pub reverse_connect_targets: Vec<ReverseConnectTarget>,
+    /// Time to wait between failed attempts to open a reverse connection, in milliseconds.
+    #[serde(default = "defaults::reverse_connect_interval_ms")]
+    pub reverse_connect_interval_ms: u64,
+    /// Run the server as a local discovery server. Other servers may register
+    /// themselves using `RegisterServer` or `RegisterServer2`, and registered
+    /// servers are returned from `FindServers`.
+    #[serde(default)]
+    pub local_discovery_server: bool,
+    /// Time in milliseconds before a registration with the local discovery server
+    /// expires, if it is not renewed by the registered server.
+    #[serde(default = "defaults::registration_timeout_ms")]
+    pub registration_timeout_ms: u64,
+    /// Name used to announce the server over multicast DNS, if multicast discovery
+    /// is enabled. Defaults to the application name.
+    #[serde(default)]
+    pub mdns_server_name: Option<String>,
+    /// Capability identifiers announced over multicast DNS, see Part 12. `LDS` is always
+    /// announced when the server is running as a local discovery server.
+    #[serde(default)]
+    pub server_capabilities: Vec<String>,
+    /// Redundant server set the server belongs to, if any.
+    #[serde(default)]
+    pub redundancy: RedundancyConfig,
 }
 
 mod defaults {
@@ -247,6 +322,14 @@ mod defaults {
     pub fn max_session_timeout_ms() -> u64 {
         constants::MAX_SESSION_TIMEOUT
     }
+
+    pub fn reverse_connect_interval_ms() -> u64 {
+        5_000
+    }
+
+    pub fn registration_timeout_ms() -> u64 {
+        600_000
+    }
 }
 
 impl Config for ServerConfig {
@@ -301,6 +384,25 @@ impl Config for ServerConfig {
         if self.discovery_urls.is_empty() {
             errors.push("Server configuration is invalid. Discovery urls not set".to_owned());
         }
+        for target in &self.reverse_connect_targets {
+            if !is_opc_ua_binary_url(&target.client_url) {
+                errors.push(format!(
+                    "Reverse connect client url {} is not a valid opc.tcp url",
+                    target.client_url
+                ));
+            }
+        }
+        if !self.redundancy.is_enabled() && !self.redundancy.peers.is_empty() {
+            errors.push("Redundancy peers are set, but redundancy mode is None".to_owned());
+        }
+        for peer in &self.redundancy.peers {
+            if !is_opc_ua_binary_url(&peer.endpoint_url) {
+                errors.push(format!(
+                    "Redundancy peer url {} is not a valid opc.tcp url",
+                    peer.endpoint_url
+                ));
+            }
+        }
 
         if errors.is_empty() {
             Ok(())
@@ -322,7 +424,11 @@ impl Config for ServerConfig {
     }
 
     fn application_type(&self) -> ApplicationType {
-        ApplicationType::Server
+        if self.local_discovery_server {
+            ApplicationType::DiscoveryServer
+        } else {
+            ApplicationType::Server
+        }
     }
 
     fn discovery_urls(&self) -> Option<Vec<UAString>> {
@@ -375,6 +481,13 @@ impl Default for ServerConfig {
             max_timeout_ms: defaults::max_timeout_ms(),
             max_secure_channel_token_lifetime_ms: defaults::max_secure_channel_token_lifetime_ms(),
             max_session_timeout_ms: defaults::max_session_timeout_ms(),
+            reverse_connect_targets: Vec::new(),
+            reverse_connect_interval_ms: defaults::reverse_connect_interval_ms(),
+            local_discovery_server: false,
+            registration_timeout_ms: defaults::registration_timeout_ms(),
+            mdns_server_name: None,
+            server_capabilities: Vec::new(),
+            redundancy: RedundancyConfig::default(),
         }
     }
 }
@@ -413,6 +526,7 @@ impl ServerConfig {
             certificate_validation: CertificateValidation {
                 trust_client_certs: false,
                 check_time: true,
+                allow_unknown_revocation: false,
             },
             pki_dir,
             discovery_server_url,
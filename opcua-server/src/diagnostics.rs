@@ -0,0 +1,373 @@
+//! Counters backing the diagnostics exposed under the `Server.ServerDiagnostics` object.
+//!
+//! Most diagnostic values are computed on demand from the current state of the server,
+//! these types only keep track of the values that cannot be derived from that state,
+//! such as the number of sessions created since the server started.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use arc_swap::ArcSwap;
+use opcua_core::{sync::RwLock, trace_read_lock, RequestMessage, ResponseMessage};
+use opcua_types::{
+    DateTime, ServerDiagnosticsSummaryDataType, ServiceCounterDataType, SessionDiagnosticsDataType,
+    SessionSecurityDiagnosticsDataType, StatusCode,
+};
+
+use crate::{
+    info::ServerInfo,
+    session::{instance::Session, manager::SessionManager},
+    SubscriptionCache,
+};
+
+/// Server wide diagnostic counters.
+#[derive(Default)]
+pub struct ServerDiagnostics {
+    cumulated_session_count: AtomicU32,
+    security_rejected_session_count: AtomicU32,
+    rejected_session_count: AtomicU32,
+    session_timeout_count: AtomicU32,
+    cumulated_subscription_count: AtomicU32,
+    security_rejected_requests_count: AtomicU32,
+    rejected_requests_count: AtomicU32,
+}
+
+/// Return `true` if `status` indicates that something was rejected for security reasons.
+fn is_security_error(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BadSecurityChecksFailed
+            | StatusCode::BadSecureChannelIdInvalid
+            | StatusCode::BadSecurityModeRejected
+            | StatusCode::BadSecurityPolicyRejected
+            | StatusCode::BadApplicationSignatureInvalid
+            | StatusCode::BadUserSignatureInvalid
+            | StatusCode::BadIdentityTokenInvalid
+            | StatusCode::BadIdentityTokenRejected
+            | StatusCode::BadUserAccessDenied
+            | StatusCode::BadCertificateInvalid
+            | StatusCode::BadCertificateTimeInvalid
+            | StatusCode::BadCertificateIssuerTimeInvalid
+            | StatusCode::BadCertificateHostNameInvalid
+            | StatusCode::BadCertificateUriInvalid
+            | StatusCode::BadCertificateUseNotAllowed
+            | StatusCode::BadCertificateIssuerUseNotAllowed
+            | StatusCode::BadCertificateUntrusted
+            | StatusCode::BadCertificateRevocationUnknown
+            | StatusCode::BadCertificateIssuerRevocationUnknown
+            | StatusCode::BadCertificateRevoked
+            | StatusCode::BadCertificateIssuerRevoked
+    )
+}
+
+impl ServerDiagnostics {
+    pub(crate) fn on_session_created(&self) {
+        self.cumulated_session_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A `CreateSession` or `ActivateSession` request failed with `status`.
+    pub(crate) fn on_session_rejected(&self, status: StatusCode) {
+        if is_security_error(status) {
+            self.security_rejected_session_count
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        self.rejected_session_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_session_timeout(&self) {
+        self.session_timeout_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_subscription_created(&self) {
+        self.cumulated_subscription_count
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A request was rejected with `status` before it was processed.
+    pub(crate) fn on_request_rejected(&self, status: StatusCode) {
+        if is_security_error(status) {
+            self.security_rejected_requests_count
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        self.rejected_requests_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Build the server diagnostics summary from the counters, given the
+    /// parts of the summary that are derived from current server state.
+    pub fn summary(
+        &self,
+        current_session_count: u32,
+        current_subscription_count: u32,
+        publishing_interval_count: u32,
+    ) -> ServerDiagnosticsSummaryDataType {
+        ServerDiagnosticsSummaryDataType {
+            // This counts views created by the server at runtime, views defined
+            // in the address space by node managers are not included.
+            server_view_count: 0,
+            current_session_count,
+            cumulated_session_count: self.cumulated_session_count.load(Ordering::Relaxed),
+            security_rejected_session_count: self
+                .security_rejected_session_count
+                .load(Ordering::Relaxed),
+            rejected_session_count: self.rejected_session_count.load(Ordering::Relaxed),
+            session_timeout_count: self.session_timeout_count.load(Ordering::Relaxed),
+            // Sessions are only closed by the client or when they time out.
+            session_abort_count: 0,
+            current_subscription_count,
+            cumulated_subscription_count: self.cumulated_subscription_count.load(Ordering::Relaxed),
+            publishing_interval_count,
+            security_rejected_requests_count: self
+                .security_rejected_requests_count
+                .load(Ordering::Relaxed),
+            rejected_requests_count: self.rejected_requests_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ServiceCounter {
+    total: AtomicU32,
+    errors: AtomicU32,
+}
+
+impl ServiceCounter {
+    fn get(&self) -> ServiceCounterDataType {
+        ServiceCounterDataType {
+            total_count: self.total.load(Ordering::Relaxed),
+            error_count: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Services with a dedicated counter in `SessionDiagnosticsDataType`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DiagnosticsService {
+    Read,
+    HistoryRead,
+    Write,
+    HistoryUpdate,
+    Call,
+    CreateMonitoredItems,
+    ModifyMonitoredItems,
+    SetMonitoringMode,
+    SetTriggering,
+    DeleteMonitoredItems,
+    CreateSubscription,
+    ModifySubscription,
+    SetPublishingMode,
+    Publish,
+    Republish,
+    TransferSubscriptions,
+    DeleteSubscriptions,
+    AddNodes,
+    AddReferences,
+    DeleteNodes,
+    DeleteReferences,
+    Browse,
+    BrowseNext,
+    TranslateBrowsePathsToNodeIds,
+    QueryFirst,
+    QueryNext,
+    RegisterNodes,
+    UnregisterNodes,
+}
+
+const NUM_SERVICES: usize = DiagnosticsService::UnregisterNodes as usize + 1;
+
+impl DiagnosticsService {
+    fn from_request(message: &RequestMessage) -> Option<Self> {
+        Some(match message {
+            RequestMessage::Read(_) => Self::Read,
+            RequestMessage::HistoryRead(_) => Self::HistoryRead,
+            RequestMessage::Write(_) => Self::Write,
+            RequestMessage::HistoryUpdate(_) => Self::HistoryUpdate,
+            RequestMessage::Call(_) => Self::Call,
+            RequestMessage::CreateMonitoredItems(_) => Self::CreateMonitoredItems,
+            RequestMessage::ModifyMonitoredItems(_) => Self::ModifyMonitoredItems,
+            RequestMessage::SetMonitoringMode(_) => Self::SetMonitoringMode,
+            RequestMessage::SetTriggering(_) => Self::SetTriggering,
+            RequestMessage::DeleteMonitoredItems(_) => Self::DeleteMonitoredItems,
+            RequestMessage::CreateSubscription(_) => Self::CreateSubscription,
+            RequestMessage::ModifySubscription(_) => Self::ModifySubscription,
+            RequestMessage::SetPublishingMode(_) => Self::SetPublishingMode,
+            RequestMessage::Publish(_) => Self::Publish,
+            RequestMessage::Republish(_) => Self::Republish,
+            RequestMessage::TransferSubscriptions(_) => Self::TransferSubscriptions,
+            RequestMessage::DeleteSubscriptions(_) => Self::DeleteSubscriptions,
+            RequestMessage::AddNodes(_) => Self::AddNodes,
+            RequestMessage::AddReferences(_) => Self::AddReferences,
+            RequestMessage::DeleteNodes(_) => Self::DeleteNodes,
+            RequestMessage::DeleteReferences(_) => Self::DeleteReferences,
+            RequestMessage::Browse(_) => Self::Browse,
+            RequestMessage::BrowseNext(_) => Self::BrowseNext,
+            RequestMessage::TranslateBrowsePathsToNodeIds(_) => Self::TranslateBrowsePathsToNodeIds,
+            RequestMessage::QueryFirst(_) => Self::QueryFirst,
+            RequestMessage::QueryNext(_) => Self::QueryNext,
+            RequestMessage::RegisterNodes(_) => Self::RegisterNodes,
+            RequestMessage::UnregisterNodes(_) => Self::UnregisterNodes,
+            _ => return None,
+        })
+    }
+}
+
+/// Diagnostic counters for a single session.
+pub struct SessionDiagnostics {
+    client_connection_time: DateTime,
+    client_last_contact_time: ArcSwap<DateTime>,
+    total_requests: ServiceCounter,
+    unauthorized_requests: AtomicU32,
+    services: [ServiceCounter; NUM_SERVICES],
+}
+
+impl Default for SessionDiagnostics {
+    fn default() -> Self {
+        let now = DateTime::now();
+        Self {
+            client_connection_time: now,
+            client_last_contact_time: ArcSwap::new(Arc::new(now)),
+            total_requests: ServiceCounter::default(),
+            unauthorized_requests: AtomicU32::new(0),
+            services: Default::default(),
+        }
+    }
+}
+
+/// Handle for a request that has been registered with the session diagnostics,
+/// used to record the outcome of the request once it has completed.
+pub(crate) struct RequestDiagnostics {
+    diagnostics: Arc<SessionDiagnostics>,
+    service: Option<DiagnosticsService>,
+}
+
+impl RequestDiagnostics {
+    /// Record the response to the request, counting it as an error if it is a service fault.
+    pub(crate) fn complete(&self, response: &ResponseMessage) {
+        if !matches!(response, ResponseMessage::ServiceFault(_)) {
+            return;
+        }
+        self.diagnostics
+            .total_requests
+            .errors
+            .fetch_add(1, Ordering::Relaxed);
+        if let Some(service) = self.service {
+            self.diagnostics.services[service as usize]
+                .errors
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl SessionDiagnostics {
+    /// Register a new request on the session, returning a handle used to
+    /// record the result of the request.
+    pub(crate) fn on_request(self: &Arc<Self>, message: &RequestMessage) -> RequestDiagnostics {
+        self.client_last_contact_time
+            .store(Arc::new(DateTime::now()));
+        self.total_requests.total.fetch_add(1, Ordering::Relaxed);
+        let service = DiagnosticsService::from_request(message);
+        if let Some(service) = service {
+            self.services[service as usize]
+                .total
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        RequestDiagnostics {
+            diagnostics: self.clone(),
+            service,
+        }
+    }
+
+    /// Record a request on the session that was rejected due to insufficient authorization.
+    pub(crate) fn on_unauthorized_request(&self) {
+        self.unauthorized_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time the session was created.
+    pub fn client_connection_time(&self) -> DateTime {
+        self.client_connection_time
+    }
+
+    /// Time of the last request on the session.
+    pub fn client_last_contact_time(&self) -> DateTime {
+        **self.client_last_contact_time.load()
+    }
+
+    /// Total number of requests made on the session.
+    pub fn total_request_count(&self) -> ServiceCounterDataType {
+        self.total_requests.get()
+    }
+
+    /// Number of requests on the session rejected due to authorization failures.
+    pub fn unauthorized_request_count(&self) -> u32 {
+        self.unauthorized_requests.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn service_count(&self, service: DiagnosticsService) -> ServiceCounterDataType {
+        self.services[service as usize].get()
+    }
+}
+
+/// Get the current diagnostics of a single session.
+pub(crate) fn session_diagnostics(
+    session: &RwLock<Session>,
+    info: &ServerInfo,
+    subscriptions: &SubscriptionCache,
+) -> SessionDiagnosticsDataType {
+    // Avoid holding the session lock while locking the subscription cache.
+    let id = trace_read_lock!(session).session_id_numeric();
+    let counts = subscriptions.session_subscription_counts(id);
+    trace_read_lock!(session).diagnostics_data(info, counts)
+}
+
+/// Get the diagnostics of all sessions on the server.
+pub(crate) fn all_session_diagnostics(
+    session_manager: &RwLock<SessionManager>,
+    info: &ServerInfo,
+    subscriptions: &SubscriptionCache,
+) -> Vec<SessionDiagnosticsDataType> {
+    let sessions: Vec<_> = trace_read_lock!(session_manager)
+        .sessions()
+        .cloned()
+        .collect();
+    sessions
+        .iter()
+        .map(|s| session_diagnostics(s, info, subscriptions))
+        .collect()
+}
+
+/// Get the security diagnostics of all sessions on the server.
+pub(crate) fn all_session_security_diagnostics(
+    session_manager: &RwLock<SessionManager>,
+) -> Vec<SessionSecurityDiagnosticsDataType> {
+    let sessions: Vec<_> = trace_read_lock!(session_manager)
+        .sessions()
+        .cloned()
+        .collect();
+    sessions
+        .iter()
+        .map(|s| trace_read_lock!(s).security_diagnostics_data())
+        .collect()
+}
+
+/// Get the server diagnostics summary.
+pub(crate) fn server_diagnostics_summary(
+    session_manager: &RwLock<SessionManager>,
+    info: &ServerInfo,
+    subscriptions: &SubscriptionCache,
+) -> ServerDiagnosticsSummaryDataType {
+    let current_session_count = trace_read_lock!(session_manager).len() as u32;
+    let subs = subscriptions.subscription_diagnostics(None);
+    let publishing_intervals: HashSet<_> = subs
+        .iter()
+        .map(|s| s.publishing_interval.to_bits())
+        .collect();
+    info.diagnostics.summary(
+        current_session_count,
+        subs.len() as u32,
+        publishing_intervals.len() as u32,
+    )
+}
@@ -0,0 +1,210 @@
+//! Multicast discovery, as used by a local discovery server with the
+//! multicast extension (LDS-ME), described in Part 12 of the OPC-UA standard.
+
+use std::{io, sync::Arc};
+
+use futures::{never::Never, stream::BoxStream, StreamExt};
+use log::{debug, error, warn};
+use opcua_core::{sync::RwLock, trace_read_lock, trace_write_lock};
+use opcua_types::{DateTime, ServerOnNetwork, UAString};
+
+/// Server announced or discovered over multicast DNS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdnsServerRecord {
+    /// Name of the server on the network. This is the mDNS instance name,
+    /// and must be unique on the network.
+    pub server_name: String,
+    /// Discovery URL of the server.
+    pub discovery_url: String,
+    /// Capability identifiers of the server, as defined in Part 12.
+    pub server_capabilities: Vec<String>,
+}
+
+/// Event produced when browsing for servers over multicast DNS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MdnsEvent {
+    /// A server was announced, or its announcement was updated.
+    ServerFound(MdnsServerRecord),
+    /// A server with the given name is no longer announced.
+    ServerRemoved(String),
+}
+
+/// Trait for multicast DNS responders, used to announce servers on the network
+/// and to learn about other servers.
+///
+/// With the `mdns` feature, [`MdnsSdResponder`](crate::MdnsSdResponder) implements
+/// this using the system network interfaces. Tests may implement this with an
+/// in-process stand-in shared between servers.
+pub trait MdnsResponder: Send + Sync + 'static {
+    /// Announce a server on the network, replacing any previous announcement
+    /// with the same server name.
+    fn announce(&self, record: &MdnsServerRecord) -> io::Result<()>;
+
+    /// Stop announcing the server with name `server_name`.
+    fn withdraw(&self, server_name: &str) -> io::Result<()>;
+
+    /// Start browsing for servers on the network. Servers already known
+    /// should be reported as found once browsing starts.
+    fn browse(&self) -> io::Result<BoxStream<'static, MdnsEvent>>;
+}
+
+struct ServerRecords {
+    records: Vec<ServerOnNetwork>,
+    last_record_id: u32,
+    last_counter_reset_time: DateTime,
+}
+
+impl ServerRecords {
+    fn next_record_id(&mut self) -> u32 {
+        if self.last_record_id == u32::MAX {
+            // Renumber existing records, so that clients know to start over.
+            self.last_record_id = 0;
+            self.last_counter_reset_time = DateTime::now();
+            for record in &mut self.records {
+                self.last_record_id += 1;
+                record.record_id = self.last_record_id;
+            }
+        }
+        self.last_record_id += 1;
+        self.last_record_id
+    }
+}
+
+/// Multicast discovery state of the server, keeping the list of servers
+/// on the network returned by `FindServersOnNetwork`.
+pub(crate) struct MulticastDiscovery {
+    responder: Arc<dyn MdnsResponder>,
+    records: RwLock<ServerRecords>,
+}
+
+impl MulticastDiscovery {
+    pub(crate) fn new(responder: Arc<dyn MdnsResponder>) -> Self {
+        Self {
+            responder,
+            records: RwLock::new(ServerRecords {
+                records: Vec::new(),
+                last_record_id: 0,
+                last_counter_reset_time: DateTime::now(),
+            }),
+        }
+    }
+
+    /// Announce a server on the network, and add it to the list of servers.
+    pub(crate) fn announce(&self, record: MdnsServerRecord) {
+        debug!("Announcing server {} over mDNS", record.server_name);
+        if let Err(e) = self.responder.announce(&record) {
+            error!(
+                "Failed to announce server {} over mDNS: {e}",
+                record.server_name
+            );
+        }
+        self.add_record(record);
+    }
+
+    /// Stop announcing a server, and remove it from the list of servers.
+    pub(crate) fn withdraw(&self, server_name: &str) {
+        debug!("Withdrawing mDNS announcement of server {server_name}");
+        if let Err(e) = self.responder.withdraw(server_name) {
+            error!("Failed to withdraw mDNS announcement of server {server_name}: {e}");
+        }
+        self.remove_record(server_name);
+    }
+
+    fn add_record(&self, record: MdnsServerRecord) {
+        let server_capabilities: Vec<UAString> = record
+            .server_capabilities
+            .iter()
+            .map(UAString::from)
+            .collect();
+        let discovery_url = UAString::from(record.discovery_url);
+        let server_name = UAString::from(record.server_name);
+
+        let mut records = trace_write_lock!(self.records);
+        if let Some(idx) = records
+            .records
+            .iter()
+            .position(|r| r.server_name == server_name)
+        {
+            let existing = &records.records[idx];
+            if existing.discovery_url == discovery_url
+                && existing.server_capabilities.as_deref() == Some(server_capabilities.as_slice())
+            {
+                return;
+            }
+            // Changed records get a new ID, so that clients see the change.
+            records.records.remove(idx);
+        }
+        let record_id = records.next_record_id();
+        records.records.push(ServerOnNetwork {
+            record_id,
+            server_name,
+            discovery_url,
+            server_capabilities: Some(server_capabilities),
+        });
+    }
+
+    fn remove_record(&self, server_name: &str) {
+        let mut records = trace_write_lock!(self.records);
+        records
+            .records
+            .retain(|r| r.server_name.as_ref() != server_name);
+    }
+
+    /// Find servers on the network with a record ID greater than `starting_record_id`,
+    /// and all the capabilities in `server_capability_filter`. Returns the time the
+    /// record IDs were last reset, and the matching records.
+    pub(crate) fn find_servers_on_network(
+        &self,
+        starting_record_id: u32,
+        max_records_to_return: u32,
+        server_capability_filter: &[UAString],
+    ) -> (DateTime, Vec<ServerOnNetwork>) {
+        let records = trace_read_lock!(self.records);
+        let max_records = if max_records_to_return == 0 {
+            usize::MAX
+        } else {
+            max_records_to_return as usize
+        };
+        let servers = records
+            .records
+            .iter()
+            .filter(|r| r.record_id > starting_record_id)
+            .filter(|r| {
+                let capabilities = r.server_capabilities.as_deref().unwrap_or_default();
+                server_capability_filter.iter().all(|f| {
+                    capabilities
+                        .iter()
+                        .any(|c| c.as_ref().eq_ignore_ascii_case(f.as_ref()))
+                })
+            })
+            .take(max_records)
+            .cloned()
+            .collect();
+        (records.last_counter_reset_time, servers)
+    }
+
+    /// Browse for servers on the network, updating the list of servers.
+    pub(crate) async fn run(&self) -> Never {
+        let mut events = match self.responder.browse() {
+            Ok(events) => events,
+            Err(e) => {
+                error!("Failed to browse for servers over mDNS: {e}");
+                return futures::future::pending().await;
+            }
+        };
+        while let Some(event) = events.next().await {
+            match event {
+                MdnsEvent::ServerFound(record) => {
+                    debug!("Found server {} over mDNS", record.server_name);
+                    self.add_record(record);
+                }
+                MdnsEvent::ServerRemoved(server_name) => {
+                    debug!("Server {server_name} removed over mDNS");
+                    self.remove_record(&server_name);
+                }
+            }
+        }
+        warn!("Browsing for servers over mDNS stopped");
+        futures::future::pending().await
+    }
+}
@@ -0,0 +1,138 @@
+use std::io;
+
+use futures::{stream::BoxStream, StreamExt};
+use log::warn;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use opcua_core::comms::url::hostname_port_from_url;
+
+use super::mdns::{MdnsEvent, MdnsResponder, MdnsServerRecord};
+
+/// mDNS service type of OPC-UA servers using `opc.tcp`, from Part 12.
+const SERVICE_TYPE: &str = "_opcua-tcp._tcp.local.";
+
+/// Default port of `opc.tcp` discovery URLs.
+const DEFAULT_PORT: u16 = 4840;
+
+fn to_io_error(e: mdns_sd::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+/// mDNS responder using the system network interfaces.
+///
+/// Servers are announced with the `_opcua-tcp._tcp` service type, with the path of
+/// the discovery URL and the server capabilities in the `path` and `caps` TXT records,
+/// as described in Part 12. Only `opc.tcp` discovery URLs can be announced.
+pub struct MdnsSdResponder {
+    daemon: ServiceDaemon,
+}
+
+impl MdnsSdResponder {
+    /// Create a new responder, starting a background thread that handles mDNS traffic.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            daemon: ServiceDaemon::new().map_err(to_io_error)?,
+        })
+    }
+
+    fn service_info(record: &MdnsServerRecord) -> io::Result<ServiceInfo> {
+        let (host, port) = hostname_port_from_url(&record.discovery_url, DEFAULT_PORT)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // opc.tcp://host:port/path, the path is everything after the authority.
+        let path = format!(
+            "/{}",
+            record
+                .discovery_url
+                .splitn(4, '/')
+                .nth(3)
+                .unwrap_or_default()
+        );
+        let host = if host.ends_with(".local") {
+            format!("{host}.")
+        } else {
+            format!("{host}.local.")
+        };
+        let properties = [
+            ("path", path),
+            ("caps", record.server_capabilities.join(",")),
+        ];
+        ServiceInfo::new(
+            SERVICE_TYPE,
+            &record.server_name,
+            &host,
+            "",
+            port,
+            &properties[..],
+        )
+        .map(|s| s.enable_addr_auto())
+        .map_err(to_io_error)
+    }
+
+    fn server_name(fullname: &str) -> &str {
+        fullname
+            .strip_suffix(SERVICE_TYPE)
+            .and_then(|n| n.strip_suffix('.'))
+            .unwrap_or(fullname)
+    }
+
+    fn record(info: &ServiceInfo) -> MdnsServerRecord {
+        let host = info.get_hostname().trim_end_matches('.');
+        let path = info.get_property_val_str("path").unwrap_or("/");
+        let path = path.strip_prefix('/').unwrap_or(path);
+        MdnsServerRecord {
+            server_name: Self::server_name(info.get_fullname()).to_owned(),
+            discovery_url: format!("opc.tcp://{host}:{}/{path}", info.get_port()),
+            server_capabilities: info
+                .get_property_val_str("caps")
+                .unwrap_or_default()
+                .split(',')
+                .filter(|c| !c.is_empty())
+                .map(|c| c.to_owned())
+                .collect(),
+        }
+    }
+}
+
+impl MdnsResponder for MdnsSdResponder {
+    fn announce(&self, record: &MdnsServerRecord) -> io::Result<()> {
+        self.daemon
+            .register(Self::service_info(record)?)
+            .map_err(to_io_error)
+    }
+
+    fn withdraw(&self, server_name: &str) -> io::Result<()> {
+        self.daemon
+            .unregister(&format!("{server_name}.{SERVICE_TYPE}"))
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    fn browse(&self) -> io::Result<BoxStream<'static, MdnsEvent>> {
+        let receiver = self.daemon.browse(SERVICE_TYPE).map_err(to_io_error)?;
+        let (send, recv) = futures::channel::mpsc::unbounded();
+        // The daemon delivers events on a blocking channel, forward them from a separate thread.
+        std::thread::spawn(move || {
+            while let Ok(event) = receiver.recv() {
+                let event = match event {
+                    ServiceEvent::ServiceResolved(info) => {
+                        MdnsEvent::ServerFound(Self::record(&info))
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        MdnsEvent::ServerRemoved(Self::server_name(&fullname).to_owned())
+                    }
+                    _ => continue,
+                };
+                if send.unbounded_send(event).is_err() {
+                    break;
+                }
+            }
+            warn!("mDNS browse stopped");
+        });
+        Ok(recv.boxed())
+    }
+}
+
+impl Drop for MdnsSdResponder {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
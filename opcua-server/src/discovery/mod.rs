@@ -0,0 +1,17 @@
+//! Discovery support, registering the server with a discovery server, and
+//! acting as a local discovery server for other servers.
+
+mod mdns;
+#[cfg(feature = "mdns")]
+mod mdns_sd;
+#[cfg(feature = "discovery-server-registration")]
+mod registration;
+mod registry;
+
+pub(crate) use mdns::MulticastDiscovery;
+pub use mdns::{MdnsEvent, MdnsResponder, MdnsServerRecord};
+#[cfg(feature = "mdns")]
+pub use mdns_sd::MdnsSdResponder;
+#[cfg(feature = "discovery-server-registration")]
+pub(crate) use registration::periodic_discovery_server_registration;
+pub use registry::ServerRegistry;
@@ -0,0 +1,222 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::info;
+use opcua_core::{
+    comms::url::is_opc_ua_binary_url, sync::RwLock, trace_read_lock, trace_write_lock,
+};
+use opcua_types::{
+    ApplicationDescription, ApplicationType, LocalizedText, MdnsDiscoveryConfiguration,
+    RegisteredServer, StatusCode, UAString,
+};
+
+use super::mdns::{MdnsServerRecord, MulticastDiscovery};
+
+struct Registration {
+    server: RegisteredServer,
+    mdns_config: Option<MdnsDiscoveryConfiguration>,
+    last_registered: Instant,
+}
+
+impl Registration {
+    fn is_expired(&self, now: Instant, timeout: Duration) -> bool {
+        now.duration_since(self.last_registered) > timeout
+    }
+
+    fn mdns_server_name(&self) -> Option<&str> {
+        self.mdns_config
+            .as_ref()
+            .map(|c| c.mdns_server_name.as_ref())
+    }
+
+    /// Record announced over mDNS on behalf of the registered server, if it gave an
+    /// mDNS configuration. Only `opc.tcp` discovery URLs can be announced.
+    fn mdns_record(&self) -> Option<MdnsServerRecord> {
+        let config = self.mdns_config.as_ref()?;
+        let discovery_url = self
+            .server
+            .discovery_urls
+            .iter()
+            .flatten()
+            .find(|u| is_opc_ua_binary_url(u.as_ref()))?;
+        Some(MdnsServerRecord {
+            server_name: config.mdns_server_name.as_ref().to_owned(),
+            discovery_url: discovery_url.as_ref().to_owned(),
+            server_capabilities: config
+                .server_capabilities
+                .iter()
+                .flatten()
+                .map(|c| c.as_ref().to_owned())
+                .collect(),
+        })
+    }
+}
+
+/// Registry of servers registered with this server through `RegisterServer` or
+/// `RegisterServer2`, used when the server is running as a local discovery server.
+///
+/// Registrations are removed when a server registers itself as offline, when
+/// it does not renew its registration before the registration timeout, or when
+/// the semaphore file given in the registration is deleted.
+///
+/// If the server uses multicast discovery, servers registered with an mDNS
+/// configuration are announced on the network until their registration is removed.
+pub struct ServerRegistry {
+    servers: RwLock<HashMap<String, Registration>>,
+    timeout: Duration,
+    multicast: Option<Arc<MulticastDiscovery>>,
+}
+
+impl ServerRegistry {
+    pub(crate) fn new(timeout: Duration, multicast: Option<Arc<MulticastDiscovery>>) -> Self {
+        Self {
+            servers: RwLock::new(HashMap::new()),
+            timeout,
+            multicast,
+        }
+    }
+
+    /// Register a server, or renew its existing registration. If the server
+    /// is registered as offline, its registration is removed instead.
+    ///
+    /// `mdns_config` is the mDNS configuration given in `RegisterServer2`, if any.
+    pub fn register(
+        &self,
+        server: RegisteredServer,
+        mdns_config: Option<MdnsDiscoveryConfiguration>,
+    ) -> Result<(), StatusCode> {
+        Self::validate(&server)?;
+
+        let server_uri = server.server_uri.as_ref().to_owned();
+        let (previous, record) = {
+            let mut servers = trace_write_lock!(self.servers);
+            if server.is_online {
+                let registration = Registration {
+                    server,
+                    mdns_config,
+                    last_registered: Instant::now(),
+                };
+                let record = registration.mdns_record();
+                let previous = servers.insert(server_uri.clone(), registration);
+                if previous.is_none() {
+                    info!("Server {server_uri} registered");
+                }
+                (previous, record)
+            } else {
+                let previous = servers.remove(&server_uri);
+                if previous.is_some() {
+                    info!("Server {server_uri} unregistered");
+                }
+                (previous, None)
+            }
+        };
+
+        if let Some(multicast) = &self.multicast {
+            // Withdraw the previous announcement, unless it is replaced by one with the same name.
+            if let Some(name) = previous.as_ref().and_then(|p| p.mdns_server_name()) {
+                if record.as_ref().is_none_or(|r| r.server_name != name) {
+                    multicast.withdraw(name);
+                }
+            }
+            if let Some(record) = record {
+                multicast.announce(record);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(server: &RegisteredServer) -> Result<(), StatusCode> {
+        if server.server_uri.is_empty() {
+            return Err(StatusCode::BadServerUriInvalid);
+        }
+        if server.server_names.as_ref().is_none_or(|n| n.is_empty()) {
+            return Err(StatusCode::BadServerNameMissing);
+        }
+        if server.discovery_urls.as_ref().is_none_or(|n| n.is_empty()) {
+            return Err(StatusCode::BadDiscoveryUrlMissing);
+        }
+        if server.server_type == ApplicationType::Client {
+            return Err(StatusCode::BadInvalidArgument);
+        }
+        if server.is_online && !semaphore_file_exists(server) {
+            return Err(StatusCode::BadSempahoreFileMissing);
+        }
+        Ok(())
+    }
+
+    /// Remove registrations that have expired, or whose semaphore file has been deleted.
+    pub fn remove_expired(&self) {
+        let now = Instant::now();
+        let mut removed = Vec::new();
+        {
+            let mut servers = trace_write_lock!(self.servers);
+            servers.retain(|server_uri, registration| {
+                let keep = if registration.is_expired(now, self.timeout) {
+                    info!("Registration of server {server_uri} expired");
+                    false
+                } else if !semaphore_file_exists(&registration.server) {
+                    info!("Semaphore file of server {server_uri} was removed, unregistering it");
+                    false
+                } else {
+                    true
+                };
+                if !keep {
+                    removed.extend(registration.mdns_server_name().map(|n| n.to_owned()));
+                }
+                keep
+            });
+        }
+        if let Some(multicast) = &self.multicast {
+            for name in removed {
+                multicast.withdraw(&name);
+            }
+        }
+    }
+
+    /// Get the currently registered servers.
+    pub fn registered_servers(&self) -> Vec<RegisteredServer> {
+        self.remove_expired();
+        trace_read_lock!(self.servers)
+            .values()
+            .map(|r| r.server.clone())
+            .collect()
+    }
+
+    /// Get application descriptions of the registered servers, using the first
+    /// server name matching one of `locale_ids`.
+    pub(crate) fn application_descriptions(
+        &self,
+        locale_ids: &[UAString],
+    ) -> Vec<ApplicationDescription> {
+        self.registered_servers()
+            .into_iter()
+            .map(|server| ApplicationDescription {
+                application_name: server_name(&server, locale_ids),
+                application_uri: server.server_uri,
+                product_uri: server.product_uri,
+                application_type: server.server_type,
+                gateway_server_uri: server.gateway_server_uri,
+                discovery_profile_uri: UAString::null(),
+                discovery_urls: server.discovery_urls,
+            })
+            .collect()
+    }
+}
+
+fn semaphore_file_exists(server: &RegisteredServer) -> bool {
+    server.semaphore_file_path.is_empty() || Path::new(server.semaphore_file_path.as_ref()).exists()
+}
+
+fn server_name(server: &RegisteredServer, locale_ids: &[UAString]) -> LocalizedText {
+    let names = server.server_names.as_deref().unwrap_or_default();
+    locale_ids
+        .iter()
+        .find_map(|locale| names.iter().find(|n| n.locale == *locale))
+        .or_else(|| names.first())
+        .cloned()
+        .unwrap_or_else(LocalizedText::null)
+}
@@ -7,13 +7,17 @@
 use std::sync::atomic::{AtomicU16, AtomicU8, Ordering};
 use std::sync::Arc;
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use log::{debug, error, warn};
 use opcua_nodes::DefaultTypeTree;
 
-use crate::authenticator::{user_pass_security_policy_id, Password};
+use crate::authenticator::{is_user_pass_allowed, user_pass_security_policy_id, Password};
 use crate::node_manager::TypeTreeForUser;
-use opcua_core::comms::url::{hostname_from_url, url_matches_except_host};
+use opcua_core::comms::url::{
+    hostname_from_url, is_opc_ua_binary_url, url_matches_except_host, url_with_replaced_hostname,
+    HTTPS_SCHEME, OPC_WSS_SCHEME,
+};
+use opcua_core::config::Config;
 use opcua_core::handle::AtomicHandle;
 use opcua_core::sync::RwLock;
 use opcua_crypto::{user_identity, PrivateKey, SecurityPolicy, X509};
@@ -28,7 +32,13 @@ use opcua_types::{
     MessageSecurityMode, NamespaceMap, TypeLoaderCollection, UAString,
 };
 
+use crate::certificate_manager::CertificateManager;
+use crate::conditions::ConditionCache;
 use crate::config::{ServerConfig, ServerEndpoint};
+use crate::diagnostics::ServerDiagnostics;
+use crate::discovery::{MdnsServerRecord, MulticastDiscovery, ServerRegistry};
+use crate::redundancy::RedundancyManager;
+use crate::roles::RoleSet;
 
 use super::authenticator::{AuthManager, UserToken};
 use super::identity_token::{IdentityToken, POLICY_ID_ANONYMOUS, POLICY_ID_X509};
@@ -49,18 +59,19 @@ pub struct ServerInfo {
     pub servers: Vec<String>,
     /// Server configuration
     pub config: Arc<ServerConfig>,
-    /// Server public certificate read from config location or null if there is none
-    pub server_certificate: Option<X509>,
+    /// Server public certificate read from config location or null if there is none.
+    /// This is replaced when a new certificate is applied through the server configuration.
+    pub server_certificate: ArcSwapOption<X509>,
     /// Server private key
-    pub server_pkey: Option<PrivateKey>,
+    pub server_pkey: ArcSwapOption<PrivateKey>,
     /// Operational limits
     pub(crate) operational_limits: OperationalLimits,
     /// Current state
     pub state: ArcSwap<ServerStateType>,
     /// Audit log
     // pub(crate) audit_log: Arc<RwLock<AuditLog>>,
-    /// Diagnostic information
-    // pub(crate) diagnostics: Arc<RwLock<ServerDiagnostics>>,
+    /// Diagnostic counters
+    pub diagnostics: ServerDiagnostics,
     /// Size of the send buffer in bytes
     pub send_buffer_size: usize,
     /// Size of the receive buffer in bytes
@@ -83,8 +94,22 @@ pub struct ServerInfo {
     pub service_level: Arc<AtomicU8>,
     /// Currently active local port.
     pub port: AtomicU16,
+    /// URL scheme of the active transport, used to create endpoint URLs.
+    pub url_scheme: ArcSwap<String>,
     /// List of active type loaders
     pub type_loaders: TypeLoaderCollection,
+    /// Registry of alarms and conditions on the server.
+    pub conditions: Arc<ConditionCache>,
+    /// Roles on the server, and the default role permissions of each namespace.
+    pub roles: Arc<RoleSet>,
+    /// Remote management of the server certificate and trust list.
+    pub certificate_manager: Arc<CertificateManager>,
+    /// Redundancy state of the server, and the service level computed from it.
+    pub redundancy: Arc<RedundancyManager>,
+    /// Servers registered with this server, if it is running as a local discovery server.
+    pub server_registry: Option<ServerRegistry>,
+    /// Multicast discovery state, if the server has an mDNS responder.
+    pub(crate) multicast_discovery: Option<Arc<MulticastDiscovery>>,
 }
 
 impl ServerInfo {
@@ -102,10 +127,11 @@ impl ServerInfo {
         if let Some(ref transport_profile_uris) = *transport_profile_uris {
             // Note - some clients pass an empty array
             if !transport_profile_uris.is_empty() {
-                // As we only support binary transport, the result is None if the supplied profile_uris does not contain that profile
-                let found_binary_transport = transport_profile_uris.iter().any(|profile_uri| {
-                    profile_uri.as_ref() == profiles::TRANSPORT_PROFILE_URI_BINARY
-                });
+                // As we only support a single transport at a time, the result is None if the supplied profile_uris does not contain that profile
+                let transport_profile_uri = self.transport_profile_uri();
+                let found_binary_transport = transport_profile_uris
+                    .iter()
+                    .any(|profile_uri| profile_uri.as_ref() == transport_profile_uri);
                 if !found_binary_transport {
                     error!(
                         "Client wants to connect with a non binary transport {:#?}",
@@ -140,6 +166,72 @@ impl ServerInfo {
         }
     }
 
+    /// Get the list of servers returned by `FindServers`. This is the server itself,
+    /// and any registered servers if it is running as a local discovery server.
+    pub fn find_servers(
+        &self,
+        endpoint_url: &UAString,
+        locale_ids: &Option<Vec<UAString>>,
+        server_uris: &Option<Vec<UAString>>,
+    ) -> Vec<ApplicationDescription> {
+        let mut servers = vec![self.config.application_description()];
+        if let Some(registry) = &self.server_registry {
+            servers.extend(
+                registry.application_descriptions(locale_ids.as_deref().unwrap_or_default()),
+            );
+        }
+
+        // Filter servers that do not have a matching application uri
+        if let Some(server_uris) = server_uris {
+            if !server_uris.is_empty() {
+                servers.retain(|server| server_uris.contains(&server.application_uri));
+            }
+        }
+
+        // Registered servers are on the same host as the discovery server, and may use
+        // localhost in their discovery URLs. Replace it with the hostname used by the client.
+        if let Ok(hostname) = hostname_from_url(endpoint_url.as_ref()) {
+            if !is_localhost(&hostname) {
+                for url in servers
+                    .iter_mut()
+                    .filter_map(|s| s.discovery_urls.as_mut())
+                    .flatten()
+                {
+                    if hostname_from_url(url.as_ref()).is_ok_and(|h| is_localhost(&h)) {
+                        if let Ok(replaced) = url_with_replaced_hostname(url.as_ref(), &hostname) {
+                            *url = replaced.into();
+                        }
+                    }
+                }
+            }
+        }
+
+        servers
+    }
+
+    /// Get the record used to announce this server over multicast DNS. Only `opc.tcp`
+    /// discovery URLs can be announced, so this uses the first of those.
+    pub(crate) fn mdns_record(&self) -> Option<MdnsServerRecord> {
+        let discovery_url = self
+            .config
+            .discovery_urls
+            .iter()
+            .find(|u| is_opc_ua_binary_url(u))?;
+        let mut server_capabilities = self.config.server_capabilities.clone();
+        if self.config.local_discovery_server && !server_capabilities.iter().any(|c| c == "LDS") {
+            server_capabilities.push("LDS".to_owned());
+        }
+        Some(MdnsServerRecord {
+            server_name: self
+                .config
+                .mdns_server_name
+                .clone()
+                .unwrap_or_else(|| self.config.application_name.clone()),
+            discovery_url: discovery_url.clone(),
+            server_capabilities,
+        })
+    }
+
     /// Check if the endpoint given by `endpoint_url`, `security_policy`, and `security_mode`
     /// exists on the server.
     pub fn endpoint_exists(
@@ -232,7 +324,7 @@ impl ServerInfo {
             security_mode: endpoint.message_security_mode(),
             security_policy_uri: UAString::from(endpoint.security_policy().to_uri()),
             user_identity_tokens: Some(user_identity_tokens),
-            transport_profile_uri: UAString::from(profiles::TRANSPORT_PROFILE_URI_BINARY),
+            transport_profile_uri: UAString::from(self.transport_profile_uri()),
             security_level: endpoint.security_level,
         }
     }
@@ -252,9 +344,10 @@ impl ServerInfo {
         }
     }
 
-    /// Get the application type, will be `Server`.
+    /// Get the application type, will be `DiscoveryServer` if the server is running
+    /// as a local discovery server, `Server` otherwise.
     pub fn application_type(&self) -> ApplicationType {
-        ApplicationType::Server
+        self.config.application_type()
     }
 
     /// Get the gateway server URI.
@@ -272,18 +365,28 @@ impl ServerInfo {
         self.state() == ServerStateType::Running
     }
 
-    /// Get the base endpoint, i.e. the configured host + current port.
+    /// Get the base endpoint, i.e. the current URL scheme + configured host + current port.
     pub fn base_endpoint(&self) -> String {
         format!(
-            "opc.tcp://{}:{}",
+            "{}://{}:{}",
+            self.url_scheme.load(),
             self.config.tcp_config.host,
             self.port.load(Ordering::Relaxed)
         )
     }
 
+    /// Get the transport profile URI of the active transport.
+    pub fn transport_profile_uri(&self) -> &'static str {
+        match self.url_scheme.load().as_str() {
+            OPC_WSS_SCHEME => profiles::TRANSPORT_PROFILE_URI_WSS_BINARY,
+            HTTPS_SCHEME => profiles::TRANSPORT_PROFILE_URI_HTTPS_BINARY,
+            _ => profiles::TRANSPORT_PROFILE_URI_BINARY,
+        }
+    }
+
     /// Get the server certificate as a byte string.
     pub fn server_certificate_as_byte_string(&self) -> ByteString {
-        if let Some(ref server_certificate) = self.server_certificate {
+        if let Some(server_certificate) = self.server_certificate.load().as_deref() {
             server_certificate.as_byte_string()
         } else {
             ByteString::null()
@@ -350,7 +453,7 @@ impl ServerInfo {
                     self.authenticate_username_identity_token(
                         endpoint,
                         &token,
-                        &self.server_pkey,
+                        self.server_pkey.load_full().as_deref(),
                         server_nonce,
                     )
                     .await
@@ -360,7 +463,7 @@ impl ServerInfo {
                         endpoint,
                         &token,
                         &request.user_token_signature,
-                        &self.server_certificate,
+                        self.server_certificate.load_full().as_deref(),
                         server_nonce,
                     )
                     .await
@@ -414,10 +517,10 @@ impl ServerInfo {
         &self,
         endpoint: &ServerEndpoint,
         token: &UserNameIdentityToken,
-        server_key: &Option<PrivateKey>,
+        server_key: Option<&PrivateKey>,
         server_nonce: &ByteString,
     ) -> Result<UserToken, Error> {
-        if !self.authenticator.supports_user_pass(endpoint) {
+        if !is_user_pass_allowed(endpoint) || !self.authenticator.supports_user_pass(endpoint) {
             Err(Error::new(
                 StatusCode::BadIdentityTokenRejected,
                 "Endpoint doesn't support username password tokens",
@@ -439,7 +542,7 @@ impl ServerInfo {
                 token.encryption_algorithm.as_ref()
             );
             let token_password = if !token.encryption_algorithm.is_null() {
-                if let Some(ref server_key) = server_key {
+                if let Some(server_key) = server_key {
                     user_identity::decrypt_user_identity_token_password(
                         token,
                         server_nonce.as_ref(),
@@ -473,7 +576,7 @@ impl ServerInfo {
         endpoint: &ServerEndpoint,
         token: &X509IdentityToken,
         user_token_signature: &SignatureData,
-        server_certificate: &Option<X509>,
+        server_certificate: Option<&X509>,
         server_nonce: &ByteString,
     ) -> Result<UserToken, Error> {
         if !self.authenticator.supports_x509(endpoint) {
@@ -490,7 +593,7 @@ impl ServerInfo {
             ))
         } else {
             match server_certificate {
-                Some(ref server_certificate) => {
+                Some(server_certificate) => {
                     // Find the security policy used for verifying tokens
                     let user_identity_tokens = self.authenticator.user_token_policies(endpoint);
                     let security_policy = user_identity_tokens
@@ -550,3 +653,7 @@ impl ServerInfo {
         audit_log.raise_and_log(event)
     } */
 }
+
+fn is_localhost(hostname: &str) -> bool {
+    hostname.eq_ignore_ascii_case("localhost") || hostname == "127.0.0.1" || hostname == "[::1]"
+}
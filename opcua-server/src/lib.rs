@@ -11,14 +11,19 @@
 //! See docs for the main `opcua` crate for details on usage.
 
 pub mod address_space;
+pub mod aggregates;
 pub mod authenticator;
 mod builder;
+pub mod certificate_manager;
+pub mod conditions;
 mod config;
-#[cfg(feature = "discovery-server-registration")]
+mod diagnostics;
 mod discovery;
 mod identity_token;
 mod info;
 pub mod node_manager;
+pub mod redundancy;
+pub mod roles;
 mod server;
 mod server_handle;
 mod server_status;
@@ -28,6 +33,10 @@ mod transport;
 
 pub use builder::ServerBuilder;
 pub use config::*;
+pub use diagnostics::{ServerDiagnostics, SessionDiagnostics};
+#[cfg(feature = "mdns")]
+pub use discovery::MdnsSdResponder;
+pub use discovery::{MdnsEvent, MdnsResponder, MdnsServerRecord, ServerRegistry};
 pub use opcua_types::event_field::EventField;
 pub use server::Server;
 pub use server_handle::ServerHandle;
@@ -37,6 +46,11 @@ pub use subscriptions::{
     CreateMonitoredItem, MonitoredItem, MonitoredItemHandle, SessionSubscriptions, Subscription,
     SubscriptionCache, SubscriptionState,
 };
+#[cfg(feature = "https")]
+pub use transport::HttpsListener;
+pub use transport::Listener;
+#[cfg(feature = "websocket")]
+pub use transport::WebSocketListener;
 
 /// Contains constaints for default configuration values.
 /// These are for the most part possible to override through server configuration.
@@ -8,11 +8,13 @@ use crate::{
 };
 use opcua_core::{sync::RwLock, trace_read_lock};
 use opcua_nodes::TypeTree;
-use opcua_types::{BrowseDescriptionResultMask, NodeId};
+use opcua_types::{
+    BrowseDescriptionResultMask, MessageSecurityMode, NodeId, StatusCode, ViewDescription,
+};
 use parking_lot::lock_api::{RawRwLock, RwLockReadGuard};
 
 use super::{
-    view::{ExternalReferenceRequest, NodeMetadata},
+    view::{ExternalReferenceRequest, NodeMetadata, ViewContents},
     DefaultTypeTree, NodeManagers,
 };
 
@@ -67,6 +69,10 @@ pub struct RequestContext {
     pub authenticator: Arc<dyn AuthManager>,
     /// The current user token.
     pub token: UserToken,
+    /// The roles granted to the current user.
+    pub roles: Arc<[NodeId]>,
+    /// The message security mode of the session.
+    pub security_mode: MessageSecurityMode,
     /// Index of the current node manager.
     pub current_node_manager_index: usize,
     /// Global type tree object.
@@ -109,3 +115,42 @@ pub(crate) async fn resolve_external_references(
 
     res.into_iter().map(|r| r.into_inner()).collect()
 }
+
+/// Resolve the contents of the view given by `view`, returns `None` if
+/// no view was specified.
+pub(crate) async fn resolve_view(
+    context: &RequestContext,
+    node_managers: &NodeManagers,
+    view: &ViewDescription,
+) -> Result<Option<Arc<ViewContents>>, StatusCode> {
+    if view.view_id.is_null() {
+        if !view.timestamp.is_null() || view.view_version != 0 {
+            return Err(StatusCode::BadViewIdUnknown);
+        }
+        return Ok(None);
+    }
+    // The server does not keep a history of the address space, so
+    // views at a specific point in time are not supported.
+    if !view.timestamp.is_null() {
+        return Err(StatusCode::BadViewTimestampInvalid);
+    }
+    if !node_managers.iter().any(|nm| nm.owns_node(&view.view_id)) {
+        return Err(StatusCode::BadViewIdUnknown);
+    }
+
+    let mut contents = ViewContents::new(view.view_id.clone(), view.view_version);
+    loop {
+        let before = contents.len();
+        for nm in node_managers.iter() {
+            nm.resolve_view(context, &mut contents).await?;
+        }
+        // Stop once every node is expanded, or when no node manager made any progress,
+        // meaning the remaining nodes are not owned by anyone.
+        let after = contents.len();
+        if after.1 == 0 || after == before {
+            break;
+        }
+    }
+
+    Ok(Some(Arc::new(contents)))
+}
@@ -4,9 +4,9 @@ use opcua_types::{
     match_extension_object_owned, ByteString, DeleteAtTimeDetails, DeleteEventDetails,
     DeleteRawModifiedDetails, DynEncodable, ExtensionObject, HistoryData, HistoryEvent,
     HistoryModifiedData, HistoryReadResult, HistoryReadValueId, HistoryUpdateResult, NodeId,
-    NumericRange, QualifiedName, ReadAnnotationDataDetails, ReadAtTimeDetails, ReadEventDetails,
-    ReadProcessedDetails, ReadRawModifiedDetails, StatusCode, UpdateDataDetails,
-    UpdateEventDetails, UpdateStructureDataDetails,
+    NumericRange, PerformUpdateType, PermissionType, QualifiedName, ReadAnnotationDataDetails,
+    ReadAtTimeDetails, ReadEventDetails, ReadProcessedDetails, ReadRawModifiedDetails, StatusCode,
+    UpdateDataDetails, UpdateEventDetails, UpdateStructureDataDetails,
 };
 
 /// Container for a single node in a history read request.
@@ -14,6 +14,7 @@ pub struct HistoryNode {
     node_id: NodeId,
     index_range: NumericRange,
     data_encoding: QualifiedName,
+    aggregate_type: Option<NodeId>,
     input_continuation_point: Option<ContinuationPoint>,
     next_continuation_point: Option<ContinuationPoint>,
     result: Option<ExtensionObject>,
@@ -83,6 +84,26 @@ impl HistoryUpdateDetails {
             HistoryUpdateDetails::DeleteEvent(d) => &d.node_id,
         }
     }
+
+    /// Get the permission required to perform this history update.
+    pub fn required_permission(&self) -> PermissionType {
+        let update_type = match self {
+            HistoryUpdateDetails::UpdateData(d) => d.perform_insert_replace,
+            HistoryUpdateDetails::UpdateStructureData(d) => d.perform_insert_replace,
+            HistoryUpdateDetails::UpdateEvent(d) => d.perform_insert_replace,
+            HistoryUpdateDetails::DeleteRawModified(_)
+            | HistoryUpdateDetails::DeleteAtTime(_)
+            | HistoryUpdateDetails::DeleteEvent(_) => PerformUpdateType::Remove,
+        };
+        match update_type {
+            PerformUpdateType::Insert => PermissionType::InsertHistory,
+            PerformUpdateType::Replace => PermissionType::ModifyHistory,
+            PerformUpdateType::Update => {
+                PermissionType::InsertHistory | PermissionType::ModifyHistory
+            }
+            PerformUpdateType::Remove => PermissionType::DeleteHistory,
+        }
+    }
 }
 
 /// Trait for values storable as history data.
@@ -121,6 +142,7 @@ impl HistoryNode {
             node_id: node.node_id,
             index_range,
             data_encoding: node.data_encoding,
+            aggregate_type: None,
             input_continuation_point: cp,
             next_continuation_point: None,
             result: None,
@@ -143,6 +165,16 @@ impl HistoryNode {
         &self.data_encoding
     }
 
+    /// Get the aggregate to calculate for this node, only set for
+    /// `ReadProcessed` requests.
+    pub fn aggregate_type(&self) -> Option<&NodeId> {
+        self.aggregate_type.as_ref()
+    }
+
+    pub(crate) fn set_aggregate_type(&mut self, aggregate_type: NodeId) {
+        self.aggregate_type = Some(aggregate_type);
+    }
+
     /// Get the current continuation point.
     pub fn continuation_point(&self) -> Option<&ContinuationPoint> {
         self.input_continuation_point.as_ref()
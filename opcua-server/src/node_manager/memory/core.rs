@@ -7,19 +7,28 @@ use opcua_nodes::NodeType;
 
 use crate::{
     address_space::{read_node_value, AddressSpace, CoreNamespace},
+    aggregates::AggregateFunction,
+    certificate_manager::CertificateManager,
+    diagnostics::{
+        all_session_diagnostics, all_session_security_diagnostics, server_diagnostics_summary,
+    },
+    info::ServerInfo,
     load_method_args,
     node_manager::{
         MethodCall, MonitoredItemRef, MonitoredItemUpdateRef, NodeManagersRef, ParsedReadValueId,
         RequestContext, ServerContext, SyncSampler,
     },
+    roles::{security_admin_permissions, RoleSet},
+    session::manager::SessionManager,
     subscriptions::CreateMonitoredItem,
     ServerCapabilities, ServerStatusWrapper,
 };
 use opcua_core::{sync::RwLock, trace_lock};
 use opcua_types::{
     DataValue, DateTime, ExtensionObject, IdType, Identifier, MethodId, MonitoringMode,
-    NumericRange, ObjectId, ReferenceTypeId, StatusCode, TimeZoneDataType, TimestampsToReturn,
-    VariableId, Variant, VariantScalarTypeId, VariantTypeId,
+    NumericRange, ObjectId, PermissionType, ReferenceTypeId, ServerDiagnosticsSummaryDataType,
+    StatusCode, TimeZoneDataType, TimestampsToReturn, VariableId, Variant, VariantScalarTypeId,
+    VariantTypeId,
 };
 
 use super::{
@@ -31,6 +40,8 @@ pub struct CoreNodeManagerImpl {
     sampler: SyncSampler,
     node_managers: NodeManagersRef,
     status: Arc<ServerStatusWrapper>,
+    session_manager: Arc<RwLock<SessionManager>>,
+    info: Arc<ServerInfo>,
 }
 
 /// Node manager for the core namespace.
@@ -48,7 +59,12 @@ impl InMemoryNodeManagerImplBuilder for CoreNodeManagerBuilder {
             address_space.import_node_set(&CoreNamespace, type_tree.namespaces_mut());
         }
 
-        CoreNodeManagerImpl::new(context.node_managers.clone(), context.status.clone())
+        CoreNodeManagerImpl::new(
+            context.node_managers.clone(),
+            context.status.clone(),
+            context.session_manager.clone(),
+            context.info.clone(),
+        )
     }
 }
 
@@ -80,6 +96,17 @@ impl InMemoryNodeManagerImpl for CoreNodeManagerImpl {
         // Some core methods should be generally executable
         Self::set_method_executable(address_space, MethodId::Server_GetMonitoredItems);
         Self::set_method_executable(address_space, MethodId::Server_ResendData);
+        Self::add_condition_methods(address_space);
+        // The security diagnostics of every session may only be read by security administrators.
+        if let Some(node) = address_space.find_mut(
+            VariableId::Server_ServerDiagnostics_SessionsDiagnosticsSummary_SessionSecurityDiagnosticsArray,
+        ) {
+            node.as_mut_node()
+                .set_role_permissions(Some(security_admin_permissions(PermissionType::Read)));
+        }
+        RoleSet::init_address_space(address_space);
+        CertificateManager::init_address_space(address_space);
+        context.info.redundancy.init_address_space(address_space);
     }
 
     fn namespaces(&self) -> Vec<NamespaceMetadata> {
@@ -101,6 +128,10 @@ impl InMemoryNodeManagerImpl for CoreNodeManagerImpl {
         "core"
     }
 
+    fn session_closed(&self, session_id: u32) {
+        self.info.certificate_manager.session_closed(session_id);
+    }
+
     async fn read_values(
         &self,
         context: &RequestContext,
@@ -213,11 +244,18 @@ impl InMemoryNodeManagerImpl for CoreNodeManagerImpl {
 }
 
 impl CoreNodeManagerImpl {
-    pub(super) fn new(node_managers: NodeManagersRef, status: Arc<ServerStatusWrapper>) -> Self {
+    pub(super) fn new(
+        node_managers: NodeManagersRef,
+        status: Arc<ServerStatusWrapper>,
+        session_manager: Arc<RwLock<SessionManager>>,
+        info: Arc<ServerInfo>,
+    ) -> Self {
         Self {
             sampler: SyncSampler::new(),
             status,
             node_managers,
+            session_manager,
+            info,
         }
     }
 
@@ -436,6 +474,69 @@ impl CoreNodeManagerImpl {
                 (self.status.state() as i32).into()
             }
 
+            // Server diagnostics
+            VariableId::Server_ServerDiagnostics_EnabledFlag => true.into(),
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary => {
+                ExtensionObject::from_message(self.diagnostics_summary(context)).into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_CumulatedSessionCount => {
+                self.diagnostics_summary(context).cumulated_session_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_CumulatedSubscriptionCount => {
+                self.diagnostics_summary(context).cumulated_subscription_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_CurrentSessionCount => {
+                self.diagnostics_summary(context).current_session_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_CurrentSubscriptionCount => {
+                self.diagnostics_summary(context).current_subscription_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_PublishingIntervalCount => {
+                self.diagnostics_summary(context).publishing_interval_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_RejectedRequestsCount => {
+                self.diagnostics_summary(context).rejected_requests_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_RejectedSessionCount => {
+                self.diagnostics_summary(context).rejected_session_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_SecurityRejectedRequestsCount => {
+                self.diagnostics_summary(context).security_rejected_requests_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_SecurityRejectedSessionCount => {
+                self.diagnostics_summary(context).security_rejected_session_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_ServerViewCount => {
+                self.diagnostics_summary(context).server_view_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_SessionAbortCount => {
+                self.diagnostics_summary(context).session_abort_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_ServerDiagnosticsSummary_SessionTimeoutCount => {
+                self.diagnostics_summary(context).session_timeout_count.into()
+            }
+            VariableId::Server_ServerDiagnostics_SessionsDiagnosticsSummary_SessionDiagnosticsArray => {
+                all_session_diagnostics(&self.session_manager, &context.info, &context.subscriptions)
+                    .into_iter()
+                    .map(ExtensionObject::from_message)
+                    .collect::<Vec<_>>()
+                    .into()
+            }
+            VariableId::Server_ServerDiagnostics_SessionsDiagnosticsSummary_SessionSecurityDiagnosticsArray => {
+                all_session_security_diagnostics(&self.session_manager)
+                    .into_iter()
+                    .map(ExtensionObject::from_message)
+                    .collect::<Vec<_>>()
+                    .into()
+            }
+            VariableId::Server_ServerDiagnostics_SubscriptionDiagnosticsArray => {
+                context.subscriptions.subscription_diagnostics(None)
+                    .into_iter()
+                    .map(ExtensionObject::from_message)
+                    .collect::<Vec<_>>()
+                    .into()
+            }
+
             VariableId::Server_NamespaceArray => {
                 // This actually calls into other node managers to obtain the value, in fact
                 // it calls into _this_ node manager as well.
@@ -450,7 +551,12 @@ impl CoreNodeManagerImpl {
                 namespaces.into()
             }
 
-            _ => return None,
+            _ => context
+                .info
+                .roles
+                .read_identities(var_id)
+                .or_else(|| context.info.certificate_manager.read_value(&context.info, var_id))
+                .or_else(|| context.info.redundancy.read_value(var_id))?,
         };
 
         let v = if !matches!(node.index_range, NumericRange::None) {
@@ -477,7 +583,24 @@ impl CoreNodeManagerImpl {
         })
     }
 
+    fn diagnostics_summary(&self, context: &RequestContext) -> ServerDiagnosticsSummaryDataType {
+        server_diagnostics_summary(&self.session_manager, &context.info, &context.subscriptions)
+    }
+
     fn add_aggregates(&self, address_space: &mut AddressSpace, capabilities: &ServerCapabilities) {
+        // Aggregates calculated by the server itself, for monitored items with an AggregateFilter.
+        for aggregate in AggregateFunction::ALL {
+            for folder in [
+                ObjectId::Server_ServerCapabilities_AggregateFunctions,
+                ObjectId::HistoryServerCapabilities_AggregateFunctions,
+            ] {
+                address_space.insert_reference(
+                    &folder.into(),
+                    &aggregate.object_id().into(),
+                    ReferenceTypeId::Organizes,
+                )
+            }
+        }
         for aggregate in &capabilities.history.aggregates {
             address_space.insert_reference(
                 &ObjectId::HistoryServerCapabilities_AggregateFunctions.into(),
@@ -487,6 +610,20 @@ impl CoreNodeManagerImpl {
         }
     }
 
+    fn add_condition_methods(address_space: &mut AddressSpace) {
+        for method in [
+            MethodId::ConditionType_Enable,
+            MethodId::ConditionType_Disable,
+            MethodId::ConditionType_AddComment,
+            MethodId::AcknowledgeableConditionType_Acknowledge,
+            MethodId::AcknowledgeableConditionType_Confirm,
+            MethodId::ConditionType_ConditionRefresh,
+            MethodId::ConditionType_ConditionRefresh2,
+        ] {
+            Self::set_method_executable(address_space, method);
+        }
+    }
+
     fn set_method_executable(address_space: &mut AddressSpace, method: MethodId) {
         let Some(NodeType::Method(m)) = address_space.find_mut(method) else {
             return;
@@ -531,6 +668,19 @@ impl CoreNodeManagerImpl {
                 sub.set_resend_data();
                 call.set_status(StatusCode::Good);
             }
+            MethodId::ConditionType_Enable
+            | MethodId::ConditionType_Disable
+            | MethodId::ConditionType_AddComment
+            | MethodId::AcknowledgeableConditionType_Acknowledge
+            | MethodId::AcknowledgeableConditionType_Confirm
+            | MethodId::ConditionType_ConditionRefresh
+            | MethodId::ConditionType_ConditionRefresh2 => {
+                context.info.conditions.call(context, call)?;
+            }
+            id if RoleSet::is_role_method(id) => context.info.roles.call(call)?,
+            id if CertificateManager::is_certificate_method(id) => {
+                context.info.certificate_manager.call(context, call)?
+            }
             _ => return Err(StatusCode::BadNotSupported),
         }
         Ok(())
@@ -5,11 +5,12 @@ use std::{
 };
 
 use async_trait::async_trait;
-use opcua_core::trace_read_lock;
+use opcua_core::{sync::RwLock, trace_read_lock};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     address_space::AccessLevel,
+    diagnostics::session_diagnostics,
     node_manager::{
         as_opaque_node_id,
         build::NodeManagerBuilder,
@@ -19,8 +20,10 @@ use crate::{
             NodeMetadata,
         },
         BrowseNode, BrowsePathItem, DefaultTypeTree, DynNodeManager, NodeManager, NodeManagersRef,
-        ReadNode, RequestContext, ServerContext, SyncSampler,
+        QueryRequest, ReadNode, RequestContext, ServerContext, SyncSampler,
     },
+    roles::is_security_admin,
+    session::{instance::Session, manager::SessionManager},
 };
 use opcua_types::{
     AccessLevelExType, AccessRestrictionType, AttributeId, BrowseDirection, DataTypeId, DataValue,
@@ -35,6 +38,7 @@ use opcua_types::{
 pub struct DiagnosticsNodeManager {
     sampler: SyncSampler,
     node_managers: NodeManagersRef,
+    session_manager: Arc<RwLock<SessionManager>>,
     namespace_index: u16,
 }
 
@@ -90,9 +94,72 @@ struct NamespaceNode {
     property: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+enum SessionComponent {
+    SessionDiagnostics,
+    SessionSecurityDiagnostics,
+    SubscriptionDiagnosticsArray,
+}
+
+impl SessionComponent {
+    const ALL: [SessionComponent; 3] = [
+        SessionComponent::SessionDiagnostics,
+        SessionComponent::SessionSecurityDiagnostics,
+        SessionComponent::SubscriptionDiagnosticsArray,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SessionComponent::SessionDiagnostics => "SessionDiagnostics",
+            SessionComponent::SessionSecurityDiagnostics => "SessionSecurityDiagnostics",
+            SessionComponent::SubscriptionDiagnosticsArray => "SubscriptionDiagnosticsArray",
+        }
+    }
+
+    fn type_definition(self) -> (VariableTypeId, &'static str) {
+        match self {
+            SessionComponent::SessionDiagnostics => (
+                VariableTypeId::SessionDiagnosticsVariableType,
+                "SessionDiagnosticsVariableType",
+            ),
+            SessionComponent::SessionSecurityDiagnostics => (
+                VariableTypeId::SessionSecurityDiagnosticsType,
+                "SessionSecurityDiagnosticsType",
+            ),
+            SessionComponent::SubscriptionDiagnosticsArray => (
+                VariableTypeId::SubscriptionDiagnosticsArrayType,
+                "SubscriptionDiagnosticsArrayType",
+            ),
+        }
+    }
+
+    fn data_type(self) -> DataTypeId {
+        match self {
+            SessionComponent::SessionDiagnostics => DataTypeId::SessionDiagnosticsDataType,
+            SessionComponent::SessionSecurityDiagnostics => {
+                DataTypeId::SessionSecurityDiagnosticsDataType
+            }
+            SessionComponent::SubscriptionDiagnosticsArray => {
+                DataTypeId::SubscriptionDiagnosticsDataType
+            }
+        }
+    }
+
+    fn is_array(self) -> bool {
+        matches!(self, SessionComponent::SubscriptionDiagnosticsArray)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SessionNode {
+    session_id: u32,
+    component: Option<SessionComponent>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 enum DiagnosticsNode {
     Namespace(NamespaceNode),
+    Session(SessionNode),
 }
 
 /// Builder for the diagnostics node manager.
@@ -115,6 +182,7 @@ impl DiagnosticsNodeManager {
         Self {
             sampler: SyncSampler::new(),
             node_managers: context.node_managers.clone(),
+            session_manager: context.session_manager.clone(),
             namespace_index,
         }
     }
@@ -550,6 +618,321 @@ impl DiagnosticsNodeManager {
             self.read_namespace_metadata_node(start_time, node_to_read, namespace);
         }
     }
+
+    fn session_node_id(&self, session_id: u32, component: Option<SessionComponent>) -> NodeId {
+        as_opaque_node_id(
+            &DiagnosticsNode::Session(SessionNode {
+                session_id,
+                component,
+            }),
+            self.namespace_index,
+        )
+        .unwrap_or_default()
+    }
+
+    fn find_session(&self, session_id: u32) -> Option<Arc<RwLock<Session>>> {
+        trace_read_lock!(self.session_manager).find_by_id(session_id)
+    }
+
+    fn session_node_metadata(&self, session: &Session) -> NodeMetadata {
+        NodeMetadata {
+            node_id: self
+                .session_node_id(session.session_id_numeric(), None)
+                .into(),
+            type_definition: ObjectTypeId::SessionDiagnosticsObjectType.into(),
+            browse_name: QualifiedName::new(self.namespace_index, session.session_name()),
+            display_name: LocalizedText::new("", session.session_name()),
+            node_class: NodeClass::Object,
+        }
+    }
+
+    fn session_component_metadata(
+        &self,
+        session_id: u32,
+        component: SessionComponent,
+    ) -> NodeMetadata {
+        NodeMetadata {
+            node_id: self.session_node_id(session_id, Some(component)).into(),
+            type_definition: component.type_definition().0.into(),
+            browse_name: QualifiedName::new(0, component.name()),
+            display_name: LocalizedText::new("", component.name()),
+            node_class: NodeClass::Variable,
+        }
+    }
+
+    fn browse_sessions(&self, node_to_browse: &mut BrowseNode, type_tree: &DefaultTypeTree) {
+        if !matches!(
+            node_to_browse.browse_direction(),
+            BrowseDirection::Forward | BrowseDirection::Both
+        ) {
+            return;
+        }
+
+        if !node_to_browse.allows_reference_type(&ReferenceTypeId::HasComponent.into(), type_tree)
+            || !node_to_browse.allows_node_class(NodeClass::Object)
+        {
+            return;
+        }
+
+        let sessions: Vec<_> = trace_read_lock!(self.session_manager)
+            .sessions()
+            .cloned()
+            .collect();
+
+        let mut cp = BrowseContinuationPoint::default();
+
+        for session in sessions {
+            let metadata = self.session_node_metadata(&*trace_read_lock!(session));
+            let ref_desc = metadata.into_ref_desc(true, ReferenceTypeId::HasComponent);
+
+            if let AddReferenceResult::Full(c) = node_to_browse.add(type_tree, ref_desc) {
+                cp.nodes.push_back(c);
+            }
+        }
+
+        if !cp.nodes.is_empty() {
+            node_to_browse.set_next_continuation_point(Box::new(cp));
+        }
+    }
+
+    fn browse_session_node(
+        &self,
+        node_to_browse: &mut BrowseNode,
+        type_tree: &DefaultTypeTree,
+        session_node: &SessionNode,
+    ) {
+        let Some(session) = self.find_session(session_node.session_id) else {
+            node_to_browse.set_status(StatusCode::BadNodeIdUnknown);
+            return;
+        };
+
+        let mut cp = BrowseContinuationPoint::default();
+        let mut add = |node_to_browse: &mut BrowseNode, ref_desc: ReferenceDescription| {
+            if let AddReferenceResult::Full(c) = node_to_browse.add(type_tree, ref_desc) {
+                cp.nodes.push_back(c);
+            }
+        };
+
+        if matches!(
+            node_to_browse.browse_direction(),
+            BrowseDirection::Forward | BrowseDirection::Both
+        ) {
+            match session_node.component {
+                Some(component) => {
+                    let (type_id, type_name) = component.type_definition();
+                    add(
+                        node_to_browse,
+                        ReferenceDescription {
+                            reference_type_id: ReferenceTypeId::HasTypeDefinition.into(),
+                            is_forward: true,
+                            node_id: type_id.into(),
+                            browse_name: QualifiedName::new(0, type_name),
+                            display_name: LocalizedText::new("", type_name),
+                            node_class: NodeClass::VariableType,
+                            type_definition: ExpandedNodeId::null(),
+                        },
+                    );
+                }
+                None => {
+                    for component in SessionComponent::ALL {
+                        let metadata =
+                            self.session_component_metadata(session_node.session_id, component);
+                        add(
+                            node_to_browse,
+                            metadata.into_ref_desc(true, ReferenceTypeId::HasComponent),
+                        );
+                    }
+                    add(
+                        node_to_browse,
+                        ReferenceDescription {
+                            reference_type_id: ReferenceTypeId::HasTypeDefinition.into(),
+                            is_forward: true,
+                            node_id: ObjectTypeId::SessionDiagnosticsObjectType.into(),
+                            browse_name: QualifiedName::new(0, "SessionDiagnosticsObjectType"),
+                            display_name: LocalizedText::new("", "SessionDiagnosticsObjectType"),
+                            node_class: NodeClass::ObjectType,
+                            type_definition: ExpandedNodeId::null(),
+                        },
+                    );
+                }
+            }
+        }
+
+        if matches!(
+            node_to_browse.browse_direction(),
+            BrowseDirection::Inverse | BrowseDirection::Both
+        ) {
+            let ref_desc = match session_node.component {
+                Some(_) => self
+                    .session_node_metadata(&*trace_read_lock!(session))
+                    .into_ref_desc(false, ReferenceTypeId::HasComponent),
+                None => ReferenceDescription {
+                    reference_type_id: ReferenceTypeId::HasComponent.into(),
+                    is_forward: false,
+                    node_id: ObjectId::Server_ServerDiagnostics_SessionsDiagnosticsSummary.into(),
+                    browse_name: QualifiedName::new(0, "SessionsDiagnosticsSummary"),
+                    display_name: LocalizedText::new("", "SessionsDiagnosticsSummary"),
+                    node_class: NodeClass::Object,
+                    type_definition: ObjectTypeId::SessionsDiagnosticsSummaryType.into(),
+                },
+            };
+            add(node_to_browse, ref_desc);
+        }
+
+        if !cp.nodes.is_empty() {
+            node_to_browse.set_next_continuation_point(Box::new(cp));
+        }
+    }
+
+    fn read_session_object_node(
+        &self,
+        node_to_read: &mut ReadNode,
+        session: &Session,
+        now: DateTime,
+    ) {
+        let v: Variant = match node_to_read.node().attribute_id {
+            AttributeId::NodeId => self
+                .session_node_id(session.session_id_numeric(), None)
+                .into(),
+            AttributeId::NodeClass => (NodeClass::Object as i32).into(),
+            AttributeId::BrowseName => {
+                QualifiedName::new(self.namespace_index, session.session_name()).into()
+            }
+            AttributeId::DisplayName => LocalizedText::new("", session.session_name()).into(),
+            AttributeId::EventNotifier => 0u8.into(),
+            AttributeId::WriteMask | AttributeId::UserWriteMask => 0u32.into(),
+            _ => {
+                node_to_read.set_error(StatusCode::BadAttributeIdInvalid);
+                return;
+            }
+        };
+
+        node_to_read.set_result(DataValue {
+            value: Some(v),
+            status: Some(StatusCode::Good),
+            source_timestamp: Some(now),
+            source_picoseconds: None,
+            server_timestamp: Some(now),
+            server_picoseconds: None,
+        });
+    }
+
+    fn read_session_component_node(
+        &self,
+        context: &RequestContext,
+        node_to_read: &mut ReadNode,
+        session: &RwLock<Session>,
+        session_id: u32,
+        component: SessionComponent,
+        now: DateTime,
+    ) {
+        // Security diagnostics expose the identity and certificate of the client,
+        // so only the session itself and security administrators may read them.
+        let is_readable = !matches!(component, SessionComponent::SessionSecurityDiagnostics)
+            || session_id == context.session_id
+            || is_security_admin(&context.roles);
+        let v: Variant = match node_to_read.node().attribute_id {
+            AttributeId::NodeId => self.session_node_id(session_id, Some(component)).into(),
+            AttributeId::NodeClass => (NodeClass::Variable as i32).into(),
+            AttributeId::BrowseName => QualifiedName::new(0, component.name()).into(),
+            AttributeId::DisplayName => LocalizedText::new("", component.name()).into(),
+            AttributeId::Value if !is_readable => {
+                node_to_read.set_error(StatusCode::BadUserAccessDenied);
+                return;
+            }
+            AttributeId::Value => {
+                let value: Variant = match component {
+                    SessionComponent::SessionDiagnostics => ExtensionObject::from_message(
+                        session_diagnostics(session, &context.info, &context.subscriptions),
+                    )
+                    .into(),
+                    SessionComponent::SessionSecurityDiagnostics => ExtensionObject::from_message(
+                        trace_read_lock!(session).security_diagnostics_data(),
+                    )
+                    .into(),
+                    SessionComponent::SubscriptionDiagnosticsArray => context
+                        .subscriptions
+                        .subscription_diagnostics(Some(session_id))
+                        .into_iter()
+                        .map(ExtensionObject::from_message)
+                        .collect::<Vec<_>>()
+                        .into(),
+                };
+                match value.range_of(&node_to_read.node().index_range) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        node_to_read.set_error(e);
+                        return;
+                    }
+                }
+            }
+            AttributeId::DataType => Variant::NodeId(Box::new(component.data_type().into())),
+            AttributeId::ValueRank => {
+                if component.is_array() {
+                    1.into()
+                } else {
+                    (-1).into()
+                }
+            }
+            AttributeId::ArrayDimensions => {
+                if component.is_array() {
+                    vec![0u32].into()
+                } else {
+                    Variant::Empty
+                }
+            }
+            AttributeId::AccessLevel => AccessLevel::CURRENT_READ.bits().into(),
+            AttributeId::UserAccessLevel => {
+                if is_readable {
+                    AccessLevel::CURRENT_READ.bits().into()
+                } else {
+                    AccessLevel::empty().bits().into()
+                }
+            }
+            AttributeId::AccessLevelEx => (AccessLevelExType::CurrentRead.bits() as u32).into(),
+            AttributeId::MinimumSamplingInterval => 0.0.into(),
+            AttributeId::Historizing => false.into(),
+            AttributeId::WriteMask | AttributeId::UserWriteMask => 0u32.into(),
+            _ => {
+                node_to_read.set_error(StatusCode::BadAttributeIdInvalid);
+                return;
+            }
+        };
+
+        node_to_read.set_result(DataValue {
+            value: Some(v),
+            status: Some(StatusCode::Good),
+            source_timestamp: Some(now),
+            source_picoseconds: None,
+            server_timestamp: Some(now),
+            server_picoseconds: None,
+        });
+    }
+
+    fn read_session_node(
+        &self,
+        context: &RequestContext,
+        node_to_read: &mut ReadNode,
+        session_node: &SessionNode,
+    ) {
+        let Some(session) = self.find_session(session_node.session_id) else {
+            node_to_read.set_error(StatusCode::BadNodeIdUnknown);
+            return;
+        };
+        let now = DateTime::now();
+
+        match session_node.component {
+            Some(component) => self.read_session_component_node(
+                context,
+                node_to_read,
+                &session,
+                session_node.session_id,
+                component,
+                now,
+            ),
+            None => self.read_session_object_node(node_to_read, &*trace_read_lock!(session), now),
+        }
+    }
 }
 
 #[async_trait]
@@ -615,6 +998,17 @@ impl NodeManager for DiagnosticsNodeManager {
                         self.namespace_node_metadata(ns_node)
                     }
                 }
+                DiagnosticsNode::Session(session_node) => {
+                    let Some(session) = self.find_session(session_node.session_id) else {
+                        continue;
+                    };
+                    match session_node.component {
+                        Some(component) => {
+                            self.session_component_metadata(session_node.session_id, component)
+                        }
+                        None => self.session_node_metadata(&*trace_read_lock!(session)),
+                    }
+                }
             };
             req.set(meta);
         }
@@ -642,14 +1036,18 @@ impl NodeManager for DiagnosticsNodeManager {
             }
 
             if node.node_id().namespace == 0 {
-                let namespaces = lazy_namespaces.get_or_insert_with(|| self.namespaces(context));
                 let Ok(obj_id) = node.node_id().as_object_id() else {
                     continue;
                 };
                 match obj_id {
                     ObjectId::Server_Namespaces => {
+                        let namespaces =
+                            lazy_namespaces.get_or_insert_with(|| self.namespaces(context));
                         self.browse_namespaces(node, &type_tree, namespaces);
                     }
+                    ObjectId::Server_ServerDiagnostics_SessionsDiagnosticsSummary => {
+                        self.browse_sessions(node, &type_tree);
+                    }
                     _ => continue,
                 }
             } else if node.node_id().namespace == self.namespace_index {
@@ -664,6 +1062,9 @@ impl NodeManager for DiagnosticsNodeManager {
                             lazy_namespaces.get_or_insert_with(|| self.namespaces(context));
                         self.browse_namespace_node(node, &type_tree, namespaces, &ns);
                     }
+                    DiagnosticsNode::Session(session_node) => {
+                        self.browse_session_node(node, &type_tree, &session_node);
+                    }
                 }
             }
         }
@@ -693,6 +1094,9 @@ impl NodeManager for DiagnosticsNodeManager {
                         lazy_namespaces.get_or_insert_with(|| self.namespaces(context));
                     self.read_namespace_node(start_time, node, namespaces, &ns);
                 }
+                DiagnosticsNode::Session(session_node) => {
+                    self.read_session_node(context, node, &session_node);
+                }
             }
         }
         Ok(())
@@ -705,4 +1109,14 @@ impl NodeManager for DiagnosticsNodeManager {
     ) -> Result<(), StatusCode> {
         impl_translate_browse_paths_using_browse(self, context, nodes).await
     }
+
+    async fn query(
+        &self,
+        _context: &RequestContext,
+        _request: &mut QueryRequest,
+    ) -> Result<(), StatusCode> {
+        // Diagnostics nodes are generated on the fly from server state,
+        // and are not included in query results.
+        Ok(())
+    }
 }
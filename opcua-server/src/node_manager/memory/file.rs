@@ -0,0 +1,999 @@
+//! Node manager exposing files and directories on the local file system as objects of
+//! `FileType` and `FileDirectoryType`, see Part 20 of the standard.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use hashbrown::HashMap;
+use log::{error, warn};
+use opcua_core::{
+    sync::{Mutex, RwLock},
+    trace_lock, trace_read_lock, trace_write_lock,
+};
+use opcua_types::{
+    Argument, BrowseDirection, ByteString, DataTypeId, DataValue, Identifier, NodeId, ObjectTypeId,
+    OpenFileMode, QualifiedName, ReferenceTypeId, StatusCode, TimestampsToReturn, VariableTypeId,
+    Variant, VariantScalarTypeId, VariantTypeId,
+};
+
+use crate::{
+    address_space::{read_node_value, AddressSpace, MethodBuilder, ObjectBuilder, VariableBuilder},
+    load_method_args,
+    node_manager::{
+        validate_open_file_mode, DefaultTypeTree, FileHandles, MethodCall, ParsedReadValueId,
+        RequestContext, ServerContext,
+    },
+};
+
+use super::{
+    InMemoryNodeManager, InMemoryNodeManagerImpl, InMemoryNodeManagerImplBuilder, NamespaceMetadata,
+};
+
+/// Node manager exposing files and directories on the local file system.
+pub type FileNodeManager = InMemoryNodeManager<FileNodeManagerImpl>;
+
+/// A file or directory given to the [FileNodeManagerBuilder].
+struct FileRoot {
+    path: PathBuf,
+    name: String,
+    parent: NodeId,
+    writable: bool,
+}
+
+/// Builder for the [FileNodeManager].
+///
+/// Each file or directory added to the builder is exposed as a child of a given parent
+/// node, with a string node ID equal to its name. Entries of a directory get node IDs
+/// made from the path of the entry relative to the exposed directory, i.e. `name/dir/file`.
+/// The components of these objects have the ID of the object followed by `//` and the
+/// browse name of the component.
+///
+/// The contents of directories are read when the server starts, and updated as clients
+/// create, delete and move files through the methods of the directory objects.
+/// Symbolic links are ignored.
+pub struct FileNodeManagerBuilder {
+    namespace: NamespaceMetadata,
+    name: String,
+    roots: Vec<FileRoot>,
+}
+
+impl FileNodeManagerBuilder {
+    /// Create a new file node manager builder with the given namespace and name.
+    pub fn new(namespace: NamespaceMetadata, name: &str) -> Self {
+        Self {
+            namespace,
+            name: name.to_owned(),
+            roots: Vec::new(),
+        }
+    }
+
+    /// Expose the file at `path` as a `FileType` object organized by `parent`, with
+    /// browse name `name`. If `writable` is false, the file can only be opened for reading.
+    pub fn add_file(
+        mut self,
+        path: impl Into<PathBuf>,
+        name: &str,
+        parent: impl Into<NodeId>,
+        writable: bool,
+    ) -> Self {
+        self.roots.push(FileRoot {
+            path: path.into(),
+            name: name.to_owned(),
+            parent: parent.into(),
+            writable,
+        });
+        self
+    }
+
+    /// Expose the directory at `path` and everything in it as a `FileDirectoryType`
+    /// object organized by `parent`, with browse name `name`. The directory is created
+    /// if it does not exist. If `writable` is false, files in the directory can only be
+    /// opened for reading, and nothing can be created, deleted or moved.
+    pub fn add_directory(
+        self,
+        path: impl Into<PathBuf>,
+        name: &str,
+        parent: impl Into<NodeId>,
+        writable: bool,
+    ) -> Self {
+        let path = path.into();
+        if let Err(e) = fs::create_dir_all(&path) {
+            error!("Failed to create directory {}: {e}", path.display());
+        }
+        self.add_file(path, name, parent, writable)
+    }
+}
+
+impl InMemoryNodeManagerImplBuilder for FileNodeManagerBuilder {
+    type Impl = FileNodeManagerImpl;
+
+    fn build(mut self, context: ServerContext, address_space: &mut AddressSpace) -> Self::Impl {
+        {
+            let mut type_tree = context.type_tree.write();
+            self.namespace.namespace_index = type_tree
+                .namespaces_mut()
+                .add_namespace(&self.namespace.namespace_uri);
+        }
+        address_space.add_namespace(
+            &self.namespace.namespace_uri,
+            self.namespace.namespace_index,
+        );
+        FileNodeManagerImpl {
+            namespace: self.namespace,
+            name: self.name,
+            roots: self.roots,
+            state: Mutex::new(FileNodeManagerState::default()),
+        }
+    }
+}
+
+/// A file opened by a session. Open files are shared with blocking tasks doing
+/// I/O on them, so that the state does not stay locked while the I/O runs.
+type SharedFile = Arc<Mutex<File>>;
+
+/// A file or directory in the address space.
+#[derive(Clone)]
+struct FileSystemNode {
+    path: PathBuf,
+    is_directory: bool,
+    writable: bool,
+    /// The directory containing this node, if it is not one of the exposed roots.
+    parent: Option<NodeId>,
+}
+
+#[derive(Default)]
+struct FileNodeManagerState {
+    /// Files and directories, by node ID.
+    nodes: HashMap<NodeId, FileSystemNode>,
+    /// Files opened by sessions.
+    handles: FileHandles<SharedFile>,
+}
+
+/// A file or directory read from the file system, to be added to the address space.
+struct FileEntry {
+    path: PathBuf,
+    /// The entries of a directory by name, sorted, or `None` for files.
+    children: Option<Vec<(String, FileEntry)>>,
+}
+
+impl FileEntry {
+    /// Read the file or directory at `path` and, for directories, everything in it.
+    /// This does blocking I/O.
+    fn scan(path: &Path) -> Self {
+        if !path.is_dir() {
+            return Self {
+                path: path.to_owned(),
+                children: None,
+            };
+        }
+        let mut entries: Vec<_> = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_file() || t.is_dir()))
+                .filter_map(|e| Some((e.file_name().into_string().ok()?, e.path())))
+                .collect(),
+            Err(e) => {
+                error!("Failed to read directory {}: {e}", path.display());
+                Vec::new()
+            }
+        };
+        entries.sort();
+        Self {
+            path: path.to_owned(),
+            children: Some(
+                entries
+                    .into_iter()
+                    .map(|(name, path)| (name, Self::scan(&path)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Implementation of the [FileNodeManager].
+///
+/// Files are opened by clients with the `Open` method of a file object, which returns
+/// a handle that is only valid in the session that opened it. Any number of sessions
+/// may open a file for reading, but only one may open it for writing. Handles left open
+/// by a session are released when the session is closed.
+///
+/// File system I/O is done in blocking tasks, without holding the lock on the address
+/// space, which is only locked to add and remove nodes.
+pub struct FileNodeManagerImpl {
+    namespace: NamespaceMetadata,
+    name: String,
+    roots: Vec<FileRoot>,
+    state: Mutex<FileNodeManagerState>,
+}
+
+#[async_trait]
+impl InMemoryNodeManagerImpl for FileNodeManagerImpl {
+    async fn init(&self, address_space: &mut AddressSpace, _context: ServerContext) {
+        let mut state = trace_lock!(self.state);
+        for root in &self.roots {
+            if root.name.is_empty() || root.name.contains('/') {
+                error!(
+                    "Invalid name {} for file {}",
+                    root.name,
+                    root.path.display()
+                );
+                continue;
+            }
+            let id = NodeId::new(self.namespace.namespace_index, root.name.clone());
+            if !root.path.exists() {
+                error!("File {} does not exist", root.path.display());
+                continue;
+            }
+            Self::add_node(
+                address_space,
+                &mut state,
+                &id,
+                &root.name,
+                &FileEntry::scan(&root.path),
+                &root.parent,
+                root.writable,
+                None,
+            );
+        }
+    }
+
+    fn namespaces(&self) -> Vec<NamespaceMetadata> {
+        vec![self.namespace.clone()]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn session_closed(&self, session_id: u32) {
+        trace_lock!(self.state).handles.remove_session(session_id);
+    }
+
+    async fn read_values(
+        &self,
+        context: &RequestContext,
+        address_space: &RwLock<AddressSpace>,
+        nodes: &[&ParsedReadValueId],
+        max_age: f64,
+        timestamps_to_return: TimestampsToReturn,
+    ) -> Vec<DataValue> {
+        let sizes = self.read_file_sizes(nodes).await;
+        let address_space = trace_read_lock!(address_space);
+        let state = trace_lock!(self.state);
+
+        nodes
+            .iter()
+            .map(|n| {
+                let node = match address_space.validate_node_read(context, n) {
+                    Ok(node) => node,
+                    Err(e) => {
+                        return DataValue {
+                            status: Some(e),
+                            ..Default::default()
+                        }
+                    }
+                };
+                match Self::read_file_property(&state, &sizes, &n.node_id) {
+                    Some(v) => DataValue::new_now(v),
+                    None => read_node_value(node, context, n, max_age, timestamps_to_return),
+                }
+            })
+            .collect()
+    }
+
+    async fn call(
+        &self,
+        context: &RequestContext,
+        address_space: &RwLock<AddressSpace>,
+        methods_to_call: &mut [&mut &mut MethodCall],
+    ) -> Result<(), StatusCode> {
+        for method in methods_to_call {
+            match self.call_method(context, address_space, method).await {
+                Ok(outputs) => {
+                    method.set_outputs(outputs);
+                    method.set_status(StatusCode::Good);
+                }
+                Err(e) => method.set_status(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FileNodeManagerImpl {
+    /// Read the sizes of the files whose `Size` property is in `nodes`, by the node ID
+    /// of the property.
+    async fn read_file_sizes(&self, nodes: &[&ParsedReadValueId]) -> HashMap<NodeId, u64> {
+        let paths: Vec<(NodeId, PathBuf)> = {
+            let state = trace_lock!(self.state);
+            nodes
+                .iter()
+                .filter_map(|n| {
+                    let (object, name) = split_component_id(&n.node_id)?;
+                    let node = state
+                        .nodes
+                        .get(&object)
+                        .filter(|f| !f.is_directory && name == "Size")?;
+                    Some((n.node_id.clone(), node.path.clone()))
+                })
+                .collect()
+        };
+        if paths.is_empty() {
+            return HashMap::new();
+        }
+        blocking(move || {
+            Ok(paths
+                .into_iter()
+                .map(|(id, path)| match fs::metadata(&path) {
+                    Ok(m) => (id, m.len()),
+                    Err(e) => {
+                        warn!("Failed to read size of {}: {e}", path.display());
+                        (id, 0)
+                    }
+                })
+                .collect())
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Get the value of the `Size` or `OpenCount` property of a file. Sizes are
+    /// read from the file system beforehand, with [Self::read_file_sizes].
+    fn read_file_property(
+        state: &FileNodeManagerState,
+        sizes: &HashMap<NodeId, u64>,
+        id: &NodeId,
+    ) -> Option<Variant> {
+        let (object, name) = split_component_id(id)?;
+        state.nodes.get(&object).filter(|n| !n.is_directory)?;
+        match name {
+            "Size" => Some(sizes.get(id).copied().unwrap_or_default().into()),
+            "OpenCount" => Some((state.handles.open_count(&object) as u16).into()),
+            _ => None,
+        }
+    }
+
+    /// Get the file of the handle `handle`, checking that it was opened with `mode`,
+    /// if given.
+    fn handle_file(
+        &self,
+        object_id: &NodeId,
+        session_id: u32,
+        handle: u32,
+        mode: Option<OpenFileMode>,
+    ) -> Result<SharedFile, StatusCode> {
+        let mut state = trace_lock!(self.state);
+        let h = state.handles.get_mut(object_id, session_id, handle)?;
+        if mode.is_some_and(|m| h.mode & m as u8 == 0) {
+            return Err(StatusCode::BadInvalidState);
+        }
+        Ok(h.file.clone())
+    }
+
+    async fn call_method(
+        &self,
+        context: &RequestContext,
+        address_space: &RwLock<AddressSpace>,
+        call: &MethodCall,
+    ) -> Result<Vec<Variant>, StatusCode> {
+        let Some((object_id, name)) = split_component_id(call.method_id()) else {
+            return Err(StatusCode::BadMethodInvalid);
+        };
+        let node = {
+            let state = trace_lock!(self.state);
+            state.nodes.get(&object_id).cloned()
+        };
+        let Some(node) = node else {
+            return Err(StatusCode::BadNodeIdUnknown);
+        };
+        let session_id = context.session_id;
+
+        let outputs: Vec<Variant> = match (node.is_directory, name) {
+            (false, "Open") => {
+                let mode = validate_open_file_mode(load_method_args!(call, Byte)?)?;
+                let write = mode & OpenFileMode::Write as u8 != 0;
+                let erase = mode & OpenFileMode::EraseExisting as u8 != 0;
+                let append = mode & OpenFileMode::Append as u8 != 0;
+                if write && !node.writable {
+                    return Err(StatusCode::BadNotWritable);
+                }
+                trace_lock!(self.state)
+                    .handles
+                    .check_open(&object_id, mode)?;
+                let path = node.path.clone();
+                let file = blocking(move || {
+                    let mut file = OpenOptions::new()
+                        .read(mode & OpenFileMode::Read as u8 != 0)
+                        .write(write)
+                        .open(&path)
+                        .map_err(|e| io_status(&path, e))?;
+                    if append && !erase {
+                        file.seek(SeekFrom::End(0))
+                            .map_err(|e| io_status(&path, e))?;
+                    }
+                    Ok(Arc::new(Mutex::new(file)))
+                })
+                .await?;
+                let handle = {
+                    let mut state = trace_lock!(self.state);
+                    // Another session may have opened the file in the meantime.
+                    state.handles.check_open(&object_id, mode)?;
+                    state
+                        .handles
+                        .insert(object_id.clone(), session_id, mode, file.clone())
+                };
+                // Only erase the file once the handle is registered, so that it cannot
+                // be erased while another session has it open.
+                if erase {
+                    let path = node.path.clone();
+                    let res = blocking(move || {
+                        trace_lock!(file)
+                            .set_len(0)
+                            .map_err(|e| io_status(&path, e))
+                    })
+                    .await;
+                    if let Err(e) = res {
+                        let _ = trace_lock!(self.state)
+                            .handles
+                            .remove(&object_id, session_id, handle);
+                        return Err(e);
+                    }
+                }
+                vec![handle.into()]
+            }
+            (false, "Close") => {
+                let handle = load_method_args!(call, UInt32)?;
+                trace_lock!(self.state)
+                    .handles
+                    .remove(&object_id, session_id, handle)?;
+                Vec::new()
+            }
+            (false, "Read") => {
+                let (handle, length) = load_method_args!(call, UInt32, Int32)?;
+                let mut length =
+                    u64::try_from(length).map_err(|_| StatusCode::BadInvalidArgument)?;
+                let max_length = context.info.config.limits.max_byte_string_length as u64;
+                if max_length > 0 {
+                    length = length.min(max_length);
+                }
+                let file =
+                    self.handle_file(&object_id, session_id, handle, Some(OpenFileMode::Read))?;
+                let path = node.path;
+                let data = blocking(move || {
+                    let mut data = Vec::new();
+                    (&mut *trace_lock!(file))
+                        .take(length)
+                        .read_to_end(&mut data)
+                        .map_err(|e| io_status(&path, e))?;
+                    Ok(data)
+                })
+                .await?;
+                vec![ByteString::from(data).into()]
+            }
+            (false, "Write") => {
+                let (handle, data) = load_method_args!(call, UInt32, ByteString)?;
+                let file =
+                    self.handle_file(&object_id, session_id, handle, Some(OpenFileMode::Write))?;
+                let path = node.path;
+                blocking(move || {
+                    trace_lock!(file)
+                        .write_all(data.as_ref())
+                        .map_err(|e| io_status(&path, e))
+                })
+                .await?;
+                Vec::new()
+            }
+            (false, "GetPosition") => {
+                let handle = load_method_args!(call, UInt32)?;
+                let file = self.handle_file(&object_id, session_id, handle, None)?;
+                let path = node.path;
+                let position = blocking(move || {
+                    trace_lock!(file)
+                        .stream_position()
+                        .map_err(|e| io_status(&path, e))
+                })
+                .await?;
+                vec![position.into()]
+            }
+            (false, "SetPosition") => {
+                let (handle, position) = load_method_args!(call, UInt32, UInt64)?;
+                let file = self.handle_file(&object_id, session_id, handle, None)?;
+                let path = node.path;
+                blocking(move || {
+                    trace_lock!(file)
+                        .seek(SeekFrom::Start(position))
+                        .map_err(|e| io_status(&path, e))
+                })
+                .await?;
+                Vec::new()
+            }
+            (true, "CreateDirectory") => {
+                let name = load_method_args!(call, String)?;
+                let (id, path) =
+                    Self::new_entry(&*trace_lock!(self.state), &object_id, &node, name.as_ref())?;
+                let entry = blocking(move || {
+                    fs::create_dir(&path).map_err(|e| io_status(&path, e))?;
+                    Ok(FileEntry {
+                        path,
+                        children: Some(Vec::new()),
+                    })
+                })
+                .await?;
+                let mut address_space = trace_write_lock!(address_space);
+                Self::add_node(
+                    &mut address_space,
+                    &mut *trace_lock!(self.state),
+                    &id,
+                    name.as_ref(),
+                    &entry,
+                    &object_id,
+                    node.writable,
+                    Some(&object_id),
+                );
+                vec![id.into()]
+            }
+            (true, "CreateFile") => {
+                let (name, open) = load_method_args!(call, String, Boolean)?;
+                let (id, path) =
+                    Self::new_entry(&*trace_lock!(self.state), &object_id, &node, name.as_ref())?;
+                let (entry, file) = blocking(move || {
+                    let file = File::create_new(&path).map_err(|e| io_status(&path, e))?;
+                    Ok((
+                        FileEntry {
+                            path,
+                            children: None,
+                        },
+                        file,
+                    ))
+                })
+                .await?;
+                let mut address_space = trace_write_lock!(address_space);
+                let mut state = trace_lock!(self.state);
+                Self::add_node(
+                    &mut address_space,
+                    &mut state,
+                    &id,
+                    name.as_ref(),
+                    &entry,
+                    &object_id,
+                    node.writable,
+                    Some(&object_id),
+                );
+                // A handle of 0 means the file was not opened.
+                let handle = if open {
+                    state.handles.insert(
+                        id.clone(),
+                        session_id,
+                        OpenFileMode::Write as u8,
+                        Arc::new(Mutex::new(file)),
+                    )
+                } else {
+                    0
+                };
+                vec![id.into(), handle.into()]
+            }
+            (true, "Delete") => {
+                let target = *load_method_args!(call, NodeId)?;
+                if !node.writable {
+                    return Err(StatusCode::BadUserAccessDenied);
+                }
+                let target_node = {
+                    let state = trace_lock!(self.state);
+                    let target_node = Self::child_of(&state, &object_id, &target)?;
+                    Self::check_not_open(&state, &target_node.path)?;
+                    target_node
+                };
+                let path = target_node.path;
+                blocking(move || {
+                    if target_node.is_directory {
+                        fs::remove_dir_all(&path)
+                    } else {
+                        fs::remove_file(&path)
+                    }
+                    .map_err(|e| io_status(&path, e))
+                })
+                .await?;
+                let mut address_space = trace_write_lock!(address_space);
+                let mut state = trace_lock!(self.state);
+                let type_tree = trace_read_lock!(context.type_tree);
+                Self::remove_node(&mut address_space, &type_tree, &mut state, &target);
+                Vec::new()
+            }
+            (true, "MoveOrCopy") => {
+                let (source, target_dir, copy, new_name) =
+                    load_method_args!(call, NodeId, NodeId, Boolean, String)?;
+                let (source, target_dir) = (*source, *target_dir);
+                if !copy && !node.writable {
+                    return Err(StatusCode::BadUserAccessDenied);
+                }
+                let (source_node, target_node, name, id, path) = {
+                    let state = trace_lock!(self.state);
+                    let source_node = Self::child_of(&state, &object_id, &source)?;
+                    let Some(target_node) = state
+                        .nodes
+                        .get(&target_dir)
+                        .filter(|n| n.is_directory)
+                        .cloned()
+                    else {
+                        return Err(StatusCode::BadNotFound);
+                    };
+                    let name = match new_name.value() {
+                        Some(n) if !n.is_empty() => n.clone(),
+                        _ => file_name(&source_node.path).ok_or(StatusCode::BadInvalidArgument)?,
+                    };
+                    let (id, path) = Self::new_entry(&state, &target_dir, &target_node, &name)?;
+                    if !copy {
+                        Self::check_not_open(&state, &source_node.path)?;
+                    }
+                    (source_node, target_node, name, id, path)
+                };
+                // A directory cannot be moved or copied into itself.
+                if path.starts_with(&source_node.path) {
+                    return Err(StatusCode::BadInvalidArgument);
+                }
+                let entry = blocking(move || {
+                    // Renaming replaces existing files, so check for them first.
+                    if fs::symlink_metadata(&path).is_ok() {
+                        return Err(StatusCode::BadBrowseNameDuplicated);
+                    }
+                    if copy {
+                        copy_all(&source_node.path, &path)
+                    } else {
+                        fs::rename(&source_node.path, &path)
+                    }
+                    .map_err(|e| io_status(&path, e))?;
+                    Ok(FileEntry::scan(&path))
+                })
+                .await?;
+                let mut address_space = trace_write_lock!(address_space);
+                let mut state = trace_lock!(self.state);
+                if !copy {
+                    let type_tree = trace_read_lock!(context.type_tree);
+                    Self::remove_node(&mut address_space, &type_tree, &mut state, &source);
+                }
+                Self::add_node(
+                    &mut address_space,
+                    &mut state,
+                    &id,
+                    &name,
+                    &entry,
+                    &target_dir,
+                    target_node.writable,
+                    Some(&target_dir),
+                );
+                vec![id.into()]
+            }
+            _ => return Err(StatusCode::BadMethodInvalid),
+        };
+        Ok(outputs)
+    }
+
+    /// Get the node ID and path of a new entry called `name` in the directory `dir`.
+    fn new_entry(
+        state: &FileNodeManagerState,
+        dir_id: &NodeId,
+        dir: &FileSystemNode,
+        name: &str,
+    ) -> Result<(NodeId, PathBuf), StatusCode> {
+        if !dir.writable {
+            return Err(StatusCode::BadUserAccessDenied);
+        }
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(StatusCode::BadInvalidArgument);
+        }
+        let id = child_id(dir_id, name);
+        if state.nodes.contains_key(&id) {
+            return Err(StatusCode::BadBrowseNameDuplicated);
+        }
+        Ok((id, dir.path.join(name)))
+    }
+
+    /// Get the node `id`, which must be an entry in the directory `dir_id`.
+    fn child_of(
+        state: &FileNodeManagerState,
+        dir_id: &NodeId,
+        id: &NodeId,
+    ) -> Result<FileSystemNode, StatusCode> {
+        state
+            .nodes
+            .get(id)
+            .filter(|n| n.parent.as_ref() == Some(dir_id))
+            .cloned()
+            .ok_or(StatusCode::BadNotFound)
+    }
+
+    /// Check that no file at or below `path` is open.
+    fn check_not_open(state: &FileNodeManagerState, path: &Path) -> Result<(), StatusCode> {
+        let is_open = state.handles.any(|h| {
+            state
+                .nodes
+                .get(&h.node_id)
+                .is_some_and(|n| n.path.starts_with(path))
+        });
+        if is_open {
+            Err(StatusCode::BadInvalidState)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Add the object for the file or directory `entry`, and, for directories,
+    /// objects for everything in it.
+    #[allow(clippy::too_many_arguments)]
+    fn add_node(
+        address_space: &mut AddressSpace,
+        state: &mut FileNodeManagerState,
+        id: &NodeId,
+        name: &str,
+        entry: &FileEntry,
+        organized_by: &NodeId,
+        writable: bool,
+        parent: Option<&NodeId>,
+    ) {
+        let is_directory = entry.children.is_some();
+        let browse_name = QualifiedName::new(id.namespace, name);
+        let type_id = if is_directory {
+            ObjectTypeId::FileDirectoryType
+        } else {
+            ObjectTypeId::FileType
+        };
+        ObjectBuilder::new(id, browse_name, name)
+            .has_type_definition(type_id)
+            .organized_by(organized_by.clone())
+            .insert(address_space);
+        state.nodes.insert(
+            id.clone(),
+            FileSystemNode {
+                path: entry.path.clone(),
+                is_directory,
+                writable,
+                parent: parent.cloned(),
+            },
+        );
+
+        let Some(children) = &entry.children else {
+            Self::add_file_components(address_space, id, writable);
+            return;
+        };
+        Self::add_directory_components(address_space, id);
+        for (child_name, child) in children {
+            Self::add_node(
+                address_space,
+                state,
+                &child_id(id, child_name),
+                child_name,
+                child,
+                id,
+                writable,
+                Some(id),
+            );
+        }
+    }
+
+    fn add_file_components(address_space: &mut AddressSpace, id: &NodeId, writable: bool) {
+        let properties: [(&str, DataTypeId, Variant); 4] = [
+            ("Size", DataTypeId::UInt64, 0u64.into()),
+            ("Writable", DataTypeId::Boolean, writable.into()),
+            ("UserWritable", DataTypeId::Boolean, writable.into()),
+            ("OpenCount", DataTypeId::UInt16, 0u16.into()),
+        ];
+        for (name, data_type, value) in properties {
+            VariableBuilder::new(&component_id(id, name), name, name)
+                .property_of(id.clone())
+                .has_type_definition(VariableTypeId::PropertyType)
+                .data_type(data_type)
+                .value(value)
+                .insert(address_space);
+        }
+
+        let handle = || Argument::from(("FileHandle", DataTypeId::UInt32));
+        add_method(
+            address_space,
+            id,
+            "Open",
+            &[("Mode", DataTypeId::Byte).into()],
+            &[handle()],
+        );
+        add_method(address_space, id, "Close", &[handle()], &[]);
+        add_method(
+            address_space,
+            id,
+            "Read",
+            &[handle(), ("Length", DataTypeId::Int32).into()],
+            &[("Data", DataTypeId::ByteString).into()],
+        );
+        add_method(
+            address_space,
+            id,
+            "Write",
+            &[handle(), ("Data", DataTypeId::ByteString).into()],
+            &[],
+        );
+        add_method(
+            address_space,
+            id,
+            "GetPosition",
+            &[handle()],
+            &[("Position", DataTypeId::UInt64).into()],
+        );
+        add_method(
+            address_space,
+            id,
+            "SetPosition",
+            &[handle(), ("Position", DataTypeId::UInt64).into()],
+            &[],
+        );
+    }
+
+    fn add_directory_components(address_space: &mut AddressSpace, id: &NodeId) {
+        add_method(
+            address_space,
+            id,
+            "CreateDirectory",
+            &[("DirectoryName", DataTypeId::String).into()],
+            &[("DirectoryNodeId", DataTypeId::NodeId).into()],
+        );
+        add_method(
+            address_space,
+            id,
+            "CreateFile",
+            &[
+                ("FileName", DataTypeId::String).into(),
+                ("RequestFileOpen", DataTypeId::Boolean).into(),
+            ],
+            &[
+                ("FileNodeId", DataTypeId::NodeId).into(),
+                ("FileHandle", DataTypeId::UInt32).into(),
+            ],
+        );
+        add_method(
+            address_space,
+            id,
+            "Delete",
+            &[("ObjectToDelete", DataTypeId::NodeId).into()],
+            &[],
+        );
+        add_method(
+            address_space,
+            id,
+            "MoveOrCopy",
+            &[
+                ("ObjectToMoveOrCopy", DataTypeId::NodeId).into(),
+                ("TargetDirectory", DataTypeId::NodeId).into(),
+                ("CreateCopy", DataTypeId::Boolean).into(),
+                ("NewName", DataTypeId::String).into(),
+            ],
+            &[("NewNodeId", DataTypeId::NodeId).into()],
+        );
+    }
+
+    /// Remove the object `id` from the address space, along with its components and,
+    /// for directories, everything in it.
+    fn remove_node(
+        address_space: &mut AddressSpace,
+        type_tree: &DefaultTypeTree,
+        state: &mut FileNodeManagerState,
+        id: &NodeId,
+    ) {
+        if let Some(node) = state.nodes.remove(id) {
+            state.nodes.retain(|_, n| !n.path.starts_with(&node.path));
+        }
+        delete_recursive(address_space, type_tree, id);
+    }
+}
+
+/// Delete `id` and all nodes in the same namespace below it in the hierarchy.
+fn delete_recursive(address_space: &mut AddressSpace, type_tree: &DefaultTypeTree, id: &NodeId) {
+    let children: Vec<NodeId> = address_space
+        .find_references(
+            id,
+            Some((ReferenceTypeId::HierarchicalReferences, true)),
+            type_tree,
+            BrowseDirection::Forward,
+        )
+        .filter(|r| r.target_node.namespace == id.namespace)
+        .map(|r| r.target_node.clone())
+        .collect();
+    for child in children {
+        delete_recursive(address_space, type_tree, &child);
+    }
+    address_space.delete(id, true);
+}
+
+fn add_method(
+    address_space: &mut AddressSpace,
+    object: &NodeId,
+    name: &str,
+    inputs: &[Argument],
+    outputs: &[Argument],
+) {
+    let id = component_id(object, name);
+    let mut builder = MethodBuilder::new(&id, name, name)
+        .component_of(object.clone())
+        .executable(true)
+        .user_executable(true);
+    if !inputs.is_empty() {
+        builder = builder.input_args(address_space, &component_id(&id, "InputArguments"), inputs);
+    }
+    if !outputs.is_empty() {
+        builder = builder.output_args(
+            address_space,
+            &component_id(&id, "OutputArguments"),
+            outputs,
+        );
+    }
+    builder.insert(address_space);
+}
+
+fn id_string(id: &NodeId) -> String {
+    match &id.identifier {
+        Identifier::String(s) => s.as_ref().to_owned(),
+        _ => id.identifier.to_string(),
+    }
+}
+
+/// Get the node ID of the entry `name` in the directory `dir`. File names cannot
+/// contain `/`, so these never collide.
+fn child_id(dir: &NodeId, name: &str) -> NodeId {
+    NodeId::new(dir.namespace, format!("{}/{name}", id_string(dir)))
+}
+
+/// Get the node ID of the component `name` of `object`. File names cannot be empty,
+/// so these never collide with the IDs of directory entries.
+fn component_id(object: &NodeId, name: &str) -> NodeId {
+    NodeId::new(object.namespace, format!("{}//{name}", id_string(object)))
+}
+
+/// Split a component node ID into the ID of its object and its browse name.
+fn split_component_id(id: &NodeId) -> Option<(NodeId, &str)> {
+    let Identifier::String(s) = &id.identifier else {
+        return None;
+    };
+    let (object, name) = s.as_ref().split_once("//")?;
+    Some((NodeId::new(id.namespace, object.to_owned()), name))
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name()?.to_str().map(|s| s.to_owned())
+}
+
+/// Copy the file or directory at `from` to `to`.
+fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_file() || file_type.is_dir() {
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run blocking file system I/O on a thread where blocking is allowed.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, StatusCode> + Send + 'static,
+) -> Result<T, StatusCode> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| {
+        error!("File operation panicked: {e}");
+        StatusCode::BadUnexpectedError
+    })?
+}
+
+fn io_status(path: &Path, e: io::Error) -> StatusCode {
+    error!("File operation on {} failed: {e}", path.display());
+    match e.kind() {
+        io::ErrorKind::NotFound => StatusCode::BadNotFound,
+        io::ErrorKind::PermissionDenied => StatusCode::BadUserAccessDenied,
+        io::ErrorKind::AlreadyExists => StatusCode::BadBrowseNameDuplicated,
+        _ => StatusCode::BadUnexpectedError,
+    }
+}
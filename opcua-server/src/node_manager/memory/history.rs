@@ -0,0 +1,727 @@
+//! A simple bounded historian for [InMemoryNodeManager](super::InMemoryNodeManager),
+//! enabled with [InMemoryNodeManagerBuilder::with_history](super::InMemoryNodeManagerBuilder::with_history).
+
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+use opcua_core::{sync::RwLock, trace_read_lock, trace_write_lock};
+use opcua_types::{
+    DataValue, DateTime, DeleteAtTimeDetails, DeleteRawModifiedDetails, HistoryData,
+    HistoryModifiedData, HistoryUpdateType, ModificationInfo, NodeId, PerformUpdateType,
+    ReadAtTimeDetails, ReadProcessedDetails, ReadRawModifiedDetails, StatusCode,
+    TimestampsToReturn, UAString, UpdateDataDetails,
+};
+
+use crate::{
+    aggregates::{
+        calculate_aggregate, default_aggregate_configuration, read_processed, AggregateFunction,
+        AggregateInterval,
+    },
+    node_manager::{HistoryNode, HistoryUpdateDetails, HistoryUpdateNode, RequestContext},
+    ContinuationPoint,
+};
+
+#[derive(Debug, Clone)]
+/// Configuration for the in-memory historian.
+pub struct InMemoryHistoryConfig {
+    /// Maximum number of raw values stored per node. When this is exceeded the
+    /// oldest values are discarded.
+    pub max_values_per_node: usize,
+    /// Maximum number of values returned per node in a single history read.
+    /// Reads returning more values than this return a continuation point.
+    pub max_values_per_read: usize,
+}
+
+impl Default for InMemoryHistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_values_per_node: 10_000,
+            max_values_per_read: 1_000,
+        }
+    }
+}
+
+#[derive(Default)]
+struct NodeHistory {
+    /// Raw values, ordered by timestamp.
+    values: VecDeque<DataValue>,
+    /// Values that were replaced or deleted, ordered by the timestamp
+    /// of the original value.
+    modified: VecDeque<(DataValue, ModificationInfo)>,
+}
+
+/// Continuation point for raw history reads. This refers to the next value
+/// by timestamp rather than by position, since values may be inserted or
+/// discarded between reads.
+struct RawContinuationPoint {
+    /// Timestamp of the next value to return.
+    time: DateTime,
+    /// Number of values with the same timestamp that were already returned.
+    skip: usize,
+}
+
+/// A value in the result of a raw history read.
+enum RawEntry {
+    Value(usize),
+    Modified(usize),
+    MissingBound(DateTime),
+}
+
+/// Bounded in-memory store of the raw history of historizing variables.
+///
+/// Values are recorded whenever the value of a variable with the `Historizing`
+/// attribute set is changed through [InMemoryNodeManager::set_values](super::InMemoryNodeManager::set_values).
+/// Values are only kept in memory, and are lost when the server stops.
+pub struct InMemoryHistory {
+    config: InMemoryHistoryConfig,
+    nodes: RwLock<HashMap<NodeId, NodeHistory>>,
+}
+
+fn timestamp(value: &DataValue) -> DateTime {
+    value
+        .source_timestamp
+        .or(value.server_timestamp)
+        .unwrap_or_else(DateTime::null)
+}
+
+fn bound_not_found(time: DateTime) -> DataValue {
+    DataValue {
+        status: Some(StatusCode::BadBoundNotFound),
+        source_timestamp: Some(time),
+        server_timestamp: Some(time),
+        ..Default::default()
+    }
+}
+
+fn apply_timestamps(value: &mut DataValue, timestamps_to_return: TimestampsToReturn) {
+    match timestamps_to_return {
+        TimestampsToReturn::Source => {
+            value.server_timestamp = None;
+            value.server_picoseconds = None;
+        }
+        TimestampsToReturn::Server => {
+            value.source_timestamp = None;
+            value.source_picoseconds = None;
+        }
+        TimestampsToReturn::Neither => {
+            value.source_timestamp = None;
+            value.source_picoseconds = None;
+            value.server_timestamp = None;
+            value.server_picoseconds = None;
+        }
+        _ => (),
+    }
+}
+
+impl NodeHistory {
+    fn position(&self, time: DateTime) -> Result<usize, usize> {
+        let idx = self.values.partition_point(|v| timestamp(v) < time);
+        match self.values.get(idx) {
+            Some(v) if timestamp(v) == time => Ok(idx),
+            _ => Err(idx),
+        }
+    }
+
+    fn insert(&mut self, value: DataValue, max_values: usize) {
+        // Values are almost always recorded in order, so this is usually a push.
+        let idx = self
+            .values
+            .partition_point(|v| timestamp(v) <= timestamp(&value));
+        self.values.insert(idx, value);
+        while self.values.len() > max_values {
+            self.values.pop_front();
+        }
+    }
+
+    fn add_modified(&mut self, value: DataValue, info: ModificationInfo, max_values: usize) {
+        let idx = self
+            .modified
+            .partition_point(|(v, _)| timestamp(v) <= timestamp(&value));
+        self.modified.insert(idx, (value, info));
+        while self.modified.len() > max_values {
+            self.modified.pop_front();
+        }
+    }
+
+    fn entry_time(&self, entry: &RawEntry) -> DateTime {
+        match entry {
+            RawEntry::Value(idx) => timestamp(&self.values[*idx]),
+            RawEntry::Modified(idx) => timestamp(&self.modified[*idx].0),
+            RawEntry::MissingBound(time) => *time,
+        }
+    }
+
+    /// Find the index in `entries` to resume a raw read from.
+    fn resume_offset(
+        &self,
+        entries: &[RawEntry],
+        cp: &RawContinuationPoint,
+        forward: bool,
+    ) -> usize {
+        let start = entries.partition_point(|e| {
+            let time = self.entry_time(e);
+            if forward {
+                time < cp.time
+            } else {
+                time > cp.time
+            }
+        });
+        let same = entries[start..]
+            .iter()
+            .take_while(|e| self.entry_time(e) == cp.time)
+            .count();
+        start + cp.skip.min(same)
+    }
+
+    /// Create a continuation point referring to `entries[offset]`.
+    fn continuation_point(&self, entries: &[RawEntry], offset: usize) -> RawContinuationPoint {
+        let time = self.entry_time(&entries[offset]);
+        let skip = entries[..offset]
+            .iter()
+            .rev()
+            .take_while(|e| self.entry_time(e) == time)
+            .count();
+        RawContinuationPoint { time, skip }
+    }
+
+    /// List the values returned by a raw or modified read, in the order they
+    /// should be returned, and whether the read goes forward in time.
+    fn raw_entries(
+        &self,
+        details: &ReadRawModifiedDetails,
+    ) -> Result<(Vec<RawEntry>, bool), StatusCode> {
+        // Reads go from `from`, which is inclusive, towards `to`, which is exclusive.
+        let (from, to, forward) = match (details.start_time, details.end_time) {
+            (start, end) if start.is_null() && end.is_null() => {
+                return Err(StatusCode::BadInvalidArgument)
+            }
+            // With only one of the times given, the number of values must be limited.
+            (start, end)
+                if (start.is_null() || end.is_null()) && details.num_values_per_node == 0 =>
+            {
+                return Err(StatusCode::BadInvalidArgument)
+            }
+            (start, end) if end.is_null() => (start, None, true),
+            (start, end) if start.is_null() => (end, None, false),
+            (start, end) => (start, Some(end), start <= end),
+        };
+
+        let timestamps: Vec<_> = if details.is_read_modified {
+            self.modified.iter().map(|(v, _)| timestamp(v)).collect()
+        } else {
+            self.values.iter().map(timestamp).collect()
+        };
+        let entry = |idx| {
+            if details.is_read_modified {
+                RawEntry::Modified(idx)
+            } else {
+                RawEntry::Value(idx)
+            }
+        };
+
+        let mut entries: Vec<_> = if forward {
+            let lo = timestamps.partition_point(|t| *t < from);
+            let hi = to
+                .map(|to| timestamps.partition_point(|t| *t < to))
+                .unwrap_or(timestamps.len());
+            (lo..hi.max(lo)).map(entry).collect()
+        } else {
+            let hi = timestamps.partition_point(|t| *t <= from);
+            let lo = to
+                .map(|to| timestamps.partition_point(|t| *t <= to))
+                .unwrap_or(0);
+            (lo.min(hi)..hi).rev().map(entry).collect()
+        };
+
+        if details.return_bounds && !details.is_read_modified {
+            let before = |time: DateTime| {
+                let idx = timestamps.partition_point(|t| *t < time);
+                match timestamps.get(idx) {
+                    Some(t) if *t == time => RawEntry::Value(idx),
+                    _ if idx > 0 => RawEntry::Value(idx - 1),
+                    _ => RawEntry::MissingBound(time),
+                }
+            };
+            let after = |time: DateTime| {
+                let idx = timestamps.partition_point(|t| *t < time);
+                if idx < timestamps.len() {
+                    RawEntry::Value(idx)
+                } else {
+                    RawEntry::MissingBound(time)
+                }
+            };
+            let (first, last) = if forward {
+                (before(from), to.map(after))
+            } else {
+                (after(from), to.map(before))
+            };
+            // Don't return a value twice if it is exactly at the bound.
+            if !matches!((&first, entries.first()), (RawEntry::Value(a), Some(RawEntry::Value(b))) if a == b)
+            {
+                entries.insert(0, first);
+            }
+            if let Some(last) = last {
+                if !matches!((&last, entries.last()), (RawEntry::Value(a), Some(RawEntry::Value(b))) if a == b)
+                {
+                    entries.push(last);
+                }
+            }
+        }
+
+        Ok((entries, forward))
+    }
+}
+
+impl InMemoryHistory {
+    pub(crate) fn new(config: InMemoryHistoryConfig) -> Self {
+        Self {
+            config,
+            nodes: Default::default(),
+        }
+    }
+
+    /// Get the configuration of the historian.
+    pub fn config(&self) -> &InMemoryHistoryConfig {
+        &self.config
+    }
+
+    /// Record a new raw value for the node with ID `node_id`. Values without
+    /// a source timestamp are recorded with the current time.
+    pub fn record_value(&self, node_id: &NodeId, mut value: DataValue) {
+        if value.source_timestamp.is_none() {
+            value.source_timestamp = Some(value.server_timestamp.unwrap_or_else(DateTime::now));
+        }
+        let mut nodes = trace_write_lock!(self.nodes);
+        nodes
+            .entry(node_id.clone())
+            .or_default()
+            .insert(value, self.config.max_values_per_node);
+    }
+
+    /// Get all recorded raw values for the node with ID `node_id`, ordered by
+    /// timestamp.
+    pub fn values(&self, node_id: &NodeId) -> Vec<DataValue> {
+        let nodes = trace_read_lock!(self.nodes);
+        nodes
+            .get(node_id)
+            .map(|h| h.values.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove all history for the node with ID `node_id`.
+    pub fn clear(&self, node_id: &NodeId) {
+        let mut nodes = trace_write_lock!(self.nodes);
+        nodes.remove(node_id);
+    }
+
+    fn page_size(&self, requested: u32) -> usize {
+        if requested == 0 {
+            self.config.max_values_per_read
+        } else {
+            (requested as usize).min(self.config.max_values_per_read)
+        }
+    }
+
+    pub(crate) fn read_raw_modified(
+        &self,
+        details: &ReadRawModifiedDetails,
+        nodes: &mut [&mut &mut HistoryNode],
+        timestamps_to_return: TimestampsToReturn,
+    ) {
+        let history = trace_read_lock!(self.nodes);
+        let page_size = self.page_size(details.num_values_per_node);
+        let empty = NodeHistory::default();
+
+        for node in nodes {
+            let data = history.get(node.node_id()).unwrap_or(&empty);
+            let (entries, forward) = match data.raw_entries(details) {
+                Ok(e) => e,
+                Err(e) => {
+                    node.set_status(e);
+                    continue;
+                }
+            };
+            let offset = match node.continuation_point() {
+                Some(cp) => match cp.get::<RawContinuationPoint>() {
+                    Some(cp) => data.resume_offset(&entries, cp, forward),
+                    None => {
+                        node.set_status(StatusCode::BadContinuationPointInvalid);
+                        continue;
+                    }
+                },
+                None => 0,
+            };
+
+            let end = offset.saturating_add(page_size).min(entries.len());
+            let mut values = Vec::with_capacity(end.saturating_sub(offset));
+            let mut infos = Vec::new();
+            for entry in entries.iter().skip(offset).take(page_size) {
+                let mut value = match entry {
+                    RawEntry::Value(idx) => data.values[*idx].clone(),
+                    RawEntry::Modified(idx) => {
+                        let (value, info) = &data.modified[*idx];
+                        infos.push(info.clone());
+                        value.clone()
+                    }
+                    RawEntry::MissingBound(time) => bound_not_found(*time),
+                };
+                apply_timestamps(&mut value, timestamps_to_return);
+                values.push(value);
+            }
+
+            if end < entries.len() {
+                node.set_next_continuation_point(Some(ContinuationPoint::new(Box::new(
+                    data.continuation_point(&entries, end),
+                ))));
+            }
+            let status = if values.is_empty() {
+                StatusCode::GoodNoData
+            } else {
+                StatusCode::Good
+            };
+            if details.is_read_modified {
+                node.set_result(HistoryModifiedData {
+                    data_values: Some(values),
+                    modification_infos: Some(infos),
+                });
+            } else {
+                node.set_result(HistoryData {
+                    data_values: Some(values),
+                });
+            }
+            node.set_status(status);
+        }
+    }
+
+    pub(crate) fn read_at_time(
+        &self,
+        details: &ReadAtTimeDetails,
+        nodes: &mut [&mut &mut HistoryNode],
+        timestamps_to_return: TimestampsToReturn,
+    ) {
+        let history = trace_read_lock!(self.nodes);
+        // Values between raw values are always interpolated using simple bounds.
+        let config = default_aggregate_configuration();
+        let req_times = details.req_times.as_deref().unwrap_or_default();
+
+        for node in nodes {
+            if node.continuation_point().is_some() {
+                node.set_status(StatusCode::BadContinuationPointInvalid);
+                continue;
+            }
+            let data: Vec<_> = history
+                .get(node.node_id())
+                .map(|h| h.values.iter().cloned().collect())
+                .unwrap_or_default();
+
+            let values = req_times
+                .iter()
+                .map(|time| {
+                    let mut value = calculate_aggregate(
+                        AggregateFunction::Interpolative,
+                        &config,
+                        &AggregateInterval {
+                            start: *time,
+                            end: *time,
+                            data: &data,
+                            stepped: false,
+                            partial: false,
+                        },
+                    );
+                    // The partial bit is not meaningful for a single point in time.
+                    value.status = value.status.map(|s| s.set_partial(false));
+                    apply_timestamps(&mut value, timestamps_to_return);
+                    value
+                })
+                .collect();
+
+            node.set_result(HistoryData {
+                data_values: Some(values),
+            });
+            node.set_status(StatusCode::Good);
+        }
+    }
+
+    pub(crate) fn read_processed(
+        &self,
+        details: &ReadProcessedDetails,
+        nodes: &mut [&mut &mut HistoryNode],
+        timestamps_to_return: TimestampsToReturn,
+    ) {
+        let history = trace_read_lock!(self.nodes);
+        for node in nodes {
+            let data: Vec<_> = history
+                .get(node.node_id())
+                .map(|h| h.values.iter().cloned().collect())
+                .unwrap_or_default();
+            read_processed(
+                details,
+                node,
+                &data,
+                false,
+                timestamps_to_return,
+                self.config.max_values_per_read,
+            );
+        }
+    }
+
+    pub(crate) fn update(
+        &self,
+        context: &RequestContext,
+        nodes: &mut [&mut &mut HistoryUpdateNode],
+    ) {
+        let mut history = trace_write_lock!(self.nodes);
+        let user_name = UAString::from(context.token.0.as_str());
+        let info = |update_type| ModificationInfo {
+            modification_time: DateTime::now(),
+            update_type,
+            user_name: user_name.clone(),
+        };
+
+        for node in nodes {
+            let data = history.entry(node.details().node_id().clone()).or_default();
+            let res = match node.details() {
+                HistoryUpdateDetails::UpdateData(d) => self.update_data(data, d, &info),
+                HistoryUpdateDetails::DeleteRawModified(d) => {
+                    self.delete_raw_modified(data, d, &info)
+                }
+                HistoryUpdateDetails::DeleteAtTime(d) => self.delete_at_time(data, d, &info),
+                _ => Err(StatusCode::BadHistoryOperationUnsupported),
+            };
+            match res {
+                Ok(results) => {
+                    node.set_operation_results(results);
+                    node.set_status(StatusCode::Good);
+                }
+                Err(e) => node.set_status(e),
+            }
+        }
+    }
+
+    fn update_data(
+        &self,
+        data: &mut NodeHistory,
+        details: &UpdateDataDetails,
+        info: &impl Fn(HistoryUpdateType) -> ModificationInfo,
+    ) -> Result<Option<Vec<StatusCode>>, StatusCode> {
+        let mode = details.perform_insert_replace;
+        if mode == PerformUpdateType::Remove {
+            return Err(StatusCode::BadHistoryOperationInvalid);
+        }
+
+        let mut results = Vec::new();
+        for value in details.update_values.iter().flatten() {
+            let Some(time) = value.source_timestamp else {
+                results.push(StatusCode::BadInvalidTimestamp);
+                continue;
+            };
+            let status = match (data.position(time), mode) {
+                (Ok(_), PerformUpdateType::Insert) => StatusCode::BadEntryExists,
+                (Err(_), PerformUpdateType::Replace) => StatusCode::BadNoEntryExists,
+                (Ok(idx), _) => {
+                    let old = std::mem::replace(&mut data.values[idx], value.clone());
+                    let update_type = if mode == PerformUpdateType::Replace {
+                        HistoryUpdateType::Replace
+                    } else {
+                        HistoryUpdateType::Update
+                    };
+                    data.add_modified(old, info(update_type), self.config.max_values_per_node);
+                    StatusCode::GoodEntryReplaced
+                }
+                (Err(_), _) => {
+                    data.insert(value.clone(), self.config.max_values_per_node);
+                    StatusCode::GoodEntryInserted
+                }
+            };
+            results.push(status);
+        }
+        Ok(Some(results))
+    }
+
+    fn delete_raw_modified(
+        &self,
+        data: &mut NodeHistory,
+        details: &DeleteRawModifiedDetails,
+        info: &impl Fn(HistoryUpdateType) -> ModificationInfo,
+    ) -> Result<Option<Vec<StatusCode>>, StatusCode> {
+        let (start, end) = if details.start_time <= details.end_time {
+            (details.start_time, details.end_time)
+        } else {
+            (details.end_time, details.start_time)
+        };
+        let in_range = |v: &DataValue| timestamp(v) >= start && timestamp(v) < end;
+
+        if details.is_delete_modified {
+            let len = data.modified.len();
+            data.modified.retain(|(v, _)| !in_range(v));
+            return if data.modified.len() == len {
+                Err(StatusCode::BadNoData)
+            } else {
+                Ok(None)
+            };
+        }
+
+        let lo = data.values.partition_point(|v| timestamp(v) < start);
+        let hi = data.values.partition_point(|v| timestamp(v) < end).max(lo);
+        if lo == hi {
+            return Err(StatusCode::BadNoData);
+        }
+        let removed: Vec<_> = data.values.drain(lo..hi).collect();
+        for value in removed {
+            data.add_modified(
+                value,
+                info(HistoryUpdateType::Delete),
+                self.config.max_values_per_node,
+            );
+        }
+        Ok(None)
+    }
+
+    fn delete_at_time(
+        &self,
+        data: &mut NodeHistory,
+        details: &DeleteAtTimeDetails,
+        info: &impl Fn(HistoryUpdateType) -> ModificationInfo,
+    ) -> Result<Option<Vec<StatusCode>>, StatusCode> {
+        let mut results = Vec::new();
+        for time in details.req_times.iter().flatten() {
+            match data.position(*time) {
+                Ok(idx) => {
+                    let value = data.values.remove(idx).unwrap();
+                    data.add_modified(
+                        value,
+                        info(HistoryUpdateType::Delete),
+                        self.config.max_values_per_node,
+                    );
+                    results.push(StatusCode::Good);
+                }
+                Err(_) => results.push(StatusCode::BadNoEntryExists),
+            }
+        }
+        Ok(Some(results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use opcua_types::{DataValue, DateTime, NodeId, ReadRawModifiedDetails, StatusCode};
+
+    use super::{InMemoryHistory, InMemoryHistoryConfig, RawContinuationPoint, RawEntry};
+
+    #[test]
+    fn raw_entries() {
+        let history = InMemoryHistory::new(InMemoryHistoryConfig {
+            max_values_per_node: 5,
+            max_values_per_read: 100,
+        });
+        let id = NodeId::new(1, 1);
+        let t0 = DateTime::now();
+        let at = |ms| t0 + Duration::milliseconds(ms);
+        // The oldest value is dropped.
+        for i in 0..6 {
+            history.record_value(&id, DataValue::new_at(i, at(i as i64 * 100)));
+        }
+        assert_eq!(history.values(&id).len(), 5);
+
+        let nodes = history.nodes.read();
+        let data = nodes.get(&id).unwrap();
+        let indices = |details: &ReadRawModifiedDetails| -> Vec<String> {
+            data.raw_entries(details)
+                .unwrap()
+                .0
+                .into_iter()
+                .map(|e| match e {
+                    RawEntry::Value(i) => i.to_string(),
+                    RawEntry::Modified(i) => format!("m{i}"),
+                    RawEntry::MissingBound(_) => "missing".to_owned(),
+                })
+                .collect()
+        };
+
+        // Values are at 100, 200, 300, 400 and 500 ms, with index 0 to 4.
+        let mut details = ReadRawModifiedDetails {
+            is_read_modified: false,
+            start_time: at(150),
+            end_time: at(400),
+            num_values_per_node: 0,
+            return_bounds: false,
+        };
+        assert_eq!(indices(&details), ["1", "2"]);
+        details.return_bounds = true;
+        assert_eq!(indices(&details), ["0", "1", "2", "3"]);
+
+        // Reverse
+        details.start_time = at(400);
+        details.end_time = at(150);
+        details.return_bounds = false;
+        assert_eq!(indices(&details), ["3", "2", "1"]);
+
+        // Open ended reads require a value limit.
+        details.end_time = DateTime::null();
+        assert_eq!(
+            data.raw_entries(&details).err(),
+            Some(StatusCode::BadInvalidArgument)
+        );
+        details.num_values_per_node = 2;
+        assert_eq!(indices(&details), ["3", "4"]);
+
+        details.start_time = at(450);
+        details.end_time = at(600);
+        details.return_bounds = true;
+        assert_eq!(indices(&details), ["3", "4", "missing"]);
+    }
+
+    #[test]
+    fn raw_continuation_point() {
+        let history = InMemoryHistory::new(InMemoryHistoryConfig {
+            max_values_per_node: 5,
+            max_values_per_read: 100,
+        });
+        let id = NodeId::new(1, 1);
+        let t0 = DateTime::now();
+        let at = |ms| t0 + Duration::milliseconds(ms);
+        // Two values share the timestamp 200.
+        for (i, ms) in [100, 200, 200, 300, 400].into_iter().enumerate() {
+            history.record_value(&id, DataValue::new_at(i as i32, at(ms)));
+        }
+        let details = ReadRawModifiedDetails {
+            is_read_modified: false,
+            start_time: at(0),
+            end_time: at(1000),
+            num_values_per_node: 0,
+            return_bounds: false,
+        };
+        let next_value = |cp: &RawContinuationPoint| {
+            let nodes = history.nodes.read();
+            let data = nodes.get(&id).unwrap();
+            let (entries, forward) = data.raw_entries(&details).unwrap();
+            let offset = data.resume_offset(&entries, cp, forward);
+            entries.get(offset).map(|e| match e {
+                RawEntry::Value(i) => data.values[*i].value.clone().unwrap(),
+                _ => panic!("Expected value"),
+            })
+        };
+
+        // Stop between the two values at 200.
+        let cp = {
+            let nodes = history.nodes.read();
+            let data = nodes.get(&id).unwrap();
+            let (entries, _) = data.raw_entries(&details).unwrap();
+            data.continuation_point(&entries, 2)
+        };
+        assert_eq!(cp.skip, 1);
+        assert_eq!(next_value(&cp), Some(2.into()));
+
+        // A new value evicts the oldest, shifting the position of the remaining values.
+        // The next value is still the same.
+        history.record_value(&id, DataValue::new_at(5, at(500)));
+        assert_eq!(history.values(&id).len(), 5);
+        assert_eq!(next_value(&cp), Some(2.into()));
+
+        // If the next value is evicted, the read resumes at the first value after it.
+        history.record_value(&id, DataValue::new_at(6, at(600)));
+        history.record_value(&id, DataValue::new_at(7, at(700)));
+        assert_eq!(next_value(&cp), Some(3.into()));
+    }
+}
@@ -70,6 +70,10 @@ pub trait InMemoryNodeManagerImpl: Send + Sync + 'static {
         false
     }
 
+    /// Called when a session is closed or expires. Node managers keeping
+    /// per-session state, such as open file handles, should release it here.
+    fn session_closed(&self, session_id: u32) {}
+
     /// Perform the register nodes service. The default behavior for this service is to
     /// do nothing and pretend the nodes were registered.
     async fn register_nodes(
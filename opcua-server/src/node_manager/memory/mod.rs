@@ -3,7 +3,10 @@
 //! details to a type implementing [InMemoryNodeManagerImpl].
 
 mod diagnostics;
+mod file;
+mod history;
 mod implementation;
+mod query;
 mod simple;
 
 #[cfg(feature = "generated-address-space")]
@@ -13,6 +16,8 @@ mod core;
 pub use core::{CoreNodeManager, CoreNodeManagerBuilder, CoreNodeManagerImpl};
 
 pub use diagnostics::{DiagnosticsNodeManager, DiagnosticsNodeManagerBuilder, NamespaceMetadata};
+pub use file::{FileNodeManager, FileNodeManagerBuilder, FileNodeManagerImpl};
+pub use history::{InMemoryHistory, InMemoryHistoryConfig};
 pub use implementation::*;
 use log::warn;
 use opcua_core::{trace_read_lock, trace_write_lock};
@@ -28,11 +33,11 @@ use hashbrown::HashMap;
 
 use crate::{
     address_space::{
-        read_node_value, user_access_level, AccessLevel, EventNotifier, NodeType,
-        ReferenceDirection,
+        is_browsable, read_node_value, user_access_level, AccessLevel, EventNotifier,
+        NodePermissions, NodeType, ReferenceDirection,
     },
     subscriptions::CreateMonitoredItem,
-    SubscriptionCache,
+    ContinuationPoint, SubscriptionCache,
 };
 use opcua_core::sync::RwLock;
 use opcua_types::{
@@ -48,9 +53,10 @@ use super::{
     view::{AddReferenceResult, ExternalReference, ExternalReferenceRequest, NodeMetadata},
     AddNodeItem, AddReferenceItem, BrowseNode, BrowsePathItem, DefaultTypeTree, DeleteNodeItem,
     DeleteReferenceItem, DynNodeManager, HistoryNode, HistoryUpdateDetails, HistoryUpdateNode,
-    MethodCall, MonitoredItemRef, MonitoredItemUpdateRef, NodeManager, ReadNode, RegisterNodeItem,
-    RequestContext, ServerContext, WriteNode,
+    MethodCall, MonitoredItemRef, MonitoredItemUpdateRef, NodeManager, QueryRequest, ReadNode,
+    RegisterNodeItem, RequestContext, ServerContext, ViewContents, WriteNode,
 };
+use query::QueryContinuationPoint;
 
 use crate::address_space::AddressSpace;
 
@@ -68,26 +74,51 @@ pub struct InMemoryNodeManager<TImpl> {
     address_space: Arc<RwLock<AddressSpace>>,
     namespaces: HashMap<u16, String>,
     inner: TImpl,
+    history: Option<InMemoryHistory>,
 }
 
 /// Builder for the in-memory node manager.
 pub struct InMemoryNodeManagerBuilder<T> {
     impl_builder: T,
+    history: Option<InMemoryHistoryConfig>,
 }
 
 impl<T: InMemoryNodeManagerImplBuilder> InMemoryNodeManagerBuilder<T> {
     /// Create a new in memory node manager builder with the given
     /// builder for the [InMemoryNodeManagerImpl].
     pub fn new(impl_builder: T) -> Self {
-        Self { impl_builder }
+        Self {
+            impl_builder,
+            history: None,
+        }
+    }
+
+    /// Enable the built-in [InMemoryHistory]. Values set on historizing
+    /// variables are recorded, and history reads and updates for those variables
+    /// are handled by the node manager instead of the [InMemoryNodeManagerImpl].
+    pub fn with_history(mut self, config: InMemoryHistoryConfig) -> Self {
+        self.history = Some(config);
+        self
     }
 }
 
 impl<T: InMemoryNodeManagerImplBuilder> NodeManagerBuilder for InMemoryNodeManagerBuilder<T> {
     fn build(self: Box<Self>, context: ServerContext) -> Arc<DynNodeManager> {
         let mut address_space = AddressSpace::new();
+        let roles = context.info.roles.clone();
         let inner = self.impl_builder.build(context, &mut address_space);
-        Arc::new(InMemoryNodeManager::new(inner, address_space))
+        for ns in inner.namespaces() {
+            if ns.default_role_permissions.is_some() || !ns.default_access_restrictions.is_empty() {
+                roles.set_namespace_defaults(
+                    ns.namespace_index,
+                    ns.default_role_permissions,
+                    ns.default_access_restrictions,
+                );
+            }
+        }
+        let mut node_manager = InMemoryNodeManager::new(inner, address_space);
+        node_manager.history = self.history.map(InMemoryHistory::new);
+        Arc::new(node_manager)
     }
 }
 
@@ -97,6 +128,7 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
             namespaces: address_space.namespaces().clone(),
             address_space: Arc::new(RwLock::new(address_space)),
             inner,
+            history: None,
         }
     }
 
@@ -110,6 +142,11 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
         &self.address_space
     }
 
+    /// Get the built-in historian, if it is enabled.
+    pub fn history(&self) -> Option<&InMemoryHistory> {
+        self.history.as_ref()
+    }
+
     /// Get a reference to the namespaces managed by this node manager,
     /// by namespace index.
     pub fn namespaces(&self) -> &HashMap<u16, String> {
@@ -204,6 +241,17 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
                     } else {
                         v.set_data_value(value)
                     }
+                    if let Some(history) = self.history.as_ref().filter(|_| v.historizing()) {
+                        history.record_value(
+                            id,
+                            v.value(
+                                TimestampsToReturn::Both,
+                                &NumericRange::None,
+                                &DataEncoding::Binary,
+                                0.0,
+                            ),
+                        );
+                    }
                 }
                 NodeType::VariableType(v) => v.set_value(value.value.unwrap_or_default()),
                 _ => return Err(StatusCode::BadAttributeIdInvalid),
@@ -290,6 +338,7 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
     fn browse_node(
         address_space: &AddressSpace,
         type_tree: &DefaultTypeTree,
+        context: &RequestContext,
         node: &mut BrowseNode,
         namespaces: &hashbrown::HashMap<u16, String>,
     ) {
@@ -339,6 +388,10 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
                 continue;
             };
 
+            if !is_browsable(context, target_node) {
+                continue;
+            }
+
             let r_node =
                 Self::get_reference(address_space, type_tree, target_node, node.result_mask());
 
@@ -362,6 +415,50 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
         }
     }
 
+    /// Validate that the view given by `view` exists, is visible to the user,
+    /// and matches the requested view version.
+    fn validate_view(
+        address_space: &AddressSpace,
+        type_tree: &DefaultTypeTree,
+        context: &RequestContext,
+        view: &ViewContents,
+    ) -> Result<(), StatusCode> {
+        let Some(node) = address_space.find(view.view_id()) else {
+            return Err(StatusCode::BadViewIdUnknown);
+        };
+        if node.node_class() != NodeClass::View || !is_browsable(context, node) {
+            return Err(StatusCode::BadViewIdUnknown);
+        }
+        if view.view_version() == 0 {
+            return Ok(());
+        }
+        let version = address_space
+            .find_node_by_browse_name(
+                view.view_id(),
+                Some((ReferenceTypeId::HasProperty, false)),
+                type_tree,
+                BrowseDirection::Forward,
+                "ViewVersion",
+            )
+            .and_then(|n| match n {
+                NodeType::Variable(v) => {
+                    v.value(
+                        TimestampsToReturn::Neither,
+                        &NumericRange::None,
+                        &DataEncoding::Binary,
+                        0.0,
+                    )
+                    .value
+                }
+                _ => None,
+            });
+        if version == Some(Variant::UInt32(view.view_version())) {
+            Ok(())
+        } else {
+            Err(StatusCode::BadViewVersionInvalid)
+        }
+    }
+
     fn translate_browse_paths(
         address_space: &AddressSpace,
         type_tree: &DefaultTypeTree,
@@ -419,6 +516,10 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
                             continue;
                         };
 
+                        if !is_browsable(context, node) {
+                            continue;
+                        }
+
                         if element.target_name.is_null()
                             || node.as_node().browse_name() == &element.target_name
                         {
@@ -484,6 +585,40 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
         valid
     }
 
+    /// Remove the nodes handled by the built-in historian from `nodes`, if it
+    /// is enabled. These are the historizing variables.
+    fn take_history_nodes<T>(&self, nodes: &mut Vec<T>, node_id: impl Fn(&T) -> &NodeId) -> Vec<T> {
+        if self.history.is_none() {
+            return Vec::new();
+        }
+        let address_space = trace_read_lock!(self.address_space);
+        let (history_nodes, rest) = std::mem::take(nodes).into_iter().partition(|n| {
+            matches!(address_space.find(node_id(n)), Some(NodeType::Variable(v)) if v.historizing())
+        });
+        *nodes = rest;
+        history_nodes
+    }
+
+    /// Combine the result of calling the [InMemoryNodeManagerImpl] with
+    /// the remaining `nodes` after some were handled by the built-in historian.
+    /// Errors are then only reported for the nodes passed to the impl.
+    fn inner_history_result<T>(
+        result: Result<(), StatusCode>,
+        handled_any: bool,
+        nodes: &mut [T],
+        set_status: impl Fn(&mut T, StatusCode),
+    ) -> Result<(), StatusCode> {
+        match result {
+            Err(e) if handled_any => {
+                for node in nodes {
+                    set_status(node, e);
+                }
+                Ok(())
+            }
+            r => r,
+        }
+    }
+
     fn validate_history_write_nodes<'a, 'b>(
         &self,
         context: &RequestContext,
@@ -546,21 +681,31 @@ impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
         let mut valid = Vec::with_capacity(methods.len());
 
         for method in methods {
-            let Some(method_ref) = address_space
+            // Condition methods are called on condition instances using the method ID
+            // defined on the condition type, the condition may be owned by a different
+            // node manager than the method.
+            let is_method_of_object = address_space
                 .find_references(
                     method.object_id(),
                     Some((ReferenceTypeId::HasComponent, false)),
                     &*type_tree,
                     BrowseDirection::Forward,
                 )
-                .find(|r| r.target_node == method.method_id())
-            else {
+                .any(|r| r.target_node == method.method_id())
+                || context
+                    .info
+                    .conditions
+                    .is_condition_method_call(method.object_id(), method.method_id());
+            if !is_method_of_object {
                 method.set_status(StatusCode::BadMethodInvalid);
                 continue;
-            };
+            }
 
-            let Some(NodeType::Method(method_node)) = address_space.find(method_ref.target_node)
-            else {
+            let Some(node) = address_space.find(method.method_id()) else {
+                method.set_status(StatusCode::BadMethodInvalid);
+                continue;
+            };
+            let NodeType::Method(method_node) = node else {
                 method.set_status(StatusCode::BadMethodInvalid);
                 continue;
             };
@@ -678,6 +823,10 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         self.inner.handle_new_node(parent_id)
     }
 
+    fn session_closed(&self, session_id: u32) {
+        self.inner.session_closed(session_id)
+    }
+
     async fn resolve_external_references(
         &self,
         context: &RequestContext,
@@ -693,6 +842,10 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
                 continue;
             };
 
+            if !is_browsable(context, target_node) {
+                continue;
+            }
+
             item.set(Self::get_reference(
                 &address_space,
                 &type_tree,
@@ -702,6 +855,23 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         }
     }
 
+    async fn node_permissions(
+        &self,
+        _context: &RequestContext,
+        node_ids: &[&NodeId],
+    ) -> Vec<NodePermissions> {
+        let address_space = trace_read_lock!(self.address_space);
+        node_ids
+            .iter()
+            .map(|id| {
+                address_space
+                    .find(*id)
+                    .map(NodePermissions::of_node)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
     async fn browse(
         &self,
         context: &RequestContext,
@@ -715,6 +885,14 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
                 continue;
             }
 
+            if address_space
+                .find(node.node_id())
+                .is_some_and(|n| !is_browsable(context, n))
+            {
+                node.set_status(StatusCode::BadNodeIdUnknown);
+                continue;
+            }
+
             node.set_status(StatusCode::Good);
 
             if let Some(mut point) = node.take_continuation_point::<BrowseContinuationPoint>() {
@@ -732,7 +910,7 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
                     node.set_next_continuation_point(point);
                 }
             } else {
-                Self::browse_node(&address_space, &type_tree, node, &self.namespaces);
+                Self::browse_node(&address_space, &type_tree, context, node, &self.namespaces);
             }
         }
 
@@ -805,6 +983,65 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         Ok(())
     }
 
+    async fn resolve_view(
+        &self,
+        context: &RequestContext,
+        view: &mut ViewContents,
+    ) -> Result<(), StatusCode> {
+        let address_space = trace_read_lock!(self.address_space);
+        let type_tree = trace_read_lock!(context.type_tree);
+
+        if self.owns_node(view.view_id()) {
+            Self::validate_view(&address_space, &type_tree, context, view)?;
+        }
+
+        for node_id in view.take_pending(|n| self.owns_node(n)) {
+            let targets: Vec<_> = address_space
+                .find_references(
+                    &node_id,
+                    Some((ReferenceTypeId::HierarchicalReferences, true)),
+                    &*type_tree,
+                    BrowseDirection::Forward,
+                )
+                .map(|r| r.target_node.clone())
+                .collect();
+            for target in targets {
+                view.add_node(target);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        context: &RequestContext,
+        request: &mut QueryRequest,
+    ) -> Result<(), StatusCode> {
+        let mut data_sets =
+            if let Some(point) = request.take_continuation_point::<QueryContinuationPoint>() {
+                point.data_sets
+            } else {
+                let address_space = trace_read_lock!(self.address_space);
+                let type_tree = trace_read_lock!(context.type_tree);
+                Self::query_address_space(&address_space, &type_tree, context, request)
+            };
+
+        while request.remaining_data_sets() > 0 {
+            let Some(data_set) = data_sets.pop_front() else {
+                break;
+            };
+            request.add_data_set(data_set);
+        }
+        if !data_sets.is_empty() {
+            request.set_next_continuation_point(Some(ContinuationPoint::new(Box::new(
+                QueryContinuationPoint { data_sets },
+            ))));
+        }
+
+        Ok(())
+    }
+
     async fn register_nodes(
         &self,
         context: &RequestContext,
@@ -960,9 +1197,20 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         timestamps_to_return: TimestampsToReturn,
     ) -> Result<(), StatusCode> {
         let mut nodes = self.validate_history_read_nodes(context, nodes, false);
-        self.inner
+        let mut history_nodes = self.take_history_nodes(&mut nodes, |n| n.node_id());
+        if let Some(history) = &self.history {
+            history.read_raw_modified(details, &mut history_nodes, timestamps_to_return);
+        }
+        if nodes.is_empty() && !history_nodes.is_empty() {
+            return Ok(());
+        }
+        let result = self
+            .inner
             .history_read_raw_modified(context, details, &mut nodes, timestamps_to_return)
-            .await
+            .await;
+        Self::inner_history_result(result, !history_nodes.is_empty(), &mut nodes, |n, e| {
+            n.set_status(e)
+        })
     }
 
     async fn history_read_processed(
@@ -973,9 +1221,20 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         timestamps_to_return: TimestampsToReturn,
     ) -> Result<(), StatusCode> {
         let mut nodes = self.validate_history_read_nodes(context, nodes, false);
-        self.inner
+        let mut history_nodes = self.take_history_nodes(&mut nodes, |n| n.node_id());
+        if let Some(history) = &self.history {
+            history.read_processed(details, &mut history_nodes, timestamps_to_return);
+        }
+        if nodes.is_empty() && !history_nodes.is_empty() {
+            return Ok(());
+        }
+        let result = self
+            .inner
             .history_read_processed(context, details, &mut nodes, timestamps_to_return)
-            .await
+            .await;
+        Self::inner_history_result(result, !history_nodes.is_empty(), &mut nodes, |n, e| {
+            n.set_status(e)
+        })
     }
 
     async fn history_read_at_time(
@@ -986,9 +1245,20 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         timestamps_to_return: TimestampsToReturn,
     ) -> Result<(), StatusCode> {
         let mut nodes = self.validate_history_read_nodes(context, nodes, false);
-        self.inner
+        let mut history_nodes = self.take_history_nodes(&mut nodes, |n| n.node_id());
+        if let Some(history) = &self.history {
+            history.read_at_time(details, &mut history_nodes, timestamps_to_return);
+        }
+        if nodes.is_empty() && !history_nodes.is_empty() {
+            return Ok(());
+        }
+        let result = self
+            .inner
             .history_read_at_time(context, details, &mut nodes, timestamps_to_return)
-            .await
+            .await;
+        Self::inner_history_result(result, !history_nodes.is_empty(), &mut nodes, |n, e| {
+            n.set_status(e)
+        })
     }
 
     async fn history_read_events(
@@ -1033,7 +1303,17 @@ impl<TImpl: InMemoryNodeManagerImpl> NodeManager for InMemoryNodeManager<TImpl>
         nodes: &mut [&mut HistoryUpdateNode],
     ) -> Result<(), StatusCode> {
         let mut nodes = self.validate_history_write_nodes(context, nodes);
-        self.inner.history_update(context, &mut nodes).await
+        let mut history_nodes = self.take_history_nodes(&mut nodes, |n| n.details().node_id());
+        if let Some(history) = &self.history {
+            history.update(context, &mut history_nodes);
+        }
+        if nodes.is_empty() && !history_nodes.is_empty() {
+            return Ok(());
+        }
+        let result = self.inner.history_update(context, &mut nodes).await;
+        Self::inner_history_result(result, !history_nodes.is_empty(), &mut nodes, |n, e| {
+            n.set_status(e)
+        })
     }
 
     async fn call(
@@ -0,0 +1,207 @@
+//! Implementation of the Query service for [InMemoryNodeManager].
+
+use std::collections::VecDeque;
+
+use opcua_nodes::{AttributeQueryable, DefaultTypeTree, TypeTree};
+use opcua_types::{
+    AttributeId, BrowseDirection, DataEncoding, ExpandedNodeId, NodeClass, NodeId, NumericRange,
+    QualifiedName, QueryDataSet, ReferenceTypeId, RelativePath, TimestampsToReturn, Variant,
+};
+
+use crate::{
+    address_space::{
+        is_browsable, read_node_value, validate_node_read, AddressSpace, HasNodeId, NodeType,
+    },
+    node_manager::{ParsedNodeTypeDescription, ParsedReadValueId, QueryRequest, RequestContext},
+};
+
+use super::{InMemoryNodeManager, InMemoryNodeManagerImpl};
+
+/// Continuation point for queries, containing the data sets that
+/// did not fit in the last response.
+pub(super) struct QueryContinuationPoint {
+    pub(super) data_sets: VecDeque<QueryDataSet>,
+}
+
+/// A node being evaluated against the content filter of a query.
+#[derive(Clone, Copy)]
+struct QueryNode<'a> {
+    address_space: &'a AddressSpace,
+    type_tree: &'a DefaultTypeTree,
+    context: &'a RequestContext,
+    node: &'a NodeType,
+    type_definition: &'a NodeId,
+}
+
+impl AttributeQueryable for QueryNode<'_> {
+    fn get_attribute(
+        &self,
+        type_definition_id: &NodeId,
+        browse_path: &[QualifiedName],
+        attribute_id: AttributeId,
+        index_range: &NumericRange,
+    ) -> Variant {
+        // Operands only apply to nodes of the type they refer to.
+        if !self
+            .type_tree
+            .is_subtype_of(self.type_definition, type_definition_id)
+        {
+            return Variant::Empty;
+        }
+        let target = if browse_path.is_empty() {
+            Some(self.node)
+        } else {
+            self.address_space.find_node_by_browse_path(
+                self.node.node_id(),
+                Some((ReferenceTypeId::HierarchicalReferences, true)),
+                self.type_tree,
+                BrowseDirection::Forward,
+                browse_path,
+            )
+        };
+        let Some(target) = target else {
+            return Variant::Empty;
+        };
+        read_attribute(self.context, target, attribute_id, index_range)
+    }
+}
+
+/// Read an attribute from a node, returning an empty variant if the read fails.
+fn read_attribute(
+    context: &RequestContext,
+    node: &NodeType,
+    attribute_id: AttributeId,
+    index_range: &NumericRange,
+) -> Variant {
+    let to_read = ParsedReadValueId {
+        node_id: node.node_id().clone(),
+        attribute_id,
+        index_range: index_range.clone(),
+        data_encoding: DataEncoding::Binary,
+    };
+    if validate_node_read(node, context, &to_read).is_err() {
+        return Variant::Empty;
+    }
+    read_node_value(node, context, &to_read, 0.0, TimestampsToReturn::Neither)
+        .value
+        .unwrap_or_default()
+}
+
+/// Follow a relative path from `node`, returning the first node matching it.
+fn follow_relative_path<'a>(
+    address_space: &'a AddressSpace,
+    type_tree: &DefaultTypeTree,
+    node: &'a NodeType,
+    path: &RelativePath,
+) -> Option<&'a NodeType> {
+    let mut current = node;
+    for element in path.elements.iter().flatten() {
+        let filter = if element.reference_type_id.is_null() {
+            None
+        } else {
+            Some((element.reference_type_id.clone(), element.include_subtypes))
+        };
+        let direction = if element.is_inverse {
+            BrowseDirection::Inverse
+        } else {
+            BrowseDirection::Forward
+        };
+        current = address_space
+            .find_references(current.node_id(), filter, type_tree, direction)
+            .filter_map(|r| address_space.find_node(r.target_node))
+            .find(|n| {
+                element.target_name.is_null() || n.as_node().browse_name() == &element.target_name
+            })?;
+    }
+    Some(current)
+}
+
+impl<TImpl: InMemoryNodeManagerImpl> InMemoryNodeManager<TImpl> {
+    /// Evaluate a query against the entire address space, returning a data set
+    /// for each matching node.
+    ///
+    /// Values are read from the address space directly, so variables with values
+    /// provided by the node manager implementation may not be current.
+    pub(super) fn query_address_space(
+        address_space: &AddressSpace,
+        type_tree: &DefaultTypeTree,
+        context: &RequestContext,
+        request: &QueryRequest,
+    ) -> VecDeque<QueryDataSet> {
+        let mut data_sets = VecDeque::new();
+        for node in address_space.iter_nodes() {
+            // Only objects and variables have type definitions.
+            if !matches!(node.node_class(), NodeClass::Object | NodeClass::Variable) {
+                continue;
+            }
+            if request.view().is_some_and(|v| !v.contains(node.node_id())) {
+                continue;
+            }
+            if !is_browsable(context, node) {
+                continue;
+            }
+            let Some(type_definition) = address_space
+                .find_references(
+                    node.node_id(),
+                    Some((ReferenceTypeId::HasTypeDefinition, false)),
+                    type_tree,
+                    BrowseDirection::Forward,
+                )
+                .next()
+                .map(|r| r.target_node)
+            else {
+                continue;
+            };
+            let Some(node_type) = request
+                .node_types()
+                .iter()
+                .find(|t| Self::matches_node_type(type_tree, type_definition, t))
+            else {
+                continue;
+            };
+            let item = QueryNode {
+                address_space,
+                type_tree,
+                context,
+                node,
+                type_definition,
+            };
+            if !request.filter().evaluate(item) {
+                continue;
+            }
+
+            let values = node_type
+                .data_to_return
+                .iter()
+                .map(|d| {
+                    follow_relative_path(address_space, type_tree, node, &d.relative_path)
+                        .map(|n| read_attribute(context, n, d.attribute_id, &d.index_range))
+                        .unwrap_or_default()
+                })
+                .collect();
+
+            data_sets.push_back(QueryDataSet {
+                node_id: ExpandedNodeId::new(node.node_id().clone()),
+                type_definition_node: ExpandedNodeId::new(type_definition.clone()),
+                values: Some(values),
+            });
+        }
+        data_sets
+    }
+
+    fn matches_node_type(
+        type_tree: &DefaultTypeTree,
+        type_definition: &NodeId,
+        node_type: &ParsedNodeTypeDescription,
+    ) -> bool {
+        let wanted = &node_type.type_definition_node;
+        if wanted.server_index != 0 {
+            return false;
+        }
+        if node_type.include_sub_types {
+            type_tree.is_subtype_of(type_definition, &wanted.node_id)
+        } else {
+            type_definition == &wanted.node_id
+        }
+    }
+}
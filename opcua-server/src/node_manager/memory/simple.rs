@@ -8,7 +8,8 @@ use crate::{
     address_space::{read_node_value, AddressSpace, NodeBase, NodeType},
     node_manager::{
         DefaultTypeTree, MethodCall, MonitoredItemRef, MonitoredItemUpdateRef, NodeManagerBuilder,
-        NodeManagersRef, ParsedReadValueId, RequestContext, ServerContext, SyncSampler, WriteNode,
+        NodeManagersRef, ParsedReadValueId, RequestContext, ServerContext, SyncSampler,
+        TypedMethod, WriteNode,
     },
     CreateMonitoredItem,
 };
@@ -34,7 +35,14 @@ type ReadCB = Arc<
         + Sync
         + 'static,
 >;
-type MethodCB = Arc<dyn Fn(&[Variant]) -> Result<Vec<Variant>, StatusCode> + Send + Sync + 'static>;
+type SyncMethodCB =
+    Arc<dyn Fn(&[Variant]) -> Result<Vec<Variant>, StatusCode> + Send + Sync + 'static>;
+
+#[derive(Clone)]
+enum MethodCB {
+    Sync(SyncMethodCB),
+    Typed(TypedMethod),
+}
 
 /// Builder for the [SimpleNodeManager].
 pub struct SimpleNodeManagerBuilder {
@@ -284,16 +292,20 @@ impl InMemoryNodeManagerImpl for SimpleNodeManagerImpl {
         _address_space: &RwLock<AddressSpace>,
         methods_to_call: &mut [&mut &mut MethodCall],
     ) -> Result<(), StatusCode> {
-        let cbs = trace_read_lock!(self.method_cbs);
         for method in methods_to_call {
-            if let Some(cb) = cbs.get(method.method_id()) {
-                match cb(method.arguments()) {
+            let cb = trace_read_lock!(self.method_cbs)
+                .get(method.method_id())
+                .cloned();
+            match cb {
+                Some(MethodCB::Sync(cb)) => match cb(method.arguments()) {
                     Ok(r) => {
                         method.set_outputs(r);
                         method.set_status(StatusCode::Good);
                     }
                     Err(e) => method.set_status(e),
-                }
+                },
+                Some(MethodCB::Typed(typed)) => typed.call(method).await,
+                None => (),
             }
         }
 
@@ -408,6 +420,15 @@ impl SimpleNodeManagerImpl {
         cb: impl Fn(&[Variant]) -> Result<Vec<Variant>, StatusCode> + Send + Sync + 'static,
     ) {
         let mut cbs = trace_write_lock!(self.method_cbs);
-        cbs.insert(id, Arc::new(cb));
+        cbs.insert(id, MethodCB::Sync(Arc::new(cb)));
+    }
+
+    /// Add a [TypedMethod] called on `Call` for the method given by `id`.
+    ///
+    /// Use [TypedMethod::add_arguments] when creating the method node to
+    /// add properties describing its arguments.
+    pub fn add_typed_method(&self, id: NodeId, method: TypedMethod) {
+        let mut cbs = trace_write_lock!(self.method_cbs);
+        cbs.insert(id, MethodCB::Typed(method));
     }
 }
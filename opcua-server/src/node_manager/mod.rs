@@ -31,13 +31,13 @@ mod query;
 mod utils;
 mod view;
 
-use crate::ServerStatusWrapper;
+use crate::{address_space::NodePermissions, ServerStatusWrapper};
 
 use self::view::ExternalReferenceRequest;
 
 use super::{
-    authenticator::AuthManager, info::ServerInfo, subscriptions::CreateMonitoredItem,
-    SubscriptionCache,
+    authenticator::AuthManager, info::ServerInfo, session::manager::SessionManager,
+    subscriptions::CreateMonitoredItem, SubscriptionCache,
 };
 
 pub use {
@@ -50,11 +50,14 @@ pub use {
     node_management::{AddNodeItem, AddReferenceItem, DeleteNodeItem, DeleteReferenceItem},
     query::{ParsedNodeTypeDescription, ParsedQueryDataDescription, QueryRequest},
     utils::*,
-    view::{AddReferenceResult, BrowseNode, BrowsePathItem, ExternalReference, RegisterNodeItem},
+    view::{
+        AddReferenceResult, BrowseNode, BrowsePathItem, ExternalReference, RegisterNodeItem,
+        ViewContents,
+    },
 };
 
-pub(crate) use context::resolve_external_references;
 pub(crate) use context::DefaultTypeTreeGetter;
+pub(crate) use context::{resolve_external_references, resolve_view};
 pub(crate) use history::HistoryReadDetails;
 pub(crate) use query::QueryContinuationPoint;
 pub(crate) use view::{BrowseContinuationPoint, ExternalReferencesContPoint};
@@ -248,6 +251,8 @@ pub struct ServerContext {
     pub type_tree_getter: Arc<dyn TypeTreeForUser>,
     /// Wrapper managing the `ServerStatus` server variable.
     pub status: Arc<ServerStatusWrapper>,
+    /// Session manager, containing all sessions on the server.
+    pub session_manager: Arc<RwLock<SessionManager>>,
 }
 
 /// This trait is a workaround for the lack of
@@ -308,6 +313,10 @@ pub trait NodeManager: IntoAnyArc + Any {
         false
     }
 
+    /// Called when a session is closed or expires. Node managers keeping
+    /// per-session state, such as open file handles, should release it here.
+    fn session_closed(&self, session_id: u32) {}
+
     /// Namespaces for a given user, used to populate the namespace array.
     /// This being a method allows different users to see different namespaces.
     fn namespaces_for_user(&self, context: &RequestContext) -> Vec<NamespaceMetadata>;
@@ -324,6 +333,21 @@ pub trait NodeManager: IntoAnyArc + Any {
     ) {
     }
 
+    /// Get the `RolePermissions` and `AccessRestrictions` of each node in `node_ids`,
+    /// which are all owned by this node manager.
+    ///
+    /// The server validates these before calling services on the node manager, and
+    /// before delivering events to event monitored items. The default implementation
+    /// defines no permissions on individual nodes, so only the defaults of the
+    /// namespace apply.
+    async fn node_permissions(
+        &self,
+        context: &RequestContext,
+        node_ids: &[&NodeId],
+    ) -> Vec<NodePermissions> {
+        vec![NodePermissions::default(); node_ids.len()]
+    }
+
     // ATTRIBUTES
     /// Execute the Read service. This should set results on the given nodes_to_read as needed.
     async fn read(
@@ -435,6 +459,28 @@ pub trait NodeManager: IntoAnyArc + Any {
         Err(StatusCode::BadServiceUnsupported)
     }
 
+    /// Resolve the contents of a View, used when a client passes a view to Browse or Query,
+    /// or translates a browse path starting at a View node.
+    ///
+    /// The node manager that owns the View node must validate that it exists, is a View
+    /// and matches `view_version`, and return `BadViewIdUnknown` or `BadViewVersionInvalid`
+    /// otherwise. Every node manager should then take the pending nodes it owns using
+    /// `ViewContents::take_pending`, and add the targets of their forward hierarchical
+    /// references. This is called repeatedly until no more nodes are added.
+    ///
+    /// The default implementation rejects any view owned by this node manager.
+    async fn resolve_view(
+        &self,
+        context: &RequestContext,
+        view: &mut ViewContents,
+    ) -> Result<(), StatusCode> {
+        if self.owns_node(view.view_id()) {
+            Err(StatusCode::BadViewIdUnknown)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Perform the register nodes service. The default behavior for this service is to
     /// do nothing and pretend the nodes were registered.
     async fn register_nodes(
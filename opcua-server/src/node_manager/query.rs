@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::session::{
     continuation_points::{ContinuationPoint, EmptyContinuationPoint},
     instance::Session,
@@ -9,6 +11,8 @@ use opcua_types::{
     QueryDataDescription, QueryDataSet, RelativePath, StatusCode,
 };
 
+use super::ViewContents;
+
 pub(crate) struct QueryContinuationPoint {
     pub node_manager_index: usize,
     pub continuation_point: ContinuationPoint,
@@ -18,6 +22,7 @@ pub(crate) struct QueryContinuationPoint {
     filter: ParsedContentFilter,
     max_data_sets_to_return: usize,
     max_references_to_return: usize,
+    view: Option<Arc<ViewContents>>,
 }
 
 #[derive(Debug)]
@@ -115,6 +120,7 @@ pub struct QueryRequest {
     next_continuation_point: Option<ContinuationPoint>,
     status: StatusCode,
     node_manager_index: usize,
+    view: Option<Arc<ViewContents>>,
 
     data_sets: Vec<QueryDataSet>,
 }
@@ -125,6 +131,7 @@ impl QueryRequest {
         filter: ParsedContentFilter,
         max_data_sets_to_return: usize,
         max_references_to_return: usize,
+        view: Option<Arc<ViewContents>>,
     ) -> Self {
         Self {
             node_types,
@@ -136,6 +143,7 @@ impl QueryRequest {
             data_sets: Vec::new(),
             status: StatusCode::Good,
             node_manager_index: 0,
+            view,
         }
     }
 
@@ -150,6 +158,7 @@ impl QueryRequest {
             status: StatusCode::Good,
             data_sets: Vec::new(),
             node_manager_index: point.node_manager_index,
+            view: point.view,
         }
     }
 
@@ -163,6 +172,22 @@ impl QueryRequest {
         self.continuation_point.as_ref()
     }
 
+    /// Consume the continuation point created during the last request.
+    pub fn take_continuation_point<T: Send + Sync + 'static>(&mut self) -> Option<Box<T>> {
+        self.continuation_point.take().and_then(|c| c.take())
+    }
+
+    pub(crate) fn clear_continuation_point(&mut self) {
+        self.continuation_point = None;
+    }
+
+    /// Add a data set to the result. Node managers must make sure not to
+    /// exceed `remaining_data_sets`, and set a continuation point
+    /// if there are more results.
+    pub fn add_data_set(&mut self, data_set: QueryDataSet) {
+        self.data_sets.push(data_set);
+    }
+
     /// Maximum number of references to return.
     pub fn max_references_to_return(&self) -> usize {
         self.max_references_to_return
@@ -178,6 +203,12 @@ impl QueryRequest {
         &self.filter
     }
 
+    /// The view to query, if any. Only nodes contained in the view should
+    /// be returned.
+    pub fn view(&self) -> Option<&ViewContents> {
+        self.view.as_deref()
+    }
+
     /// Node types to query.
     pub fn node_types(&self) -> &[ParsedNodeTypeDescription] {
         &self.node_types
@@ -234,6 +265,7 @@ impl QueryRequest {
             filter: self.filter,
             max_data_sets_to_return: self.max_data_sets_to_return,
             max_references_to_return: self.max_references_to_return,
+            view: self.view,
         });
 
         let mut status = self.status;
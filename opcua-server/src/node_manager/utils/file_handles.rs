@@ -0,0 +1,159 @@
+//! Utility for managing handles to objects of `FileType`, as used by the
+//! `Open`, `Close`, `Read`, `Write`, `GetPosition` and `SetPosition` methods.
+
+use hashbrown::HashMap;
+use opcua_types::{NodeId, OpenFileMode, StatusCode};
+
+/// Check that `mode` is a valid mask of [OpenFileMode] values for the `Open` method
+/// on a `FileType` object. The file must be opened for reading, writing or both,
+/// and `EraseExisting` and `Append` are only valid when writing.
+pub fn validate_open_file_mode(mode: u8) -> Result<u8, StatusCode> {
+    let all = OpenFileMode::Read as u8
+        | OpenFileMode::Write as u8
+        | OpenFileMode::EraseExisting as u8
+        | OpenFileMode::Append as u8;
+    let read_or_write = OpenFileMode::Read as u8 | OpenFileMode::Write as u8;
+    let write_only = OpenFileMode::EraseExisting as u8 | OpenFileMode::Append as u8;
+
+    if mode & !all != 0
+        || mode & read_or_write == 0
+        || (mode & write_only != 0 && mode & OpenFileMode::Write as u8 == 0)
+    {
+        Err(StatusCode::BadInvalidArgument)
+    } else {
+        Ok(mode)
+    }
+}
+
+/// A handle to an open file.
+pub struct FileHandle<T> {
+    /// The node ID of the file object the handle was opened on.
+    pub node_id: NodeId,
+    /// The numeric ID of the session that opened the file.
+    pub session_id: u32,
+    /// Mask of [OpenFileMode] values the file was opened with.
+    pub mode: u8,
+    /// The open file itself.
+    pub file: T,
+}
+
+impl<T> FileHandle<T> {
+    /// Get whether the file was opened for reading.
+    pub fn is_readable(&self) -> bool {
+        self.mode & OpenFileMode::Read as u8 != 0
+    }
+
+    /// Get whether the file was opened for writing.
+    pub fn is_writable(&self) -> bool {
+        self.mode & OpenFileMode::Write as u8 != 0
+    }
+}
+
+/// Collection of file handles opened by sessions. Handles are only valid in the
+/// session that opened them, and are removed when that session is closed.
+///
+/// A file can be opened for reading by any number of handles, or by a single handle
+/// for writing.
+pub struct FileHandles<T> {
+    handles: HashMap<u32, FileHandle<T>>,
+    last_handle: u32,
+}
+
+impl<T> Default for FileHandles<T> {
+    fn default() -> Self {
+        Self {
+            handles: HashMap::new(),
+            last_handle: 0,
+        }
+    }
+}
+
+impl<T> FileHandles<T> {
+    /// Create a new, empty, collection of file handles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the file given by `node_id` may be opened with `mode`.
+    ///
+    /// Returns `BadNotReadable` if the file is open for writing, and `BadNotWritable`
+    /// if it is to be opened for writing while any other handle is open.
+    pub fn check_open(&self, node_id: &NodeId, mode: u8) -> Result<(), StatusCode> {
+        let mut open = self.handles.values().filter(|h| &h.node_id == node_id);
+        if mode & OpenFileMode::Write as u8 != 0 {
+            if open.next().is_some() {
+                return Err(StatusCode::BadNotWritable);
+            }
+        } else if open.any(|h| h.is_writable()) {
+            return Err(StatusCode::BadNotReadable);
+        }
+        Ok(())
+    }
+
+    /// Add a handle to an open file, returning the new file handle.
+    ///
+    /// This does not check that the file may be opened, call [FileHandles::check_open] first.
+    pub fn insert(&mut self, node_id: NodeId, session_id: u32, mode: u8, file: T) -> u32 {
+        // Handle 0 is reserved, `CreateFile` returns it when the file is not opened.
+        loop {
+            self.last_handle = self.last_handle.wrapping_add(1).max(1);
+            if !self.handles.contains_key(&self.last_handle) {
+                break;
+            }
+        }
+        self.handles.insert(
+            self.last_handle,
+            FileHandle {
+                node_id,
+                session_id,
+                mode,
+                file,
+            },
+        );
+        self.last_handle
+    }
+
+    /// Get the handle `handle` opened by `session_id` on the file given by `node_id`.
+    /// Returns `BadInvalidArgument` if no such handle is open.
+    pub fn get_mut(
+        &mut self,
+        node_id: &NodeId,
+        session_id: u32,
+        handle: u32,
+    ) -> Result<&mut FileHandle<T>, StatusCode> {
+        match self.handles.get_mut(&handle) {
+            Some(h) if &h.node_id == node_id && h.session_id == session_id => Ok(h),
+            _ => Err(StatusCode::BadInvalidArgument),
+        }
+    }
+
+    /// Close the handle `handle` opened by `session_id` on the file given by `node_id`.
+    /// Returns `BadInvalidArgument` if no such handle is open.
+    pub fn remove(
+        &mut self,
+        node_id: &NodeId,
+        session_id: u32,
+        handle: u32,
+    ) -> Result<FileHandle<T>, StatusCode> {
+        self.get_mut(node_id, session_id, handle)?;
+        Ok(self.handles.remove(&handle).unwrap())
+    }
+
+    /// Get the number of handles open on the file given by `node_id`.
+    pub fn open_count(&self, node_id: &NodeId) -> usize {
+        self.handles
+            .values()
+            .filter(|h| &h.node_id == node_id)
+            .count()
+    }
+
+    /// Get whether any open handle matches `filter`.
+    pub fn any(&self, filter: impl FnMut(&FileHandle<T>) -> bool) -> bool {
+        self.handles.values().any(filter)
+    }
+
+    /// Close all handles opened by `session_id`. Call this when the session is closed.
+    pub fn remove_session(&mut self, session_id: u32) {
+        self.handles.retain(|_, h| h.session_id != session_id);
+    }
+}
@@ -1,9 +1,14 @@
+mod file_handles;
 mod opaque_node_id;
 mod operations;
 mod result;
 mod sync_sampler;
+mod typed_method;
 
+pub use file_handles::{validate_open_file_mode, FileHandle, FileHandles};
 pub use opaque_node_id::*;
-pub use operations::{get_namespaces_for_user, get_node_metadata};
+pub(crate) use operations::validate_permissions;
+pub use operations::{get_namespaces_for_user, get_node_metadata, get_node_permissions};
 pub(crate) use result::{consume_results, IntoResult};
 pub use sync_sampler::SyncSampler;
+pub use typed_method::{MethodHandler, MethodOutputs, TypedMethod};
@@ -1,9 +1,12 @@
-use crate::node_manager::{
-    view::{ExternalReferenceRequest, NodeMetadata},
-    NodeManagerCollection, RequestContext,
+use crate::{
+    address_space::NodePermissions,
+    node_manager::{
+        view::{ExternalReferenceRequest, NodeMetadata},
+        NodeManagerCollection, RequestContext,
+    },
 };
 use hashbrown::HashMap;
-use opcua_types::{BrowseDescriptionResultMask, NamespaceMap, NodeId};
+use opcua_types::{BrowseDescriptionResultMask, NamespaceMap, NodeId, PermissionType, StatusCode};
 
 /// Fetch external references by requesting them from their owning node manager.
 ///
@@ -44,3 +47,75 @@ pub fn get_namespaces_for_user(
 
     NamespaceMap::new_full(nss)
 }
+
+/// Get the permissions of each node in `node_ids` from the node manager owning it,
+/// with the defaults of the namespace applied. Nodes not owned by any node manager
+/// get `None`.
+pub async fn get_node_permissions(
+    context: &RequestContext,
+    node_managers: &impl NodeManagerCollection,
+    node_ids: &[&NodeId],
+) -> Vec<Option<NodePermissions>> {
+    let mut res: Vec<Option<NodePermissions>> = vec![None; node_ids.len()];
+
+    for nm in node_managers.iter_node_managers() {
+        let (indices, owned): (Vec<_>, Vec<_>) = node_ids
+            .iter()
+            .enumerate()
+            .filter(|(idx, id)| res[*idx].is_none() && nm.owns_node(id))
+            .map(|(idx, id)| (idx, *id))
+            .unzip();
+        if owned.is_empty() {
+            continue;
+        }
+        let permissions = nm.node_permissions(context, &owned).await;
+        for ((idx, id), p) in indices.into_iter().zip(owned).zip(permissions) {
+            res[idx] = Some(p.with_namespace_defaults(&context.info.roles, id.namespace));
+        }
+    }
+
+    res
+}
+
+/// Validate that the user given by `context` has the permission required for
+/// each item in `items`, and call `set_error` on any item they are not allowed
+/// to access. `required` returns `None` for items that should not be checked.
+///
+/// Returns whether each item is allowed. Items that are not allowed must not be
+/// passed to node managers, since the error may be `BadNodeIdUnknown`.
+pub(crate) async fn validate_permissions<T>(
+    context: &RequestContext,
+    node_managers: &impl NodeManagerCollection,
+    items: &mut [T],
+    required: impl Fn(&T) -> Option<(&NodeId, PermissionType)>,
+    set_error: impl Fn(&mut T, StatusCode),
+) -> Vec<bool> {
+    let required: Vec<_> = items
+        .iter()
+        .map(|it| required(it).map(|(id, p)| (id.clone(), p)))
+        .collect();
+    let node_ids: Vec<_> = required.iter().flatten().map(|(id, _)| id).collect();
+    let mut permissions = get_node_permissions(context, node_managers, &node_ids)
+        .await
+        .into_iter();
+
+    items
+        .iter_mut()
+        .zip(required.iter())
+        .map(|(item, required)| {
+            let Some((_, permission)) = required else {
+                return true;
+            };
+            let Some(Some(node_permissions)) = permissions.next() else {
+                return true;
+            };
+            match node_permissions.validate(&context.roles, context.security_mode, *permission) {
+                Ok(()) => true,
+                Err(e) => {
+                    set_error(item, e);
+                    false
+                }
+            }
+        })
+        .collect()
+}
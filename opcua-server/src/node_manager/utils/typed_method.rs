@@ -0,0 +1,283 @@
+//! Framework for implementing methods as async functions with typed arguments.
+
+use std::{future::Future, sync::Arc};
+
+use futures::future::BoxFuture;
+use log::warn;
+use opcua_types::{
+    Argument, DataTypeId, ExpandedNodeId, LocalizedText, MethodArg, NodeId, StatusCode, UAString,
+    Variant,
+};
+
+use crate::{
+    address_space::{AddressSpace, MethodBuilder},
+    node_manager::MethodCall,
+};
+
+/// The data type and value rank of a method argument.
+type ArgumentType = (ExpandedNodeId, i32);
+
+type MethodFuture = BoxFuture<'static, Result<Vec<Variant>, StatusCode>>;
+
+type DynMethodHandler = dyn Fn(&[Variant]) -> Result<MethodFuture, Vec<StatusCode>> + Send + Sync;
+
+/// Trait for the values returned by a typed method, implemented for
+/// tuples of up to 8 values implementing [MethodArg].
+pub trait MethodOutputs {
+    /// Get the data type and value rank of each output.
+    fn output_types() -> Vec<(ExpandedNodeId, i32)>;
+
+    /// Convert the outputs to a list of variants.
+    fn into_outputs(self) -> Vec<Variant>;
+}
+
+/// Trait for functions that can be used as the implementation of a [TypedMethod].
+///
+/// This is implemented for `Fn(A, B, ...) -> impl Future<Output = Result<O, StatusCode>>`,
+/// with up to 8 arguments implementing [MethodArg] and `O` implementing [MethodOutputs].
+/// `Args` is a tuple of the argument types, and is used to tell the implementations apart.
+pub trait MethodHandler<Args>: Send + Sync + 'static {
+    /// Get the data type and value rank of each input.
+    fn input_types() -> Vec<(ExpandedNodeId, i32)>;
+
+    /// Get the data type and value rank of each output.
+    fn output_types() -> Vec<(ExpandedNodeId, i32)>;
+
+    /// Convert the arguments and call the function. `args` must have exactly as many
+    /// elements as there are inputs. If any argument cannot be converted, returns
+    /// the result for each argument.
+    fn call(&self, args: &[Variant]) -> Result<MethodFuture, Vec<StatusCode>>;
+}
+
+macro_rules! impl_method_outputs {
+    ($($tp:ident),*) => {
+        impl<$($tp: MethodArg),*> MethodOutputs for ($($tp,)*) {
+            fn output_types() -> Vec<ArgumentType> {
+                vec![$(($tp::data_type(), $tp::value_rank())),*]
+            }
+
+            #[allow(non_snake_case)]
+            fn into_outputs(self) -> Vec<Variant> {
+                let ($($tp,)*) = self;
+                vec![$($tp.into()),*]
+            }
+        }
+    };
+}
+
+macro_rules! impl_method_handler {
+    ($($tp:ident),*) => {
+        impl<F, Fut, O, $($tp),*> MethodHandler<($($tp,)*)> for F
+        where
+            F: Fn($($tp),*) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<O, StatusCode>> + Send + 'static,
+            O: MethodOutputs,
+            $($tp: MethodArg),*
+        {
+            fn input_types() -> Vec<ArgumentType> {
+                vec![$(($tp::data_type(), $tp::value_rank())),*]
+            }
+
+            fn output_types() -> Vec<ArgumentType> {
+                O::output_types()
+            }
+
+            #[allow(non_snake_case, unused_variables, unused_mut, irrefutable_let_patterns)]
+            fn call(&self, args: &[Variant]) -> Result<MethodFuture, Vec<StatusCode>> {
+                let mut args = args.iter();
+                let mut results = Vec::new();
+                $(
+                    let $tp = args
+                        .next()
+                        .and_then(|v| $tp::try_from_variant(v.clone()).ok());
+                    results.push(if $tp.is_some() {
+                        StatusCode::Good
+                    } else {
+                        StatusCode::BadTypeMismatch
+                    });
+                )*
+                let ($(Some($tp),)*) = ($($tp,)*) else {
+                    return Err(results);
+                };
+                let fut = self($($tp),*);
+                Ok(Box::pin(async move { Ok(fut.await?.into_outputs()) }))
+            }
+        }
+    };
+}
+
+macro_rules! impl_for_tuples {
+    ($($tp:ident),*) => {
+        impl_method_outputs!($($tp),*);
+        impl_method_handler!($($tp),*);
+    };
+}
+
+impl_for_tuples!();
+impl_for_tuples!(A1);
+impl_for_tuples!(A1, A2);
+impl_for_tuples!(A1, A2, A3);
+impl_for_tuples!(A1, A2, A3, A4);
+impl_for_tuples!(A1, A2, A3, A4, A5);
+impl_for_tuples!(A1, A2, A3, A4, A5, A6);
+impl_for_tuples!(A1, A2, A3, A4, A5, A6, A7);
+impl_for_tuples!(A1, A2, A3, A4, A5, A6, A7, A8);
+
+/// A method implemented by an async function with typed arguments.
+///
+/// The method takes care of converting and validating the input arguments,
+/// and of generating the `InputArguments` and `OutputArguments` properties
+/// describing them.
+///
+/// # Example
+///
+/// ```ignore
+/// let method = TypedMethod::new(|a: i32, b: i32| async move { Ok((a + b,)) })
+///     .input_names(&["A", "B"])
+///     .output_names(&["Sum"]);
+/// ```
+#[derive(Clone)]
+pub struct TypedMethod {
+    inputs: Vec<ArgumentType>,
+    outputs: Vec<ArgumentType>,
+    input_names: Vec<String>,
+    output_names: Vec<String>,
+    handler: Arc<DynMethodHandler>,
+}
+
+impl TypedMethod {
+    /// Create a new typed method from an async function.
+    ///
+    /// The inputs are named `Input1`, `Input2`, etc., and the outputs `Output1`,
+    /// `Output2`, etc., unless names are given with [TypedMethod::input_names] and
+    /// [TypedMethod::output_names].
+    pub fn new<Args, H: MethodHandler<Args>>(handler: H) -> Self {
+        let inputs = H::input_types();
+        let outputs = H::output_types();
+        Self {
+            input_names: (1..=inputs.len()).map(|i| format!("Input{i}")).collect(),
+            output_names: (1..=outputs.len()).map(|i| format!("Output{i}")).collect(),
+            inputs,
+            outputs,
+            handler: Arc::new(move |args| handler.call(args)),
+        }
+    }
+
+    /// Set the names of the inputs. Any names beyond the number of inputs are ignored.
+    pub fn input_names(mut self, names: &[&str]) -> Self {
+        for (name, target) in names.iter().zip(self.input_names.iter_mut()) {
+            *target = (*name).to_owned();
+        }
+        self
+    }
+
+    /// Set the names of the outputs. Any names beyond the number of outputs are ignored.
+    pub fn output_names(mut self, names: &[&str]) -> Self {
+        for (name, target) in names.iter().zip(self.output_names.iter_mut()) {
+            *target = (*name).to_owned();
+        }
+        self
+    }
+
+    /// Get the `Argument` metadata of each input.
+    ///
+    /// Data types of custom structures are resolved using the namespaces in `address_space`.
+    pub fn input_arguments(&self, address_space: &AddressSpace) -> Vec<Argument> {
+        make_arguments(&self.input_names, &self.inputs, address_space)
+    }
+
+    /// Get the `Argument` metadata of each output.
+    ///
+    /// Data types of custom structures are resolved using the namespaces in `address_space`.
+    pub fn output_arguments(&self, address_space: &AddressSpace) -> Vec<Argument> {
+        make_arguments(&self.output_names, &self.outputs, address_space)
+    }
+
+    /// Add the `InputArguments` and `OutputArguments` properties for this method to
+    /// `builder`, with node IDs `input_args_id` and `output_args_id`. The properties
+    /// are only created if the method has any inputs or outputs, respectively.
+    pub fn add_arguments(
+        &self,
+        builder: MethodBuilder,
+        address_space: &mut AddressSpace,
+        input_args_id: &NodeId,
+        output_args_id: &NodeId,
+    ) -> MethodBuilder {
+        let inputs = self.input_arguments(address_space);
+        let outputs = self.output_arguments(address_space);
+        let mut builder = builder;
+        if !inputs.is_empty() {
+            builder = builder.input_args(address_space, input_args_id, &inputs);
+        }
+        if !outputs.is_empty() {
+            builder = builder.output_args(address_space, output_args_id, &outputs);
+        }
+        builder
+    }
+
+    /// Call the method, setting the status, outputs, and argument results of `call`.
+    ///
+    /// If too few arguments are given, the status is set to `BadArgumentsMissing`, and if
+    /// too many are given, to `BadTooManyArguments`. If any argument has the wrong type,
+    /// the status is set to `BadInvalidArgument`, with `BadTypeMismatch` as the result
+    /// for each invalid argument.
+    pub async fn call(&self, call: &mut MethodCall) {
+        let args = call.arguments();
+        if args.len() < self.inputs.len() {
+            call.set_status(StatusCode::BadArgumentsMissing);
+            return;
+        }
+        if args.len() > self.inputs.len() {
+            call.set_status(StatusCode::BadTooManyArguments);
+            return;
+        }
+        let fut = match (self.handler)(args) {
+            Ok(fut) => fut,
+            Err(results) => {
+                call.set_argument_error(results);
+                return;
+            }
+        };
+        match fut.await {
+            Ok(outputs) => {
+                call.set_outputs(outputs);
+                call.set_status(StatusCode::Good);
+            }
+            Err(e) => call.set_status(e),
+        }
+    }
+}
+
+fn make_arguments(
+    names: &[String],
+    types: &[ArgumentType],
+    address_space: &AddressSpace,
+) -> Vec<Argument> {
+    names
+        .iter()
+        .zip(types)
+        .map(|(name, (data_type, value_rank))| Argument {
+            name: UAString::from(name),
+            data_type: resolve_data_type(data_type, address_space),
+            value_rank: *value_rank,
+            array_dimensions: (*value_rank == 1).then(|| vec![0]),
+            description: LocalizedText::null(),
+        })
+        .collect()
+}
+
+fn resolve_data_type(data_type: &ExpandedNodeId, address_space: &AddressSpace) -> NodeId {
+    let Some(uri) = data_type.namespace_uri.value() else {
+        return data_type.node_id.clone();
+    };
+    match address_space.namespace_index(uri) {
+        Some(namespace) => NodeId {
+            namespace,
+            identifier: data_type.node_id.identifier.clone(),
+        },
+        None => {
+            warn!("Namespace {uri} of method argument data type is not in the address space");
+            DataTypeId::Structure.into()
+        }
+    }
+}
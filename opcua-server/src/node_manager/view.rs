@@ -1,4 +1,7 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
 
 use crate::{
     address_space::ReferenceDirection,
@@ -92,6 +95,86 @@ impl ExternalReferenceRequest {
     }
 }
 
+#[derive(Debug, Clone)]
+/// The resolved contents of a View, used to restrict the results of
+/// Browse, TranslateBrowsePathsToNodeIds and Query.
+///
+/// A View contains the View node itself, and every node reachable from it
+/// through forward hierarchical references. References are part of the view
+/// if both source and target are. The contents are collected by calling
+/// `resolve_view` on each node manager, which should take the pending nodes
+/// it owns using `take_pending` and add their hierarchical children.
+pub struct ViewContents {
+    view_id: NodeId,
+    view_version: u32,
+    nodes: HashSet<NodeId>,
+    pending: Vec<NodeId>,
+}
+
+impl ViewContents {
+    pub(crate) fn new(view_id: NodeId, view_version: u32) -> Self {
+        Self {
+            nodes: HashSet::from([view_id.clone()]),
+            pending: vec![view_id.clone()],
+            view_id,
+            view_version,
+        }
+    }
+
+    /// Node ID of the View node.
+    pub fn view_id(&self) -> &NodeId {
+        &self.view_id
+    }
+
+    /// The requested version of the view, or zero if the client
+    /// did not request a specific version.
+    pub fn view_version(&self) -> u32 {
+        self.view_version
+    }
+
+    /// Add a node to the view. If the node has not been seen before it
+    /// is queued for expansion by the node manager that owns it.
+    pub fn add_node(&mut self, node_id: NodeId) -> bool {
+        if self.nodes.insert(node_id.clone()) {
+            self.pending.push(node_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take any nodes that have been added to the view but not yet expanded,
+    /// and that match `filter`. Typically `filter` is whether the node
+    /// manager owns the node.
+    pub fn take_pending(&mut self, filter: impl Fn(&NodeId) -> bool) -> Vec<NodeId> {
+        let (taken, rest) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|n| filter(n));
+        self.pending = rest;
+        taken
+    }
+
+    /// Return `true` if the given node is part of the view.
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.nodes.contains(node_id)
+    }
+
+    /// Return `true` if the given node is part of the view. Nodes on other servers
+    /// are never part of a view.
+    pub fn contains_expanded(&self, node_id: &ExpandedNodeId) -> bool {
+        node_id.server_index == 0 && self.contains(&node_id.node_id)
+    }
+
+    /// Iterate over the nodes in the view.
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeId> {
+        self.nodes.iter()
+    }
+
+    pub(crate) fn len(&self) -> (usize, usize) {
+        (self.nodes.len(), self.pending.len())
+    }
+}
+
 #[derive(Debug)]
 /// A reference pointing to some node in a different node manager.
 pub struct ExternalReference {
@@ -159,6 +242,7 @@ pub struct BrowseNode {
     max_references_per_node: usize,
     input_index: usize,
     pub(crate) start_node_manager: usize,
+    view: Option<Arc<ViewContents>>,
 
     /// List of references to nodes not owned by the node manager that generated the
     /// reference. These are resolved after the initial browse, and any excess is stored
@@ -178,6 +262,7 @@ pub(crate) struct BrowseContinuationPoint {
     node_class_mask: NodeClassMask,
     result_mask: BrowseDescriptionResultMask,
     pub(crate) max_references_per_node: usize,
+    view: Option<Arc<ViewContents>>,
 
     external_references: Vec<ExternalReference>,
 }
@@ -188,6 +273,7 @@ impl BrowseNode {
         description: BrowseDescription,
         max_references_per_node: usize,
         input_index: usize,
+        view: Option<Arc<ViewContents>>,
     ) -> Self {
         Self {
             node_id: description.node_id,
@@ -203,6 +289,7 @@ impl BrowseNode {
             status_code: StatusCode::BadNodeIdUnknown,
             input_index,
             start_node_manager: 0,
+            view,
             external_references: Vec::new(),
         }
     }
@@ -225,6 +312,7 @@ impl BrowseNode {
             max_references_per_node: point.max_references_per_node,
             input_index,
             start_node_manager: point.node_manager_index,
+            view: point.view,
             external_references: point.external_references,
         }
     }
@@ -312,6 +400,20 @@ impl BrowseNode {
                 .contains(NodeClassMask::from_bits_truncate(node_class as u32))
     }
 
+    /// Return `true` if the given node is part of the view being browsed,
+    /// or if no view was specified.
+    pub fn allows_node(&self, node_id: &ExpandedNodeId) -> bool {
+        self.view
+            .as_ref()
+            .is_none_or(|v| v.contains_expanded(node_id))
+    }
+
+    /// The view being browsed, if any. References to nodes outside the
+    /// view are rejected by `add`.
+    pub fn view(&self) -> Option<&ViewContents> {
+        self.view.as_deref()
+    }
+
     /// Return `true` if the given reference should be returned.
     pub fn matches_filter(
         &self,
@@ -347,6 +449,10 @@ impl BrowseNode {
             return false;
         }
 
+        if !self.allows_node(&reference.node_id) {
+            return false;
+        }
+
         // Check the reference type filter.
         self.allows_reference_type(&reference.reference_type_id, type_tree)
     }
@@ -475,6 +581,7 @@ impl BrowseNode {
             node_class_mask: self.node_class_mask,
             result_mask: self.result_mask,
             max_references_per_node: self.max_references_per_node,
+            view: self.view,
             external_references: self.external_references,
         });
 
@@ -583,6 +690,7 @@ pub struct BrowsePathItem<'a> {
     results: Vec<BrowsePathResultElement>,
     status: StatusCode,
     unmatched_browse_name: Option<QualifiedName>,
+    view: Option<Arc<ViewContents>>,
 }
 
 impl<'a> BrowsePathItem<'a> {
@@ -607,10 +715,15 @@ impl<'a> BrowsePathItem<'a> {
             status: StatusCode::Good,
             iteration_number,
             unmatched_browse_name: elem.unmatched_browse_name,
+            view: root.view.clone(),
         }
     }
 
-    pub(crate) fn new_root(path: &'a BrowsePath, input_index: usize) -> Self {
+    pub(crate) fn new_root(
+        path: &'a BrowsePath,
+        input_index: usize,
+        view: Option<Arc<ViewContents>>,
+    ) -> Self {
         let mut status = StatusCode::Good;
         let elements = path.relative_path.elements.as_ref();
         if elements.is_none() || elements.is_some_and(|e| e.is_empty()) {
@@ -629,6 +742,7 @@ impl<'a> BrowsePathItem<'a> {
             status,
             iteration_number: 0,
             unmatched_browse_name: None,
+            view,
         }
     }
 
@@ -642,6 +756,12 @@ impl<'a> BrowsePathItem<'a> {
         &self.node
     }
 
+    /// The view the path is resolved in, if the starting node of the path is a View.
+    /// Elements outside the view are discarded by the server.
+    pub fn view(&self) -> Option<&ViewContents> {
+        self.view.as_deref()
+    }
+
     /// Add a path result element.
     pub fn add_element(
         &mut self,
@@ -661,8 +781,13 @@ impl<'a> BrowsePathItem<'a> {
         self.status = status;
     }
 
-    pub(crate) fn results_mut(&mut self) -> &mut Vec<BrowsePathResultElement> {
-        &mut self.results
+    /// Take the results of this item, discarding any that are outside the view.
+    pub(crate) fn take_results(&mut self) -> Vec<BrowsePathResultElement> {
+        let mut results = std::mem::take(&mut self.results);
+        if let Some(view) = &self.view {
+            results.retain(|r| view.contains(&r.node));
+        }
+        results
     }
 
     pub(crate) fn input_index(&self) -> usize {
@@ -811,6 +936,7 @@ pub async fn impl_translate_browse_paths_using_browse<'a>(
                     .operational
                     .max_references_per_browse_node,
                 *target,
+                None,
             ));
         }
         mgr.browse(context, &mut targets).await?;
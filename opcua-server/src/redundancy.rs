@@ -0,0 +1,607 @@
+//! Support for running the server as part of a redundant server set, as described
+//! in Part 4 6.6.2.
+//!
+//! The [RedundancyManager] populates the `ServerRedundancy` object of the server from
+//! the [RedundancyConfig](crate::RedundancyConfig), and computes the `ServiceLevel` of
+//! the server from its state, the health reported through [ServerHandle::set_health](crate::ServerHandle::set_health),
+//! and how many of the other servers in the set it can reach.
+//!
+//! With the `redundancy-heartbeat` feature, the server connects to each configured peer
+//! and periodically reads its `ServiceLevel` and state. These are exposed to clients
+//! through the `RedundantServerArray`, so that clients can pick the healthiest server
+//! to fail over to.
+//!
+//! In `HotAndMirrored` and `Transparent` mode, sessions can be shared between the servers
+//! in the set through a [SessionReplication] implementation, letting a client reactivate
+//! its session on a different server. Subscriptions are not replicated, clients
+//! must recreate them after failing over.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use log::{info, warn};
+use opcua_core::{sync::Mutex, trace_lock};
+use opcua_types::{
+    ApplicationDescription, AttributeId, ByteString, DataTypeId, DataValue, ExtensionObject,
+    MessageSecurityMode, NodeId, ObjectId, ObjectTypeId, RedundancySupport,
+    RedundantServerDataType, ReferenceTypeId, ServerState, UAString, VariableId, VariableTypeId,
+    Variant,
+};
+use parking_lot::MutexGuard;
+
+use crate::{
+    address_space::{AddressSpace, VariableBuilder},
+    config::{RedundancyMode, ServerConfig},
+    SubscriptionCache,
+};
+
+/// Health of the server, used to compute its `ServiceLevel`.
+/// See Part 4 6.6.2.4.2 for the meaning of each service level band.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ServerHealth {
+    /// The server is fully operational, service level 200 to 255, depending on how many of
+    /// its peers it can reach.
+    #[default]
+    Healthy,
+    /// The server is operational, but with reduced capacity or data quality.
+    /// The inner value is the remaining capacity, between 0 and 1, mapped to
+    /// service level 2 to 199.
+    Degraded(f32),
+    /// The server is running, but not currently able to deliver data, service level 1.
+    NoData,
+    /// The server is under maintenance, service level 0.
+    Maintenance,
+}
+
+/// Status of another server in the redundant server set, as seen by this server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerStatus {
+    /// Server URI of the peer.
+    pub server_uri: String,
+    /// Endpoint URL used to monitor the peer.
+    pub endpoint_url: String,
+    /// Last `ServiceLevel` read from the peer, 0 if it is unreachable.
+    pub service_level: u8,
+    /// Last state read from the peer.
+    pub state: ServerState,
+    /// Whether the last heartbeat to the peer succeeded.
+    pub reachable: bool,
+}
+
+/// State of a session, shared between the servers of a redundant server set
+/// through [SessionReplication]. This contains everything needed to let a client
+/// reactivate the session on a different server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicatedSession {
+    /// Authentication token of the session, used by the client to identify it.
+    pub authentication_token: NodeId,
+    /// Name of the session given by the client.
+    pub session_name: UAString,
+    /// Session timeout in milliseconds.
+    pub session_timeout: u64,
+    /// Maximum size of requests sent by the client.
+    pub max_request_message_size: u32,
+    /// Maximum size of responses sent to the client.
+    pub max_response_message_size: u32,
+    /// Endpoint URL the session was created on.
+    pub endpoint_url: UAString,
+    /// Security policy of the session.
+    pub security_policy_uri: String,
+    /// Message security mode of the session.
+    pub message_security_mode: MessageSecurityMode,
+    /// Client application instance certificate, if the session is secure.
+    pub client_certificate: ByteString,
+    /// The last server nonce sent to the client.
+    pub session_nonce: ByteString,
+    /// Description of the client application.
+    pub client_description: ApplicationDescription,
+}
+
+/// Storage for sessions shared between the servers of a redundant server set.
+///
+/// Each server calls `session_activated` when a session is activated, and `session_closed`
+/// when the client closes it. When a client activates a session the server does not know,
+/// the server looks it up with `find_session`, and restores it if it is found.
+///
+/// Sessions that expire are not removed, since a client may have moved to
+/// another server. Implementations should expire entries based on their session timeout.
+pub trait SessionReplication: Send + Sync {
+    /// Called when a session is activated on this server.
+    fn session_activated(&self, session: ReplicatedSession);
+
+    /// Called when a session is closed by the client.
+    fn session_closed(&self, authentication_token: &NodeId);
+
+    /// Find a session activated on another server.
+    fn find_session(&self, authentication_token: &NodeId) -> Option<ReplicatedSession>;
+}
+
+/// Session replication for servers running in the same process, sharing
+/// a single instance. Mainly useful for testing.
+#[derive(Default)]
+pub struct InMemorySessionReplication {
+    sessions: Mutex<HashMap<NodeId, ReplicatedSession>>,
+}
+
+impl InMemorySessionReplication {
+    /// Create a new, empty, in-memory session store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionReplication for InMemorySessionReplication {
+    fn session_activated(&self, session: ReplicatedSession) {
+        trace_lock!(self.sessions).insert(session.authentication_token.clone(), session);
+    }
+
+    fn session_closed(&self, authentication_token: &NodeId) {
+        trace_lock!(self.sessions).remove(authentication_token);
+    }
+
+    fn find_session(&self, authentication_token: &NodeId) -> Option<ReplicatedSession> {
+        trace_lock!(self.sessions)
+            .get(authentication_token)
+            .cloned()
+    }
+}
+
+struct RedundancyState {
+    health: ServerHealth,
+    server_state: ServerState,
+    peers: Vec<PeerStatus>,
+}
+
+/// Manager for the redundancy state of the server, exposed through
+/// the `ServerRedundancy` object and the `ServiceLevel` of the server.
+pub struct RedundancyManager {
+    mode: RedundancyMode,
+    server_id: String,
+    service_level: Arc<AtomicU8>,
+    subscriptions: Arc<SubscriptionCache>,
+    replication: Option<Arc<dyn SessionReplication>>,
+    state: Mutex<RedundancyState>,
+}
+
+impl RedundancyManager {
+    pub(crate) fn new(
+        config: &ServerConfig,
+        service_level: Arc<AtomicU8>,
+        subscriptions: Arc<SubscriptionCache>,
+        replication: Option<Arc<dyn SessionReplication>>,
+    ) -> Self {
+        let redundancy = &config.redundancy;
+        let state = RedundancyState {
+            health: ServerHealth::Healthy,
+            server_state: ServerState::Shutdown,
+            peers: redundancy
+                .peers
+                .iter()
+                .map(|p| PeerStatus {
+                    server_uri: p.server_uri.clone(),
+                    endpoint_url: p.endpoint_url.clone(),
+                    service_level: 0,
+                    state: ServerState::Unknown,
+                    reachable: false,
+                })
+                .collect(),
+        };
+        if redundancy.is_enabled() {
+            service_level.store(compute_service_level(&state), Ordering::Relaxed);
+        }
+        Self {
+            mode: redundancy.mode,
+            server_id: redundancy
+                .server_id
+                .clone()
+                .unwrap_or_else(|| config.application_uri.clone()),
+            service_level,
+            subscriptions,
+            replication,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Get the redundancy mode of the server set.
+    pub fn mode(&self) -> RedundancyMode {
+        self.mode
+    }
+
+    /// Get the ID of this server in the server set.
+    pub fn server_id(&self) -> &str {
+        &self.server_id
+    }
+
+    /// Get the current health of the server.
+    pub fn health(&self) -> ServerHealth {
+        trace_lock!(self.state).health
+    }
+
+    /// Set the health of the server, updating the `ServiceLevel` if the
+    /// server is part of a redundant server set.
+    pub fn set_health(&self, health: ServerHealth) {
+        let mut state = trace_lock!(self.state);
+        state.health = health;
+        self.refresh(state, false);
+    }
+
+    /// Get the status of each peer in the server set.
+    pub fn peers(&self) -> Vec<PeerStatus> {
+        trace_lock!(self.state).peers.clone()
+    }
+
+    /// Get the session replication used to share sessions with other servers, if any.
+    pub fn session_replication(&self) -> Option<&Arc<dyn SessionReplication>> {
+        self.replication.as_ref()
+    }
+
+    pub(crate) fn set_server_state(&self, server_state: ServerState) {
+        let mut state = trace_lock!(self.state);
+        state.server_state = server_state;
+        self.refresh(state, true);
+    }
+
+    /// Update the status of the peer at `index` after a heartbeat. `status` is the service
+    /// level and state read from the peer, or `None` if the heartbeat failed.
+    pub(crate) fn update_peer(&self, index: usize, status: Option<(u8, ServerState)>) {
+        let mut state = trace_lock!(self.state);
+        let Some(peer) = state.peers.get_mut(index) else {
+            return;
+        };
+        let (service_level, server_state, reachable) = match status {
+            Some((level, server_state)) => (level, server_state, true),
+            None => (0, ServerState::CommunicationFault, false),
+        };
+        if peer.reachable != reachable {
+            if reachable {
+                info!("Redundancy peer {} is reachable", peer.server_uri);
+            } else {
+                warn!("Lost contact with redundancy peer {}", peer.server_uri);
+            }
+        }
+        if peer.service_level == service_level
+            && peer.state == server_state
+            && peer.reachable == reachable
+        {
+            return;
+        }
+        peer.service_level = service_level;
+        peer.state = server_state;
+        peer.reachable = reachable;
+        self.refresh(state, true);
+    }
+
+    /// Recompute the service level after `state` has changed, and notify subscribers
+    /// of the service level and the `RedundantServerArray` if they changed.
+    fn refresh(&self, state: MutexGuard<'_, RedundancyState>, servers_changed: bool) {
+        if self.mode == RedundancyMode::None {
+            return;
+        }
+        let level = compute_service_level(&state);
+        let level_changed = self.service_level.swap(level, Ordering::Relaxed) != level;
+        let servers = (servers_changed || level_changed).then(|| self.redundant_servers(&state));
+        drop(state);
+
+        let level_id = VariableId::Server_ServiceLevel.into();
+        let servers_id = VariableId::Server_ServerRedundancy_RedundantServerArray.into();
+        self.subscriptions.notify_data_change(
+            level_changed
+                .then(|| (DataValue::new_now(level), &level_id, AttributeId::Value))
+                .into_iter()
+                .chain(servers.map(|s| (DataValue::new_now(s), &servers_id, AttributeId::Value))),
+        );
+    }
+
+    /// Get the `RedundantServerArray`, containing this server followed by each peer.
+    fn redundant_servers(&self, state: &RedundancyState) -> Variant {
+        let own = RedundantServerDataType {
+            server_id: self.server_id.as_str().into(),
+            service_level: self.service_level.load(Ordering::Relaxed),
+            server_state: state.server_state,
+        };
+        let peers = state.peers.iter().map(|p| RedundantServerDataType {
+            server_id: p.server_uri.as_str().into(),
+            service_level: p.service_level,
+            server_state: p.state,
+        });
+        std::iter::once(own)
+            .chain(peers)
+            .map(ExtensionObject::from_message)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Set the type of the `ServerRedundancy` object to match the redundancy mode,
+    /// and add the properties defined on that type.
+    pub(crate) fn init_address_space(&self, address_space: &mut AddressSpace) {
+        if self.mode == RedundancyMode::None {
+            return;
+        }
+        let redundancy: NodeId = ObjectId::Server_ServerRedundancy.into();
+        let (type_id, properties) = if self.mode == RedundancyMode::Transparent {
+            (
+                ObjectTypeId::TransparentRedundancyType,
+                VariableId::Server_ServerRedundancy_CurrentServerId,
+            )
+        } else {
+            (
+                ObjectTypeId::NonTransparentRedundancyType,
+                VariableId::Server_ServerRedundancy_ServerUriArray,
+            )
+        };
+        address_space.delete_reference(
+            &redundancy,
+            &ObjectTypeId::ServerRedundancyType.into(),
+            ReferenceTypeId::HasTypeDefinition,
+        );
+        address_space.insert_reference(
+            &redundancy,
+            &type_id.into(),
+            ReferenceTypeId::HasTypeDefinition,
+        );
+        address_space.insert_reference(
+            &redundancy,
+            &properties.into(),
+            ReferenceTypeId::HasProperty,
+        );
+
+        VariableBuilder::new(
+            &VariableId::Server_ServerRedundancy_RedundantServerArray.into(),
+            "RedundantServerArray",
+            "RedundantServerArray",
+        )
+        .property_of(redundancy)
+        .has_type_definition(VariableTypeId::PropertyType)
+        .data_type(DataTypeId::RedundantServerDataType)
+        .value_rank(1)
+        .insert(address_space);
+    }
+
+    /// Get the value of a variable on the `ServerRedundancy` object, if it is managed
+    /// by the redundancy manager.
+    pub(crate) fn read_value(&self, variable: VariableId) -> Option<Variant> {
+        let v = match variable {
+            VariableId::Server_ServerRedundancy_RedundancySupport => {
+                (RedundancySupport::from(self.mode) as i32).into()
+            }
+            _ if self.mode == RedundancyMode::None => return None,
+            VariableId::Server_ServerRedundancy_CurrentServerId => {
+                UAString::from(self.server_id.as_str()).into()
+            }
+            VariableId::Server_ServerRedundancy_ServerUriArray => {
+                let state = trace_lock!(self.state);
+                std::iter::once(self.server_id.as_str())
+                    .chain(state.peers.iter().map(|p| p.server_uri.as_str()))
+                    .map(UAString::from)
+                    .collect::<Vec<_>>()
+                    .into()
+            }
+            VariableId::Server_ServerRedundancy_RedundantServerArray => {
+                let state = trace_lock!(self.state);
+                self.redundant_servers(&state)
+            }
+            _ => return None,
+        };
+        Some(v)
+    }
+}
+
+/// Compute the service level from the server state, its health, and how many
+/// of its peers are reachable.
+fn compute_service_level(state: &RedundancyState) -> u8 {
+    match state.server_state {
+        ServerState::Running => (),
+        ServerState::Failed
+        | ServerState::NoConfiguration
+        | ServerState::CommunicationFault
+        | ServerState::Unknown => return 1,
+        ServerState::Suspended | ServerState::Shutdown | ServerState::Test => return 0,
+    }
+    match state.health {
+        ServerHealth::Maintenance => 0,
+        ServerHealth::NoData => 1,
+        ServerHealth::Degraded(capacity) => 2 + (capacity.clamp(0.0, 1.0) * 197.0).round() as u8,
+        // A server that cannot reach the rest of the set may be the one that is cut off,
+        // so it reports a lower level than one that can.
+        ServerHealth::Healthy if state.peers.is_empty() => 255,
+        ServerHealth::Healthy => {
+            let reachable = state.peers.iter().filter(|p| p.reachable).count();
+            200 + (55 * reachable / state.peers.len()) as u8
+        }
+    }
+}
+
+#[cfg(feature = "redundancy-heartbeat")]
+mod heartbeat {
+    use std::{sync::Arc, time::Duration};
+
+    use futures::never::Never;
+    use log::error;
+    use opcua_client::{ClientBuilder, Session};
+    use opcua_crypto::SecurityPolicy;
+    use opcua_types::{
+        EndpointDescription, MessageSecurityMode, ReadValueId, ServerState, TimestampsToReturn,
+        UserTokenPolicy, VariableId, Variant,
+    };
+
+    use crate::{config::RedundancyPeer, info::ServerInfo};
+
+    /// Periodically read the service level and state of each peer in the server set.
+    pub(crate) async fn run_heartbeat(info: Arc<ServerInfo>) -> Never {
+        let config = &info.config.redundancy;
+        if !config.is_enabled() || config.peers.is_empty() {
+            return futures::future::pending().await;
+        }
+        let interval = Duration::from_millis(config.heartbeat_interval_ms);
+        let timeout = Duration::from_millis(config.heartbeat_timeout_ms);
+
+        let client = ClientBuilder::new()
+            .application_name("RedundancyHeartbeat")
+            .application_uri("urn:RedundancyHeartbeat")
+            .pki_dir(info.config.pki_dir.clone())
+            .session_retry_limit(-1)
+            .session_retry_max(interval)
+            .keep_alive_interval(interval)
+            .request_timeout(timeout)
+            .client();
+        let Ok(client) = client else {
+            error!("Failed to create a valid client for the redundancy heartbeat");
+            return futures::future::pending().await;
+        };
+
+        futures::future::join_all(config.peers.iter().enumerate().map(|(index, peer)| {
+            let info = info.clone();
+            let client = &client;
+            async move {
+                let endpoint: EndpointDescription = (
+                    peer.endpoint_url.as_str(),
+                    SecurityPolicy::None.to_uri(),
+                    MessageSecurityMode::None,
+                    UserTokenPolicy::anonymous(),
+                )
+                    .into();
+                let (session, event_loop) = match client
+                    .session_builder()
+                    .connect_to_endpoint_directly(endpoint)
+                {
+                    Ok(b) => b.build(client.certificate_store().clone()),
+                    Err(e) => {
+                        error!(
+                            "Invalid endpoint for redundancy peer {}: {e}",
+                            peer.server_uri
+                        );
+                        return;
+                    }
+                };
+                tokio::select! {
+                    _ = event_loop.run() => {}
+                    _ = run_peer_heartbeat(&info, &session, index, peer, interval, timeout) => {}
+                }
+                info.redundancy.update_peer(index, None);
+            }
+        }))
+        .await;
+        futures::future::pending().await
+    }
+
+    async fn run_peer_heartbeat(
+        info: &ServerInfo,
+        session: &Session,
+        index: usize,
+        peer: &RedundancyPeer,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Never {
+        let nodes: Vec<_> = [
+            VariableId::Server_ServiceLevel,
+            VariableId::Server_ServerStatus_State,
+        ]
+        .into_iter()
+        .map(|id| ReadValueId::from(<opcua_types::NodeId>::from(id)))
+        .collect();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let res = tokio::time::timeout(
+                timeout,
+                session.read(&nodes, TimestampsToReturn::Neither, 0.0),
+            )
+            .await;
+            let status = match res {
+                Ok(Ok(values)) => match (
+                    values.first().and_then(|v| v.value.as_ref()),
+                    values.get(1).and_then(|v| v.value.as_ref()),
+                ) {
+                    (Some(Variant::Byte(level)), Some(Variant::Int32(state))) => {
+                        ServerState::try_from(*state).ok().map(|s| (*level, s))
+                    }
+                    _ => None,
+                },
+                Ok(Err(e)) => {
+                    log::debug!(
+                        "Heartbeat to redundancy peer {} failed: {e}",
+                        peer.server_uri
+                    );
+                    None
+                }
+                Err(_) => None,
+            };
+            info.redundancy.update_peer(index, status);
+        }
+    }
+}
+
+#[cfg(feature = "redundancy-heartbeat")]
+pub(crate) use heartbeat::run_heartbeat;
+
+#[cfg(test)]
+mod tests {
+    use opcua_types::ServerState;
+
+    use super::{compute_service_level, PeerStatus, RedundancyState, ServerHealth};
+
+    fn state(health: ServerHealth, reachable: &[bool]) -> RedundancyState {
+        RedundancyState {
+            health,
+            server_state: ServerState::Running,
+            peers: reachable
+                .iter()
+                .map(|&reachable| PeerStatus {
+                    server_uri: "urn:peer".to_owned(),
+                    endpoint_url: "opc.tcp://peer:4840".to_owned(),
+                    service_level: 255,
+                    state: ServerState::Running,
+                    reachable,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn service_level_from_health() {
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Healthy, &[])),
+            255
+        );
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Healthy, &[true, true])),
+            255
+        );
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Healthy, &[true, false])),
+            227
+        );
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Healthy, &[false])),
+            200
+        );
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Degraded(1.0), &[])),
+            199
+        );
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Degraded(0.0), &[])),
+            2
+        );
+        assert_eq!(compute_service_level(&state(ServerHealth::NoData, &[])), 1);
+        assert_eq!(
+            compute_service_level(&state(ServerHealth::Maintenance, &[])),
+            0
+        );
+    }
+
+    #[test]
+    fn service_level_from_server_state() {
+        let mut s = state(ServerHealth::Healthy, &[]);
+        s.server_state = ServerState::Shutdown;
+        assert_eq!(compute_service_level(&s), 0);
+        s.server_state = ServerState::Failed;
+        assert_eq!(compute_service_level(&s), 1);
+    }
+}
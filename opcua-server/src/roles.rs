@@ -0,0 +1,428 @@
+//! Role based access control, as described in Part 18 of the standard.
+//!
+//! Each session is granted a set of roles when it is activated. Roles are granted either
+//! by the [AuthManager](crate::authenticator::AuthManager) through
+//! `user_roles`, or by matching the identity of the session against the identity
+//! mapping rules of each role in the server-wide [RoleSet].
+//!
+//! Nodes may define `RolePermissions`, which are the permissions granted to each role
+//! on that node. If a node does not define any role permissions, the default role
+//! permissions of its namespace are used. If neither are defined, the node is not
+//! restricted by roles at all.
+//!
+//! The well-known roles are exposed in the address space under
+//! `Server/ServerCapabilities/RoleSet`, and their identity mapping rules can be modified
+//! using the `AddIdentity` and `RemoveIdentity` methods on each role. These are
+//! handled by the core node manager.
+
+use hashbrown::HashMap;
+use opcua_core::{sync::RwLock, trace_read_lock, trace_write_lock};
+use opcua_crypto::X509;
+use opcua_types::{
+    AccessRestrictionType, ExtensionObject, IdentityCriteriaType, IdentityMappingRuleType,
+    MethodId, NodeId, ObjectId, PermissionType, RolePermissionType, StatusCode, UAString,
+    VariableId, Variant, VariantScalarTypeId, VariantTypeId,
+};
+
+use crate::{
+    address_space::{AddressSpace, NodeBase, NodeType},
+    identity_token::IdentityToken,
+    load_method_args,
+    node_manager::MethodCall,
+};
+
+/// A well-known role, with the IDs of its role object in the core namespace and
+/// the method and variable nodes used to manage it.
+struct WellKnownRole {
+    role: ObjectId,
+    name: &'static str,
+    add_identity: MethodId,
+    remove_identity: MethodId,
+    identities: VariableId,
+}
+
+const WELL_KNOWN_ROLES: [WellKnownRole; 8] = [
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_Anonymous,
+        name: "Anonymous",
+        add_identity: MethodId::WellKnownRole_Anonymous_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_Anonymous_RemoveIdentity,
+        identities: VariableId::WellKnownRole_Anonymous_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_AuthenticatedUser,
+        name: "AuthenticatedUser",
+        add_identity: MethodId::WellKnownRole_AuthenticatedUser_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_AuthenticatedUser_RemoveIdentity,
+        identities: VariableId::WellKnownRole_AuthenticatedUser_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_Observer,
+        name: "Observer",
+        add_identity: MethodId::WellKnownRole_Observer_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_Observer_RemoveIdentity,
+        identities: VariableId::WellKnownRole_Observer_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_Operator,
+        name: "Operator",
+        add_identity: MethodId::WellKnownRole_Operator_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_Operator_RemoveIdentity,
+        identities: VariableId::WellKnownRole_Operator_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_Engineer,
+        name: "Engineer",
+        add_identity: MethodId::WellKnownRole_Engineer_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_Engineer_RemoveIdentity,
+        identities: VariableId::WellKnownRole_Engineer_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_Supervisor,
+        name: "Supervisor",
+        add_identity: MethodId::WellKnownRole_Supervisor_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_Supervisor_RemoveIdentity,
+        identities: VariableId::WellKnownRole_Supervisor_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_ConfigureAdmin,
+        name: "ConfigureAdmin",
+        add_identity: MethodId::WellKnownRole_ConfigureAdmin_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_ConfigureAdmin_RemoveIdentity,
+        identities: VariableId::WellKnownRole_ConfigureAdmin_Identities,
+    },
+    WellKnownRole {
+        role: ObjectId::WellKnownRole_SecurityAdmin,
+        name: "SecurityAdmin",
+        add_identity: MethodId::WellKnownRole_SecurityAdmin_AddIdentity,
+        remove_identity: MethodId::WellKnownRole_SecurityAdmin_RemoveIdentity,
+        identities: VariableId::WellKnownRole_SecurityAdmin_Identities,
+    },
+];
+
+/// A role on the server.
+#[derive(Debug, Clone)]
+pub struct Role {
+    name: String,
+    identities: Vec<IdentityMappingRuleType>,
+}
+
+impl Role {
+    /// Create a new role with the given name and no identity mapping rules.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            identities: Vec::new(),
+        }
+    }
+
+    /// Get the name of the role.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the identity mapping rules of the role.
+    pub fn identities(&self) -> &[IdentityMappingRuleType] {
+        &self.identities
+    }
+
+    fn matches(&self, identity: &IdentityToken, thumbprint: Option<&str>) -> bool {
+        self.identities.iter().any(|rule| match rule.criteria_type {
+            IdentityCriteriaType::Anonymous => matches!(identity, IdentityToken::Anonymous(_)),
+            IdentityCriteriaType::AuthenticatedUser => {
+                matches!(
+                    identity,
+                    IdentityToken::UserName(_) | IdentityToken::X509(_)
+                )
+            }
+            IdentityCriteriaType::UserName => match identity {
+                IdentityToken::UserName(token) => token.user_name == rule.criteria,
+                _ => false,
+            },
+            IdentityCriteriaType::Thumbprint => {
+                thumbprint.is_some_and(|t| rule.criteria.as_ref().eq_ignore_ascii_case(t))
+            }
+            // Roles and groups are properties of access tokens, which are not supported.
+            IdentityCriteriaType::Role | IdentityCriteriaType::GroupId => false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct NamespaceAccess {
+    role_permissions: Option<Vec<RolePermissionType>>,
+    access_restrictions: AccessRestrictionType,
+}
+
+/// Registry of the roles on the server, and the default role permissions of
+/// each namespace.
+///
+/// The well-known roles are always present. By default, sessions with anonymous
+/// credentials are granted the `Anonymous` role, and all other sessions are granted
+/// the `AuthenticatedUser` role.
+pub struct RoleSet {
+    roles: RwLock<HashMap<NodeId, Role>>,
+    namespaces: RwLock<HashMap<u16, NamespaceAccess>>,
+}
+
+impl Default for RoleSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoleSet {
+    /// Create a new role set containing the well-known roles.
+    pub fn new() -> Self {
+        let mut roles: HashMap<_, _> = WELL_KNOWN_ROLES
+            .iter()
+            .map(|r| (r.role.into(), Role::new(r.name)))
+            .collect();
+        for (role, criteria_type) in [
+            (
+                ObjectId::WellKnownRole_Anonymous,
+                IdentityCriteriaType::Anonymous,
+            ),
+            (
+                ObjectId::WellKnownRole_AuthenticatedUser,
+                IdentityCriteriaType::AuthenticatedUser,
+            ),
+        ] {
+            if let Some(r) = roles.get_mut(&NodeId::from(role)) {
+                r.identities.push(IdentityMappingRuleType {
+                    criteria_type,
+                    criteria: UAString::null(),
+                });
+            }
+        }
+        Self {
+            roles: RwLock::new(roles),
+            namespaces: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Add a custom role. Custom roles are not added to the address space,
+    /// but they can be granted by the authenticator and referenced in
+    /// role permissions.
+    ///
+    /// Returns `false` if a role with the given ID already exists.
+    pub fn add_role(&self, role_id: NodeId, role: Role) -> bool {
+        let mut roles = trace_write_lock!(self.roles);
+        if roles.contains_key(&role_id) {
+            return false;
+        }
+        roles.insert(role_id, role);
+        true
+    }
+
+    /// Get a copy of the role with the given ID.
+    pub fn get(&self, role_id: &NodeId) -> Option<Role> {
+        trace_read_lock!(self.roles).get(role_id).cloned()
+    }
+
+    /// Get the IDs of all roles on the server.
+    pub fn role_ids(&self) -> Vec<NodeId> {
+        trace_read_lock!(self.roles).keys().cloned().collect()
+    }
+
+    /// Add an identity mapping rule to a role. Adding a rule that already exists
+    /// does nothing.
+    pub fn add_identity(
+        &self,
+        role_id: &NodeId,
+        rule: IdentityMappingRuleType,
+    ) -> Result<(), StatusCode> {
+        let needs_criteria = !matches!(
+            rule.criteria_type,
+            IdentityCriteriaType::Anonymous | IdentityCriteriaType::AuthenticatedUser
+        );
+        if needs_criteria && rule.criteria.is_empty() {
+            return Err(StatusCode::BadInvalidArgument);
+        }
+        let mut roles = trace_write_lock!(self.roles);
+        let role = roles.get_mut(role_id).ok_or(StatusCode::BadNodeIdUnknown)?;
+        if !role.identities.contains(&rule) {
+            role.identities.push(rule);
+        }
+        Ok(())
+    }
+
+    /// Remove an identity mapping rule from a role.
+    pub fn remove_identity(
+        &self,
+        role_id: &NodeId,
+        rule: &IdentityMappingRuleType,
+    ) -> Result<(), StatusCode> {
+        let mut roles = trace_write_lock!(self.roles);
+        let role = roles.get_mut(role_id).ok_or(StatusCode::BadNodeIdUnknown)?;
+        let len = role.identities.len();
+        role.identities.retain(|r| r != rule);
+        if role.identities.len() == len {
+            return Err(StatusCode::BadNotFound);
+        }
+        Ok(())
+    }
+
+    /// Set the default role permissions and access restrictions for nodes in `namespace`.
+    /// These apply to any node that does not define its own.
+    pub fn set_namespace_defaults(
+        &self,
+        namespace: u16,
+        role_permissions: Option<Vec<RolePermissionType>>,
+        access_restrictions: AccessRestrictionType,
+    ) {
+        trace_write_lock!(self.namespaces).insert(
+            namespace,
+            NamespaceAccess {
+                role_permissions,
+                access_restrictions,
+            },
+        );
+    }
+
+    /// Get the default role permissions of `namespace`, if it defines any.
+    pub fn namespace_role_permissions(&self, namespace: u16) -> Option<Vec<RolePermissionType>> {
+        trace_read_lock!(self.namespaces)
+            .get(&namespace)
+            .and_then(|n| n.role_permissions.clone())
+    }
+
+    /// Get the permissions granted to `roles` by the default role permissions of `namespace`,
+    /// or `None` if the namespace does not define any.
+    pub fn namespace_permissions(
+        &self,
+        namespace: u16,
+        roles: &[NodeId],
+    ) -> Option<PermissionType> {
+        trace_read_lock!(self.namespaces)
+            .get(&namespace)
+            .and_then(|n| n.role_permissions.as_deref())
+            .map(|p| permissions_for_roles(p, roles))
+    }
+
+    /// Get the default access restrictions of `namespace`.
+    pub fn namespace_access_restrictions(&self, namespace: u16) -> AccessRestrictionType {
+        trace_read_lock!(self.namespaces)
+            .get(&namespace)
+            .map(|n| n.access_restrictions)
+            .unwrap_or_default()
+    }
+
+    /// Get the roles granted to a session with the given identity through identity mapping rules.
+    pub(crate) fn roles_for_identity(&self, identity: &IdentityToken) -> Vec<NodeId> {
+        let thumbprint = match identity {
+            IdentityToken::X509(token) => X509::from_byte_string(&token.certificate_data)
+                .ok()
+                .map(|c| c.thumbprint().as_hex_string()),
+            _ => None,
+        };
+        trace_read_lock!(self.roles)
+            .iter()
+            .filter(|(_, r)| r.matches(identity, thumbprint.as_deref()))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Get the value of the `Identities` property of a well-known role.
+    pub(crate) fn read_identities(&self, variable: VariableId) -> Option<Variant> {
+        let role = WELL_KNOWN_ROLES.iter().find(|r| r.identities == variable)?;
+        let identities = trace_read_lock!(self.roles)
+            .get(&NodeId::from(role.role))?
+            .identities
+            .iter()
+            .cloned()
+            .map(ExtensionObject::from_message)
+            .collect::<Vec<_>>();
+        Some(identities.into())
+    }
+
+    /// Get whether `method` is an `AddIdentity` or `RemoveIdentity` method on a role.
+    pub(crate) fn is_role_method(method: MethodId) -> bool {
+        matches!(
+            method,
+            MethodId::RoleType_AddIdentity | MethodId::RoleType_RemoveIdentity
+        ) || WELL_KNOWN_ROLES
+            .iter()
+            .any(|r| r.add_identity == method || r.remove_identity == method)
+    }
+
+    /// Make the methods managing the well-known roles executable, and restrict them
+    /// and the `Identities` of each role to the `SecurityAdmin` role.
+    pub(crate) fn init_address_space(address_space: &mut AddressSpace) {
+        for role in &WELL_KNOWN_ROLES {
+            for method in [role.add_identity, role.remove_identity] {
+                if let Some(NodeType::Method(m)) = address_space.find_mut(method) {
+                    m.set_executable(true);
+                    m.set_user_executable(true);
+                    m.set_role_permissions(Some(security_admin_permissions(PermissionType::Call)));
+                }
+            }
+            if let Some(node) = address_space.find_mut(role.identities) {
+                node.as_mut_node()
+                    .set_role_permissions(Some(security_admin_permissions(PermissionType::Read)));
+            }
+        }
+    }
+
+    /// Call `AddIdentity` or `RemoveIdentity` on the role given by the object ID of `call`.
+    pub(crate) fn call(&self, call: &mut MethodCall) -> Result<(), StatusCode> {
+        let Ok(id) = call.method_id().as_method_id() else {
+            return Err(StatusCode::BadMethodInvalid);
+        };
+        let is_add = id == MethodId::RoleType_AddIdentity
+            || WELL_KNOWN_ROLES.iter().any(|r| r.add_identity == id);
+        let is_remove = id == MethodId::RoleType_RemoveIdentity
+            || WELL_KNOWN_ROLES.iter().any(|r| r.remove_identity == id);
+
+        let rule = load_method_args!(call, ExtensionObject)?;
+        let rule = rule
+            .into_inner_as::<IdentityMappingRuleType>()
+            .ok_or(StatusCode::BadInvalidArgument)?;
+        let role_id = call.object_id().clone();
+
+        if is_add {
+            self.add_identity(&role_id, *rule)?;
+        } else if is_remove {
+            self.remove_identity(&role_id, &rule)?;
+        } else {
+            return Err(StatusCode::BadMethodInvalid);
+        }
+        call.set_status(StatusCode::Good);
+        Ok(())
+    }
+}
+
+/// Get role permissions letting any user browse a node, but only the `SecurityAdmin`
+/// role use it with `permissions`.
+pub(crate) fn security_admin_permissions(permissions: PermissionType) -> Vec<RolePermissionType> {
+    let browse = |role: ObjectId| RolePermissionType {
+        role_id: role.into(),
+        permissions: PermissionType::Browse,
+    };
+    vec![
+        browse(ObjectId::WellKnownRole_Anonymous),
+        browse(ObjectId::WellKnownRole_AuthenticatedUser),
+        RolePermissionType {
+            role_id: ObjectId::WellKnownRole_SecurityAdmin.into(),
+            permissions: PermissionType::Browse | permissions,
+        },
+    ]
+}
+
+/// Get whether `roles` includes the `SecurityAdmin` role.
+pub(crate) fn is_security_admin(roles: &[NodeId]) -> bool {
+    roles
+        .iter()
+        .any(|r| r == &ObjectId::WellKnownRole_SecurityAdmin)
+}
+
+/// Get the combined permissions granted to `roles` by `role_permissions`.
+pub fn permissions_for_roles(
+    role_permissions: &[RolePermissionType],
+    roles: &[NodeId],
+) -> PermissionType {
+    role_permissions
+        .iter()
+        .filter(|p| roles.contains(&p.role_id))
+        .fold(PermissionType::empty(), |acc, p| acc | p.permissions)
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    future::Future,
     net::{SocketAddr, ToSocketAddrs},
     sync::{
         atomic::{AtomicU16, AtomicU8},
@@ -8,13 +9,17 @@ use std::{
     time::Duration,
 };
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use futures::{future::Either, never::Never, stream::FuturesUnordered, FutureExt, StreamExt};
 use log::{error, info, warn};
-use opcua_core::{sync::RwLock, trace_read_lock, trace_write_lock};
+use opcua_core::{
+    comms::{tcp_codec::AsyncStream, url::OPC_TCP_SCHEME},
+    sync::RwLock,
+    trace_read_lock, trace_write_lock,
+};
 use opcua_nodes::DefaultTypeTree;
 use tokio::{
-    net::TcpListener,
+    net::{TcpListener, TcpStream},
     pin,
     sync::Notify,
     task::{JoinError, JoinHandle},
@@ -22,22 +27,36 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 
 use opcua_core::{config::Config, handle::AtomicHandle};
-use opcua_crypto::CertificateStore;
+use opcua_crypto::{CertificateStore, FileSystemCertificateStore};
 
+#[cfg(feature = "https")]
+use crate::transport::HttpsConnector;
 use crate::{
     node_manager::{DefaultTypeTreeGetter, ServerContext},
     session::controller::{ControllerCommand, SessionStarter},
-    transport::tcp::{TcpConnector, TransportConfig},
+    transport::{
+        reverse_connect,
+        tcp::{TcpConnector, TransportConfig},
+        Connector, Listener,
+    },
     ServerStatusWrapper,
 };
-use opcua_types::{DateTime, LocalizedText, ServerState, UAString};
+#[cfg(feature = "https")]
+use opcua_core::comms::url::HTTPS_SCHEME;
+use opcua_types::{DateTime, LocalizedText, ServerState, StatusCode, UAString};
 
 use super::{
     authenticator::DefaultAuthenticator,
     builder::ServerBuilder,
+    certificate_manager::CertificateManager,
+    conditions::ConditionCache,
     config::ServerConfig,
+    diagnostics::ServerDiagnostics,
+    discovery::{MulticastDiscovery, ServerRegistry},
     info::ServerInfo,
     node_manager::{NodeManagers, NodeManagersRef},
+    redundancy::RedundancyManager,
+    roles::RoleSet,
     server_handle::ServerHandle,
     session::manager::SessionManager,
     subscriptions::SubscriptionCache,
@@ -52,7 +71,7 @@ struct ConnectionInfo {
 /// periods of time.
 pub struct Server {
     /// Certificate store
-    certificate_store: Arc<RwLock<CertificateStore>>,
+    certificate_store: Arc<RwLock<dyn CertificateStore>>,
     /// Session manager
     session_manager: Arc<RwLock<SessionManager>>,
     /// Open connections.
@@ -95,24 +114,37 @@ impl Server {
             config.tcp_config.host, config.tcp_config.port
         ); */
 
-        // let diagnostics = Arc::new(RwLock::new(ServerDiagnostics::default()));
         let send_buffer_size = config.limits.send_buffer_size;
         let receive_buffer_size = config.limits.receive_buffer_size;
 
-        let application_description = if config.create_sample_keypair {
-            Some(config.application_description())
-        } else {
-            None
-        };
-
-        let (mut certificate_store, server_certificate, server_pkey) =
-            CertificateStore::new_with_x509_data(
-                &config.pki_dir,
-                false,
-                config.certificate_path.as_deref(),
-                config.private_key_path.as_deref(),
-                application_description,
-            );
+        let (certificate_store, server_certificate, server_pkey) =
+            if let Some(certificate_store) = builder.certificate_store {
+                let (server_certificate, server_pkey) = {
+                    let certificate_store = trace_read_lock!(certificate_store);
+                    (
+                        certificate_store.read_own_cert().ok(),
+                        certificate_store.read_own_pkey().ok(),
+                    )
+                };
+                (certificate_store, server_certificate, server_pkey)
+            } else {
+                let application_description = if config.create_sample_keypair {
+                    Some(config.application_description())
+                } else {
+                    None
+                };
+                let (certificate_store, server_certificate, server_pkey) =
+                    FileSystemCertificateStore::new_with_x509_data(
+                        &config.pki_dir,
+                        false,
+                        config.certificate_path.as_deref(),
+                        config.private_key_path.as_deref(),
+                        application_description,
+                    );
+                let certificate_store: Arc<RwLock<dyn CertificateStore>> =
+                    Arc::new(RwLock::new(certificate_store));
+                (certificate_store, server_certificate, server_pkey)
+            };
 
         if server_certificate.is_none() || server_pkey.is_none() {
             warn!("Server is missing its application instance certificate and/or its private key. Encrypted endpoints will not function correctly.");
@@ -120,11 +152,17 @@ impl Server {
 
         config.read_x509_thumbprints();
 
-        if config.certificate_validation.trust_client_certs {
-            info!("Server has chosen to auto trust client certificates. You do not want to do this in production code.");
-            certificate_store.set_trust_unknown_certs(true);
+        {
+            let mut certificate_store = trace_write_lock!(certificate_store);
+            if config.certificate_validation.trust_client_certs {
+                info!("Server has chosen to auto trust client certificates. You do not want to do this in production code.");
+                certificate_store.set_trust_unknown_certs(true);
+            }
+            certificate_store.set_check_time(config.certificate_validation.check_time);
+            certificate_store.set_allow_unknown_revocation(
+                config.certificate_validation.allow_unknown_revocation,
+            );
         }
-        certificate_store.set_check_time(config.certificate_validation.check_time);
 
         let config = Arc::new(config);
 
@@ -132,6 +170,15 @@ impl Server {
 
         let type_tree = Arc::new(RwLock::new(DefaultTypeTree::new()));
 
+        let subscriptions = Arc::new(SubscriptionCache::new(config.limits.subscriptions));
+        let conditions = Arc::new(ConditionCache::new(
+            subscriptions.clone(),
+            type_tree.clone(),
+        ));
+        let multicast_discovery = builder
+            .mdns_responder
+            .map(|r| Arc::new(MulticastDiscovery::new(r)));
+
         let info = ServerInfo {
             authenticator: builder
                 .authenticator
@@ -145,8 +192,8 @@ impl Server {
             start_time: ArcSwap::new(Arc::new(opcua_types::DateTime::now())),
             servers,
             config: config.clone(),
-            server_certificate,
-            server_pkey,
+            server_certificate: ArcSwapOption::from(server_certificate.map(Arc::new)),
+            server_pkey: ArcSwapOption::from(server_pkey.map(Arc::new)),
             operational_limits: config.limits.operational.clone(),
             state: ArcSwap::new(Arc::new(ServerState::Shutdown)),
             send_buffer_size,
@@ -158,18 +205,40 @@ impl Server {
             capabilities: ServerCapabilities::default(),
             service_level: service_level.clone(),
             port: AtomicU16::new(0),
+            url_scheme: ArcSwap::new(Arc::new(OPC_TCP_SCHEME.to_owned())),
             type_tree_getter: builder
                 .type_tree_getter
                 .unwrap_or_else(|| Arc::new(DefaultTypeTreeGetter)),
             type_loaders: builder.type_loaders,
+            conditions,
+            roles: Arc::new(RoleSet::new()),
+            certificate_manager: Arc::new(CertificateManager::new(certificate_store.clone())),
+            redundancy: Arc::new(RedundancyManager::new(
+                &config,
+                service_level.clone(),
+                subscriptions.clone(),
+                builder.session_replication,
+            )),
+            server_registry: config.local_discovery_server.then(|| {
+                ServerRegistry::new(
+                    Duration::from_millis(config.registration_timeout_ms),
+                    multicast_discovery.clone(),
+                )
+            }),
+            multicast_discovery,
+            diagnostics: ServerDiagnostics::default(),
         };
 
-        let certificate_store = Arc::new(RwLock::new(certificate_store));
-
         let info = Arc::new(info);
-        let subscriptions = Arc::new(SubscriptionCache::new(config.limits.subscriptions));
 
         let node_managers_ref = NodeManagersRef::new_empty();
+        let session_notify = Arc::new(Notify::new());
+        let session_manager = Arc::new(RwLock::new(SessionManager::new(
+            info.clone(),
+            session_notify.clone(),
+            node_managers_ref.clone(),
+        )));
+
         let status_wrapper = Arc::new(ServerStatusWrapper::new(
             builder.build_info,
             subscriptions.clone(),
@@ -182,6 +251,7 @@ impl Server {
             type_tree: type_tree.clone(),
             type_tree_getter: info.type_tree_getter.clone(),
             status: status_wrapper.clone(),
+            session_manager: session_manager.clone(),
         };
 
         let mut final_node_managers = Vec::new();
@@ -192,12 +262,6 @@ impl Server {
         let node_managers = NodeManagers::new(final_node_managers);
         node_managers_ref.init_from_node_managers(node_managers.clone());
 
-        let session_notify = Arc::new(Notify::new());
-        let session_manager = Arc::new(RwLock::new(SessionManager::new(
-            info.clone(),
-            session_notify.clone(),
-        )));
-
         let handle = ServerHandle::new(
             info.clone(),
             service_level,
@@ -268,13 +332,14 @@ impl Server {
         .await
     }
 
-    /// Run the server using a given TCP listener.
+    /// Run the server using a given listener, for example a `TcpListener`.
     /// Note that the configured TCP endpoint is still used to create the endpoint
     /// descriptions, you must properly set `host` and `port` even when using this.
+    /// If the listener is bound to a TCP port, that port is used instead of the configured one.
     ///
     /// This is useful for testing, as you can bind a `TcpListener` to port `0` auto-assign
-    /// a port.
-    pub async fn run_with(mut self, listener: TcpListener) -> Result<(), String> {
+    /// a port, or use a channel of in-memory streams. See [`Listener`] for details.
+    pub async fn run_with(mut self, mut listener: impl Listener) -> Result<(), String> {
         let context = ServerContext {
             node_managers: self.node_managers.as_weak(),
             subscriptions: self.subscriptions.clone(),
@@ -283,21 +348,30 @@ impl Server {
             type_tree: self.info.type_tree.clone(),
             type_tree_getter: self.info.type_tree_getter.clone(),
             status: self.status.clone(),
+            session_manager: self.session_manager.clone(),
         };
 
         self.initialize_node_managers(&context).await?;
 
         self.status.set_server_started();
         self.info.start_time.store(Arc::new(DateTime::now()));
+        self.info.state.store(Arc::new(ServerState::Running));
+        self.info.redundancy.set_server_state(ServerState::Running);
 
         let addr = listener
             .local_addr()
             .map_err(|e| format!("Failed to bind socket: {e:?}"))?;
-        info!("Now listening for connections on {addr}");
+        info!("Now listening for connections on {addr:?}");
 
-        self.info
-            .port
-            .store(addr.port(), std::sync::atomic::Ordering::Relaxed);
+        if let Some(port) = listener.local_port() {
+            self.info
+                .port
+                .store(port, std::sync::atomic::Ordering::Relaxed);
+        }
+        let url_scheme = listener.url_scheme();
+        #[cfg(feature = "https")]
+        let terminates_tls = listener.terminates_tls();
+        self.info.url_scheme.store(Arc::new(url_scheme.to_owned()));
 
         self.log_endpoint_info();
 
@@ -315,10 +389,35 @@ impl Server {
             Self::run_subscription_ticks(self.config.subscription_poll_interval_ms, &context);
         pin!(subscription_fut);
 
-        let session_expiry_fut =
-            Self::run_session_expiry(&self.session_manager, &self.session_notify);
+        let (session_manager, session_notify) =
+            (self.session_manager.clone(), self.session_notify.clone());
+        let session_expiry_fut = Self::run_session_expiry(&session_manager, &session_notify);
         pin!(session_expiry_fut);
 
+        let (reverse_send, mut reverse_recv) = tokio::sync::mpsc::channel(1);
+        let reverse_connect_fut = self.run_reverse_connect(reverse_send);
+        pin!(reverse_connect_fut);
+
+        let info = self.info.clone();
+        let multicast_fut = Self::run_multicast_discovery(&info);
+        pin!(multicast_fut);
+
+        let registration_expiry_fut = Self::run_registration_expiry(&info);
+        pin!(registration_expiry_fut);
+
+        #[cfg(feature = "redundancy-heartbeat")]
+        let redundancy_fut = crate::redundancy::run_heartbeat(self.info.clone());
+
+        #[cfg(not(feature = "redundancy-heartbeat"))]
+        let redundancy_fut = {
+            if !self.config.redundancy.peers.is_empty() {
+                warn!("The redundancy-heartbeat feature is disabled, redundancy peers will not be monitored");
+            }
+            futures::future::pending::<Never>()
+        };
+
+        pin!(redundancy_fut);
+
         loop {
             let conn_fut = if self.connections.is_empty() {
                 if self.token.is_cancelled() {
@@ -342,31 +441,21 @@ impl Server {
                 _ = &mut subscription_fut => {}
                 _ = &mut discovery_fut => {}
                 _ = &mut session_expiry_fut => {}
+                _ = &mut reverse_connect_fut => {}
+                _ = &mut multicast_fut => {}
+                _ = &mut registration_expiry_fut => {}
+                _ = &mut redundancy_fut => {}
                 rs = listener.accept() => {
                     match rs {
                         Ok((socket, addr)) => {
-                            info!("Accept new connection from {addr} ({connection_counter})");
-                            let conn = SessionStarter::new(
-                                TcpConnector::new(socket, TransportConfig {
-                                    send_buffer_size: self.info.config.limits.send_buffer_size,
-                                    max_message_size: self.info.config.limits.max_message_size,
-                                    max_chunk_count: self.info.config.limits.max_chunk_count,
-                                    receive_buffer_size: self.info.config.limits.receive_buffer_size,
-                                    hello_timeout: Duration::from_secs(self.info.config.tcp_config.hello_timeout as u64),
-                                }, self.info.decoding_options()),
-                                self.info.clone(),
-                                self.session_manager.clone(),
-                                self.certificate_store.clone(),
-                                self.node_managers.clone(),
-                                self.subscriptions.clone()
-                            );
-
-                            let (send, recv) = tokio::sync::mpsc::channel(5);
-                            let handle = tokio::spawn(conn.run(recv).map(move |_| connection_counter));
-                            self.connections.push(handle);
-                            self.connection_map.insert(connection_counter, ConnectionInfo {
-                                command_send: send
-                            });
+                            info!("Accept new connection from {addr:?} ({connection_counter})");
+                            #[cfg(feature = "https")]
+                            if url_scheme == HTTPS_SCHEME {
+                                self.start_https_connection(socket, connection_counter, terminates_tls);
+                                connection_counter += 1;
+                                continue;
+                            }
+                            self.start_connection(socket, connection_counter);
                             connection_counter += 1;
                         }
                         Err(e) => {
@@ -374,6 +463,14 @@ impl Server {
                         }
                     }
                 }
+                Some((socket, client_url)) = reverse_recv.recv() => {
+                    if self.token.is_cancelled() {
+                        continue;
+                    }
+                    info!("Reverse connection to {client_url} accepted by client ({connection_counter})");
+                    self.start_connection(socket, connection_counter);
+                    connection_counter += 1;
+                }
                 _ = self.token.cancelled() => {
                     for conn in self.connection_map.values() {
                         let _ = conn.command_send.send(ControllerCommand::Close).await;
@@ -382,9 +479,124 @@ impl Server {
             }
         }
 
+        if let (Some(multicast), Some(record)) =
+            (&self.info.multicast_discovery, self.info.mdns_record())
+        {
+            multicast.withdraw(&record.server_name);
+        }
+
         Ok(())
     }
 
+    fn start_connection(&mut self, socket: impl AsyncStream, connection_id: u32) {
+        let connector = TcpConnector::new(
+            socket,
+            TransportConfig {
+                send_buffer_size: self.info.config.limits.send_buffer_size,
+                max_message_size: self.info.config.limits.max_message_size,
+                max_chunk_count: self.info.config.limits.max_chunk_count,
+                receive_buffer_size: self.info.config.limits.receive_buffer_size,
+                hello_timeout: Duration::from_secs(
+                    self.info.config.tcp_config.hello_timeout as u64,
+                ),
+            },
+            self.info.decoding_options(),
+        );
+        self.spawn_connection(connector, connection_id);
+    }
+
+    #[cfg(feature = "https")]
+    fn start_https_connection(
+        &mut self,
+        socket: impl AsyncStream,
+        connection_id: u32,
+        terminates_tls: bool,
+    ) {
+        let connector = HttpsConnector::new(
+            socket,
+            self.info.config.limits.max_message_size,
+            terminates_tls,
+        );
+        self.spawn_connection(connector, connection_id);
+    }
+
+    fn spawn_connection(&mut self, connector: impl Connector + Send + 'static, connection_id: u32) {
+        let conn = SessionStarter::new(
+            connector,
+            self.info.clone(),
+            self.session_manager.clone(),
+            self.certificate_store.clone(),
+            self.node_managers.clone(),
+            self.subscriptions.clone(),
+        );
+
+        let (send, recv) = tokio::sync::mpsc::channel(5);
+        let handle = tokio::spawn(conn.run(recv).map(move |_| connection_id));
+        self.connections.push(handle);
+        self.connection_map
+            .insert(connection_id, ConnectionInfo { command_send: send });
+    }
+
+    /// Keep a reverse connection open to each configured reverse connect target.
+    /// Once a client starts using a connection it is sent to `send`, and a new
+    /// connection is opened to the same client.
+    fn run_reverse_connect(
+        &self,
+        send: tokio::sync::mpsc::Sender<(TcpStream, String)>,
+    ) -> impl Future<Output = Never> + 'static {
+        let hello_timeout = Duration::from_secs(self.config.tcp_config.hello_timeout as u64);
+        let interval = Duration::from_millis(self.config.reverse_connect_interval_ms);
+        let server_uri = self.config.application_uri.clone();
+        let targets: Vec<_> = self
+            .config
+            .reverse_connect_targets
+            .iter()
+            .map(|t| {
+                (
+                    t.client_url.clone(),
+                    t.endpoint_url
+                        .clone()
+                        .unwrap_or_else(|| self.default_endpoint_url()),
+                )
+            })
+            .collect();
+
+        let futs = targets.into_iter().map(move |(client_url, endpoint_url)| {
+            let send = send.clone();
+            let server_uri = server_uri.clone();
+            async move {
+                loop {
+                    match reverse_connect(&client_url, &server_uri, &endpoint_url, hello_timeout)
+                        .await
+                    {
+                        Ok(socket) => {
+                            if send.send((socket, client_url.clone())).await.is_err() {
+                                break;
+                            }
+                        }
+                        // The client did not use the connection in time, open a new one.
+                        Err(StatusCode::BadTimeout) => {}
+                        Err(e) => {
+                            warn!("Reverse connection to {client_url} failed: {e}");
+                            tokio::time::sleep(interval).await;
+                        }
+                    }
+                }
+            }
+        });
+        futures::future::join_all(futs).then(|_| futures::future::pending())
+    }
+
+    /// Get the URL of the default endpoint, or the first endpoint if there is no default.
+    fn default_endpoint_url(&self) -> String {
+        let base_endpoint = self.info.base_endpoint();
+        self.config
+            .default_endpoint()
+            .or_else(|| self.config.endpoints.values().next())
+            .map(|e| e.endpoint_url(&base_endpoint))
+            .unwrap_or(base_endpoint)
+    }
+
     /// Run the server. The provided `token` can be used to stop the server gracefully.
     pub async fn run(self) -> Result<(), String> {
         let addr = self.get_socket_address();
@@ -406,6 +618,33 @@ impl Server {
         self.run_with(listener).await
     }
 
+    async fn run_multicast_discovery(info: &ServerInfo) -> Never {
+        let Some(multicast) = &info.multicast_discovery else {
+            return futures::future::pending().await;
+        };
+        match info.mdns_record() {
+            Some(record) => multicast.announce(record),
+            None => {
+                warn!("Server has no opc.tcp discovery URL, it will not be announced over mDNS")
+            }
+        }
+        multicast.run().await
+    }
+
+    async fn run_registration_expiry(info: &ServerInfo) -> Never {
+        let Some(registry) = &info.server_registry else {
+            return futures::future::pending().await;
+        };
+        // Registrations are also checked whenever they are read, this makes sure
+        // that announcements on behalf of expired servers are withdrawn.
+        let mut tick = tokio::time::interval(Duration::from_secs(10));
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            registry.remove_expired();
+        }
+    }
+
     async fn run_subscription_ticks(interval: u64, context: &ServerContext) -> Never {
         if interval == 0 {
             futures::future::pending().await
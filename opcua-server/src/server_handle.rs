@@ -13,7 +13,12 @@ use opcua_types::{AttributeId, DataValue, LocalizedText, ServerState, VariableId
 use crate::ServerStatusWrapper;
 
 use super::{
-    info::ServerInfo, node_manager::NodeManagers, session::manager::SessionManager,
+    conditions::ConditionCache,
+    info::ServerInfo,
+    node_manager::NodeManagers,
+    redundancy::{RedundancyManager, ServerHealth},
+    roles::RoleSet,
+    session::manager::SessionManager,
     SubscriptionCache,
 };
 
@@ -65,7 +70,32 @@ impl ServerHandle {
         &self.subscriptions
     }
 
+    /// Get a reference to the condition cache, containing all registered alarms and conditions.
+    pub fn conditions(&self) -> &Arc<ConditionCache> {
+        &self.info.conditions
+    }
+
+    /// Get a reference to the role set, containing the roles on the server.
+    pub fn roles(&self) -> &Arc<RoleSet> {
+        &self.info.roles
+    }
+
+    /// Get a reference to the redundancy manager, containing the redundancy state of the server.
+    pub fn redundancy(&self) -> &Arc<RedundancyManager> {
+        &self.info.redundancy
+    }
+
+    /// Set the health of the server. If the server is part of a redundant server set,
+    /// this updates the service level and notifies subscribed clients.
+    pub fn set_health(&self, health: ServerHealth) {
+        self.info.redundancy.set_health(health);
+    }
+
     /// Set the service level, properly notifying subscribed clients of the change.
+    ///
+    /// If the server is part of a redundant server set, the service level is computed
+    /// by the redundancy manager, and this value is replaced on the next change
+    /// to the redundancy state. Use [`ServerHandle::set_health`] instead.
     pub fn set_service_level(&self, sl: u8) {
         self.service_level
             .store(sl, std::sync::atomic::Ordering::Relaxed);
@@ -95,9 +125,11 @@ impl ServerHandle {
     }
 
     /// Set the server state. Note that this does not do anything beyond just setting
-    /// the state and notifying clients.
+    /// the state and notifying clients, and updating the service level if the server
+    /// is part of a redundant server set.
     pub fn set_server_state(&self, state: ServerState) {
         self.status.set_state(state);
+        self.info.redundancy.set_server_state(state);
     }
 
     /// Get the cancellation token.
@@ -12,14 +12,15 @@ use opcua_core::{
     comms::{
         secure_channel::SecureChannel, security_header::SecurityHeader, tcp_types::ErrorMessage,
     },
-    config::Config,
     handle::AtomicHandle,
     sync::RwLock,
 };
 use opcua_crypto::{CertificateStore, SecurityPolicy};
 use opcua_types::{
-    ChannelSecurityToken, DateTime, FindServersResponse, GetEndpointsResponse, MessageSecurityMode,
-    OpenSecureChannelRequest, OpenSecureChannelResponse, ResponseHeader, SecurityTokenRequestType,
+    profiles, ChannelSecurityToken, DateTime, FindServersOnNetworkResponse, FindServersResponse,
+    GetEndpointsResponse, MdnsDiscoveryConfiguration, MessageSecurityMode,
+    OpenSecureChannelRequest, OpenSecureChannelResponse, RegisterServer2Response,
+    RegisterServerResponse, RegisteredServer, ResponseHeader, SecurityTokenRequestType,
     ServiceFault, StatusCode,
 };
 use tokio_util::sync::CancellationToken;
@@ -29,8 +30,8 @@ use crate::{
     info::ServerInfo,
     node_manager::NodeManagers,
     subscriptions::SubscriptionCache,
-    transport::tcp::{Request, TcpTransport, TransportPollResult},
-    transport::Connector,
+    transport::tcp::{Request, TransportPollResult},
+    transport::{Connector, Transport},
 };
 
 use super::{
@@ -70,12 +71,12 @@ pub(crate) enum ControllerCommand {
 type PendingMessageResponse = dyn Future<Output = Result<Response, String>> + Send + Sync + 'static;
 
 /// Master type managing a single connection.
-pub(crate) struct SessionController {
+pub(crate) struct SessionController<T> {
     channel: SecureChannel,
-    transport: TcpTransport,
+    transport: T,
     secure_channel_state: SecureChannelState,
     session_manager: Arc<RwLock<SessionManager>>,
-    certificate_store: Arc<RwLock<CertificateStore>>,
+    certificate_store: Arc<RwLock<dyn CertificateStore>>,
     message_handler: MessageHandler,
     pending_messages: FuturesUnordered<Pin<Box<PendingMessageResponse>>>,
     info: Arc<ServerInfo>,
@@ -91,7 +92,7 @@ pub struct SessionStarter<T> {
     connector: T,
     info: Arc<ServerInfo>,
     session_manager: Arc<RwLock<SessionManager>>,
-    certificate_store: Arc<RwLock<CertificateStore>>,
+    certificate_store: Arc<RwLock<dyn CertificateStore>>,
     node_managers: NodeManagers,
     subscriptions: Arc<SubscriptionCache>,
 }
@@ -101,7 +102,7 @@ impl<T: Connector> SessionStarter<T> {
         connector: T,
         info: Arc<ServerInfo>,
         session_manager: Arc<RwLock<SessionManager>>,
-        certificate_store: Arc<RwLock<CertificateStore>>,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
         node_managers: NodeManagers,
         subscriptions: Arc<SubscriptionCache>,
     ) -> Self {
@@ -152,11 +153,11 @@ impl<T: Connector> SessionStarter<T> {
     }
 }
 
-impl SessionController {
+impl<T: Transport> SessionController<T> {
     pub fn new(
-        transport: TcpTransport,
+        transport: T,
         session_manager: Arc<RwLock<SessionManager>>,
-        certificate_store: Arc<RwLock<CertificateStore>>,
+        certificate_store: Arc<RwLock<dyn CertificateStore>>,
         info: Arc<ServerInfo>,
         node_managers: NodeManagers,
         subscriptions: Arc<SubscriptionCache>,
@@ -190,7 +191,7 @@ impl SessionController {
             };
 
             tokio::select! {
-                _ = tokio::time::sleep_until(self.deadline.into()) => {
+                _ = tokio::time::sleep_until(self.deadline.into()), if T::USES_SECURE_CHANNEL => {
                     warn!("Connection timed out, closing");
                     self.fatal_error(StatusCode::BadTimeout, "Connection timeout");
                 }
@@ -261,8 +262,8 @@ impl SessionController {
         match req.message {
             RequestMessage::OpenSecureChannel(r) => {
                 let res = self.open_secure_channel(
-                    &req.chunk_info.security_header,
-                    self.transport.client_protocol_version,
+                    req.chunk_info.as_ref().map(|c| &c.security_header),
+                    self.transport.client_protocol_version(),
                     &r,
                 );
                 if res.is_ok() {
@@ -296,6 +297,9 @@ impl SessionController {
                 let mut mgr = trace_write_lock!(self.session_manager);
                 let res = mgr.create_session(&mut self.channel, &self.certificate_store, &request);
                 drop(mgr);
+                if let Err(e) = &res {
+                    self.info.diagnostics.on_session_rejected(*e);
+                }
                 self.process_service_result(res, request.request_header.request_handle, id)
             }
 
@@ -307,6 +311,9 @@ impl SessionController {
                     &mut self.message_handler,
                 )
                 .await;
+                if let Err(e) = &res {
+                    self.info.diagnostics.on_session_rejected(*e);
+                }
                 self.process_service_result(res, request.request_header.request_handle, id)
             }
 
@@ -339,72 +346,77 @@ impl SessionController {
                 )
             }
             RequestMessage::FindServers(request) => {
-                let desc = self.info.config.application_description();
-                let mut servers = vec![desc];
-
-                // TODO endpoint URL
-
-                // TODO localeids, filter out servers that do not support locale ids
-
-                // Filter servers that do not have a matching application uri
-                if let Some(ref server_uris) = request.server_uris {
-                    if !server_uris.is_empty() {
-                        // Filter the servers down
-                        servers.retain(|server| {
-                            server_uris.iter().any(|uri| *uri == server.application_uri)
-                        });
-                    }
-                }
-
-                let servers = Some(servers);
+                let servers = self.info.find_servers(
+                    &request.endpoint_url,
+                    &request.locale_ids,
+                    &request.server_uris,
+                );
 
                 self.process_service_result(
                     Ok(FindServersResponse {
                         response_header: ResponseHeader::new_good(&request.request_header),
-                        servers,
+                        servers: Some(servers),
                     }),
                     request.request_header.request_handle,
                     id,
                 )
             }
             RequestMessage::FindServersOnNetwork(request) => {
-                if let Err(e) = self.transport.enqueue_message_for_send(
-                    &mut self.channel,
-                    ServiceFault::new(&request.request_header, StatusCode::BadServiceUnsupported)
-                        .into(),
-                    id,
-                ) {
-                    error!("Failed to send request response: {e}");
-                    RequestProcessResult::Close
-                } else {
-                    RequestProcessResult::Ok
-                }
+                let res = match &self.info.multicast_discovery {
+                    Some(multicast) => {
+                        let (last_counter_reset_time, servers) = multicast.find_servers_on_network(
+                            request.starting_record_id,
+                            request.max_records_to_return,
+                            request
+                                .server_capability_filter
+                                .as_deref()
+                                .unwrap_or_default(),
+                        );
+                        Ok(FindServersOnNetworkResponse {
+                            response_header: ResponseHeader::new_good(&request.request_header),
+                            last_counter_reset_time,
+                            servers: Some(servers),
+                        })
+                    }
+                    None => Err(StatusCode::BadServiceUnsupported),
+                };
+                self.process_service_result(res, request.request_header.request_handle, id)
             }
             RequestMessage::RegisterServer(request) => {
-                if let Err(e) = self.transport.enqueue_message_for_send(
-                    &mut self.channel,
-                    ServiceFault::new(&request.request_header, StatusCode::BadServiceUnsupported)
-                        .into(),
-                    id,
-                ) {
-                    error!("Failed to send request response: {e}");
-                    RequestProcessResult::Close
-                } else {
-                    RequestProcessResult::Ok
-                }
+                let res =
+                    self.register_server(&request.server, None)
+                        .map(|_| RegisterServerResponse {
+                            response_header: ResponseHeader::new_good(&request.request_header),
+                        });
+                self.process_service_result(res, request.request_header.request_handle, id)
             }
             RequestMessage::RegisterServer2(request) => {
-                if let Err(e) = self.transport.enqueue_message_for_send(
-                    &mut self.channel,
-                    ServiceFault::new(&request.request_header, StatusCode::BadServiceUnsupported)
-                        .into(),
-                    id,
-                ) {
-                    error!("Failed to send request response: {e}");
-                    RequestProcessResult::Close
-                } else {
-                    RequestProcessResult::Ok
-                }
+                let mdns_config = request
+                    .discovery_configuration
+                    .iter()
+                    .flatten()
+                    .find_map(|c| c.inner_as::<MdnsDiscoveryConfiguration>())
+                    .cloned();
+                let res = self.register_server(&request.server, mdns_config).map(|_| {
+                    // Only the mDNS discovery configuration is supported.
+                    let configuration_results = request.discovery_configuration.as_ref().map(|c| {
+                        c.iter()
+                            .map(|c| {
+                                if c.inner_is::<MdnsDiscoveryConfiguration>() {
+                                    StatusCode::Good
+                                } else {
+                                    StatusCode::BadNotSupported
+                                }
+                            })
+                            .collect()
+                    });
+                    RegisterServer2Response {
+                        response_header: ResponseHeader::new_good(&request.request_header),
+                        configuration_results,
+                        diagnostic_infos: None,
+                    }
+                });
+                self.process_service_result(res, request.request_header.request_handle, id)
             }
 
             message => {
@@ -413,9 +425,11 @@ impl SessionController {
                 let session = mgr.find_by_token(&message.request_header().authentication_token);
 
                 let (session_id, session, user_token) =
-                    match Self::validate_request(&message, session, &self.channel) {
+                    match Self::validate_request(session, &self.channel) {
                         Ok(s) => s,
                         Err(e) => {
+                            self.info.diagnostics.on_request_rejected(e);
+                            let e = ServiceFault::new(message.request_header(), e).into();
                             match self
                                 .transport
                                 .enqueue_message_for_send(&mut self.channel, e, id)
@@ -445,6 +459,7 @@ impl SessionController {
                     }
                 };
                 let request_handle = message.request_handle();
+                let diagnostics = trace_read_lock!(session).diagnostics().on_request(&message);
 
                 match self
                     .message_handler
@@ -456,7 +471,7 @@ impl SessionController {
                                 // Select biased because if for some reason there's a long time between polls,
                                 // we want to return the response even if the timeout expired. We only want to send a timeout
                                 // if the call has not been finished yet.
-                                tokio::select! {
+                                let res = tokio::select! {
                                     biased;
                                     r = &mut handle => {
                                         r.map_err(|e| e.to_string())
@@ -465,11 +480,16 @@ impl SessionController {
                                         handle.abort();
                                         Ok(Response { message: ServiceFault::new(request_handle, StatusCode::BadTimeout).into(), request_id: id })
                                     }
+                                };
+                                if let Ok(r) = &res {
+                                    diagnostics.complete(&r.message);
                                 }
+                                res
                             }));
                         RequestProcessResult::Ok
                     }
                     super::message_handler::HandleMessageResult::SyncMessage(s) => {
+                        diagnostics.complete(&s.message);
                         if let Err(e) = self.transport.enqueue_message_for_send(
                             &mut self.channel,
                             s.message,
@@ -481,7 +501,13 @@ impl SessionController {
                         RequestProcessResult::Ok
                     }
                     super::message_handler::HandleMessageResult::PublishResponse(resp) => {
-                        self.pending_messages.push(Box::pin(resp.recv()));
+                        self.pending_messages.push(Box::pin(async move {
+                            let res = resp.recv().await;
+                            if let Ok(r) = &res {
+                                diagnostics.complete(&r.message);
+                            }
+                            res
+                        }));
                         RequestProcessResult::Ok
                     }
                 }
@@ -489,6 +515,33 @@ impl SessionController {
         }
     }
 
+    fn register_server(
+        &self,
+        server: &RegisteredServer,
+        mdns_config: Option<MdnsDiscoveryConfiguration>,
+    ) -> Result<(), StatusCode> {
+        let Some(registry) = &self.info.server_registry else {
+            return Err(StatusCode::BadServiceUnsupported);
+        };
+        // Servers must register over a secure channel that authenticates them.
+        if self.channel.security_mode() == MessageSecurityMode::None {
+            return Err(StatusCode::BadSecurityModeInsufficient);
+        }
+        // A server may only register itself, so the server URI must be the application
+        // URI of the certificate used to open the secure channel.
+        let Some(cert) = self.channel.remote_cert() else {
+            return Err(StatusCode::BadSecurityModeInsufficient);
+        };
+        if let Err(e) = cert.is_application_uri_valid(server.server_uri.as_ref()) {
+            warn!(
+                "Rejecting registration of server {}: {e}",
+                server.server_uri
+            );
+            return Err(StatusCode::BadServerUriInvalid);
+        }
+        registry.register(server.clone(), mdns_config)
+    }
+
     fn process_service_result(
         &mut self,
         res: Result<impl Into<ResponseMessage>, StatusCode>,
@@ -511,37 +564,49 @@ impl SessionController {
     }
 
     fn validate_request(
-        message: &RequestMessage,
         session: Option<Arc<RwLock<Session>>>,
         channel: &SecureChannel,
-    ) -> Result<(u32, Arc<RwLock<Session>>, UserToken), ResponseMessage> {
-        let header = message.request_header();
-
+    ) -> Result<(u32, Arc<RwLock<Session>>, UserToken), StatusCode> {
         let Some(session) = session else {
-            return Err(ServiceFault::new(header, StatusCode::BadSessionIdInvalid).into());
+            return Err(StatusCode::BadSessionIdInvalid);
         };
 
         let session_lock = trace_read_lock!(session);
         let id = session_lock.session_id_numeric();
 
-        let user_token = (move || {
+        let user_token = (|| {
             let token = session_lock.validate_activated()?;
-            session_lock.validate_secure_channel_id(channel.secure_channel_id())?;
+            // Sessions created over HTTPS are not bound to a single connection,
+            // clients may send requests on any connection using the same transport.
+            if T::USES_SECURE_CHANNEL
+                || session_lock.transport_profile_uri()
+                    != profiles::TRANSPORT_PROFILE_URI_HTTPS_BINARY
+            {
+                session_lock.validate_secure_channel_id(channel.secure_channel_id())?;
+            }
             session_lock.validate_timed_out()?;
             Ok(token.clone())
         })()
-        .map_err(|e| ServiceFault::new(header, e))?;
+        .inspect_err(|e| {
+            if matches!(
+                *e,
+                StatusCode::BadSessionNotActivated | StatusCode::BadSecureChannelIdInvalid
+            ) {
+                session_lock.diagnostics().on_unauthorized_request();
+            }
+        })?;
+        drop(session_lock);
         Ok((id, session, user_token))
     }
 
     fn open_secure_channel(
         &mut self,
-        security_header: &SecurityHeader,
+        security_header: Option<&SecurityHeader>,
         client_protocol_version: u32,
         request: &OpenSecureChannelRequest,
     ) -> Result<ResponseMessage, StatusCode> {
         let security_header = match security_header {
-            SecurityHeader::Asymmetric(security_header) => security_header,
+            Some(SecurityHeader::Asymmetric(security_header)) => security_header,
             _ => {
                 error!("Secure channel request message does not have asymmetric security header");
                 return Err(StatusCode::BadUnexpectedError);
@@ -650,7 +715,10 @@ impl SessionController {
             && (security_mode == MessageSecurityMode::Sign
                 || security_mode == MessageSecurityMode::SignAndEncrypt)
         {
-            self.channel.derive_keys();
+            if let Err(err) = self.channel.derive_keys() {
+                error!("Was unable to derive the secure channel keys");
+                return Ok(ServiceFault::new(&request.request_header, err).into());
+            }
         }
 
         let response = OpenSecureChannelResponse {
@@ -8,12 +8,14 @@ use log::error;
 use super::continuation_points::ContinuationPoint;
 use super::manager::next_session_id;
 use crate::authenticator::UserToken;
+use crate::diagnostics::SessionDiagnostics;
 use crate::identity_token::IdentityToken;
 use crate::info::ServerInfo;
 use crate::node_manager::{BrowseContinuationPoint, QueryContinuationPoint};
 use opcua_crypto::X509;
 use opcua_types::{
-    ApplicationDescription, ByteString, MessageSecurityMode, NodeId, StatusCode, UAString,
+    ApplicationDescription, ByteString, MessageSecurityMode, NodeId, SessionDiagnosticsDataType,
+    SessionSecurityDiagnosticsDataType, StatusCode, UAString,
 };
 
 /// An instance of an OPC-UA session.
@@ -56,6 +58,8 @@ pub struct Session {
     application_description: ApplicationDescription,
     /// Message security mode. Set on the channel, but cached here.
     message_security_mode: MessageSecurityMode,
+    /// Transport profile of the connection the session was created on.
+    transport_profile_uri: &'static str,
     /// Time of last service request.
     last_service_request: ArcSwap<Instant>,
     /// Continuation points for browse.
@@ -66,8 +70,14 @@ pub struct Session {
     query_continuation_points: HashMap<ByteString, QueryContinuationPoint>,
     /// User token.
     user_token: Option<UserToken>,
+    /// Roles granted to the user of this session.
+    roles: Arc<[NodeId]>,
     /// Whether the session has been closed.
     is_closed: bool,
+    /// IDs of all users that have been active on this session.
+    client_user_id_history: Vec<UAString>,
+    /// Diagnostic counters for this session.
+    diagnostics: Arc<SessionDiagnostics>,
 }
 
 impl Session {
@@ -117,9 +127,13 @@ impl Session {
             history_continuation_points: Default::default(),
             query_continuation_points: Default::default(),
             user_token: None,
+            roles: Arc::new([]),
             application_description,
             message_security_mode,
+            transport_profile_uri: info.transport_profile_uri(),
             is_closed: false,
+            client_user_id_history: Vec::new(),
+            diagnostics: Arc::new(SessionDiagnostics::default()),
         }
     }
 
@@ -171,6 +185,11 @@ impl Session {
         }
     }
 
+    /// Get the transport profile of the connection the session was created on.
+    pub(crate) fn transport_profile_uri(&self) -> &'static str {
+        self.transport_profile_uri
+    }
+
     /// Activate the session.
     pub(crate) fn activate(
         &mut self,
@@ -179,8 +198,14 @@ impl Session {
         identity: IdentityToken,
         locale_ids: Option<Vec<UAString>>,
         user_token: UserToken,
+        roles: Vec<NodeId>,
     ) {
+        let user_id = UAString::from(&user_token.0);
+        if self.client_user_id_history.last() != Some(&user_id) {
+            self.client_user_id_history.push(user_id);
+        }
         self.user_token = Some(user_token);
+        self.roles = roles.into();
         self.secure_channel_id = secure_channel_id;
         self.session_nonce = server_nonce;
         self.user_identity = identity;
@@ -301,6 +326,11 @@ impl Session {
         self.user_token.as_ref()
     }
 
+    /// Get the IDs of the roles granted to the user of this session.
+    pub fn roles(&self) -> &Arc<[NodeId]> {
+        &self.roles
+    }
+
     /// Get the message security mode used by this session.
     pub fn message_security_mode(&self) -> MessageSecurityMode {
         self.message_security_mode
@@ -311,6 +341,11 @@ impl Session {
         self.session_id_numeric
     }
 
+    /// Get the session timeout.
+    pub fn session_timeout(&self) -> Duration {
+        self.session_timeout
+    }
+
     /// Get the negotiated max request message size.
     pub fn max_request_message_size(&self) -> u32 {
         self.max_request_message_size
@@ -330,4 +365,99 @@ impl Session {
     pub fn security_policy_uri(&self) -> &str {
         &self.security_policy_uri
     }
+
+    /// Get the diagnostic counters for this session.
+    pub fn diagnostics(&self) -> &Arc<SessionDiagnostics> {
+        &self.diagnostics
+    }
+
+    /// Get the current diagnostics of this session. `subscription_counts` is
+    /// the number of subscriptions, monitored items and queued publish requests
+    /// on the session, which must be obtained from the subscription cache.
+    pub(crate) fn diagnostics_data(
+        &self,
+        info: &ServerInfo,
+        subscription_counts: (u32, u32, u32),
+    ) -> SessionDiagnosticsDataType {
+        use crate::diagnostics::DiagnosticsService as S;
+
+        let d = &self.diagnostics;
+        let (current_subscriptions_count, current_monitored_items_count, publish_requests) =
+            subscription_counts;
+        SessionDiagnosticsDataType {
+            session_id: self.session_id.clone(),
+            session_name: self.session_name.clone(),
+            client_description: self.application_description.clone(),
+            server_uri: info.application_uri.clone(),
+            endpoint_url: self.endpoint_url.clone(),
+            locale_ids: self.locale_ids.clone(),
+            actual_session_timeout: self.session_timeout.as_millis() as f64,
+            max_response_message_size: self.max_response_message_size,
+            client_connection_time: d.client_connection_time(),
+            client_last_contact_time: d.client_last_contact_time(),
+            current_subscriptions_count,
+            current_monitored_items_count,
+            current_publish_requests_in_queue: publish_requests,
+            total_request_count: d.total_request_count(),
+            unauthorized_request_count: d.unauthorized_request_count(),
+            read_count: d.service_count(S::Read),
+            history_read_count: d.service_count(S::HistoryRead),
+            write_count: d.service_count(S::Write),
+            history_update_count: d.service_count(S::HistoryUpdate),
+            call_count: d.service_count(S::Call),
+            create_monitored_items_count: d.service_count(S::CreateMonitoredItems),
+            modify_monitored_items_count: d.service_count(S::ModifyMonitoredItems),
+            set_monitoring_mode_count: d.service_count(S::SetMonitoringMode),
+            set_triggering_count: d.service_count(S::SetTriggering),
+            delete_monitored_items_count: d.service_count(S::DeleteMonitoredItems),
+            create_subscription_count: d.service_count(S::CreateSubscription),
+            modify_subscription_count: d.service_count(S::ModifySubscription),
+            set_publishing_mode_count: d.service_count(S::SetPublishingMode),
+            publish_count: d.service_count(S::Publish),
+            republish_count: d.service_count(S::Republish),
+            transfer_subscriptions_count: d.service_count(S::TransferSubscriptions),
+            delete_subscriptions_count: d.service_count(S::DeleteSubscriptions),
+            add_nodes_count: d.service_count(S::AddNodes),
+            add_references_count: d.service_count(S::AddReferences),
+            delete_nodes_count: d.service_count(S::DeleteNodes),
+            delete_references_count: d.service_count(S::DeleteReferences),
+            browse_count: d.service_count(S::Browse),
+            browse_next_count: d.service_count(S::BrowseNext),
+            translate_browse_paths_to_node_ids_count: d
+                .service_count(S::TranslateBrowsePathsToNodeIds),
+            query_first_count: d.service_count(S::QueryFirst),
+            query_next_count: d.service_count(S::QueryNext),
+            register_nodes_count: d.service_count(S::RegisterNodes),
+            unregister_nodes_count: d.service_count(S::UnregisterNodes),
+        }
+    }
+
+    /// Get the current security diagnostics of this session.
+    pub(crate) fn security_diagnostics_data(&self) -> SessionSecurityDiagnosticsDataType {
+        let authentication_mechanism = match &self.user_identity {
+            IdentityToken::Anonymous(_) => "Anonymous".into(),
+            IdentityToken::UserName(_) => "UserName".into(),
+            IdentityToken::X509(_) => "Certificate".into(),
+            IdentityToken::None | IdentityToken::Invalid(_) => UAString::null(),
+        };
+        SessionSecurityDiagnosticsDataType {
+            session_id: self.session_id.clone(),
+            client_user_id_of_session: self
+                .user_token
+                .as_ref()
+                .map(|t| UAString::from(&t.0))
+                .unwrap_or_default(),
+            client_user_id_history: Some(self.client_user_id_history.clone()),
+            authentication_mechanism,
+            encoding: "UA Binary".into(),
+            transport_protocol: self.transport_profile_uri.into(),
+            security_mode: self.message_security_mode,
+            security_policy_uri: self.security_policy_uri.clone().into(),
+            client_certificate: self
+                .client_certificate
+                .as_ref()
+                .map(|c| c.as_byte_string())
+                .unwrap_or_default(),
+        }
+    }
 }
@@ -13,7 +13,10 @@ use opcua_crypto::{random, security_policy::SecurityPolicy, CertificateStore};
 use parking_lot::RwLock;
 use tokio::sync::Notify;
 
-use crate::{identity_token::IdentityToken, info::ServerInfo};
+use crate::{
+    identity_token::IdentityToken, info::ServerInfo, node_manager::NodeManagersRef,
+    redundancy::ReplicatedSession,
+};
 use opcua_types::{
     ActivateSessionRequest, ActivateSessionResponse, CloseSessionRequest, CloseSessionResponse,
     CreateSessionRequest, CreateSessionResponse, Error, NodeId, ResponseHeader, SignatureData,
@@ -37,14 +40,20 @@ pub struct SessionManager {
     sessions: HashMap<NodeId, Arc<RwLock<Session>>>,
     info: Arc<ServerInfo>,
     notify: Arc<Notify>,
+    node_managers: NodeManagersRef,
 }
 
 impl SessionManager {
-    pub(crate) fn new(info: Arc<ServerInfo>, notify: Arc<Notify>) -> Self {
+    pub(crate) fn new(
+        info: Arc<ServerInfo>,
+        notify: Arc<Notify>,
+        node_managers: NodeManagersRef,
+    ) -> Self {
         Self {
             sessions: Default::default(),
             info,
             notify,
+            node_managers,
         }
     }
 
@@ -53,6 +62,29 @@ impl SessionManager {
         Self::find_by_token_int(&self.sessions, authentication_token)
     }
 
+    /// Get a session by its numeric session ID.
+    pub fn find_by_id(&self, session_id: u32) -> Option<Arc<RwLock<Session>>> {
+        self.sessions
+            .values()
+            .find(|s| s.read().session_id_numeric() == session_id)
+            .cloned()
+    }
+
+    /// Iterate over all sessions on the server.
+    pub fn sessions(&self) -> impl Iterator<Item = &Arc<RwLock<Session>>> {
+        self.sessions.values()
+    }
+
+    /// Get the number of sessions on the server.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Return `true` if there are no sessions on the server.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
     fn find_by_token_int(
         sessions: &HashMap<NodeId, Arc<RwLock<Session>>>,
         authentication_token: &NodeId,
@@ -66,7 +98,7 @@ impl SessionManager {
     pub(crate) fn create_session(
         &mut self,
         channel: &mut SecureChannel,
-        certificate_store: &RwLock<CertificateStore>,
+        certificate_store: &RwLock<dyn CertificateStore>,
         request: &CreateSessionRequest,
     ) -> Result<CreateSessionResponse, StatusCode> {
         if self.sessions.len() >= self.info.config.limits.max_sessions {
@@ -111,7 +143,7 @@ impl SessionManager {
             .min(request.requested_session_timeout.floor() as u64);
         let max_request_message_size = self.info.config.limits.max_message_size as u32;
 
-        let server_signature = if let Some(ref pkey) = self.info.server_pkey {
+        let server_signature = if let Some(pkey) = self.info.server_pkey.load().as_deref() {
             opcua_crypto::create_signature_data(
                 pkey,
                 security_policy,
@@ -155,6 +187,7 @@ impl SessionManager {
         let session_id = session.session_id().clone();
         self.sessions
             .insert(session_id.clone(), Arc::new(RwLock::new(session)));
+        self.info.diagnostics.on_session_created();
 
         self.notify.notify_waiters();
 
@@ -179,7 +212,7 @@ impl SessionManager {
         client_signature: &SignatureData,
     ) -> Result<(), Error> {
         if let Some(client_certificate) = session.client_certificate() {
-            if let Some(ref server_certificate) = info.server_certificate {
+            if let Some(server_certificate) = info.server_certificate.load().as_deref() {
                 opcua_crypto::verify_signature_data(
                     client_signature,
                     security_policy,
@@ -202,15 +235,84 @@ impl SessionManager {
         }
     }
 
+    /// Restore a session activated on another server in the redundant server set,
+    /// if the server shares sessions with the set and the session is known.
+    fn restore_replicated_session(
+        &mut self,
+        channel: &SecureChannel,
+        authentication_token: &NodeId,
+    ) {
+        let Some(replicated) = self
+            .info
+            .redundancy
+            .session_replication()
+            .and_then(|r| r.find_session(authentication_token))
+        else {
+            return;
+        };
+        if replicated.security_policy_uri != channel.security_policy().to_uri()
+            || replicated.message_security_mode != channel.security_mode()
+            || self.sessions.len() >= self.info.config.limits.max_sessions
+        {
+            return;
+        }
+        let client_certificate = if replicated.client_certificate.is_null() {
+            None
+        } else {
+            let Ok(cert) = opcua_crypto::X509::from_byte_string(&replicated.client_certificate)
+            else {
+                return;
+            };
+            Some(cert)
+        };
+
+        let session = Session::create(
+            &self.info,
+            replicated.authentication_token,
+            channel.secure_channel_id(),
+            replicated.session_timeout,
+            replicated.max_request_message_size,
+            replicated.max_response_message_size,
+            local_endpoint_url(&self.info, replicated.endpoint_url.as_ref()).into(),
+            replicated.security_policy_uri,
+            IdentityToken::None,
+            client_certificate,
+            replicated.session_nonce,
+            replicated.session_name,
+            replicated.client_description,
+            replicated.message_security_mode,
+        );
+        info!(
+            "Restored session replicated from another server with ID {}",
+            session.session_id()
+        );
+        self.sessions
+            .insert(session.session_id().clone(), Arc::new(RwLock::new(session)));
+        self.info.diagnostics.on_session_created();
+        self.notify.notify_waiters();
+    }
+
     pub(crate) fn expire_session(&mut self, id: &NodeId) {
         let Some(session) = self.sessions.remove(id) else {
             return;
         };
 
         info!("Session {id} has expired, removing it from the session map. Subscriptions will remain until they individually expire");
+        self.info.diagnostics.on_session_timeout();
 
-        let mut session = trace_write_lock!(session);
-        session.close();
+        let session_id = {
+            let mut session = trace_write_lock!(session);
+            session.close();
+            session.session_id_numeric()
+        };
+        self.session_closed(session_id);
+    }
+
+    /// Let the node managers release any state belonging to a removed session.
+    fn session_closed(&self, session_id: u32) {
+        for node_manager in self.node_managers.iter() {
+            node_manager.session_closed(session_id);
+        }
     }
 
     pub(crate) fn check_session_expiry(&self) -> (Instant, Vec<NodeId>) {
@@ -230,6 +332,36 @@ impl SessionManager {
     }
 }
 
+/// Rewrite `url` to point at this server, keeping the path. Servers in a redundant
+/// server set have different addresses, so a session restored from another server
+/// would otherwise refer to an endpoint that does not exist here.
+fn local_endpoint_url(info: &ServerInfo, url: &str) -> String {
+    let path = url
+        .split_once("://")
+        .and_then(|(_, rest)| rest.find('/').map(|idx| &rest[idx..]))
+        .unwrap_or("/");
+    format!("{}{}", info.base_endpoint(), path)
+}
+
+fn replicated_session(session: &Session) -> ReplicatedSession {
+    ReplicatedSession {
+        authentication_token: session.authentication_token.clone(),
+        session_name: session.session_name().into(),
+        session_timeout: session.session_timeout().as_millis() as u64,
+        max_request_message_size: session.max_request_message_size(),
+        max_response_message_size: session.max_response_message_size(),
+        endpoint_url: session.endpoint_url().clone(),
+        security_policy_uri: session.security_policy_uri().to_owned(),
+        message_security_mode: session.message_security_mode(),
+        client_certificate: session
+            .client_certificate()
+            .map(|c| c.as_byte_string())
+            .unwrap_or_default(),
+        session_nonce: session.session_nonce().clone(),
+        client_description: session.application_description().clone(),
+    }
+}
+
 // This is a non-self method to avoid holding the manager
 // across an await point.
 pub(crate) async fn close_session(
@@ -263,6 +395,10 @@ pub(crate) async fn close_session(
             let mut session_lck = trace_write_lock!(session);
             session_lck.close();
         }
+        mgr.session_closed(id);
+        if let Some(replication) = mgr.info.redundancy.session_replication() {
+            replication.session_closed(&request.request_header.authentication_token);
+        }
         (session, id, token)
     };
 
@@ -290,10 +426,19 @@ pub(crate) async fn activate_session(
     let security_mode = channel.security_mode();
     let secure_channel_id = channel.secure_channel_id();
     let server_nonce = security_policy.random_nonce();
+    let authentication_token = &request.request_header.authentication_token;
+    let restore = {
+        let mgr = trace_read_lock!(mgr_lck);
+        mgr.info.redundancy.session_replication().is_some()
+            && mgr.find_by_token(authentication_token).is_none()
+    };
+    if restore {
+        trace_write_lock!(mgr_lck).restore_replicated_session(channel, authentication_token);
+    }
+
     let (endpoint_url, session_nonce, session_lck, info) = {
         let mgr = trace_read_lock!(mgr_lck);
-        let Some(session_lck) = mgr.find_by_token(&request.request_header.authentication_token)
-        else {
+        let Some(session_lck) = mgr.find_by_token(authentication_token) else {
             return Err(StatusCode::BadSessionIdInvalid);
         };
 
@@ -336,6 +481,14 @@ pub(crate) async fn activate_session(
         )
         .await?;
 
+    let identity = IdentityToken::new(request.user_identity_token.clone());
+    let mut roles = info.roles.roles_for_identity(&identity);
+    for role in info.authenticator.user_roles(&user_token) {
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+    }
+
     let (server_nonce, session_id) = {
         let mut session = trace_write_lock!(session_lck);
 
@@ -355,10 +508,14 @@ pub(crate) async fn activate_session(
         session.activate(
             secure_channel_id,
             server_nonce,
-            IdentityToken::new(request.user_identity_token.clone()),
+            identity,
             request.locale_ids.clone(),
             user_token.clone(),
+            roles,
         );
+        if let Some(replication) = info.redundancy.session_replication() {
+            replication.session_activated(replicated_session(&session));
+        }
         (
             session.session_nonce().clone(),
             session.session_id_numeric(),
@@ -2,16 +2,37 @@ use opcua_core::trace_write_lock;
 
 use crate::{
     node_manager::{
-        consume_results, HistoryNode, HistoryReadDetails, HistoryUpdateDetails, HistoryUpdateNode,
-        NodeManagers, ReadNode, WriteNode,
+        consume_results, validate_permissions, HistoryNode, HistoryReadDetails,
+        HistoryUpdateDetails, HistoryUpdateNode, NodeManagers, ReadNode, WriteNode,
     },
     session::{controller::Response, message_handler::Request},
 };
 use opcua_types::{
-    ByteString, DeleteAtTimeDetails, ExtensionObject, HistoryReadRequest, HistoryReadResponse,
-    HistoryReadResult, HistoryUpdateRequest, HistoryUpdateResponse, NodeId, ObjectId, ReadRequest,
-    ReadResponse, ResponseHeader, StatusCode, TimestampsToReturn, WriteRequest, WriteResponse,
+    AttributeId, ByteString, DeleteAtTimeDetails, ExtensionObject, HistoryReadRequest,
+    HistoryReadResponse, HistoryReadResult, HistoryUpdateRequest, HistoryUpdateResponse, NodeId,
+    ObjectId, PermissionType, ReadRequest, ReadResponse, ResponseHeader, StatusCode,
+    TimestampsToReturn, WriteRequest, WriteResponse,
 };
+
+/// Get the permission required to read `attribute_id`.
+pub(super) fn read_permission(attribute_id: AttributeId) -> PermissionType {
+    match attribute_id {
+        AttributeId::Value => PermissionType::Read,
+        AttributeId::RolePermissions => PermissionType::ReadRolePermissions,
+        _ => PermissionType::Browse,
+    }
+}
+
+/// Get the permission required to write `attribute_id`.
+fn write_permission(attribute_id: AttributeId) -> PermissionType {
+    match attribute_id {
+        AttributeId::Value => PermissionType::Write,
+        AttributeId::RolePermissions => PermissionType::WriteRolePermissions,
+        AttributeId::Historizing => PermissionType::WriteHistorizing,
+        _ => PermissionType::WriteAttribute,
+    }
+}
+
 pub async fn read(node_managers: NodeManagers, request: Request<ReadRequest>) -> Response {
     let mut context = request.context();
     let nodes_to_read = take_service_items!(
@@ -31,14 +52,29 @@ pub async fn read(node_managers: NodeManagers, request: Request<ReadRequest>) ->
         .map(|n| ReadNode::new(n, request.request.request_header.return_diagnostics))
         .collect();
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut results,
+        |n| {
+            (n.status() == StatusCode::BadNodeIdUnknown)
+                .then(|| (&n.node().node_id, read_permission(n.node().attribute_id)))
+        },
+        |n, e| n.set_error(e),
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.into_iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut batch: Vec<_> = results
             .iter_mut()
-            .filter(|n| {
-                node_manager.owns_node(&n.node().node_id)
+            .zip(allowed.iter())
+            .filter(|(n, allowed)| {
+                **allowed
+                    && node_manager.owns_node(&n.node().node_id)
                     && n.status() == StatusCode::BadNodeIdUnknown
             })
+            .map(|(n, _)| n)
             .collect();
 
         if batch.is_empty() {
@@ -87,14 +123,29 @@ pub async fn write(node_managers: NodeManagers, request: Request<WriteRequest>)
         .map(|n| WriteNode::new(n, request.request.request_header.return_diagnostics))
         .collect();
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut results,
+        |n| {
+            (n.status() == StatusCode::BadNodeIdUnknown)
+                .then(|| (&n.value().node_id, write_permission(n.value().attribute_id)))
+        },
+        |n, e| n.set_status(e),
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.into_iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut batch: Vec<_> = results
             .iter_mut()
-            .filter(|n| {
-                node_manager.owns_node(&n.value().node_id)
+            .zip(allowed.iter())
+            .filter(|(n, allowed)| {
+                **allowed
+                    && node_manager.owns_node(&n.value().node_id)
                     && n.status() == StatusCode::BadNodeIdUnknown
             })
+            .map(|(n, _)| n)
             .collect();
 
         if batch.is_empty() {
@@ -158,6 +209,18 @@ pub async fn history_read(
     {
         return service_fault!(request, StatusCode::BadTooManyOperations);
     }
+    // ReadProcessed has one aggregate per node.
+    let aggregate_types = match &details {
+        HistoryReadDetails::Processed(d) => {
+            let aggregate_types = d.aggregate_type.clone().unwrap_or_default();
+            if aggregate_types.len() != items.len() {
+                return service_fault!(request, StatusCode::BadAggregateListMismatch);
+            }
+            Some(aggregate_types)
+        }
+        _ => None,
+    };
+
     let mut nodes: Vec<_> = {
         let mut session = trace_write_lock!(request.session);
         items
@@ -183,6 +246,11 @@ pub async fn history_read(
             })
             .collect()
     };
+    if let Some(aggregate_types) = aggregate_types {
+        for (node, aggregate_type) in nodes.iter_mut().zip(aggregate_types) {
+            node.set_aggregate_type(aggregate_type);
+        }
+    }
 
     // If we are releasing continuation points we should not return any data.
     if request.request.release_continuation_points {
@@ -206,10 +274,25 @@ pub async fn history_read(
         };
     }
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut nodes,
+        |n| {
+            (n.status() == StatusCode::BadNodeIdUnknown)
+                .then(|| (n.node_id(), PermissionType::ReadHistory))
+        },
+        |n, e| n.set_status(e),
+    )
+    .await;
+
     for (idx, manager) in node_managers.into_iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut batch: Vec<_> = nodes
             .iter_mut()
+            .zip(allowed.iter())
+            .filter(|(_, allowed)| **allowed)
+            .map(|(n, _)| n)
             .filter(|n| {
                 if n.node_id() == &ObjectId::Server
                     && matches!(details, HistoryReadDetails::Events(_))
@@ -335,10 +418,25 @@ pub async fn history_update(
         })
         .collect();
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut nodes,
+        |n| {
+            (n.status() == StatusCode::BadNodeIdUnknown)
+                .then(|| (n.details().node_id(), n.details().required_permission()))
+        },
+        |n, e| n.set_status(e),
+    )
+    .await;
+
     for (idx, manager) in node_managers.into_iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut batch: Vec<_> = nodes
             .iter_mut()
+            .zip(allowed.iter())
+            .filter(|(_, allowed)| **allowed)
+            .map(|(n, _)| n)
             .filter(|n| {
                 if n.details().node_id() == &ObjectId::Server
                     && matches!(
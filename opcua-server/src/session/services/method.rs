@@ -1,8 +1,8 @@
 use crate::{
-    node_manager::{consume_results, MethodCall, NodeManagers},
+    node_manager::{consume_results, validate_permissions, MethodCall, NodeManagers},
     session::{controller::Response, message_handler::Request},
 };
-use opcua_types::{CallRequest, CallResponse, ResponseHeader, StatusCode};
+use opcua_types::{CallRequest, CallResponse, PermissionType, ResponseHeader, StatusCode};
 
 pub async fn call(node_managers: NodeManagers, request: Request<CallRequest>) -> Response {
     let mut context = request.context();
@@ -17,13 +17,29 @@ pub async fn call(node_managers: NodeManagers, request: Request<CallRequest>) ->
         .map(|c| MethodCall::new(c, request.request.request_header.return_diagnostics))
         .collect();
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut calls,
+        |c| {
+            (c.status() == StatusCode::BadMethodInvalid)
+                .then(|| (c.method_id(), PermissionType::Call))
+        },
+        |c, e| c.set_status(e),
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.into_iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut owned: Vec<_> = calls
             .iter_mut()
-            .filter(|c| {
-                node_manager.owns_node(c.method_id()) && c.status() == StatusCode::BadMethodInvalid
+            .zip(allowed.iter())
+            .filter(|(c, allowed)| {
+                **allowed
+                    && node_manager.owns_node(c.method_id())
+                    && c.status() == StatusCode::BadMethodInvalid
             })
+            .map(|(c, _)| c)
             .collect();
 
         if owned.is_empty() {
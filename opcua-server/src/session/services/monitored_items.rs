@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 use crate::{
-    node_manager::{MonitoredItemRef, NodeManagers, RequestContext},
+    node_manager::{
+        get_node_permissions, validate_permissions, MonitoredItemRef, NodeManagers, RequestContext,
+    },
     session::{controller::Response, message_handler::Request},
     subscriptions::CreateMonitoredItem,
 };
@@ -9,13 +11,13 @@ use opcua_core::ResponseMessage;
 use opcua_types::{
     AttributeId, BrowsePath, CreateMonitoredItemsRequest, CreateMonitoredItemsResponse,
     DataChangeFilter, DeadbandType, DeleteMonitoredItemsRequest, DeleteMonitoredItemsResponse,
-    ModifyMonitoredItemsRequest, ModifyMonitoredItemsResponse, NodeId, Range, ReadRequest,
-    ReferenceTypeId, RelativePath, RelativePathElement, RequestHeader, ResponseHeader,
+    ModifyMonitoredItemsRequest, ModifyMonitoredItemsResponse, NodeId, PermissionType, Range,
+    ReadRequest, ReferenceTypeId, RelativePath, RelativePathElement, RequestHeader, ResponseHeader,
     SetMonitoringModeRequest, SetMonitoringModeResponse, StatusCode, TimestampsToReturn,
     TranslateBrowsePathsToNodeIdsRequest, Variant,
 };
 
-use super::{read, translate_browse_paths};
+use super::{attribute::read_permission, read, translate_browse_paths};
 
 // OPC-UA is sometimes very painful. In order to actually implement percent-deadband, we need to
 // fetch the EURange property from the node hierarchy. This method does that by calling TranslateBrowsePaths
@@ -199,14 +201,52 @@ pub async fn create_monitored_items(
             .collect()
     };
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut items,
+        |n| {
+            let item = n.item_to_monitor();
+            let permission = match item.attribute_id {
+                AttributeId::EventNotifier => PermissionType::ReceiveEvents,
+                a => read_permission(a),
+            };
+            (n.status_code() == StatusCode::BadNodeIdUnknown).then_some((&item.node_id, permission))
+        },
+        |n, e| n.set_status(e),
+    )
+    .await;
+
+    // Keep the permissions of event notifiers, so that ReceiveEvents can be
+    // checked again when events are delivered.
+    let mut event_items: Vec<_> = items
+        .iter_mut()
+        .zip(allowed.iter())
+        .filter(|(n, allowed)| {
+            **allowed && n.item_to_monitor().attribute_id == AttributeId::EventNotifier
+        })
+        .map(|(n, _)| n)
+        .collect();
+    let notifiers: Vec<_> = event_items
+        .iter()
+        .map(|n| &n.item_to_monitor().node_id)
+        .collect();
+    let permissions = get_node_permissions(&context, &node_managers, &notifiers).await;
+    for (item, permissions) in event_items.iter_mut().zip(permissions) {
+        item.set_permissions(permissions);
+    }
+
     for (idx, mgr) in node_managers.iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut owned: Vec<_> = items
             .iter_mut()
-            .filter(|n| {
-                n.status_code() == StatusCode::BadNodeIdUnknown
+            .zip(allowed.iter())
+            .filter(|(n, allowed)| {
+                **allowed
+                    && n.status_code() == StatusCode::BadNodeIdUnknown
                     && mgr.owns_node(&n.item_to_monitor().node_id)
             })
+            .map(|(n, _)| n)
             .collect();
 
         if owned.is_empty() {
@@ -1,14 +1,14 @@
 use crate::{
     node_manager::{
-        consume_results, AddNodeItem, AddReferenceItem, DeleteNodeItem, DeleteReferenceItem,
-        NodeManagers,
+        consume_results, validate_permissions, AddNodeItem, AddReferenceItem, DeleteNodeItem,
+        DeleteReferenceItem, NodeManagers,
     },
     session::{controller::Response, message_handler::Request},
 };
 use opcua_types::{
     AddNodesRequest, AddNodesResponse, AddReferencesRequest, AddReferencesResponse,
     DeleteNodesRequest, DeleteNodesResponse, DeleteReferencesRequest, DeleteReferencesResponse,
-    NodeId, ResponseHeader, StatusCode,
+    NodeId, PermissionType, ResponseHeader, StatusCode,
 };
 
 pub async fn add_nodes(node_managers: NodeManagers, request: Request<AddNodesRequest>) -> Response {
@@ -28,6 +28,18 @@ pub async fn add_nodes(node_managers: NodeManagers, request: Request<AddNodesReq
         .map(|it| AddNodeItem::new(it, request.request.request_header.return_diagnostics))
         .collect();
 
+    validate_permissions(
+        &context,
+        &node_managers,
+        &mut to_add,
+        |it| {
+            (it.status() == StatusCode::BadNotSupported)
+                .then(|| (&it.parent_node_id().node_id, PermissionType::AddNode))
+        },
+        |it, e| it.set_result(NodeId::null(), e),
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut owned: Vec<_> = to_add
@@ -89,6 +101,21 @@ pub async fn add_references(
         .map(|it| AddReferenceItem::new(it, request.request.request_header.return_diagnostics))
         .collect();
 
+    validate_permissions(
+        &context,
+        &node_managers,
+        &mut to_add,
+        |it| {
+            (it.source_status() == StatusCode::BadNotSupported)
+                .then(|| (it.source_node_id(), PermissionType::AddReference))
+        },
+        |it, e| {
+            it.set_source_result(e);
+            it.set_target_result(e);
+        },
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut owned: Vec<_> = to_add
@@ -154,13 +181,29 @@ pub async fn delete_nodes(
         .map(|v| DeleteNodeItem::new(v, request.request.request_header.return_diagnostics))
         .collect();
 
+    let allowed = validate_permissions(
+        &context,
+        &node_managers,
+        &mut to_delete,
+        |it| {
+            (it.status() == StatusCode::BadNodeIdUnknown)
+                .then(|| (it.node_id(), PermissionType::DeleteNode))
+        },
+        |it, e| it.set_result(e),
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut owned: Vec<_> = to_delete
             .iter_mut()
-            .filter(|it| {
-                it.status() == StatusCode::BadNodeIdUnknown && node_manager.owns_node(it.node_id())
+            .zip(allowed.iter())
+            .filter(|(it, allowed)| {
+                **allowed
+                    && it.status() == StatusCode::BadNodeIdUnknown
+                    && node_manager.owns_node(it.node_id())
             })
+            .map(|(it, _)| it)
             .collect();
 
         if owned.is_empty() {
@@ -221,6 +264,21 @@ pub async fn delete_references(
         .map(|it| DeleteReferenceItem::new(it, request.request.request_header.return_diagnostics))
         .collect();
 
+    validate_permissions(
+        &context,
+        &node_managers,
+        &mut to_delete,
+        |it| {
+            (it.source_status() == StatusCode::BadNotSupported)
+                .then(|| (it.source_node_id(), PermissionType::RemoveReference))
+        },
+        |it, e| {
+            it.set_source_result(e);
+            it.set_target_result(e);
+        },
+    )
+    .await;
+
     for (idx, node_manager) in node_managers.iter().enumerate() {
         context.current_node_manager_index = idx;
         let mut owned: Vec<_> = to_delete
@@ -3,7 +3,7 @@ use opcua_core::trace_write_lock;
 use opcua_nodes::ParsedContentFilter;
 
 use crate::{
-    node_manager::{NodeManagers, ParsedNodeTypeDescription, QueryRequest},
+    node_manager::{resolve_view, NodeManagers, ParsedNodeTypeDescription, QueryRequest},
     session::{controller::Response, message_handler::Request},
 };
 use opcua_types::{
@@ -33,10 +33,13 @@ pub async fn query_first(
     } else {
         references_limit.min(request.request.max_references_to_return as usize)
     };
-    if !request.request.view.view_id.is_null() || !request.request.view.timestamp.is_null() {
-        info!("Browse request ignored because view was specified (views not supported)");
-        return service_fault!(request, StatusCode::BadViewIdUnknown);
-    }
+    let view = match resolve_view(&context, &node_managers, &request.request.view).await {
+        Ok(v) => v,
+        Err(e) => {
+            info!("QueryFirst request rejected due to invalid view: {e}");
+            return service_fault!(request, e);
+        }
+    };
 
     let mut status_code = StatusCode::Good;
 
@@ -88,14 +91,13 @@ pub async fn query_first(
         content_filter,
         max_data_sets_to_return,
         max_references_to_return,
+        view,
     );
 
     for (index, node_manager) in node_managers.iter().enumerate() {
         context.current_node_manager_index = index;
         // All node managers must succeed. Partial success is really
         // hard to quantify for query...
-        // TODO: This is pretty much impossible to implement
-        // until we actually implement this in the core node manager.
         if let Err(e) = node_manager.query(&context, &mut query_request).await {
             return Response {
                 message: QueryFirstResponse {
@@ -114,6 +116,8 @@ pub async fn query_first(
         if query_request.is_completed() {
             break;
         }
+        // The continuation point is only meant for the node manager that created it.
+        query_request.clear_continuation_point();
     }
     let (result, continuation_point, status) = {
         let mut session = trace_write_lock!(request.session);
@@ -177,6 +181,8 @@ pub async fn query_next(
         if query_request.is_completed() {
             break;
         }
+        // The continuation point is only meant for the node manager that created it.
+        query_request.clear_continuation_point();
     }
 
     let (result, continuation_point, status) = {
@@ -5,8 +5,8 @@ use opcua_core::trace_write_lock;
 
 use crate::{
     node_manager::{
-        resolve_external_references, BrowseNode, BrowsePathItem, ExternalReferencesContPoint,
-        NodeManagers, RegisterNodeItem, RequestContext,
+        resolve_external_references, resolve_view, BrowseNode, BrowsePathItem,
+        ExternalReferencesContPoint, NodeManagers, RegisterNodeItem, RequestContext,
     },
     session::{controller::Response, message_handler::Request},
 };
@@ -15,6 +15,7 @@ use opcua_types::{
     BrowseResponse, BrowseResult, ByteString, RegisterNodesRequest, RegisterNodesResponse,
     ResponseHeader, StatusCode, TranslateBrowsePathsToNodeIdsRequest,
     TranslateBrowsePathsToNodeIdsResponse, UnregisterNodesRequest, UnregisterNodesResponse,
+    ViewDescription,
 };
 
 pub async fn browse(node_managers: NodeManagers, request: Request<BrowseRequest>) -> Response {
@@ -24,10 +25,13 @@ pub async fn browse(node_managers: NodeManagers, request: Request<BrowseRequest>
         request.request.nodes_to_browse,
         request.info.operational_limits.max_nodes_per_browse
     );
-    if !request.request.view.view_id.is_null() || !request.request.view.timestamp.is_null() {
-        info!("Browse request ignored because view was specified (views not supported)");
-        return service_fault!(request, StatusCode::BadViewIdUnknown);
-    }
+    let view = match resolve_view(&context, &node_managers, &request.request.view).await {
+        Ok(v) => v,
+        Err(e) => {
+            info!("Browse request rejected due to invalid view: {e}");
+            return service_fault!(request, e);
+        }
+    };
 
     let max_references_per_node = if request.request.requested_max_references_per_node == 0 {
         request
@@ -42,13 +46,24 @@ pub async fn browse(node_managers: NodeManagers, request: Request<BrowseRequest>
             .min(request.request.requested_max_references_per_node as usize)
     };
 
-    let mut nodes: Vec<_> = nodes_to_browse
-        .into_iter()
-        .enumerate()
-        .map(|(idx, r)| BrowseNode::new(r, max_references_per_node, idx))
-        .collect();
-
-    let mut results: Vec<_> = (0..nodes.len()).map(|_| None).collect();
+    let mut results: Vec<_> = (0..nodes_to_browse.len()).map(|_| None).collect();
+    let mut nodes = Vec::with_capacity(nodes_to_browse.len());
+    for (idx, r) in nodes_to_browse.into_iter().enumerate() {
+        if view.as_ref().is_some_and(|v| !v.contains(&r.node_id)) {
+            results[idx] = Some(BrowseResult {
+                status_code: StatusCode::BadNodeNotInView,
+                continuation_point: ByteString::null(),
+                references: None,
+            });
+            continue;
+        }
+        nodes.push(BrowseNode::new(
+            r,
+            max_references_per_node,
+            idx,
+            view.clone(),
+        ));
+    }
     let node_manager_count = node_managers.len();
 
     for (node_manager_index, node_manager) in node_managers.iter().enumerate() {
@@ -344,10 +359,23 @@ pub async fn translate_browse_paths(
             .max_nodes_per_translate_browse_paths_to_node_ids
     );
 
+    // Browse paths starting at a View node are resolved within that view.
+    let mut views = HashMap::new();
+    for path in &paths {
+        if !views.contains_key(&path.starting_node) {
+            let view = ViewDescription {
+                view_id: path.starting_node.clone(),
+                ..Default::default()
+            };
+            let view = resolve_view(&context, &node_managers, &view).await.ok();
+            views.insert(path.starting_node.clone(), view.flatten());
+        }
+    }
+
     let mut items: Vec<_> = paths
         .iter()
         .enumerate()
-        .map(|(i, p)| BrowsePathItem::new_root(p, i))
+        .map(|(i, p)| BrowsePathItem::new_root(p, i, views[&p.starting_node].clone()))
         .collect();
 
     let mut idx = 0;
@@ -386,7 +414,7 @@ pub async fn translate_browse_paths(
                 let mut next = Vec::new();
                 for n in &mut chunk {
                     let index = n.input_index();
-                    for el in n.results_mut().drain(..) {
+                    for el in n.take_results() {
                         next.push((el, index));
                     }
                     if n.path().is_empty() && n.unmatched_browse_name().is_none() {
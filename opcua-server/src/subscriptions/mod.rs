@@ -22,8 +22,8 @@ use opcua_types::{
     MonitoredItemCreateResult, MonitoredItemModifyRequest, MonitoringMode, NodeId,
     NotificationMessage, NumericRange, ObjectId, PublishRequest, RepublishRequest,
     RepublishResponse, ResponseHeader, SetPublishingModeRequest, SetPublishingModeResponse,
-    StatusCode, TimestampsToReturn, TransferResult, TransferSubscriptionsRequest,
-    TransferSubscriptionsResponse,
+    StatusCode, SubscriptionDiagnosticsDataType, TimestampsToReturn, TransferResult,
+    TransferSubscriptionsRequest, TransferSubscriptionsResponse,
 };
 
 use super::{
@@ -142,20 +142,27 @@ impl SubscriptionCache {
         for (session, items) in items_to_delete {
             // Create a local request context, since we need to call delete monitored items.
 
-            let (id, token) = {
+            let (id, token, roles, security_mode) = {
                 let lck = session.read();
                 let Some(token) = lck.user_token() else {
                     error!("Active session missing user token, this should be impossible");
                     continue;
                 };
 
-                (lck.session_id_numeric(), token.clone())
+                (
+                    lck.session_id_numeric(),
+                    token.clone(),
+                    lck.roles().clone(),
+                    lck.message_security_mode(),
+                )
             };
             let ctx = RequestContext {
                 session,
                 session_id: id,
                 authenticator: context.authenticator.clone(),
                 token,
+                roles,
+                security_mode,
                 current_node_manager_index: 0,
                 type_tree: context.type_tree.clone(),
                 subscriptions: context.subscriptions.clone(),
@@ -214,6 +221,7 @@ impl SubscriptionCache {
         let res = cache_lck.create_subscription(request, info)?;
         lck.subscription_to_session
             .insert(res.subscription_id, session_id);
+        info.diagnostics.on_subscription_created();
         Ok(res)
     }
 
@@ -259,7 +267,7 @@ impl SubscriptionCache {
         }) else {
             return Err(StatusCode::BadNoSubscription);
         };
-        let cache_lck = cache.lock();
+        let mut cache_lck = cache.lock();
         cache_lck.republish(request)
     }
 
@@ -638,6 +646,44 @@ impl SubscriptionCache {
         Ok(result)
     }
 
+    /// Get diagnostics for the subscriptions owned by the session with numeric ID
+    /// `session_id`, or for all subscriptions on the server if `session_id` is `None`.
+    pub(crate) fn subscription_diagnostics(
+        &self,
+        session_id: Option<u32>,
+    ) -> Vec<SubscriptionDiagnosticsDataType> {
+        let caches: Vec<_> = {
+            let lck = trace_read_lock!(self.inner);
+            match session_id {
+                Some(id) => lck
+                    .session_subscriptions
+                    .get(&id)
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+                None => lck.session_subscriptions.values().cloned().collect(),
+            }
+        };
+        caches
+            .into_iter()
+            .flat_map(|c| c.lock().diagnostics())
+            .collect()
+    }
+
+    /// Get the number of subscriptions, monitored items, and queued publish requests
+    /// on the session with numeric ID `session_id`.
+    pub(crate) fn session_subscription_counts(&self, session_id: u32) -> (u32, u32, u32) {
+        let Some(cache) = self.get_session_subscriptions(session_id) else {
+            return (0, 0, 0);
+        };
+        let cache_lck = cache.lock();
+        (
+            cache_lck.len() as u32,
+            cache_lck.monitored_item_count() as u32,
+            cache_lck.publish_request_queue_len() as u32,
+        )
+    }
+
     pub(crate) fn get_session_subscription_ids(&self, session_id: u32) -> Vec<u32> {
         let Some(cache) = ({
             let lck = trace_read_lock!(self.inner);
@@ -692,6 +738,9 @@ impl SubscriptionCache {
                     continue;
                 };
                 if session_id == *current_owner_session_id {
+                    if let Some(sub) = session_subs_lck.get_mut(*sub_id) {
+                        sub.counters_mut().transfer_request_count += 1;
+                    }
                     res.status_code = StatusCode::Good;
                     res.available_sequence_numbers =
                         session_subs_lck.available_sequence_numbers(*sub_id);
@@ -709,12 +758,22 @@ impl SubscriptionCache {
 
                 let mut session_lck = session_cache.lock();
 
+                if let Some(sub) = session_lck.get_mut(*sub_id) {
+                    sub.counters_mut().transfer_request_count += 1;
+                }
+
                 if !session_lck.user_token().is_equivalent_for_transfer(&key) {
                     res.status_code = StatusCode::BadUserAccessDenied;
                     continue;
                 }
 
-                if let (Some(sub), notifs) = session_lck.remove(*sub_id) {
+                let same_client = session_lck.user_token().application_uri == key.application_uri;
+                if let (Some(mut sub), notifs) = session_lck.remove(*sub_id) {
+                    if same_client {
+                        sub.counters_mut().transferred_to_same_client_count += 1;
+                    } else {
+                        sub.counters_mut().transferred_to_alt_client_count += 1;
+                    }
                     log::debug!(
                         "Transfer subscription {} to session {}",
                         sub.id(),
@@ -4,12 +4,17 @@ use log::error;
 use opcua_nodes::{Event, ParsedEventFilter, TypeTree};
 
 use super::MonitoredItemHandle;
-use crate::{info::ServerInfo, node_manager::ParsedReadValueId};
+use crate::{
+    address_space::NodePermissions,
+    aggregates::{IntervalAggregator, ParsedAggregateFilter},
+    info::ServerInfo,
+    node_manager::ParsedReadValueId,
+};
 use opcua_types::{
-    match_extension_object_owned, DataChangeFilter, DataValue, DateTime, EventFieldList,
-    EventFilter, EventFilterResult, ExtensionObject, MonitoredItemCreateRequest,
-    MonitoredItemModifyRequest, MonitoredItemNotification, MonitoringMode, NumericRange,
-    ParsedDataChangeFilter, StatusCode, TimestampsToReturn, Variant,
+    match_extension_object_owned, AggregateFilter, DataChangeFilter, DataValue, DateTime,
+    EventFieldList, EventFilter, ExtensionObject, MessageSecurityMode, MonitoredItemCreateRequest,
+    MonitoredItemModifyRequest, MonitoredItemNotification, MonitoringMode, NodeId, NumericRange,
+    ParsedDataChangeFilter, PermissionType, StatusCode, TimestampsToReturn, Variant,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -37,16 +42,21 @@ pub enum FilterType {
     None,
     DataChangeFilter(ParsedDataChangeFilter),
     EventFilter(ParsedEventFilter),
+    AggregateFilter(ParsedAggregateFilter),
 }
 
 impl FilterType {
     /// Try to create a filter from an extension object, returning
-    /// an `EventFilterResult` if the filter is for events.
+    /// a filter result if the filter is for events or aggregates.
+    ///
+    /// `sampling_interval` is the revised sampling interval of the monitored item,
+    /// in milliseconds.
     pub fn from_filter(
         filter: ExtensionObject,
         eu_range: Option<(f64, f64)>,
         type_tree: &dyn TypeTree,
-    ) -> (Option<EventFilterResult>, Result<FilterType, StatusCode>) {
+        sampling_interval: f64,
+    ) -> (Option<ExtensionObject>, Result<FilterType, StatusCode>) {
         // Check if the filter is a supported filter type
         if filter.is_null() {
             return (None, Ok(FilterType::None));
@@ -59,7 +69,11 @@ impl FilterType {
             },
             v: EventFilter => {
                 let (res, filter_res) = ParsedEventFilter::new(v, type_tree);
-                (Some(res), filter_res.map(FilterType::EventFilter))
+                (Some(ExtensionObject::from_message(res)), filter_res.map(FilterType::EventFilter))
+            },
+            v: AggregateFilter => {
+                let (res, filter_res) = ParsedAggregateFilter::parse(v, sampling_interval);
+                (Some(ExtensionObject::from_message(res)), filter_res.map(FilterType::AggregateFilter))
             },
             _ => {
                 error!(
@@ -90,9 +104,10 @@ pub struct CreateMonitoredItem {
     initial_value: Option<DataValue>,
     status_code: StatusCode,
     filter: FilterType,
-    filter_res: Option<EventFilterResult>,
+    filter_res: Option<ExtensionObject>,
     timestamps_to_return: TimestampsToReturn,
     eu_range: Option<(f64, f64)>,
+    permissions: Option<NodePermissions>,
 }
 
 /// Takes the requested sampling interval value supplied by client and ensures it is within
@@ -111,6 +126,13 @@ fn sanitize_sampling_interval(info: &ServerInfo, requested_sampling_interval: f6
     }
 }
 
+/// Get the sampling interval used for revising an aggregate filter, given the sanitized
+/// sampling interval. A negative interval means that the publishing interval is used,
+/// which is at least the minimum sampling interval.
+fn effective_sampling_interval(info: &ServerInfo, sampling_interval: f64) -> f64 {
+    sampling_interval.max(info.config.limits.subscriptions.min_sampling_interval_ms)
+}
+
 /// Takes the requested queue size and ensures it is within the range supported by the server
 fn sanitize_queue_size(info: &ServerInfo, requested_queue_size: usize) -> usize {
     if requested_queue_size == 0 || requested_queue_size == 1 {
@@ -146,10 +168,14 @@ impl CreateMonitoredItem {
         type_tree: &dyn TypeTree,
         eu_range: Option<(f64, f64)>,
     ) -> Self {
-        let (filter_res, filter) =
-            FilterType::from_filter(req.requested_parameters.filter, eu_range, type_tree);
         let sampling_interval =
             sanitize_sampling_interval(info, req.requested_parameters.sampling_interval);
+        let (filter_res, filter) = FilterType::from_filter(
+            req.requested_parameters.filter,
+            eu_range,
+            type_tree,
+            effective_sampling_interval(info, sampling_interval),
+        );
         let queue_size = sanitize_queue_size(info, req.requested_parameters.queue_size as usize);
 
         let (filter, mut status) = match filter {
@@ -180,6 +206,7 @@ impl CreateMonitoredItem {
             timestamps_to_return,
             filter_res,
             eu_range,
+            permissions: None,
         }
     }
 
@@ -206,6 +233,12 @@ impl CreateMonitoredItem {
         self.status_code = status;
     }
 
+    /// Set the permissions of the event notifier, checked before each event
+    /// is delivered to the monitored item.
+    pub(crate) fn set_permissions(&mut self, permissions: Option<NodePermissions>) {
+        self.permissions = permissions;
+    }
+
     /// Attribute to monitor.
     pub fn item_to_monitor(&self) -> &ParsedReadValueId {
         &self.item_to_monitor
@@ -259,7 +292,7 @@ impl CreateMonitoredItem {
         self.status_code
     }
 
-    pub(crate) fn filter_res(&self) -> Option<&EventFilterResult> {
+    pub(crate) fn filter_res(&self) -> Option<&ExtensionObject> {
         self.filter_res.as_ref()
     }
 }
@@ -280,10 +313,13 @@ pub struct MonitoredItem {
     queue_size: usize,
     notification_queue: VecDeque<Notification>,
     queue_overflow: bool,
+    queue_overflow_count: u32,
     timestamps_to_return: TimestampsToReturn,
     last_data_value: Option<DataValue>,
     any_new_notification: bool,
     eu_range: Option<(f64, f64)>,
+    aggregator: Option<IntervalAggregator>,
+    permissions: Option<NodePermissions>,
 }
 
 impl MonitoredItem {
@@ -302,12 +338,15 @@ impl MonitoredItem {
             queue_size: request.queue_size,
             notification_queue: VecDeque::new(),
             queue_overflow: false,
+            queue_overflow_count: 0,
             any_new_notification: false,
             eu_range: request.eu_range,
+            aggregator: Self::make_aggregator(&request.filter),
+            permissions: request.permissions.clone(),
         };
         if let Some(val) = request.initial_value.as_ref() {
             v.notify_data_value(val.clone());
-        } else {
+        } else if v.aggregator.is_none() {
             let now = DateTime::now();
             v.notify_data_value(DataValue {
                 value: Some(Variant::Empty),
@@ -321,6 +360,15 @@ impl MonitoredItem {
         v
     }
 
+    fn make_aggregator(filter: &FilterType) -> Option<IntervalAggregator> {
+        match filter {
+            FilterType::AggregateFilter(f) => {
+                Some(IntervalAggregator::new(f.clone(), DateTime::now()))
+            }
+            _ => None,
+        }
+    }
+
     /// Modifies the existing item with the values of the modify request. On success, the result
     /// holds the filter result.
     pub(super) fn modify(
@@ -329,19 +377,22 @@ impl MonitoredItem {
         timestamps_to_return: TimestampsToReturn,
         request: &MonitoredItemModifyRequest,
         type_tree: &dyn TypeTree,
-    ) -> (Option<EventFilterResult>, StatusCode) {
+    ) -> (Option<ExtensionObject>, StatusCode) {
         self.timestamps_to_return = timestamps_to_return;
+        let sampling_interval =
+            sanitize_sampling_interval(info, request.requested_parameters.sampling_interval);
         let (filter_res, filter) = FilterType::from_filter(
             request.requested_parameters.filter.clone(),
             self.eu_range,
             type_tree,
+            effective_sampling_interval(info, sampling_interval),
         );
         self.filter = match filter {
             Ok(f) => f,
             Err(e) => return (filter_res, e),
         };
-        self.sampling_interval =
-            sanitize_sampling_interval(info, request.requested_parameters.sampling_interval);
+        self.aggregator = Self::make_aggregator(&self.filter);
+        self.sampling_interval = sampling_interval;
         self.queue_size =
             sanitize_queue_size(info, request.requested_parameters.queue_size as usize);
         self.client_handle = request.requested_parameters.client_handle;
@@ -398,6 +449,12 @@ impl MonitoredItem {
             }
         }
 
+        // Values for aggregates are collected until the end of the processing interval.
+        if let Some(aggregator) = &mut self.aggregator {
+            aggregator.push(value, DateTime::now());
+            return false;
+        }
+
         let data_change = match (&self.last_data_value, &self.filter) {
             (Some(last_dv), FilterType::DataChangeFilter(filter)) => {
                 filter.is_changed(&value, last_dv)
@@ -414,6 +471,33 @@ impl MonitoredItem {
             return false;
         }
 
+        self.enqueue_data_value(value);
+
+        true
+    }
+
+    /// Calculate aggregates for any processing intervals that have ended, if this
+    /// monitored item has an aggregate filter. Returns `true` if any new values were enqueued.
+    pub(super) fn tick_aggregate(&mut self, now: DateTime) -> bool {
+        let is_sampling = self.is_sampling();
+        let Some(aggregator) = &mut self.aggregator else {
+            return false;
+        };
+        if !is_sampling {
+            aggregator.skip(now);
+            return false;
+        }
+        let values = aggregator.close_elapsed(now, self.queue_size);
+        if values.is_empty() {
+            return false;
+        }
+        for value in values {
+            self.enqueue_data_value(value);
+        }
+        true
+    }
+
+    fn enqueue_data_value(&mut self, mut value: DataValue) {
         self.last_data_value = Some(value.clone());
 
         match self.timestamps_to_return {
@@ -441,15 +525,26 @@ impl MonitoredItem {
             client_handle,
             value,
         });
-
-        true
     }
 
-    pub(super) fn notify_event(&mut self, event: &dyn Event) -> bool {
+    pub(super) fn notify_event(
+        &mut self,
+        event: &dyn Event,
+        roles: &[NodeId],
+        security_mode: MessageSecurityMode,
+    ) -> bool {
         if self.monitoring_mode == MonitoringMode::Disabled {
             return false;
         }
 
+        // The user may have lost access to the notifier since the item was created.
+        if self.permissions.as_ref().is_some_and(|p| {
+            p.validate(roles, security_mode, PermissionType::ReceiveEvents)
+                .is_err()
+        }) {
+            return false;
+        }
+
         let FilterType::EventFilter(filter) = &self.filter else {
             return false;
         };
@@ -480,6 +575,7 @@ impl MonitoredItem {
                 n.value.status = Some(n.value.status().set_overflow(true));
             }
             self.queue_overflow = true;
+            self.queue_overflow_count += 1;
         }
 
         self.notification_queue.push_back(notification);
@@ -506,6 +602,11 @@ impl MonitoredItem {
         ));
     }
 
+    /// Number of times the notification queue of this item has overflowed.
+    pub fn queue_overflow_count(&self) -> u32 {
+        self.queue_overflow_count
+    }
+
     /// Return `true` if this item has a stored last value.
     pub fn has_last_value(&self) -> bool {
         self.last_data_value.is_some()
@@ -637,10 +738,13 @@ pub(super) mod tests {
             queue_size: 10,
             notification_queue: Default::default(),
             queue_overflow: false,
+            queue_overflow_count: 0,
             timestamps_to_return: opcua_types::TimestampsToReturn::Both,
             last_data_value: None,
             any_new_notification: false,
             eu_range: None,
+            aggregator: None,
+            permissions: None,
         };
 
         if let Some(val) = initial_value {
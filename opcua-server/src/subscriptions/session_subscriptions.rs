@@ -18,14 +18,14 @@ use crate::{
     session::instance::Session,
     SubscriptionLimits,
 };
-use opcua_core::sync::RwLock;
+use opcua_core::{sync::RwLock, trace_read_lock};
 use opcua_types::{
     AttributeId, CreateSubscriptionRequest, CreateSubscriptionResponse, DataValue, DateTime,
     DateTimeUtc, ExtensionObject, ModifySubscriptionRequest, ModifySubscriptionResponse,
     MonitoredItemCreateResult, MonitoredItemModifyRequest, MonitoredItemModifyResult,
     MonitoringMode, NodeId, NotificationMessage, PublishRequest, PublishResponse, RepublishRequest,
     RepublishResponse, ResponseHeader, ServiceFault, SetPublishingModeRequest,
-    SetPublishingModeResponse, StatusCode, TimestampsToReturn,
+    SetPublishingModeResponse, StatusCode, SubscriptionDiagnosticsDataType, TimestampsToReturn,
 };
 
 /// Subscriptions belonging to a single session. Note that they are technically _owned_ by
@@ -44,6 +44,8 @@ pub struct SessionSubscriptions {
 
     /// Static reference to the session owning this, required to cleanly handle deletion.
     session: Arc<RwLock<Session>>,
+    /// ID of the session owning this, cached to avoid locking the session.
+    session_id: NodeId,
 }
 
 impl SessionSubscriptions {
@@ -52,6 +54,7 @@ impl SessionSubscriptions {
         user_token: PersistentSessionKey,
         session: Arc<RwLock<Session>>,
     ) -> Self {
+        let session_id = session.read().session_id().clone();
         Self {
             user_token,
             subscriptions: HashMap::new(),
@@ -59,6 +62,7 @@ impl SessionSubscriptions {
             retransmission_queue: VecDeque::new(),
             limits,
             session,
+            session_id,
         }
     }
 
@@ -193,6 +197,7 @@ impl SessionSubscriptions {
         subscription.reset_lifetime_counter();
         subscription.reset_keep_alive_counter();
         subscription.set_max_notifications_per_publish(max_notifications_per_publish);
+        subscription.counters_mut().modify_count += 1;
 
         Ok(ModifySubscriptionResponse {
             response_header: ResponseHeader::new_good(&request.request_header),
@@ -219,6 +224,12 @@ impl SessionSubscriptions {
                 Some(sub) => {
                     sub.set_publishing_enabled(request.publishing_enabled);
                     sub.reset_lifetime_counter();
+                    let counters = sub.counters_mut();
+                    if request.publishing_enabled {
+                        counters.enable_count += 1;
+                    } else {
+                        counters.disable_count += 1;
+                    }
                     StatusCode::Good
                 }
                 None => StatusCode::BadSubscriptionIdInvalid,
@@ -232,13 +243,19 @@ impl SessionSubscriptions {
     }
 
     pub(super) fn republish(
-        &self,
+        &mut self,
         request: &RepublishRequest,
     ) -> Result<RepublishResponse, StatusCode> {
-        let msg = self.find_notification_message(
-            request.subscription_id,
-            request.retransmit_sequence_number,
-        )?;
+        let msg = self
+            .find_notification_message(request.subscription_id, request.retransmit_sequence_number);
+        if let Some(sub) = self.subscriptions.get_mut(&request.subscription_id) {
+            let counters = sub.counters_mut();
+            counters.republish_request_count += 1;
+            if msg.is_ok() {
+                counters.republish_message_count += 1;
+            }
+        }
+        let msg = msg?;
         Ok(RepublishResponse {
             response_header: ResponseHeader::new_good(&request.request_header),
             notification_message: msg,
@@ -258,7 +275,7 @@ impl SessionSubscriptions {
         for item in requests {
             let filter_result = item
                 .filter_res()
-                .map(|r| ExtensionObject::from_message(r.clone()))
+                .cloned()
                 .unwrap_or_else(ExtensionObject::null);
             if item.status_code().is_good() {
                 let new_item = MonitoredItem::new(item);
@@ -300,9 +317,7 @@ impl SessionSubscriptions {
             if let Some(item) = sub.get_mut(&request.monitored_item_id) {
                 let (filter_result, status) =
                     item.modify(info, timestamps_to_return, &request, type_tree);
-                let filter_result = filter_result
-                    .map(ExtensionObject::from_message)
-                    .unwrap_or_else(ExtensionObject::null);
+                let filter_result = filter_result.unwrap_or_else(ExtensionObject::null);
 
                 results.push(MonitoredItemUpdateRef::new(
                     MonitoredItemHandle {
@@ -603,6 +618,7 @@ impl SessionSubscriptions {
             while !self.publish_request_queue.is_empty() {
                 if let Some(notification_message) = subscription.take_notification() {
                     let publish_request = self.publish_request_queue.pop_front().unwrap();
+                    subscription.counters_mut().publish_request_count += 1;
                     responses.push((publish_request, notification_message, sub_id));
                 } else {
                     break;
@@ -642,7 +658,11 @@ impl SessionSubscriptions {
             let available_sequence_numbers = self.available_sequence_numbers(subscription_id);
 
             if self.retransmission_queue.len() >= self.max_publish_requests() * 2 {
-                self.retransmission_queue.pop_front();
+                if let Some(discarded) = self.retransmission_queue.pop_front() {
+                    if let Some(sub) = self.subscriptions.get_mut(&discarded.subscription_id) {
+                        sub.counters_mut().discarded_message_count += 1;
+                    }
+                }
             }
             self.retransmission_queue.push_back(NonAckedPublish {
                 message: notification.clone(),
@@ -762,11 +782,15 @@ impl SessionSubscriptions {
     }
 
     pub(super) fn notify_events(&mut self, events: Vec<(MonitoredItemHandle, &dyn Event)>) {
+        let (roles, security_mode) = {
+            let session = trace_read_lock!(self.session);
+            (session.roles().clone(), session.message_security_mode())
+        };
         for (handle, event) in events {
             let Some(sub) = self.subscriptions.get_mut(&handle.subscription_id) else {
                 continue;
             };
-            sub.notify_event(&handle.monitored_item_id, event);
+            sub.notify_event(&handle.monitored_item_id, event, &roles, security_mode);
         }
     }
 
@@ -782,4 +806,39 @@ impl SessionSubscriptions {
     pub fn session(&self) -> &Arc<RwLock<Session>> {
         &self.session
     }
+
+    /// Get the number of publish requests currently waiting in the queue.
+    pub fn publish_request_queue_len(&self) -> usize {
+        self.publish_request_queue.len()
+    }
+
+    /// Get the total number of monitored items in all subscriptions on this session.
+    pub fn monitored_item_count(&self) -> usize {
+        self.subscriptions.values().map(|s| s.len()).sum()
+    }
+
+    /// Get diagnostics for each subscription on this session.
+    pub(super) fn diagnostics(&self) -> Vec<SubscriptionDiagnosticsDataType> {
+        self.subscriptions
+            .values()
+            .map(|sub| {
+                let unacknowledged = self
+                    .retransmission_queue
+                    .iter()
+                    .filter(|m| m.subscription_id == sub.id())
+                    .count();
+                sub.diagnostics(&self.session_id, unacknowledged as u32)
+            })
+            .collect()
+    }
+
+    /// Get the number of subscriptions on this session.
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// Return `true` if there are no subscriptions on this session.
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
 }
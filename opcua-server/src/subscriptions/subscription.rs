@@ -6,7 +6,10 @@ use std::{
 use log::{debug, trace, warn};
 use opcua_core::handle::Handle;
 use opcua_nodes::Event;
-use opcua_types::{DataValue, DateTime, DateTimeUtc, NotificationMessage, StatusCode};
+use opcua_types::{
+    DataValue, DateTime, DateTimeUtc, MessageSecurityMode, MonitoringMode, NodeId,
+    NotificationMessage, StatusCode, SubscriptionDiagnosticsDataType,
+};
 
 use super::monitored_item::{MonitoredItem, Notification};
 
@@ -88,6 +91,25 @@ pub(crate) enum HandledState {
     Closed27 = 27,
 }
 
+#[derive(Debug, Default)]
+/// Counters for events in the lifetime of a subscription, reported in the
+/// subscription diagnostics.
+pub(super) struct SubscriptionCounters {
+    pub modify_count: u32,
+    pub enable_count: u32,
+    pub disable_count: u32,
+    pub republish_request_count: u32,
+    pub republish_message_count: u32,
+    pub transfer_request_count: u32,
+    pub transferred_to_alt_client_count: u32,
+    pub transferred_to_same_client_count: u32,
+    pub publish_request_count: u32,
+    pub data_change_notifications_count: u32,
+    pub event_notifications_count: u32,
+    pub late_publish_request_count: u32,
+    pub discarded_message_count: u32,
+}
+
 #[derive(Debug)]
 /// A single subscription maintained by the server.
 pub struct Subscription {
@@ -131,6 +153,8 @@ pub struct Subscription {
     max_queued_notifications: usize,
     /// Maximum number of notifications per publish.
     max_notifications_per_publish: usize,
+    /// Diagnostic counters.
+    counters: SubscriptionCounters,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -173,6 +197,7 @@ impl Subscription {
             notifications: VecDeque::new(),
             max_queued_notifications,
             max_notifications_per_publish: max_notifications_per_publish as usize,
+            counters: SubscriptionCounters::default(),
         }
     }
 
@@ -234,9 +259,15 @@ impl Subscription {
     }
 
     /// Notify the given monitored item of a new event.
-    pub fn notify_event(&mut self, id: &u32, event: &dyn Event) {
+    pub fn notify_event(
+        &mut self,
+        id: &u32,
+        event: &dyn Event,
+        roles: &[NodeId],
+        security_mode: MessageSecurityMode,
+    ) {
         if let Some(item) = self.monitored_items.get_mut(id) {
-            if item.notify_event(event) {
+            if item.notify_event(event, roles, security_mode) {
                 self.notified_monitored_items.insert(*id);
             }
         }
@@ -399,6 +430,7 @@ impl Subscription {
             }
             HandledState::IntervalElapsed8 => {
                 self.start_publishing_timer();
+                self.counters.late_publish_request_count += 1;
                 self.state = SubscriptionState::Late;
                 UpdateStateAction::None
             }
@@ -448,6 +480,7 @@ impl Subscription {
             }
             HandledState::KeepAlive17 => {
                 self.start_publishing_timer();
+                self.counters.late_publish_request_count += 1;
                 self.state = SubscriptionState::Late;
                 UpdateStateAction::None
             }
@@ -490,6 +523,10 @@ impl Subscription {
         if matches!(tick_reason, TickReason::TickTimerFired) && !publishing_interval_elapsed {
             return TickResult::None;
         }
+        if publishing_interval_elapsed {
+            self.tick_aggregates(now);
+        }
+
         // First, get the actual state transition we're in.
         let transition = self.get_state_transition(
             tick_reason,
@@ -534,6 +571,17 @@ impl Subscription {
         }
     }
 
+    /// Calculate aggregates for monitored items with an aggregate filter
+    /// whose processing interval has ended.
+    fn tick_aggregates(&mut self, now: &DateTimeUtc) {
+        let now = DateTime::from(*now);
+        for (id, item) in self.monitored_items.iter_mut() {
+            if item.tick_aggregate(now) {
+                self.notified_monitored_items.insert(*id);
+            }
+        }
+    }
+
     fn enqueue_notification(&mut self, notification: NotificationMessage) {
         // For sanity, check the sequence number is the expected sequence number.
         let expected_sequence_number = if self.last_sequence_number == u32::MAX {
@@ -550,6 +598,7 @@ impl Subscription {
         if self.notifications.len() >= self.max_queued_notifications {
             warn!("Maximum number of queued notifications exceeded, dropping oldest. Subscription ID: {}", self.id);
             self.notifications.pop_front();
+            self.counters.discarded_message_count += 1;
         }
 
         // debug!("Enqueuing notification {:?}", notification);
@@ -593,6 +642,7 @@ impl Subscription {
                         self.sequence_number.next(),
                         std::mem::take(notifications),
                         now,
+                        &mut self.counters,
                     ));
                 }
             }
@@ -603,6 +653,7 @@ impl Subscription {
         next_sequence_number: u32,
         notifications: Vec<Notification>,
         now: &DateTimeUtc,
+        counters: &mut SubscriptionCounters,
     ) -> NotificationMessage {
         let mut data_change_notifications = Vec::new();
         let mut event_notifications = Vec::new();
@@ -613,6 +664,8 @@ impl Subscription {
                 Notification::Event(n) => event_notifications.push(n),
             }
         }
+        counters.data_change_notifications_count += data_change_notifications.len() as u32;
+        counters.event_notifications_count += event_notifications.len() as u32;
 
         NotificationMessage::data_change(
             next_sequence_number,
@@ -632,6 +685,7 @@ impl Subscription {
         notifications: &mut Vec<Notification>,
         messages: &mut Vec<NotificationMessage>,
         sequence_numbers: &mut Handle,
+        counters: &mut SubscriptionCounters,
     ) {
         if monitored_item.is_sampling() && monitored_item.has_new_notifications() {
             triggers.extend(
@@ -655,6 +709,7 @@ impl Subscription {
                             sequence_numbers.next(),
                             std::mem::take(notifications),
                             now,
+                            counters,
                         ));
                     }
                 }
@@ -683,6 +738,7 @@ impl Subscription {
                     &mut notifications,
                     &mut messages,
                     &mut self.sequence_number,
+                    &mut self.counters,
                 );
             }
         } else {
@@ -699,6 +755,7 @@ impl Subscription {
                     &mut notifications,
                     &mut messages,
                     &mut self.sequence_number,
+                    &mut self.counters,
                 );
             }
         }
@@ -710,6 +767,7 @@ impl Subscription {
                 self.sequence_number.next(),
                 notifications,
                 now,
+                &mut self.counters,
             ));
         }
 
@@ -795,6 +853,68 @@ impl Subscription {
     pub fn state(&self) -> SubscriptionState {
         self.state
     }
+
+    pub(super) fn counters_mut(&mut self) -> &mut SubscriptionCounters {
+        &mut self.counters
+    }
+
+    /// Get the current diagnostics of this subscription.
+    /// `unacknowledged_message_count` is the number of messages in the
+    /// retransmission queue, which is managed at the session level.
+    pub(super) fn diagnostics(
+        &self,
+        session_id: &NodeId,
+        unacknowledged_message_count: u32,
+    ) -> SubscriptionDiagnosticsDataType {
+        let c = &self.counters;
+        SubscriptionDiagnosticsDataType {
+            session_id: session_id.clone(),
+            subscription_id: self.id,
+            priority: self.priority,
+            publishing_interval: self.publishing_interval.as_secs_f64() * 1000.0,
+            max_keep_alive_count: self.max_keep_alive_counter,
+            max_lifetime_count: self.max_lifetime_counter,
+            max_notifications_per_publish: self.max_notifications_per_publish as u32,
+            publishing_enabled: self.publishing_enabled,
+            modify_count: c.modify_count,
+            enable_count: c.enable_count,
+            disable_count: c.disable_count,
+            republish_request_count: c.republish_request_count,
+            // Each republish request asks for a single message.
+            republish_message_request_count: c.republish_request_count,
+            republish_message_count: c.republish_message_count,
+            transfer_request_count: c.transfer_request_count,
+            transferred_to_alt_client_count: c.transferred_to_alt_client_count,
+            transferred_to_same_client_count: c.transferred_to_same_client_count,
+            publish_request_count: c.publish_request_count,
+            data_change_notifications_count: c.data_change_notifications_count,
+            event_notifications_count: c.event_notifications_count,
+            notifications_count: c.data_change_notifications_count + c.event_notifications_count,
+            late_publish_request_count: c.late_publish_request_count,
+            current_keep_alive_count: self.keep_alive_counter,
+            current_lifetime_count: self.lifetime_counter,
+            unacknowledged_message_count,
+            discarded_message_count: c.discarded_message_count,
+            monitored_item_count: self.monitored_items.len() as u32,
+            disabled_monitored_item_count: self
+                .monitored_items
+                .values()
+                .filter(|i| i.monitoring_mode() == MonitoringMode::Disabled)
+                .count() as u32,
+            monitoring_queue_overflow_count: self
+                .monitored_items
+                .values()
+                .map(|i| i.queue_overflow_count())
+                .sum(),
+            next_sequence_number: if self.last_sequence_number == u32::MAX {
+                1
+            } else {
+                self.last_sequence_number + 1
+            },
+            // Event queue overflow events are not produced.
+            event_queue_over_flow_count: 0,
+        }
+    }
 }
 
 #[cfg(test)]
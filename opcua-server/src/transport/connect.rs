@@ -1,16 +1,59 @@
 use std::{future::Future, sync::Arc};
 
+use opcua_core::{
+    comms::{secure_channel::SecureChannel, tcp_types::ErrorMessage},
+    ResponseMessage,
+};
 use opcua_types::StatusCode;
 use tokio_util::sync::CancellationToken;
 
 use crate::info::ServerInfo;
 
-use super::tcp::TcpTransport;
+use super::tcp::TransportPollResult;
+
+pub(crate) trait Connector {
+    type Transport: Transport;
 
-pub trait Connector {
     fn connect(
         self,
         info: Arc<ServerInfo>,
         token: CancellationToken,
-    ) -> impl Future<Output = Result<TcpTransport, StatusCode>> + Send + Sync;
+    ) -> impl Future<Output = Result<Self::Transport, StatusCode>> + Send + Sync;
+}
+
+/// Trait for server transports, used by the session controller to receive requests
+/// and send responses on a single connection.
+pub(crate) trait Transport: Send + 'static {
+    /// Whether the transport uses secure channels. If this is `true`, the client must
+    /// open a secure channel before the hello timeout, and renew the security token
+    /// before it expires. If not, the transport is responsible for closing idle connections.
+    const USES_SECURE_CHANNEL: bool;
+
+    /// Protocol version sent by the client when the connection was opened.
+    fn client_protocol_version(&self) -> u32;
+
+    /// Set the transport state to closing, once the final message is sent
+    /// the connection will be closed.
+    fn set_closing(&mut self);
+
+    /// Check whether the transport is closing.
+    fn is_closing(&self) -> bool;
+
+    /// Enqueue an error message, sent to the client before closing the connection.
+    fn enqueue_error(&mut self, message: ErrorMessage);
+
+    /// Enqueue a response to the request with ID `request_id`.
+    fn enqueue_message_for_send(
+        &mut self,
+        channel: &mut SecureChannel,
+        message: ResponseMessage,
+        request_id: u32,
+    ) -> Result<(), StatusCode>;
+
+    /// Poll the transport, sending outgoing messages and receiving incoming requests.
+    /// This must be cancel safe.
+    fn poll(
+        &mut self,
+        channel: &mut SecureChannel,
+    ) -> impl Future<Output = TransportPollResult> + Send;
 }
@@ -0,0 +1,493 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    io,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::CONTENT_TYPE,
+    server::conn::http1,
+    service::Service,
+    Method, Request as HttpRequest, Response as HttpResponse, StatusCode as HttpStatusCode,
+};
+use hyper_util::rt::TokioIo;
+use log::{debug, warn};
+use opcua_core::{
+    comms::{
+        https::{
+            decode_message, encode_message, OPCUA_BINARY_CONTENT_TYPE, OPCUA_SECURITY_POLICY_HEADER,
+        },
+        secure_channel::SecureChannel,
+        tcp_codec::AsyncStream,
+        tcp_types::ErrorMessage,
+        url::HTTPS_SCHEME,
+    },
+    Message, RequestMessage, ResponseMessage,
+};
+use opcua_crypto::SecurityPolicy;
+use opcua_types::{MessageSecurityMode, ResponseHeader, ServiceFault, StatusCode};
+use tokio::sync::{mpsc, oneshot};
+use tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
+use tokio_util::sync::CancellationToken;
+
+use crate::info::ServerInfo;
+
+use super::{
+    connect::{Connector, Transport},
+    tcp::{Request, TransportPollResult},
+    Listener,
+};
+
+/// Time an HTTPS connection may be idle before it is closed by the server.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time a client has to complete the TLS handshake after connecting.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type BoxedStream = Box<dyn AsyncStream>;
+
+type Handshake<A> = BoxFuture<'static, (io::Result<BoxedStream>, A)>;
+
+type HttpResult = Result<Bytes, HttpStatusCode>;
+
+/// Listener for OPC-UA binary over HTTPS, using `https` endpoint URLs.
+///
+/// This wraps another listener, and serves the HTTPS transport mapping on each
+/// accepted connection. Each request is a POST with an `application/opcua+uabinary`
+/// body containing a single service request. There are no secure channels, message
+/// security is provided by TLS. Use [`HttpsListener::with_tls`] to terminate TLS in
+/// the listener. If TLS is terminated elsewhere, for example by a proxy in front of the
+/// server, the inner listener must report this through [`Listener::terminates_tls`].
+///
+/// The security policy used to sign and validate sessions is given by the
+/// `OPCUA-SecurityPolicy` HTTP header. Endpoints with a security policy other than
+/// `None` must use the `SignAndEncrypt` security mode, and are only usable over TLS.
+/// Requests with such a policy on connections without TLS are rejected with
+/// `BadSecurityModeRejected`.
+///
+/// Endpoints are advertised with `https` URLs when the server is run with this listener.
+pub struct HttpsListener<L: Listener> {
+    inner: L,
+    tls: Option<TlsAcceptor>,
+    /// TLS handshakes in progress on accepted connections.
+    handshakes: FuturesUnordered<Handshake<L::Addr>>,
+}
+
+impl<L: Listener> HttpsListener<L> {
+    /// Create a new HTTPS listener, accepting connections from `inner`. TLS
+    /// is not terminated by this listener.
+    pub fn new(inner: L) -> Self {
+        Self {
+            inner,
+            tls: None,
+            handshakes: FuturesUnordered::new(),
+        }
+    }
+
+    /// Create a new HTTPS listener, accepting connections from `inner`, and securing
+    /// them with TLS using `config`.
+    pub fn with_tls(inner: L, config: Arc<ServerConfig>) -> Self {
+        Self {
+            inner,
+            tls: Some(TlsAcceptor::from(config)),
+            handshakes: FuturesUnordered::new(),
+        }
+    }
+}
+
+impl<L: Listener> Listener for HttpsListener<L>
+where
+    L::Addr: 'static,
+{
+    type Stream = BoxedStream;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> io::Result<(Self::Stream, Self::Addr)> {
+        let Some(tls) = &self.tls else {
+            let (stream, addr) = self.inner.accept().await?;
+            return Ok((Box::new(stream), addr));
+        };
+        // Handshakes are kept in the listener, so that a slow client does not keep
+        // others from connecting, and so that this is cancel safe.
+        loop {
+            tokio::select! {
+                r = self.inner.accept() => {
+                    let (stream, addr) = r?;
+                    let tls = tls.clone();
+                    self.handshakes.push(Box::pin(async move {
+                        let res = tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls.accept(stream))
+                            .await
+                            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()));
+                        (res.map(|s| Box::new(s) as BoxedStream), addr)
+                    }));
+                }
+                Some((res, addr)) = self.handshakes.next(), if !self.handshakes.is_empty() => {
+                    match res {
+                        Ok(stream) => return Ok((stream, addr)),
+                        Err(e) => warn!("TLS handshake with {addr:?} failed: {e}"),
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        self.inner.local_port()
+    }
+
+    fn url_scheme(&self) -> &'static str {
+        HTTPS_SCHEME
+    }
+
+    fn terminates_tls(&self) -> bool {
+        self.tls.is_some() || self.inner.terminates_tls()
+    }
+}
+
+/// Request received by the HTTP server, waiting to be handled by the transport.
+struct IncomingRequest {
+    security_policy: Option<String>,
+    body: Bytes,
+    response: oneshot::Sender<HttpResult>,
+}
+
+/// HTTP service forwarding OPC-UA requests to the transport.
+struct HttpsService {
+    requests: mpsc::Sender<IncomingRequest>,
+    max_message_size: usize,
+}
+
+impl HttpsService {
+    async fn handle(
+        request: HttpRequest<Incoming>,
+        requests: mpsc::Sender<IncomingRequest>,
+        max_message_size: usize,
+    ) -> HttpResult {
+        if request.method() != Method::POST {
+            return Err(HttpStatusCode::METHOD_NOT_ALLOWED);
+        }
+        if request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_none_or(|v| v.trim() != OPCUA_BINARY_CONTENT_TYPE)
+        {
+            return Err(HttpStatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+        let security_policy = request
+            .headers()
+            .get(OPCUA_SECURITY_POLICY_HEADER)
+            .map(|v| v.to_str().map(|v| v.trim().to_owned()))
+            .transpose()
+            .map_err(|_| HttpStatusCode::BAD_REQUEST)?;
+
+        let limit = if max_message_size == 0 {
+            usize::MAX
+        } else {
+            max_message_size
+        };
+        let body = Limited::new(request.into_body(), limit)
+            .collect()
+            .await
+            .map_err(|_| HttpStatusCode::PAYLOAD_TOO_LARGE)?
+            .to_bytes();
+
+        let (send, recv) = oneshot::channel();
+        requests
+            .send(IncomingRequest {
+                security_policy,
+                body,
+                response: send,
+            })
+            .await
+            .map_err(|_| HttpStatusCode::SERVICE_UNAVAILABLE)?;
+        recv.await
+            .unwrap_or(Err(HttpStatusCode::SERVICE_UNAVAILABLE))
+    }
+}
+
+impl Service<HttpRequest<Incoming>> for HttpsService {
+    type Response = HttpResponse<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn call(&self, request: HttpRequest<Incoming>) -> Self::Future {
+        let requests = self.requests.clone();
+        let max_message_size = self.max_message_size;
+        Box::pin(async move {
+            let mut response = HttpResponse::builder();
+            let body = match Self::handle(request, requests, max_message_size).await {
+                Ok(body) => {
+                    response = response.header(CONTENT_TYPE, OPCUA_BINARY_CONTENT_TYPE);
+                    body
+                }
+                Err(status) => {
+                    response = response.status(status);
+                    Bytes::new()
+                }
+            };
+            // Cannot fail, the status and headers are always valid.
+            Ok(response.body(Full::new(body)).unwrap())
+        })
+    }
+}
+
+/// Connector for incoming HTTPS connections.
+pub(crate) struct HttpsConnector {
+    stream: BoxedStream,
+    max_message_size: usize,
+    tls: bool,
+}
+
+impl HttpsConnector {
+    /// Create a connector for `stream`. `tls` is whether the stream is known to be
+    /// secured with TLS.
+    pub fn new(stream: impl AsyncStream, max_message_size: usize, tls: bool) -> Self {
+        Self {
+            stream: Box::new(stream),
+            max_message_size,
+            tls,
+        }
+    }
+}
+
+impl Connector for HttpsConnector {
+    type Transport = HttpsTransport;
+
+    async fn connect(
+        self,
+        info: Arc<ServerInfo>,
+        _token: CancellationToken,
+    ) -> Result<HttpsTransport, StatusCode> {
+        // There is no handshake beyond what is done by TLS, which happens before
+        // the stream reaches the server, so the transport is ready right away.
+        let (send, recv) = mpsc::channel(1);
+        let service = HttpsService {
+            requests: send,
+            max_message_size: self.max_message_size,
+        };
+        let connection = http1::Builder::new().serve_connection(TokioIo::new(self.stream), service);
+        Ok(HttpsTransport {
+            connection: Box::pin(connection),
+            requests: recv,
+            pending: HashMap::new(),
+            next_request_id: 1,
+            secure_channel_id: info.secure_channel_id_handle.next(),
+            max_message_size: self.max_message_size,
+            tls: self.tls,
+            idle_deadline: Instant::now() + KEEP_ALIVE_TIMEOUT,
+            closing: false,
+            closed: false,
+        })
+    }
+}
+
+/// Transport implementation for the OPC-UA HTTPS transport mapping.
+///
+/// Each HTTP request contains a single service request, the response is
+/// sent as the body of the HTTP response.
+pub(crate) struct HttpsTransport {
+    connection: Pin<Box<http1::Connection<TokioIo<BoxedStream>, HttpsService>>>,
+    requests: mpsc::Receiver<IncomingRequest>,
+    /// Requests waiting for a response, by request ID.
+    pending: HashMap<u32, oneshot::Sender<HttpResult>>,
+    next_request_id: u32,
+    /// Secure channel ID assigned to this connection. There is no actual secure
+    /// channel, but sessions still need an ID to refer to.
+    secure_channel_id: u32,
+    max_message_size: usize,
+    /// Whether the connection is secured with TLS.
+    tls: bool,
+    idle_deadline: Instant,
+    closing: bool,
+    closed: bool,
+}
+
+impl HttpsTransport {
+    /// Decode an incoming request and prepare the channel for it. Returns `None` if the request
+    /// is invalid or was handled by the transport.
+    fn process_request(
+        &mut self,
+        request: IncomingRequest,
+        channel: &mut SecureChannel,
+    ) -> Option<Request> {
+        let message: RequestMessage = {
+            let ctx_r = channel.context();
+            match decode_message(&request.body, &ctx_r.context()) {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!("Failed to decode HTTPS request: {e}");
+                    let _ = request.response.send(Err(HttpStatusCode::BAD_REQUEST));
+                    return None;
+                }
+            }
+        };
+
+        let request_id = self.next_request_id;
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+        self.pending.insert(request_id, request.response);
+
+        let security_policy = match &request.security_policy {
+            Some(uri) => SecurityPolicy::from_uri(uri),
+            None => SecurityPolicy::None,
+        };
+        let error = match &message {
+            RequestMessage::OpenSecureChannel(_) | RequestMessage::CloseSecureChannel(_) => {
+                Some(StatusCode::BadServiceUnsupported)
+            }
+            _ if security_policy == SecurityPolicy::Unknown => {
+                Some(StatusCode::BadSecurityPolicyRejected)
+            }
+            // Without TLS the messages are neither signed nor encrypted.
+            _ if security_policy != SecurityPolicy::None && !self.tls => {
+                Some(StatusCode::BadSecurityModeRejected)
+            }
+            _ => None,
+        };
+        if let Some(error) = error {
+            let fault = ServiceFault::new(message.request_header(), error).into();
+            if let Err(e) = self.enqueue_message_for_send(channel, fault, request_id) {
+                warn!("Failed to send HTTPS response: {e}");
+            }
+            return None;
+        }
+
+        // Message security is handled by TLS, the security policy is only used
+        // for session signatures.
+        channel.set_security_policy(security_policy);
+        channel.set_security_mode(if security_policy == SecurityPolicy::None {
+            MessageSecurityMode::None
+        } else {
+            MessageSecurityMode::SignAndEncrypt
+        });
+        channel.set_secure_channel_id(self.secure_channel_id);
+
+        Some(Request {
+            message,
+            chunk_info: None,
+            request_id,
+        })
+    }
+
+    fn encode_response(
+        &self,
+        channel: &SecureChannel,
+        message: &ResponseMessage,
+    ) -> Result<Bytes, StatusCode> {
+        let ctx_r = channel.context();
+        let body = encode_message(message, &ctx_r.context()).map_err(|e| {
+            warn!("Failed to encode outgoing message: {e:?}");
+            e.status()
+        })?;
+        if self.max_message_size > 0 && body.len() > self.max_message_size {
+            warn!(
+                "Max message size is {} and message {} exceeds that",
+                self.max_message_size,
+                body.len()
+            );
+            return Err(StatusCode::BadResponseTooLarge);
+        }
+        Ok(body.into())
+    }
+}
+
+impl Transport for HttpsTransport {
+    const USES_SECURE_CHANNEL: bool = false;
+
+    fn client_protocol_version(&self) -> u32 {
+        0
+    }
+
+    fn set_closing(&mut self) {
+        if !self.closing {
+            self.closing = true;
+            // Fail any requests still waiting for a response, then let
+            // the HTTP connection finish any responses already sent.
+            self.pending.clear();
+            self.connection.as_mut().graceful_shutdown();
+        }
+    }
+
+    fn is_closing(&self) -> bool {
+        self.closing
+    }
+
+    fn enqueue_error(&mut self, message: ErrorMessage) {
+        // There are no error messages in the HTTPS mapping, pending requests
+        // get an HTTP error once the transport is closed.
+        debug!(
+            "Closing HTTPS connection with error {}: {}",
+            message.error, message.reason
+        );
+    }
+
+    fn enqueue_message_for_send(
+        &mut self,
+        channel: &mut SecureChannel,
+        message: ResponseMessage,
+        request_id: u32,
+    ) -> Result<(), StatusCode> {
+        // The request may have been canceled by the client, or failed when closing.
+        let Some(response) = self.pending.remove(&request_id) else {
+            return Ok(());
+        };
+        let body = match self.encode_response(channel, &message) {
+            Ok(body) => body,
+            Err(e) => {
+                let fault: ResponseMessage = ServiceFault {
+                    response_header: ResponseHeader::new_service_result(
+                        message.request_handle(),
+                        e,
+                    ),
+                }
+                .into();
+                self.encode_response(channel, &fault)?
+            }
+        };
+        let _ = response.send(Ok(body));
+        self.idle_deadline = Instant::now() + KEEP_ALIVE_TIMEOUT;
+        Ok(())
+    }
+
+    async fn poll(&mut self, channel: &mut SecureChannel) -> TransportPollResult {
+        loop {
+            if self.closed {
+                return TransportPollResult::Closed;
+            }
+            let idle = self.pending.is_empty() && !self.closing;
+            // All branches are cancel safe, `connection` is only polled by reference.
+            tokio::select! {
+                r = self.connection.as_mut() => {
+                    if let Err(e) = r {
+                        debug!("HTTPS connection closed with error: {e}");
+                    }
+                    self.closed = true;
+                }
+                request = self.requests.recv(), if !self.closing => {
+                    let Some(request) = request else {
+                        self.closed = true;
+                        continue;
+                    };
+                    self.idle_deadline = Instant::now() + KEEP_ALIVE_TIMEOUT;
+                    if let Some(request) = self.process_request(request, channel) {
+                        return TransportPollResult::IncomingMessage(request);
+                    }
+                }
+                _ = tokio::time::sleep_until(self.idle_deadline.into()), if idle => {
+                    debug!("Closing idle HTTPS connection");
+                    self.set_closing();
+                }
+            }
+        }
+    }
+}
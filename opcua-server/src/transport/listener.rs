@@ -0,0 +1,98 @@
+use std::{fmt::Debug, future::Future, io};
+
+use opcua_core::comms::{tcp_codec::AsyncStream, url::OPC_TCP_SCHEME};
+use tokio::{net::TcpListener, sync::mpsc::Receiver};
+
+/// Trait for listeners accepting incoming connections to the server.
+///
+/// The server runs the OPC-UA binary protocol on each accepted stream, so this
+/// can be used to serve over something other than plain TCP, like
+/// Unix domain sockets or TLS-wrapped streams.
+pub trait Listener: Send {
+    /// Type of the stream returned for each accepted connection.
+    type Stream: AsyncStream;
+    /// Address of a connection, used for logging.
+    type Addr: Debug + Send;
+
+    /// Wait for the next incoming connection.
+    ///
+    /// This must be cancel safe, the server may drop the returned
+    /// future and call `accept` again.
+    fn accept(&mut self) -> impl Future<Output = io::Result<(Self::Stream, Self::Addr)>> + Send;
+
+    /// Get the local address the listener is bound to.
+    fn local_addr(&self) -> io::Result<Self::Addr>;
+
+    /// Get the local TCP port the listener is bound to, if any.
+    /// If this returns a value, it is used in place of the configured port
+    /// when creating endpoint descriptions.
+    fn local_port(&self) -> Option<u16> {
+        None
+    }
+
+    /// Get the URL scheme of connections accepted by this listener.
+    /// This is used to create endpoint URLs, and defaults to `opc.tcp`.
+    fn url_scheme(&self) -> &'static str {
+        OPC_TCP_SCHEME
+    }
+
+    /// Get whether accepted streams are secured with TLS by the listener itself.
+    ///
+    /// Transports without secure channels, like HTTPS, rely on TLS for message security,
+    /// and only let clients use security policies other than `None` if this is `true`.
+    /// Defaults to `false`.
+    fn terminates_tls(&self) -> bool {
+        false
+    }
+}
+
+impl Listener for TcpListener {
+    type Stream = tokio::net::TcpStream;
+    type Addr = std::net::SocketAddr;
+
+    async fn accept(&mut self) -> io::Result<(Self::Stream, Self::Addr)> {
+        TcpListener::accept(self).await
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        TcpListener::local_addr(self)
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        TcpListener::local_addr(self).ok().map(|a| a.port())
+    }
+}
+
+#[cfg(unix)]
+impl Listener for tokio::net::UnixListener {
+    type Stream = tokio::net::UnixStream;
+    type Addr = tokio::net::unix::SocketAddr;
+
+    async fn accept(&mut self) -> io::Result<(Self::Stream, Self::Addr)> {
+        tokio::net::UnixListener::accept(self).await
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        tokio::net::UnixListener::local_addr(self)
+    }
+}
+
+/// A channel receiving streams can be used as a listener, for example
+/// to run a server over in-memory [`tokio::io::duplex`] streams.
+///
+/// Once all senders are dropped, the listener stops accepting connections.
+impl<S: AsyncStream> Listener for Receiver<S> {
+    type Stream = S;
+    type Addr = ();
+
+    async fn accept(&mut self) -> io::Result<(Self::Stream, Self::Addr)> {
+        match self.recv().await {
+            Some(stream) => Ok((stream, ())),
+            None => futures::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(())
+    }
+}
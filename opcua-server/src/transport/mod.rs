@@ -1,3 +1,18 @@
 mod connect;
+#[cfg(feature = "https")]
+mod https;
+mod listener;
+mod reverse;
 pub mod tcp;
-pub use connect::Connector;
+#[cfg(feature = "websocket")]
+mod websocket;
+pub(crate) use connect::Connector;
+pub(crate) use connect::Transport;
+#[cfg(feature = "https")]
+pub(crate) use https::HttpsConnector;
+#[cfg(feature = "https")]
+pub use https::HttpsListener;
+pub use listener::Listener;
+pub(crate) use reverse::reverse_connect;
+#[cfg(feature = "websocket")]
+pub use websocket::WebSocketListener;
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use log::{debug, error};
+use opcua_core::comms::{tcp_types::ReverseHelloMessage, url::hostname_port_from_url};
+use opcua_types::{SimpleBinaryEncodable, StatusCode};
+use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+/// Open a reverse connection to the client listening on `client_url`.
+///
+/// This sends a `ReverseHello` message identifying the server, then waits
+/// for the client to start using the connection by sending its `HELLO`. The
+/// returned stream is then handled like any other incoming connection.
+pub(crate) async fn reverse_connect(
+    client_url: &str,
+    server_uri: &str,
+    endpoint_url: &str,
+    hello_timeout: Duration,
+) -> Result<TcpStream, StatusCode> {
+    let (host, port) = hostname_port_from_url(
+        client_url,
+        opcua_core::constants::DEFAULT_OPC_UA_SERVER_PORT,
+    )?;
+
+    let mut stream = TcpStream::connect((host.as_str(), port))
+        .await
+        .map_err(|e| {
+            debug!("Failed to open reverse connection to {client_url}: {e}");
+            StatusCode::BadCommunicationError
+        })?;
+
+    let reverse_hello = ReverseHelloMessage::new(server_uri, endpoint_url);
+    stream
+        .write_all(&reverse_hello.encode_to_vec())
+        .await
+        .map_err(|e| {
+            error!("Failed to send ReverseHello to {client_url}: {e}");
+            StatusCode::BadCommunicationError
+        })?;
+
+    // Wait for the client to send something. The HELLO itself is
+    // left in the socket, to be read by the regular connection logic.
+    let mut buf = [0u8; 1];
+    match tokio::time::timeout(hello_timeout, stream.peek(&mut buf)).await {
+        Ok(Ok(n)) if n > 0 => Ok(stream),
+        Ok(Ok(_)) => {
+            debug!("Reverse connection to {client_url} was closed by the client");
+            Err(StatusCode::BadConnectionClosed)
+        }
+        Ok(Err(e)) => {
+            debug!("Reverse connection to {client_url} failed: {e}");
+            Err(StatusCode::BadCommunicationError)
+        }
+        Err(_) => Err(StatusCode::BadTimeout),
+    }
+}
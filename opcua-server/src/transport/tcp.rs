@@ -11,7 +11,7 @@ use opcua_core::{
         message_chunk::{MessageChunk, MessageIsFinalType},
         message_chunk_info::ChunkInfo,
         secure_channel::SecureChannel,
-        tcp_codec::{Message, TcpCodec},
+        tcp_codec::{AsyncStream, Message, TcpCodec},
         tcp_types::{AcknowledgeMessage, ErrorMessage},
     },
     RequestMessage, ResponseMessage,
@@ -21,23 +21,22 @@ use crate::info::ServerInfo;
 use opcua_types::{DecodingOptions, Error, ResponseHeader, ServiceFault, StatusCode};
 
 use futures::StreamExt;
-use tokio::{
-    io::{AsyncWriteExt, ReadHalf, WriteHalf},
-    net::TcpStream,
-};
+use tokio::io::{AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio_util::{codec::FramedRead, sync::CancellationToken};
 
-use super::connect::Connector;
+use super::connect::{Connector, Transport};
+
+type BoxedStream = Box<dyn AsyncStream>;
 
-/// Transport implementation for opc.tcp.
+/// Transport implementation for the OPC-UA binary protocol, over TCP or any other stream.
 pub(crate) struct TcpTransport {
-    read: FramedRead<ReadHalf<TcpStream>, TcpCodec>,
-    write: WriteHalf<TcpStream>,
+    read: FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+    write: WriteHalf<BoxedStream>,
     send_buffer: SendBuffer,
     state: TransportState,
     pending_chunks: Vec<MessageChunk>,
     /// Client protocol version set during HELLO
-    pub(crate) client_protocol_version: u32,
+    client_protocol_version: u32,
     /// Last decoded sequence number
     last_received_sequence_number: u32,
 }
@@ -59,12 +58,14 @@ pub(crate) struct TransportConfig {
 #[derive(Debug)]
 pub(crate) struct Request {
     pub message: RequestMessage,
-    pub chunk_info: ChunkInfo,
+    /// Chunk info of the first chunk of the request, not set for transports
+    /// that do not split messages into chunks.
+    pub chunk_info: Option<ChunkInfo>,
     pub request_id: u32,
 }
 
 #[derive(Debug)]
-/// Result of polling a TCP transport.
+/// Result of polling a transport.
 pub(crate) enum TransportPollResult {
     OutgoingMessageSent,
     IncomingChunk,
@@ -85,8 +86,8 @@ fn min_zero_infinite(server: u32, client: u32) -> u32 {
 }
 
 pub struct TcpConnector {
-    read: FramedRead<ReadHalf<TcpStream>, TcpCodec>,
-    write: WriteHalf<TcpStream>,
+    read: FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+    write: WriteHalf<BoxedStream>,
     deadline: Instant,
     config: TransportConfig,
     decoding_options: DecodingOptions,
@@ -94,11 +95,11 @@ pub struct TcpConnector {
 
 impl TcpConnector {
     pub fn new(
-        stream: TcpStream,
+        stream: impl AsyncStream,
         config: TransportConfig,
         decoding_options: DecodingOptions,
     ) -> Self {
-        let (read, write) = tokio::io::split(stream);
+        let (read, write) = tokio::io::split(Box::new(stream) as BoxedStream);
         let read = FramedRead::new(read, TcpCodec::new(decoding_options.clone()));
         TcpConnector {
             read,
@@ -198,6 +199,8 @@ impl TcpConnector {
 }
 
 impl Connector for TcpConnector {
+    type Transport = TcpTransport;
+
     async fn connect(
         mut self,
         info: Arc<ServerInfo>,
@@ -229,38 +232,26 @@ impl Connector for TcpConnector {
     }
 }
 
-impl TcpTransport {
-    pub fn new(
-        read: FramedRead<ReadHalf<TcpStream>, TcpCodec>,
-        write: WriteHalf<TcpStream>,
-        send_buffer: SendBuffer,
-    ) -> Self {
-        Self {
-            read,
-            write,
-            state: TransportState::Running,
-            pending_chunks: Vec::new(),
-            last_received_sequence_number: 0,
-            client_protocol_version: 0,
-            send_buffer,
-        }
+impl Transport for TcpTransport {
+    const USES_SECURE_CHANNEL: bool = true;
+
+    fn client_protocol_version(&self) -> u32 {
+        self.client_protocol_version
     }
 
-    /// Set the transport state to closing, once the final message is sent
-    /// the connection will be closed.
-    pub fn set_closing(&mut self) {
+    fn set_closing(&mut self) {
         self.state = TransportState::Closing;
     }
 
-    pub fn is_closing(&self) -> bool {
+    fn is_closing(&self) -> bool {
         matches!(self.state, TransportState::Closing)
     }
 
-    pub fn enqueue_error(&mut self, message: ErrorMessage) {
+    fn enqueue_error(&mut self, message: ErrorMessage) {
         self.send_buffer.write_error(message);
     }
 
-    pub fn enqueue_message_for_send(
+    fn enqueue_message_for_send(
         &mut self,
         channel: &mut SecureChannel,
         message: ResponseMessage,
@@ -289,7 +280,7 @@ impl TcpTransport {
         }
     }
 
-    pub async fn poll(&mut self, channel: &mut SecureChannel) -> TransportPollResult {
+    async fn poll(&mut self, channel: &mut SecureChannel) -> TransportPollResult {
         // Either we've got something in the send buffer, which we can send,
         // or we're waiting for more outgoing messages.
         // We won't wait for outgoing messages while sending, since that
@@ -327,6 +318,24 @@ impl TcpTransport {
             self.handle_incoming_message(incoming, channel)
         }
     }
+}
+
+impl TcpTransport {
+    pub fn new(
+        read: FramedRead<ReadHalf<BoxedStream>, TcpCodec>,
+        write: WriteHalf<BoxedStream>,
+        send_buffer: SendBuffer,
+    ) -> Self {
+        Self {
+            read,
+            write,
+            state: TransportState::Running,
+            pending_chunks: Vec::new(),
+            last_received_sequence_number: 0,
+            client_protocol_version: 0,
+            send_buffer,
+        }
+    }
 
     fn handle_incoming_message(
         &mut self,
@@ -398,7 +407,7 @@ impl TcpTransport {
                         .map_err(|e| e.with_request_id(chunk_info.sequence_header.request_id))?;
                     Ok(Some(Request {
                         request_id: chunk_info.sequence_header.request_id,
-                        chunk_info,
+                        chunk_info: Some(chunk_info),
                         message: request,
                     }))
                 }
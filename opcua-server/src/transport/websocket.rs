@@ -0,0 +1,124 @@
+use std::{io, sync::Arc, time::Duration};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use log::warn;
+use opcua_core::comms::{
+    tcp_codec::AsyncStream, url::OPC_WSS_SCHEME, websocket::WebSocketChunkStream,
+};
+use tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
+
+use super::Listener;
+
+/// Time to wait for a client to complete the TLS and WebSocket handshakes.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type BoxedStream = Box<dyn AsyncStream>;
+
+type PendingHandshake<A> = BoxFuture<'static, (io::Result<WebSocketChunkStream<BoxedStream>>, A)>;
+
+/// Listener for OPC-UA over WebSockets, using `opc.wss` endpoint URLs.
+///
+/// This wraps another listener, and performs the WebSocket handshake on each
+/// accepted connection, requiring the `opcua+uacp` subprotocol. Use
+/// [`WebSocketListener::with_tls`] to terminate TLS in the listener, as `opc.wss`
+/// requires. A listener created with [`WebSocketListener::new`] does not terminate
+/// TLS, which is only appropriate if TLS is terminated elsewhere, for example by a
+/// proxy in front of the server. In that case the inner listener should report this
+/// through [`Listener::terminates_tls`].
+///
+/// Messages are still secured by the OPC-UA secure channel, so all security
+/// policies are available regardless of TLS.
+///
+/// Endpoints are advertised with `opc.wss` URLs when the server is run with this listener.
+pub struct WebSocketListener<L: Listener> {
+    inner: L,
+    tls: Option<TlsAcceptor>,
+    pending: FuturesUnordered<PendingHandshake<L::Addr>>,
+}
+
+impl<L: Listener> WebSocketListener<L>
+where
+    L::Addr: 'static,
+{
+    /// Create a new WebSocket listener, accepting connections from `inner`. TLS
+    /// is not terminated by this listener.
+    pub fn new(inner: L) -> Self {
+        Self {
+            inner,
+            tls: None,
+            pending: FuturesUnordered::new(),
+        }
+    }
+
+    /// Create a new WebSocket listener, accepting connections from `inner`, and securing
+    /// them with TLS using `config`.
+    pub fn with_tls(inner: L, config: Arc<ServerConfig>) -> Self {
+        Self {
+            inner,
+            tls: Some(TlsAcceptor::from(config)),
+            pending: FuturesUnordered::new(),
+        }
+    }
+}
+
+impl<L: Listener> Listener for WebSocketListener<L>
+where
+    L::Addr: 'static,
+{
+    type Stream = WebSocketChunkStream<BoxedStream>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> io::Result<(Self::Stream, Self::Addr)> {
+        // Handshakes run concurrently, so that a slow client cannot block others.
+        // Both `accept` on the inner listener and `next` on `pending` are
+        // cancel safe, so this is as well.
+        loop {
+            tokio::select! {
+                r = self.inner.accept() => {
+                    let (stream, addr) = r?;
+                    let tls = self.tls.clone();
+                    let fut = async move {
+                        let handshake = async move {
+                            let stream: BoxedStream = match tls {
+                                Some(tls) => Box::new(tls.accept(stream).await?),
+                                None => Box::new(stream),
+                            };
+                            WebSocketChunkStream::accept(stream).await
+                        };
+                        let r = match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                            Ok(r) => r,
+                            Err(_) => Err(io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                "Timeout waiting for WebSocket handshake",
+                            )),
+                        };
+                        (r, addr)
+                    };
+                    self.pending.push(fut.boxed());
+                }
+                Some((r, addr)) = self.pending.next(), if !self.pending.is_empty() => {
+                    match r {
+                        Ok(stream) => return Ok((stream, addr)),
+                        Err(e) => warn!("WebSocket handshake with {addr:?} failed: {e}"),
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+
+    fn local_port(&self) -> Option<u16> {
+        self.inner.local_port()
+    }
+
+    fn url_scheme(&self) -> &'static str {
+        OPC_WSS_SCHEME
+    }
+
+    fn terminates_tls(&self) -> bool {
+        self.tls.is_some() || self.inner.terminates_tls()
+    }
+}
@@ -126,27 +126,38 @@ pub struct EncodingIds {
 }
 
 #[derive(Debug)]
+/// Parsed info about a type that is neither a structure nor an enum.
 pub struct GenericTypeInfo {
+    /// Whether this type is abstract.
     pub is_abstract: bool,
 }
 
 impl GenericTypeInfo {
+    /// Create a new generic type info.
     pub fn new(is_abstract: bool) -> Self {
         Self { is_abstract }
     }
 }
 
 #[derive(Debug)]
+/// Parsed info about a data type.
 pub enum TypeInfo {
+    /// An enum type.
     Enum(Arc<EnumTypeInfo>),
+    /// A structure type.
     Struct(Arc<StructTypeInfo>),
+    /// Some other type.
     Primitive(Arc<GenericTypeInfo>),
 }
 
 #[derive(Debug)]
+/// Reference to parsed info about a data type.
 pub enum TypeInfoRef<'a> {
+    /// An enum type.
     Enum(&'a Arc<EnumTypeInfo>),
+    /// A structure type.
     Struct(&'a Arc<StructTypeInfo>),
+    /// Some other type.
     Primitive(&'a Arc<GenericTypeInfo>),
 }
 
@@ -246,6 +257,8 @@ impl ParentIds {
 }
 
 impl TypeInfo {
+    /// Parse type info from a data type definition. `encoding_ids` is required
+    /// for structure types.
     pub fn from_type_definition(
         value: DataTypeDefinition,
         encoding_ids: Option<EncodingIds>,
@@ -326,12 +339,18 @@ impl DataTypeTree {
                 self.enum_types.insert(id.clone(), arc);
             }
             TypeInfo::Struct(arc) => {
-                self.encoding_to_data_type
-                    .insert(arc.encoding_ids.binary_id.clone(), id.clone());
-                self.encoding_to_data_type
-                    .insert(arc.encoding_ids.json_id.clone(), id.clone());
-                self.encoding_to_data_type
-                    .insert(arc.encoding_ids.xml_id.clone(), id.clone());
+                for encoding_id in [
+                    &arc.encoding_ids.binary_id,
+                    &arc.encoding_ids.json_id,
+                    &arc.encoding_ids.xml_id,
+                ] {
+                    // Abstract types or types with missing encodings may have null
+                    // encoding IDs, which should never be mapped.
+                    if !encoding_id.is_null() {
+                        self.encoding_to_data_type
+                            .insert(encoding_id.clone(), id.clone());
+                    }
+                }
                 self.struct_types.insert(id.clone(), arc);
             }
             TypeInfo::Primitive(arc) => {
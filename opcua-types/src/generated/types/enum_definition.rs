@@ -19,3 +19,17 @@ mod opcua {
 pub struct EnumDefinition {
     pub fields: Option<Vec<super::enum_field::EnumField>>,
 }
+impl opcua::types::MessageInfo for EnumDefinition {
+    fn type_id(&self) -> opcua::types::ObjectId {
+        opcua::types::ObjectId::EnumDefinition_Encoding_DefaultBinary
+    }
+    fn json_type_id(&self) -> opcua::types::ObjectId {
+        opcua::types::ObjectId::EnumDefinition_Encoding_DefaultJson
+    }
+    fn xml_type_id(&self) -> opcua::types::ObjectId {
+        opcua::types::ObjectId::EnumDefinition_Encoding_DefaultXml
+    }
+    fn data_type_id(&self) -> opcua::types::DataTypeId {
+        opcua::types::DataTypeId::EnumDefinition
+    }
+}
@@ -1050,6 +1050,11 @@ static TYPES: std::sync::LazyLock<opcua::types::TypeLoaderInstance> =
                 crate::ObjectId::EndpointUrlListDataType_Encoding_DefaultBinary as u32,
                 opcua::types::binary_decode_to_enc::<EndpointUrlListDataType>,
             );
+            inst.add_binary_type(
+                crate::DataTypeId::EnumDefinition as u32,
+                crate::ObjectId::EnumDefinition_Encoding_DefaultBinary as u32,
+                opcua::types::binary_decode_to_enc::<EnumDefinition>,
+            );
             inst.add_binary_type(
                 crate::DataTypeId::EnumDescription as u32,
                 crate::ObjectId::EnumDescription_Encoding_DefaultBinary as u32,
@@ -1750,6 +1755,11 @@ static TYPES: std::sync::LazyLock<opcua::types::TypeLoaderInstance> =
                 crate::ObjectId::StatusResult_Encoding_DefaultBinary as u32,
                 opcua::types::binary_decode_to_enc::<StatusResult>,
             );
+            inst.add_binary_type(
+                crate::DataTypeId::StructureDefinition as u32,
+                crate::ObjectId::StructureDefinition_Encoding_DefaultBinary as u32,
+                opcua::types::binary_decode_to_enc::<StructureDefinition>,
+            );
             inst.add_binary_type(
                 crate::DataTypeId::StructureDescription as u32,
                 crate::ObjectId::StructureDescription_Encoding_DefaultBinary as u32,
@@ -2449,6 +2459,11 @@ static TYPES: std::sync::LazyLock<opcua::types::TypeLoaderInstance> =
                 crate::ObjectId::EndpointUrlListDataType_Encoding_DefaultXml as u32,
                 opcua::types::xml_decode_to_enc::<EndpointUrlListDataType>,
             );
+            inst.add_xml_type(
+                crate::DataTypeId::EnumDefinition as u32,
+                crate::ObjectId::EnumDefinition_Encoding_DefaultXml as u32,
+                opcua::types::xml_decode_to_enc::<EnumDefinition>,
+            );
             inst.add_xml_type(
                 crate::DataTypeId::EnumDescription as u32,
                 crate::ObjectId::EnumDescription_Encoding_DefaultXml as u32,
@@ -3149,6 +3164,11 @@ static TYPES: std::sync::LazyLock<opcua::types::TypeLoaderInstance> =
                 crate::ObjectId::StatusResult_Encoding_DefaultXml as u32,
                 opcua::types::xml_decode_to_enc::<StatusResult>,
             );
+            inst.add_xml_type(
+                crate::DataTypeId::StructureDefinition as u32,
+                crate::ObjectId::StructureDefinition_Encoding_DefaultXml as u32,
+                opcua::types::xml_decode_to_enc::<StructureDefinition>,
+            );
             inst.add_xml_type(
                 crate::DataTypeId::StructureDescription as u32,
                 crate::ObjectId::StructureDescription_Encoding_DefaultXml as u32,
@@ -3847,6 +3867,11 @@ static TYPES: std::sync::LazyLock<opcua::types::TypeLoaderInstance> =
                 crate::ObjectId::EndpointUrlListDataType_Encoding_DefaultJson as u32,
                 opcua::types::json_decode_to_enc::<EndpointUrlListDataType>,
             );
+            inst.add_json_type(
+                crate::DataTypeId::EnumDefinition as u32,
+                crate::ObjectId::EnumDefinition_Encoding_DefaultJson as u32,
+                opcua::types::json_decode_to_enc::<EnumDefinition>,
+            );
             inst.add_json_type(
                 crate::DataTypeId::EnumDescription as u32,
                 crate::ObjectId::EnumDescription_Encoding_DefaultJson as u32,
@@ -4547,6 +4572,11 @@ static TYPES: std::sync::LazyLock<opcua::types::TypeLoaderInstance> =
                 crate::ObjectId::StatusResult_Encoding_DefaultJson as u32,
                 opcua::types::json_decode_to_enc::<StatusResult>,
             );
+            inst.add_json_type(
+                crate::DataTypeId::StructureDefinition as u32,
+                crate::ObjectId::StructureDefinition_Encoding_DefaultJson as u32,
+                opcua::types::json_decode_to_enc::<StructureDefinition>,
+            );
             inst.add_json_type(
                 crate::DataTypeId::StructureDescription as u32,
                 crate::ObjectId::StructureDescription_Encoding_DefaultJson as u32,
@@ -22,3 +22,17 @@ pub struct StructureDefinition {
     pub structure_type: super::enums::StructureType,
     pub fields: Option<Vec<super::structure_field::StructureField>>,
 }
+impl opcua::types::MessageInfo for StructureDefinition {
+    fn type_id(&self) -> opcua::types::ObjectId {
+        opcua::types::ObjectId::StructureDefinition_Encoding_DefaultBinary
+    }
+    fn json_type_id(&self) -> opcua::types::ObjectId {
+        opcua::types::ObjectId::StructureDefinition_Encoding_DefaultJson
+    }
+    fn xml_type_id(&self) -> opcua::types::ObjectId {
+        opcua::types::ObjectId::StructureDefinition_Encoding_DefaultXml
+    }
+    fn data_type_id(&self) -> opcua::types::DataTypeId {
+        opcua::types::DataTypeId::StructureDefinition
+    }
+}
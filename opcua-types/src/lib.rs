@@ -19,6 +19,12 @@ pub mod profiles {
     /// Transport profile for OPC UA Binary
     pub const TRANSPORT_PROFILE_URI_BINARY: &str =
         "http://opcfoundation.org/UA-Profile/Transport/uatcp-uasc-uabinary";
+    /// Transport profile for OPC UA Binary over secure WebSockets
+    pub const TRANSPORT_PROFILE_URI_WSS_BINARY: &str =
+        "http://opcfoundation.org/UA-Profile/Transport/wss-uasc-uabinary";
+    /// Transport profile for OPC UA Binary over HTTPS
+    pub const TRANSPORT_PROFILE_URI_HTTPS_BINARY: &str =
+        "http://opcfoundation.org/UA-Profile/Transport/https-uabinary";
     /// Security policy for anonymous tokens.
     pub const SECURITY_USER_TOKEN_POLICY_ANONYMOUS: &str =
         "http://opcfoundation.org/UA-Profile/Security/UserToken/Anonymous";
@@ -5,7 +5,8 @@ use crate::{
     status_code::StatusCode,
     variant::{Variant, VariantTypeId},
     ByteString, DataTypeId, DataValue, DateTime, DiagnosticInfo, ExpandedNodeId, Guid,
-    LocalizedText, NodeId, QualifiedName, TryFromVariant, UAString, VariantScalarTypeId,
+    LocalizedText, MethodArg, NodeId, QualifiedName, Range, TryFromVariant, UAString,
+    VariantScalarTypeId,
 };
 
 #[test]
@@ -1652,4 +1653,18 @@ fn variant_bytestring_to_bytearray() {
     assert_eq!(v[3], Variant::Byte(0x4));
 }
 
+#[test]
+fn method_arg_types() {
+    fn arg<T: MethodArg>() -> (ExpandedNodeId, i32) {
+        (T::data_type(), T::value_rank())
+    }
+    assert_eq!(arg::<i32>(), (DataTypeId::Int32.into(), -1));
+    assert_eq!(arg::<String>(), (DataTypeId::String.into(), -1));
+    assert_eq!(arg::<Vec<f64>>(), (DataTypeId::Double.into(), 1));
+    assert_eq!(arg::<Option<NodeId>>(), (DataTypeId::NodeId.into(), -1));
+    assert_eq!(arg::<Range>(), (DataTypeId::Range.into(), -1));
+    assert_eq!(arg::<Vec<Range>>(), (DataTypeId::Range.into(), 1));
+    assert_eq!(arg::<Variant>(), (DataTypeId::BaseDataType.into(), -2));
+}
+
 // TODO arrays
@@ -255,6 +255,15 @@ impl TypeLoaderCollection {
         self.loaders.push(loader);
     }
 
+    /// Remove a type loader from the collection. The loader is compared
+    /// by pointer, so this must be the same `Arc` that was added.
+    /// Returns `true` if the loader was found and removed.
+    pub fn remove(&mut self, loader: &Arc<dyn TypeLoader>) -> bool {
+        let len = self.loaders.len();
+        self.loaders.retain(|l| !Arc::ptr_eq(l, loader));
+        len != self.loaders.len()
+    }
+
     /// Iterate over the type loaders.
     pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
         self.into_iter()
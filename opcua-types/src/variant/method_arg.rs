@@ -0,0 +1,110 @@
+use uuid::Uuid;
+
+use crate::{
+    ByteString, DataTypeId, DataValue, DateTime, DateTimeUtc, DiagnosticInfo, DynEncodable,
+    ExpandedNodeId, ExtensionObject, Guid, LocalizedText, NodeId, QualifiedName, StatusCode,
+    UAString,
+};
+
+use super::{TryFromVariant, Variant, VariantType};
+
+/// Trait for types that can be used as input or output arguments of methods.
+///
+/// This provides the information needed to produce the `Argument` metadata
+/// describing a method argument, in addition to conversion to and from `Variant`.
+///
+/// This is implemented for all primitive types, structures with a default value,
+/// `Vec<T>` for arrays, `Option<T>` for arguments that may be left empty, and
+/// `Variant` for arguments of any type.
+pub trait MethodArg: TryFromVariant + Into<Variant> {
+    /// Get the data type of the argument.
+    fn data_type() -> ExpandedNodeId;
+
+    /// Get the value rank of the argument, `-1` for scalars and `1` for arrays.
+    fn value_rank() -> i32 {
+        -1
+    }
+}
+
+macro_rules! impl_method_arg {
+    ($tp:ty, $dt:ident) => {
+        impl MethodArg for $tp {
+            fn data_type() -> ExpandedNodeId {
+                DataTypeId::$dt.into()
+            }
+        }
+    };
+}
+
+impl_method_arg!(bool, Boolean);
+impl_method_arg!(i8, SByte);
+impl_method_arg!(u8, Byte);
+impl_method_arg!(i16, Int16);
+impl_method_arg!(u16, UInt16);
+impl_method_arg!(i32, Int32);
+impl_method_arg!(u32, UInt32);
+impl_method_arg!(i64, Int64);
+impl_method_arg!(u64, UInt64);
+impl_method_arg!(f32, Float);
+impl_method_arg!(f64, Double);
+impl_method_arg!(UAString, String);
+impl_method_arg!(String, String);
+impl_method_arg!(DateTime, DateTime);
+impl_method_arg!(DateTimeUtc, DateTime);
+impl_method_arg!(Guid, Guid);
+impl_method_arg!(Uuid, Guid);
+impl_method_arg!(StatusCode, StatusCode);
+impl_method_arg!(ByteString, ByteString);
+impl_method_arg!(QualifiedName, QualifiedName);
+impl_method_arg!(LocalizedText, LocalizedText);
+impl_method_arg!(NodeId, NodeId);
+impl_method_arg!(ExpandedNodeId, ExpandedNodeId);
+impl_method_arg!(ExtensionObject, Structure);
+impl_method_arg!(DataValue, DataValue);
+impl_method_arg!(DiagnosticInfo, DiagnosticInfo);
+
+impl MethodArg for Variant {
+    fn data_type() -> ExpandedNodeId {
+        DataTypeId::BaseDataType.into()
+    }
+
+    fn value_rank() -> i32 {
+        // ValueRank `Any`, the argument may be a scalar or an array.
+        -2
+    }
+}
+
+impl<T> MethodArg for T
+where
+    T: DynEncodable + Default,
+{
+    fn data_type() -> ExpandedNodeId {
+        T::default().data_type_id()
+    }
+}
+
+impl<T> MethodArg for Vec<T>
+where
+    T: MethodArg + VariantType,
+{
+    fn data_type() -> ExpandedNodeId {
+        T::data_type()
+    }
+
+    fn value_rank() -> i32 {
+        1
+    }
+}
+
+impl<T> MethodArg for Option<T>
+where
+    T: MethodArg,
+{
+    fn data_type() -> ExpandedNodeId {
+        T::data_type()
+    }
+
+    fn value_rank() -> i32 {
+        T::value_rank()
+    }
+}
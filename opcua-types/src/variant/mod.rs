@@ -9,10 +9,12 @@ mod from;
 mod into;
 #[cfg(feature = "json")]
 mod json;
+mod method_arg;
 mod type_id;
 
 pub use from::TryFromVariant;
 pub use into::IntoVariant;
+pub use method_arg::MethodArg;
 pub use type_id::*;
 
 use std::{
@@ -3,9 +3,13 @@ use std::path::PathBuf;
 use opcua::crypto::*;
 
 fn main() {
-    if let Ok((x509_data, overwrite, pki_path, cert_path, pkey_path)) = parse_x509_args() {
+    if let Ok((x509_data, curve, overwrite, pki_path, cert_path, pkey_path)) = parse_x509_args() {
         println!("Creating certificate...");
-        println!("  Key size = {}", x509_data.key_size);
+        if let Some(curve) = curve {
+            println!("  Curve = {:?}", curve);
+        } else {
+            println!("  Key size = {}", x509_data.key_size);
+        }
         println!("  CN (common name) = \"{}\"", x509_data.common_name);
         println!("  O (organization) = \"{}\"", x509_data.organization);
         println!(
@@ -40,22 +44,29 @@ fn main() {
             path
         };
 
-        let _ = CertificateStore::create_certificate_and_key(
-            &x509_data, overwrite, &cert_path, &pkey_path,
-        )
-        .map_err(|err| {
-            eprintln!(
-                "Certificate creation failed, check above and reason \"{}\" for errors",
-                err
-            );
-        })
-        .map(|_| {
-            println!(
-                "Certificate and private key have been written to {} and {}",
-                cert_path.display(),
-                pkey_path.display()
-            );
-        });
+        let result = if let Some(curve) = curve {
+            FileSystemCertificateStore::create_ecc_certificate_and_key(
+                &x509_data, curve, overwrite, &cert_path, &pkey_path,
+            )
+        } else {
+            FileSystemCertificateStore::create_certificate_and_key(
+                &x509_data, overwrite, &cert_path, &pkey_path,
+            )
+        };
+        let _ = result
+            .map_err(|err| {
+                eprintln!(
+                    "Certificate creation failed, check above and reason \"{}\" for errors",
+                    err
+                );
+            })
+            .map(|_| {
+                println!(
+                    "Certificate and private key have been written to {} and {}",
+                    cert_path.display(),
+                    pkey_path.display()
+                );
+            });
     }
 }
 
@@ -63,6 +74,7 @@ struct Args {
     help: bool,
     overwrite: bool,
     key_size: u16,
+    curve: Option<String>,
     pki_path: String,
     cert_path: String,
     pkey_path: String,
@@ -88,6 +100,7 @@ impl Args {
             key_size: args
                 .opt_value_from_str("--key-size")?
                 .unwrap_or(DEFAULT_KEY_SIZE),
+            curve: args.opt_value_from_str("--curve")?,
             pki_path: args
                 .opt_value_from_str("--pki-path")?
                 .unwrap_or_else(|| String::from(DEFAULT_PKI_PATH)),
@@ -139,6 +152,7 @@ Usage:
   -h, --help            Show help.
   -o, --overwrite       Overwrites existing files.
   --key-size size       Sets the key size in bits - [2048, 4096] (default: {})
+  --curve name          Creates an ECC key on the curve instead of an RSA key - [nistP256, nistP384]
   --pki-path path       Path to write the certificate and key. (default: {})
   --cert-name           Name of certificate file relative to pki-path. (default: {})
   --pkey-name           Name of private key file relative to pki-path. (default: {})
@@ -180,9 +194,19 @@ const DEFAULT_ST: &str = "Dublin";
 const DEFAULT_CERT_PATH: &str = "cert.der";
 const DEFAULT_PKEY_PATH: &str = "private.pem";
 
-fn parse_x509_args() -> Result<(X509Data, bool, PathBuf, PathBuf, PathBuf), ()> {
+#[allow(clippy::type_complexity)]
+fn parse_x509_args() -> Result<(X509Data, Option<EccCurve>, bool, PathBuf, PathBuf, PathBuf), ()> {
     // Read command line arguments
     let args = Args::parse_args().map_err(|_| Args::usage())?;
+    let curve = match args.curve.as_deref() {
+        None => None,
+        Some("nistP256") => Some(EccCurve::NistP256),
+        Some("nistP384") => Some(EccCurve::NistP384),
+        Some(curve) => {
+            eprintln!("Unsupported curve \"{}\"", curve);
+            return Err(());
+        }
+    };
     if args.help || ![2048u16, 4096u16].contains(&args.key_size) || args.duration == 0 {
         Args::usage();
         Err(())
@@ -231,6 +255,7 @@ fn parse_x509_args() -> Result<(X509Data, bool, PathBuf, PathBuf, PathBuf), ()>
                 alt_host_names,
                 certificate_duration_days,
             },
+            curve,
             overwrite,
             PathBuf::from(&pki_path),
             PathBuf::from(&cert_path),